@@ -0,0 +1,108 @@
+//! Benchmarks of the extraction pipeline for trafilatura-rs
+//!
+//! Each stage is measured separately over a small, a medium, and a huge page, so a
+//! change to one stage shows up on its own:
+//!
+//! ```sh
+//! cargo bench --bench extraction
+//! cargo bench --bench extraction -- clean/huge
+//! ```
+//!
+//! The small and medium pages are in `benches/fixtures`. The huge page repeats the
+//! medium article many times, and is built at startup to keep the repository small.
+
+use std::fs;
+use std::path::Path;
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use scraper::Html;
+use trafilatura::extractors::extract_content;
+use trafilatura::html::{clean_html, parse_document};
+use trafilatura::output::format_result;
+use trafilatura::readability::extract_with_readability;
+use trafilatura::{extract_html, ExtractionConfig, OutputFormat};
+
+/// Copies of the medium article in the huge page
+const HUGE_REPEATS: usize = 40;
+
+fn fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("benches").join("fixtures").join(name);
+    fs::read_to_string(&path).unwrap_or_else(|e| panic!("cannot read {}: {}", path.display(), e))
+}
+
+/// The fixtures by size, as (name, html)
+fn fixtures() -> Vec<(&'static str, String)> {
+    let small = fixture("small.html");
+    let medium = fixture("medium.html");
+
+    let start = medium.find("<article").expect("medium fixture has no article");
+    let end = medium.find("</article>").expect("medium fixture has no article") + "</article>".len();
+    let huge = medium.replacen("</main>", &format!("{}</main>", medium[start..end].repeat(HUGE_REPEATS)), 1);
+
+    vec![("small", small), ("medium", medium), ("huge", huge)]
+}
+
+fn parsed(html: &str) -> Html {
+    parse_document(html)
+}
+
+fn cleaned(html: &str, config: &ExtractionConfig) -> Html {
+    let mut document = parsed(html);
+    clean_html(&mut document, config);
+    document
+}
+
+fn bench_stages(c: &mut Criterion) {
+    let config = ExtractionConfig::default();
+
+    for (name, html) in fixtures() {
+        let bytes = Throughput::Bytes(html.len() as u64);
+
+        let mut group = c.benchmark_group("parse");
+        group.throughput(bytes.clone());
+        group.bench_with_input(BenchmarkId::from_parameter(name), &html, |b, html| {
+            b.iter(|| parsed(black_box(html)))
+        });
+        group.finish();
+
+        let mut group = c.benchmark_group("clean");
+        group.throughput(bytes.clone());
+        let document = parsed(&html);
+        group.bench_function(name, |b| {
+            b.iter_batched(|| document.clone(), |mut document| clean_html(&mut document, &config), BatchSize::LargeInput)
+        });
+        group.finish();
+
+        // Scoring picks the content container; both strategies render it as text
+        let document = cleaned(&html, &config);
+        let mut group = c.benchmark_group("score");
+        group.throughput(bytes.clone());
+        group.bench_function(BenchmarkId::new("heuristics", name), |b| {
+            b.iter(|| extract_content(black_box(&document), &config))
+        });
+        group.bench_function(BenchmarkId::new("readability", name), |b| {
+            b.iter(|| extract_with_readability(black_box(&document), &config))
+        });
+        group.finish();
+
+        let result = extract_html(&html, &config).expect("fixture extraction failed");
+        let mut group = c.benchmark_group("render");
+        for format in [OutputFormat::Text, OutputFormat::Json, OutputFormat::Xml, OutputFormat::Markdown] {
+            let config = ExtractionConfig { output_format: format, ..config.clone() };
+            group.bench_function(BenchmarkId::new(format.name(), name), |b| {
+                b.iter(|| format_result(black_box(&result), &config))
+            });
+        }
+        group.finish();
+
+        let mut group = c.benchmark_group("extract");
+        group.throughput(bytes);
+        group.bench_with_input(BenchmarkId::from_parameter(name), &html, |b, html| {
+            b.iter(|| extract_html(black_box(html), &config))
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_stages);
+criterion_main!(benches);
@@ -0,0 +1,25 @@
+//! Fuzz target for `extract_html`: arbitrary bytes, decoded as a crawler would,
+//! must never make extraction panic.
+//!
+//! ```sh
+//! cargo +nightly fuzz run extract_html
+//! ```
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use trafilatura::{extract_html, ExtractionConfig, ExtractionMode};
+
+fuzz_target!(|data: &[u8]| {
+    let html = String::from_utf8_lossy(data);
+
+    // The first byte picks the extraction mode, so mode-specific rewrites get fuzzed too
+    let mode = match data.first().map(|byte| byte % 3) {
+        Some(1) => ExtractionMode::Docs,
+        Some(2) => ExtractionMode::Email,
+        _ => ExtractionMode::Standard,
+    };
+    let config = ExtractionConfig { mode, include_comments: true, include_tables: true, ..ExtractionConfig::default() };
+
+    let _ = extract_html(&html, &config);
+});
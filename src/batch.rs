@@ -0,0 +1,576 @@
+//! Streaming batch extraction for Trafilatura Rust port.
+//! This module extracts lists of URLs, URL files, directories, sitemaps, feeds,
+//! and crawls as a lazy iterator of results. A pool of download threads feeds a
+//! pool of extraction threads through bounded channels, so network latency and
+//! parsing overlap; a consumer that stops reading holds back both stages instead
+//! of letting results pile up in memory, and dropping the iterator stops the batch.
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use url::Url;
+
+use crate::feeds::fetch_feed_entries;
+use crate::fetch::Fetcher;
+use crate::memory::{MemoryScope, MemoryUsage, PeakTracker};
+use crate::sitemaps::fetch_sitemap_entries;
+use crate::spider::Spider;
+use crate::utils::is_html_file;
+use crate::{download, extract_download, Download, DownloadSource, ExtractionConfig, ExtractionResult, TrafilaturaError};
+
+/// Fetcher shared by the download threads of a batch
+pub type SharedFetcher = Arc<dyn Fetcher + Send + Sync>;
+
+/// Tuning of a batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchOptions {
+    /// Documents buffered between the download and extraction stages, and results
+    /// buffered for the consumer
+    pub buffer: usize,
+    /// Threads downloading documents. With more than one thread, or more than one
+    /// extractor, results arrive in completion order rather than input order.
+    pub fetchers: usize,
+    /// Threads extracting downloaded documents
+    pub extractors: usize,
+    /// Pause of each download thread after every download
+    pub delay: Duration,
+    /// Extract the downloaded documents; when unset, results only carry the URL of
+    /// each document downloaded, e.g. to list the pages of a crawl
+    pub extract: bool,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            buffer: 16,
+            fetchers: 1,
+            extractors: 1,
+            delay: Duration::ZERO,
+            extract: true,
+        }
+    }
+}
+
+/// Stage of a batch a document is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStage {
+    /// Downloading the document, or reading it from a file; listing a sitemap,
+    /// feed, or crawl ends here
+    Download,
+    /// Extracting the downloaded document
+    Extract,
+}
+
+/// The result of one document of a batch
+#[derive(Debug)]
+pub struct BatchItem {
+    /// URL or file path the document was read from; for a sitemap or feed that could
+    /// not be read, its own URL
+    pub source: String,
+    /// Last stage the document went through, where an error in `result` happened
+    pub stage: BatchStage,
+    /// Extracted document, or why it could not be downloaded or extracted
+    pub result: Result<ExtractionResult, TrafilaturaError>,
+    /// Memory used to extract the document, when `memory::TrackingAllocator` is the
+    /// global allocator
+    pub memory: Option<MemoryUsage>,
+}
+
+impl BatchItem {
+    /// Record of the failure of the document, or `None` if it was extracted
+    pub fn error_record(&self) -> Option<ErrorRecord> {
+        let error = self.result.as_ref().err()?;
+        Some(ErrorRecord::new(&self.source, self.stage, error))
+    }
+}
+
+/// Machine-readable record of a document of a batch that failed, e.g. to queue
+/// failed URLs again
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorRecord {
+    /// URL or file path of the document
+    pub url: String,
+    /// Stage that failed
+    pub stage: BatchStage,
+    /// Kind of error, see `TrafilaturaError::kind`
+    pub error_kind: &'static str,
+    /// Error message
+    pub message: String,
+    /// HTTP status of the response, for download failures
+    pub http_status: Option<u16>,
+}
+
+impl ErrorRecord {
+    /// Record an error of a document in the given stage
+    pub fn new(url: &str, stage: BatchStage, error: &TrafilaturaError) -> Self {
+        Self {
+            url: url.to_string(),
+            stage,
+            error_kind: error.kind(),
+            message: error.to_string(),
+            http_status: error.http_status(),
+        }
+    }
+}
+
+/// Which failed documents of a batch report to retry: all of them without filters,
+/// otherwise those matching any of the error kinds or HTTP statuses
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetryFilter {
+    /// Error kinds to retry, see `TrafilaturaError::kind`
+    pub error_kinds: Vec<String>,
+    /// HTTP statuses to retry
+    pub http_statuses: Vec<u16>,
+}
+
+impl RetryFilter {
+    /// Check if a failed document is to be retried
+    pub fn matches(&self, error_kind: &str, http_status: Option<u16>) -> bool {
+        (self.error_kinds.is_empty() && self.http_statuses.is_empty())
+            || self.error_kinds.iter().any(|kind| kind == error_kind)
+            || http_status.is_some_and(|status| self.http_statuses.contains(&status))
+    }
+}
+
+/// Error record read back from a batch report
+#[derive(Deserialize)]
+struct FailedDocument {
+    url: String,
+    error_kind: String,
+    http_status: Option<u16>,
+}
+
+/// Read the URLs of the failed documents of a previous batch that match the filter,
+/// once each in report order. The report is either an error log of `ErrorRecord`
+/// JSON lines, or JSON output where failures appear as `{"error": {...}}` in place
+/// of the documents, which are skipped.
+pub fn read_failed_urls(path: &Path, filter: &RetryFilter) -> Result<Vec<String>, TrafilaturaError> {
+    let mut seen = HashSet::new();
+    let mut urls = Vec::new();
+    for value in serde_json::Deserializer::from_reader(BufReader::new(File::open(path)?)).into_iter::<serde_json::Value>() {
+        let mut value = value?;
+        let record = if let Some(error) = value.get_mut("error") {
+            error.take()
+        } else if value.get("error_kind").is_some() {
+            value
+        } else {
+            continue;
+        };
+        let failed: FailedDocument = serde_json::from_value(record)?;
+        if filter.matches(&failed.error_kind, failed.http_status) && seen.insert(failed.url.clone()) {
+            urls.push(failed.url);
+        }
+    }
+    Ok(urls)
+}
+
+/// A document handed from the download stage to the extraction stage
+struct Pending {
+    source: String,
+    document: Result<Download, TrafilaturaError>,
+}
+
+/// Lazy iterator over the results of a batch
+pub struct BatchStream {
+    results: Receiver<BatchItem>,
+}
+
+impl BatchStream {
+    /// Download and extract a list of URLs
+    pub fn urls<I>(urls: I, config: &ExtractionConfig, fetcher: SharedFetcher, options: BatchOptions) -> Self
+    where
+        I: IntoIterator<Item = String>,
+        I::IntoIter: Send + 'static,
+    {
+        let urls = urls.into_iter();
+        let download_config = config.clone();
+        Self::spawn_jobs(config, options, move |jobs| send_all(urls, jobs), move |url: String| {
+            let document = download(&url, &download_config, fetcher.as_ref());
+            Pending { source: url, document }
+        })
+    }
+
+    /// Download and extract the URLs of a file with one URL per line, skipping blank
+    /// lines and `#` comments. The file is read as the batch progresses.
+    pub fn input_file(path: &Path, config: &ExtractionConfig, fetcher: SharedFetcher, options: BatchOptions) -> Result<Self, TrafilaturaError> {
+        let lines = BufReader::new(File::open(path)?).lines()
+            .map_while(Result::ok)
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        Ok(Self::urls(lines, config, fetcher, options))
+    }
+
+    /// Extract local HTML files
+    pub fn files<I>(paths: I, config: &ExtractionConfig, options: BatchOptions) -> Self
+    where
+        I: IntoIterator<Item = PathBuf>,
+        I::IntoIter: Send + 'static,
+    {
+        let paths = paths.into_iter();
+        Self::spawn_jobs(config, options, move |jobs| send_all(paths, jobs), |path: PathBuf| {
+            Pending { source: path.display().to_string(), document: read_file(&path) }
+        })
+    }
+
+    /// Extract the HTML files of a directory, in file name order
+    pub fn dir(dir: &Path, config: &ExtractionConfig, options: BatchOptions) -> Result<Self, TrafilaturaError> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && is_html_file(path))
+            .collect();
+        paths.sort();
+        Ok(Self::files(paths, config, options))
+    }
+
+    /// Download and extract the pages of a sitemap, following sitemap indexes up to
+    /// `max_urls` pages
+    pub fn sitemap(url: &str, max_urls: usize, config: &ExtractionConfig, fetcher: SharedFetcher, options: BatchOptions) -> Self {
+        let url = url.to_string();
+        let list_fetcher = fetcher.clone();
+        Self::listed(config, fetcher, options, move || {
+            let entries = fetch_sitemap_entries(&url, list_fetcher.as_ref(), max_urls).map_err(|e| (url, e))?;
+            Ok(entries.into_iter().map(|entry| entry.url).collect())
+        })
+    }
+
+    /// Download and extract the entries of an RSS or Atom feed
+    pub fn feed(url: &str, config: &ExtractionConfig, fetcher: SharedFetcher, options: BatchOptions) -> Self {
+        let url = url.to_string();
+        let list_fetcher = fetcher.clone();
+        Self::listed(config, fetcher, options, move || {
+            let entries = fetch_feed_entries(&url, list_fetcher.as_ref()).map_err(|e| (url, e))?;
+            Ok(entries.into_iter().map(|entry| entry.url).collect())
+        })
+    }
+
+    /// Download and extract the URLs returned by `list`, which runs on a background
+    /// thread and reports failures with the URL of the list
+    fn listed<L>(config: &ExtractionConfig, fetcher: SharedFetcher, options: BatchOptions, list: L) -> Self
+    where
+        L: FnOnce() -> Result<Vec<String>, (String, TrafilaturaError)> + Send + 'static,
+    {
+        let download_config = config.clone();
+        let produce = move |jobs: &SyncSender<Result<String, (String, TrafilaturaError)>>| match list() {
+            Ok(urls) => send_all(urls.into_iter().map(Ok), jobs),
+            Err(failure) => {
+                let _ = jobs.send(Err(failure));
+            },
+        };
+        Self::spawn_jobs(config, options, produce, move |job| match job {
+            Ok(url) => {
+                let document = download(&url, &download_config, fetcher.as_ref());
+                Pending { source: url, document }
+            },
+            Err((source, e)) => Pending { source, document: Err(e) },
+        })
+    }
+
+    /// Crawl a site and extract every page visited. The spider is shared so the
+    /// caller can save its state or read the traps it found while the crawl runs.
+    ///
+    /// Download threads take URLs from the frontier as long as there are any; a thread
+    /// finding it empty waits for the pages still being downloaded, which may add links.
+    pub fn crawl(spider: Arc<Mutex<Spider>>, config: &ExtractionConfig, fetcher: SharedFetcher, options: BatchOptions) -> Self {
+        let download_config = config.clone();
+        // Downloads in progress, with a signal when one finishes
+        let in_flight = Arc::new((Mutex::new(0usize), Condvar::new()));
+        Self::spawn(config, options, move |pending| loop {
+            let (downloads, finished) = &*in_flight;
+            let url = {
+                let mut downloads = downloads.lock().unwrap_or_else(|e| e.into_inner());
+                loop {
+                    if let Some(url) = spider.lock().unwrap_or_else(|e| e.into_inner()).next_url() {
+                        *downloads += 1;
+                        break url;
+                    }
+                    if *downloads == 0 {
+                        finished.notify_all();
+                        return;
+                    }
+                    downloads = finished.wait(downloads).unwrap_or_else(|e| e.into_inner());
+                }
+            };
+
+            let document = download(&url, &download_config, fetcher.as_ref());
+            {
+                let mut downloads = downloads.lock().unwrap_or_else(|e| e.into_inner());
+                // Links resolve against the page as served; `url` is canonical, without
+                // the trailing slash of directory pages
+                if let Ok(document) = &document {
+                    spider.lock().unwrap_or_else(|e| e.into_inner()).add_links(document.page_url.as_str(), &document.html);
+                }
+                *downloads -= 1;
+                finished.notify_all();
+            }
+            if pending.send(Pending { source: url, document }).is_err() {
+                return;
+            }
+            thread::sleep(options.delay);
+        })
+    }
+
+    /// Run `produce` on a background thread and `handle` every job it sends on the
+    /// download threads
+    fn spawn_jobs<T, P, H>(config: &ExtractionConfig, options: BatchOptions, produce: P, handle: H) -> Self
+    where
+        T: Send + 'static,
+        P: FnOnce(&SyncSender<T>) + Send + 'static,
+        H: Fn(T) -> Pending + Send + Sync + 'static,
+    {
+        let (job_sender, jobs) = sync_channel::<T>(options.buffer.max(1));
+        thread::spawn(move || produce(&job_sender));
+
+        let jobs = Mutex::new(jobs);
+        Self::spawn(config, options, move |pending| loop {
+            let job = jobs.lock().unwrap_or_else(|e| e.into_inner()).recv();
+            let Ok(job) = job else {
+                return;
+            };
+            if pending.send(handle(job)).is_err() {
+                return;
+            }
+            thread::sleep(options.delay);
+        })
+    }
+
+    /// Run `download` on every download thread and extract what they send on the
+    /// extraction threads
+    fn spawn<W>(config: &ExtractionConfig, options: BatchOptions, download: W) -> Self
+    where
+        W: Fn(&SyncSender<Pending>) + Send + Sync + 'static,
+    {
+        let buffer = options.buffer.max(1);
+        let (pending_sender, pending) = sync_channel::<Pending>(buffer);
+        let (result_sender, results) = sync_channel(buffer);
+
+        let download = Arc::new(download);
+        for _ in 0..options.fetchers.max(1) {
+            let download = download.clone();
+            let pending_sender = pending_sender.clone();
+            thread::spawn(move || download(&pending_sender));
+        }
+
+        // Extraction threads take turns receiving, so each document is extracted once
+        let pending = Arc::new(Mutex::new(pending));
+        let peaks = Arc::new(PeakTracker::default());
+        for _ in 0..options.extractors.max(1) {
+            let pending = pending.clone();
+            let result_sender = result_sender.clone();
+            let config = config.clone();
+            let peaks = peaks.clone();
+            thread::spawn(move || loop {
+                let next = pending.lock().unwrap_or_else(|e| e.into_inner()).recv();
+                let Ok(Pending { source, document }) = next else {
+                    return;
+                };
+                let stage = if document.is_ok() && options.extract { BatchStage::Extract } else { BatchStage::Download };
+                let scope = MemoryScope::start();
+                let result = document.and_then(|document| if options.extract {
+                    extract_download(document, &config)
+                } else {
+                    Ok(ExtractionResult { url: Some(document.url.to_string()), ..Default::default() })
+                });
+                let memory = scope.usage();
+                if let Some(usage) = memory {
+                    if let Some(times) = peaks.record(usage) {
+                        warn!("{} peaked at {} MB, {} times the mean of the batch", source, usage.peak_bytes >> 20, times);
+                    }
+                }
+                if result_sender.send(BatchItem { source, stage, result, memory }).is_err() {
+                    // The consumer is gone; dropping the receiver stops the download threads
+                    return;
+                }
+            });
+        }
+
+        Self { results }
+    }
+}
+
+impl Iterator for BatchStream {
+    type Item = BatchItem;
+
+    fn next(&mut self) -> Option<BatchItem> {
+        self.results.recv().ok()
+    }
+}
+
+/// Send jobs in order until the download threads hang up
+fn send_all<T>(jobs: impl Iterator<Item = T>, sender: &SyncSender<T>) {
+    for job in jobs {
+        if sender.send(job).is_err() {
+            return;
+        }
+    }
+}
+
+/// Read a local HTML file, with a `file://` URL so it is extracted like a `file://` download
+fn read_file(path: &Path) -> Result<Download, TrafilaturaError> {
+    let html = fs::read_to_string(path)?;
+    let url = fs::canonicalize(path).ok()
+        .and_then(|path| Url::from_file_path(path).ok())
+        .ok_or_else(|| TrafilaturaError::ExtractionError(format!("Invalid file path: {}", path.display())))?;
+    Ok(Download { page_url: url.clone(), url, html, source: DownloadSource::File })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fetch::FetchedPage;
+    use crate::spider::CrawlOptions;
+
+    struct SiteFetcher;
+
+    impl Fetcher for SiteFetcher {
+        fn fetch(&self, url: &Url) -> Result<FetchedPage, TrafilaturaError> {
+            let page = |body: String| Ok(FetchedPage { url: url.to_string(), status: 200, body });
+            match url.path() {
+                "/sitemap.xml" => page("<urlset><url><loc>/a</loc></url><url><loc>/missing</loc></url><url><loc>/b</loc></url></urlset>".into()),
+                "/" => page(r#"<html><body><a href="/a">A</a> <a href="/b">B</a></body></html>"#.into()),
+                // A directory page, redirected to its URL with a trailing slash
+                "/docs" | "/docs/" => Ok(FetchedPage {
+                    url: "https://example.com/docs/".into(),
+                    status: 200,
+                    body: r#"<html><body><a href="install">Install</a></body></html>"#.into(),
+                }),
+                "/a" | "/b" | "/docs/install" => page(format!(
+                    "<html><body><article><p>Article {} reports on the harbour renovation, which finished two months early and under budget.</p></article></body></html>",
+                    url.path()
+                )),
+                _ => Ok(FetchedPage { url: url.to_string(), status: 404, body: String::new() }),
+            }
+        }
+    }
+
+    fn config() -> ExtractionConfig {
+        ExtractionConfig { min_extracted_size: 50, ..Default::default() }
+    }
+
+    #[test]
+    fn test_sitemap_stream() {
+        let stream = BatchStream::sitemap("https://example.com/sitemap.xml", 10, &config(), Arc::new(SiteFetcher), BatchOptions { buffer: 1, ..Default::default() });
+
+        let items: Vec<BatchItem> = stream.collect();
+        let sources: Vec<&str> = items.iter().map(|item| item.source.as_str()).collect();
+        assert_eq!(sources, vec!["https://example.com/a", "https://example.com/missing", "https://example.com/b"]);
+        assert!(items[0].result.as_ref().unwrap().content.contains("Article /a"));
+        assert!(matches!(items[1].result, Err(TrafilaturaError::StatusError(404, _))));
+
+        let mut stream = BatchStream::feed("https://example.com/feed.xml", &config(), Arc::new(SiteFetcher), BatchOptions::default());
+        assert_eq!(stream.next().map(|item| item.source), Some("https://example.com/feed.xml".to_string()));
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_error_records() {
+        let urls = ["/a", "/missing", "/"].iter().map(|path| format!("https://example.com{}", path)).collect::<Vec<_>>();
+        let items: Vec<BatchItem> = BatchStream::urls(urls, &config(), Arc::new(SiteFetcher), BatchOptions::default()).collect();
+
+        assert_eq!(items[0].stage, BatchStage::Extract);
+        assert_eq!(items[0].error_record(), None);
+
+        let record = items[1].error_record().unwrap();
+        assert_eq!(serde_json::to_value(&record).unwrap(), serde_json::json!({
+            "url": "https://example.com/missing",
+            "stage": "download",
+            "error_kind": "http_status",
+            "message": "HTTP status 404 for https://example.com/missing",
+            "http_status": 404,
+        }));
+
+        // The start page is downloaded, but has too little text
+        let record = items[2].error_record().unwrap();
+        assert_eq!((record.stage, record.error_kind, record.http_status), (BatchStage::Extract, "content_too_short", None));
+    }
+
+    #[test]
+    fn test_read_failed_urls() {
+        let dir = tempfile::tempdir().unwrap();
+        let record = |url: &str, error_kind: &str, http_status: Option<u16>| serde_json::json!({
+            "url": url, "stage": "download", "error_kind": error_kind, "message": "failed", "http_status": http_status,
+        });
+
+        // Error log, with a URL that failed twice
+        let log = dir.path().join("errors.jsonl");
+        let lines = [
+            record("https://example.com/a", "timeout", None),
+            record("https://example.com/b", "http_status", Some(404)),
+            record("https://example.com/c", "http_status", Some(503)),
+            record("https://example.com/a", "connect", None),
+        ];
+        fs::write(&log, lines.iter().map(|line| format!("{}\n", line)).collect::<String>()).unwrap();
+
+        assert_eq!(read_failed_urls(&log, &RetryFilter::default()).unwrap(), vec![
+            "https://example.com/a", "https://example.com/b", "https://example.com/c",
+        ]);
+        let filter = RetryFilter { error_kinds: vec!["timeout".into()], http_statuses: vec![503] };
+        assert_eq!(read_failed_urls(&log, &filter).unwrap(), vec!["https://example.com/a", "https://example.com/c"]);
+
+        // JSON output, where documents are skipped
+        let output = dir.path().join("pages.json");
+        let document = serde_json::json!({ "url": "https://example.com/d", "text": "Extracted" });
+        let error = serde_json::json!({ "error": record("https://example.com/e", "content_too_short", None) });
+        fs::write(&output, format!("{:#}\n{:#}\n", document, error)).unwrap();
+        assert_eq!(read_failed_urls(&output, &RetryFilter::default()).unwrap(), vec!["https://example.com/e"]);
+    }
+
+    #[test]
+    fn test_crawl_and_dir_streams() {
+        let spider = Arc::new(Mutex::new(Spider::new("https://example.com/", CrawlOptions::default()).unwrap()));
+        let stream = BatchStream::crawl(spider.clone(), &config(), Arc::new(SiteFetcher), BatchOptions::default());
+
+        // The start page has too little text, the articles are extracted
+        let extracted: Vec<String> = stream.filter_map(|item| item.result.ok()).filter_map(|result| result.url).collect();
+        assert_eq!(extracted, vec!["https://example.com/a", "https://example.com/b"]);
+        assert_eq!(spider.lock().unwrap().visited(), 3);
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("b.html"), "<html><body><p>Second document, long enough to pass the minimum size of the test.</p></body></html>").unwrap();
+        fs::write(dir.path().join("a.htm"), "<html><body><p>First document, long enough to pass the minimum size of the test.</p></body></html>").unwrap();
+        fs::write(dir.path().join("notes.txt"), "not HTML").unwrap();
+
+        let contents: Vec<String> = BatchStream::dir(dir.path(), &config(), BatchOptions::default()).unwrap()
+            .map(|item| item.result.unwrap().content)
+            .collect();
+        assert_eq!(contents.len(), 2);
+        assert!(contents[0].starts_with("First document"));
+    }
+
+    #[test]
+    fn test_crawl_resolves_links_against_fetched_url() {
+        let spider = Arc::new(Mutex::new(Spider::new("https://example.com/docs/", CrawlOptions::default()).unwrap()));
+        let stream = BatchStream::crawl(spider.clone(), &config(), Arc::new(SiteFetcher), BatchOptions::default());
+
+        // The frontier holds `/docs`, but the link of the page is relative to `/docs/`
+        let extracted: Vec<String> = stream.filter_map(|item| item.result.ok()).filter_map(|result| result.url).collect();
+        assert_eq!(extracted, vec!["https://example.com/docs/install"]);
+        assert_eq!(spider.lock().unwrap().visited_urls(), ["https://example.com/docs", "https://example.com/docs/install"]);
+    }
+
+    #[test]
+    fn test_worker_pools() {
+        let options = BatchOptions { fetchers: 3, extractors: 2, ..Default::default() };
+        let urls = ["/a", "/b", "/missing", "/a"].iter().map(|path| format!("https://example.com{}", path)).collect::<Vec<_>>();
+        let mut sources: Vec<String> = BatchStream::urls(urls, &config(), Arc::new(SiteFetcher), options).map(|item| item.source).collect();
+        sources.sort();
+        assert_eq!(sources, vec!["https://example.com/a", "https://example.com/a", "https://example.com/b", "https://example.com/missing"]);
+
+        // Listing a crawl skips extraction, so the start page is reported too
+        let spider = Arc::new(Mutex::new(Spider::new("https://example.com/", CrawlOptions::default()).unwrap()));
+        let options = BatchOptions { fetchers: 4, extract: false, ..Default::default() };
+        let mut listed: Vec<String> = BatchStream::crawl(spider, &config(), Arc::new(SiteFetcher), options)
+            .filter_map(|item| item.result.ok()?.url)
+            .collect();
+        listed.sort();
+        assert_eq!(listed, vec!["https://example.com/", "https://example.com/a", "https://example.com/b"]);
+    }
+}
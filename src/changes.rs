@@ -0,0 +1,82 @@
+//! Change detection for Trafilatura Rust port.
+//! Monitoring pipelines re-fetch pages and only want to store articles that were
+//! actually updated. This module fingerprints extracted text with a hash of its
+//! normalized words and a SimHash, and compares a stored fingerprint with the
+//! fingerprint of a re-fetched page.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::utils::{simhash, tokenize};
+use crate::ExtractionResult;
+
+/// SimHash fingerprints differing in at most this many bits belong to the same version
+/// of a text, e.g. one with a corrected typo or a new view counter
+pub const MAX_UNCHANGED_DISTANCE: u32 = 3;
+
+/// Fingerprint of extracted text, stored to detect later updates
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentFingerprint {
+    /// Hex-encoded SHA-256 of the lowercased words of the text, ignoring whitespace
+    /// and punctuation
+    pub hash: String,
+    /// SimHash of the text, as in the `fingerprint` field of corpus records
+    pub simhash: u64,
+}
+
+impl ContentFingerprint {
+    /// Fingerprint a text
+    pub fn new(text: &str) -> Self {
+        Self {
+            hash: content_hash(text),
+            simhash: simhash(text),
+        }
+    }
+}
+
+impl From<&ExtractionResult> for ContentFingerprint {
+    fn from(result: &ExtractionResult) -> Self {
+        Self::new(&result.content)
+    }
+}
+
+/// Hex-encoded SHA-256 of the lowercased words of a text: reflowed lines, changed
+/// punctuation, or different whitespace give the same hash
+pub fn content_hash(text: &str) -> String {
+    let words: Vec<String> = tokenize(text).into_iter()
+        .filter(|token| token.chars().any(char::is_alphanumeric))
+        .map(str::to_lowercase)
+        .collect();
+    Sha256::digest(words.join(" ").as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Check if a re-fetched page is materially updated: its normalized text differs and
+/// its SimHash is more than `MAX_UNCHANGED_DISTANCE` bits away from the previous one
+pub fn has_changed(previous: &ContentFingerprint, new: &ContentFingerprint) -> bool {
+    previous.hash != new.hash && (previous.simhash ^ new.simhash).count_ones() > MAX_UNCHANGED_DISTANCE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_changed() {
+        let article = "The city council approved the new budget on Tuesday. Spending on public transport \
+            rises by twelve percent, while the road maintenance budget stays flat. The opposition \
+            criticised the plan as too cautious and announced amendments for the autumn session, \
+            when the council will also debate the proposed tram extension to the northern districts.";
+        let previous = ContentFingerprint::new(article);
+
+        let reflowed = article.replace(". ", ".\n\n").to_uppercase();
+        assert_eq!(ContentFingerprint::new(&reflowed).hash, previous.hash);
+        assert!(!has_changed(&previous, &ContentFingerprint::new(&reflowed)));
+
+        let updated = format!("{} Update: the mayor vetoed the budget on Friday, sending it back to the finance \
+            committee. A new vote is expected next month after talks with the transport operators.", article);
+        assert!(has_changed(&previous, &ContentFingerprint::new(&updated)));
+
+        let result = ExtractionResult { content: updated.clone(), ..Default::default() };
+        assert_eq!(ContentFingerprint::from(&result), ContentFingerprint::new(&updated));
+    }
+}
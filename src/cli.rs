@@ -1,48 +1,561 @@
 //! Command-line interface for Trafilatura Rust port.
 //! This module provides the CLI interface for the Trafilatura library.
+//!
+//! Functionality is grouped into subcommands (`extract`, `fetch`, `metadata`,
+//! `sitemap`, `feed`, `crawl`, `serve`, `eval`, `diff`). Invoking the binary without a
+//! subcommand behaves like `extract`.
 
 use std::io::{self, Read};
-use std::path::PathBuf;
-use std::fs::File;
+use std::process;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::fs::{self, File};
 use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::thread;
 
-use clap::{Parser, ValueEnum};
-use log::debug;
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use tracing::debug;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
+use url::Url;
 
-use crate::{ExtractionConfig, OutputFormat, TrafilaturaError};
-use crate::{extract_html, extract_url, extract_file};
-use crate::utils::{is_url, is_file_path, is_html_content};
+use crate::{ExtractionConfig, ExtractionMode, ExtractionResult, Focus, ImageStyle, LinkStyle, OutputFormat, Preset, RubyStyle, ScoringMode, TextHeader, TrafilaturaError, XmlMetadata};
+use crate::batch::{read_failed_urls, BatchItem, BatchOptions, BatchStream, ErrorRecord, RetryFilter, SharedFetcher};
+use crate::corpus::{CorpusOptions, CorpusWriter, MinHashOptions, SimHashFilter};
+use crate::dates::DateOptions;
+use crate::dedup::{DedupCache, DedupOptions};
+use crate::diff::{diff_text, DiffReport, DocumentDiff};
+use crate::dns::{DnsOptions, IpFamily};
+use crate::eval::{evaluate_dir, EvalLevel};
+use crate::feeds::fetch_feed_entries;
+use crate::fetch::{HttpFetcher, TlsOptions, TlsVersion};
+use crate::memory::MemoryUsage;
+use crate::metadata::{MetadataBlacklist, MetadataField};
+use crate::output::{format_csv_row, format_feed, format_result, to_json, FeedChannel, FeedFormat};
+use crate::politeness::{DomainPolicy, PolitenessRules};
+use crate::proxy::{ProxyOptions, ProxyRotation};
+use crate::server::serve;
+use crate::shutdown::ShutdownSignal;
+use crate::settings::Settings;
+use crate::site_rules::{SiteRuleCache, SiteRuleOptions};
+use crate::sitemaps::{fetch_sitemap_entries_since, parse_lastmod, write_sitemap_files, SitemapEntry, SitemapState};
+use crate::spider::{CrawlOptions, CrawlScope, HostScope, Spider};
+use crate::tables::Table;
+use crate::xpath_expr::XPathExpr;
+use crate::{extract_html, extract_html_metadata, extract_html_metadata_prefix, extract_url, extract_url_metadata, extract_file, fetch_url};
+use crate::utils::{is_url, is_file_path, is_html_content, is_html_file};
+use crate::watch::watch_directory;
 
 /// Command line arguments
 #[derive(Parser, Debug)]
 #[clap(name = "trafilatura")]
 #[clap(version = env!("CARGO_PKG_VERSION"))]
 #[clap(about = "A Rust port of Python's trafilatura library for extracting text from web pages")]
+#[clap(args_conflicts_with_subcommands = true)]
 pub struct Cli {
+    #[clap(subcommand)]
+    command: Option<Command>,
+    
+    /// Without a subcommand, the arguments of `extract` are accepted directly
+    #[clap(flatten)]
+    extract: ExtractArgs,
+    
+    /// Be verbose
+    #[clap(short, long, global = true)]
+    verbose: bool,
+}
+
+/// Subcommands
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Extract the main text of a URL, file, or HTML passed via stdin (default)
+    Extract(ExtractArgs),
+    /// Download the raw HTML of a URL
+    Fetch(FetchArgs),
+    /// Extract only the metadata of a URL, file, or HTML passed via stdin, as JSON
+    Metadata(MetadataArgs),
+    /// List or extract the pages of a sitemap
+    Sitemap(SitemapArgs),
+    /// List or extract the articles of an RSS/Atom feed
+    Feed(FeedArgs),
+    /// Crawl a site from a start page and list or extract the pages found
+    Crawl(CrawlArgs),
+    /// Serve extraction over HTTP
+    Serve(ServeArgs),
+    /// Score extraction against a directory of `<name>.html`/`<name>.txt` pairs
+    Eval(EvalArgs),
+    /// Compare the extractions of a sample set under two configs or binaries
+    Diff(DiffArgs),
+}
+
+/// Arguments of the `extract` subcommand
+#[derive(Args, Debug)]
+pub struct ExtractArgs {
     /// Input URL, file path, or HTML content
     #[clap(index = 1, required = false)]
     input: Option<String>,
     
-    /// Output format
-    #[clap(short, long, value_enum, default_value = "text")]
-    format: Format,
+    /// Output file (defaults to stdout); with `--watch`, the directory results are written to
+    #[clap(short = 'o', long)]
+    output: Option<PathBuf>,
+    
+    /// Write each extracted table as a CSV file into this directory, leaving
+    /// `[Table N]` placeholders in the text
+    #[clap(long)]
+    tables_dir: Option<PathBuf>,
+    
+    /// Watch a directory and extract new HTML files as they appear
+    #[clap(long, value_name = "DIR", conflicts_with = "input")]
+    watch: Option<PathBuf>,
+    
+    #[clap(flatten)]
+    options: ExtractionArgs,
+}
+
+/// Arguments of the `fetch` subcommand
+#[derive(Args, Debug)]
+pub struct FetchArgs {
+    /// URL to download
+    url: String,
     
     /// Output file (defaults to stdout)
     #[clap(short = 'o', long)]
     output: Option<PathBuf>,
     
-    /// Include tables in the extraction
-    #[clap(short = 't', long, default_value = "true")]
-    include_tables: bool,
+    #[clap(flatten)]
+    http: HttpArgs,
+}
+
+/// Arguments of the `metadata` subcommand
+#[derive(Args, Debug)]
+pub struct MetadataArgs {
+    /// Input URL, file path, or HTML content
+    input: Option<String>,
+    
+    /// Output file (defaults to stdout)
+    #[clap(short = 'o', long)]
+    output: Option<PathBuf>,
+    
+    /// Parse and download the page only up to 64 KB after the start of the body;
+    /// faster on long pages, but misses metadata further down
+    #[clap(long)]
+    prefix: bool,
+    
+    #[clap(flatten)]
+    http: HttpArgs,
+}
+
+/// Arguments of the `sitemap` subcommand
+#[derive(Args, Debug)]
+pub struct SitemapArgs {
+    /// URL of the sitemap or sitemap index
+    url: String,
+    
+    /// Maximum number of pages to list or extract
+    #[clap(long, default_value = "1000")]
+    max_urls: usize,
+    
+    /// Only pages with a `lastmod` after this date (e.g. 2024-05-01 or 2024-05-01T06:00:00Z)
+    #[clap(long, value_name = "DATE", value_parser = parse_date)]
+    since: Option<DateTime<Utc>>,
+    
+    /// JSON file recording the last run over each sitemap; pages not modified since
+    /// the last run are skipped. The time of this run is only recorded once it got
+    /// through every modified page, not when interrupted or cut short by --max-urls.
+    #[clap(long, value_name = "FILE")]
+    state: Option<PathBuf>,
+    
+    #[clap(flatten)]
+    batch: BatchArgs,
+}
+
+/// Arguments of the `feed` subcommand
+#[derive(Args, Debug)]
+pub struct FeedArgs {
+    /// URL of the feed, or of a page declaring one
+    url: String,
+    
+    #[clap(flatten)]
+    batch: BatchArgs,
+}
+
+/// Arguments of the `crawl` subcommand
+#[derive(Args, Debug)]
+pub struct CrawlArgs {
+    /// Start page of the crawl
+    url: String,
+    
+    /// Maximum number of pages to visit
+    #[clap(long, default_value = "10")]
+    max_pages: usize,
+    
+    /// Maximum link depth from the start page
+    #[clap(long, default_value = "3")]
+    max_depth: usize,
+    
+    /// Minimum time between the starts of two requests to the same host in
+    /// milliseconds, shared by all fetchers; the crawl delays of --politeness rules
+    /// take precedence for their domains [default: 1000, or SLEEP_TIME of --config]
+    #[clap(long)]
+    delay: Option<u64>,
+    
+    /// Hosts to crawl: the start host only, or its whole registrable domain
+    #[clap(long, value_enum, default_value = "host")]
+    scope: ScopeArg,
+    
+    /// Crawl these domains and their subdomains besides the start host (repeatable)
+    #[clap(long = "allow-domain", value_name = "DOMAIN")]
+    allow_domains: Vec<String>,
+    
+    /// Only crawl URLs whose path starts with this prefix (repeatable)
+    #[clap(long = "path-prefix", value_name = "PREFIX")]
+    path_prefixes: Vec<String>,
+    
+    /// Only crawl URLs matching one of these regular expressions (repeatable)
+    #[clap(long, value_name = "REGEX")]
+    include: Vec<String>,
+    
+    /// Never crawl URLs matching these regular expressions (repeatable)
+    #[clap(long, value_name = "REGEX")]
+    exclude: Vec<String>,
+    
+    /// Skip URLs longer than this many characters
+    #[clap(long, value_name = "N")]
+    max_url_length: Option<usize>,
+    
+    /// JSON Lines file the crawl state is saved to after every page and resumed from
+    /// on the next run; `--max-pages` then counts the pages of all runs, and the
+    /// crawl delays of the politeness rules count from the requests of earlier runs
+    #[clap(long, value_name = "FILE")]
+    state: Option<PathBuf>,
+    
+    /// Write the fetched pages to an XML sitemap, with their fetch times as `lastmod`;
+    /// beyond 50,000 pages, FILE is a sitemap index of numbered parts next to it
+    #[clap(long, value_name = "FILE")]
+    export_sitemap: Option<PathBuf>,
+    
+    /// Write the URLs of the fetched pages to a file, one per line
+    #[clap(long, value_name = "FILE")]
+    export_urls: Option<PathBuf>,
+    
+    #[clap(flatten)]
+    batch: BatchArgs,
+}
+
+/// Arguments of the `serve` subcommand
+#[derive(Args, Debug)]
+pub struct ServeArgs {
+    /// Address to listen on
+    #[clap(long, default_value = "127.0.0.1:8080")]
+    bind: String,
+    
+    #[clap(flatten)]
+    options: ExtractionArgs,
+}
+
+/// Arguments of the `eval` subcommand
+#[derive(Args, Debug)]
+pub struct EvalArgs {
+    /// Directory holding the labeled dataset
+    dir: PathBuf,
+    
+    /// Unit in which extracted and expected text are compared
+    #[clap(long, value_enum, default_value = "token")]
+    level: EvalLevelArg,
+    
+    #[clap(flatten)]
+    options: ExtractionArgs,
+}
+
+/// Arguments of the `diff` subcommand
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// HTML file, or directory of HTML files, to extract on both sides
+    samples: PathBuf,
+    
+    /// Extraction options of the baseline, separated by spaces, e.g. "--preset news"
+    #[clap(long, value_name = "OPTIONS", default_value = "", allow_hyphen_values = true)]
+    a: String,
+    
+    /// Extraction options of the changed side, separated by spaces
+    #[clap(long, value_name = "OPTIONS", default_value = "", allow_hyphen_values = true)]
+    b: String,
+    
+    /// Extract the baseline by running this trafilatura binary, e.g. a release build,
+    /// on each sample with the `--a` options, comparing its output
+    #[clap(long, value_name = "PATH")]
+    a_binary: Option<PathBuf>,
+    
+    /// Extract the changed side by running this trafilatura binary with the `--b` options
+    #[clap(long, value_name = "PATH")]
+    b_binary: Option<PathBuf>,
+}
+
+/// Extraction options given as a single argument of `diff`
+#[derive(Parser, Debug)]
+#[clap(no_binary_name = true)]
+struct DiffOptions {
+    #[clap(flatten)]
+    options: ExtractionArgs,
+}
+
+/// One side of a diff: this build with a config, or another binary with its options
+enum DiffSide {
+    Config(Box<ExtractionConfig>),
+    Binary(PathBuf, Vec<String>),
+}
+
+impl DiffSide {
+    fn new(options: &str, binary: Option<&Path>) -> Result<Self, TrafilaturaError> {
+        let options: Vec<String> = options.split_whitespace().map(String::from).collect();
+        match binary {
+            // Flat invocation, which binaries predating subcommands accept too
+            Some(binary) => Ok(DiffSide::Binary(binary.to_path_buf(), options)),
+            None => {
+                let parsed = DiffOptions::try_parse_from(&options)
+                    .map_err(|e| TrafilaturaError::ConfigError(format!("invalid diff options {:?}: {}", options.join(" "), e)))?;
+                Ok(DiffSide::Config(Box::new(parsed.options.to_config()?)))
+            },
+        }
+    }
+    
+    /// Extract the main text of an HTML file
+    fn extract(&self, path: &Path) -> Result<String, TrafilaturaError> {
+        match self {
+            DiffSide::Config(config) => extract_file(path, config).map(|result| result.content),
+            DiffSide::Binary(binary, options) => {
+                let output = process::Command::new(binary).arg(path).args(options).output()?;
+                if !output.status.success() {
+                    return Err(TrafilaturaError::ExtractionError(format!(
+                        "{} exited with {}: {}", binary.display(), output.status, String::from_utf8_lossy(&output.stderr).trim()
+                    )));
+                }
+                Ok(String::from_utf8_lossy(&output.stdout).trim_end().to_string())
+            },
+        }
+    }
+}
+
+/// Arguments shared by subcommands that process many URLs
+#[derive(Args, Debug)]
+pub struct BatchArgs {
+    /// Only list the URLs instead of extracting them
+    #[clap(long)]
+    list: bool,
+    
+    /// Output file (defaults to stdout)
+    #[clap(short = 'o', long)]
+    output: Option<PathBuf>,
+    
+    /// Aggregate the extracted pages into one RSS or Atom feed instead of writing them one by one
+    #[clap(long, value_enum)]
+    feed: Option<FeedFormatArg>,
+    
+    /// Title of the aggregated feed [default: the host of the source URL]
+    #[clap(long, value_name = "TITLE")]
+    feed_title: Option<String>,
+    
+    /// Description of the aggregated feed
+    #[clap(long, value_name = "TEXT")]
+    feed_description: Option<String>,
+    
+    /// Write the extracted pages as a corpus of sharded JSON Lines files (text, url,
+    /// timestamp, language, fingerprint) with a manifest to this directory, replacing
+    /// the shards of an earlier export there
+    #[clap(long, value_name = "DIR")]
+    corpus: Option<PathBuf>,
+    
+    /// Maximum uncompressed size of a corpus shard, in megabytes
+    #[clap(long, value_name = "MB", default_value = "100")]
+    shard_size: u64,
+    
+    /// Compress corpus shards with gzip
+    #[clap(long)]
+    gzip: bool,
+    
+    /// Leave pages out of the corpus whose fingerprint differs in at most this many bits
+    /// from a page already written
+    #[clap(long, value_name = "BITS")]
+    near_dup_distance: Option<u32>,
+    
+    /// Add MinHash signatures to the corpus records, for deduplication with LSH downstream
+    #[clap(long)]
+    minhash: bool,
+    
+    /// Number of MinHash permutations, i.e. the signature length
+    #[clap(long, value_name = "N", default_value = "128")]
+    minhash_permutations: usize,
+    
+    /// Words per MinHash shingle
+    #[clap(long, value_name = "N", default_value = "5")]
+    shingle_size: usize,
+    
+    /// Number of threads downloading pages
+    #[clap(long, value_name = "N", default_value = "4")]
+    fetchers: usize,
+    
+    /// Write a JSON line for each page that could not be downloaded or extracted to
+    /// FILE (url, stage, error_kind, message, http_status), e.g. to queue failed URLs again
+    #[clap(long, value_name = "FILE")]
+    errors: Option<PathBuf>,
+    
+    /// Only download and extract again the pages recorded as failed in FILE, an
+    /// `--errors` log or the JSON output of a previous batch, instead of finding the
+    /// pages of the sitemap, feed, or crawl anew
+    #[clap(long, value_name = "FILE")]
+    retry_from: Option<PathBuf>,
+    
+    /// Only retry pages that failed with one of these error kinds (e.g. timeout,connect)
+    #[clap(long, value_delimiter = ',', value_name = "KINDS", requires = "retry_from")]
+    retry_kind: Vec<String>,
+    
+    /// Only retry pages that failed with one of these HTTP statuses (e.g. 429,503)
+    #[clap(long, value_delimiter = ',', value_name = "STATUSES", requires = "retry_from")]
+    retry_status: Vec<u16>,
+    
+    /// Number of threads extracting downloaded pages [default: number of CPUs]
+    #[clap(long, value_name = "N")]
+    extractors: Option<usize>,
+    
+    /// Stop extracting a page once it uses more than this many megabytes, so one
+    /// giant page cannot exhaust the memory of the batch
+    #[clap(long, value_name = "MB")]
+    memory_budget: Option<usize>,
+    
+    #[clap(flatten)]
+    options: ExtractionArgs,
+}
+
+impl BatchArgs {
+    /// Build the extraction configuration; feeds and corpora need the metadata
+    fn to_config(&self) -> Result<ExtractionConfig, TrafilaturaError> {
+        let config = self.options.to_config()?;
+        Ok(ExtractionConfig {
+            extract_metadata: config.extract_metadata || self.feed.is_some() || self.corpus.is_some(),
+            memory_budget: self.memory_budget.map(|megabytes| megabytes << 20),
+            ..config
+        })
+    }
+    
+    /// URLs of the failed pages to retry with `--retry-from`, if set
+    fn retry_urls(&self) -> Result<Option<Vec<String>>, TrafilaturaError> {
+        let Some(path) = &self.retry_from else {
+            return Ok(None);
+        };
+        let filter = RetryFilter {
+            error_kinds: self.retry_kind.clone(),
+            http_statuses: self.retry_status.clone(),
+        };
+        let urls = read_failed_urls(path, &filter)?;
+        eprintln!("Retrying {} failed pages from {}", urls.len(), path.display());
+        Ok(Some(urls))
+    }
+    
+    /// Size the download and extraction pools; results are written in completion order
+    fn batch_options(&self) -> BatchOptions {
+        let extractors = self.extractors
+            .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()));
+        BatchOptions {
+            fetchers: self.fetchers.max(1),
+            extractors: extractors.max(1),
+            extract: !self.list,
+            ..Default::default()
+        }
+    }
+}
+
+/// Extraction options shared by all extracting subcommands
+#[derive(Args, Debug)]
+pub struct ExtractionArgs {
+    /// Output format
+    #[clap(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+    
+    /// Start from the settings tuned for a kind of site; other options override them
+    #[clap(long, value_enum)]
+    preset: Option<PresetArg>,
+    
+    /// Kind of document to extract from [default: standard]
+    #[clap(long, value_enum)]
+    mode: Option<ModeArg>,
+    
+    /// Reflow text output to at most N characters per line
+    #[clap(long, value_name = "N")]
+    wrap: Option<usize>,
+    
+    /// Prepend YAML front matter (title, date, author, url, tags) to Markdown output
+    #[clap(long)]
+    front_matter: bool,
+    
+    /// Index named in the action lines of bulk output
+    #[clap(long, value_name = "NAME", default_value = "trafilatura")]
+    bulk_index: String,
+    
+    /// Write XML output in the schema of Python trafilatura's `--xml` output
+    /// (`<doc>` root with metadata attributes, text in `<main>`)
+    #[clap(long)]
+    python_xml: bool,
+    
+    /// Where XML output puts the metadata: attributes of the `<document>` root, as in
+    /// Python trafilatura, or elements after the content
+    #[clap(long, value_enum, default_value = "attributes")]
+    xml_metadata: XmlMetadataArg,
+    
+    /// Indent JSON and XML output (default)
+    #[clap(long, overrides_with = "compact")]
+    pretty: bool,
+    
+    /// Write JSON and XML output on one line per document, e.g. JSON Lines for large dumps
+    #[clap(long, overrides_with = "pretty")]
+    compact: bool,
+    
+    /// Spaces per nesting level of indented JSON and XML output
+    #[clap(long, value_name = "N", default_value = "2")]
+    indent: usize,
+    
+    /// Include tables in the extraction, or not with `--include-tables=false`;
+    /// overrides --preset [default: true]
+    #[clap(short = 't', long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    include_tables: Option<bool>,
+    
+    /// Include links in the extraction, or not with `--include-links=false`;
+    /// overrides --preset [default: true]
+    #[clap(short = 'l', long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true", overrides_with = "no_links")]
+    include_links: Option<bool>,
     
-    /// Include links in the extraction
-    #[clap(short = 'l', long, default_value = "true")]
-    include_links: bool,
+    /// Leave link targets out of the text, whatever --link-style and --preset say
+    #[clap(long, overrides_with = "include_links")]
+    no_links: bool,
+    
+    /// How links are rendered in text output
+    #[clap(long, value_enum, default_value = "parenthetical")]
+    link_style: LinkStyleArg,
+    
+    /// How ruby readings such as furigana are rendered: dropped, or as `base(reading)`
+    #[clap(long, value_enum, default_value = "stripped")]
+    ruby: RubyStyleArg,
+    
+    /// Put the title, or the title and a line with the author and date, before the
+    /// text of plain-text output
+    #[clap(long, value_enum, default_value = "none")]
+    text_header: TextHeaderArg,
     
     /// Include images in the extraction
     #[clap(short = 'i', long, default_value = "false")]
     include_images: bool,
     
+    /// How images are rendered: skip, alt-text (`[Image: alt]`), markdown (`[Image: alt](url)`),
+    /// or figure (also the figure caption); implies --include-images [default: alt-text]
+    #[clap(long, value_enum)]
+    image_style: Option<ImageStyleArg>,
+    
+    /// Render inline SVG graphics with a title as images captioned with it; their
+    /// other text, like chart labels, is always left out
+    #[clap(long, default_value = "false")]
+    svg_titles: bool,
+    
     /// Include comments in the extraction
     #[clap(short = 'c', long, default_value = "false")]
     include_comments: bool,
@@ -51,39 +564,615 @@ pub struct Cli {
     #[clap(short = 'm', long, default_value = "false")]
     extract_metadata: bool,
     
+    /// Favor precision: also discard headers and link-heavy or bordered boxes
+    #[clap(long, conflicts_with = "recall")]
+    precision: bool,
+    
+    /// Favor recall: keep teasers
+    #[clap(long)]
+    recall: bool,
+    
+    /// How to score content candidates: by class and ID names, by structure alone,
+    /// or by structure when class names look generated [default: auto]
+    #[clap(long, value_enum)]
+    scoring: Option<ScoringModeArg>,
+    
+    /// Fall back to the latest Wayback Machine snapshot for 404/410/paywalled URLs
+    #[clap(long)]
+    wayback: bool,
+    
+    /// Output the article links of index and category pages instead of their teaser text
+    #[clap(long)]
+    harvest_listings: bool,
+    
+    /// Tag each block of JSON and XML output with its detected language, for pages mixing languages
+    #[clap(long)]
+    block_languages: bool,
+    
+    /// Drop paragraphs and documents seen more than twice before, like Python trafilatura's --deduplicate
+    #[clap(long)]
+    deduplicate: bool,
+    
+    /// Number of texts remembered for --deduplicate
+    #[clap(long, value_name = "N", default_value = "4096")]
+    dedup_cache_size: usize,
+    
+    /// Drop paragraphs repeating an earlier paragraph verbatim (duplicated AMP bodies, repeated ledes)
+    #[clap(long)]
+    dedup_paragraphs: bool,
+    
+    /// Learn the content container of each site from repeated wins and use it on later pages of the site
+    #[clap(long)]
+    learn_site_rules: bool,
+    
+    /// Extract only these metadata fields (comma-separated), e.g. `title,date`; implies --extract-metadata
+    #[clap(long, value_delimiter = ',', value_name = "FIELDS")]
+    metadata_fields: Vec<MetadataFieldArg>,
+    
+    /// Date format (chrono syntax, e.g. `%d.%m.%y`) tried before the built-in formats; repeatable
+    #[clap(long = "date-format", value_name = "FORMAT")]
+    date_formats: Vec<String>,
+    
+    /// Read dates in the day/month order and month names of this language (e.g. `de`, `en-GB`)
+    /// instead of the language declared by the page
+    #[clap(long, value_name = "LANG")]
+    date_locale: Option<String>,
+    
+    /// Earliest date accepted as the date of a page, YYYY-MM-DD (default 1995-01-01)
+    #[clap(long, value_name = "DATE")]
+    min_date: Option<NaiveDate>,
+    
+    /// Latest date accepted as the date of a page, YYYY-MM-DD (default today)
+    #[clap(long, value_name = "DATE")]
+    max_date: Option<NaiveDate>,
+    
+    /// JSON config file whose `metadata_blacklist` section lists generic author names
+    /// ("Staff Writer") and sitewide phrases to drop from the metadata, globally or per domain
+    #[clap(long, value_name = "FILE")]
+    metadata_blacklist: Option<PathBuf>,
+    
+    /// Keep paragraphs that look like publication boilerplate ("Published on", "Read More")
+    #[clap(long)]
+    keep_boilerplate: bool,
+    
+    /// Let the og:type of a page adjust extraction: video and music pages return only
+    /// metadata, home pages and profiles need more text, and products less
+    #[clap(long)]
+    og_type_shortcuts: bool,
+    
+    /// Additional elements to remove before extraction (comma-separated tag names)
+    #[clap(long, value_delimiter = ',')]
+    strip_elements: Vec<String>,
+    
+    /// Elements to preserve even though they are removed by default, e.g. `time,cite,figcaption`
+    #[clap(long, value_delimiter = ',')]
+    keep_elements: Vec<String>,
+    
+    /// XPath rule for the main content container, tried before the built-in selectors;
+    /// repeat to try several rules in order, e.g. rules from Python trafilatura's BODY_XPATH
+    #[clap(long, value_name = "EXPR", value_parser = parse_xpath)]
+    content_xpath: Vec<XPathExpr>,
+    
+    /// JSON config file whose `content_xpaths` section lists XPath rules for the
+    /// main content container, tried after --content-xpath
+    #[clap(long, value_name = "FILE")]
+    xpath_rules: Option<PathBuf>,
+    
+    /// Additional query parameters to strip when normalizing URLs (comma-separated,
+    /// a trailing `*` matches a prefix)
+    #[clap(long, value_delimiter = ',')]
+    strip_params: Vec<String>,
+    
+    /// Minimum extracted content size to be considered valid [default: 250]
+    #[clap(long)]
+    min_extracted_size: Option<usize>,
+    
+    #[clap(flatten)]
+    http: HttpArgs,
+}
+
+/// HTTP options shared by all subcommands that fetch pages
+#[derive(Args, Debug)]
+pub struct HttpArgs {
     /// User agent for HTTP requests
     #[clap(short = 'u', long)]
     user_agent: Option<String>,
     
-    /// Timeout in seconds for HTTP requests
-    #[clap(short = 's', long, default_value = "30")]
-    timeout: u64,
+    /// Timeout in seconds for HTTP requests [default: 30]
+    #[clap(short = 's', long)]
+    timeout: Option<u64>,
     
-    /// Minimum extracted content size to be considered valid
-    #[clap(long, default_value = "250")]
-    min_extracted_size: usize,
+    /// Refuse pages larger than this many megabytes, before or after decompression
+    /// [default: 20]
+    #[clap(long, value_name = "MB")]
+    max_body_size: Option<usize>,
     
-    /// Be verbose
-    #[clap(short, long)]
-    verbose: bool,
+    /// Settings file (INI or TOML, like Python trafilatura's settings.cfg) with min
+    /// sizes, user agent, timeout, unwanted classes and IDs, and selectors; other
+    /// options override it
+    #[clap(long = "config", value_name = "FILE")]
+    settings: Option<PathBuf>,
+    
+    /// JSON config file whose `politeness` section maps domains to crawl delay,
+    /// concurrency, headers, and user agent
+    #[clap(long, value_name = "FILE")]
+    politeness: Option<PathBuf>,
+    
+    /// PEM file of additional root certificates to trust, e.g. a private CA
+    #[clap(long, value_name = "FILE")]
+    ca_bundle: Option<PathBuf>,
+    
+    /// Accept invalid and self-signed TLS certificates (internal mirrors only)
+    #[clap(long)]
+    danger_accept_invalid_certs: bool,
+    
+    /// Minimum TLS version to connect with
+    #[clap(long, value_enum, value_name = "VERSION")]
+    min_tls_version: Option<TlsVersionArg>,
+    
+    /// Proxies to rotate requests through (comma-separated or repeated)
+    #[clap(long = "proxy", value_name = "URL", value_delimiter = ',')]
+    proxies: Vec<String>,
+    
+    /// How the proxy of each request is chosen
+    #[clap(long, value_enum, default_value = "round-robin")]
+    proxy_rotation: ProxyRotationArg,
+    
+    /// Consecutive failures after which a proxy is no longer used
+    #[clap(long, value_name = "N", default_value = "3")]
+    proxy_max_failures: usize,
+    
+    /// Connect over IPv4 only
+    #[clap(short = '4', long, conflicts_with = "ipv6")]
+    ipv4: bool,
+    
+    /// Connect over IPv6 only
+    #[clap(short = '6', long)]
+    ipv6: bool,
+    
+    /// Use a fixed address for a host instead of resolving it (repeatable)
+    #[clap(long, value_name = "HOST:ADDRESS", value_parser = parse_resolve)]
+    resolve: Vec<(String, IpAddr)>,
+    
+    /// Refuse URLs resolving to loopback, private, or link-local addresses
+    #[clap(long)]
+    block_private_ips: bool,
+}
+
+impl ExtractionArgs {
+    /// Build the extraction config from the command line options
+    fn to_config(&self) -> Result<ExtractionConfig, TrafilaturaError> {
+        let defaults = match self.preset {
+            Some(preset) => self.http.apply(ExtractionConfig::preset(preset.into()))?,
+            None => self.http.to_config()?,
+        };
+        let mut url_param_blocklist = defaults.url_param_blocklist.clone();
+        url_param_blocklist.extend(self.strip_params.iter().cloned());
+        
+        let config = ExtractionConfig {
+            include_comments: defaults.include_comments || self.include_comments,
+            focus: if self.precision {
+                Focus::Precision
+            } else if self.recall {
+                Focus::Recall
+            } else {
+                defaults.focus
+            },
+            scoring_mode: self.scoring.map_or(defaults.scoring_mode, Into::into),
+            include_tables: self.include_tables.unwrap_or(defaults.include_tables),
+            include_links: !self.no_links && self.include_links.unwrap_or(defaults.include_links),
+            link_style: self.link_style.into(),
+            ruby_style: self.ruby.into(),
+            text_header: self.text_header.into(),
+            include_images: defaults.include_images || self.include_images || self.image_style.is_some(),
+            image_style: self.image_style.map_or(defaults.image_style, Into::into),
+            svg_titles: self.svg_titles,
+            output_format: self.format,
+            min_extracted_size: self.min_extracted_size.unwrap_or(defaults.min_extracted_size),
+            // Front matter, text headers, and the attributes of Python-style XML are built
+            // from the metadata
+            extract_metadata: defaults.extract_metadata || self.extract_metadata || self.front_matter || self.python_xml
+                || !matches!(self.text_header, TextHeaderArg::None)
+                || !self.metadata_fields.is_empty(),
+            metadata_fields: if self.metadata_fields.is_empty() {
+                defaults.metadata_fields.clone()
+            } else {
+                Some(self.metadata_fields.iter().map(|&field| field.into()).collect())
+            },
+            dates: DateOptions {
+                formats: self.date_formats.iter().chain(&defaults.dates.formats).cloned().collect(),
+                locale: self.date_locale.clone().or_else(|| defaults.dates.locale.clone()),
+                min_date: self.min_date.unwrap_or(defaults.dates.min_date),
+                max_date: self.max_date.or(defaults.dates.max_date),
+            },
+            front_matter: self.front_matter,
+            wrap_width: self.wrap,
+            bulk_index: self.bulk_index.clone(),
+            python_xml: self.python_xml,
+            xml_metadata: self.xml_metadata.into(),
+            indent: (self.pretty || !self.compact).then_some(self.indent),
+            mode: self.mode.map_or(defaults.mode, Into::into),
+            wayback_fallback: self.wayback,
+            url_param_blocklist,
+            harvest_listings: self.harvest_listings,
+            tag_block_languages: self.block_languages,
+            dedup: self.deduplicate.then(|| {
+                Arc::new(DedupCache::new(DedupOptions { cache_size: self.dedup_cache_size, ..defaults.dedup_options }))
+            }),
+            dedup_paragraphs: defaults.dedup_paragraphs || self.dedup_paragraphs,
+            site_rules: self.learn_site_rules.then(|| Arc::new(SiteRuleCache::new(SiteRuleOptions::default()))),
+            metadata_blacklist: match &self.metadata_blacklist {
+                Some(path) => MetadataBlacklist::from_file(path)?,
+                None => defaults.metadata_blacklist.clone(),
+            },
+            filter_boilerplate: defaults.filter_boilerplate && !self.keep_boilerplate,
+            og_type_shortcuts: defaults.og_type_shortcuts || self.og_type_shortcuts,
+            strip_elements: defaults.strip_elements.iter().chain(&self.strip_elements).cloned().collect(),
+            keep_elements: defaults.keep_elements.iter().chain(&self.keep_elements).cloned().collect(),
+            // Rules given on the command line are tried before those of the settings file
+            content_xpaths: {
+                let mut rules = self.content_xpath.clone();
+                if let Some(path) = &self.xpath_rules {
+                    rules.extend(XPathExpr::from_file(path)?);
+                }
+                rules.extend(defaults.content_xpaths.iter().cloned());
+                rules
+            },
+            ..defaults
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+impl HttpArgs {
+    /// Build a default extraction config with the HTTP options applied
+    fn to_config(&self) -> Result<ExtractionConfig, TrafilaturaError> {
+        self.apply(ExtractionConfig::default())
+    }
+    
+    /// Apply the address family, host override, and address blocking options
+    fn dns_options(&self, mut dns: DnsOptions) -> DnsOptions {
+        if self.ipv4 {
+            dns.ip_family = IpFamily::V4;
+        } else if self.ipv6 {
+            dns.ip_family = IpFamily::V6;
+        }
+        for (host, address) in &self.resolve {
+            dns.overrides.entry(host.clone()).or_default().push(*address);
+        }
+        dns.block_private |= self.block_private_ips;
+        dns
+    }
+    
+    /// Apply the settings file and the HTTP options to an extraction config
+    fn apply(&self, config: ExtractionConfig) -> Result<ExtractionConfig, TrafilaturaError> {
+        let config = match &self.settings {
+            Some(path) => Settings::from_file(path)?.apply(config)?,
+            None => config,
+        };
+        let politeness = match &self.politeness {
+            Some(path) => {
+                // The delay of a settings file still applies to the other domains
+                let mut politeness = PolitenessRules::from_file(path)?;
+                if let Some(fallback) = config.politeness.fallback() {
+                    politeness.set_fallback(fallback.clone());
+                }
+                politeness
+            },
+            None => config.politeness.clone(),
+        };
+        
+        Ok(ExtractionConfig {
+            extraction_timeout: self.timeout.unwrap_or(config.extraction_timeout),
+            max_body_size: self.max_body_size.map_or(config.max_body_size, |megabytes| megabytes * 1_000_000),
+            user_agent: self.user_agent.clone().unwrap_or(config.user_agent.clone()),
+            politeness,
+            tls: TlsOptions {
+                ca_bundle: self.ca_bundle.clone().or(config.tls.ca_bundle.clone()),
+                danger_accept_invalid_certs: self.danger_accept_invalid_certs || config.tls.danger_accept_invalid_certs,
+                min_version: self.min_tls_version.map(Into::into).or(config.tls.min_version),
+            },
+            proxies: ProxyOptions {
+                proxies: self.proxies.clone(),
+                rotation: self.proxy_rotation.into(),
+                max_failures: self.proxy_max_failures,
+            },
+            dns: self.dns_options(config.dns.clone()),
+            ..config
+        })
+    }
+}
+
+/// Parse a `--since` date in the formats of sitemap `lastmod` values
+fn parse_date(value: &str) -> Result<DateTime<Utc>, String> {
+    parse_lastmod(value).ok_or_else(|| format!("invalid date: {}", value))
+}
+
+/// Parse a `HOST:ADDRESS` host override, e.g. `archive.internal:10.0.0.5` or `example.com:[::1]`
+fn parse_resolve(value: &str) -> Result<(String, IpAddr), String> {
+    let (host, address) = value.split_once(':').ok_or("expected HOST:ADDRESS")?;
+    let address = address.trim_start_matches('[').trim_end_matches(']')
+        .parse()
+        .map_err(|e| format!("invalid address: {}", e))?;
+    Ok((host.to_lowercase(), address))
 }
 
-/// Output format enum
+/// Parse a `--content-xpath` rule
+fn parse_xpath(value: &str) -> Result<XPathExpr, String> {
+    XPathExpr::parse(value).map_err(|e| e.to_string())
+}
+
+/// Aggregated feed format enum
 #[derive(Debug, Clone, Copy, ValueEnum)]
-pub enum Format {
-    Text,
-    Html,
-    Json,
-    Xml,
+pub enum FeedFormatArg {
+    Rss,
+    Atom,
 }
 
-impl From<Format> for OutputFormat {
-    fn from(format: Format) -> Self {
+impl From<FeedFormatArg> for FeedFormat {
+    fn from(format: FeedFormatArg) -> Self {
         match format {
-            Format::Text => OutputFormat::Text,
-            Format::Html => OutputFormat::Html,
-            Format::Json => OutputFormat::Json,
-            Format::Xml => OutputFormat::Xml,
+            FeedFormatArg::Rss => FeedFormat::Rss,
+            FeedFormatArg::Atom => FeedFormat::Atom,
+        }
+    }
+}
+
+/// Link rendering style enum
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum LinkStyleArg {
+    Stripped,
+    Parenthetical,
+    Footnote,
+    Markdown,
+}
+
+impl From<LinkStyleArg> for LinkStyle {
+    fn from(style: LinkStyleArg) -> Self {
+        match style {
+            LinkStyleArg::Stripped => LinkStyle::Stripped,
+            LinkStyleArg::Parenthetical => LinkStyle::Parenthetical,
+            LinkStyleArg::Footnote => LinkStyle::Footnote,
+            LinkStyleArg::Markdown => LinkStyle::Markdown,
+        }
+    }
+}
+
+/// Image rendering style enum
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ImageStyleArg {
+    Skip,
+    AltText,
+    Markdown,
+    Figure,
+}
+
+impl From<ImageStyleArg> for ImageStyle {
+    fn from(style: ImageStyleArg) -> Self {
+        match style {
+            ImageStyleArg::Skip => ImageStyle::Skip,
+            ImageStyleArg::AltText => ImageStyle::AltText,
+            ImageStyleArg::Markdown => ImageStyle::Markdown,
+            ImageStyleArg::Figure => ImageStyle::Figure,
+        }
+    }
+}
+
+/// Ruby annotation style enum
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum RubyStyleArg {
+    Stripped,
+    Annotated,
+}
+
+impl From<RubyStyleArg> for RubyStyle {
+    fn from(style: RubyStyleArg) -> Self {
+        match style {
+            RubyStyleArg::Stripped => RubyStyle::Stripped,
+            RubyStyleArg::Annotated => RubyStyle::Annotated,
+        }
+    }
+}
+
+/// Text header enum
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum TextHeaderArg {
+    None,
+    Title,
+    TitleAndByline,
+}
+
+impl From<TextHeaderArg> for TextHeader {
+    fn from(header: TextHeaderArg) -> Self {
+        match header {
+            TextHeaderArg::None => TextHeader::None,
+            TextHeaderArg::Title => TextHeader::Title,
+            TextHeaderArg::TitleAndByline => TextHeader::TitleAndByline,
+        }
+    }
+}
+
+/// XML metadata layout enum
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum XmlMetadataArg {
+    Attributes,
+    Elements,
+}
+
+impl From<XmlMetadataArg> for XmlMetadata {
+    fn from(layout: XmlMetadataArg) -> Self {
+        match layout {
+            XmlMetadataArg::Attributes => XmlMetadata::Attributes,
+            XmlMetadataArg::Elements => XmlMetadata::Elements,
+        }
+    }
+}
+
+/// Extraction mode enum
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ModeArg {
+    Standard,
+    Email,
+    Docs,
+}
+
+impl From<ModeArg> for ExtractionMode {
+    fn from(mode: ModeArg) -> Self {
+        match mode {
+            ModeArg::Standard => ExtractionMode::Standard,
+            ModeArg::Email => ExtractionMode::Email,
+            ModeArg::Docs => ExtractionMode::Docs,
+        }
+    }
+}
+
+/// Candidate scoring mode enum
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ScoringModeArg {
+    Auto,
+    Hints,
+    Structural,
+}
+
+impl From<ScoringModeArg> for ScoringMode {
+    fn from(mode: ScoringModeArg) -> Self {
+        match mode {
+            ScoringModeArg::Auto => ScoringMode::Auto,
+            ScoringModeArg::Hints => ScoringMode::Hints,
+            ScoringModeArg::Structural => ScoringMode::Structural,
+        }
+    }
+}
+
+/// Configuration preset enum
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum PresetArg {
+    News,
+    Blog,
+    Wiki,
+    Forum,
+    Docs,
+    Recall,
+    Precision,
+}
+
+impl From<PresetArg> for Preset {
+    fn from(preset: PresetArg) -> Self {
+        match preset {
+            PresetArg::News => Preset::News,
+            PresetArg::Blog => Preset::Blog,
+            PresetArg::Wiki => Preset::Wiki,
+            PresetArg::Forum => Preset::Forum,
+            PresetArg::Docs => Preset::Docs,
+            PresetArg::Recall => Preset::Recall,
+            PresetArg::Precision => Preset::Precision,
+        }
+    }
+}
+
+/// TLS version enum
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum TlsVersionArg {
+    #[value(name = "1.2")]
+    Tls1_2,
+    #[value(name = "1.3")]
+    Tls1_3,
+}
+
+impl From<TlsVersionArg> for TlsVersion {
+    fn from(version: TlsVersionArg) -> Self {
+        match version {
+            TlsVersionArg::Tls1_2 => TlsVersion::Tls1_2,
+            TlsVersionArg::Tls1_3 => TlsVersion::Tls1_3,
+        }
+    }
+}
+
+/// Proxy rotation enum
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ProxyRotationArg {
+    RoundRobin,
+    Random,
+}
+
+impl From<ProxyRotationArg> for ProxyRotation {
+    fn from(rotation: ProxyRotationArg) -> Self {
+        match rotation {
+            ProxyRotationArg::RoundRobin => ProxyRotation::RoundRobin,
+            ProxyRotationArg::Random => ProxyRotation::Random,
+        }
+    }
+}
+
+/// Metadata field enum
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum MetadataFieldArg {
+    Title,
+    Author,
+    AuthorUrl,
+    Date,
+    Description,
+    Publisher,
+    CopyrightYear,
+    Sitename,
+    Language,
+    CanonicalUrl,
+    Academic,
+    OgType,
+    Alternates,
+    Categories,
+}
+
+impl From<MetadataFieldArg> for MetadataField {
+    fn from(field: MetadataFieldArg) -> Self {
+        match field {
+            MetadataFieldArg::Title => MetadataField::Title,
+            MetadataFieldArg::Author => MetadataField::Author,
+            MetadataFieldArg::AuthorUrl => MetadataField::AuthorUrl,
+            MetadataFieldArg::Date => MetadataField::Date,
+            MetadataFieldArg::Description => MetadataField::Description,
+            MetadataFieldArg::Publisher => MetadataField::Publisher,
+            MetadataFieldArg::CopyrightYear => MetadataField::CopyrightYear,
+            MetadataFieldArg::Sitename => MetadataField::Sitename,
+            MetadataFieldArg::Language => MetadataField::Language,
+            MetadataFieldArg::CanonicalUrl => MetadataField::CanonicalUrl,
+            MetadataFieldArg::Academic => MetadataField::Academic,
+            MetadataFieldArg::OgType => MetadataField::OgType,
+            MetadataFieldArg::Alternates => MetadataField::Alternates,
+            MetadataFieldArg::Categories => MetadataField::Categories,
+        }
+    }
+}
+
+/// Crawl scope enum
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ScopeArg {
+    Host,
+    Domain,
+}
+
+impl From<ScopeArg> for HostScope {
+    fn from(scope: ScopeArg) -> Self {
+        match scope {
+            ScopeArg::Host => HostScope::SameHost,
+            ScopeArg::Domain => HostScope::SameDomain,
+        }
+    }
+}
+
+/// Evaluation level enum
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum EvalLevelArg {
+    Token,
+    Sentence,
+}
+
+impl From<EvalLevelArg> for EvalLevel {
+    fn from(level: EvalLevelArg) -> Self {
+        match level {
+            EvalLevelArg::Token => EvalLevel::Token,
+            EvalLevelArg::Sentence => EvalLevel::Sentence,
         }
     }
 }
@@ -92,38 +1181,71 @@ impl From<Format> for OutputFormat {
 pub fn run() -> Result<(), TrafilaturaError> {
     let cli = Cli::parse();
     
-    // Setup logging
-    if cli.verbose {
-        env_logger::Builder::new()
-            .filter_level(log::LevelFilter::Debug)
-            .init();
+    init_tracing(cli.verbose);
+    
+    match cli.command {
+        None => run_extract(cli.extract),
+        Some(Command::Extract(args)) => run_extract(args),
+        Some(Command::Fetch(args)) => run_fetch(args),
+        Some(Command::Metadata(args)) => run_metadata(args),
+        Some(Command::Sitemap(args)) => run_sitemap(args),
+        Some(Command::Feed(args)) => {
+            let config = args.batch.to_config()?;
+            let fetcher = HttpFetcher::new(&config)?;
+            let urls = match args.batch.retry_urls()? {
+                Some(urls) => urls,
+                None => fetch_feed_entries(&args.url, &fetcher)?
+                    .into_iter()
+                    .map(|entry| entry.url)
+                    .collect(),
+            };
+            run_batch(&args.url, urls, &args.batch, &config, Arc::new(fetcher)).map(|_| ())
+        },
+        Some(Command::Crawl(args)) => run_crawl(args),
+        Some(Command::Serve(args)) => {
+            let shutdown = ShutdownSignal::install()?;
+            serve(&args.bind, &args.options.to_config()?, &shutdown)
+        },
+        Some(Command::Eval(args)) => {
+            let report = evaluate_dir(&args.dir, &args.options.to_config()?, args.level.into())?;
+            println!("{}", report);
+            Ok(())
+        },
+        Some(Command::Diff(args)) => {
+            let baseline = DiffSide::new(&args.a, args.a_binary.as_deref())?;
+            let changed = DiffSide::new(&args.b, args.b_binary.as_deref())?;
+            println!("{}", diff_samples(&args.samples, &baseline, &changed)?);
+            Ok(())
+        },
     }
+}
+
+/// Log to stderr, honoring `RUST_LOG`; verbose mode logs debug events and the
+/// duration of every pipeline stage
+fn init_tracing(verbose: bool) {
+    let default_filter = if verbose { "trafilatura=debug" } else { "trafilatura=warn" };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter));
+    let span_events = if verbose { FmtSpan::CLOSE } else { FmtSpan::NONE };
     
-    // Create extraction config
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(span_events)
+        .with_writer(io::stderr)
+        .init();
+}
+
+/// Extract a single input, or watch a directory
+fn run_extract(args: ExtractArgs) -> Result<(), TrafilaturaError> {
     let config = ExtractionConfig {
-        include_comments: cli.include_comments,
-        include_tables: cli.include_tables,
-        include_links: cli.include_links,
-        include_images: cli.include_images,
-        output_format: cli.format.into(),
-        extraction_timeout: cli.timeout,
-        min_extracted_size: cli.min_extracted_size,
-        extract_metadata: cli.extract_metadata,
-        user_agent: cli.user_agent.unwrap_or_else(|| {
-            "Mozilla/5.0 (compatible; trafilatura-rs/0.1; +https://github.com/user/trafilatura-rs)".into()
-        }),
+        separate_tables: args.tables_dir.is_some(),
+        ..args.options.to_config()?
     };
     
-    // Get input
-    let input = match cli.input {
-        Some(input) => input,
-        None => {
-            // Read from stdin
-            let mut buffer = String::new();
-            io::stdin().read_to_string(&mut buffer)?;
-            buffer
-        }
-    };
+    if let Some(dir) = &args.watch {
+        return watch(dir, args.output.as_deref(), args.tables_dir.as_deref(), &config);
+    }
+    
+    let input = read_input(args.input)?;
     
     // Process input
     let result = if is_url(&input) {
@@ -141,103 +1263,553 @@ pub fn run() -> Result<(), TrafilaturaError> {
         ));
     };
     
+    // Write tables to their own CSV files
+    if let Some(dir) = &args.tables_dir {
+        write_tables(&result.tables, dir, &document_name(&input))?;
+    }
+    
     // Format output
-    let output = match config.output_format {
-        OutputFormat::Text => result.content,
-        OutputFormat::Html => format!(
-            "<html><body>{}</body></html>",
-            result.content
-        ),
-        OutputFormat::Json => {
-            let mut json_obj = serde_json::Map::new();
-            json_obj.insert("content".into(), serde_json::Value::String(result.content));
-            
-            if let Some(title) = result.title {
-                json_obj.insert("title".into(), serde_json::Value::String(title));
-            }
-            
-            if let Some(author) = result.author {
-                json_obj.insert("author".into(), serde_json::Value::String(author));
-            }
-            
-            if let Some(date) = result.date {
-                json_obj.insert("date".into(), serde_json::Value::String(date));
-            }
-            
-            if let Some(url) = result.url {
-                json_obj.insert("url".into(), serde_json::Value::String(url));
-            }
-            
-            if let Some(description) = result.description {
-                json_obj.insert("description".into(), serde_json::Value::String(description));
-            }
-            
-            if let Some(sitename) = result.sitename {
-                json_obj.insert("sitename".into(), serde_json::Value::String(sitename));
-            }
-            
-            if !result.categories.is_empty() {
-                let categories = serde_json::Value::Array(
-                    result.categories.into_iter()
-                        .map(|c| serde_json::Value::String(c))
-                        .collect()
-                );
-                json_obj.insert("categories".into(), categories);
-            }
-            
-            serde_json::to_string_pretty(&serde_json::Value::Object(json_obj))?
+    let output = format_result(&result, &config)?;
+    write_output(args.output.as_deref(), &output)
+}
+
+/// Download a page and output its HTML unchanged
+fn run_fetch(args: FetchArgs) -> Result<(), TrafilaturaError> {
+    let html = fetch_url(&args.url, &args.http.to_config()?)?;
+    write_output(args.output.as_deref(), &html)
+}
+
+/// Output the metadata of a single input as JSON
+fn run_metadata(args: MetadataArgs) -> Result<(), TrafilaturaError> {
+    let config = ExtractionConfig {
+        output_format: OutputFormat::Json,
+        metadata_prefix: args.prefix,
+        ..args.http.to_config()?
+    };
+    
+    let input = read_input(args.input)?;
+    let extract_html_metadata = if args.prefix { extract_html_metadata_prefix } else { extract_html_metadata };
+    let result = if is_url(&input) {
+        ExtractionResult { url: Some(input.clone()), ..extract_url_metadata(&input, &config)? }
+    } else if is_file_path(&input) {
+        extract_html_metadata(&fs::read_to_string(&input)?)?
+    } else {
+        extract_html_metadata(&input)?
+    };
+    
+    write_output(args.output.as_deref(), &format_result(&result, &config)?)
+}
+
+/// Crawl a site and list or extract every page visited
+fn run_crawl(args: CrawlArgs) -> Result<(), TrafilaturaError> {
+    let mut config = args.batch.to_config()?;
+    // The delay goes through the politeness gate, so fetchers share it per host;
+    // without --delay, that of a settings file is kept
+    if args.delay.is_some() || config.politeness.fallback().is_none() {
+        let delay = args.delay.unwrap_or(1000);
+        config.politeness.set_fallback(DomainPolicy { crawl_delay_ms: Some(delay), ..Default::default() });
+    }
+    // Retried pages are not crawled: their links are not followed, nor is the state saved
+    if let Some(urls) = args.batch.retry_urls()? {
+        return run_batch(&args.url, urls, &args.batch, &config, Arc::new(HttpFetcher::new(&config)?)).map(|_| ());
+    }
+    let options = CrawlOptions {
+        max_pages: args.max_pages,
+        max_depth: args.max_depth,
+        url_param_blocklist: config.url_param_blocklist.clone(),
+        scope: CrawlScope {
+            hosts: if args.allow_domains.is_empty() {
+                args.scope.into()
+            } else {
+                HostScope::Allowlist(args.allow_domains.clone())
+            },
+            path_prefixes: args.path_prefixes.clone(),
+            include: args.include.clone(),
+            exclude: args.exclude.clone(),
+            max_url_length: args.max_url_length,
         },
-        OutputFormat::Xml => {
-            let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<document>\n");
-            
-            xml.push_str(&format!("  <content><![CDATA[{}]]></content>\n", result.content));
-            
-            if let Some(title) = result.title {
-                xml.push_str(&format!("  <title><![CDATA[{}]]></title>\n", title));
-            }
-            
-            if let Some(author) = result.author {
-                xml.push_str(&format!("  <author><![CDATA[{}]]></author>\n", author));
-            }
-            
-            if let Some(date) = result.date {
-                xml.push_str(&format!("  <date><![CDATA[{}]]></date>\n", date));
-            }
-            
-            if let Some(url) = result.url {
-                xml.push_str(&format!("  <url><![CDATA[{}]]></url>\n", url));
-            }
-            
-            if let Some(description) = result.description {
-                xml.push_str(&format!("  <description><![CDATA[{}]]></description>\n", description));
-            }
-            
-            if let Some(sitename) = result.sitename {
-                xml.push_str(&format!("  <sitename><![CDATA[{}]]></sitename>\n", sitename));
-            }
-            
-            if !result.categories.is_empty() {
-                xml.push_str("  <categories>\n");
-                for category in result.categories {
-                    xml.push_str(&format!("    <category><![CDATA[{}]]></category>\n", category));
-                }
-                xml.push_str("  </categories>\n");
+    };
+    let spider = match &args.state {
+        Some(path) => Spider::resume_or_new(path, &args.url, options)?,
+        None => Spider::new(&args.url, options)?,
+    };
+    if spider.visited() > 0 {
+        eprintln!("Resuming crawl: {} pages visited, {} queued", spider.visited(), spider.frontier_len());
+    }
+    let fetcher = HttpFetcher::new(&config)?;
+    let gate = fetcher.politeness_gate();
+    gate.restore(spider.last_requests());
+    let spider = Arc::new(Mutex::new(spider));
+    
+    let shutdown = ShutdownSignal::install()?;
+    let mut sink = BatchSink::open(&args.batch, &args.url)?;
+    let mut summary = Summary::default();
+    
+    for item in BatchStream::crawl(spider.clone(), &config, Arc::new(fetcher), args.batch.batch_options()) {
+        if shutdown.is_requested() {
+            break;
+        }
+        if let Some(path) = &args.state {
+            let mut spider = spider.lock().unwrap_or_else(|e| e.into_inner());
+            spider.record_requests(gate.last_requests());
+            spider.save(path)?;
+        }
+        
+        if args.batch.list {
+            match item.error_record() {
+                None => {
+                    writeln!(sink.writer, "{}", item.source)?;
+                    summary.extracted += 1;
+                },
+                Some(record) => {
+                    eprintln!("Error: {}: {}", record.url, record.message);
+                    sink.log_error(&record)?;
+                    summary.failed += 1;
+                },
             }
-            
-            xml.push_str("</document>");
-            xml
+            continue;
         }
+        summary.record_memory(&item.source, item.memory);
+        sink.write_result(item, &config, &mut summary)?;
+    }
+    
+    sink.finish()?;
+    eprintln!("{}", summary);
+    let spider = spider.lock().unwrap_or_else(|e| e.into_inner());
+    for trap in spider.traps() {
+        eprintln!("Blacklisted crawler trap: {}", trap);
+    }
+    export_crawl(&spider, &args)
+}
+
+/// Extract an HTML file, or the HTML files of a directory, on both sides of a diff
+/// and compare the text
+fn diff_samples(samples: &Path, baseline: &DiffSide, changed: &DiffSide) -> Result<DiffReport, TrafilaturaError> {
+    let paths = if samples.is_dir() {
+        let mut paths: Vec<_> = fs::read_dir(samples)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<_, _>>()?;
+        paths.retain(|path| is_html_file(path));
+        paths.sort();
+        paths
+    } else {
+        vec![samples.to_path_buf()]
     };
     
-    // Output result
-    match cli.output {
+    let mut report = DiffReport::default();
+    for path in paths {
+        // Failed extractions count as empty output, as in `eval`
+        let mut errors = Vec::new();
+        let mut extract = |side: &DiffSide| side.extract(&path).unwrap_or_else(|e| {
+            errors.push(e.to_string());
+            String::new()
+        });
+        let diff = diff_text(&extract(baseline), &extract(changed));
+        report.documents.push(DocumentDiff {
+            name: path.file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+            diff,
+            error: (!errors.is_empty()).then(|| errors.join("; ")),
+        });
+    }
+    Ok(report)
+}
+
+/// Write the pages fetched by a crawl as a sitemap or URL list, if requested
+fn export_crawl(spider: &Spider, args: &CrawlArgs) -> Result<(), TrafilaturaError> {
+    if let Some(path) = &args.export_sitemap {
+        let entries: Vec<SitemapEntry> = spider.crawled_pages().iter()
+            .map(|page| SitemapEntry {
+                url: page.url.clone(),
+                lastmod: Some(page.fetched.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            })
+            .collect();
+        write_sitemap_files(path, &entries)?;
+    }
+    
+    if let Some(path) = &args.export_urls {
+        let mut file = io::BufWriter::new(File::create(path)?);
+        for page in spider.crawled_pages() {
+            writeln!(file, "{}", page.url)?;
+        }
+        file.flush()?;
+    }
+    Ok(())
+}
+
+/// List or extract the pages of a sitemap, incrementally with `--since` or `--state`
+fn run_sitemap(args: SitemapArgs) -> Result<(), TrafilaturaError> {
+    let config = args.batch.to_config()?;
+    let fetcher = HttpFetcher::new(&config)?;
+    // Retrying failed pages is not a run over the sitemap, so the state is left as is
+    if let Some(urls) = args.batch.retry_urls()? {
+        return run_batch(&args.url, urls, &args.batch, &config, Arc::new(fetcher)).map(|_| ());
+    }
+    let started = Utc::now();
+    let mut state = args.state.as_deref().map(SitemapState::load).transpose()?;
+    let since = args.since.or_else(|| state.as_ref().and_then(|state| state.last_run(&args.url)));
+    
+    // One page more than the limit tells whether pages were left out
+    let mut urls: Vec<String> = fetch_sitemap_entries_since(&args.url, &fetcher, args.max_urls.saturating_add(1), since)?
+        .into_iter()
+        .map(|entry| entry.url)
+        .collect();
+    let truncated = urls.len() > args.max_urls;
+    urls.truncate(args.max_urls);
+    let finished = run_batch(&args.url, urls, &args.batch, &config, Arc::new(fetcher))?;
+    
+    // Pages left out must still count as modified on the next run
+    if let (Some(state), Some(path)) = (state.as_mut(), args.state.as_deref()) {
+        if finished && !truncated {
+            state.record_run(&args.url, started);
+            state.save(path)?;
+        } else {
+            eprintln!("Not all modified pages were processed, the state file is left as is");
+        }
+    }
+    Ok(())
+}
+
+/// List or extract a batch of URLs found at `source`, returning whether every URL
+/// was processed, i.e. the batch was not stopped by a shutdown signal
+fn run_batch(source: &str, urls: Vec<String>, args: &BatchArgs, config: &ExtractionConfig, fetcher: SharedFetcher) -> Result<bool, TrafilaturaError> {
+    let mut sink = BatchSink::open(args, source)?;
+    
+    if args.list {
+        for url in &urls {
+            writeln!(sink.writer, "{}", url)?;
+        }
+        sink.writer.flush()?;
+        return Ok(true);
+    }
+    
+    let shutdown = ShutdownSignal::install()?;
+    let mut summary = Summary::default();
+    
+    // Dropping the stream on shutdown stops the download and extraction threads
+    for item in BatchStream::urls(urls, config, fetcher, args.batch_options()) {
+        if shutdown.is_requested() {
+            break;
+        }
+        summary.record_memory(&item.source, item.memory);
+        sink.write_result(item, config, &mut summary)?;
+    }
+    
+    sink.finish()?;
+    eprintln!("{}", summary);
+    Ok(!shutdown.is_requested())
+}
+
+/// Destination of batch results: each result is written as soon as it is extracted
+/// or added to a corpus, or collected and written as one feed by `finish`
+struct BatchSink {
+    writer: Box<dyn Write>,
+    feed: Option<(FeedFormat, FeedChannel)>,
+    items: Vec<ExtractionResult>,
+    corpus: Option<CorpusWriter>,
+    /// JSON Lines log of the pages that failed
+    errors: Option<io::BufWriter<File>>,
+    /// Whether the header row of CSV output was written, so later documents add a row only
+    csv_header_written: bool,
+}
+
+impl BatchSink {
+    /// Open the output of a batch, with `source` as the link of an aggregated feed
+    fn open(args: &BatchArgs, source: &str) -> Result<Self, TrafilaturaError> {
+        let feed = args.feed.map(|format| {
+            let title = args.feed_title.clone().unwrap_or_else(|| {
+                Url::parse(source).ok()
+                    .and_then(|url| url.host_str().map(str::to_string))
+                    .unwrap_or_else(|| source.to_string())
+            });
+            let description = args.feed_description.clone()
+                .unwrap_or_else(|| format!("Pages extracted from {}", source));
+            (format.into(), FeedChannel { title, link: source.to_string(), description })
+        });
+        
+        let corpus = match &args.corpus {
+            Some(dir) => {
+                let options = CorpusOptions {
+                    max_shard_bytes: args.shard_size.saturating_mul(1024 * 1024),
+                    compress: args.gzip,
+                    minhash: args.minhash.then(|| MinHashOptions {
+                        permutations: args.minhash_permutations,
+                        shingle_size: args.shingle_size,
+                        ..Default::default()
+                    }),
+                };
+                let writer = CorpusWriter::create(dir, options)?;
+                Some(match args.near_dup_distance {
+                    Some(distance) => writer.with_filter(Box::new(SimHashFilter::new(distance))),
+                    None => writer,
+                })
+            },
+            None => None,
+        };
+        
+        let errors = args.errors.as_deref().map(File::create).transpose()?.map(io::BufWriter::new);
+        Ok(Self { writer: open_sink(args.output.as_deref())?, feed, items: Vec::new(), corpus, errors, csv_header_written: false })
+    }
+    
+    /// Write one result of a batch, reporting failures on stderr and in the error log
+    /// without stopping the batch. In JSON output, failures are written in place of
+    /// the document as `{"error": {...}}` records.
+    fn write_result(&mut self, item: BatchItem, config: &ExtractionConfig, summary: &mut Summary) -> Result<(), TrafilaturaError> {
+        let BatchItem { source, stage, result, .. } = item;
+        let output = match (&mut self.corpus, &self.feed, result) {
+            (Some(corpus), _, Ok(result)) => {
+                corpus.write(&result)?;
+                Ok(None)
+            },
+            // Feed items are formatted together by `finish`
+            (None, Some(_), Ok(result)) => {
+                self.items.push(result);
+                Ok(None)
+            },
+            (_, _, Ok(result)) if config.output_format == OutputFormat::Csv && self.csv_header_written => {
+                Ok(Some(format_csv_row(&result)))
+            },
+            (_, _, result) => result.and_then(|result| format_result(&result, config)).map(Some),
+        };
+        match output {
+            Ok(output) => {
+                if let Some(output) = output {
+                    writeln!(self.writer, "{}", output)?;
+                    self.csv_header_written |= config.output_format == OutputFormat::Csv;
+                }
+                summary.extracted += 1;
+            },
+            Err(e) => {
+                eprintln!("Error: {}: {}", source, e);
+                let record = ErrorRecord::new(&source, stage, &e);
+                self.log_error(&record)?;
+                let is_json = matches!(config.output_format, OutputFormat::Json | OutputFormat::JsonLines);
+                if is_json && self.corpus.is_none() && self.feed.is_none() {
+                    let indent = if config.output_format == OutputFormat::JsonLines { None } else { config.indent };
+                    writeln!(self.writer, "{}", to_json(&serde_json::json!({ "error": record }), indent)?)?;
+                }
+                summary.failed += 1;
+            },
+        }
+        
+        Ok(())
+    }
+    
+    /// Add a failed page to the error log, if any
+    fn log_error(&mut self, record: &ErrorRecord) -> Result<(), TrafilaturaError> {
+        if let Some(errors) = &mut self.errors {
+            writeln!(errors, "{}", serde_json::to_string(record)?)?;
+        }
+        Ok(())
+    }
+    
+    /// Write the aggregated feed or the corpus manifest, if any, and flush the output
+    fn finish(mut self) -> Result<(), TrafilaturaError> {
+        if let Some(corpus) = self.corpus.take() {
+            let manifest = corpus.finish()?;
+            eprintln!("Corpus: {} records in {} shards, {} near-duplicates skipped",
+                manifest.records, manifest.shards.len(), manifest.duplicates);
+        }
+        if let Some((format, channel)) = &self.feed {
+            writeln!(self.writer, "{}", format_feed(&self.items, channel, *format, Utc::now()))?;
+        }
+        if let Some(errors) = &mut self.errors {
+            errors.flush()?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Read the input argument, or stdin when it is missing
+fn read_input(input: Option<String>) -> Result<String, TrafilaturaError> {
+    match input {
+        Some(input) => Ok(input),
+        None => {
+            let mut buffer = String::new();
+            io::stdin().read_to_string(&mut buffer)?;
+            Ok(buffer)
+        }
+    }
+}
+
+/// Open the output file, or stdout when no file is given
+fn open_sink(output: Option<&Path>) -> Result<Box<dyn Write>, TrafilaturaError> {
+    Ok(match output {
+        Some(path) => Box::new(io::BufWriter::new(File::create(path)?)),
+        None => Box::new(io::stdout().lock()),
+    })
+}
+
+/// Write a single output to a file or stdout
+fn write_output(output: Option<&Path>, content: &str) -> Result<(), TrafilaturaError> {
+    match output {
         Some(path) => {
             let mut file = File::create(path)?;
-            file.write_all(output.as_bytes())?;
+            file.write_all(content.as_bytes())?;
         },
         None => {
-            println!("{}", output);
+            println!("{}", content);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Extract HTML files as they appear in `dir`, writing each result to
+/// `<output>/<document>.<ext>` or to stdout, until SIGINT/SIGTERM
+fn watch(
+    dir: &Path,
+    output: Option<&Path>,
+    tables_dir: Option<&Path>,
+    config: &ExtractionConfig,
+) -> Result<(), TrafilaturaError> {
+    if let Some(output) = output {
+        fs::create_dir_all(output)?;
+    }
+    
+    let shutdown = ShutdownSignal::install()?;
+    let mut summary = Summary::default();
+    
+    let watched = watch_directory(dir, &shutdown, |path| {
+        let input = path.to_string_lossy();
+        let document = document_name(&input);
+        
+        // A single bad document must not stop the watcher
+        let result = match extract_file(path, config) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("Error: {}: {}", path.display(), e);
+                summary.failed += 1;
+                return Ok(());
+            }
+        };
+        
+        if let Some(tables_dir) = tables_dir {
+            write_tables(&result.tables, tables_dir, &document)?;
+        }
+        
+        let formatted = format_result(&result, config)?;
+        match output {
+            Some(output) => {
+                let target = output.join(format!("{}.{}", document, output_extension(config.output_format)));
+                debug!("Writing {} to {}", path.display(), target.display());
+                write_atomically(&target, &formatted)?;
+            },
+            None => {
+                let mut stdout = io::stdout().lock();
+                writeln!(stdout, "{}", formatted)?;
+                stdout.flush()?;
+            },
+        }
+        
+        summary.extracted += 1;
+        Ok(())
+    });
+    
+    eprintln!("{}", summary);
+    watched
+}
+
+/// Counts of documents handled by a long-running mode
+#[derive(Debug, Default)]
+struct Summary {
+    extracted: usize,
+    failed: usize,
+    /// Highest peak memory of a document, with its source
+    largest: Option<(usize, String)>,
+}
+
+impl Summary {
+    /// Remember the document with the highest peak memory
+    fn record_memory(&mut self, source: &str, memory: Option<MemoryUsage>) {
+        if let Some(usage) = memory {
+            if self.largest.as_ref().is_none_or(|(peak, _)| usage.peak_bytes > *peak) {
+                self.largest = Some((usage.peak_bytes, source.to_string()));
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Processed {} documents: {} extracted, {} failed",
+            self.extracted + self.failed, self.extracted, self.failed)?;
+        if let Some((peak, source)) = &self.largest {
+            write!(f, "; peak memory {:.1} MB ({})", *peak as f64 / (1 << 20) as f64, source)?;
         }
+        Ok(())
+    }
+}
+
+/// Write a file through a temporary sibling so an interrupted write never leaves a truncated file
+fn write_atomically(path: &Path, contents: &str) -> Result<(), TrafilaturaError> {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".part");
+    let partial = PathBuf::from(partial);
+    
+    fs::write(&partial, contents)?;
+    fs::rename(&partial, path)?;
+    Ok(())
+}
+
+/// File extension for an output format
+fn output_extension(format: OutputFormat) -> &'static str {
+    match format {
+        OutputFormat::Text => "txt",
+        OutputFormat::Html => "html",
+        OutputFormat::Json => "json",
+        OutputFormat::JsonLines => "jsonl",
+        OutputFormat::Csv => "csv",
+        OutputFormat::Xml => "xml",
+        OutputFormat::Markdown => "md",
+        OutputFormat::Bulk => "ndjson",
+    }
+}
+
+/// Derive a file-name friendly document name from the CLI input
+fn document_name(input: &str) -> String {
+    let raw = if is_url(input) {
+        match Url::parse(input) {
+            Ok(url) => format!("{}{}", url.host_str().unwrap_or_default(), url.path()),
+            Err(_) => input.to_string(),
+        }
+    } else if is_file_path(input) {
+        Path::new(input)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    } else {
+        String::new()
+    };
+    
+    let mut name = String::new();
+    for c in raw.chars() {
+        if c.is_ascii_alphanumeric() {
+            name.push(c.to_ascii_lowercase());
+        } else if !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+    
+    let name: String = name.trim_matches('-').chars().take(100).collect();
+    if name.is_empty() {
+        "document".into()
+    } else {
+        name
+    }
+}
+
+/// Write each table as `<document>-table-<index>.csv` into the given directory
+fn write_tables(tables: &[Table], dir: &Path, document: &str) -> Result<(), TrafilaturaError> {
+    if tables.is_empty() {
+        return Ok(());
+    }
+    
+    fs::create_dir_all(dir)?;
+    for (index, table) in tables.iter().enumerate() {
+        let path = dir.join(format!("{}-table-{}.csv", document, index + 1));
+        debug!("Writing table {} to {}", index + 1, path.display());
+        fs::write(path, table.to_csv())?;
     }
     
     Ok(())
@@ -248,10 +1820,297 @@ mod tests {
     use super::*;
     
     #[test]
-    fn test_format_conversion() {
-        assert_eq!(OutputFormat::from(Format::Text), OutputFormat::Text);
-        assert_eq!(OutputFormat::from(Format::Html), OutputFormat::Html);
-        assert_eq!(OutputFormat::from(Format::Json), OutputFormat::Json);
-        assert_eq!(OutputFormat::from(Format::Xml), OutputFormat::Xml);
+    fn test_format_option() {
+        for format in OutputFormat::ALL {
+            let cli = Cli::try_parse_from(["trafilatura", "-f", format.name(), "page.html"]).unwrap();
+            assert_eq!(cli.extract.options.to_config().unwrap().output_format, format);
+        }
+        assert!(Cli::try_parse_from(["trafilatura", "-f", "yaml", "page.html"]).is_err());
+    }
+    
+    #[test]
+    fn test_flat_invocation_is_extract() {
+        let cli = Cli::try_parse_from(["trafilatura", "-f", "json", "page.html"]).unwrap();
+        assert!(cli.command.is_none());
+        assert_eq!(cli.extract.input.as_deref(), Some("page.html"));
+        assert_eq!(cli.extract.options.to_config().unwrap().output_format, OutputFormat::Json);
+        
+        let cli = Cli::try_parse_from(["trafilatura", "extract", "-f", "json", "page.html"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Extract(ref args)) if args.input.as_deref() == Some("page.html")));
+        
+        // Options that make every extraction fail are rejected up front
+        let cli = Cli::try_parse_from(["trafilatura", "--timeout", "0", "page.html"]).unwrap();
+        assert!(matches!(cli.extract.options.to_config(), Err(TrafilaturaError::ConfigError(_))));
+    }
+    
+    #[test]
+    fn test_preset_option() {
+        let cli = Cli::try_parse_from(["trafilatura", "--preset", "forum", "--min-extracted-size", "40", "page.html"]).unwrap();
+        let config = cli.extract.options.to_config().unwrap();
+        assert!(config.include_comments);
+        assert_eq!(config.min_extracted_size, 40);
+        
+        let cli = Cli::try_parse_from(["trafilatura", "--preset", "docs", "page.html"]).unwrap();
+        assert_eq!(cli.extract.options.to_config().unwrap().mode, ExtractionMode::Docs);
+    }
+    
+    #[test]
+    fn test_links_option() {
+        let config = |args: &[&str]| {
+            let cli = Cli::try_parse_from(["trafilatura"].iter().chain(args).chain(&["page.html"])).unwrap();
+            cli.extract.options.to_config().unwrap()
+        };
+        
+        // Links are included by default, as in `ExtractionConfig::default()`
+        assert_eq!(config(&[]).include_links, ExtractionConfig::default().include_links);
+        assert!(!config(&["--no-links"]).include_links);
+        assert!(!config(&["--preset", "news", "--no-links"]).include_links);
+        assert!(config(&["--no-links", "-l"]).include_links);
+        assert!(!config(&["-l", "--no-links"]).include_links);
+        assert!(!config(&["--include-links=false"]).include_links);
+    }
+    
+    #[test]
+    fn test_flags_override_preset() {
+        let config = |args: &[&str]| {
+            let cli = Cli::try_parse_from(["trafilatura"].iter().chain(args).chain(&["page.html"])).unwrap();
+            cli.extract.options.to_config().unwrap()
+        };
+        
+        // The precision preset leaves tables and links out, unless asked for
+        let preset = config(&["--preset", "precision"]);
+        assert!(!preset.include_tables && !preset.include_links);
+        assert!(config(&["--preset", "precision", "-t"]).include_tables);
+        assert!(config(&["--preset", "precision", "-l"]).include_links);
+        assert!(config(&["--preset", "wiki", "-l"]).include_links);
+        
+        // Presets that keep tables and links can drop them
+        assert!(config(&["--preset", "news"]).include_tables);
+        assert!(!config(&["--preset", "news", "--include-tables=false"]).include_tables);
+        assert!(!config(&["--preset", "news", "--include-links=false"]).include_links);
+        assert!(config(&[]).include_tables);
+    }
+    
+    #[test]
+    fn test_tls_options() {
+        let cli = Cli::try_parse_from([
+            "trafilatura", "--ca-bundle", "internal-ca.pem", "--min-tls-version", "1.3", "page.html",
+        ]).unwrap();
+        let tls = cli.extract.options.to_config().unwrap().tls;
+        assert_eq!(tls.ca_bundle.as_deref(), Some(Path::new("internal-ca.pem")));
+        assert_eq!(tls.min_version, Some(TlsVersion::Tls1_3));
+        assert!(!tls.danger_accept_invalid_certs);
+    }
+    
+    #[test]
+    fn test_dns_options() {
+        let cli = Cli::try_parse_from([
+            "trafilatura", "serve", "-4", "--block-private-ips", "--resolve", "archive.internal:10.0.0.5",
+        ]).unwrap();
+        let Some(Command::Serve(args)) = cli.command else { panic!("expected serve") };
+        let dns = args.options.to_config().unwrap().dns;
+        assert_eq!(dns.ip_family, IpFamily::V4);
+        assert!(dns.block_private);
+        assert_eq!(dns.overrides["archive.internal"], vec![IpAddr::from([10, 0, 0, 5])]);
+        
+        assert!(Cli::try_parse_from(["trafilatura", "-4", "-6", "page.html"]).is_err());
+        assert!(Cli::try_parse_from(["trafilatura", "--resolve", "example.com", "page.html"]).is_err());
+    }
+    
+    #[test]
+    fn test_subcommands() {
+        let cli = Cli::try_parse_from(["trafilatura", "crawl", "-v", "--max-pages", "5", "--list", "https://example.com"]).unwrap();
+        assert!(cli.verbose);
+        match cli.command {
+            Some(Command::Crawl(args)) => {
+                assert_eq!(args.max_pages, 5);
+                assert!(args.batch.list);
+            },
+            other => panic!("unexpected command: {:?}", other),
+        }
+        
+        let cli = Cli::try_parse_from(["trafilatura", "sitemap", "https://example.com/sitemap.xml", "-f", "json"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Sitemap(_))));
+        
+        let cli = Cli::try_parse_from(["trafilatura", "sitemap", "--since", "2024-05-01", "https://example.com/sitemap.xml"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Sitemap(ref args)) if args.since == parse_lastmod("2024-05-01")));
+        assert!(Cli::try_parse_from(["trafilatura", "sitemap", "--since", "recently", "https://example.com/sitemap.xml"]).is_err());
+        
+        // Extraction options belong to the subcommand, not before it
+        assert!(Cli::try_parse_from(["trafilatura", "-f", "json", "eval", "dataset"]).is_err());
+    }
+    
+    #[test]
+    fn test_sitemap_state_after_truncated_run() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/sitemap.xml", server.server_addr().to_ip().unwrap());
+        std::thread::spawn(move || {
+            let sitemap = "<urlset><url><loc>/autumn</loc><lastmod>2024-05-02</lastmod></url>\
+                <url><loc>/winter</loc><lastmod>2024-05-03</lastmod></url></urlset>";
+            for request in server.incoming_requests() {
+                let _ = request.respond(tiny_http::Response::from_string(sitemap));
+            }
+        });
+        let dir = tempfile::tempdir().unwrap();
+        let state = dir.path().join("state.json");
+        let run = |max_urls: &str| {
+            let cli = Cli::try_parse_from([
+                "trafilatura", "sitemap", "--list", "--max-urls", max_urls,
+                "--state", state.to_str().unwrap(), "-o", dir.path().join("urls.txt").to_str().unwrap(), &url,
+            ]).unwrap();
+            match cli.command {
+                Some(Command::Sitemap(args)) => run_sitemap(args).unwrap(),
+                other => panic!("unexpected command: {:?}", other),
+            }
+        };
+        
+        // The second timetable was left out, so the next run must still list it
+        run("1");
+        assert!(SitemapState::load(&state).unwrap().last_run(&url).is_none());
+        run("2");
+        assert!(SitemapState::load(&state).unwrap().last_run(&url).is_some());
+    }
+    
+    #[test]
+    fn test_settings_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.cfg");
+        fs::write(&path, "[DEFAULT]\nDOWNLOAD_TIMEOUT = 10\nMIN_EXTRACTED_SIZE = 200\nstrip_elements = mark\n").unwrap();
+        let path = path.to_str().unwrap();
+        
+        let cli = Cli::try_parse_from(["trafilatura", "--config", path, "--strip-elements", "del", "page.html"]).unwrap();
+        let config = cli.extract.options.to_config().unwrap();
+        assert_eq!(config.extraction_timeout, 10);
+        assert_eq!(config.min_extracted_size, 200);
+        assert_eq!(config.strip_elements, ["mark", "del"]);
+        
+        // Command-line options override the file, which overrides presets
+        let cli = Cli::try_parse_from(["trafilatura", "--preset", "wiki", "--config", path, "-s", "5", "page.html"]).unwrap();
+        let config = cli.extract.options.to_config().unwrap();
+        assert_eq!(config.extraction_timeout, 5);
+        assert_eq!(config.min_extracted_size, 200);
+        
+        let cli = Cli::try_parse_from(["trafilatura", "--config", "missing.cfg", "page.html"]).unwrap();
+        assert!(matches!(cli.extract.options.to_config(), Err(TrafilaturaError::IoError(_))));
+        
+        // The deduplication and delay settings reach the cache and the politeness gate,
+        // also next to politeness rules
+        let path = dir.path().join("dedup.cfg");
+        fs::write(&path, "MIN_DUPLCHECK_SIZE = 40
+MAX_REPETITIONS = 5
+SLEEP_TIME = 2
+").unwrap();
+        let politeness = dir.path().join("politeness.json");
+        fs::write(&politeness, r#"{"politeness": {"example.org": {"concurrency": 1}}}"#).unwrap();
+        let cli = Cli::try_parse_from([
+            "trafilatura", "--config", path.to_str().unwrap(), "--politeness", politeness.to_str().unwrap(),
+            "--deduplicate", "--dedup-cache-size", "100", "page.html",
+        ]).unwrap();
+        let config = cli.extract.options.to_config().unwrap();
+        let options = config.dedup.as_ref().unwrap().options();
+        assert_eq!((options.cache_size, options.min_size, options.max_repetitions), (100, 40, 5));
+        for host in ["example.com", "example.org"] {
+            assert_eq!(config.politeness.for_host(host).and_then(|policy| policy.crawl_delay_ms), Some(2000), "{}", host);
+        }
+    }
+    
+    #[test]
+    fn test_diff() {
+        let cli = Cli::try_parse_from(["trafilatura", "diff", "samples", "--a", "--preset news", "--b", "--image-style alt-text"]).unwrap();
+        let Some(Command::Diff(args)) = cli.command else { panic!("expected diff") };
+        assert_eq!(args.a, "--preset news");
+        assert!(matches!(DiffSide::new(&args.a, None), Ok(DiffSide::Config(ref config)) if config.min_extracted_size == 250));
+        assert!(matches!(DiffSide::new("--frobnicate", None), Err(TrafilaturaError::ConfigError(_))));
+        assert!(matches!(DiffSide::new("-f json", Some(Path::new("trafilatura-1.0"))), Ok(DiffSide::Binary(_, ref options)) if options == &["-f", "json"]));
+        
+        let dir = tempfile::tempdir().unwrap();
+        // Only the side rendering images as alt text has the photo of the cake
+        let paragraph = "<p>Whisk the eggs and sugar until pale and thick, then fold in the flour in three batches.</p>";
+        fs::write(dir.path().join("sponge.html"), format!(
+            r#"<html><body><article>{p}<img src="sponge.jpg" alt="The finished sponge">{p}{p}</article></body></html>"#, p = paragraph
+        )).unwrap();
+        fs::write(dir.path().join("notes.txt"), "not a sample").unwrap();
+        
+        let report = diff_samples(dir.path(), &DiffSide::new("", None).unwrap(), &DiffSide::new(&args.b, None).unwrap()).unwrap();
+        assert_eq!(report.documents.len(), 1);
+        assert_eq!(report.documents[0].name, "sponge");
+        assert_eq!(report.documents[0].diff.added, ["[Image: The finished sponge]"]);
+        assert!(report.documents[0].diff.removed.is_empty());
+    }
+    
+    #[test]
+    fn test_document_name() {
+        assert_eq!(document_name("https://example.com/news/2024/story.html"), "example-com-news-2024-story-html");
+        assert_eq!(document_name("<html><body>inline</body></html>"), "document");
+    }
+    
+    #[test]
+    fn test_write_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        let tables = vec![
+            Table { rows: vec![vec!["a".into(), "b".into()]] },
+            Table { rows: vec![vec!["c,d".into()]] },
+        ];
+        
+        write_tables(&tables, dir.path(), "story").unwrap();
+        
+        assert_eq!(fs::read_to_string(dir.path().join("story-table-1.csv")).unwrap(), "a,b\n");
+        assert_eq!(fs::read_to_string(dir.path().join("story-table-2.csv")).unwrap(), "\"c,d\"\n");
+    }
+    
+    #[test]
+    fn test_write_atomically() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("story.txt");
+        
+        write_atomically(&path, "content").unwrap();
+        
+        assert_eq!(fs::read_to_string(&path).unwrap(), "content");
+        assert!(!dir.path().join("story.txt.part").exists());
+    }
+    
+    #[test]
+    fn test_link_style_option() {
+        let config = |args: &[&str]| {
+            let cli = Cli::try_parse_from(["trafilatura"].iter().chain(args).chain(&["page.html"])).unwrap();
+            cli.extract.options.to_config().unwrap()
+        };
+        
+        assert_eq!(config(&[]).link_style, LinkStyle::Parenthetical);
+        assert_eq!(config(&["--link-style", "footnote"]).link_style, LinkStyle::Footnote);
+        let config = config(&["--link-style", "markdown", "--no-links"]);
+        assert_eq!(config.link_style, LinkStyle::Markdown);
+        assert!(!config.include_links);
+        assert!(Cli::try_parse_from(["trafilatura", "--link-style", "html", "page.html"]).is_err());
+    }
+    
+    #[test]
+    fn test_image_style_option() {
+        let config = |args: &[&str]| {
+            let cli = Cli::try_parse_from(["trafilatura"].iter().chain(args).chain(&["page.html"])).unwrap();
+            cli.extract.options.to_config().unwrap()
+        };
+        
+        assert!(!config(&[]).include_images);
+        assert_eq!(config(&["-i"]).image_style, ImageStyle::AltText);
+        // A style implies --include-images
+        let figure = config(&["--image-style", "figure"]);
+        assert!(figure.include_images);
+        assert_eq!(figure.image_style, ImageStyle::Figure);
+        assert!(!config(&["--image-style", "skip"]).renders_images());
+        assert!(Cli::try_parse_from(["trafilatura", "--image-style", "thumbnail", "page.html"]).is_err());
+    }
+    
+    #[test]
+    fn test_scoring_option() {
+        let config = |args: &[&str]| {
+            let cli = Cli::try_parse_from(["trafilatura"].iter().chain(args).chain(&["page.html"])).unwrap();
+            cli.extract.options.to_config().unwrap()
+        };
+        
+        assert_eq!(config(&[]).scoring_mode, ScoringMode::Auto);
+        assert_eq!(config(&["--scoring", "structural"]).scoring_mode, ScoringMode::Structural);
+        assert!(config(&["--scoring", "hints"]).uses_class_hints());
+        assert!(Cli::try_parse_from(["trafilatura", "--scoring", "css", "page.html"]).is_err());
     }
 }
\ No newline at end of file
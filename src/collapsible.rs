@@ -0,0 +1,263 @@
+//! Collapsible content support for Trafilatura Rust port.
+//! FAQs, accordions, and tabbed widgets keep part of an article collapsed:
+//! `<details>` bodies behind a `<summary>`, and panels hidden until their button
+//! or tab is clicked. This module expands such content in place and turns each
+//! label into a heading above its panel, so the panel text is neither dropped as
+//! hidden nor preceded by a detached list of tab labels.
+
+use std::collections::HashMap;
+
+use ego_tree::NodeId;
+use html5ever::tendril::StrTendril;
+use html5ever::{local_name, namespace_url, ns, QualName};
+use lazy_static::lazy_static;
+use regex::Regex;
+use scraper::node::{Element, Text};
+use scraper::{ElementRef, Html, Node};
+
+use crate::html::{descendants_named, has_role};
+
+lazy_static! {
+    /// Inline styles collapsing a panel
+    static ref COLLAPSED_STYLE_RE: Regex = Regex::new(r"(?i)display\s*:\s*none|visibility\s*:\s*hidden").unwrap();
+}
+
+/// Values of `data-toggle` and `data-bs-toggle` on accordion and tab controls
+const TOGGLE_KINDS: [&str; 3] = ["collapse", "tab", "pill"];
+
+/// Headings a label may already be wrapped in
+const HEADINGS: [&str; 6] = ["h1", "h2", "h3", "h4", "h5", "h6"];
+
+/// Elements holding the text of a panel; panels without any get their content wrapped in a
+/// paragraph. Nested `<details>` become blocks too, and wrapping them would nest paragraphs.
+const BLOCKS: [&str; 17] = [
+    "p", "div", "section", "article", "ul", "ol", "dl", "table", "pre", "blockquote",
+    "h1", "h2", "h3", "h4", "h5", "h6", "details",
+];
+
+/// A collapsed panel and the label shown in its place
+struct Panel {
+    id: NodeId,
+    label: Option<String>,
+}
+
+/// Expand `<details>` elements, accordions, and tab panels in place, returning how
+/// many panels were expanded. Each panel loses the attributes and inline styles
+/// hiding it and starts with an `h3` heading made from its summary, button, or tab
+/// label; the original labels and tab lists are detached.
+pub fn expand_collapsed(document: &mut Html) -> usize {
+    let root = document.root_element();
+    let by_id: HashMap<&str, ElementRef> = root.descendants()
+        .filter_map(ElementRef::wrap)
+        .filter_map(|element| element.value().id().map(|id| (id, element)))
+        .collect();
+
+    let mut labels: HashMap<NodeId, (String, NodeId)> = HashMap::new();
+    let mut detached: Vec<NodeId> = Vec::new();
+    let mut panels: Vec<Panel> = Vec::new();
+
+    // Labels are only collected for controls, since collecting the text of every
+    // element is quadratic in the nesting depth
+    for control in root.descendants().filter_map(ElementRef::wrap) {
+        let targets = controlled_ids(control);
+        if targets.is_empty() {
+            continue;
+        }
+        let label = label_text(control);
+        if label.is_empty() {
+            continue;
+        }
+        for target in targets {
+            if let Some(panel) = by_id.get(target) {
+                labels.entry(panel.id()).or_insert_with(|| (label.clone(), label_container(control)));
+            }
+        }
+    }
+
+    for element in root.descendants().filter_map(ElementRef::wrap) {
+        let value = element.value();
+        if has_role(&element, &["tablist"]) {
+            detached.push(element.id());
+            continue;
+        }
+        let is_panel = value.name() == "details" || labels.contains_key(&element.id()) || has_role(&element, &["tabpanel"]);
+        if !is_panel || is_menu(element) {
+            continue;
+        }
+        if value.name() == "details" {
+            let summary = element.children().filter_map(ElementRef::wrap).find(|child| child.value().name() == "summary");
+            let label = summary.map(label_text).filter(|label| !label.is_empty());
+            detached.extend(summary.map(|summary| summary.id()));
+            panels.push(Panel { id: element.id(), label });
+        } else if let Some((label, container)) = labels.get(&element.id()) {
+            if !element.ancestors().any(|ancestor| ancestor.id() == *container) {
+                detached.push(*container);
+            }
+            panels.push(Panel { id: element.id(), label: Some(label.clone()) });
+        } else {
+            let tab = value.attr("aria-labelledby")
+                .and_then(|ids| ids.split_whitespace().find_map(|id| by_id.get(id)))
+                .filter(|tab| !element.ancestors().any(|ancestor| ancestor.id() == tab.id()));
+            detached.extend(tab.map(|tab| label_container(*tab)));
+            let label = tab.map(|tab| label_text(*tab)).filter(|label| !label.is_empty());
+            panels.push(Panel { id: element.id(), label });
+        }
+    }
+
+    for panel in &panels {
+        expand_panel(document, panel);
+    }
+    for id in detached {
+        if let Some(mut node) = document.tree.get_mut(id) {
+            node.detach();
+        }
+    }
+    panels.len()
+}
+
+/// Unhide a panel, wrap bare content in a paragraph, and add its label as a heading
+fn expand_panel(document: &mut Html, panel: &Panel) {
+    let Some(element) = document.tree.get(panel.id).and_then(ElementRef::wrap) else {
+        return;
+    };
+    let has_blocks = element.children()
+        .filter_map(ElementRef::wrap)
+        .any(|child| child.value().name() != "summary" && BLOCKS.contains(&child.value().name()));
+    let bare_content: Vec<NodeId> = if has_blocks {
+        Vec::new()
+    } else {
+        element.children()
+            .filter(|child| ElementRef::wrap(*child).is_none_or(|child| child.value().name() != "summary"))
+            .map(|child| child.id())
+            .collect()
+    };
+    // A panel already starting with its label as a heading keeps that heading
+    let label = panel.label.as_ref().filter(|label| {
+        descendants_named(element, &HEADINGS).next().is_none_or(|heading| label_text(heading) != **label)
+    });
+
+    let Some(mut node) = document.tree.get_mut(panel.id) else {
+        return;
+    };
+    if let Node::Element(element) = node.value() {
+        if element.name() == "details" {
+            element.name = QualName::new(None, ns!(html), local_name!("div"));
+        }
+        element.attrs.retain(|name, value| match &*name.local {
+            "hidden" | "aria-hidden" => false,
+            "style" => !COLLAPSED_STYLE_RE.is_match(value),
+            _ => true,
+        });
+    }
+
+    if bare_content.iter().any(|id| !is_blank(document, *id)) {
+        let mut node = document.tree.get_mut(panel.id).unwrap();
+        let mut paragraph = node.append(new_element(local_name!("p")));
+        for id in bare_content {
+            paragraph.append_id(id);
+        }
+    }
+
+    if let Some(label) = label {
+        let mut node = document.tree.get_mut(panel.id).unwrap();
+        node.prepend(new_element(local_name!("h3")))
+            .append(Node::Text(Text { text: StrTendril::from(label.as_str()) }));
+    }
+}
+
+/// Navigation menus opened by a toggle button stay collapsed
+fn is_menu(panel: ElementRef) -> bool {
+    let value = panel.value();
+    if value.name() == "nav" || has_role(&panel, &["navigation", "menu"]) {
+        return true;
+    }
+    let text = label_text(panel).len();
+    let link_text: usize = descendants_named(panel, &["a"]).map(|link| label_text(link).len()).sum();
+    text > 0 && link_text * 2 >= text
+}
+
+/// IDs of the panels a button or tab opens
+fn controlled_ids<'a>(control: ElementRef<'a>) -> Vec<&'a str> {
+    let value = control.value();
+    let toggles = ["data-toggle", "data-bs-toggle"].iter()
+        .filter_map(|attr| value.attr(attr))
+        .any(|kind| TOGGLE_KINDS.iter().any(|toggle| kind.eq_ignore_ascii_case(toggle)));
+    let is_control = toggles || has_role(&control, &["tab"]) || value.attr("aria-expanded").is_some();
+    if !is_control {
+        return Vec::new();
+    }
+
+    let mut ids: Vec<&str> = value.attr("aria-controls").map(|ids| ids.split_whitespace().collect()).unwrap_or_default();
+    if toggles {
+        ids.extend(
+            ["data-target", "data-bs-target", "href"].iter()
+                .filter_map(|attr| value.attr(attr))
+                .filter_map(|target| target.strip_prefix('#'))
+        );
+    }
+    ids
+}
+
+/// The control, or the heading it is the only content of
+fn label_container(control: ElementRef) -> NodeId {
+    control.parent()
+        .and_then(ElementRef::wrap)
+        .filter(|parent| HEADINGS.contains(&parent.value().name()) && label_text(*parent) == label_text(control))
+        .map_or(control.id(), |heading| heading.id())
+}
+
+fn label_text(element: ElementRef) -> String {
+    element.text().flat_map(str::split_whitespace).collect::<Vec<_>>().join(" ")
+}
+
+fn is_blank(document: &Html, id: NodeId) -> bool {
+    document.tree.get(id).is_some_and(|node| match node.value() {
+        Node::Text(text) => text.trim().is_empty(),
+        Node::Comment(_) => true,
+        _ => false,
+    })
+}
+
+fn new_element(name: html5ever::LocalName) -> Node {
+    Node::Element(Element::new(QualName::new(None, ns!(html), name), Vec::new()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_collapsed() {
+        let html = r##"<html><body><article>
+            <details><summary>Can I bring my bike?</summary>Bikes travel free on all off-peak trains.</details>
+            <h3><button aria-expanded="false" aria-controls="refunds">Refunds</button></h3>
+            <div id="refunds" hidden><p>Unused tickets are refunded within 28 days.</p></div>
+            <ul role="tablist">
+              <li><a role="tab" id="tab-summer" href="#summer">Summer</a></li>
+              <li><a role="tab" id="tab-winter" href="#winter">Winter</a></li>
+            </ul>
+            <div role="tabpanel" aria-labelledby="tab-summer"><p>Trains run every 20 minutes.</p></div>
+            <div role="tabpanel" aria-labelledby="tab-winter" style="display: none"><p>Trains run hourly.</p></div>
+            <button aria-expanded="false" aria-controls="menu">Menu</button>
+            <div id="menu" hidden><a href="/">Home</a> <a href="/times">Timetables</a></div>
+            </article></body></html>"##;
+        let mut document = Html::parse_document(html);
+
+        assert_eq!(expand_collapsed(&mut document), 4);
+
+        let article = descendants_named(document.root_element(), &["article"]).next().unwrap();
+        let headings: Vec<String> = descendants_named(article, &HEADINGS).map(label_text).collect();
+        assert_eq!(headings, vec!["Can I bring my bike?", "Refunds", "Summer", "Winter"]);
+        let paragraphs: Vec<String> = descendants_named(article, &["p"]).map(label_text).collect();
+        assert_eq!(paragraphs[0], "Bikes travel free on all off-peak trains.");
+        assert_eq!(paragraphs.len(), 4);
+
+        // The menu keeps its toggle and stays hidden
+        let menu = descendants_named(article, &["div"]).find(|div| div.value().id() == Some("menu")).unwrap();
+        assert!(menu.value().attr("hidden").is_some());
+        assert!(article.html().contains("Menu</button>"));
+        for attr in ["style", "role=\"tablist\"", "summary", "Refunds</button>"] {
+            assert!(!article.html().contains(attr), "{} should be gone", attr);
+        }
+    }
+}
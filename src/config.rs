@@ -0,0 +1,238 @@
+//! Configuration builder for Trafilatura Rust port.
+//! `ExtractionConfig` gains fields as features are added; code that sets the few
+//! options it needs through `ExtractionConfigBuilder` keeps compiling when that
+//! happens, and `build` checks the options for values that make every extraction
+//! fail or hang.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::dates::DateOptions;
+use crate::dedup::{DedupCache, DedupOptions};
+use crate::dns::DnsOptions;
+use crate::fetch::TlsOptions;
+use crate::metadata::{MetadataBlacklist, MetadataField};
+use crate::politeness::PolitenessRules;
+use crate::proxy::ProxyOptions;
+use crate::site_rules::SiteRuleCache;
+use crate::xpath_expr::XPathExpr;
+use crate::{
+    ExtractionConfig, ExtractionMode, Focus, ImageStyle, LinkStyle, OutputFormat, RubyStyle, ScoringMode,
+    Strategy, TextHeader, TrafilaturaError, XmlMetadata,
+};
+
+/// Largest sensible `ExtractionConfig::min_extracted_size`, in characters. A larger
+/// minimum, such as a byte count taken for characters, rejects every page.
+pub const MAX_MIN_EXTRACTED_SIZE: usize = 100_000;
+
+/// Generate a setter per field, taking the value as it is stored
+macro_rules! setters {
+    ($($field:ident: $ty:ty),* $(,)?) => {
+        $(
+            #[doc = concat!("Set `ExtractionConfig::", stringify!($field), "`")]
+            pub fn $field(mut self, $field: $ty) -> Self {
+                self.config.$field = $field;
+                self
+            }
+        )*
+    };
+}
+
+/// Generate a setter per optional field, taking the value to wrap in `Some`
+macro_rules! optional_setters {
+    ($($field:ident: $ty:ty),* $(,)?) => {
+        $(
+            #[doc = concat!("Set `ExtractionConfig::", stringify!($field), "`")]
+            pub fn $field(mut self, $field: $ty) -> Self {
+                self.config.$field = Some($field);
+                self
+            }
+        )*
+    };
+}
+
+/// Builder of an `ExtractionConfig`, starting from the defaults or from a given
+/// config such as a preset:
+///
+/// ```
+/// use trafilatura::{ExtractionConfig, OutputFormat};
+///
+/// let config = ExtractionConfig::builder()
+///     .output_format(OutputFormat::Markdown)
+///     .min_extracted_size(100)
+///     .build()
+///     .unwrap();
+/// assert_eq!(config.min_extracted_size, 100);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ExtractionConfigBuilder {
+    config: ExtractionConfig,
+}
+
+impl From<ExtractionConfig> for ExtractionConfigBuilder {
+    fn from(config: ExtractionConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl ExtractionConfigBuilder {
+    setters! {
+        mode: ExtractionMode,
+        include_comments: bool,
+        focus: Focus,
+        scoring_mode: ScoringMode,
+        include_tables: bool,
+        separate_tables: bool,
+        include_links: bool,
+        link_style: LinkStyle,
+        ruby_style: RubyStyle,
+        include_images: bool,
+        image_style: ImageStyle,
+        svg_titles: bool,
+        output_format: OutputFormat,
+        front_matter: bool,
+        text_header: TextHeader,
+        python_xml: bool,
+        xml_metadata: XmlMetadata,
+        indent: Option<usize>,
+        extraction_timeout: u64,
+        max_body_size: usize,
+        min_extracted_size: usize,
+        strategies: Vec<Strategy>,
+        extract_metadata: bool,
+        dates: DateOptions,
+        politeness: PolitenessRules,
+        tls: TlsOptions,
+        proxies: ProxyOptions,
+        dns: DnsOptions,
+        wayback_fallback: bool,
+        url_param_blocklist: Vec<String>,
+        harvest_listings: bool,
+        tag_block_languages: bool,
+        dedup_options: DedupOptions,
+        dedup_paragraphs: bool,
+        metadata_blacklist: MetadataBlacklist,
+        filter_boilerplate: bool,
+        strip_elements: Vec<String>,
+        keep_elements: Vec<String>,
+        unwanted_classes: Vec<String>,
+        unwanted_ids: Vec<String>,
+        content_xpaths: Vec<XPathExpr>,
+        og_type_shortcuts: bool,
+        metadata_prefix: bool,
+    }
+
+    optional_setters! {
+        wrap_width: usize,
+        memory_budget: usize,
+        metadata_fields: HashSet<MetadataField>,
+        dedup: Arc<DedupCache>,
+        site_rules: Arc<SiteRuleCache>,
+    }
+
+    /// Set `ExtractionConfig::bulk_index`
+    pub fn bulk_index(mut self, bulk_index: impl Into<String>) -> Self {
+        self.config.bulk_index = bulk_index.into();
+        self
+    }
+
+    /// Set `ExtractionConfig::user_agent`
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.config.user_agent = user_agent.into();
+        self
+    }
+
+    /// Check the options and return the config
+    pub fn build(self) -> Result<ExtractionConfig, TrafilaturaError> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+impl ExtractionConfig {
+    /// Start building a config from the defaults
+    pub fn builder() -> ExtractionConfigBuilder {
+        ExtractionConfigBuilder::default()
+    }
+
+    /// Check the options for values that make every extraction fail or hang: no
+    /// timeout, no strategies, zero widths and budgets, and a minimum content size
+    /// above `MAX_MIN_EXTRACTED_SIZE`
+    pub fn validate(&self) -> Result<(), TrafilaturaError> {
+        let invalid = |message: &str| Err(TrafilaturaError::ConfigError(message.to_string()));
+        if self.extraction_timeout == 0 {
+            return invalid("extraction timeout must be at least 1 second");
+        }
+        if self.strategies.is_empty() {
+            return invalid("at least one extraction strategy is needed");
+        }
+        if self.min_extracted_size > MAX_MIN_EXTRACTED_SIZE {
+            return Err(TrafilaturaError::ConfigError(format!(
+                "minimum extracted size {} exceeds {} characters",
+                self.min_extracted_size, MAX_MIN_EXTRACTED_SIZE
+            )));
+        }
+        if self.wrap_width == Some(0) {
+            return invalid("wrap width must be at least 1 character");
+        }
+        if self.memory_budget == Some(0) {
+            return invalid("memory budget must be at least 1 byte");
+        }
+        if self.max_body_size == 0 {
+            return invalid("maximum body size must be at least 1 byte");
+        }
+        if self.user_agent.trim().is_empty() {
+            return invalid("user agent must not be empty");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Preset;
+
+    #[test]
+    fn test_builder() {
+        let config = ExtractionConfig::builder()
+            .include_links(false)
+            .link_style(LinkStyle::Markdown)
+            .wrap_width(72)
+            .strategies(vec![Strategy::Readability])
+            .user_agent("harbour-bot/1.0")
+            .build()
+            .unwrap();
+        assert!(!config.include_links);
+        assert_eq!(config.link_style, LinkStyle::Markdown);
+        assert_eq!(config.wrap_width, Some(72));
+        assert_eq!(config.strategies, [Strategy::Readability]);
+        assert_eq!(config.user_agent, "harbour-bot/1.0");
+        // Options left alone keep their defaults
+        assert_eq!(config.min_extracted_size, ExtractionConfig::default().min_extracted_size);
+
+        let config = ExtractionConfigBuilder::from(ExtractionConfig::preset(Preset::Wiki))
+            .extract_metadata(true)
+            .build()
+            .unwrap();
+        assert!(config.extract_metadata);
+        assert_eq!(config.min_extracted_size, 500);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(ExtractionConfig::default().validate().is_ok());
+
+        let invalid = [
+            ExtractionConfig::builder().extraction_timeout(0),
+            ExtractionConfig::builder().strategies(Vec::new()),
+            ExtractionConfig::builder().min_extracted_size(MAX_MIN_EXTRACTED_SIZE + 1),
+            ExtractionConfig::builder().wrap_width(0),
+            ExtractionConfig::builder().memory_budget(0),
+            ExtractionConfig::builder().user_agent(" "),
+        ];
+        for builder in invalid {
+            assert!(matches!(builder.build(), Err(TrafilaturaError::ConfigError(_))));
+        }
+    }
+}
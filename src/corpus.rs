@@ -0,0 +1,446 @@
+//! Corpus export for Trafilatura Rust port.
+//! Language model data pipelines ingest web text as sharded JSON Lines files with
+//! a fixed schema. This module writes extraction results to size-capped shards,
+//! optionally gzip-compressed, describes them in a manifest, and lets a filter
+//! drop near-duplicate documents on the way. Records can carry MinHash signatures
+//! for corpus-level deduplication downstream.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{minhash, simhash};
+use crate::{ExtractionResult, TrafilaturaError};
+
+/// Fields of every record, in order
+pub const CORPUS_SCHEMA: [&str; 5] = ["text", "url", "timestamp", "language", "fingerprint"];
+
+/// Field added to the records when MinHash signatures are computed
+pub const MINHASH_FIELD: &str = "minhash";
+
+/// Name of the manifest written next to the shards
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// One document of the corpus
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CorpusRecord {
+    /// Extracted main text
+    pub text: String,
+    pub url: Option<String>,
+    /// Time of the extraction, RFC 3339 in UTC
+    pub timestamp: String,
+    /// Language declared by the document
+    pub language: Option<String>,
+    /// 64-bit SimHash of the text as 16 hex digits
+    pub fingerprint: String,
+    /// MinHash signature of the text, when enabled in `CorpusOptions`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minhash: Option<Vec<u32>>,
+}
+
+impl CorpusRecord {
+    /// Build the record of an extraction result, with a MinHash signature if settings are given
+    pub fn from_result(result: &ExtractionResult, timestamp: DateTime<Utc>, minhash_options: Option<&MinHashOptions>) -> Self {
+        Self {
+            text: result.content.clone(),
+            url: result.url.clone().or_else(|| result.canonical_url.clone()),
+            timestamp: timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+            language: result.language.clone(),
+            fingerprint: format!("{:016x}", simhash(&result.content)),
+            minhash: minhash_options.map(|options| {
+                minhash(&result.content, options.permutations, options.shingle_size, options.seed)
+            }),
+        }
+    }
+}
+
+/// Settings of MinHash signatures; signatures are only comparable when computed
+/// with the same settings, so they are recorded in the manifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MinHashOptions {
+    /// Number of hash functions, i.e. the length of the signature
+    pub permutations: usize,
+    /// Words per shingle
+    pub shingle_size: usize,
+    /// Seed the hash function parameters are derived from
+    pub seed: u64,
+}
+
+impl Default for MinHashOptions {
+    fn default() -> Self {
+        Self {
+            permutations: 128,
+            shingle_size: 5,
+            seed: 1,
+        }
+    }
+}
+
+/// Hook deciding which records are left out of the corpus as near-duplicates
+pub trait DuplicateFilter: Send {
+    /// Check a record against the records kept so far: returns `true` to drop it
+    fn is_duplicate(&mut self, record: &CorpusRecord) -> bool;
+}
+
+/// Drops records whose fingerprint differs in at most `max_distance` bits from the
+/// fingerprint of a kept record.
+///
+/// Fingerprints are split into `max_distance + 1` blocks: two fingerprints within the
+/// distance share at least one block, so only records sharing a block are compared.
+#[derive(Debug, Clone, Default)]
+pub struct SimHashFilter {
+    max_distance: u32,
+    blocks: HashMap<(u32, u64), Vec<u64>>,
+}
+
+impl SimHashFilter {
+    pub fn new(max_distance: u32) -> Self {
+        Self { max_distance: max_distance.min(63), blocks: HashMap::new() }
+    }
+
+    /// Block index and value pairs of a fingerprint
+    fn block_keys(&self, fingerprint: u64) -> impl Iterator<Item = (u32, u64)> {
+        let count = self.max_distance + 1;
+        let width = 64_u32.div_ceil(count);
+        (0..count).map(move |block| {
+            let shifted = fingerprint.checked_shr(block * width).unwrap_or(0);
+            (block, shifted & u64::MAX.checked_shr(64 - width).unwrap_or(u64::MAX))
+        })
+    }
+}
+
+impl DuplicateFilter for SimHashFilter {
+    fn is_duplicate(&mut self, record: &CorpusRecord) -> bool {
+        let fingerprint = match u64::from_str_radix(&record.fingerprint, 16) {
+            Ok(fingerprint) => fingerprint,
+            Err(_) => return false,
+        };
+
+        let keys: Vec<(u32, u64)> = self.block_keys(fingerprint).collect();
+        let is_duplicate = keys.iter()
+            .filter_map(|key| self.blocks.get(key))
+            .flatten()
+            .any(|other| (other ^ fingerprint).count_ones() <= self.max_distance);
+        if !is_duplicate {
+            for key in keys {
+                self.blocks.entry(key).or_default().push(fingerprint);
+            }
+        }
+        is_duplicate
+    }
+}
+
+/// Settings of a corpus export
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorpusOptions {
+    /// Uncompressed size after which a new shard is started, in bytes
+    pub max_shard_bytes: u64,
+    /// Write `.jsonl.gz` shards instead of `.jsonl`
+    pub compress: bool,
+    /// Add MinHash signatures to the records
+    pub minhash: Option<MinHashOptions>,
+}
+
+impl Default for CorpusOptions {
+    fn default() -> Self {
+        Self {
+            max_shard_bytes: 100 * 1024 * 1024,
+            compress: false,
+            minhash: None,
+        }
+    }
+}
+
+/// A finished shard, as listed in the manifest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShardInfo {
+    /// File name, relative to the corpus directory
+    pub file: String,
+    pub records: usize,
+    /// Size of the file on disk
+    pub bytes: u64,
+}
+
+/// Description of a corpus directory, written to `manifest.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub schema: Vec<String>,
+    /// `gzip`, or `None` for plain JSON Lines
+    pub compression: Option<String>,
+    /// Settings the MinHash signatures were computed with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minhash: Option<MinHashOptions>,
+    pub records: usize,
+    /// Records dropped by the duplicate filter
+    pub duplicates: usize,
+    pub shards: Vec<ShardInfo>,
+}
+
+enum ShardFile {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+struct OpenShard {
+    file: ShardFile,
+    name: String,
+    records: usize,
+    /// Bytes written before compression
+    bytes: u64,
+}
+
+impl OpenShard {
+    fn write_line(&mut self, line: &[u8]) -> std::io::Result<()> {
+        match &mut self.file {
+            ShardFile::Plain(writer) => writer.write_all(line)?,
+            ShardFile::Gzip(writer) => writer.write_all(line)?,
+        }
+        self.records += 1;
+        self.bytes += line.len() as u64;
+        Ok(())
+    }
+
+    fn finish(self) -> std::io::Result<(String, usize)> {
+        match self.file {
+            ShardFile::Plain(mut writer) => writer.flush()?,
+            ShardFile::Gzip(writer) => writer.finish()?.flush()?,
+        }
+        Ok((self.name, self.records))
+    }
+}
+
+/// Writes records to numbered shards (`part-00000.jsonl`, ...) in a directory.
+/// Shards and the manifest of an earlier export to the same directory are removed
+/// on creation, so they cannot get mixed into the new corpus; other files are kept.
+pub struct CorpusWriter {
+    dir: PathBuf,
+    options: CorpusOptions,
+    filter: Option<Box<dyn DuplicateFilter>>,
+    current: Option<OpenShard>,
+    shards: Vec<ShardInfo>,
+    records: usize,
+    duplicates: usize,
+}
+
+impl CorpusWriter {
+    /// Create the corpus directory if needed, remove the files of an earlier export,
+    /// and start writing to it
+    pub fn create(dir: &Path, options: CorpusOptions) -> Result<Self, TrafilaturaError> {
+        fs::create_dir_all(dir)?;
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let is_stale = name.to_str().is_some_and(|name| is_shard_name(name) || name == MANIFEST_FILE);
+            if is_stale && entry.file_type()?.is_file() {
+                fs::remove_file(entry.path())?;
+            }
+        }
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            options,
+            filter: None,
+            current: None,
+            shards: Vec::new(),
+            records: 0,
+            duplicates: 0,
+        })
+    }
+
+    /// Leave out the records the filter reports as duplicates
+    pub fn with_filter(mut self, filter: Box<dyn DuplicateFilter>) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Add the record of an extraction result: returns `false` if the duplicate
+    /// filter dropped it
+    pub fn write(&mut self, result: &ExtractionResult) -> Result<bool, TrafilaturaError> {
+        self.write_record(&CorpusRecord::from_result(result, Utc::now(), self.options.minhash.as_ref()))
+    }
+
+    /// Add a record: returns `false` if the duplicate filter dropped it
+    pub fn write_record(&mut self, record: &CorpusRecord) -> Result<bool, TrafilaturaError> {
+        if self.filter.as_mut().is_some_and(|filter| filter.is_duplicate(record)) {
+            self.duplicates += 1;
+            return Ok(false);
+        }
+
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+
+        let is_full = self.current.as_ref()
+            .is_some_and(|shard| shard.bytes + line.len() as u64 > self.options.max_shard_bytes);
+        if is_full {
+            self.close_shard()?;
+        }
+        if self.current.is_none() {
+            self.current = Some(self.open_shard()?);
+        }
+        if let Some(shard) = self.current.as_mut() {
+            shard.write_line(&line)?;
+        }
+
+        self.records += 1;
+        Ok(true)
+    }
+
+    /// Close the last shard and write the manifest
+    pub fn finish(mut self) -> Result<Manifest, TrafilaturaError> {
+        self.close_shard()?;
+
+        let mut schema: Vec<String> = CORPUS_SCHEMA.iter().map(|field| field.to_string()).collect();
+        if self.options.minhash.is_some() {
+            schema.push(MINHASH_FIELD.to_string());
+        }
+        let manifest = Manifest {
+            schema,
+            compression: self.options.compress.then(|| "gzip".to_string()),
+            minhash: self.options.minhash,
+            records: self.records,
+            duplicates: self.duplicates,
+            shards: self.shards,
+        };
+        fs::write(self.dir.join(MANIFEST_FILE), serde_json::to_string_pretty(&manifest)?)?;
+        Ok(manifest)
+    }
+
+    fn open_shard(&self) -> Result<OpenShard, TrafilaturaError> {
+        let extension = if self.options.compress { "jsonl.gz" } else { "jsonl" };
+        let name = format!("part-{:05}.{}", self.shards.len(), extension);
+        let writer = BufWriter::new(File::create(self.dir.join(&name))?);
+        let file = if self.options.compress {
+            ShardFile::Gzip(GzEncoder::new(writer, Compression::default()))
+        } else {
+            ShardFile::Plain(writer)
+        };
+        Ok(OpenShard { file, name, records: 0, bytes: 0 })
+    }
+
+    fn close_shard(&mut self) -> Result<(), TrafilaturaError> {
+        if let Some(shard) = self.current.take() {
+            let (file, records) = shard.finish()?;
+            let bytes = fs::metadata(self.dir.join(&file))?.len();
+            self.shards.push(ShardInfo { file, records, bytes });
+        }
+        Ok(())
+    }
+}
+
+/// Check if a file name is one of the shard names `CorpusWriter` writes
+fn is_shard_name(name: &str) -> bool {
+    let Some(rest) = name.strip_prefix("part-") else { return false };
+    let number = rest.strip_suffix(".jsonl.gz").or_else(|| rest.strip_suffix(".jsonl"));
+    number.is_some_and(|number| !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    use super::*;
+
+    fn result(url: &str, content: &str) -> ExtractionResult {
+        ExtractionResult {
+            url: Some(url.into()),
+            content: content.into(),
+            language: Some("en".into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_shards_and_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = CorpusOptions { max_shard_bytes: 300, compress: true, ..Default::default() };
+        let mut writer = CorpusWriter::create(dir.path(), options).unwrap()
+            .with_filter(Box::new(SimHashFilter::new(3)));
+
+        let story = "The city council approved the new budget for public libraries on Tuesday evening after a long debate";
+        assert!(writer.write(&result("https://example.com/a", story)).unwrap());
+        // The same story under another URL is dropped
+        assert!(!writer.write(&result("https://example.com/a?ref=home", story)).unwrap());
+        for (index, text) in ["Rain is expected across the region for the rest of the week", "The museum opens a new wing dedicated to modern photography"].iter().enumerate() {
+            assert!(writer.write(&result(&format!("https://example.com/{}", index), text)).unwrap());
+        }
+
+        let manifest = writer.finish().unwrap();
+        assert_eq!(manifest.records, 3);
+        assert_eq!(manifest.duplicates, 1);
+        assert_eq!(manifest.shards.len(), 3);
+        assert_eq!(manifest.shards[0].file, "part-00000.jsonl.gz");
+        assert_eq!(manifest.shards.iter().map(|shard| shard.records).sum::<usize>(), 3);
+
+        let mut jsonl = String::new();
+        GzDecoder::new(File::open(dir.path().join("part-00000.jsonl.gz")).unwrap()).read_to_string(&mut jsonl).unwrap();
+        let record: CorpusRecord = serde_json::from_str(jsonl.lines().next().unwrap()).unwrap();
+        assert_eq!(record.text, story);
+        assert_eq!(record.language.as_deref(), Some("en"));
+        assert_eq!(record.fingerprint.len(), 16);
+
+        let stored: Manifest = serde_json::from_str(&fs::read_to_string(dir.path().join(MANIFEST_FILE)).unwrap()).unwrap();
+        assert_eq!(stored, manifest);
+    }
+
+    #[test]
+    fn test_stale_shards_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["part-00000.jsonl", "part-00001.jsonl", "part-00002.jsonl.gz", MANIFEST_FILE, "README.txt"] {
+            fs::write(dir.path().join(name), "stale\n").unwrap();
+        }
+
+        let mut writer = CorpusWriter::create(dir.path(), CorpusOptions::default()).unwrap();
+        writer.write(&result("https://example.com/a", "Rain is expected across the region for the rest of the week")).unwrap();
+        let manifest = writer.finish().unwrap();
+
+        let mut names: Vec<String> = fs::read_dir(dir.path()).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["README.txt", MANIFEST_FILE, "part-00000.jsonl"]);
+        assert_eq!(manifest.shards.len(), 1);
+        assert!(!fs::read_to_string(dir.path().join("part-00000.jsonl")).unwrap().contains("stale"));
+        assert!(is_shard_name("part-00012.jsonl.gz"));
+        assert!(!is_shard_name("part-notes.jsonl"));
+    }
+
+    #[test]
+    fn test_minhash_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let minhash = MinHashOptions { permutations: 16, shingle_size: 3, seed: 7 };
+        let mut writer = CorpusWriter::create(dir.path(), CorpusOptions { minhash: Some(minhash), ..Default::default() }).unwrap();
+
+        writer.write(&result("https://example.com/a", "Rain is expected across the region for the rest of the week")).unwrap();
+
+        let manifest = writer.finish().unwrap();
+        assert_eq!(manifest.schema.last().map(String::as_str), Some(MINHASH_FIELD));
+        assert_eq!(manifest.minhash, Some(minhash));
+        let jsonl = fs::read_to_string(dir.path().join("part-00000.jsonl")).unwrap();
+        let record: CorpusRecord = serde_json::from_str(jsonl.trim()).unwrap();
+        assert_eq!(record.minhash.map(|signature| signature.len()), Some(16));
+    }
+
+    #[test]
+    fn test_simhash_filter_distance() {
+        let record = |fingerprint: u64| CorpusRecord {
+            text: String::new(),
+            url: None,
+            timestamp: String::new(),
+            language: None,
+            fingerprint: format!("{:016x}", fingerprint),
+            minhash: None,
+        };
+        let mut filter = SimHashFilter::new(3);
+
+        assert!(!filter.is_duplicate(&record(0xf0f0_0000_0000_0000)));
+        assert!(filter.is_duplicate(&record(0xf0f0_0000_0000_0007)));
+        assert!(!filter.is_duplicate(&record(0xf0f0_0000_0000_000f)));
+    }
+}
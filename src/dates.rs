@@ -0,0 +1,298 @@
+//! Date parsing for Trafilatura Rust port.
+//! Dates written in the text of a page are turned into ISO dates (`YYYY-MM-DD`).
+//! The formats of `DATE_FORMATS` are tried first; numeric dates such as
+//! `03/04/2021` are then read in the day/month order of the locale, and dates
+//! with month names in the language of one of the locale packs (English, German,
+//! French, Spanish, Italian, Portuguese, Dutch, Russian). The locale is the
+//! language of the page unless `DateOptions::locale` sets one. Dates outside the
+//! window of `DateOptions::min_date` and `max_date` are skipped, like the
+//! `min_date`/`max_date` options of htmldate.
+
+use chrono::{Datelike, Local, NaiveDate};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// Formats (chrono syntax) tried at each word of a date text before the locale
+/// rules; they are unambiguous, with the year first
+pub const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%Y/%m/%d", "%Y.%m.%d"];
+
+lazy_static! {
+    /// Numeric dates with the year last: day and month in the order of the locale
+    static ref NUMERIC_DATE_RE: Regex = Regex::new(r"\b(\d{1,2})[./-](\d{1,2})[./-](\d{4}|\d{2})\b").unwrap();
+
+    /// Dates with a month name after the day: `3 May 2021`, `3. März 2021`, `3 de abril de 2021`
+    static ref DAY_MONTH_RE: Regex = Regex::new(
+        r"(?i)\b(\d{1,2})(?:st|nd|rd|th|er|º|\.)?\s+(?:de\s+)?(\p{L}+)\.?,?\s+(?:de\s+)?(\d{4})\b"
+    ).unwrap();
+
+    /// Dates with a month name before the day: `May 3, 2021`
+    static ref MONTH_DAY_RE: Regex = Regex::new(r"(?i)\b(\p{L}+)\.?\s+(\d{1,2})(?:st|nd|rd|th)?,?\s+(\d{4})\b").unwrap();
+}
+
+/// Month names of a language, with their spelling variants and inflected forms;
+/// abbreviations are matched as prefixes of at least 3 letters
+struct LocalePack {
+    language: &'static str,
+    months: [&'static [&'static str]; 12],
+}
+
+const LOCALE_PACKS: &[LocalePack] = &[
+    LocalePack {
+        language: "en",
+        months: [
+            &["january"], &["february"], &["march"], &["april"], &["may"], &["june"],
+            &["july"], &["august"], &["september"], &["october"], &["november"], &["december"],
+        ],
+    },
+    LocalePack {
+        language: "de",
+        months: [
+            &["januar", "jänner"], &["februar"], &["märz", "maerz"], &["april"], &["mai"], &["juni"],
+            &["juli"], &["august"], &["september"], &["oktober"], &["november"], &["dezember"],
+        ],
+    },
+    LocalePack {
+        language: "fr",
+        months: [
+            &["janvier"], &["février", "fevrier"], &["mars"], &["avril"], &["mai"], &["juin"],
+            &["juillet"], &["août", "aout"], &["septembre"], &["octobre"], &["novembre"], &["décembre", "decembre"],
+        ],
+    },
+    LocalePack {
+        language: "es",
+        months: [
+            &["enero"], &["febrero"], &["marzo"], &["abril"], &["mayo"], &["junio"],
+            &["julio"], &["agosto"], &["septiembre", "setiembre"], &["octubre"], &["noviembre"], &["diciembre"],
+        ],
+    },
+    LocalePack {
+        language: "it",
+        months: [
+            &["gennaio"], &["febbraio"], &["marzo"], &["aprile"], &["maggio"], &["giugno"],
+            &["luglio"], &["agosto"], &["settembre"], &["ottobre"], &["novembre"], &["dicembre"],
+        ],
+    },
+    LocalePack {
+        language: "pt",
+        months: [
+            &["janeiro"], &["fevereiro"], &["março", "marco"], &["abril"], &["maio"], &["junho"],
+            &["julho"], &["agosto"], &["setembro"], &["outubro"], &["novembro"], &["dezembro"],
+        ],
+    },
+    LocalePack {
+        language: "nl",
+        months: [
+            &["januari"], &["februari"], &["maart", "mrt"], &["april"], &["mei"], &["juni"],
+            &["juli"], &["augustus"], &["september"], &["oktober"], &["november"], &["december"],
+        ],
+    },
+    LocalePack {
+        language: "ru",
+        months: [
+            &["января", "январь"], &["февраля", "февраль"], &["марта", "март"], &["апреля", "апрель"],
+            &["мая", "май"], &["июня", "июнь"], &["июля", "июль"], &["августа", "август"],
+            &["сентября", "сентябрь"], &["октября", "октябрь"], &["ноября", "ноябрь"], &["декабря", "декабрь"],
+        ],
+    },
+];
+
+/// English-speaking regions writing the day before the month
+const DAY_FIRST_ENGLISH_REGIONS: &[&str] = &["gb", "uk", "ie", "au", "nz", "in", "za", "sg"];
+
+/// Settings of date parsing
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateOptions {
+    /// Formats (chrono syntax) tried before the locale rules, e.g. `%d.%m.%y` for a
+    /// site with two-digit years
+    pub formats: Vec<String>,
+    /// Language tag whose day/month order and month names apply, e.g. `de` or
+    /// `en-GB`, instead of the language of the page
+    pub locale: Option<String>,
+    /// Earliest date accepted; earlier ones are placeholders such as 1970-01-01
+    /// (1995-01-01 by default, as in htmldate)
+    pub min_date: NaiveDate,
+    /// Latest date accepted, or the current day for `None`; later ones are mostly
+    /// the dates of announced events
+    pub max_date: Option<NaiveDate>,
+}
+
+impl Default for DateOptions {
+    fn default() -> Self {
+        Self {
+            formats: DATE_FORMATS.iter().map(|format| format.to_string()).collect(),
+            locale: None,
+            min_date: NaiveDate::from_ymd_opt(1995, 1, 1).unwrap(),
+            max_date: None,
+        }
+    }
+}
+
+impl DateOptions {
+    /// Parse the first date in a text within `min_date` and `max_date`; `language`
+    /// is the language of the page, used when no locale is set
+    pub fn parse(&self, text: &str, language: Option<&str>) -> Option<NaiveDate> {
+        self.parse_all(text, language).into_iter().find(|&date| self.in_window(date))
+    }
+
+    /// Parse the dates of a text, whether they are within the window or not: the
+    /// matches of the formats, then the numeric dates, then the dates with month
+    /// names. Dates without a known language are read month first.
+    pub fn parse_all(&self, text: &str, language: Option<&str>) -> Vec<NaiveDate> {
+        let locale = self.locale.as_deref().or(language).unwrap_or("en").to_lowercase();
+        let (language, region) = locale.split_once(['-', '_']).unwrap_or((&locale, ""));
+        let day_first = language != "en" || DAY_FIRST_ENGLISH_REGIONS.contains(&region);
+
+        let mut dates = self.parse_formats(text);
+        dates.extend(parse_numeric(text, day_first));
+        dates.extend(parse_month_names(text, language));
+        dates
+    }
+
+    /// Whether a date is within `min_date` and `max_date`
+    pub fn in_window(&self, date: NaiveDate) -> bool {
+        let max_date = self.max_date.unwrap_or_else(|| Local::now().date_naive());
+        (self.min_date..=max_date).contains(&date)
+    }
+
+    /// Try the formats at the start of each word, the date not running into more digits
+    fn parse_formats(&self, text: &str) -> Vec<NaiveDate> {
+        let mut dates = Vec::new();
+        let mut previous = None;
+        for (start, c) in text.char_indices() {
+            let word_start = c.is_alphanumeric() && !previous.is_some_and(char::is_alphanumeric);
+            previous = Some(c);
+            if !word_start {
+                continue;
+            }
+            let date = self.formats.iter().find_map(|format| {
+                let (date, rest) = NaiveDate::parse_and_remainder(&text[start..], format).ok()?;
+                (plausible(date) && !rest.starts_with(|c: char| c.is_ascii_digit())).then_some(date)
+            });
+            dates.extend(date);
+        }
+        dates
+    }
+}
+
+/// Read `dd/mm/yyyy` or `mm/dd/yyyy`, taking the other order when only it is valid
+fn parse_numeric(text: &str, day_first: bool) -> impl Iterator<Item = NaiveDate> + '_ {
+    NUMERIC_DATE_RE.captures_iter(text).filter_map(move |captures| {
+        let first: u32 = captures[1].parse().ok()?;
+        let second: u32 = captures[2].parse().ok()?;
+        let year = full_year(&captures[3])?;
+        let (day, month) = if day_first { (first, second) } else { (second, first) };
+        NaiveDate::from_ymd_opt(year, month, day)
+            .or_else(|| NaiveDate::from_ymd_opt(year, day, month))
+            .filter(|&date| plausible(date))
+    })
+}
+
+fn parse_month_names<'a>(text: &'a str, language: &'a str) -> impl Iterator<Item = NaiveDate> + 'a {
+    let day_month = DAY_MONTH_RE.captures_iter(text).filter_map(move |captures| {
+        let month = month_number(&captures[2], language)?;
+        date(&captures[3], month, &captures[1])
+    });
+    let month_day = MONTH_DAY_RE.captures_iter(text).filter_map(move |captures| {
+        let month = month_number(&captures[1], language)?;
+        date(&captures[3], month, &captures[2])
+    });
+    day_month.chain(month_day)
+}
+
+fn date(year: &str, month: u32, day: &str) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(year.parse().ok()?, month, day.parse().ok()?).filter(|&date| plausible(date))
+}
+
+/// Number of a month name or abbreviation, looked up in the pack of the language
+/// first; an abbreviation matching several months of a pack is skipped
+fn month_number(word: &str, language: &str) -> Option<u32> {
+    let word = word.to_lowercase();
+    let packs = LOCALE_PACKS.iter().filter(|pack| pack.language == language)
+        .chain(LOCALE_PACKS.iter().filter(|pack| pack.language != language));
+    for pack in packs {
+        let mut matches = pack.months.iter().enumerate().filter(|(_, names)| {
+            names.iter().any(|name| *name == word || (word.chars().count() >= 3 && name.starts_with(word.as_str())))
+        });
+        if let (Some((index, _)), None) = (matches.next(), matches.next()) {
+            return Some(index as u32 + 1);
+        }
+    }
+    None
+}
+
+/// Two-digit years are taken as 1970 to 2069
+fn full_year(year: &str) -> Option<i32> {
+    let value: i32 = year.parse().ok()?;
+    Some(match year.len() {
+        2 if value < 70 => 2000 + value,
+        2 => 1900 + value,
+        _ => value,
+    })
+}
+
+/// Dates with four-digit years, as opposed to numbers read as a date by a format;
+/// whether they are meant is for the window of the options
+fn plausible(date: NaiveDate) -> bool {
+    (1000..=9999).contains(&date.year())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(options: &DateOptions, text: &str, language: Option<&str>) -> Option<String> {
+        options.parse(text, language).map(|date| date.to_string())
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let options = DateOptions::default();
+        let iso = |text: &str, language: Option<&str>| parse(&options, text, language);
+
+        // The order of ambiguous numeric dates follows the language of the page
+        assert_eq!(iso("Published 03/04/2021", None).as_deref(), Some("2021-03-04"));
+        assert_eq!(iso("Published 03/04/2021", Some("en-US")).as_deref(), Some("2021-03-04"));
+        assert_eq!(iso("Published 03/04/2021", Some("en-GB")).as_deref(), Some("2021-04-03"));
+        assert_eq!(iso("Veröffentlicht am 03.04.2021", Some("de")).as_deref(), Some("2021-04-03"));
+        assert_eq!(iso("13/04/2021", None).as_deref(), Some("2021-04-13"));
+        assert_eq!(iso("04.13.21", Some("fr")).as_deref(), Some("2021-04-13"));
+
+        assert_eq!(iso("Updated 2021-04-03T10:00:00Z", Some("fr")).as_deref(), Some("2021-04-03"));
+        assert_eq!(iso("3. März 2021", Some("de")).as_deref(), Some("2021-03-03"));
+        assert_eq!(iso("le 1er févr. 2021", None).as_deref(), Some("2021-02-01"));
+        assert_eq!(iso("3 de abril de 2021", Some("es")).as_deref(), Some("2021-04-03"));
+        assert_eq!(iso("15 августа 2021", Some("ru")).as_deref(), Some("2021-08-15"));
+        assert_eq!(iso("Monday, Sept. 6th, 2021", None).as_deref(), Some("2021-09-06"));
+        assert_eq!(iso("12 mrt 2021", Some("nl")).as_deref(), Some("2021-03-12"));
+
+        assert_eq!(iso("Version 3.4.2", None), None);
+        assert_eq!(iso("Chapter 12 of 2021", None), None);
+        assert_eq!(iso("Jun 31, 2021", None), None);
+
+        // A configured locale overrides the page, and configured formats come first
+        let options = DateOptions { locale: Some("de-AT".into()), ..Default::default() };
+        assert_eq!(parse(&options, "03/04/2021", Some("en-US")).as_deref(), Some("2021-04-03"));
+        let mut options = DateOptions::default();
+        options.formats.insert(0, "%y%m%d".into());
+        assert_eq!(parse(&options, "Issue 210403", None).as_deref(), Some("2021-04-03"));
+    }
+
+    #[test]
+    fn test_date_window() {
+        let options = DateOptions::default();
+        let tomorrow = Local::now().date_naive().succ_opt().unwrap().format("%Y-%m-%d").to_string();
+
+        // Placeholders and future dates are skipped for the next date of the text
+        assert_eq!(parse(&options, "01/01/1970", None), None);
+        assert_eq!(options.parse_all("01/01/1970", None).len(), 1);
+        assert_eq!(parse(&options, &format!("Event on {}, posted 2021-04-03", tomorrow), None).as_deref(), Some("2021-04-03"));
+
+        let options = DateOptions {
+            min_date: NaiveDate::from_ymd_opt(1960, 1, 1).unwrap(),
+            max_date: NaiveDate::from_ymd_opt(2020, 12, 31),
+            ..Default::default()
+        };
+        assert_eq!(parse(&options, "01/01/1970", None).as_deref(), Some("1970-01-01"));
+        assert_eq!(parse(&options, "2021-04-03", None), None);
+    }
+}
@@ -0,0 +1,162 @@
+//! Cross-document deduplication for Trafilatura Rust port.
+//! Sites repeat teasers, disclaimers, and whole articles across pages. This module
+//! keeps an LRU cache of the texts seen by earlier extractions, with the same
+//! semantics and defaults as Python trafilatura's `--deduplicate` option: a text
+//! longer than `min_size` characters is a duplicate once it was seen more than
+//! `max_repetitions + 1` times, both for single paragraphs and for whole documents.
+//! Shorter texts are not remembered at all.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+/// Settings of a deduplication cache, defaulting to the values of Python trafilatura
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DedupOptions {
+    /// Texts remembered before the least recently seen is evicted (`LRU_SIZE`)
+    pub cache_size: usize,
+    /// Texts of at most this many characters are never duplicates (`MIN_DUPLCHECK_SIZE`)
+    pub min_size: usize,
+    /// Times a text may occur, plus one, before further occurrences are duplicates
+    /// (`MAX_REPETITIONS`): Python counts the first occurrence as 0
+    pub max_repetitions: usize,
+    /// Characters of a text hashed into its cache key. Python compares whole texts;
+    /// texts sharing their first `teaser_length` characters count as the same text.
+    pub teaser_length: usize,
+}
+
+impl Default for DedupOptions {
+    fn default() -> Self {
+        Self {
+            cache_size: 4096,
+            min_size: 100,
+            max_repetitions: 2,
+            teaser_length: 1000,
+        }
+    }
+}
+
+/// LRU cache of text teasers and their occurrence counts, shared by the extractions
+/// of a batch or a server
+#[derive(Debug)]
+pub struct DedupCache {
+    options: DedupOptions,
+    state: Mutex<LruState>,
+}
+
+#[derive(Debug, Default)]
+struct LruState {
+    /// Occurrences and last use of each teaser hash
+    entries: HashMap<u64, (usize, u64)>,
+    /// Teaser hashes by last use, oldest first
+    recency: BTreeMap<u64, u64>,
+    clock: u64,
+}
+
+impl LruState {
+    /// Occurrences of a teaser, marking it as recently used
+    fn get(&mut self, key: u64) -> Option<usize> {
+        let count = self.entries.get(&key)?.0;
+        self.put(key, count);
+        Some(count)
+    }
+
+    fn put(&mut self, key: u64, count: usize) {
+        self.clock += 1;
+        if let Some((_, used)) = self.entries.insert(key, (count, self.clock)) {
+            self.recency.remove(&used);
+        }
+        self.recency.insert(self.clock, key);
+    }
+
+    fn evict_to(&mut self, size: usize) {
+        while self.entries.len() > size {
+            let Some((_, key)) = self.recency.pop_first() else {
+                return;
+            };
+            self.entries.remove(&key);
+        }
+    }
+}
+
+impl DedupCache {
+    pub fn new(options: DedupOptions) -> Self {
+        Self { options, state: Mutex::new(LruState::default()) }
+    }
+
+    pub fn options(&self) -> DedupOptions {
+        self.options
+    }
+
+    /// Record an occurrence of a text and check if it is a duplicate, like Python
+    /// trafilatura's `duplicate_test`. Whitespace is normalized first.
+    pub fn is_duplicate(&self, text: &str) -> bool {
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        // Short texts are neither duplicates nor cached, so they never evict others
+        if text.chars().count() <= self.options.min_size {
+            return false;
+        }
+        let mut hasher = DefaultHasher::new();
+        text.chars().take(self.options.teaser_length).collect::<String>().hash(&mut hasher);
+        let key = hasher.finish();
+
+        // Python's `LRUCache.get` returns -1 for a missing text, so the first
+        // occurrence is stored as 0
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let count = state.get(key);
+        let duplicate = count.is_some_and(|count| count > self.options.max_repetitions);
+        state.put(key, count.map_or(0, |count| count + 1));
+        state.evict_to(self.options.cache_size.max(1));
+        duplicate
+    }
+
+    /// Drop the paragraphs (lines) of extracted text that are duplicates, with the
+    /// blank line separating them from the previous paragraph
+    pub fn remove_duplicates(&self, content: &str) -> String {
+        let mut kept: Vec<&str> = Vec::new();
+        for line in content.lines() {
+            if !line.trim().is_empty() && self.is_duplicate(line) {
+                if kept.last().is_some_and(|previous| previous.trim().is_empty()) {
+                    kept.pop();
+                }
+                continue;
+            }
+            kept.push(line);
+        }
+        kept.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dedup_cache() {
+        let cache = DedupCache::new(DedupOptions { cache_size: 2, ..Default::default() });
+        let teaser = "Subscribe to our newsletter for the latest news on the harbour renovation and the new ferry timetable.";
+        assert!(teaser.len() > 100);
+
+        // As in Python, the fifth occurrence of a long text is the first duplicate
+        let results: Vec<bool> = (0..6).map(|_| cache.is_duplicate(teaser)).collect();
+        assert_eq!(results, vec![false, false, false, false, true, true]);
+        assert!(!(0..6).any(|_| cache.is_duplicate("Short line")));
+
+        // Short texts are not cached, so they do not evict the teaser
+        cache.is_duplicate("Another short line");
+        assert!(cache.is_duplicate(teaser));
+
+        // Two other long texts evict the least recently seen teaser
+        cache.is_duplicate(&teaser.replace("harbour", "marina"));
+        cache.is_duplicate(&teaser.replace("harbour", "waterfront"));
+        assert!(!cache.is_duplicate(&format!("  {}\n", teaser)));
+
+        let cache = DedupCache::new(DedupOptions::default());
+        let content = format!("Intro.\n\n{}\n\nOutro.", teaser);
+        for _ in 0..4 {
+            cache.remove_duplicates(&content);
+        }
+        assert_eq!(cache.remove_duplicates(&content), "Intro.\n\nOutro.");
+    }
+}
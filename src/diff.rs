@@ -0,0 +1,184 @@
+//! Extraction diffs for Trafilatura Rust port.
+//! This module compares the output of two extractions of the same page, such as
+//! before and after a config change, so that the impact of tuning can be reviewed
+//! on a sample set: which paragraphs one of them added or removed, and how similar
+//! their text is overall.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::eval::{score_text, EvalLevel};
+use crate::ExtractionResult;
+
+/// Difference between a baseline extraction and a changed one
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractionDiff {
+    /// Paragraphs of the changed extraction missing from the baseline, in order
+    pub added: Vec<String>,
+    /// Paragraphs of the baseline missing from the changed extraction, in order
+    pub removed: Vec<String>,
+    /// Token-level F1 of the two texts, from 0 to 1; two empty texts are identical
+    pub similarity: f64,
+}
+
+impl ExtractionDiff {
+    /// Check if the extractions have the same paragraphs
+    pub fn is_unchanged(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl fmt::Display for ExtractionDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "similarity {:.3}", self.similarity)?;
+        for paragraph in &self.removed {
+            write!(f, "\n- {}", paragraph)?;
+        }
+        for paragraph in &self.added {
+            write!(f, "\n+ {}", paragraph)?;
+        }
+        Ok(())
+    }
+}
+
+/// Diff of one document of a sample set
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentDiff {
+    /// Name of the document (file stem)
+    pub name: String,
+    pub diff: ExtractionDiff,
+    /// Extraction errors of either side, which then counts as empty output
+    pub error: Option<String>,
+}
+
+/// Diffs of a whole sample set
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiffReport {
+    /// Per-document diffs, ordered by name
+    pub documents: Vec<DocumentDiff>,
+}
+
+impl DiffReport {
+    /// Number of documents whose paragraphs differ
+    pub fn changed(&self) -> usize {
+        self.documents.iter().filter(|document| !document.diff.is_unchanged()).count()
+    }
+
+    /// Mean similarity over all documents, 1 for an empty sample set
+    pub fn mean_similarity(&self) -> f64 {
+        if self.documents.is_empty() {
+            return 1.0;
+        }
+        self.documents.iter().map(|document| document.diff.similarity).sum::<f64>() / self.documents.len() as f64
+    }
+}
+
+impl fmt::Display for DiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Unchanged documents only count towards the total
+        for document in self.documents.iter().filter(|document| !document.diff.is_unchanged() || document.error.is_some()) {
+            write!(f, "== {}", document.name)?;
+            if let Some(error) = &document.error {
+                write!(f, "  ({})", error)?;
+            }
+            writeln!(f, "\n{}\n", document.diff)?;
+        }
+        write!(f, "TOTAL ({} documents): {} changed, mean similarity {:.3}",
+            self.documents.len(), self.changed(), self.mean_similarity())
+    }
+}
+
+/// Compare the content of a baseline extraction with that of a changed one
+pub fn diff_extractions(baseline: &ExtractionResult, changed: &ExtractionResult) -> ExtractionDiff {
+    diff_text(&baseline.content, &changed.content)
+}
+
+/// Compare two extracted texts paragraph by paragraph, paragraphs being separated
+/// by blank lines. Paragraphs are matched as a multiset, so moving a paragraph is
+/// not a change, while repeating one is.
+pub fn diff_text(baseline: &str, changed: &str) -> ExtractionDiff {
+    let (baseline_paragraphs, changed_paragraphs) = (paragraphs(baseline), paragraphs(changed));
+    let similarity = if baseline_paragraphs.is_empty() && changed_paragraphs.is_empty() {
+        1.0
+    } else {
+        score_text(changed, baseline, EvalLevel::Token).f1()
+    };
+
+    ExtractionDiff {
+        added: unmatched(&changed_paragraphs, &baseline_paragraphs),
+        removed: unmatched(&baseline_paragraphs, &changed_paragraphs),
+        similarity,
+    }
+}
+
+/// Paragraphs of a text, trimmed, without empty ones
+fn paragraphs(text: &str) -> Vec<&str> {
+    text.split("\n\n")
+        .map(str::trim)
+        .filter(|paragraph| !paragraph.is_empty())
+        .collect()
+}
+
+/// Paragraphs of `paragraphs` left over after matching each of `others` once
+fn unmatched(paragraphs: &[&str], others: &[&str]) -> Vec<String> {
+    let mut remaining: HashMap<&str, usize> = HashMap::new();
+    for paragraph in others {
+        *remaining.entry(paragraph).or_insert(0) += 1;
+    }
+
+    paragraphs.iter()
+        .filter(|paragraph| match remaining.get_mut(*paragraph).filter(|count| **count > 0) {
+            Some(count) => {
+                *count -= 1;
+                false
+            },
+            None => true,
+        })
+        .map(|paragraph| paragraph.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_text() {
+        // A tuned config drops a share button and finds the last step of a recipe
+        let baseline = "Apple tart\n\nPreheat the oven to 200 °C.\n\nPin this recipe";
+        let changed = "Apple tart\n\nPreheat the oven to 200 °C.\n\nBake for 35 minutes.";
+        let diff = diff_text(baseline, changed);
+
+        assert_eq!(diff.added, ["Bake for 35 minutes."]);
+        assert_eq!(diff.removed, ["Pin this recipe"]);
+        assert!(diff.similarity > 0.5 && diff.similarity < 1.0, "{}", diff.similarity);
+        assert_eq!(diff.to_string(), format!("similarity {:.3}\n- Pin this recipe\n+ Bake for 35 minutes.", diff.similarity));
+
+        // Moved paragraphs are unchanged, repeated ones are added
+        let diff = diff_text("One.\n\nTwo.", "Two.\n\nOne.\n\nTwo.");
+        assert_eq!(diff.added, ["Two."]);
+        assert!(diff.removed.is_empty());
+
+        let diff = diff_text("", "\n\n");
+        assert!(diff.is_unchanged());
+        assert_eq!(diff.similarity, 1.0);
+        assert_eq!(diff_text("", "Text").similarity, 0.0);
+    }
+
+    #[test]
+    fn test_report() {
+        let report = DiffReport {
+            documents: vec![
+                DocumentDiff { name: "tart".into(), diff: diff_text("One.\n\nTwo.", "One."), error: None },
+                DocumentDiff { name: "scones".into(), diff: diff_text("Three.", "Three."), error: None },
+            ],
+        };
+
+        assert_eq!(report.changed(), 1);
+        let text = report.to_string();
+        assert!(text.starts_with("== tart\nsimilarity 0.667\n- Two.\n\n"), "{}", text);
+        assert!(!text.contains("scones"));
+        assert!(text.ends_with("TOTAL (2 documents): 1 changed, mean similarity 0.833"), "{}", text);
+        assert_eq!(DiffReport::default().mean_similarity(), 1.0);
+    }
+}
@@ -0,0 +1,190 @@
+//! Name resolution policy for Trafilatura Rust port.
+//! This module decides which addresses a download may connect to: it restricts
+//! connections to IPv4 or IPv6, applies fixed host-to-address overrides, and
+//! refuses hosts resolving to private or loopback ranges, which protects the HTTP
+//! service against server-side request forgery with user-supplied URLs.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs};
+
+use crate::TrafilaturaError;
+
+/// Address family of outgoing connections
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IpFamily {
+    #[default]
+    Any,
+    V4,
+    V6,
+}
+
+impl IpFamily {
+    fn allows(&self, ip: &IpAddr) -> bool {
+        match self {
+            IpFamily::Any => true,
+            IpFamily::V4 => ip.is_ipv4(),
+            IpFamily::V6 => ip.is_ipv6(),
+        }
+    }
+}
+
+impl fmt::Display for IpFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            IpFamily::Any => "IP",
+            IpFamily::V4 => "IPv4",
+            IpFamily::V6 => "IPv6",
+        })
+    }
+}
+
+/// Resolves host names to socket addresses
+pub trait Resolver: Send + Sync {
+    /// Look up the addresses of a host
+    fn resolve(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>>;
+}
+
+/// `Resolver` using the resolver of the operating system
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemResolver;
+
+impl Resolver for SystemResolver {
+    fn resolve(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+        Ok((host, port).to_socket_addrs()?.collect())
+    }
+}
+
+/// Resolution settings of HTTP requests
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DnsOptions {
+    /// Connect only over this address family
+    pub ip_family: IpFamily,
+    /// Fixed addresses of host names, used instead of the resolver
+    pub overrides: BTreeMap<String, Vec<IpAddr>>,
+    /// Refuse hosts resolving to loopback, private, link-local, or other non-public addresses
+    pub block_private: bool,
+}
+
+impl DnsOptions {
+    /// Check if any setting differs from plain system resolution
+    pub fn is_active(&self) -> bool {
+        self.ip_family != IpFamily::Any || !self.overrides.is_empty() || self.block_private
+    }
+
+    /// Resolve a host (or IP literal) and check the addresses against the policy.
+    /// All addresses must be allowed, so that no connection attempt can reach a blocked one.
+    pub fn resolve(&self, host: &str, port: u16, resolver: &dyn Resolver) -> Result<Vec<SocketAddr>, TrafilaturaError> {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let addresses = if let Ok(ip) = host.parse::<IpAddr>() {
+            vec![SocketAddr::new(ip, port)]
+        } else if let Some(ips) = self.overrides.get(&host.to_lowercase()) {
+            ips.iter().map(|ip| SocketAddr::new(*ip, port)).collect()
+        } else {
+            resolver.resolve(host, port)?
+        };
+
+        let addresses: Vec<SocketAddr> = addresses.into_iter()
+            .filter(|address| self.ip_family.allows(&address.ip()))
+            .collect();
+        if addresses.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("No {} address for {}", self.ip_family, host)).into());
+        }
+
+        if self.block_private {
+            if let Some(address) = addresses.iter().find(|address| !is_public_ip(address.ip())) {
+                return Err(TrafilaturaError::BlockedAddress(host.to_string(), address.ip()));
+            }
+        }
+
+        Ok(addresses)
+    }
+}
+
+/// Check if an address is globally routable, i.e. not loopback, private, link-local,
+/// shared, documentation, benchmarking, multicast, or reserved
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public_ipv4(mapped),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        // Carrier-grade NAT, 100.64.0.0/10
+        || (a == 100 && (64..128).contains(&b))
+        // IETF protocol assignments, 192.0.0.0/24
+        || (a == 192 && b == 0 && c == 0)
+        // Benchmarking, 198.18.0.0/15
+        || (a == 198 && (b == 18 || b == 19))
+        // Reserved, 240.0.0.0/4
+        || a >= 240)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // Unique local, fc00::/7
+        || (first & 0xfe00) == 0xfc00
+        // Link-local, fe80::/10
+        || (first & 0xffc0) == 0xfe80
+        // Documentation, 2001:db8::/32
+        || (first == 0x2001 && ip.segments()[1] == 0x0db8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Resolves every host to the same addresses
+    struct FixedResolver(Vec<IpAddr>);
+
+    impl Resolver for FixedResolver {
+        fn resolve(&self, _host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+            Ok(self.0.iter().map(|ip| SocketAddr::new(*ip, port)).collect())
+        }
+    }
+
+    #[test]
+    fn test_is_public_ip() {
+        for ip in ["127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0", "::1", "fd00::1", "fe80::1", "::ffff:127.0.0.1"] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{} should be blocked", ip);
+        }
+        for ip in ["93.184.216.34", "8.8.8.8", "2606:2800:220:1::1"] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{} should be allowed", ip);
+        }
+    }
+
+    #[test]
+    fn test_resolve_policy() {
+        let options = DnsOptions { block_private: true, ..Default::default() };
+        let resolver = FixedResolver(vec!["93.184.216.34".parse().unwrap(), "10.0.0.1".parse().unwrap()]);
+
+        assert!(matches!(options.resolve("rebind.example.com", 80, &resolver), Err(TrafilaturaError::BlockedAddress(..))));
+        assert!(matches!(options.resolve("[::1]", 80, &resolver), Err(TrafilaturaError::BlockedAddress(..))));
+
+        let options = DnsOptions {
+            ip_family: IpFamily::V6,
+            overrides: [("archive.internal".to_string(), vec!["10.0.0.5".parse().unwrap(), "fd00::5".parse().unwrap()])].into(),
+            ..Default::default()
+        };
+        let addresses = options.resolve("archive.internal", 443, &resolver).unwrap();
+        assert_eq!(addresses, vec!["[fd00::5]:443".parse().unwrap()]);
+        assert!(options.resolve("example.com", 443, &resolver).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+//! Structured content model for Trafilatura Rust port.
+//! The strategies return the main content as a `Document`: its blocks in document
+//! order (headings, paragraphs, lists, code samples, tables, and images), which
+//! serializers turn into text or other formats. The text of blocks already has its
+//! links rendered according to `ExtractionConfig::rendered_link_style`.
+
+use crate::tables::Table;
+use crate::ImageStyle;
+
+/// The main content of a page as a sequence of blocks
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Document {
+    pub elements: Vec<Element>,
+    /// Tables kept aside with `ExtractionConfig::separate_tables`, referenced by
+    /// `Element::TableRef`
+    pub tables: Vec<Table>,
+    /// Link targets of footnote-style links, referenced by their `[N]` markers
+    pub footnotes: Vec<String>,
+}
+
+/// A block of content
+#[derive(Debug, Clone, PartialEq)]
+pub enum Element {
+    /// Heading of level 1 to 6
+    Heading { level: u8, text: String },
+    Paragraph(String),
+    /// Items of a list; items of nested lists follow the item holding them
+    List { ordered: bool, items: Vec<String> },
+    /// Code sample, with its whitespace as in the page
+    Code(String),
+    /// Table rendered in place, as rows of cell texts
+    Table(Vec<Vec<String>>),
+    /// Placeholder of the table kept aside at this 1-based index of `Document::tables`
+    TableRef(usize),
+    Image(Image),
+}
+
+/// An image, or an SVG graphic with its title as alternative text
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Image {
+    pub alt: Option<String>,
+    pub src: Option<String>,
+    /// Caption of the figure holding the image
+    pub caption: Option<String>,
+}
+
+impl Document {
+    /// Check if the document has no content
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Serialize the document as plain text: blocks separated by blank lines, list
+    /// items as bullets, tables as `[Table: ...]` or `[Table N]`, images in the given
+    /// style, and the footnote link list at the end
+    pub fn to_text(&self, image_style: ImageStyle) -> String {
+        let mut blocks: Vec<String> = self.elements.iter()
+            .filter_map(|element| element.to_text(image_style))
+            .collect();
+        if !self.footnotes.is_empty() {
+            let list = self.footnotes.iter()
+                .enumerate()
+                .map(|(i, href)| format!("[{}] {}", i + 1, href))
+                .collect::<Vec<_>>();
+            blocks.push(list.join("\n"));
+        }
+        blocks.join("\n\n")
+    }
+}
+
+impl Element {
+    /// The plain text of the block, or `None` for images skipped in the given style
+    pub fn to_text(&self, image_style: ImageStyle) -> Option<String> {
+        Some(match self {
+            Element::Heading { text, .. } | Element::Paragraph(text) | Element::Code(text) => text.clone(),
+            Element::List { items, .. } => items.iter()
+                .map(|item| format!("• {}", item))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Element::Table(rows) => {
+                let rows = rows.iter().map(|cells| cells.join(" | ")).collect::<Vec<_>>();
+                format!("[Table: {}]", rows.join("\n"))
+            },
+            Element::TableRef(index) => format!("[Table {}]", index),
+            Element::Image(image) => image.to_text(image_style)?,
+        })
+    }
+}
+
+impl Image {
+    /// Render the image in the given style, or `None` if there is nothing to show in it
+    pub fn to_text(&self, style: ImageStyle) -> Option<String> {
+        let (alt, src) = (self.alt.as_deref(), self.src.as_deref());
+        let label = alt.map_or_else(|| "Image".to_string(), |alt| format!("Image: {}", alt));
+        let linked = || match src {
+            Some(src) => format!("[{}]({})", label, src),
+            None => format!("[{}]", label),
+        };
+
+        match style {
+            ImageStyle::Skip => None,
+            ImageStyle::AltText => alt.map(|alt| format!("[Image: {}]", alt)),
+            ImageStyle::Markdown => (alt.is_some() || src.is_some()).then(linked),
+            ImageStyle::Figure => {
+                let image = (alt.is_some() || src.is_some()).then(linked)?;
+                Some(match &self.caption {
+                    Some(caption) => format!("{}\nFigure: {}", image, caption),
+                    None => image,
+                })
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_text() {
+        let document = Document {
+            elements: vec![
+                Element::Heading { level: 2, text: "Harbour news".into() },
+                Element::Paragraph("The pier reopened[1] on Monday.".into()),
+                Element::Image(Image { alt: None, src: Some("https://example.com/map.png".into()), caption: None }),
+                Element::List { ordered: false, items: vec!["Ferries".into(), "Boats".into()] },
+                Element::Table(vec![vec!["Day".into(), "Berths".into()], vec!["Monday".into(), "12".into()]]),
+                Element::TableRef(1),
+            ],
+            tables: vec![Table::default()],
+            footnotes: vec!["https://example.com/pier".into()],
+        };
+
+        // Images without alternative text are skipped in the alt-text style
+        assert_eq!(
+            document.to_text(ImageStyle::AltText),
+            "Harbour news\n\nThe pier reopened[1] on Monday.\n\n• Ferries\n• Boats\n\n\
+             [Table: Day | Berths\nMonday | 12]\n\n[Table 1]\n\n[1] https://example.com/pier"
+        );
+        assert!(document.to_text(ImageStyle::Markdown).contains("Monday.\n\n[Image](https://example.com/map.png)\n\n• Ferries"));
+        assert_eq!(Document::default().to_text(ImageStyle::AltText), "");
+    }
+}
@@ -0,0 +1,194 @@
+//! Newsletter and email HTML support for Trafilatura Rust port.
+//! Email HTML is laid out with nested tables, fixed-width wrappers, and inline
+//! styles, and carries tracking pixels and unsubscribe footers. This module
+//! rewrites such a document into plain blocks the extraction strategies can handle.
+
+use std::collections::HashSet;
+
+use ego_tree::NodeId;
+use html5ever::{local_name, namespace_url, ns, QualName};
+use lazy_static::lazy_static;
+use regex::Regex;
+use scraper::{ElementRef, Html, Node};
+
+use crate::html::descendants_named;
+
+lazy_static! {
+    /// Phrases of the footers and preheaders newsletters wrap their content in
+    static ref FOOTER_RE: Regex = Regex::new(
+        r"(?i)unsubscribe|view (?:this email |it )?in (?:your )?browser|(?:manage|update) (?:your )?(?:email |subscription )?preferences|you(?:'re| are) receiving this|no longer wish to receive|sent to \S+@\S+"
+    ).unwrap();
+
+    /// Inline styles of hidden elements and 0-1px images
+    static ref HIDDEN_STYLE_RE: Regex = Regex::new(
+        r"(?i)display\s*:\s*none|(?:^|[;\s])(?:width|height)\s*:\s*[01]px"
+    ).unwrap();
+
+    /// Inline styles of fixed-width wrappers
+    static ref WIDTH_STYLE_RE: Regex = Regex::new(r"(?i)(?:^|[;\s])(?:max-)?width\s*:").unwrap();
+}
+
+/// Footers are short: longer blocks mentioning "unsubscribe" hold content too
+const MAX_FOOTER_LENGTH: usize = 400;
+
+/// Blocks that can be removed as a footer
+const BLOCK_ELEMENTS: [&str; 12] = ["div", "p", "td", "th", "tr", "table", "li", "ul", "section", "footer", "center", "address"];
+
+/// Table parts renamed to `div` when unwrapping a layout table
+const TABLE_PARTS: [&str; 7] = ["table", "thead", "tbody", "tfoot", "tr", "td", "th"];
+
+/// Rewrite an email document in place: drop tracking pixels, hidden preheaders,
+/// and footers, turn layout tables into `div` blocks, and unwrap fixed-width wrappers
+pub fn prepare_email(document: &mut Html) {
+    let mut removed = Vec::new();
+    let root = document.root_element();
+    removed.extend(
+        root.descendants()
+            .filter_map(ElementRef::wrap)
+            .filter(|element| is_hidden(*element))
+            .map(|element| element.id())
+    );
+    find_footers(root, &mut removed);
+
+    let layout_tables: HashSet<NodeId> = descendants_named(root, &["table"])
+        .filter(|table| is_layout_table(*table))
+        .map(|table| table.id())
+        .collect();
+    // Parts of nested data tables belong to those tables and keep their names
+    let layout_parts: Vec<NodeId> = descendants_named(root, &TABLE_PARTS)
+        .filter(|part| {
+            let table = if part.value().name() == "table" { Some(*part) } else { owning_table(*part) };
+            table.is_some_and(|table| layout_tables.contains(&table.id()))
+        })
+        .map(|part| part.id())
+        .collect();
+
+    let wrappers: Vec<NodeId> = descendants_named(root, &["div", "center"])
+        .filter(|element| is_width_wrapper(*element))
+        .map(|element| element.id())
+        .collect();
+
+    for id in removed {
+        if let Some(mut node) = document.tree.get_mut(id) {
+            node.detach();
+        }
+    }
+
+    for id in layout_parts {
+        if let Some(mut node) = document.tree.get_mut(id) {
+            if let Node::Element(element) = node.value() {
+                element.name = QualName::new(None, ns!(html), local_name!("div"));
+            }
+        }
+    }
+
+    for id in wrappers {
+        unwrap(document, id);
+    }
+}
+
+/// Replace an element with its children
+fn unwrap(document: &mut Html, id: NodeId) {
+    let children: Vec<NodeId> = match document.tree.get(id) {
+        Some(node) => node.children().map(|child| child.id()).collect(),
+        None => return,
+    };
+
+    if let Some(mut node) = document.tree.get_mut(id) {
+        if node.parent().is_none() {
+            return;
+        }
+        for child in children {
+            node.insert_id_before(child);
+        }
+        node.detach();
+    }
+}
+
+/// Tracking pixels and hidden preheader text
+fn is_hidden(element: ElementRef) -> bool {
+    let value = element.value();
+    if value.attr("style").is_some_and(|style| HIDDEN_STYLE_RE.is_match(style)) {
+        return true;
+    }
+
+    let tiny = |attr: &str| value.attr(attr).and_then(|size| size.trim_end_matches("px").parse::<u32>().ok()).is_some_and(|size| size <= 1);
+    value.name() == "img" && (tiny("width") || tiny("height"))
+}
+
+/// Collect the innermost blocks containing footer phrases. The tree is walked with an
+/// explicit stack, as deeply nested markup would overflow a recursion.
+fn find_footers(element: ElementRef, footers: &mut Vec<NodeId>) {
+    let is_footer_text = |element: ElementRef| FOOTER_RE.is_match(&element.text().collect::<String>());
+
+    let mut pending: Vec<ElementRef> = element.children().filter_map(ElementRef::wrap).rev().collect();
+    while let Some(child) = pending.pop() {
+        if !is_footer_text(child) {
+            continue;
+        }
+
+        let innermost = BLOCK_ELEMENTS.contains(&child.value().name())
+            && !descendants_named(child, &BLOCK_ELEMENTS).any(is_footer_text);
+        if innermost && child.text().collect::<String>().trim().chars().count() <= MAX_FOOTER_LENGTH {
+            footers.push(child.id());
+        } else {
+            pending.extend(child.children().filter_map(ElementRef::wrap).rev());
+        }
+    }
+}
+
+/// The nearest table around a table part
+fn owning_table(part: ElementRef) -> Option<ElementRef> {
+    part.ancestors()
+        .filter_map(ElementRef::wrap)
+        .find(|ancestor| ancestor.value().name() == "table")
+}
+
+/// Tables without header cells of their own are used for layout
+fn is_layout_table(table: ElementRef) -> bool {
+    table.value().attr("role") == Some("presentation")
+        || !descendants_named(table, &["th"]).any(|th| owning_table(th).is_some_and(|owner| owner.id() == table.id()))
+}
+
+/// Fixed-width and centered wrappers around the newsletter body
+fn is_width_wrapper(element: ElementRef) -> bool {
+    let value = element.value();
+    value.name() == "center"
+        || value.attr("width").is_some()
+        || value.attr("style").is_some_and(|style| WIDTH_STYLE_RE.is_match(style))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NEWSLETTER: &str = r#"<html><body>
+        <span style="display:none">Preheader teaser text</span>
+        <center><table role="presentation" width="600"><tr><td>
+            <div style="max-width:600px"><p>Welcome to the weekly digest.</p></div>
+        </td></tr><tr><td>
+            <table><tr><th>Item</th><th>Price</th></tr><tr><td>Tea</td><td>3</td></tr></table>
+        </td></tr><tr><td>
+            <p>You are receiving this because you signed up. <a href="/u">Unsubscribe</a></p>
+        </td></tr></table></center>
+        <img src="https://t.example.com/open.gif" width="1" height="1">
+        </body></html>"#;
+
+    #[test]
+    fn test_prepare_email() {
+        let mut document = Html::parse_document(NEWSLETTER);
+
+        prepare_email(&mut document);
+
+        let html = document.html();
+        assert!(html.contains("Welcome to the weekly digest."));
+        assert!(!html.contains("Preheader"));
+        assert!(!html.contains("Unsubscribe"));
+        assert!(!html.contains("open.gif"));
+        assert!(!html.contains("<center"));
+        assert!(!html.contains("max-width"));
+        // Data tables survive, layout tables become blocks
+        assert_eq!(html.matches("<table").count(), 1);
+        assert!(html.contains("<th>Item</th>"));
+    }
+}
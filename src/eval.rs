@@ -0,0 +1,236 @@
+//! Evaluation harness for Trafilatura Rust port.
+//! This module scores extraction output against a labeled dataset so that
+//! heuristic changes can be measured with precision, recall, and F1.
+//!
+//! A dataset is a directory of `<name>.html` documents, each paired with a
+//! `<name>.txt` file holding the expected main text.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use tracing::debug;
+
+use crate::utils::{is_html_file, tokenize};
+use crate::{extract_html, ExtractionConfig, TrafilaturaError};
+
+/// Unit in which extracted and expected text are compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvalLevel {
+    /// Compare bags of lowercased words
+    #[default]
+    Token,
+    /// Compare bags of whitespace-normalized sentences
+    Sentence,
+}
+
+/// Matched and unmatched unit counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Counts {
+    /// Units found in both the extraction and the expected text
+    pub true_positives: usize,
+    /// Extracted units missing from the expected text
+    pub false_positives: usize,
+    /// Expected units missing from the extraction
+    pub false_negatives: usize,
+}
+
+impl Counts {
+    /// Fraction of extracted units that are expected
+    pub fn precision(&self) -> f64 {
+        ratio(self.true_positives, self.true_positives + self.false_positives)
+    }
+
+    /// Fraction of expected units that were extracted
+    pub fn recall(&self) -> f64 {
+        ratio(self.true_positives, self.true_positives + self.false_negatives)
+    }
+
+    /// Harmonic mean of precision and recall
+    pub fn f1(&self) -> f64 {
+        let (precision, recall) = (self.precision(), self.recall());
+        if precision + recall == 0.0 {
+            0.0
+        } else {
+            2.0 * precision * recall / (precision + recall)
+        }
+    }
+
+    fn add(&mut self, other: &Counts) {
+        self.true_positives += other.true_positives;
+        self.false_positives += other.false_positives;
+        self.false_negatives += other.false_negatives;
+    }
+}
+
+/// Score of a single document of the dataset
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentScore {
+    /// Name of the document (file stem)
+    pub name: String,
+    /// Unit counts of the document
+    pub counts: Counts,
+    /// Extraction error, if the document could not be extracted
+    pub error: Option<String>,
+}
+
+/// Scores of a whole dataset
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EvalReport {
+    /// Per-document scores, ordered by name
+    pub documents: Vec<DocumentScore>,
+    /// Counts summed over all documents (micro average)
+    pub total: Counts,
+}
+
+impl fmt::Display for EvalReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<40} {:>9} {:>9} {:>9}", "document", "precision", "recall", "f1")?;
+        for document in &self.documents {
+            write!(f, "{:<40} {:>9.3} {:>9.3} {:>9.3}", document.name,
+                document.counts.precision(), document.counts.recall(), document.counts.f1())?;
+            if let Some(error) = &document.error {
+                write!(f, "  ({})", error)?;
+            }
+            writeln!(f)?;
+        }
+        write!(f, "{:<40} {:>9.3} {:>9.3} {:>9.3}", format!("TOTAL ({} documents)", self.documents.len()),
+            self.total.precision(), self.total.recall(), self.total.f1())
+    }
+}
+
+/// Run extraction over every `<name>.html`/`<name>.txt` pair in a directory and score it
+pub fn evaluate_dir<P: AsRef<Path>>(dir: P, config: &ExtractionConfig, level: EvalLevel) -> Result<EvalReport, TrafilaturaError> {
+    let mut paths: Vec<_> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    paths.sort();
+
+    let mut report = EvalReport::default();
+    for html_path in paths.iter().filter(|path| is_html_file(path)) {
+        let expected_path = html_path.with_extension("txt");
+        if !expected_path.is_file() {
+            debug!("Skipping {}: no expected text", html_path.display());
+            continue;
+        }
+
+        let html = fs::read_to_string(html_path)?;
+        let expected = fs::read_to_string(&expected_path)?;
+
+        // Failed extractions count as empty output, so they lower recall
+        let (extracted, error) = match extract_html(&html, config) {
+            Ok(result) => (result.content, None),
+            Err(e) => (String::new(), Some(e.to_string())),
+        };
+
+        let counts = score_text(&extracted, &expected, level);
+        report.total.add(&counts);
+        report.documents.push(DocumentScore {
+            name: html_path.file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+            counts,
+            error,
+        });
+    }
+
+    Ok(report)
+}
+
+/// Compare extracted text with the expected text as bags of units
+pub fn score_text(extracted: &str, expected: &str, level: EvalLevel) -> Counts {
+    let extracted = units(extracted, level);
+    let mut remaining: HashMap<String, usize> = HashMap::new();
+    let expected = units(expected, level);
+    for unit in &expected {
+        *remaining.entry(unit.clone()).or_insert(0) += 1;
+    }
+
+    let mut true_positives = 0;
+    for unit in &extracted {
+        if let Some(count) = remaining.get_mut(unit).filter(|count| **count > 0) {
+            *count -= 1;
+            true_positives += 1;
+        }
+    }
+
+    Counts {
+        true_positives,
+        false_positives: extracted.len() - true_positives,
+        false_negatives: expected.len() - true_positives,
+    }
+}
+
+/// Split text into normalized comparison units
+fn units(text: &str, level: EvalLevel) -> Vec<String> {
+    match level {
+        EvalLevel::Token => tokenize(text)
+            .into_iter()
+            .map(|token| token.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+            .filter(|token| !token.is_empty())
+            .collect(),
+        EvalLevel::Sentence => text
+            .split(['.', '!', '?', '\n', '。', '！', '？'])
+            .map(|sentence| sentence.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase())
+            .filter(|sentence| !sentence.is_empty())
+            .collect(),
+    }
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_tokens() {
+        let counts = score_text("The quick brown fox. Share this!", "the quick brown fox", EvalLevel::Token);
+
+        assert_eq!(counts, Counts { true_positives: 4, false_positives: 2, false_negatives: 0 });
+        assert!((counts.precision() - 4.0 / 6.0).abs() < 1e-9);
+        assert_eq!(counts.recall(), 1.0);
+        assert!((counts.f1() - 0.8).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_score_sentences() {
+        let counts = score_text(
+            "First sentence.  Second\nsentence? Menu",
+            "First sentence. Second sentence? Third one.",
+            EvalLevel::Sentence,
+        );
+
+        // "Second\nsentence" is split at the line break
+        assert_eq!(counts, Counts { true_positives: 1, false_positives: 3, false_negatives: 2 });
+    }
+
+    #[test]
+    fn test_empty_scores() {
+        let counts = score_text("", "", EvalLevel::Token);
+        assert_eq!(counts.f1(), 0.0);
+    }
+
+    #[test]
+    fn test_evaluate_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let paragraph = "Evaluation fixtures need paragraphs that are long enough to pass the minimum size check of the extractor.";
+        fs::write(
+            dir.path().join("story.html"),
+            format!("<html><body><nav>Home</nav><article><p>{0}</p><p>{0}</p><p>{0}</p></article></body></html>", paragraph),
+        ).unwrap();
+        fs::write(dir.path().join("story.txt"), format!("{0}\n{0}\n{0}", paragraph)).unwrap();
+        fs::write(dir.path().join("unlabeled.html"), "<p>ignored</p>").unwrap();
+
+        let report = evaluate_dir(dir.path(), &ExtractionConfig::default(), EvalLevel::Token).unwrap();
+
+        assert_eq!(report.documents.len(), 1);
+        assert_eq!(report.documents[0].name, "story");
+        assert_eq!(report.total.recall(), 1.0);
+    }
+}
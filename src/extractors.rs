@@ -1,12 +1,20 @@
 //! Content extraction algorithms for Trafilatura Rust port.
 //! This module implements various extraction strategies to identify the main content.
 
-use scraper::{Html, Selector, ElementRef, Element};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use ego_tree::NodeId;
+use scraper::{Html, ElementRef, Element};
 use lazy_static::lazy_static;
-use log::debug;
+use tracing::{debug, debug_span};
 
-use crate::{ExtractionConfig, TrafilaturaError};
-use crate::html::{clean_html, get_text_content, has_class_hint, has_id_hint};
+use crate::{ExtractionConfig, ExtractionMode, TrafilaturaError};
+use crate::html::{add_content, ancestors_within, css_path, descendants_named, get_content, has_class_hint, has_id_hint, has_role, TextLengths, BOILERPLATE_ROLES, CONTENT_ROLES};
+use crate::document::Document;
+use crate::render::ContentRenderer;
+use crate::structure::{repeated_children, similar_siblings, MIN_REPEATED_SIBLINGS};
+use crate::utils::{create_selector, text_length, try_selector};
 
 lazy_static! {
     /// Content element hints - classes that suggest main content
@@ -63,161 +71,147 @@ lazy_static! {
 
     /// Boilerplate link density threshold - links above this ratio are likely navigation
     static ref LINK_DENSITY_THRESHOLD: f64 = 0.33;  // Lowered from 0.5 to be more aggressive at filtering
+
+    /// Link density threshold for documentation pages, whose text is cross-reference heavy
+    static ref DOCS_LINK_DENSITY_THRESHOLD: f64 = 0.75;
+}
+
+/// Elements considered as content containers by density scoring
+const CANDIDATE_TAGS: [&str; 5] = ["article", "main", "section", "div", "body"];
+
+/// Share of a candidate's text held by a nested candidate above which the nested one is preferred
+const MIN_NESTED_TEXT_SHARE: f64 = 0.6;
+
+/// Characters per element above which text density earns no further bonus in
+/// structural scoring
+const MAX_DENSITY_BONUS_CHARS: f64 = 200.0;
+
+/// Average text length of repeated children below which they are teaser cards
+const MAX_CARD_TEXT_LENGTH: usize = 120;
+
+/// Classes of the boxes around a Wikipedia article: table of contents, navigation
+/// boxes, infoboxes, and maintenance notes
+const WIKI_BOILERPLATE_CLASSES: [&str; 7] = ["toc", "navbox", "infobox", "hatnote", "metadata", "mw-editsection", "reflist"];
+
+/// Link density above which an element is treated as navigation
+fn link_density_threshold(config: &ExtractionConfig) -> f64 {
+    if config.mode == ExtractionMode::Docs {
+        *DOCS_LINK_DENSITY_THRESHOLD
+    } else {
+        *LINK_DENSITY_THRESHOLD
+    }
 }
 
-/// Extract content from Wikipedia pages using their specific structure
-fn extract_wikipedia_content(document: &Html, _config: &ExtractionConfig) -> Option<String> {
+/// Extract content from Wikipedia pages using their specific structure, with the
+/// element it was extracted from
+fn extract_wikipedia_content<'a>(document: &'a Html, config: &ExtractionConfig) -> Option<(Document, ElementRef<'a>)> {
     // Check if this is a Wikipedia page (looking for specific elements or patterns)
     // Wikipedia pages have a specific structure with id="content" and class="mw-parser-output"
     
     // First check for the main content wrapper
-    let main_content_selector = Selector::parse("#mw-content-text").unwrap();
+    let main_content_selector = try_selector("#mw-content-text")?;
     let main_content = document.select(&main_content_selector).next()?;
     
     // Find the parser output div which contains all the article content
-    let parser_output_selector = Selector::parse(".mw-parser-output").unwrap();
+    let parser_output_selector = try_selector(".mw-parser-output")?;
     let parser_output = main_content.select(&parser_output_selector).next()?;
     
-    // Remove unwanted elements specific to Wikipedia
-    // - Table of contents
-    // - Navigation boxes
-    // - Infoboxes
-    // - References section
-    // - External links section
-    // - See also section
-    // Prepare text extraction
-
-    // Extract all paragraphs first
-    let mut content = String::new();
+    let mut renderer = ContentRenderer::new(config).skip_reference_sections();
     
     // Add the title
-    let title_selector = Selector::parse("#firstHeading").unwrap();
+    let title_selector = try_selector("#firstHeading")?;
     if let Some(title) = document.select(&title_selector).next() {
-        content.push_str(&title.text().collect::<String>());
-        content.push_str("\n\n");
+        renderer.add(&title, &|_| false);
     }
     
-    // Process sections and paragraphs
-    let section_selector = Selector::parse("h1, h2, h3, h4, h5, h6, p, ul, ol").unwrap();
-    let mut skip_section = false;
-    
-    for element in parser_output.select(&section_selector) {
-        let tag_name = element.value().name();
-        let element_text = element.text().collect::<String>().trim().to_string();
-        
-        // Skip empty elements
-        if element_text.is_empty() {
-            continue;
-        }
-        
-        // Check for heading indicating sections to skip
-        if tag_name.starts_with('h') {
-            skip_section = element_text == "References" || 
-                          element_text == "External links" || 
-                          element_text == "See also" || 
-                          element_text == "Further reading" ||
-                          element_text == "Notes" ||
-                          element_text.contains("Bibliography") ||
-                          element_text.contains("Sources");
-            
-            if !skip_section {
-                content.push_str(&element_text);
-                content.push_str("\n\n");
-            }
-            continue;
-        }
-        
-        if skip_section {
-            continue;
+    // Process sections, paragraphs, and lists, without the navigation notes and
+    // the boxes around the article
+    renderer.add(&parser_output, &|block| {
+        let boxed = std::iter::once(*block)
+            .chain(ancestors_within(block, &parser_output))
+            .any(|element| has_class_hint(&element, &WIKI_BOILERPLATE_CLASSES));
+        if boxed {
+            return true;
         }
-        
-        // Process paragraphs and lists
-        if tag_name == "p" {
-            // Skip very short paragraphs that are likely metadata
-            if element_text.len() < 20 && (
-                element_text.contains("Redirected from") || 
-                element_text.contains("Jump to navigation") || 
-                element_text.contains("From Wikipedia")
-            ) {
-                continue;
-            }
-            
-            content.push_str(&element_text);
-            content.push_str("\n\n");
-        } else if tag_name == "ul" || tag_name == "ol" {
-            // Process lists
-            let li_selector = Selector::parse("li").unwrap();
-            for li in element.select(&li_selector) {
-                let li_text = li.text().collect::<String>().trim().to_string();
-                if !li_text.is_empty() {
-                    content.push_str("• ");
-                    content.push_str(&li_text);
-                    content.push_str("\n");
-                }
-            }
-            content.push_str("\n");
-        }
-    }
-    
-    if !content.is_empty() {
-        Some(content.trim().to_string())
-    } else {
-        None
-    }
+        // Skip very short paragraphs that are likely metadata
+        let text = block.text().collect::<String>();
+        block.value().name() == "p" && text_length(&text) < 20 && (
+            text.contains("Redirected from") || 
+            text.contains("Jump to navigation") || 
+            text.contains("From Wikipedia")
+        )
+    });
+    
+    let content = renderer.finish();
+    (!content.is_empty()).then_some((content, parser_output))
 }
 
-/// Extract content from a document using multiple strategies
-pub fn extract_content(document: &Html, config: &ExtractionConfig) -> Result<String, TrafilaturaError> {
-    // First clean the document
-    let cleaned_document = clean_html(document, config)?;
-    
+/// Check if extracted content reaches `ExtractionConfig::min_extracted_size`
+fn is_long_enough(content: &Document, config: &ExtractionConfig) -> bool {
+    let text = content.to_text(config.image_style);
+    !text.is_empty() && text_length(&text) >= config.min_extracted_size
+}
+
+/// Extract content from a document using multiple strategies.
+/// The document is expected to have been cleaned with `clean_html`.
+pub fn extract_content(document: &Html, config: &ExtractionConfig) -> Result<Document, TrafilaturaError> {
+    extract_content_container(document, config).map(|(content, _)| content)
+}
+
+/// Extract content like `extract_content`, also returning the CSS path of the
+/// element it was extracted from, unless it was gathered from scattered paragraphs
+pub fn extract_content_container(document: &Html, config: &ExtractionConfig) -> Result<(Document, Option<String>), TrafilaturaError> {
     // Check if this is a Wikipedia page and use specialized extraction
-    if let Some(content) = extract_wikipedia_content(&cleaned_document, config) {
-        if !content.is_empty() && content.len() >= config.min_extracted_size {
-            debug!("Content extracted using Wikipedia-specific strategy");
-            return Ok(content);
+    if let Some((content, container)) = extract_wikipedia_content(document, config) {
+        if is_long_enough(&content, config) {
+            debug!(strategy = "wikipedia", "Content extracted");
+            return Ok((content, Some(css_path(&container))));
         }
     }
     
     // Try to extract content using different strategies in order
+    let stats = DocumentStats::collect(document);
     
-    // 1. Try with article tag - semantic HTML is the most reliable indicator
-    let article_selector = Selector::parse("article").unwrap();
-    let articles = cleaned_document.select(&article_selector);
+    // 1. Try with article tags and main content landmarks - semantic HTML is the
+    // most reliable indicator, even where class names are generated
+    let article_selector = create_selector("article, [role~='article'], [role~='main']")?;
+    let articles = document.select(&article_selector);
     
     // Find the longest and most content-rich article element
-    let mut best_article_text = String::new();
+    let mut best_article = None;
     let mut best_article_score = 0;
     
     for article in articles {
-        let text = get_text_content(&article, config);
-        if !text.is_empty() && text.len() >= config.min_extracted_size {
-            let score = score_node(&article, config);
+        let content = get_content(&article, config);
+        if is_long_enough(&content, config) {
+            let score = score_node(&article, &stats, config);
             if score > best_article_score {
-                best_article_text = text;
+                best_article = Some((content, article));
                 best_article_score = score;
             }
         }
     }
     
-    if !best_article_text.is_empty() {
-        debug!("Content extracted using article tag strategy");
-        return Ok(best_article_text);
+    if let Some((content, article)) = best_article {
+        debug!(strategy = "article", "Content extracted");
+        return Ok((content, Some(css_path(&article))));
     }
     
     // 2. Try with content hints - classes and IDs that suggest content
-    if let Some(content) = extract_by_hints(&cleaned_document, config) {
-        if !content.is_empty() && content.len() >= config.min_extracted_size {
-            debug!("Content extracted using hints strategy");
-            return Ok(content);
+    let by_hints = config.uses_class_hints().then(|| extract_by_hints(document, config)).flatten();
+    if let Some((content, container)) = by_hints {
+        if is_long_enough(&content, config) {
+            debug!(strategy = "hints", "Content extracted");
+            return Ok((content, Some(css_path(&container))));
         }
     }
     
     // 3. Try with content density - most reliable fallback
-    if let Some(content) = extract_by_density(&cleaned_document, config) {
-        if !content.is_empty() && content.len() >= config.min_extracted_size {
-            debug!("Content extracted using density strategy");
-            return Ok(content);
+    let density_content = debug_span!("score").in_scope(|| extract_by_density(document, &stats, config));
+    if let Some((content, container)) = density_content {
+        if is_long_enough(&content, config) {
+            debug!(strategy = "density", "Content extracted");
+            return Ok((content, Some(css_path(&container))));
         }
     }
     
@@ -225,23 +219,22 @@ pub fn extract_content(document: &Html, config: &ExtractionConfig) -> Result<Str
     let mut paragraphs = Vec::new();
     
     // Get all paragraphs
-    let p_selector = Selector::parse("p").unwrap();
-    for p in cleaned_document.select(&p_selector) {
+    let p_selector = create_selector("p")?;
+    for p in document.select(&p_selector) {
         // Skip very short paragraphs that are likely menu items or buttons
-        let text = p.text().collect::<String>();
-        if text.len() < 20 {
+        let lengths = stats.lengths.get(&p);
+        if lengths.text < 20 && config.mode != ExtractionMode::Docs {
             continue;
         }
         
         // Skip paragraphs with high link density
-        let link_density = calculate_link_density(&p);
-        if link_density > *LINK_DENSITY_THRESHOLD {
+        if lengths.link_density() > link_density_threshold(config) {
             continue;
         }
         
         // Skip paragraphs in unwanted containers
         if let Some(parent) = p.parent_element() {
-            if has_class_hint(&parent, &["nav", "menu", "footer", "header", "sidebar", "comment"]) {
+            if config.uses_class_hints() && has_class_hint(&parent, &["nav", "menu", "footer", "header", "sidebar", "comment"]) {
                 continue;
             }
         }
@@ -251,56 +244,50 @@ pub fn extract_content(document: &Html, config: &ExtractionConfig) -> Result<Str
     
     // If we have multiple paragraphs, try to find clusters of them
     if paragraphs.len() >= 3 {
-        // Group consecutive paragraphs that are likely part of the main content
-        let mut text = String::new();
+        // Group consecutive paragraphs that are likely part of the main content,
+        // sharing one renderer so footnote numbering spans the whole text
+        let mut renderer = ContentRenderer::new(config).skip_boilerplate();
         for p in paragraphs {
-            let paragraph_text = get_text_content(&p, config);
-            if !paragraph_text.trim().is_empty() {
-                text.push_str(&paragraph_text);
-                text.push('\n');
-            }
+            add_content(&p, &mut renderer);
         }
+        let content = renderer.finish();
         
-        if text.len() >= config.min_extracted_size {
-            debug!("Content extracted using filtered paragraphs strategy");
-            return Ok(text.trim().to_string());
+        if text_length(&content.to_text(config.image_style)) >= config.min_extracted_size {
+            debug!(strategy = "paragraphs", "Content extracted");
+            return Ok((content, None));
         }
     }
     
-    // 5. Last resort - just try to get any text
-    let mut text = String::new();
-    for p in cleaned_document.select(&p_selector) {
-        let paragraph_text = get_text_content(&p, config);
-        if !paragraph_text.trim().is_empty() {
-            text.push_str(&paragraph_text);
-            text.push('\n');
-        }
-    }
+    // 5. Last resort - just try to get any text from the body. Pages too short for
+    // the strategies above keep their headings and lists along with the paragraphs.
+    let body_selector = create_selector("body")?;
+    let body = document.select(&body_selector).next();
+    let content = body.map(|body| get_content(&body, config)).unwrap_or_default();
     
-    debug!("Content extracted using last-resort paragraphs strategy");
-    Ok(text.trim().to_string())
+    debug!(strategy = "body", "Content extracted");
+    Ok((content, body.map(|body| css_path(&body))))
 }
 
-/// Extract content based on class and ID hints
-fn extract_by_hints(document: &Html, config: &ExtractionConfig) -> Option<String> {
+/// Extract content based on class and ID hints, with the element it was extracted from
+fn extract_by_hints<'a>(document: &'a Html, config: &ExtractionConfig) -> Option<(Document, ElementRef<'a>)> {
     // Try to find elements with content class hints
     for class_hint in CONTENT_CLASSES.iter() {
-        let selector = Selector::parse(&format!("[class*='{}']", class_hint)).unwrap();
+        let selector = try_selector(&format!("[class*='{}']", class_hint))?;
         if let Some(element) = document.select(&selector).next() {
-            let text = get_text_content(&element, config);
-            if !text.is_empty() && text.len() >= config.min_extracted_size {
-                return Some(text);
+            let content = get_content(&element, config);
+            if is_long_enough(&content, config) {
+                return Some((content, element));
             }
         }
     }
     
     // Try to find elements with content ID hints
     for id_hint in CONTENT_IDS.iter() {
-        let selector = Selector::parse(&format!("[id*='{}']", id_hint)).unwrap();
+        let selector = try_selector(&format!("[id*='{}']", id_hint))?;
         if let Some(element) = document.select(&selector).next() {
-            let text = get_text_content(&element, config);
-            if !text.is_empty() && text.len() >= config.min_extracted_size {
-                return Some(text);
+            let content = get_content(&element, config);
+            if is_long_enough(&content, config) {
+                return Some((content, element));
             }
         }
     }
@@ -308,35 +295,52 @@ fn extract_by_hints(document: &Html, config: &ExtractionConfig) -> Option<String
     None
 }
 
-/// Extract content based on text density
-fn extract_by_density(document: &Html, config: &ExtractionConfig) -> Option<String> {
-    // Find all potential content containers
-    let candidates = find_content_candidates(document);
-    
-    // If we found candidates, return the best one
-    if !candidates.is_empty() {
-        let mut best_candidate = &candidates[0];
-        let mut best_score = score_node(best_candidate, config);
-        
-        for candidate in &candidates[1..] {
-            let score = score_node(candidate, config);
-            if score > best_score {
-                best_candidate = candidate;
-                best_score = score;
-            }
-        }
-        
-        let text = get_text_content(best_candidate, config);
-        if !text.is_empty() {
-            return Some(text);
-        }
-    }
-    
-    None
+/// Extract content based on text density, with the element it was extracted from
+fn extract_by_density<'a>(document: &'a Html, stats: &DocumentStats, config: &ExtractionConfig) -> Option<(Document, ElementRef<'a>)> {
+    // Find all potential content containers and return the best one
+    let candidates = find_content_candidates(document, stats, config);
+    let best = best_candidate(&candidates, stats, config)?;
+    
+    let content = get_content(&best, config);
+    (!content.is_empty()).then_some((content, best))
+}
+
+/// A content container with the keys it is ranked by
+struct ScoredCandidate<'a> {
+    element: ElementRef<'a>,
+    score: i32,
+    /// Number of ancestors, higher for more specific containers
+    depth: usize,
+    /// Position in document order
+    position: usize,
+}
+
+/// Rank candidates: higher score first, then the deeper (more specific) container,
+/// then the one earlier in the document. Positions are unique, so the order is total
+/// and the winner does not depend on how candidates were collected.
+fn rank_candidates(a: &ScoredCandidate, b: &ScoredCandidate) -> Ordering {
+    b.score.cmp(&a.score)
+        .then(b.depth.cmp(&a.depth))
+        .then(a.position.cmp(&b.position))
 }
 
-/// Find potential content containers in the document
-fn find_content_candidates(document: &Html) -> Vec<ElementRef> {
+/// Pick the best of candidates given in document order
+fn best_candidate<'a>(candidates: &[ElementRef<'a>], stats: &DocumentStats, config: &ExtractionConfig) -> Option<ElementRef<'a>> {
+    candidates.iter()
+        .enumerate()
+        .map(|(position, element)| ScoredCandidate {
+            element: *element,
+            score: score_node(element, stats, config),
+            depth: element.ancestors().count(),
+            position,
+        })
+        .min_by(rank_candidates)
+        .map(|candidate| candidate.element)
+}
+
+/// Find potential content containers in the document, in document order.
+/// Each element is visited once, and wrappers around a more specific candidate are dropped.
+fn find_content_candidates<'a>(document: &'a Html, stats: &DocumentStats, config: &ExtractionConfig) -> Vec<ElementRef<'a>> {
     let mut candidates = Vec::new();
     
     // Common unwanted classes to filter out
@@ -357,58 +361,167 @@ fn find_content_candidates(document: &Html) -> Vec<ElementRef> {
         "pager", "widget", "banner"
     ];
     
-    // Look for common content containers - prioritizing semantic tags first
-    for &tag in &["article", "main", "section", "div", "body"] {
-        let selector = Selector::parse(tag).unwrap();
-        for element in document.select(&selector) {
-            // Skip elements that are likely navigation or other non-content
-            if has_class_hint(&element, &unwanted_classes) || has_id_hint(&element, &unwanted_ids) {
-                continue;
-            }
-            
-            // Skip elements that have too many links (likely navigation)
-            let link_density = calculate_link_density(&element);
-            if link_density > *LINK_DENSITY_THRESHOLD {
-                continue;
-            }
-            
-            // Check paragraph count - content likely has multiple paragraphs
-            let p_selector = Selector::parse("p").unwrap();
-            let p_count = element.select(&p_selector).count();
-            
-            // Check if this element has enough text content
-            let text_content = element.text().collect::<String>();
-            let text_length = text_content.len();
-            
-            // Prioritize elements with good content indicators
-            if (text_length > 250 && p_count >= 2) || 
-               (text_length > 500) || 
-               (p_count >= 4) || 
-               has_class_hint(&element, &CONTENT_CLASSES) || 
-               has_id_hint(&element, &CONTENT_IDS) {
-                candidates.push(element);
-                
-                // For article tags, give them higher priority by adding them earlier in the list
-                if tag == "article" || tag == "main" {
-                    candidates.insert(0, element);
+    // Look for common content containers; ranking happens in `best_candidate`
+    for element in descendants_named(document.root_element(), &CANDIDATE_TAGS) {
+        // Skip elements that are likely navigation or other non-content
+        let hinted = config.uses_class_hints() && (has_class_hint(&element, &unwanted_classes) || has_id_hint(&element, &unwanted_ids));
+        if hinted || has_role(&element, &BOILERPLATE_ROLES) {
+            continue;
+        }
+        
+        // Skip elements that have too many links (likely navigation)
+        let lengths = stats.lengths.get(&element);
+        if lengths.link_density() > link_density_threshold(config) {
+            continue;
+        }
+        
+        // Check paragraph count - content likely has multiple paragraphs
+        let p_count = stats.get(&element).paragraphs;
+        
+        // Check if this element has enough text content
+        let content_length = lengths.text;
+        
+        // Keep elements with good content indicators, or at least some text
+        if (content_length > 250 && p_count >= 2) || 
+           (content_length > 500) || 
+           (p_count >= 4) || 
+           (config.uses_class_hints() && (has_class_hint(&element, &CONTENT_CLASSES) || has_id_hint(&element, &CONTENT_IDS))) ||
+           has_role(&element, &CONTENT_ROLES) ||
+           content_length > 100 {
+            candidates.push((element, content_length));
+        }
+    }
+    
+    // Prefer the most specific container: a wrapper whose text is mostly held by
+    // one nested candidate would outscore it with the same paragraphs counted twice
+    let lengths: HashMap<NodeId, usize> = candidates.iter()
+        .map(|(element, length)| (element.id(), *length))
+        .collect();
+    let mut wrappers = HashSet::new();
+    for (element, length) in &candidates {
+        for ancestor in element.ancestors() {
+            if let Some(&ancestor_length) = lengths.get(&ancestor.id()) {
+                if *length as f64 >= ancestor_length as f64 * MIN_NESTED_TEXT_SHARE {
+                    wrappers.insert(ancestor.id());
                 }
-            } else if text_length > 100 {
-                // Lower-quality candidates still get added
-                candidates.push(element);
             }
         }
     }
     
-    candidates
+    candidates.into_iter()
+        .map(|(element, _)| element)
+        .filter(|element| !wrappers.contains(&element.id()))
+        .collect()
+}
+
+/// Totals over the descendants of a node, gathered for the whole document in one
+/// bottom-up pass so nested candidates don't re-walk the same subtrees
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct SubtreeStats {
+    /// Descendant elements
+    elements: usize,
+    paragraphs: usize,
+    /// Paragraphs, headings, and list items
+    content_elements: usize,
+    /// Sum of `TAG_WEIGHTS` over the descendant elements
+    tag_weight: i32,
+    /// Whether there is an `h1`, `h2`, or `h3` below the node
+    has_heading: bool,
+}
+
+impl SubtreeStats {
+    /// Add a child element with the stats of its own subtree
+    fn add_child(&mut self, child: &ElementRef, child_stats: &SubtreeStats) {
+        let name = child.value().name();
+        self.elements += child_stats.elements + 1;
+        self.paragraphs += child_stats.paragraphs + usize::from(name == "p");
+        self.content_elements += child_stats.content_elements
+            + usize::from(matches!(name, "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "li"));
+        self.tag_weight += child_stats.tag_weight
+            + TAG_WEIGHTS.iter().find(|(tag, _)| *tag == name).map_or(0, |(_, weight)| *weight);
+        self.has_heading |= child_stats.has_heading || matches!(name, "h1" | "h2" | "h3");
+    }
+}
+
+/// Subtree stats and text lengths of every node in a document, keyed by node id
+struct DocumentStats {
+    subtrees: HashMap<NodeId, SubtreeStats>,
+    lengths: TextLengths,
+}
+
+impl DocumentStats {
+    fn collect(document: &Html) -> Self {
+        let elements: Vec<_> = document.root_element().descendants().filter_map(ElementRef::wrap).collect();
+        let mut subtrees: HashMap<NodeId, SubtreeStats> = HashMap::with_capacity(elements.len());
+
+        // Reverse document order visits children before their parents
+        for element in elements.into_iter().rev() {
+            let mut total = SubtreeStats::default();
+            for child in element.children().filter_map(ElementRef::wrap) {
+                let child_stats = subtrees.get(&child.id()).copied().unwrap_or_default();
+                total.add_child(&child, &child_stats);
+            }
+            subtrees.insert(element.id(), total);
+        }
+
+        DocumentStats { subtrees, lengths: TextLengths::new(document) }
+    }
+
+    fn get(&self, element: &ElementRef) -> SubtreeStats {
+        self.subtrees.get(&element.id()).copied().unwrap_or_default()
+    }
 }
 
 /// Score a node based on its content
-fn score_node(element: &ElementRef, _config: &ExtractionConfig) -> i32 {
+fn score_node(element: &ElementRef, document_stats: &DocumentStats, config: &ExtractionConfig) -> i32 {
+    let lengths = document_stats.lengths.get(element);
+    let stats = document_stats.get(element);
     let mut score = 0;
     
     // Score based on text length (more text = more likely to be content)
-    let text_content: String = element.text().collect();
-    score += (text_content.len() / 20) as i32; // Increased the text weight factor
+    score += (lengths.text / 20) as i32; // Increased the text weight factor
+    
+    if config.uses_class_hints() {
+        score += class_hint_score(element);
+    } else {
+        score += structure_score(element, document_stats);
+    }
+    
+    // Landmark roles hold up where class names are obfuscated
+    if has_role(element, &CONTENT_ROLES) {
+        score += 75;
+    }
+    
+    // Count paragraphs - articles typically have several paragraphs
+    score += stats.paragraphs as i32 * 10; // Each paragraph adds to the score
+    
+    // Count text-heavy elements that suggest content (paragraphs, headings, list items)
+    score += stats.content_elements as i32 * 5;
+    
+    if has_role(element, &BOILERPLATE_ROLES) {
+        score -= 50;
+    }
+    
+    // Score based on child elements' tag types
+    score += stats.tag_weight;
+    
+    // Penalize for high link density (navigation-heavy content)
+    let link_density = lengths.link_density();
+    if link_density > link_density_threshold(config) {
+        score -= (link_density * 150.0) as i32; // Increased penalty for link-heavy content
+    }
+    
+    // Bonus for elements with common article structure (heading followed by paragraphs)
+    if stats.has_heading && stats.paragraphs >= 2 {
+        score += 30; // Bonus for having a heading and multiple paragraphs
+    }
+    
+    score
+}
+
+/// Bonuses and penalties of the content and non-content class and ID hints
+fn class_hint_score(element: &ElementRef) -> i32 {
+    let mut score = 0;
     
     // Bonus for content class/id hints
     if has_class_hint(element, &CONTENT_CLASSES) {
@@ -419,16 +532,6 @@ fn score_node(element: &ElementRef, _config: &ExtractionConfig) -> i32 {
         score += 75; // Increased the bonus for content ID hints
     }
     
-    // Count paragraphs - articles typically have several paragraphs
-    let p_selector = Selector::parse("p").unwrap();
-    let p_count = element.select(&p_selector).count();
-    score += p_count as i32 * 10; // Each paragraph adds to the score
-    
-    // Count text-heavy elements that suggest content (paragraphs, headings, list items)
-    let content_elements_selector = Selector::parse("p, h1, h2, h3, h4, h5, h6, li").unwrap();
-    let content_elements_count = element.select(&content_elements_selector).count();
-    score += content_elements_count as i32 * 5;
-    
     // Penalize elements with non-content hints
     let unwanted_classes = vec![
         "nav", "navbar", "navigation", "menu", "footer", "header", "sidebar", 
@@ -446,58 +549,44 @@ fn score_node(element: &ElementRef, _config: &ExtractionConfig) -> i32 {
         score -= 50;
     }
     
-    // Score based on child elements' tag types
-    let all_selector = Selector::parse("*").unwrap();
-    for child in element.select(&all_selector) {
-        let tag_name = child.value().name();
-        
-        // Add weight based on tag
-        for &(tag, weight) in TAG_WEIGHTS.iter() {
-            if tag_name == tag {
-                score += weight;
-                break;
-            }
-        }
-    }
-    
-    // Penalize for high link density (navigation-heavy content)
-    let link_density = calculate_link_density(element);
-    if link_density > *LINK_DENSITY_THRESHOLD {
-        score -= (link_density * 150.0) as i32; // Increased penalty for link-heavy content
-    }
-    
-    // Bonus for elements with common article structure (heading followed by paragraphs)
-    let heading_selector = Selector::parse("h1, h2, h3").unwrap();
-    if element.select(&heading_selector).next().is_some() && p_count >= 2 {
-        score += 30; // Bonus for having a heading and multiple paragraphs
-    }
-    
     score
 }
 
-/// Calculate the link density of a node (text in links / total text)
-fn calculate_link_density(element: &ElementRef) -> f64 {
-    let total_text_length = element.text().collect::<String>().len();
+/// Scoring in place of the class and ID hints on pages with generated class names:
+/// a bonus for text density, and penalties for the wrappers right below the body
+/// and for series of cards, such as one card of a teaser rail or a container
+/// holding a rail of short cards
+fn structure_score(element: &ElementRef, stats: &DocumentStats) -> i32 {
+    let mut score = 0;
     
-    if total_text_length == 0 {
-        return 0.0;
-    }
+    // Characters per descendant element: running text has few tags, rails and
+    // menus have a tag every few words
+    let density = stats.lengths.get(element).text as f64 / (stats.get(element).elements + 1) as f64;
+    score += (density.min(MAX_DENSITY_BONUS_CHARS) / 4.0) as i32;
     
-    let a_selector = Selector::parse("a").unwrap();
-    let links = element.select(&a_selector);
-    let mut link_text_length = 0;
+    // The body and the wrapper right below it hold the whole layout
+    if element.ancestors().filter_map(ElementRef::wrap).count() <= 2 {
+        score -= 25;
+    }
     
-    for link in links {
-        link_text_length += link.text().collect::<String>().len();
+    if similar_siblings(element) >= MIN_REPEATED_SIBLINGS - 1 {
+        score -= 50;
+    }
+    let cards = repeated_children(element);
+    if !cards.is_empty() {
+        let card_text: usize = cards.iter().map(|card| stats.lengths.get(card).text).sum();
+        if card_text / cards.len() < MAX_CARD_TEXT_LENGTH {
+            score -= 50;
+        }
     }
     
-    link_text_length as f64 / total_text_length as f64
+    score
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use scraper::Html;
+    use scraper::{Html, Selector};
 
     #[test]
     fn test_extract_content_with_article() {
@@ -505,7 +594,7 @@ mod tests {
         let document = Html::parse_document(html);
         let config = ExtractionConfig::default();
         
-        let content = extract_content(&document, &config).unwrap();
+        let content = extract_content(&document, &config).unwrap().to_text(config.image_style);
         
         assert!(content.contains("Title"));
         assert!(content.contains("Main content paragraph"));
@@ -517,22 +606,111 @@ mod tests {
         let document = Html::parse_document(html);
         let config = ExtractionConfig::default();
         
-        let content = extract_content(&document, &config).unwrap();
+        let content = extract_content(&document, &config).unwrap().to_text(config.image_style);
         
         assert!(content.contains("Title"));
         assert!(content.contains("Main content paragraph"));
     }
 
     #[test]
-    fn test_calculate_link_density() {
+    fn test_body_last_resort() {
+        // A short notice with no article, hints or paragraph cluster
+        let html = r#"<html><body><h2>Library closed</h2><ul><li>The branch reopens on Monday.</li></ul>
+            <p>Returns can go in the box by the door.</p></body></html>"#;
+        let document = Html::parse_document(html);
+        let config = ExtractionConfig::default();
+        
+        let (content, container) = extract_content_container(&document, &config).unwrap();
+        let text = content.to_text(config.image_style);
+        
+        assert!(text.contains("Library closed"), "{}", text);
+        assert!(text.contains("The branch reopens on Monday."), "{}", text);
+        assert!(text.contains("Returns can go in the box by the door."), "{}", text);
+        assert_eq!(container.as_deref(), Some("body"));
+    }
+
+    #[test]
+    fn test_candidate_ties_break_by_depth_then_document_order() {
+        let paragraph = "<p>A paragraph of article text that is long enough to count as content.</p>";
+        let html = format!(
+            r#"<html><body><div id="first">{p}{p}{p}{p}</div><div id="second">{p}{p}{p}{p}</div></body></html>"#,
+            p = paragraph
+        );
+        let document = Html::parse_document(&html);
+        let config = ExtractionConfig::default();
+        let stats = DocumentStats::collect(&document);
+        
+        // The body outscores both; rank the two equal siblings on their own
+        let candidates: Vec<ElementRef> = find_content_candidates(&document, &stats, &config)
+            .into_iter()
+            .filter(|candidate| candidate.value().name() == "div")
+            .collect();
+        let best = best_candidate(&candidates, &stats, &config).unwrap();
+        
+        // Equal scores: the earlier sibling wins, on every run
+        assert_eq!(best.value().attr("id"), Some("first"));
+        for _ in 0..5 {
+            assert_eq!(best_candidate(&candidates, &stats, &config).unwrap().id(), best.id());
+        }
+        
+        let shallow = ScoredCandidate { element: best, score: 10, depth: 2, position: 0 };
+        let deep = ScoredCandidate { element: best, score: 10, depth: 3, position: 1 };
+        let higher = ScoredCandidate { element: best, score: 11, depth: 1, position: 2 };
+        assert_eq!(rank_candidates(&deep, &shallow), Ordering::Less);
+        assert_eq!(rank_candidates(&higher, &deep), Ordering::Less);
+        assert_eq!(rank_candidates(&shallow, &shallow), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_candidates_prefer_specific_container() {
+        let paragraph = "<p>A paragraph of article text that is long enough to count as content.</p>";
+        let html = format!(
+            r#"<html><body><div id="layout"><div id="story">{p}{p}{p}{p}{p}{p}</div>
+            <div id="rail"><p>Most read today in the magazine and around the site.</p></div></div></body></html>"#,
+            p = paragraph
+        );
+        let document = Html::parse_document(&html);
+        let config = ExtractionConfig::default();
+        let stats = DocumentStats::collect(&document);
+        
+        let candidates = find_content_candidates(&document, &stats, &config);
+        let ids: Vec<_> = candidates.iter().filter_map(|candidate| candidate.value().attr("id")).collect();
+        
+        // The layout wrapper and the body are mostly the story, and give way to it
+        assert_eq!(ids, vec!["story"]);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(best_candidate(&candidates, &stats, &config).unwrap().value().attr("id"), Some("story"));
+    }
+
+    #[test]
+    fn test_link_density() {
         let html = "<div>This is a <a href=\"#\">link</a> in some text.</div>";
         let document = Html::parse_document(html);
         
         let div_selector = Selector::parse("div").unwrap();
         let div = document.select(&div_selector).next().unwrap();
-        let density = calculate_link_density(&div);
+        let density = TextLengths::new(&document).get(&div).link_density();
         
         // Link text "link" is 4 chars, total text is "This is a link in some text." (27 chars)
         assert!((density - 4.0/27.0).abs() < 0.01);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_subtree_stats() {
+        let html = r#"<html><body><div id="story"><h2>Heading</h2><p>Some text with <a href="/x">a link</a>.</p><ul><li>Item</li></ul></div></body></html>"#;
+        let document = Html::parse_document(html);
+        let stats = DocumentStats::collect(&document);
+        
+        let story = document.select(&Selector::parse("#story").unwrap()).next().unwrap();
+        let story_stats = stats.get(&story);
+        assert_eq!(story_stats.paragraphs, 1);
+        assert_eq!(story_stats.content_elements, 3);
+        assert_eq!(story_stats.tag_weight, 8 + 15 - 5 + 1);
+        assert!(story_stats.has_heading);
+        assert_eq!(stats.lengths.get(&story).link_text, 6);
+        
+        // The body totals include everything below the story
+        let body = document.select(&Selector::parse("body").unwrap()).next().unwrap();
+        assert_eq!(stats.get(&body).tag_weight, story_stats.tag_weight + 5);
+    }
+}
@@ -0,0 +1,177 @@
+//! Web feed handling for Trafilatura Rust port.
+//! This module parses RSS and Atom feeds and discovers feeds linked from HTML
+//! pages, so the articles of a feed can be listed or extracted in one run.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use scraper::Html;
+use url::Url;
+
+use crate::utils::try_selector;
+use crate::fetch::Fetcher;
+use crate::{fetch_url_with, TrafilaturaError};
+
+lazy_static! {
+    static ref RSS_ITEM: Regex = Regex::new(r"(?is)<item[\s>](.*?)</item>").unwrap();
+    static ref ATOM_ENTRY: Regex = Regex::new(r"(?is)<entry[\s>](.*?)</entry>").unwrap();
+    static ref RSS_LINK: Regex = Regex::new(r"(?is)<link>\s*(?:<!\[CDATA\[)?\s*(.*?)\s*(?:\]\]>)?\s*</link>").unwrap();
+    static ref ATOM_LINK: Regex = Regex::new(r"(?is)<link\s([^>]*)/?>").unwrap();
+    static ref HREF: Regex = Regex::new(r#"(?is)href\s*=\s*["']([^"']*)["']"#).unwrap();
+    static ref REL: Regex = Regex::new(r#"(?is)rel\s*=\s*["']([^"']*)["']"#).unwrap();
+    static ref TITLE: Regex = Regex::new(r"(?is)<title[^>]*>\s*(?:<!\[CDATA\[)?\s*(.*?)\s*(?:\]\]>)?\s*</title>").unwrap();
+    static ref PUBLISHED: Regex = Regex::new(
+        r"(?is)<(pubDate|published|updated|dc:date)>\s*(.*?)\s*</(?:pubDate|published|updated|dc:date)>"
+    ).unwrap();
+}
+
+/// An article listed in a feed
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedEntry {
+    /// Article URL
+    pub url: String,
+    /// Article title
+    pub title: Option<String>,
+    /// Publication date as declared in the feed
+    pub published: Option<String>,
+}
+
+/// Check if a document looks like an RSS or Atom feed
+pub fn is_feed(content: &str) -> bool {
+    let head: String = content.chars().take(1000).collect::<String>().to_lowercase();
+    head.contains("<rss") || head.contains("<feed") || head.contains("<rdf:rdf")
+}
+
+/// Parse the entries of an RSS or Atom feed
+pub fn parse_feed(content: &str, base: &Url) -> Vec<FeedEntry> {
+    let mut entries = Vec::new();
+
+    for item in RSS_ITEM.captures_iter(content) {
+        let item = &item[1];
+        let link = RSS_LINK.captures(item).map(|link| link[1].to_string());
+        if let Some(url) = link.and_then(|link| resolve(&link, base)) {
+            entries.push(entry(url, item));
+        }
+    }
+
+    for item in ATOM_ENTRY.captures_iter(content) {
+        let item = &item[1];
+        if let Some(url) = atom_link(item).and_then(|link| resolve(&link, base)) {
+            entries.push(entry(url, item));
+        }
+    }
+
+    entries
+}
+
+/// Find the URLs of feeds declared with `<link rel="alternate">` in an HTML page
+pub fn discover_feeds(html: &str, base: &Url) -> Vec<String> {
+    let document = Html::parse_document(html);
+    let Some(selector) = try_selector(
+        "link[rel~='alternate'][type='application/rss+xml'], link[rel~='alternate'][type='application/atom+xml']"
+    ) else {
+        return Vec::new();
+    };
+
+    document.select(&selector)
+        .filter_map(|link| link.value().attr("href"))
+        .filter_map(|href| resolve(href, base))
+        .collect()
+}
+
+/// Fetch a feed, or the first feed declared by an HTML page, and return its entries
+pub fn fetch_feed_entries(url: &str, fetcher: &dyn Fetcher) -> Result<Vec<FeedEntry>, TrafilaturaError> {
+    let mut base = Url::parse(url)?;
+    let mut content = fetch_url_with(base.as_str(), fetcher)?;
+
+    if !is_feed(&content) {
+        let feed_url = discover_feeds(&content, &base).into_iter().next().ok_or_else(|| {
+            TrafilaturaError::ExtractionError(format!("No feed found at {}", url))
+        })?;
+        base = Url::parse(&feed_url)?;
+        content = fetch_url_with(&feed_url, fetcher)?;
+    }
+
+    Ok(parse_feed(&content, &base))
+}
+
+fn entry(url: String, item: &str) -> FeedEntry {
+    FeedEntry {
+        url,
+        title: TITLE.captures(item)
+            .map(|title| html_escape::decode_html_entities(&title[1]).trim().to_string())
+            .filter(|title| !title.is_empty()),
+        published: PUBLISHED.captures(item).map(|published| published[2].to_string()),
+    }
+}
+
+/// Pick the `alternate` (or untyped) link of an Atom entry
+fn atom_link(item: &str) -> Option<String> {
+    ATOM_LINK.captures_iter(item)
+        .map(|link| link[1].to_string())
+        .find(|attributes| REL.captures(attributes).is_none_or(|rel| &rel[1] == "alternate"))
+        .and_then(|attributes| HREF.captures(&attributes).map(|href| href[1].to_string()))
+}
+
+/// Resolve a feed link against the feed URL, keeping only HTTP(S) URLs
+fn resolve(link: &str, base: &Url) -> Option<String> {
+    let link = html_escape::decode_html_entities(link.trim());
+    if link.is_empty() {
+        return None;
+    }
+
+    let url = base.join(&link).ok()?;
+    matches!(url.scheme(), "http" | "https").then(|| url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> Url {
+        Url::parse("https://example.com/feed").unwrap()
+    }
+
+    #[test]
+    fn test_parse_rss() {
+        let rss = r#"<?xml version="1.0"?><rss version="2.0"><channel><title>Blog</title>
+            <item><title><![CDATA[First &amp; best]]></title><link>https://example.com/first</link>
+            <pubDate>Mon, 06 May 2024 10:00:00 GMT</pubDate></item>
+            <item><title>Second</title><link>/second</link></item>
+            </channel></rss>"#;
+
+        let entries = parse_feed(rss, &base());
+
+        assert!(is_feed(rss));
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].title.as_deref(), Some("First & best"));
+        assert_eq!(entries[0].published.as_deref(), Some("Mon, 06 May 2024 10:00:00 GMT"));
+        assert_eq!(entries[1].url, "https://example.com/second");
+    }
+
+    #[test]
+    fn test_parse_atom() {
+        let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>Blog</title>
+            <entry><title>Post</title><link rel="edit" href="/edit/1"/>
+            <link rel="alternate" href="https://example.com/post"/><updated>2024-05-06T10:00:00Z</updated></entry>
+            </feed>"#;
+
+        let entries = parse_feed(atom, &base());
+
+        assert_eq!(entries, vec![FeedEntry {
+            url: "https://example.com/post".into(),
+            title: Some("Post".into()),
+            published: Some("2024-05-06T10:00:00Z".into()),
+        }]);
+    }
+
+    #[test]
+    fn test_discover_feeds() {
+        let html = r#"<html><head>
+            <link rel="alternate" type="application/rss+xml" href="/rss.xml">
+            <link rel="alternate" hreflang="de" href="/de/">
+            </head><body></body></html>"#;
+
+        assert_eq!(discover_feeds(html, &base()), vec!["https://example.com/rss.xml"]);
+        assert!(!is_feed(html));
+    }
+}
@@ -0,0 +1,760 @@
+//! HTTP fetching for Trafilatura Rust port.
+//! This module defines the `Fetcher` trait used for every download, so library
+//! users can plug in their own client (custom TLS, internal proxies, test mocks)
+//! in place of the default reqwest implementation, which requires the `http` feature.
+
+#[cfg(feature = "http")]
+use std::fmt;
+#[cfg(feature = "http")]
+use std::fs;
+#[cfg(feature = "http")]
+use std::io::Read;
+#[cfg(feature = "http")]
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
+#[cfg(feature = "http")]
+use std::sync::Arc;
+#[cfg(feature = "http")]
+use std::time::Duration;
+
+#[cfg(feature = "http")]
+use flate2::read::{MultiGzDecoder, ZlibDecoder};
+#[cfg(feature = "http")]
+use reqwest::blocking::{Client, ClientBuilder, Response};
+#[cfg(feature = "http")]
+use encoding_rs::{Encoding, UTF_8};
+#[cfg(feature = "http")]
+use reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE, LOCATION, USER_AGENT};
+#[cfg(feature = "http")]
+use reqwest::{redirect, tls, Certificate, Proxy};
+#[cfg(feature = "http")]
+use tracing::warn;
+#[cfg(feature = "http")]
+use url::Host;
+use url::Url;
+
+#[cfg(feature = "http")]
+use crate::dns::{IpFamily, Resolver, SystemResolver};
+#[cfg(feature = "http")]
+use crate::politeness::PolitenessGate;
+#[cfg(feature = "http")]
+use crate::proxy::ProxyPool;
+#[cfg(feature = "http")]
+use crate::ExtractionConfig;
+use crate::utils::char_chunks;
+use crate::TrafilaturaError;
+
+/// A downloaded page
+#[derive(Debug, Clone, PartialEq)]
+pub struct FetchedPage {
+    /// Final URL of the page, after redirects
+    pub url: String,
+    /// HTTP status code
+    pub status: u16,
+    /// Decoded response body
+    pub body: String,
+}
+
+impl FetchedPage {
+    /// Check if the page was served with a 2xx status
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Return the body of a successful page, or a `StatusError` otherwise
+    pub fn into_body(self) -> Result<String, TrafilaturaError> {
+        if self.is_success() {
+            Ok(self.body)
+        } else {
+            Err(TrafilaturaError::StatusError(self.status, self.url))
+        }
+    }
+}
+
+/// Maximum number of redirects followed by hand when a DNS policy checks their
+/// targets, the same limit as reqwest's default policy
+#[cfg(feature = "http")]
+const MAX_REDIRECTS: usize = 10;
+
+/// Number of leading bytes inspected when checking a body for binary content
+const SNIFF_LENGTH: usize = 1024;
+
+/// Share of control bytes above which a body is treated as binary
+const MAX_CONTROL_BYTE_RATIO: f64 = 0.05;
+
+/// Length of the body chunks `Fetcher::fetch_prefix` reads at a time
+const PREFIX_CHUNK_LENGTH: usize = 8 * 1024;
+
+/// Minimum TLS protocol version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TlsVersion {
+    Tls1_2,
+    Tls1_3,
+}
+
+/// TLS settings of the default HTTP client
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TlsOptions {
+    /// PEM file of root certificates trusted in addition to the built-in ones,
+    /// e.g. the private CA of an internal proxy
+    pub ca_bundle: Option<PathBuf>,
+    /// Accept invalid and self-signed certificates. Only for trusted internal mirrors:
+    /// this disables all protection against impersonation.
+    pub danger_accept_invalid_certs: bool,
+    /// Refuse connections below this protocol version
+    pub min_version: Option<TlsVersion>,
+}
+
+#[cfg(feature = "http")]
+impl TlsOptions {
+    /// Read the root certificates of `ca_bundle`, if any
+    fn load_certificates(&self) -> Result<Vec<Certificate>, TrafilaturaError> {
+        let Some(path) = &self.ca_bundle else {
+            return Ok(Vec::new());
+        };
+        let certificates = Certificate::from_pem_bundle(&fs::read(path)?)?;
+        if certificates.is_empty() {
+            return Err(TrafilaturaError::ExtractionError(format!("No certificates found in {}", path.display())));
+        }
+        Ok(certificates)
+    }
+
+    /// Apply the settings to a reqwest client builder, with the certificates read
+    /// by `load_certificates`
+    fn configure(&self, mut builder: ClientBuilder, certificates: &[Certificate]) -> ClientBuilder {
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate.clone());
+        }
+
+        if self.danger_accept_invalid_certs {
+            warn!("TLS certificate validation is disabled");
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+
+        if let Some(version) = self.min_version {
+            builder = builder.min_tls_version(match version {
+                TlsVersion::Tls1_2 => tls::Version::TLS_1_2,
+                TlsVersion::Tls1_3 => tls::Version::TLS_1_3,
+            });
+        }
+
+        builder
+    }
+}
+
+/// Compression format of a body, told by its first bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compression {
+    Gzip,
+    Zlib,
+    Zstd,
+}
+
+/// Recognize the start of a gzip, zlib, or zstd stream. Text can start like a
+/// zlib header (`x^` does), so a zlib stream is only trusted once it inflates
+/// whole with a valid checksum.
+fn detect_compression(body: &[u8]) -> Option<Compression> {
+    match body {
+        [0x1f, 0x8b, ..] => Some(Compression::Gzip),
+        [0x28, 0xb5, 0x2f, 0xfd, ..] => Some(Compression::Zstd),
+        [cmf, flg, ..] if is_zlib_header(*cmf, *flg) => Some(Compression::Zlib),
+        _ => None,
+    }
+}
+
+/// Check for a zlib header: deflate with a 32K window (`0x78`), a check value
+/// making the pair a multiple of 31, and no preset dictionary, which HTTP
+/// deflate streams never use
+fn is_zlib_header(cmf: u8, flg: u8) -> bool {
+    cmf == 0x78 && (u16::from(cmf) << 8 | u16::from(flg)) % 31 == 0 && flg & 0x20 == 0
+}
+
+/// Decompress a body that is still a gzip, zlib, or zstd stream because the
+/// server left out or mislabeled its `Content-Encoding`. Returns `None` for other
+/// bodies and streams that do not decode. What a truncated gzip stream, or the
+/// whole blocks of a truncated zstd stream, decode to is kept; zlib streams must
+/// be whole, as text may look like their header.
+///
+/// Fails once the output exceeds `max_size` bytes, so a small compression bomb
+/// cannot exhaust the memory.
+#[cfg(feature = "http")]
+pub fn decompress_body(body: &[u8], max_size: usize) -> Result<Option<Vec<u8>>, BodyTooLarge> {
+    let Some(compression) = detect_compression(body) else {
+        return Ok(None);
+    };
+    // One byte past the limit tells a body of exactly `max_size` from a larger one
+    let limit = max_size as u64 + 1;
+    let mut decoded = Vec::new();
+    let decoded = match compression {
+        Compression::Gzip => {
+            let result = MultiGzDecoder::new(body).take(limit).read_to_end(&mut decoded);
+            (result.is_ok() || !decoded.is_empty()).then_some(decoded)
+        },
+        Compression::Zstd => {
+            let result = zstd::stream::read::Decoder::new(body).and_then(|decoder| decoder.take(limit).read_to_end(&mut decoded));
+            (result.is_ok() || !decoded.is_empty()).then_some(decoded)
+        },
+        Compression::Zlib => ZlibDecoder::new(body).take(limit).read_to_end(&mut decoded).ok().map(|_| decoded),
+    };
+    match decoded {
+        Some(decoded) if decoded.len() > max_size => Err(BodyTooLarge(max_size)),
+        decoded => Ok(decoded),
+    }
+}
+
+/// A body larger than the given number of bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BodyTooLarge(pub usize);
+
+impl std::fmt::Display for BodyTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "body larger than {} bytes", self.0)
+    }
+}
+
+
+/// Encoding of a body: that of its byte order mark, else the declared `charset`,
+/// else that of a `<meta>` charset declaration near the start of the body, else UTF-8
+#[cfg(feature = "http")]
+pub fn sniff_encoding(body: &[u8], charset: Option<&str>) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(body) {
+        return encoding;
+    }
+    if let Some(encoding) = charset.and_then(|charset| Encoding::for_label(charset.as_bytes())) {
+        return encoding;
+    }
+
+    // `<meta charset="...">` or `<meta http-equiv="Content-Type" content="text/html; charset=...">`;
+    // ASCII-compatible encodings only, as HTML parsers do
+    let head = String::from_utf8_lossy(&body[..body.len().min(SNIFF_LENGTH)]).to_ascii_lowercase();
+    head.match_indices("<meta")
+        .filter_map(|(start, _)| {
+            let tag = &head[start..head[start..].find('>').map_or(head.len(), |end| start + end)];
+            let label = tag.split_once("charset=")?.1.trim_start_matches(['"', '\'', ' ']);
+            let label = &label[..label.find(|c: char| !(c.is_ascii_alphanumeric() || "-_:.".contains(c))).unwrap_or(label.len())];
+            Encoding::for_label(label.as_bytes())
+        })
+        .map(|encoding| if encoding == encoding_rs::UTF_16LE || encoding == encoding_rs::UTF_16BE { UTF_8 } else { encoding })
+        .next()
+        .unwrap_or(UTF_8)
+}
+
+/// Recognize response bodies that are not text: compressed streams served with
+/// a missing or wrong `Content-Encoding`, or other binary data. `charset` is the
+/// declared character set of the body, if any. Returns a description of the
+/// content, or `None` for text.
+pub fn sniff_binary_body(body: &[u8], charset: Option<&str>) -> Option<&'static str> {
+    let compression = detect_compression(body);
+    match compression {
+        Some(Compression::Gzip) => return Some("gzip data"),
+        Some(Compression::Zstd) => return Some("zstd data"),
+        Some(Compression::Zlib) | None => {},
+    }
+
+    // UTF-16 text is half zero bytes
+    let utf16_bom = body.starts_with(&[0xff, 0xfe]) || body.starts_with(&[0xfe, 0xff]);
+    if utf16_bom || charset.is_some_and(|charset| charset.to_ascii_lowercase().starts_with("utf-16")) {
+        return None;
+    }
+
+    // Brotli has no magic number, but like any binary data it is full of control bytes
+    let head = &body[..body.len().min(SNIFF_LENGTH)];
+    let control = head.iter()
+        .filter(|byte| **byte < 0x20 && !matches!(byte, b'\t' | b'\n' | b'\r' | 0x0c))
+        .count();
+    let binary = !head.is_empty() && control as f64 / head.len() as f64 > MAX_CONTROL_BYTE_RATIO;
+    // A zlib header followed by text is text
+    binary.then_some(if compression == Some(Compression::Zlib) { "zlib data" } else { "binary data" })
+}
+
+/// Downloads pages for extraction, sitemaps, feeds, and crawls.
+///
+/// Implementations report transport failures as errors and return pages with
+/// any HTTP status, leaving status handling to the caller.
+pub trait Fetcher {
+    /// Download a URL
+    fn fetch(&self, url: &Url) -> Result<FetchedPage, TrafilaturaError>;
+
+    /// Download the start of a URL, passing the decoded body to `wanted` chunk by
+    /// chunk until it returns `false`, and return the page with the body read so far.
+    /// The default downloads the whole page before passing it on.
+    fn fetch_prefix(&self, url: &Url, wanted: &mut dyn FnMut(&str) -> bool) -> Result<FetchedPage, TrafilaturaError> {
+        let page = self.fetch(url)?;
+        for chunk in char_chunks(&page.body, PREFIX_CHUNK_LENGTH) {
+            if !wanted(chunk) {
+                break;
+            }
+        }
+        Ok(page)
+    }
+}
+
+/// Default `Fetcher` backed by a blocking reqwest client, following the
+/// politeness rules, proxy rotation, and DNS policy of the config. Clones share
+/// the delay, concurrency, and proxy failure bookkeeping.
+#[cfg(feature = "http")]
+#[derive(Clone)]
+pub struct HttpFetcher {
+    client: Client,
+    /// One client per proxy, as reqwest fixes proxies when building a client
+    proxy_clients: Vec<Client>,
+    proxy_pool: Arc<ProxyPool>,
+    gate: Arc<PolitenessGate>,
+    resolver: Arc<dyn Resolver>,
+    /// Root certificates of the CA bundle, read once for all clients
+    certificates: Arc<Vec<Certificate>>,
+    config: Arc<ExtractionConfig>,
+}
+
+#[cfg(feature = "http")]
+impl HttpFetcher {
+    /// Build a fetcher using the timeout, user agent, TLS, proxy, and DNS settings of `config`
+    pub fn new(config: &ExtractionConfig) -> Result<Self, TrafilaturaError> {
+        Self::with_resolver(config, Arc::new(SystemResolver))
+    }
+
+    /// Build a fetcher that looks up host names with a custom resolver. The resolver
+    /// is consulted when a DNS policy is configured (`ExtractionConfig::dns`).
+    pub fn with_resolver(config: &ExtractionConfig, resolver: Arc<dyn Resolver>) -> Result<Self, TrafilaturaError> {
+        let certificates = config.tls.load_certificates()?;
+        let client = client_builder(config, &certificates).build()?;
+        let proxy_clients = config.proxies.proxies.iter()
+            .map(|proxy| Ok(client_builder(config, &certificates).proxy(Proxy::all(proxy)?).build()?))
+            .collect::<Result<Vec<_>, TrafilaturaError>>()?;
+
+        Ok(Self {
+            client,
+            proxy_clients,
+            proxy_pool: Arc::new(ProxyPool::new(&config.proxies)),
+            gate: Arc::default(),
+            resolver,
+            certificates: Arc::new(certificates),
+            config: Arc::new(config.clone()),
+        })
+    }
+
+    /// The delay and concurrency bookkeeping of the politeness rules, shared by clones
+    pub fn politeness_gate(&self) -> Arc<PolitenessGate> {
+        self.gate.clone()
+    }
+
+    /// The client of the next proxy and its index, or the direct client without proxies
+    fn next_client(&self) -> Result<(&Client, Option<usize>), TrafilaturaError> {
+        if self.proxy_clients.is_empty() {
+            return Ok((&self.client, None));
+        }
+
+        let index = self.proxy_pool.next()
+            .ok_or_else(|| TrafilaturaError::ExtractionError("All proxies were evicted after repeated failures".into()))?;
+        Ok((&self.proxy_clients[index], Some(index)))
+    }
+}
+
+#[cfg(feature = "http")]
+impl fmt::Debug for HttpFetcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpFetcher")
+            .field("proxy_pool", &self.proxy_pool)
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Client builder with the timeout, user agent, TLS, and DNS settings of `config`
+#[cfg(feature = "http")]
+fn client_builder(config: &ExtractionConfig, certificates: &[Certificate]) -> ClientBuilder {
+    let mut builder = Client::builder()
+        .timeout(Duration::from_secs(config.extraction_timeout))
+        .user_agent(&config.user_agent);
+
+    // Binding to the unspecified address of a family restricts connections to it
+    match config.dns.ip_family {
+        IpFamily::Any => {},
+        IpFamily::V4 => builder = builder.local_address(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+        IpFamily::V6 => builder = builder.local_address(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+    }
+
+    // Redirects are followed by `fetch_with`, which checks and pins each target
+    // like the requested URL; reqwest would resolve them again on its own
+    if config.dns.is_active() {
+        builder = builder.redirect(redirect::Policy::none());
+    }
+
+    config.tls.configure(builder, certificates)
+}
+
+#[cfg(feature = "http")]
+impl Fetcher for HttpFetcher {
+    fn fetch(&self, url: &Url) -> Result<FetchedPage, TrafilaturaError> {
+        self.fetch_with(url, |response, _| {
+            let mut bytes = Vec::new();
+            response.read_to_end(&mut bytes)?;
+            Ok(bytes)
+        })
+    }
+
+    fn fetch_prefix(&self, url: &Url, wanted: &mut dyn FnMut(&str) -> bool) -> Result<FetchedPage, TrafilaturaError> {
+        self.fetch_with(url, |response, charset| {
+            // The first chunk tells if the body is still compressed, and which
+            // encoding it is in
+            let mut bytes = Vec::new();
+            response.take(PREFIX_CHUNK_LENGTH as u64).read_to_end(&mut bytes)?;
+            if detect_compression(&bytes).is_some() {
+                // A body left compressed is only text once decompressed whole
+                response.read_to_end(&mut bytes)?;
+                let Ok(decoded) = decompress_body(&bytes, self.config.max_body_size) else {
+                    // Refused by `fetch_with`
+                    return Ok(bytes);
+                };
+                let bytes = decoded.unwrap_or(bytes);
+                if sniff_binary_body(&bytes, charset).is_none() {
+                    let (text, _, _) = sniff_encoding(&bytes, charset).decode(&bytes);
+                    for chunk in char_chunks(&text, PREFIX_CHUNK_LENGTH) {
+                        if !wanted(chunk) {
+                            break;
+                        }
+                    }
+                }
+                return Ok(bytes);
+            }
+            if sniff_binary_body(&bytes, charset).is_some() {
+                return Ok(bytes);
+            }
+
+            // Dropping the response unread closes the connection
+            let mut decoder = sniff_encoding(&bytes, charset).new_decoder();
+            let mut chunk = bytes.clone();
+            loop {
+                let last = chunk.is_empty();
+                // The buffer has room for the whole chunk, so decoding never stops early
+                let mut text = String::with_capacity(decoder.max_utf8_buffer_length(chunk.len()).unwrap_or(chunk.len() * 3));
+                let _ = decoder.decode_to_string(&chunk, &mut text, last);
+                if last || !wanted(&text) {
+                    return Ok(bytes);
+                }
+                chunk.resize(PREFIX_CHUNK_LENGTH, 0);
+                let read = response.read(&mut chunk)?;
+                chunk.truncate(read);
+                bytes.extend_from_slice(&chunk);
+            }
+        })
+    }
+}
+
+#[cfg(feature = "http")]
+impl HttpFetcher {
+    /// Send a request for a URL, and decode the body that `read_body` reads from
+    /// the response, given the charset of its `Content-Type`. Bodies larger than
+    /// `max_body_size` are refused, before and after decompression.
+    fn fetch_with(
+        &self,
+        url: &Url,
+        read_body: impl FnOnce(&mut dyn Read, Option<&str>) -> Result<Vec<u8>, TrafilaturaError>,
+    ) -> Result<FetchedPage, TrafilaturaError> {
+        let (client, proxy) = self.next_client()?;
+
+        // Under a DNS policy, redirects are followed here, so that every target is
+        // checked and connected to like the requested URL
+        let mut url = url.clone();
+        let mut redirects = 0;
+        let response = loop {
+            let response = self.send(client, proxy, &url)?;
+            let location = response.headers().get(LOCATION)
+                .and_then(|location| location.to_str().ok())
+                .and_then(|location| url.join(location).ok());
+            match location {
+                Some(location) if self.config.dns.is_active() && response.status().is_redirection() => {
+                    if redirects == MAX_REDIRECTS {
+                        return Err(TrafilaturaError::ExtractionError(format!("Too many redirects from {}", url)));
+                    }
+                    redirects += 1;
+                    url = location;
+                },
+                _ => break response,
+            }
+        };
+        let url = response.url().to_string();
+        let status = response.status().as_u16();
+        let header = |name| response.headers().get(name).and_then(|value| value.to_str().ok()).map(str::to_lowercase);
+        let content_encoding = header(CONTENT_ENCODING);
+        let charset = header(CONTENT_TYPE)
+            .and_then(|content_type| content_type.split_once("charset=").map(|(_, charset)| charset.trim_matches(['"', ' ', ';']).to_string()));
+
+        // Servers mislabeling the encoding, or sending zstd, which reqwest does not
+        // decode, leave compressed bytes that would decode to garbage. What still is
+        // not text after decompression is refused.
+        let max_size = self.config.max_body_size;
+        let bytes = read_body(&mut response.take(max_size as u64 + 1), charset.as_deref())?;
+        if bytes.len() > max_size {
+            return Err(TrafilaturaError::BodyError(url, BodyTooLarge(max_size).to_string()));
+        }
+        let bytes = match decompress_body(&bytes, max_size) {
+            Ok(decoded) => decoded.unwrap_or(bytes),
+            Err(too_large) => return Err(TrafilaturaError::BodyError(url, too_large.to_string())),
+        };
+        if let Some(kind) = sniff_binary_body(&bytes, charset.as_deref()) {
+            let label = content_encoding.map(|encoding| format!(" (Content-Encoding: {})", encoding)).unwrap_or_default();
+            return Err(TrafilaturaError::BodyError(url, format!("{}{}", kind, label)));
+        }
+
+        let (body, _, _) = sniff_encoding(&bytes, charset.as_deref()).decode(&bytes);
+        Ok(FetchedPage { url, status, body: body.into_owned() })
+    }
+
+    /// Send one request for a URL, without following redirects under a DNS policy
+    fn send(&self, client: &Client, proxy: Option<usize>, url: &Url) -> Result<Response, TrafilaturaError> {
+        let host = url.host_str().unwrap_or_default();
+
+        // Check the addresses of the host, and connect to exactly those unless a
+        // proxy resolves the host instead
+        let pinned;
+        let mut client = client;
+        if self.config.dns.is_active() {
+            let port = url.port_or_known_default().unwrap_or(80);
+            let addresses = self.config.dns.resolve(host, port, self.resolver.as_ref())?;
+            if let (Some(Host::Domain(domain)), None) = (url.host(), proxy) {
+                pinned = client_builder(&self.config, &self.certificates)
+                    .resolve_to_addrs(domain, &addresses)
+                    .build()?;
+                client = &pinned;
+            }
+        }
+
+        // reqwest asks for and decodes gzip, deflate, and brotli bodies
+        let mut request = client.get(url.clone());
+        let policy = self.config.politeness.for_host(host);
+        if let Some(policy) = policy {
+            if let Some(user_agent) = &policy.user_agent {
+                request = request.header(USER_AGENT, user_agent);
+            }
+            for (name, value) in &policy.headers {
+                request = request.header(name, value);
+            }
+        }
+
+        let _permit = policy.map(|policy| self.gate.acquire(host, policy));
+        let response = request.send();
+        if let Some(index) = proxy {
+            self.proxy_pool.report(index, response.is_ok());
+        }
+        Ok(response?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fetched_page_status() {
+        let page = FetchedPage { url: "https://example.com/".into(), status: 404, body: String::new() };
+
+        assert!(!page.is_success());
+        assert!(matches!(page.into_body(), Err(TrafilaturaError::StatusError(404, _))));
+    }
+
+    const PAGE: &str = "<html><head><title>Tide tables</title></head><body><p>High water at 06:12 and 18:40.</p></body></html>";
+
+    #[test]
+    fn test_sniff_binary_body() {
+        assert_eq!(sniff_binary_body(b"<html><body>\tText\r\n</body></html>", None), None);
+        assert_eq!(sniff_binary_body("<p>Unicode text: é ü 中文</p>".as_bytes(), None), None);
+        assert_eq!(sniff_binary_body(b"", None), None);
+
+        assert_eq!(sniff_binary_body(&[0x1f, 0x8b, 0x08, 0x00], None), Some("gzip data"));
+        assert_eq!(sniff_binary_body(&[0x28, 0xb5, 0x2f, 0xfd, 0x00], None), Some("zstd data"));
+        assert_eq!(sniff_binary_body(&[0x78, 0x9c, 0x4b, 0x4c, 0x01, 0x00, 0x02, 0x0e], None), Some("zlib data"));
+        // Brotli stream of a short HTML page
+        let brotli = [0x1b, 0x2c, 0x00, 0xf8, 0x05, 0x02, 0x00, 0x03, 0x8c, 0x94, 0x01, 0x00];
+        assert_eq!(sniff_binary_body(&brotli, None), Some("binary data"));
+
+        // Text starting like a zlib header, with or without the preset dictionary flag
+        for text in ["x marks the spot", "x? Unknown", "x^2 + y^2", "x} end"] {
+            assert_eq!(sniff_binary_body(text.as_bytes(), None), None, "{}", text);
+        }
+
+        // UTF-16 text, with a byte order mark or a declared charset
+        let utf16: Vec<u8> = PAGE.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        assert_eq!(sniff_binary_body(&utf16, None), Some("binary data"));
+        assert_eq!(sniff_binary_body(&utf16, Some("UTF-16LE")), None);
+        assert_eq!(sniff_binary_body(&[[0xff, 0xfe].as_slice(), &utf16].concat(), None), None);
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_decompress_body() {
+        use std::io::Write;
+        use flate2::write::{GzEncoder, ZlibEncoder};
+        use flate2::Compression as Level;
+
+        let mut gzip = GzEncoder::new(Vec::new(), Level::default());
+        gzip.write_all(PAGE.as_bytes()).unwrap();
+        let gzip = gzip.finish().unwrap();
+        let mut zlib = ZlibEncoder::new(Vec::new(), Level::best());
+        zlib.write_all(PAGE.as_bytes()).unwrap();
+        let zlib = zlib.finish().unwrap();
+        let zstd = zstd::encode_all(PAGE.as_bytes(), 3).unwrap();
+
+        const MAX: usize = 1 << 20;
+        for (name, body) in [("gzip", &gzip), ("zlib", &zlib), ("zstd", &zstd)] {
+            assert_eq!(decompress_body(body, MAX).unwrap().as_deref(), Some(PAGE.as_bytes()), "{}", name);
+            // Output past the limit is refused, whatever the compression
+            assert_eq!(decompress_body(body, PAGE.len() - 1), Err(BodyTooLarge(PAGE.len() - 1)), "{}", name);
+            assert!(decompress_body(body, PAGE.len()).unwrap().is_some(), "{}", name);
+        }
+        // The start of a gzip stream decodes to the start of the page
+        let tides: String = (1..=500).map(|day| format!("<p>Day {}: high water at {:02}:{:02}.</p>", day, day % 24, day * 7 % 60)).collect();
+        let mut gzip = GzEncoder::new(Vec::new(), Level::default());
+        gzip.write_all(tides.as_bytes()).unwrap();
+        let gzip = gzip.finish().unwrap();
+        let start = decompress_body(&gzip[..gzip.len() * 2 / 3], MAX).unwrap().unwrap();
+        assert!(!start.is_empty() && tides.as_bytes().starts_with(&start));
+
+        // A bomb of concatenated gzip members stops at the limit
+        let mut bomb = GzEncoder::new(Vec::new(), Level::best());
+        bomb.write_all(&vec![b' '; 4 << 20]).unwrap();
+        let bomb = bomb.finish().unwrap().repeat(4);
+        assert!(bomb.len() < 64 << 10);
+        assert_eq!(decompress_body(&bomb, MAX), Err(BodyTooLarge(MAX)));
+
+        assert_eq!(decompress_body(PAGE.as_bytes(), MAX), Ok(None));
+        assert_eq!(decompress_body(b"x^2 + y^2", MAX), Ok(None));
+        assert_eq!(decompress_body(&[0x1f, 0x8b, 0xff, 0xff, 0xff], MAX), Ok(None));
+    }
+
+    /// Serve `body` once with a `Content-Encoding` header on a local port, returning its URL
+    #[cfg(feature = "http")]
+    fn serve_once(body: Vec<u8>, content_encoding: &'static str) -> Url {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", server.server_addr().to_ip().unwrap())).unwrap();
+        std::thread::spawn(move || {
+            let request = server.recv().unwrap();
+            let header = tiny_http::Header::from_bytes("Content-Encoding", content_encoding).unwrap();
+            let _ = request.respond(tiny_http::Response::from_data(body).with_header(header));
+        });
+        url
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_fetch_compressed_bodies() {
+        use std::io::Write;
+        use flate2::write::{GzEncoder, ZlibEncoder};
+        use flate2::Compression as Level;
+
+        let mut gzip = GzEncoder::new(Vec::new(), Level::default());
+        gzip.write_all(PAGE.as_bytes()).unwrap();
+        let gzip = gzip.finish().unwrap();
+        let gzip_body = gzip.clone();
+        let mut deflate = ZlibEncoder::new(Vec::new(), Level::default());
+        deflate.write_all(PAGE.as_bytes()).unwrap();
+        let deflate = deflate.finish().unwrap();
+        let mut brotli = Vec::new();
+        brotli::BrotliCompress(&mut PAGE.as_bytes(), &mut brotli, &Default::default()).unwrap();
+        let zstd = zstd::encode_all(PAGE.as_bytes(), 3).unwrap();
+
+        let fetcher = HttpFetcher::new(&ExtractionConfig::default()).unwrap();
+        let bodies = [
+            ("gzip", gzip.clone(), "gzip"),
+            ("deflate", deflate, "deflate"),
+            ("br", brotli.clone(), "br"),
+            ("zstd", zstd, "zstd"),
+            // Mislabeled: a gzip body declared as identity
+            ("mislabeled gzip", gzip, "identity"),
+        ];
+        for (name, body, content_encoding) in bodies {
+            let page = fetcher.fetch(&serve_once(body.clone(), content_encoding)).unwrap();
+            assert_eq!(page.body, PAGE, "{}", name);
+
+            // The prefix parse is fed the decompressed text too
+            let mut prefix = String::new();
+            let page = fetcher.fetch_prefix(&serve_once(body, content_encoding), &mut |chunk| {
+                prefix.push_str(chunk);
+                true
+            }).unwrap();
+            assert_eq!(page.body, PAGE, "{}", name);
+            assert_eq!(prefix, PAGE, "{}", name);
+        }
+
+        // Bodies past the limit are refused, before and after decompression
+        let config = ExtractionConfig { max_body_size: PAGE.len() - 1, ..Default::default() };
+        let fetcher = HttpFetcher::new(&config).unwrap();
+        for (body, content_encoding) in [(PAGE.as_bytes().to_vec(), "identity"), (gzip_body.clone(), "identity")] {
+            let result = fetcher.fetch(&serve_once(body, content_encoding));
+            assert!(matches!(&result, Err(TrafilaturaError::BodyError(_, message)) if message.contains("larger than")), "{:?}", result);
+        }
+        let fetcher = HttpFetcher::new(&ExtractionConfig::default()).unwrap();
+
+        // Brotli without a label has no magic number to decode it by
+        let result = fetcher.fetch(&serve_once(brotli, "identity"));
+        assert!(matches!(result, Err(TrafilaturaError::BodyError(..))), "{:?}", result);
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_sniff_encoding() {
+        let page = b"<html><head><meta charset=\"windows-1252\"><title>Caf\xe9</title></head></html>";
+        assert_eq!(sniff_encoding(page, None), encoding_rs::WINDOWS_1252);
+        // The Content-Type wins over the page, and a byte order mark over both
+        assert_eq!(sniff_encoding(page, Some("iso-8859-2")), encoding_rs::ISO_8859_2);
+        assert_eq!(sniff_encoding(b"\xef\xbb\xbf<html>", Some("windows-1252")), UTF_8);
+
+        let page = br#"<meta http-equiv="Content-Type" content="text/html; charset=Shift_JIS">"#;
+        assert_eq!(sniff_encoding(page, None), encoding_rs::SHIFT_JIS);
+        // A page read as ASCII cannot be UTF-16
+        assert_eq!(sniff_encoding(b"<meta charset='utf-16'>", None), UTF_8);
+        assert_eq!(sniff_encoding(b"<meta charset=unknown>", None), UTF_8);
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_redirects_connect_to_checked_addresses() {
+        use std::net::IpAddr;
+        use crate::dns::DnsOptions;
+
+        // Neither host name resolves outside the overrides of the policy
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        std::thread::spawn(move || {
+            for request in server.incoming_requests().take(2) {
+                let response = if request.url() == "/" {
+                    let location = format!("http://tides.test:{}/today", port);
+                    tiny_http::Response::from_data(Vec::new()).with_status_code(302)
+                        .with_header(tiny_http::Header::from_bytes("Location", location).unwrap())
+                } else {
+                    tiny_http::Response::from_data(PAGE.as_bytes().to_vec())
+                };
+                let _ = request.respond(response);
+            }
+        });
+
+        let localhost = vec![IpAddr::from([127, 0, 0, 1])];
+        let config = ExtractionConfig {
+            dns: DnsOptions {
+                overrides: [("harbour.test".to_string(), localhost.clone()), ("tides.test".to_string(), localhost)].into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let fetcher = HttpFetcher::new(&config).unwrap();
+        let page = fetcher.fetch(&Url::parse(&format!("http://harbour.test:{}/", port)).unwrap()).unwrap();
+
+        assert_eq!(page.url, format!("http://tides.test:{}/today", port));
+        assert_eq!(page.body, PAGE);
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_invalid_ca_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ca.pem");
+        std::fs::write(&path, "not a certificate").unwrap();
+        let config = |ca_bundle| ExtractionConfig {
+            tls: TlsOptions { ca_bundle: Some(ca_bundle), ..Default::default() },
+            ..Default::default()
+        };
+
+        assert!(HttpFetcher::new(&config(path)).is_err());
+        assert!(matches!(HttpFetcher::new(&config(dir.path().join("missing.pem"))), Err(TrafilaturaError::IoError(_))));
+    }
+}
@@ -1,19 +1,30 @@
 //! HTML processing functions for Trafilatura Rust port.
 //! This module contains utilities for cleaning and normalizing HTML content.
 
-use scraper::{Html, Selector, ElementRef, Element};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use ego_tree::{NodeId, Tree};
+use html5ever::driver::Parser;
+use html5ever::tendril::{StrTendril, TendrilSink};
+use scraper::node::{Element, Text};
+use scraper::{Html, Node, ElementRef};
+use selectors::attr::CaseSensitivity;
 use regex::Regex;
 use lazy_static::lazy_static;
 
-use crate::ExtractionConfig;
+use crate::{ExtractionConfig, ImageStyle, RubyStyle};
 use crate::TrafilaturaError;
+use crate::document::Document;
+use crate::render::ContentRenderer;
+use crate::utils::{char_chunks, count_words, text_length};
 
 lazy_static! {
     /// Common elements that should be removed during cleaning
     static ref UNWANTED_ELEMENTS: Vec<&'static str> = vec![
         "script", "style", "noscript", "iframe", "footer", "nav", "aside",
         "form", "button", "svg", "head", "header", "meta", "link", "comment",
-        "cite", "figcaption", "time", "small", "address"
+        "cite", "figcaption", "time", "small", "address", "canvas"
     ];
 
     /// Common class names that indicate navigation, ads, or other non-content elements
@@ -51,251 +62,524 @@ lazy_static! {
         "recommendations", "popularity", "most-read", "most-shared", "trending", "hot"
     ];
 
-    /// Regex to match multiple spaces
-    static ref MULTIPLE_SPACES_RE: Regex = Regex::new(r"\s+").unwrap();
+    /// Block elements that can hold a navigation menu, tag cloud, or link rail
+    static ref LINK_BLOCK_ELEMENTS: Vec<&'static str> = vec![
+        "div", "section", "ul", "ol", "dl", "p", "td"
+    ];
 
-    /// Regex to match line breaks
-    static ref LINE_BREAKS_RE: Regex = Regex::new(r"(\r\n|\r|\n)+").unwrap();
+    /// Inline styles of elements that are not rendered
+    static ref HIDDEN_STYLE_RE: Regex = Regex::new(r"(?i)display\s*:\s*none|visibility\s*:\s*hidden").unwrap();
 }
 
-/// Clean an HTML document by removing unwanted elements
-pub fn clean_html(document: &Html, _config: &ExtractionConfig) -> Result<Html, TrafilaturaError> {
-    // Clone the document for modifications
-    let document_str = document.html();
+/// Ancestors checked for boilerplate wrappers, so deeply nested blocks are found
+/// without walking to the root of large documents
+pub const MAX_ANCESTOR_DEPTH: usize = 8;
+
+/// Tags of wrappers whose blocks are never content
+const BOILERPLATE_WRAPPERS: [&str; 3] = ["footer", "nav", "aside"];
+
+/// ARIA landmark roles of the main content of a page
+pub const CONTENT_ROLES: [&str; 2] = ["main", "article"];
+
+/// ARIA landmark roles of navigation, sidebars, and the site header and footer.
+/// Sites with generated class names still mark these for screen readers.
+pub const BOILERPLATE_ROLES: [&str; 4] = ["navigation", "complementary", "banner", "contentinfo"];
+
+/// Classes of text meant for screen readers only, such as skip links
+const SCREEN_READER_CLASSES: [&str; 7] = [
+    "sr-only", "visually-hidden", "visuallyhidden", "screen-reader-text", "screen-reader-only",
+    "element-invisible", "a11y-hidden",
+];
+
+/// Minimum number of anchors in a block pruned as navigation
+const MIN_LINK_BLOCK_ANCHORS: usize = 4;
+
+/// Minimum share of the block text that is anchor text
+const MIN_LINK_BLOCK_DENSITY: f64 = 0.8;
+
+/// Anchors with at most this many words on average are menu or tag entries
+const MAX_MENU_ANCHOR_WORDS: f64 = 3.0;
+
+/// Maximum coefficient of variation of anchor text lengths in a uniform link rail
+const MAX_ANCHOR_LENGTH_VARIATION: f64 = 0.3;
+
+/// Elements whose content is raw text, where comment markup is part of the text
+const RAW_TEXT_ELEMENTS: [&str; 5] = ["script", "style", "textarea", "title", "xmp"];
+
+/// Remove HTML comments, conditional comments included, and CDATA sections from
+/// markup before it is parsed.
+///
+/// The parser ends a comment at the first `-->`, so when a template comments out a
+/// block that holds comments itself, the rest of the block becomes text; here a
+/// comment ends at the `-->` matching the comments nested in it. CDATA sections end
+/// at `]]>` rather than at the first `>`. Raw text elements such as `<script>` and
+/// quoted attribute values are left as they are.
+pub fn strip_comments(html: &str) -> Cow<'_, str> {
+    if !html.contains("<!") {
+        return Cow::Borrowed(html);
+    }
     
-    // Create a mutable document
-    let fragment = Html::parse_fragment(&document_str);
+    let mut stripped = String::new();
+    // Start of the markup not yet copied
+    let mut copied = 0;
+    let mut pos = 0;
+    while let Some(offset) = html[pos..].find('<') {
+        let start = pos + offset;
+        let rest = &html.as_bytes()[start..];
+        let end = if rest.starts_with(b"<!--") {
+            comment_end(html, start)
+        } else if rest.get(..9).is_some_and(|marker| marker.eq_ignore_ascii_case(b"<![cdata[")) {
+            html[start..].find("]]>").map_or(html.len(), |end| start + end + 3)
+        } else {
+            let raw_text = RAW_TEXT_ELEMENTS.iter().find(|name| is_start_tag(rest, name));
+            pos = match raw_text {
+                Some(name) => closing_tag(html, start + 1, name).unwrap_or(html.len()),
+                None if rest.get(1).is_some_and(u8::is_ascii_alphabetic) => tag_end(html, start),
+                None => start + 1,
+            };
+            continue;
+        };
+        stripped.push_str(&html[copied..start]);
+        copied = end;
+        pos = end;
+    }
     
-    // Remove unwanted elements
-    for element_name in UNWANTED_ELEMENTS.iter() {
-        let selector = Selector::parse(element_name).unwrap();
-        for element in fragment.select(&selector) {
-            if let Some(_parent) = element.parent_element() {
-                // In a real implementation, we would remove the element here
-                // but since scraper doesn't allow mutable operations, we'll modify the HTML directly
-                // This is a simplification that would need further refinement
-            }
+    if copied == 0 {
+        return Cow::Borrowed(html);
+    }
+    stripped.push_str(&html[copied..]);
+    Cow::Owned(stripped)
+}
+
+/// End of the start tag at `start`, past its `>`. Quoted attribute values may hold
+/// `>` and comment markup.
+fn tag_end(html: &str, start: usize) -> usize {
+    let bytes = html.as_bytes();
+    let mut pos = start + 1;
+    let mut after_equals = false;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'>' => return pos + 1,
+            b'=' => after_equals = true,
+            quote @ (b'"' | b'\'') if after_equals => {
+                pos = html[pos + 1..].find(quote as char).map_or(html.len(), |end| pos + 1 + end);
+                after_equals = false;
+            },
+            byte if byte.is_ascii_whitespace() => {},
+            _ => after_equals = false,
         }
+        pos += 1;
     }
-    
-    // Since scraper doesn't allow direct DOM manipulation like kuchiki,
-    // we would need to use a different approach to modify the document.
-    // For now, we'll return the original document to keep code compiling,
-    // but in a real implementation, we would need to create a new HTML document
-    // with the modifications.
-    
-    Ok(document.clone())
+    html.len()
 }
 
-/// Get the text content of a node, preserving some formatting
-pub fn get_text_content(element: &ElementRef, config: &ExtractionConfig) -> String {
-    // Skip extraction for elements with unwanted classes or IDs
-    if has_class_hint(element, &UNWANTED_CLASSES) || has_id_hint(element, &UNWANTED_IDS) {
-        return String::new();
+/// End of the comment starting at `start`, past its `-->` and those of the comments
+/// nested in it. Without enough of them the comment ends at its first `-->`, as in
+/// the parser.
+fn comment_end(html: &str, start: usize) -> usize {
+    let body = start + "<!--".len();
+    // `<!-->` and `<!--->` are empty comments
+    for empty in [">", "->"] {
+        if html[body..].starts_with(empty) {
+            return body + empty.len();
+        }
     }
     
-    // Process child nodes instead of getting text directly to have more control
-    let mut paragraphs = Vec::new();
-    let p_selector = Selector::parse("p").unwrap();
-    let mut skip_rest = false;  // Flag to skip paragraphs after encountering boilerplate markers
+    let close = |from: usize| -> Option<usize> {
+        let end = ["-->", "--!>"].iter()
+            .filter_map(|marker| html[from..].find(marker).map(|at| from + at + marker.len()))
+            .min()?;
+        Some(end)
+    };
+    let Some(first) = close(body) else {
+        return html.len();
+    };
     
-    for p in element.select(&p_selector) {
-        // Skip paragraphs with unwanted classes/IDs
-        if has_class_hint(&p, &UNWANTED_CLASSES) || has_id_hint(&p, &UNWANTED_IDS) {
-            continue;
+    let mut open = html[body..first].matches("<!--").count();
+    let mut end = first;
+    while open > 0 {
+        match close(end) {
+            Some(next) => {
+                open += html[end..next].matches("<!--").count();
+                open -= 1;
+                end = next;
+            },
+            None => return first,
         }
-        
-        // Skip very short paragraphs (likely metadata or UI elements)
-        let p_text = p.text().collect::<String>();
-        
-        // Check for boilerplate markers that indicate we should stop extracting
-        // These are common phrases that mark the end of the main content in news articles
-        if p_text.contains("Catch all the") || 
-           p_text.contains("Download") || 
-           p_text.contains("Follow us") || 
-           p_text.contains("First Published") || 
-           p_text.contains("Read more about") || 
-           p_text.contains("More on this topic") || 
-           p_text.contains("Related articles") || 
-           p_text.contains("Tags:") || 
-           p_text.contains("Copyright") {
-            skip_rest = true;
-            continue;
+    }
+    end
+}
+
+/// Check if markup starts with a start tag of the given element
+fn is_start_tag(markup: &[u8], name: &str) -> bool {
+    markup.get(1..=name.len()).is_some_and(|tag| tag.eq_ignore_ascii_case(name.as_bytes()))
+        && markup.get(name.len() + 1).is_none_or(|next| next.is_ascii_whitespace() || *next == b'>' || *next == b'/')
+}
+
+/// Position of the closing tag of the given element at or after `from`
+fn closing_tag(html: &str, from: usize, name: &str) -> Option<usize> {
+    let mut pos = from;
+    while let Some(offset) = html[pos..].find("</") {
+        let at = pos + offset;
+        if is_start_tag(&html.as_bytes()[at + 1..], name) {
+            return Some(at);
         }
-        
-        // Skip all remaining paragraphs once we've hit a boilerplate marker
-        if skip_rest {
-            continue;
+        pos = at + 2;
+    }
+    None
+}
+
+/// Parse an HTML document, repairing the tree when the parser leaves it inconsistent.
+///
+/// When html5ever's adoption agency algorithm moves the children of a misnested
+/// formatting element (`<a><div>..<!-- --><meta></a>`), ego-tree 0.6 keeps the old
+/// parent on some of them, and detaching such a node panics. Those trees are rebuilt
+/// from the child lists, which are intact.
+pub fn parse_document(html: &str) -> Html {
+    repaired(Html::parse_document(html))
+}
+
+/// Rebuild the tree of a parsed document if any child has a stale parent pointer
+fn repaired(mut document: Html) -> Html {
+    let consistent = document.tree.root().descendants()
+        .all(|node| node.children().all(|child| child.parent().map(|parent| parent.id()) == Some(node.id())));
+    if !consistent {
+        document.tree = rebuild_tree(&document.tree);
+    }
+    document
+}
+
+/// Body read past the start of the body when parsing for metadata: enough for the
+/// headline, byline, and date at the top of the first article
+pub const METADATA_BODY_LENGTH: usize = 64 * 1024;
+
+/// Length of the chunks `parse_metadata_document` feeds to the parser
+const PARSE_CHUNK_LENGTH: usize = 8 * 1024;
+
+/// Incremental parser for metadata-only extraction. Metadata comes from the head and
+/// the top of the body, so the parser asks for no more input once the head has been
+/// parsed and `METADATA_BODY_LENGTH` bytes of the body have followed, sparing the
+/// megabytes of comments and footer scripts after them.
+pub struct MetadataParser {
+    parser: Parser<Html>,
+    /// Bytes fed since the body started
+    body_length: Option<usize>,
+}
+
+impl MetadataParser {
+    /// Create a parser for a new document
+    pub fn new() -> Self {
+        Self { parser: html5ever::parse_document(Html::new_document(), Default::default()), body_length: None }
+    }
+
+    /// Parse the next chunk of the document. Returns `false` once enough of the
+    /// document has been parsed for its metadata.
+    pub fn feed(&mut self, chunk: &str) -> bool {
+        self.parser.process(StrTendril::from(chunk));
+        self.body_length = match self.body_length {
+            Some(length) => Some(length + chunk.len()),
+            None => self.has_body().then_some(0),
+        };
+        self.body_length.is_none_or(|length| length < METADATA_BODY_LENGTH)
+    }
+
+    /// Check if the parser has moved past the head, which it does at the first
+    /// body element or text
+    fn has_body(&self) -> bool {
+        let document = &self.parser.tokenizer.sink.sink;
+        document.tree.root().children()
+            .filter(|node| node.value().as_element().is_some_and(|element| element.name() == "html"))
+            .flat_map(|html| html.children())
+            .any(|node| node.value().as_element().is_some_and(|element| matches!(element.name(), "body" | "frameset")))
+    }
+
+    /// Finish parsing and return the document parsed so far, with any unclosed
+    /// elements closed
+    pub fn finish(self) -> Html {
+        repaired(self.parser.finish())
+    }
+}
+
+impl Default for MetadataParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parse the part of an HTML document its metadata comes from, see `MetadataParser`
+pub fn parse_metadata_document(html: &str) -> Html {
+    let mut parser = MetadataParser::new();
+    for chunk in char_chunks(html, PARSE_CHUNK_LENGTH) {
+        if !parser.feed(chunk) {
+            break;
         }
-        
-        // Skip paragraphs that look like metadata
-        if p_text.len() < 30 {
-            if p_text.contains("Published") || 
-               p_text.contains("Updated") || 
-               p_text.contains("By ") || 
-               p_text.contains("Written by") || 
-               p_text.contains("Posted") || 
-               p_text.contains("Share") || 
-               p_text.contains("Read") || 
-               p_text.contains("Follow") || 
-               p_text.contains("Subscribe") || 
-               p_text.contains("Also Read") || 
-               p_text.contains("ALSO READ") || 
-               p_text.contains("More Less") || 
-               p_text.starts_with("Watch:") || 
-               p_text.contains("Business News") || 
-               p_text.contains("Latest News") {
-                continue;
+    }
+    parser.finish()
+}
+
+/// Copy a tree by walking the child lists of its nodes
+fn rebuild_tree(tree: &Tree<Node>) -> Tree<Node> {
+    let mut rebuilt = Tree::new(tree.root().value().clone());
+    let mut pending = vec![(tree.root(), rebuilt.root().id())];
+    while let Some((node, copy_id)) = pending.pop() {
+        for child in node.children() {
+            if let Some(mut copy) = rebuilt.get_mut(copy_id) {
+                pending.push((child, copy.append(child.value().clone()).id()));
             }
         }
-        
-        // Exclude paragraphs that are likely to be links to other articles (common pattern)
-        if p_text.starts_with("Also Read |") || 
-           p_text.starts_with("Read: ") || 
-           p_text.starts_with("Watch: ") || 
-           p_text.starts_with("See also: ") {
-            continue;
+    }
+    rebuilt
+}
+
+/// Clean an HTML document in place by detaching unwanted elements, HTML comments,
+/// and blocks made of repeated short links
+pub fn clean_html(document: &mut Html, config: &ExtractionConfig) {
+    remove_unwanted_elements(document, config);
+    prune_link_blocks(document);
+}
+
+/// Detach unwanted elements and HTML comments. `ExtractionConfig::strip_elements`
+/// and `ExtractionConfig::keep_elements` adjust the default list of unwanted elements,
+/// and elements with one of `ExtractionConfig::unwanted_classes` or `unwanted_ids` go too.
+pub fn remove_unwanted_elements(document: &mut Html, config: &ExtractionConfig) {
+    let unwanted: Vec<_> = document.tree.root().descendants()
+        .filter(|node| match node.value() {
+            // Figure captions are kept for images rendered with them, and SVG
+            // graphics for their titles
+            Node::Element(element) => (is_stripped_tag(element.name(), &UNWANTED_ELEMENTS, config)
+                && !(element.name() == "figcaption" && config.renders_images() && config.image_style == ImageStyle::Figure)
+                && !(element.name() == "svg" && config.svg_titles))
+                || has_configured_name(element, config)
+                || ElementRef::wrap(*node).is_some_and(|element| is_boilerplate_landmark(&element)),
+            Node::Comment(_) => true,
+            _ => false,
+        })
+        .map(|node| node.id())
+        .collect();
+
+    // Detaching a node also drops its subtree, so nested matches are harmless
+    for id in unwanted {
+        if let Some(mut node) = document.tree.get_mut(id) {
+            node.detach();
         }
-        
-        // Include the paragraph text
-        paragraphs.push(p_text);
     }
-    
-    // Initialize the text variable based on the content we found
-    let mut text = if !paragraphs.is_empty() {
-        // If we extracted paragraphs successfully, use those
-        paragraphs.join("\n\n")
-    } else {
-        // Otherwise fall back to extracting all text
-        // But filter out known metadata patterns first
-        
-        // Get all text nodes
-        let mut all_text = element.text().collect::<Vec<_>>();
-        
-        // Filter out common metadata patterns
-        all_text.retain(|&t| {
-            let trimmed = t.trim();
-            
-            // Skip these common patterns that indicate metadata, not content
-            !(trimmed.is_empty() || 
-              trimmed.starts_with("Published") || 
-              trimmed.starts_with("Updated") || 
-              trimmed.starts_with("Written by") || 
-              trimmed.starts_with("By ") || 
-              trimmed.contains("©") || 
-              trimmed.contains("All rights reserved") || 
-              trimmed.starts_with("Share") || 
-              trimmed.starts_with("Posted") ||
-              trimmed == "Read More" || 
-              trimmed == "Also Read" || 
-              trimmed.starts_with("Follow us"))
-        });
-        
-        all_text.join(" ")
-    };
-    
-    // Process specific elements
-    if config.include_links {
-        let link_selector = Selector::parse("a").unwrap();
-        for link in element.select(&link_selector) {
-            // Skip navigation/sharing links
-            if has_class_hint(&link, &["nav", "menu", "social", "share", "tag", "author", "byline", "timestamp"]) {
-                continue;
-            }
-            
-            // Skip links in news articles that typically point to other articles
-            if let Some(href) = link.value().attr("href") {
-                // Skip links to common news site patterns or social media
-                if href.contains("/tag/") || 
-                   href.contains("/tags/") ||
-                   href.contains("/topic/") || 
-                   href.contains("/topics/") ||
-                   href.contains("/author/") || 
-                   href.contains("/authors/") ||
-                   href.contains("/category/") || 
-                   href.contains("/categories/") ||
-                   href.contains("facebook.com") || 
-                   href.contains("twitter.com") || 
-                   href.contains("linkedin.com") || 
-                   href.contains("instagram.com") || 
-                   href.contains("youtube.com") || 
-                   href.contains("mailto:") {
-                    continue;
-                }
-                
-                // Only include links that have meaningful text
-                let link_text = link.text().collect::<String>();
-                if !link_text.is_empty() && link_text.len() > 3 && 
-                   !link_text.contains("Read more") && 
-                   !link_text.contains("More") && 
-                   !link_text.contains("Also") {
-                    text.push_str(&format!(" ({}) ", href));
-                }
+}
+
+/// Check if an element has one of the class names or IDs configured as unwanted
+fn has_configured_name(element: &Element, config: &ExtractionConfig) -> bool {
+    let listed = |names: &[String], name: &str| names.iter().any(|unwanted| unwanted.eq_ignore_ascii_case(name));
+    element.classes().any(|class| listed(&config.unwanted_classes, class))
+        || element.id().is_some_and(|id| listed(&config.unwanted_ids, id))
+}
+
+/// Check if an element is a navigation, sidebar, banner, or footer landmark. A
+/// landmark holding the main content, as on pages that mark the whole layout as a
+/// banner, is kept.
+fn is_boilerplate_landmark(element: &ElementRef) -> bool {
+    has_role(element, &BOILERPLATE_ROLES)
+        && !element.descendants()
+            .filter_map(ElementRef::wrap)
+            .any(|inner| inner.value().name() == "main" || has_role(&inner, &CONTENT_ROLES))
+}
+
+/// Detach elements that browsers do not render or that only screen readers announce,
+/// returning how many were removed. Sites stuff SEO text and skip links into them.
+pub fn remove_hidden_elements(document: &mut Html) -> usize {
+    let hidden: Vec<_> = document.root_element().descendants()
+        .filter_map(ElementRef::wrap)
+        .filter(is_hidden)
+        .map(|element| element.id())
+        .collect();
+
+    for id in &hidden {
+        if let Some(mut node) = document.tree.get_mut(*id) {
+            node.detach();
+        }
+    }
+    hidden.len()
+}
+
+/// Check if an element is hidden: the `hidden` attribute (except `until-found`,
+/// which browsers reveal on search), `aria-hidden="true"`, an inline style with
+/// `display: none` or `visibility: hidden`, or a screen-reader-only class
+pub fn is_hidden(element: &ElementRef) -> bool {
+    let value = element.value();
+    value.attr("hidden").is_some_and(|hidden| !hidden.trim().eq_ignore_ascii_case("until-found"))
+        || value.attr("aria-hidden").is_some_and(|hidden| hidden.trim().eq_ignore_ascii_case("true"))
+        || value.attr("style").is_some_and(|style| HIDDEN_STYLE_RE.is_match(style))
+        || value.classes().any(|class| SCREEN_READER_CLASSES.iter().any(|hidden| class.eq_ignore_ascii_case(hidden)))
+}
+
+/// Render the ruby annotations of a document in place, returning how many were
+/// rewritten. Fallback parentheses (`<rp>`) are always dropped; readings (`<rt>`,
+/// `<rtc>`) are dropped or turned into parenthesized text after their base text,
+/// so they no longer run into the words they annotate.
+pub fn rewrite_ruby(document: &mut Html, style: RubyStyle) -> usize {
+    let annotations: Vec<_> = descendants_named(document.root_element(), &["rt", "rtc", "rp"])
+        // Readings nested in a ruby text container are rendered with the container
+        .filter(|element| !(element.value().name() == "rt" && element.parent().and_then(ElementRef::wrap).is_some_and(|parent| parent.value().name() == "rtc")))
+        .map(|element| {
+            let reading = element.text().collect::<String>().trim().to_string();
+            (element.id(), element.value().name() != "rp", reading)
+        })
+        .collect();
+
+    for (id, is_reading, reading) in &annotations {
+        if let Some(mut node) = document.tree.get_mut(*id) {
+            if *is_reading && style == RubyStyle::Annotated && !reading.is_empty() {
+                node.insert_before(Node::Text(Text { text: StrTendril::from(format!("({})", reading)) }));
             }
+            node.detach();
         }
     }
-    
-    if config.include_images {
-        let img_selector = Selector::parse("img").unwrap();
-        for img in element.select(&img_selector) {
-            // Skip social/advertising/icon images
-            if has_class_hint(&img, &["icon", "logo", "social", "avatar", "ad"]) {
-                continue;
+    annotations.len()
+}
+
+/// Check if elements with the given tag name are stripped: tags in `defaults` or
+/// `ExtractionConfig::strip_elements` are, unless listed in `ExtractionConfig::keep_elements`
+pub fn is_stripped_tag(name: &str, defaults: &[&str], config: &ExtractionConfig) -> bool {
+    let listed = |tags: &[String]| tags.iter().any(|tag| tag.eq_ignore_ascii_case(name));
+    if listed(&config.keep_elements) {
+        return false;
+    }
+
+    defaults.iter().any(|tag| tag.eq_ignore_ascii_case(name)) || listed(&config.strip_elements)
+}
+
+/// Detach blocks made of repeated short links, such as menus, tag clouds, and
+/// "most read" rails, using anchor statistics rather than class names
+pub fn prune_link_blocks(document: &mut Html) {
+    let mut link_blocks = Vec::new();
+    find_link_blocks(document, &mut link_blocks);
+    for id in link_blocks {
+        if let Some(mut node) = document.tree.get_mut(id) {
+            node.detach();
+        }
+    }
+}
+
+/// Anchor statistics of a subtree. They add up from children to parents, so the
+/// statistics of every block are gathered in one pass over the document.
+#[derive(Debug, Clone, Copy, Default)]
+struct AnchorStats {
+    /// Letters and digits in the subtree
+    text_length: usize,
+    /// Anchors with letters or digits
+    anchors: usize,
+    anchor_length: usize,
+    anchor_length_squares: usize,
+    anchor_words: usize,
+}
+
+impl AnchorStats {
+    fn add(&mut self, other: &AnchorStats) {
+        self.text_length += other.text_length;
+        self.anchors += other.anchors;
+        self.anchor_length += other.anchor_length;
+        self.anchor_length_squares += other.anchor_length_squares;
+        self.anchor_words += other.anchor_words;
+    }
+}
+
+/// Collect the outermost blocks that consist of repeated short anchors. The tree is
+/// walked with an explicit stack, as deeply nested markup would overflow a recursion.
+fn find_link_blocks(document: &Html, blocks: &mut Vec<NodeId>) {
+    let nodes: Vec<_> = document.tree.root().descendants().collect();
+    let mut stats: HashMap<NodeId, AnchorStats> = HashMap::with_capacity(nodes.len());
+
+    // Reverse document order visits children before their parents
+    for node in nodes.into_iter().rev() {
+        let mut total = AnchorStats::default();
+        for child in node.children() {
+            if let Some(child_stats) = stats.get(&child.id()) {
+                total.add(child_stats);
             }
-            
-            let alt = img.value().attr("alt").unwrap_or("");
-            let src = img.value().attr("src").unwrap_or("");
-            
-            if !alt.is_empty() {
-                text.push_str(&format!("[Image: {}] ", alt));
-            } else if !src.is_empty() {
-                text.push_str(&format!("[Image: {}] ", src));
+        }
+        match node.value() {
+            Node::Text(text) => total.text_length = alphanumeric_length(std::iter::once(&**text)),
+            Node::Element(element) if element.name() == "a" => {
+                let length = total.text_length;
+                total.anchors = usize::from(length > 0);
+                total.anchor_length = length;
+                total.anchor_length_squares = length * length;
+                total.anchor_words = ElementRef::wrap(node).map_or(0, |anchor| count_words(&anchor.text().collect::<String>()));
             }
+            _ => {}
         }
+        stats.insert(node.id(), total);
+    }
+
+    let mut pending: Vec<ElementRef> = document.root_element().children().filter_map(ElementRef::wrap).rev().collect();
+    while let Some(element) = pending.pop() {
+        let is_block = LINK_BLOCK_ELEMENTS.contains(&element.value().name())
+            && stats.get(&element.id()).is_some_and(is_link_block);
+        if is_block {
+            blocks.push(element.id());
+        } else {
+            pending.extend(element.children().filter_map(ElementRef::wrap).rev());
+        }
+    }
+}
+
+/// Check if a block is navigation judging by its anchor statistics alone: nearly
+/// all of its text is in anchors, and the anchors are either very short (menus,
+/// tag clouds) or of nearly the same length (link rails)
+fn is_link_block(stats: &AnchorStats) -> bool {
+    if stats.anchors < MIN_LINK_BLOCK_ANCHORS {
+        return false;
+    }
+
+    if stats.text_length == 0 || (stats.anchor_length as f64 / stats.text_length as f64) < MIN_LINK_BLOCK_DENSITY {
+        return false;
+    }
+
+    let count = stats.anchors as f64;
+    if stats.anchor_words as f64 / count <= MAX_MENU_ANCHOR_WORDS {
+        return true;
+    }
+
+    let mean = stats.anchor_length as f64 / count;
+    let variance = (stats.anchor_length_squares as f64 / count - mean * mean).max(0.0);
+    variance.sqrt() / mean <= MAX_ANCHOR_LENGTH_VARIATION
+}
+
+/// Count letters and digits, so separators like `|` and `·` between menu entries
+/// do not count as text outside the anchors
+fn alphanumeric_length<'a>(text: impl Iterator<Item = &'a str>) -> usize {
+    text.flat_map(str::chars).filter(|c| c.is_alphanumeric()).count()
+}
+
+/// Get the text content of a node, preserving some formatting
+pub fn get_text_content(element: &ElementRef, config: &ExtractionConfig) -> String {
+    get_content(element, config).to_text(config.image_style)
+}
+
+/// Get the content of a node like `get_text_content`, as a document
+pub fn get_content(element: &ElementRef, config: &ExtractionConfig) -> Document {
+    let mut renderer = ContentRenderer::new(config).skip_boilerplate();
+    add_content(element, &mut renderer);
+    renderer.finish()
+}
+
+/// Add the content of a node to a renderer, leaving out blocks with unwanted
+/// classes, IDs, or landmark roles and blocks inside boilerplate wrappers. Class
+/// and ID hints are not checked when the config scores by structure, since hashed
+/// names match them by accident.
+pub fn add_content(element: &ElementRef, renderer: &mut ContentRenderer) {
+    let class_hints = renderer.config().uses_class_hints();
+    let has_unwanted_hint = |element: &ElementRef| {
+        class_hints && (has_class_hint(element, &UNWANTED_CLASSES) || has_id_hint(element, &UNWANTED_IDS))
+    };
+
+    // Skip extraction for elements with unwanted classes or IDs
+    if has_unwanted_hint(element) {
+        return;
     }
     
-    // Remove commonly found boilerplate phrases in news articles
-    let text = text.replace("Also Read", "")
-                  .replace("Read More", "")
-                  .replace("ALSO READ:", "")
-                  .replace("Catch all the", "")
-                  .replace("Download The", "")
-                  .replace("First Published :", "")
-                  .replace("Published :", "")
-                  .replace("Published on", "")
-                  .replace("Last Updated :", "");
-    
-    // Remove URL references that may have slipped through
-    let url_regex = Regex::new(r"https?://\S+").unwrap();
-    let text = url_regex.replace_all(&text, "").to_string();
-    
-    // Remove relative URL paths that may be in parentheses
-    let path_regex = Regex::new(r"\(\s*/[^\)]*\)").unwrap();
-    let text = path_regex.replace_all(&text, "").to_string();
-    
-    // Remove empty parentheses that might be left after URL removal
-    let empty_parentheses_regex = Regex::new(r"\(\s*\)").unwrap();
-    let text = empty_parentheses_regex.replace_all(&text, "").to_string();
-    
-    // Remove isolated single parentheses characters
-    let text = text.replace(" ( ", " ").replace(" ) ", " ");
-    
-    // Remove common ending phrases in news articles
-    let text = text.replace("Business News", "")
-                  .replace("Economy news", "")
-                  .replace("Breaking News Events", "")
-                  .replace("Latest News Updates", "")
-                  .replace("Daily Market Updates", "")
-                  .replace("More Less", "");
-    
-    // Normalize spaces
-    let text = MULTIPLE_SPACES_RE.replace_all(&text, " ").to_string();
-    
-    // Normalize line breaks
-    let text = LINE_BREAKS_RE.replace_all(&text, "\n").to_string();
-    
-    // Trim whitespace
-    text.trim().to_string()
+    renderer.add(element, &|block| {
+        has_unwanted_hint(block) || has_role(block, &BOILERPLATE_ROLES)
+            || ancestors_within(block, element).any(|ancestor| is_boilerplate_wrapper(&ancestor))
+    });
 }
 
 /// Convert the node to an HTML string
@@ -306,6 +590,92 @@ pub fn node_to_html(element: &ElementRef) -> Result<String, TrafilaturaError> {
     Ok(html)
 }
 
+/// Iterate over the descendant elements with one of the given tag names, in document order.
+///
+/// Equivalent to `element.select("name1, name2, ...")` without parsing a selector.
+pub fn descendants_named<'a, 'n>(element: ElementRef<'a>, names: &'n [&str]) -> impl Iterator<Item = ElementRef<'a>> + 'n
+where
+    'a: 'n,
+{
+    element.descendants()
+        .skip(1)
+        .filter_map(ElementRef::wrap)
+        .filter(move |descendant| names.contains(&descendant.value().name()))
+}
+
+/// Weighted length of the text of an element, without collecting it into a string
+pub fn element_text_length(element: &ElementRef) -> usize {
+    element.text().map(text_length).sum()
+}
+
+/// Text and link text lengths of a node
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NodeLengths {
+    /// Weighted length of all text below the node
+    pub text: usize,
+    /// Weighted length of the text inside links, all of it for a link itself
+    pub link_text: usize,
+}
+
+impl NodeLengths {
+    /// Lengths of a single element, walking its subtree
+    pub fn of(element: &ElementRef) -> Self {
+        NodeLengths {
+            text: element_text_length(element),
+            link_text: if element.value().name() == "a" {
+                element_text_length(element)
+            } else {
+                // The parser never nests links, so no text is counted twice
+                descendants_named(*element, &["a"]).map(|link| element_text_length(&link)).sum()
+            },
+        }
+    }
+
+    /// Text in links / total text
+    pub fn link_density(&self) -> f64 {
+        if self.text == 0 {
+            return 0.0;
+        }
+        self.link_text as f64 / self.text as f64
+    }
+}
+
+/// Text and link text lengths of every node in a document, computed in one bottom-up
+/// pass so scoring and density checks don't collect the text of an element again for
+/// each of its ancestors
+pub struct TextLengths(HashMap<NodeId, NodeLengths>);
+
+impl TextLengths {
+    pub fn new(document: &Html) -> Self {
+        let nodes: Vec<_> = document.tree.root().descendants().collect();
+        let mut lengths: HashMap<NodeId, NodeLengths> = HashMap::with_capacity(nodes.len());
+
+        // Reverse document order visits children before their parents
+        for node in nodes.into_iter().rev() {
+            let mut total = NodeLengths::default();
+            if let Node::Text(text) = node.value() {
+                total.text = text_length(text);
+            }
+            for child in node.children() {
+                let child_lengths = lengths.get(&child.id()).copied().unwrap_or_default();
+                total.text += child_lengths.text;
+                total.link_text += child_lengths.link_text;
+            }
+            if matches!(node.value(), Node::Element(element) if element.name() == "a") {
+                total.link_text = total.text;
+            }
+            lengths.insert(node.id(), total);
+        }
+
+        TextLengths(lengths)
+    }
+
+    /// Lengths of an element, computed on the spot for elements of another document
+    pub fn get(&self, element: &ElementRef) -> NodeLengths {
+        self.0.get(&element.id()).copied().unwrap_or_else(|| NodeLengths::of(element))
+    }
+}
+
 /// Check if an element has any of the given class hints
 pub fn has_class_hint(element: &ElementRef, class_hints: &[&str]) -> bool {
     if let Some(class_attr) = element.value().attr("class") {
@@ -318,6 +688,38 @@ pub fn has_class_hint(element: &ElementRef, class_hints: &[&str]) -> bool {
     false
 }
 
+/// Ancestors of an element below `container`, nearest first and at most
+/// `MAX_ANCESTOR_DEPTH` of them
+pub fn ancestors_within<'a>(element: &ElementRef<'a>, container: &ElementRef<'a>) -> impl Iterator<Item = ElementRef<'a>> {
+    let container = container.id();
+    element.ancestors()
+        .take_while(move |ancestor| ancestor.id() != container)
+        .filter_map(ElementRef::wrap)
+        .take(MAX_ANCESTOR_DEPTH)
+}
+
+/// Check if an element wraps boilerplate: a footer, navigation, or aside, a landmark
+/// with one of these roles, or an element with one of the unwanted classes or IDs.
+/// Unlike the hints checked on blocks, whole class names are compared, since a
+/// wrapper like `.article-lead` holds content.
+fn is_boilerplate_wrapper(element: &ElementRef) -> bool {
+    let el = element.value();
+    BOILERPLATE_WRAPPERS.contains(&el.name())
+        || has_role(element, &BOILERPLATE_ROLES)
+        || el.attr("class").is_some_and(|classes| {
+            classes.split_whitespace().any(|class| UNWANTED_CLASSES.iter().any(|unwanted| class.eq_ignore_ascii_case(unwanted)))
+        })
+        || el.attr("id").is_some_and(|id| UNWANTED_IDS.iter().any(|unwanted| id.eq_ignore_ascii_case(unwanted)))
+}
+
+/// Check if an element has one of the given ARIA roles. The `role` attribute may
+/// list fallback roles after the preferred one; any of them counts.
+pub fn has_role(element: &ElementRef, roles: &[&str]) -> bool {
+    element.value().attr("role").is_some_and(|value| {
+        value.split_whitespace().any(|role| roles.iter().any(|wanted| role.eq_ignore_ascii_case(wanted)))
+    })
+}
+
 /// Check if an element has any of the given ID hints
 pub fn has_id_hint(element: &ElementRef, id_hints: &[&str]) -> bool {
     if let Some(id_attr) = element.value().attr("id") {
@@ -330,29 +732,271 @@ pub fn has_id_hint(element: &ElementRef, id_hints: &[&str]) -> bool {
     false
 }
 
+/// CSS path of an element from `body`, e.g. `body > div#main > article.post`, to
+/// pin the element in a content rule. A step names the element by ID if it has one,
+/// otherwise by classes, and by position among siblings it would not tell apart.
+pub fn css_path(element: &ElementRef) -> String {
+    let mut steps: Vec<String> = std::iter::once(*element)
+        .chain(element.ancestors().filter_map(ElementRef::wrap))
+        .take_while(|element| element.value().name() != "html")
+        .map(|element| css_step(&element))
+        .collect();
+    if steps.is_empty() {
+        steps.push("html".to_string());
+    }
+    steps.reverse();
+    steps.join(" > ")
+}
+
+/// One step of a CSS path
+fn css_step(element: &ElementRef) -> String {
+    let name = element.value().name();
+    if let Some(id) = element.value().id().filter(|id| !id.is_empty()) {
+        return format!("{}#{}", name, css_identifier(id));
+    }
+    // Classes in source order, unlike `Element::classes`
+    let classes: Vec<&str> = element.value().attr("class").unwrap_or_default().split_whitespace().collect();
+    let mut step = name.to_string();
+    for class in &classes {
+        step.push('.');
+        step.push_str(&css_identifier(class));
+    }
+
+    // Siblings of the same type, and whether one of them has the same classes
+    let mut position = 0;
+    let mut ambiguous = false;
+    for sibling in element.parent().into_iter().flat_map(|parent| parent.children()).filter_map(ElementRef::wrap) {
+        if sibling.value().name() != name {
+            continue;
+        }
+        position += 1;
+        if sibling.id() == element.id() {
+            break;
+        }
+        ambiguous |= classes.iter().all(|class| sibling.value().has_class(class, CaseSensitivity::CaseSensitive));
+    }
+    let later_match = element.next_siblings().filter_map(ElementRef::wrap).any(|sibling| {
+        sibling.value().name() == name
+            && classes.iter().all(|class| sibling.value().has_class(class, CaseSensitivity::CaseSensitive))
+    });
+    if ambiguous || later_match {
+        step.push_str(&format!(":nth-of-type({})", position));
+    }
+    step
+}
+
+/// Escape an ID or class name for use in a selector
+fn css_identifier(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_digit() && i == 0 {
+            escaped.push_str(&format!("\\{:x} ", c as u32));
+        } else if c.is_alphanumeric() || c == '-' || c == '_' {
+            escaped.push(c);
+        } else {
+            escaped.push('\\');
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use scraper::Html;
+    use scraper::{Html, Selector};
+
+    #[test]
+    fn test_css_path() {
+        let html = r#"<html><body><div id="main"><article class="post">First</article><article class="post featured">Second</article>
+            <section><p>One</p><p class="note">Two</p><p>Three</p></section><div id="2024:q1"><span class="a.b">Odd</span></div></div></body></html>"#;
+        let document = Html::parse_document(html);
+        let path = |selector: &str| css_path(&document.select(&Selector::parse(selector).unwrap()).next().unwrap());
+
+        assert_eq!(path("body"), "body");
+        assert_eq!(path("html"), "html");
+        assert_eq!(path("article"), "body > div#main > article.post:nth-of-type(1)");
+        assert_eq!(path(".featured"), "body > div#main > article.post.featured");
+        assert_eq!(path(".note"), "body > div#main > section > p.note");
+        assert_eq!(path("p:nth-of-type(3)"), "body > div#main > section > p:nth-of-type(3)");
+
+        // Every path selects its element, odd names included
+        for element in document.select(&Selector::parse("body *").unwrap()) {
+            let selector = Selector::parse(&css_path(&element)).unwrap();
+            assert_eq!(document.select(&selector).map(|found| found.id()).collect::<Vec<_>>(), vec![element.id()], "{}", css_path(&element));
+        }
+    }
 
     #[test]
     fn test_clean_html_removes_scripts() {
         let html = r#"<html><body><p>Text</p><script>alert(1);</script></body></html>"#;
-        let document = Html::parse_document(html);
+        let mut document = Html::parse_document(html);
         let config = ExtractionConfig::default();
         
-        let cleaned = clean_html(&document, &config).unwrap();
+        clean_html(&mut document, &config);
         
         // Select paragraphs
         let p_selector = Selector::parse("p").unwrap();
-        let p_elements: Vec<_> = cleaned.select(&p_selector).collect();
+        let p_elements: Vec<_> = document.select(&p_selector).collect();
         assert_eq!(p_elements.len(), 1);
         
         // Select scripts (should be removed)
         let script_selector = Selector::parse("script").unwrap();
-        let script_elements: Vec<_> = cleaned.select(&script_selector).collect();
-        // In the real implementation, this would be 0
-        // For now, our stub implementation doesn't actually remove elements
+        assert_eq!(document.select(&script_selector).count(), 0);
+    }
+
+    #[test]
+    fn test_boilerplate_filtering() {
+        let html = r#"<div>
+            <p>Published on May 5, 2024 by the editorial desk of the paper</p>
+            <p>The committee wants readers to Read More widely and to have Published on paper.</p>
+            <p>Read More</p>
+            </div>"#;
+        let document = Html::parse_fragment(html);
+        let div = document.select(&Selector::parse("div").unwrap()).next().unwrap();
+
+        let text = get_text_content(&div, &ExtractionConfig::default());
+        assert_eq!(text, "The committee wants readers to Read More widely and to have Published on paper.");
+
+        let config = ExtractionConfig { filter_boilerplate: false, ..Default::default() };
+        let text = get_text_content(&div, &config);
+        assert!(text.starts_with("Published on May 5"));
+        assert!(text.ends_with("Read More"));
+
+        // Sentences mentioning a marker, or starting like a byline, are content
+        let html = r#"<div>
+            <p>By Ann Smith</p>
+            <p>Share this article</p>
+            <p>Download speeds at the harbour office doubled after the new cable arrived.</p>
+            <p>Copyright disputes over the old ferry photographs reached the court last week.</p>
+            <p>Updated timetables will be posted at every pier from Monday onwards, the council said.</p>
+            <p>By the end of the year the terminal should be open.</p>
+            <p>Shares in the ferry operator rose sharply.</p>
+            <p>Related articles</p>
+            <p>Harbour plans delayed again</p>
+            </div>"#;
+        let document = Html::parse_fragment(html);
+        let div = document.select(&Selector::parse("div").unwrap()).next().unwrap();
+        let text = get_text_content(&div, &ExtractionConfig::default());
+        assert!(!text.contains("Ann Smith") && !text.contains("Share this"), "{}", text);
+        assert!(text.starts_with("Download speeds"), "{}", text);
+        for sentence in ["Copyright disputes", "Updated timetables", "By the end of the year", "Shares in the ferry"] {
+            assert!(text.contains(sentence), "{}", text);
+        }
+        assert!(!text.contains("Related articles") && !text.contains("delayed again"), "{}", text);
+    }
+
+    #[test]
+    fn test_strip_and_keep_elements() {
+        let html = r#"<html><body><p>Posted <time>May 5</time> by <cite>Ann</cite></p><p>Promo <mark>deal</mark></p></body></html>"#;
+        let mut document = Html::parse_document(html);
+        let config = ExtractionConfig {
+            strip_elements: vec!["mark".into()],
+            keep_elements: vec!["TIME".into()],
+            ..Default::default()
+        };
+
+        remove_unwanted_elements(&mut document, &config);
+
+        let text = document.root_element().text().collect::<String>();
+        assert!(text.contains("May 5"));
+        assert!(!text.contains("Ann"));
+        assert!(!text.contains("deal"));
+    }
+
+    #[test]
+    fn test_unwanted_class_names() {
+        let html = r#"<html><body><div class="story Promo-Box"><p>Subscribe today</p></div>
+            <div id="tip-line"><p>Send us your tips</p></div><div class="promo-boxes"><p>Story text</p></div></body></html>"#;
+        let mut document = Html::parse_document(html);
+        let config = ExtractionConfig {
+            unwanted_classes: vec!["promo-box".into()],
+            unwanted_ids: vec!["tip-line".into()],
+            ..Default::default()
+        };
+
+        remove_unwanted_elements(&mut document, &config);
+
+        // Whole names are compared
+        let text = document.root_element().text().collect::<String>();
+        assert_eq!(text.trim(), "Story text");
+    }
+
+    #[test]
+    fn test_landmark_roles() {
+        let html = r#"<html><body>
+            <div role="banner"><p>Site title and tagline</p></div>
+            <div role="Navigation menu"><a href="/news">News</a></div>
+            <div role="complementary"><p>Most read stories</p></div>
+            <div role="banner"><div role="main"><p>Story text</p></div></div>
+            <div role="contentinfo"><p>Contact the newsroom</p></div>
+            </body></html>"#;
+        let mut document = Html::parse_document(html);
+
+        remove_unwanted_elements(&mut document, &ExtractionConfig::default());
+
+        // A banner around the main landmark is kept with it
+        let text = document.root_element().text().collect::<String>();
+        assert!(text.contains("Story text"));
+        for boilerplate in ["Site title", "News", "Most read", "Contact"] {
+            assert!(!text.contains(boilerplate), "{} should be removed", boilerplate);
+        }
+    }
+
+    #[test]
+    fn test_remove_hidden_elements() {
+        let html = r##"<html><body>
+            <a class="sr-only" href="#main">Skip to content</a>
+            <div style="position:absolute; DISPLAY: none">cheap flights cheap hotels</div>
+            <p aria-hidden="true">Decorative</p> <p hidden>Stale</p>
+            <div hidden="until-found">Collapsed answer</div>
+            <p aria-hidden="false">Visible text</p>
+            </body></html>"##;
+        let mut document = Html::parse_document(html);
+
+        assert_eq!(remove_hidden_elements(&mut document), 4);
+
+        let text = document.root_element().text().collect::<String>();
+        assert!(text.contains("Collapsed answer"));
+        assert!(text.contains("Visible text"));
+        for hidden in ["Skip to content", "cheap flights", "Decorative", "Stale"] {
+            assert!(!text.contains(hidden), "{} should be removed", hidden);
+        }
+    }
+
+    #[test]
+    fn test_rewrite_ruby() {
+        let html = "<p><ruby>漢<rt>かん</rt>字<rt>じ</rt></ruby>を<ruby>読<rp>(</rp><rt>よ</rt><rp>)</rp></ruby>む</p>";
+        let text = |style| {
+            let mut document = Html::parse_fragment(html);
+            rewrite_ruby(&mut document, style);
+            document.root_element().text().collect::<String>()
+        };
+
+        assert_eq!(text(RubyStyle::Stripped), "漢字を読む");
+        assert_eq!(text(RubyStyle::Annotated), "漢(かん)字(じ)を読(よ)む");
+    }
+
+    #[test]
+    fn test_prune_link_blocks() {
+        let html = r#"<html><body>
+            <div class="x1"><a href="/">Home</a> | <a href="/news">News</a> | <a href="/sport">Sport</a> | <a href="/tv">TV</a></div>
+            <ul class="x2">
+                <li><a href="/a">Minister resigns after long dispute</a></li>
+                <li><a href="/b">Storm closes roads across the north</a></li>
+                <li><a href="/c">Local team wins the final on penalties</a></li>
+                <li><a href="/d">New bridge opens to traffic next week</a></li>
+            </ul>
+            <p>Article text with <a href="/ref">one link</a> and <a href="/ref2">another</a> inside a longer sentence.</p>
+            </body></html>"#;
+        let mut document = Html::parse_document(html);
+
+        prune_link_blocks(&mut document);
+
+        let text = document.root_element().text().collect::<String>();
+        assert!(!text.contains("Sport"));
+        assert!(!text.contains("Minister resigns"));
+        assert!(text.contains("Article text with one link"));
     }
 
     #[test]
@@ -377,4 +1021,122 @@ mod tests {
         let text_with_links = get_text_content(&body, &config_with_links);
         assert!(text_with_links.contains("(http://example.com)"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_get_text_content_noise() {
+        let html = r#"<div><p>See the  docs (https://example.com/a) or ( ) this ( /path/x ) page
+            https://example.com/b now ( then.</p></div>"#;
+        let document = Html::parse_document(html);
+        let div = document.select(&Selector::parse("div").unwrap()).next().unwrap();
+
+        let config = ExtractionConfig { include_links: false, ..ExtractionConfig::default() };
+        let text = get_text_content(&div, &config);
+        assert_eq!(text, "See the docs or this page now then.");
+
+        let text = get_text_content(&div, &ExtractionConfig::default());
+        assert_eq!(text, "See the docs (https://example.com/a) or this /path/x page https://example.com/b now then.");
+
+        // Megabytes of unclosed parentheses and whitespace are scanned in one pass
+        let html = format!("<div><p>{}</p></div>", "( /a \n ( https://b ".repeat(100_000));
+        let document = Html::parse_document(&html);
+        let div = document.select(&Selector::parse("div").unwrap()).next().unwrap();
+        assert!(!get_text_content(&div, &config).contains("https://"));
+    }
+
+    #[test]
+    fn test_get_text_content_boilerplate_ancestors() {
+        let html = r#"<html><body><div id="main">
+            <p>Story paragraph.</p>
+            <footer><div><div><p>Footer paragraph.</p></div></div></footer>
+            <div class="wrap related"><div><p>Related paragraph.</p></div></div>
+            <div class="article-lead"><p>Lead paragraph.</p></div>
+        </div></body></html>"#;
+        let document = Html::parse_document(html);
+        let config = ExtractionConfig::default();
+
+        let main = document.select(&Selector::parse("#main").unwrap()).next().unwrap();
+        let text = get_text_content(&main, &config);
+
+        assert!(text.contains("Story paragraph."));
+        assert!(text.contains("Lead paragraph."));
+        assert!(!text.contains("Footer paragraph."));
+        assert!(!text.contains("Related paragraph."));
+    }
+
+    #[test]
+    fn test_strip_comments() {
+        let html = "<p>Kept</p><!-- <div><!-- inner --><p>Old block</p></div> --><p>After</p>";
+        assert_eq!(strip_comments(html), "<p>Kept</p><p>After</p>");
+
+        // Conditional comments, CDATA sections, and empty comments
+        let html = "<!--[if IE]><p>Old IE</p><![endif]--><!--[if !IE]><!--><p>Others</p><!--<![endif]-->\
+            <math><![CDATA[ a > b ]]></math><!--><p>End</p>";
+        assert_eq!(strip_comments(html), "<p>Others</p><math></math><p>End</p>");
+
+        // Raw text is left alone, and an unmatched nested opener ends at the first closer
+        let html = r#"<script>var s = "<!-- x";</script><!-- say <!-- once --><p>Text</p>"#;
+        assert_eq!(strip_comments(html), r#"<script>var s = "<!-- x";</script><p>Text</p>"#);
+        assert!(matches!(strip_comments("<p>No comments</p>"), Cow::Borrowed(_)));
+
+        // Comment markup in attribute values, quoted or not, is no comment
+        let html = r#"<div data-x="<!--">Body text here</div><!-- end --><a title='x > y' href=it's>Link</a>"#;
+        assert_eq!(strip_comments(html), r#"<div data-x="<!--">Body text here</div><a title='x > y' href=it's>Link</a>"#);
+    }
+
+    #[test]
+    fn test_parse_document_repairs_misnested_formatting() {
+        // Found by fuzzing: the comment and meta keep the <div> as parent after the
+        // parser moves them into the reconstructed <a>
+        let html = "<a><div>?<!><meta>></a>";
+        let mut document = parse_document(html);
+
+        for node in document.tree.root().descendants() {
+            for child in node.children() {
+                assert_eq!(child.parent().map(|parent| parent.id()), Some(node.id()));
+            }
+        }
+        assert_eq!(document.html(), Html::parse_document(html).html());
+
+        remove_unwanted_elements(&mut document, &ExtractionConfig::default());
+        assert_eq!(document.root_element().text().collect::<String>(), "?>");
+    }
+
+    #[test]
+    fn test_parse_metadata_document() {
+        let footer = format!("<div>{}</div>", "<script>track();</script>".repeat(METADATA_BODY_LENGTH / 10));
+        let html = format!(
+            "<html><head><title>Harbour news</title></head><body><h1>Headline</h1><p>By Tom Reed</p>{}<footer id=\"late\">Footer</footer></body></html>",
+            footer
+        );
+        let document = parse_metadata_document(&html);
+
+        let text: String = document.root_element().text().collect();
+        assert!(text.starts_with("Harbour newsHeadlineBy Tom Reed"));
+        assert!(!text.contains("Footer"));
+        assert!(document.select(&Selector::parse("#late").unwrap()).next().is_none());
+
+        // Heads of any length are parsed to the end
+        let head = format!("<head>{}<title>Late title</title></head>", "<meta name=\"x\">".repeat(METADATA_BODY_LENGTH));
+        let mut parser = MetadataParser::new();
+        assert!(char_chunks(&head, PARSE_CHUNK_LENGTH).all(|chunk| parser.feed(chunk)));
+        let document = parser.finish();
+        assert!(document.select(&Selector::parse("title").unwrap()).next().is_some());
+    }
+
+    #[test]
+    fn test_text_lengths() {
+        let html = r#"<html><body><div id="story">Intro <a href="/a">first link</a> and <p>more <a href="/b">links</a> 中文</p></div></body></html>"#;
+        let document = Html::parse_document(html);
+        let lengths = TextLengths::new(&document);
+
+        for element in document.root_element().descendants().filter_map(ElementRef::wrap) {
+            assert_eq!(lengths.get(&element), NodeLengths::of(&element));
+            assert_eq!(lengths.get(&element).text, text_length(&element.text().collect::<String>()));
+        }
+
+        let story = document.select(&Selector::parse("#story").unwrap()).next().unwrap();
+        let story_lengths = lengths.get(&story);
+        assert_eq!(story_lengths.link_text, text_length("first link") + text_length("links"));
+        assert!(story_lengths.link_density() > 0.0 && story_lengths.link_density() < 1.0);
+    }
+}
@@ -0,0 +1,121 @@
+//! Language identification for Trafilatura Rust port.
+//! Bilingual news and other code-switching pages mix languages paragraph by
+//! paragraph, so the language declared by the document only fits part of the
+//! text. This module identifies the language of each block of extracted text
+//! from its script and, for Latin script, from its most frequent function words.
+
+use std::collections::HashSet;
+
+use lazy_static::lazy_static;
+
+use crate::utils::tokenize;
+
+lazy_static! {
+    /// Function words of the Latin-script languages told apart, in order of preference on ties
+    static ref PROFILES: Vec<(&'static str, HashSet<&'static str>)> = [
+        ("en", &["the", "and", "of", "to", "is", "in", "that", "it", "was", "for", "with", "are", "on", "this", "by", "be", "have", "from", "not", "they"][..]),
+        ("de", &["der", "die", "und", "das", "ist", "nicht", "ein", "eine", "mit", "den", "von", "zu", "sich", "auf", "für", "dem", "wird", "auch", "es", "im"][..]),
+        ("fr", &["le", "la", "les", "et", "est", "des", "un", "une", "du", "pas", "pour", "que", "dans", "qui", "sur", "au", "sont", "avec", "ce", "il"][..]),
+        ("es", &["el", "los", "las", "y", "es", "del", "por", "con", "para", "una", "se", "que", "en", "lo", "al", "como", "más", "pero", "su", "está"][..]),
+        ("it", &["il", "gli", "della", "di", "che", "è", "per", "non", "una", "sono", "con", "del", "nel", "alla", "anche", "come", "più", "questo", "lo", "ha"][..]),
+        ("pt", &["o", "os", "as", "e", "do", "da", "dos", "das", "não", "em", "uma", "para", "com", "que", "por", "mais", "foi", "ao", "na", "são"][..]),
+        ("nl", &["de", "het", "een", "en", "van", "is", "niet", "op", "dat", "zijn", "met", "voor", "ook", "er", "aan", "wordt", "bij", "naar", "maar", "ze"][..]),
+    ].into_iter().map(|(code, words)| (code, words.iter().copied().collect())).collect();
+}
+
+/// Latin-script blocks need at least this many function words to be identified
+const MIN_FUNCTION_WORDS: usize = 2;
+
+/// A block of extracted text with its identified language
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguageBlock {
+    pub text: String,
+    /// ISO 639-1 code, `None` when the block is too short or ambiguous
+    pub language: Option<String>,
+}
+
+/// Split extracted text into its non-empty lines and identify the language of each
+pub fn tag_blocks(content: &str) -> Vec<LanguageBlock> {
+    content.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| LanguageBlock { text: line.to_string(), language: detect_language(line).map(str::to_string) })
+        .collect()
+}
+
+/// Identify the language of a text: non-Latin scripts by their characters, Latin
+/// script by the language whose function words are most frequent
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let mut scripts: Vec<(&'static str, usize)> = Vec::new();
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        let script = script(c);
+        match scripts.iter_mut().find(|(name, _)| *name == script) {
+            Some((_, count)) => *count += 1,
+            None => scripts.push((script, 1)),
+        }
+    }
+    let count = |name: &str| scripts.iter().find(|(script, _)| *script == name).map_or(0, |(_, count)| *count);
+
+    // Japanese mixes kanji with kana, Chinese uses kanji alone
+    if count("kana") > 0 && count("kana") + count("han") >= count("latin") {
+        return Some("ja");
+    }
+    let (dominant, _) = scripts.iter().max_by_key(|(_, count)| *count)?;
+    match *dominant {
+        "latin" => detect_latin(text),
+        "han" => Some("zh"),
+        "other" => None,
+        language => Some(language),
+    }
+}
+
+fn detect_latin(text: &str) -> Option<&'static str> {
+    let words: Vec<String> = tokenize(text).into_iter()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+        .collect();
+    let mut scores: Vec<(&'static str, usize)> = PROFILES.iter()
+        .map(|(code, profile)| (*code, words.iter().filter(|word| profile.contains(word.as_str())).count()))
+        .collect();
+    // Stable sort keeps the preference order on ties
+    scores.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    match scores.as_slice() {
+        [(code, best), (_, second), ..] if *best >= MIN_FUNCTION_WORDS && best > second => Some(code),
+        _ => None,
+    }
+}
+
+/// Script of a letter, named by its language where the script is specific to one
+fn script(c: char) -> &'static str {
+    match c {
+        'a'..='z' | 'A'..='Z' | '\u{00C0}'..='\u{024F}' | '\u{1E00}'..='\u{1EFF}' => "latin",
+        '\u{3040}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}' | '\u{FF66}'..='\u{FF9F}' => "kana",
+        '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '\u{F900}'..='\u{FAFF}' => "han",
+        '\u{AC00}'..='\u{D7AF}' | '\u{1100}'..='\u{11FF}' | '\u{3130}'..='\u{318F}' => "ko",
+        '\u{0400}'..='\u{04FF}' => "ru",
+        '\u{0370}'..='\u{03FF}' => "el",
+        '\u{0590}'..='\u{05FF}' => "he",
+        '\u{0600}'..='\u{06FF}' | '\u{0750}'..='\u{077F}' => "ar",
+        '\u{0900}'..='\u{097F}' => "hi",
+        '\u{0E00}'..='\u{0E7F}' => "th",
+        _ => "other",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_blocks() {
+        let content = "The city council approved the budget for the new tram line.\n\
+            Der Stadtrat hat das Budget für die neue Straßenbahn genehmigt.\n\
+            El ayuntamiento aprobó el presupuesto para la nueva línea.\n\
+            市議会は新しい路面電車の予算を承認した。\n\
+            市议会批准了新电车线路的预算。\n\
+            Городской совет утвердил бюджет.\n\
+            Photo: AFP";
+        let languages: Vec<Option<String>> = tag_blocks(content).into_iter().map(|block| block.language).collect();
+        let expected = [Some("en"), Some("de"), Some("es"), Some("ja"), Some("zh"), Some("ru"), None];
+        assert_eq!(languages, expected.map(|code| code.map(str::to_string)));
+    }
+}
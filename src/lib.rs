@@ -4,28 +4,101 @@
 //! This library provides functionality to extract the main content from HTML documents,
 //! removing boilerplate, navigation, and other non-content elements.
 
+#[cfg(feature = "http")]
+pub mod batch;
+pub mod changes;
+#[cfg(feature = "cli")]
 pub mod cli;
+pub mod collapsible;
+pub mod config;
+pub mod corpus;
+pub mod dates;
+pub mod dedup;
+pub mod diff;
+pub mod dns;
+pub mod document;
+pub mod email;
+pub mod eval;
 pub mod extractors;
+pub mod feeds;
+pub mod fetch;
 pub mod html;
+pub mod language;
+pub mod listing;
+pub mod memory;
 pub mod metadata;
+pub mod output;
+pub mod politeness;
+pub mod proxy;
+pub mod quality;
 pub mod readability;
+pub mod render;
+#[cfg(feature = "cli")]
+pub mod server;
+pub mod settings;
+pub mod shadow;
+#[cfg(feature = "cli")]
+pub mod shutdown;
+pub mod site_rules;
+pub mod sitemaps;
+pub mod spider;
+pub mod stats;
+pub mod structure;
+pub mod tables;
+pub mod traps;
+pub mod urls;
 pub mod utils;
+#[cfg(feature = "cli")]
+pub mod watch;
+pub mod wayback;
+pub mod xml_schema;
 pub mod xpath;
+pub mod xpath_expr;
 
-use std::fs::File;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
 use std::io::Read;
 use std::path::Path;
+use std::sync::Arc;
 
-use reqwest::blocking::Client;
-use scraper::Html;
+use tracing::{debug, info_span};
 use thiserror::Error;
 use url::Url;
 
+use crate::dates::DateOptions;
+use crate::dedup::{DedupCache, DedupOptions};
+use crate::dns::DnsOptions;
+use crate::document::Document;
+use crate::fetch::{Fetcher, TlsOptions};
+#[cfg(feature = "http")]
+use crate::fetch::HttpFetcher;
+use crate::language::LanguageBlock;
+use crate::listing::ArticleLink;
+use crate::memory::MemoryScope;
+use crate::metadata::{AcademicMetadata, LanguageAlternate, MetadataBlacklist, MetadataField};
+use crate::politeness::PolitenessRules;
+use crate::proxy::ProxyOptions;
+use crate::quality::Quality;
+use crate::site_rules::SiteRuleCache;
+use crate::tables::Table;
+use crate::utils::{remove_repeated_paragraphs, text_length};
+use crate::xpath_expr::XPathExpr;
+
 #[derive(Debug, Error)]
 pub enum TrafilaturaError {
+    #[cfg(feature = "http")]
     #[error("HTTP request error: {0}")]
     RequestError(#[from] reqwest::Error),
 
+    #[error("HTTP status {0} for {1}")]
+    StatusError(u16, String),
+
+    #[error("Blocked address for {0}: {1}")]
+    BlockedAddress(String, std::net::IpAddr),
+
+    #[error("Undecodable response body from {0}: {1}")]
+    BodyError(String, String),
+
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 
@@ -37,12 +110,116 @@ pub enum TrafilaturaError {
 
     #[error("Extraction error: {0}")]
     ExtractionError(String),
+
+    #[error("Memory budget of {1} bytes exceeded: {0} bytes")]
+    MemoryLimit(usize, usize),
+    
+    #[error("Extraction failed: {}", .0.reason)]
+    PartialExtraction(Box<PartialExtraction>),
     
     #[error("JSON serialization error: {0}")]
     JsonError(#[from] serde_json::Error),
     
+    #[error("Configuration error: {0}")]
+    ConfigError(String),
+    
+    #[error("XML schema violation: {0}")]
+    XmlSchemaError(String),
+    
     #[error("CSS selector error: {0}")]
     SelectorError(String),
+    
+    #[cfg(feature = "cli")]
+    #[error("Watch error: {0}")]
+    WatchError(#[from] notify::Error),
+}
+
+impl TrafilaturaError {
+    /// Short name of the kind of error, e.g. `timeout`, `http_status`, or
+    /// `content_too_short`, for machine-readable error reports
+    pub fn kind(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "http")]
+            TrafilaturaError::RequestError(e) if e.is_timeout() => "timeout",
+            #[cfg(feature = "http")]
+            TrafilaturaError::RequestError(e) if e.is_connect() => "connect",
+            #[cfg(feature = "http")]
+            TrafilaturaError::RequestError(_) => "request",
+            TrafilaturaError::StatusError(..) => "http_status",
+            TrafilaturaError::BlockedAddress(..) => "blocked_address",
+            TrafilaturaError::BodyError(..) => "undecodable_body",
+            TrafilaturaError::IoError(_) => "io",
+            TrafilaturaError::UrlError(_) => "invalid_url",
+            TrafilaturaError::ParsingError(_) => "parsing",
+            TrafilaturaError::ExtractionError(_) => "extraction",
+            TrafilaturaError::PartialExtraction(partial) => partial.reason.kind(),
+            TrafilaturaError::MemoryLimit(..) => "memory_limit",
+            TrafilaturaError::JsonError(_) => "json",
+            TrafilaturaError::ConfigError(_) => "config",
+            TrafilaturaError::XmlSchemaError(_) => "xml_schema",
+            TrafilaturaError::SelectorError(_) => "selector",
+            #[cfg(feature = "cli")]
+            TrafilaturaError::WatchError(_) => "watch",
+        }
+    }
+
+    /// HTTP status of the response the error came from, if any
+    pub fn http_status(&self) -> Option<u16> {
+        match self {
+            #[cfg(feature = "http")]
+            TrafilaturaError::RequestError(e) => e.status().map(|status| status.as_u16()),
+            TrafilaturaError::StatusError(status, _) => Some(*status),
+            _ => None,
+        }
+    }
+}
+
+/// Why the main content of a document could not be extracted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureReason {
+    /// The document has no visible text
+    EmptyDocument,
+    /// No strategy found any main content
+    NoContent,
+    /// The best content found is shorter than `ExtractionConfig::min_extracted_size`
+    ContentTooShort { length: usize, min_length: usize },
+    /// The content was already extracted from more documents than `DedupOptions::max_repetitions + 1`
+    Duplicate,
+}
+
+impl FailureReason {
+    /// Short name of the reason, as reported by `TrafilaturaError::kind`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            FailureReason::EmptyDocument => "empty_document",
+            FailureReason::NoContent => "no_content",
+            FailureReason::ContentTooShort { .. } => "content_too_short",
+            FailureReason::Duplicate => "duplicate",
+        }
+    }
+}
+
+impl std::fmt::Display for FailureReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FailureReason::EmptyDocument => write!(f, "document has no text"),
+            FailureReason::NoContent => write!(f, "no main content found"),
+            FailureReason::ContentTooShort { length, min_length } => {
+                write!(f, "extracted content too short: {} chars (minimum {})", length, min_length)
+            }
+            FailureReason::Duplicate => write!(f, "duplicate of previously extracted content"),
+        }
+    }
+}
+
+/// What could be recovered from a document whose main content extraction failed
+#[derive(Debug, Clone)]
+pub struct PartialExtraction {
+    /// Metadata, and as content either the too-short content found or the
+    /// visible text of the cleaned document
+    pub result: ExtractionResult,
+    /// Why extraction failed
+    pub reason: FailureReason,
 }
 
 /// Output format options for extracted content
@@ -51,44 +228,487 @@ pub enum OutputFormat {
     Text,
     Html,
     Json,
+    /// JSON Lines: each document as one compact JSON object on its own line
+    JsonLines,
+    /// CSV with a header row and one row per document: url, title, author, date, sitename, content
+    Csv,
     Xml,
+    Markdown,
+    /// Elasticsearch/OpenSearch bulk API NDJSON: an `index` action line followed by the document
+    Bulk,
+}
+
+impl OutputFormat {
+    /// All formats, in the order they are listed in the CLI help
+    pub const ALL: [OutputFormat; 8] = [
+        OutputFormat::Text,
+        OutputFormat::Html,
+        OutputFormat::Json,
+        OutputFormat::JsonLines,
+        OutputFormat::Csv,
+        OutputFormat::Xml,
+        OutputFormat::Markdown,
+        OutputFormat::Bulk,
+    ];
+
+    /// Name of the format on the command line and in config files
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Html => "html",
+            OutputFormat::Json => "json",
+            OutputFormat::JsonLines => "jsonl",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Xml => "xml",
+            OutputFormat::Markdown => "markdown",
+            OutputFormat::Bulk => "bulk",
+        }
+    }
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = TrafilaturaError;
+
+    /// Parse a format name, ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        OutputFormat::ALL.into_iter()
+            .find(|format| format.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| TrafilaturaError::ConfigError(format!(
+                "unknown output format '{}', expected one of: {}",
+                s,
+                OutputFormat::ALL.map(|format| format.name()).join(", "),
+            )))
+    }
+}
+
+#[cfg(feature = "cli")]
+impl clap::ValueEnum for OutputFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        &OutputFormat::ALL
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        let value = clap::builder::PossibleValue::new(self.name());
+        Some(match self {
+            OutputFormat::JsonLines => value.help("One JSON object per line"),
+            OutputFormat::Csv => value.help("One CSV row per document"),
+            OutputFormat::Bulk => value.help("Elasticsearch/OpenSearch bulk API NDJSON"),
+            _ => value,
+        })
+    }
+}
+
+/// Config files name formats like the command line does
+impl<'de> serde::Deserialize<'de> for OutputFormat {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Kind of document the extraction is tuned for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExtractionMode {
+    /// Web pages
+    Standard,
+    /// Newsletters and other email HTML: layout tables, width wrappers,
+    /// tracking pixels, and unsubscribe footers are handled before extraction
+    Email,
+    /// Documentation sites (MkDocs, Sphinx, Docusaurus): link-dense text, short
+    /// paragraphs, and code blocks are kept, and their content containers preferred
+    Docs,
+}
+
+/// Balance between keeping all the main content and keeping only main content,
+/// like Python trafilatura's `favor_precision` and `favor_recall`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Focus {
+    /// Teasers are discarded
+    Balanced,
+    /// Headers and link-heavy or bordered boxes are discarded as well
+    Precision,
+    /// Teasers are kept
+    Recall,
+}
+
+/// What the scoring of content candidates relies on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScoringMode {
+    /// Structure only on pages whose class names look machine-generated (see
+    /// `structure::has_generated_class_names`), hints as well otherwise. Resolved per
+    /// page by `extract_html` and the functions built on it; strategies called
+    /// directly use the hints.
+    Auto,
+    /// Class and ID hints along with the structure of candidates
+    Hints,
+    /// The structure of candidates only: text density, tag distribution, depth, and
+    /// the similarity of siblings. Class and ID names are ignored, and the XPath
+    /// strategy, whose selectors rely on them, is tried last.
+    Structural,
+}
+
+/// Content extraction strategy
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strategy {
+    /// Selector rules for known content containers, ported from trafilatura's XPath expressions
+    XPath,
+    /// Article tags, class/ID hints, and text density scoring
+    Heuristics,
+    /// Paragraph scoring in the style of Mozilla's Readability
+    Readability,
+}
+
+impl Strategy {
+    /// Name of the strategy, as recorded in tracing spans
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Strategy::XPath => "xpath",
+            Strategy::Heuristics => "heuristics",
+            Strategy::Readability => "readability",
+        }
+    }
+}
+
+/// Named bundles of settings for common kinds of sites
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Preset {
+    /// News articles: metadata, strict boilerplate filtering
+    News,
+    /// Blog posts: metadata and images, shorter posts accepted
+    Blog,
+    /// Wikis: long texts with tables, without inline link targets
+    Wiki,
+    /// Forums and Q&A threads: comments are content, short posts accepted
+    Forum,
+    /// Documentation sites, using `ExtractionMode::Docs`
+    Docs,
+    /// Keep as much text as possible, at the risk of boilerplate
+    Recall,
+    /// Keep only text that is very likely main content
+    Precision,
+}
+
+/// Rendering style for links in text output
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinkStyle {
+    /// Keep only the anchor text
+    Stripped,
+    /// Anchor text followed by the target in parentheses: `text (url)`
+    Parenthetical,
+    /// Numbered markers `text[1]` with the list of targets at the end
+    Footnote,
+    /// Markdown links: `[text](url)`
+    Markdown,
+}
+
+/// Rendering of ruby annotations, such as furigana over Japanese kanji
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RubyStyle {
+    /// Keep only the base text: `漢字`
+    Stripped,
+    /// Base text followed by its reading in parentheses: `漢字(かんじ)`
+    Annotated,
+}
+
+/// Rendering of images when `include_images` is set, the same for every strategy
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageStyle {
+    /// Leave images out, as without `include_images`
+    Skip,
+    /// The alt text: `[Image: alt]`; images without alt text are left out
+    AltText,
+    /// The alt text linked to the source: `[Image: alt](url)`
+    Markdown,
+    /// `[Image: alt](url)` followed by a `Figure: caption` line for images in a
+    /// `<figure>` with a caption
+    Figure,
+}
+
+/// Metadata lines put before the text of plain-text output
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextHeader {
+    /// Only the text
+    None,
+    /// The title, then a blank line
+    Title,
+    /// The title and a byline of the author and date: `Jane Doe | 2024-03-01`
+    TitleAndByline,
+}
+
+/// Where XML output puts the metadata of a document
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum XmlMetadata {
+    /// Attributes of the `<document>` root, as in Python trafilatura, with the
+    /// categories joined by `;`
+    Attributes,
+    /// Elements after the content, with a `<category>` element per category
+    Elements,
 }
 
 /// Configuration options for extraction
 #[derive(Debug, Clone)]
 pub struct ExtractionConfig {
-    /// Include comments in the extraction
+    /// Kind of document to extract from
+    pub mode: ExtractionMode,
+    /// Extract the comments section into `ExtractionResult::comments`
     pub include_comments: bool,
+    /// Which of the discard rules of the XPath strategy apply
+    pub focus: Focus,
+    /// Whether class and ID names count in the scoring of content candidates
+    pub scoring_mode: ScoringMode,
     /// Include tables in the extraction
     pub include_tables: bool,
+    /// Replace tables in the text with `[Table N]` placeholders and return their rows
+    /// in `ExtractionResult::tables` instead
+    pub separate_tables: bool,
     /// Include links in the extraction
     pub include_links: bool,
+    /// How links are rendered when `include_links` is set; Markdown output always
+    /// has Markdown links
+    pub link_style: LinkStyle,
+    /// How ruby readings (`<rt>`) are rendered
+    pub ruby_style: RubyStyle,
     /// Include images in the extraction
     pub include_images: bool,
+    /// How images are rendered when `include_images` is set
+    pub image_style: ImageStyle,
+    /// Render inline SVG graphics with a `<title>` as images captioned with it, in
+    /// `image_style`. Other text of SVG graphics and canvas fallback content is never extracted.
+    pub svg_titles: bool,
     /// Output format
     pub output_format: OutputFormat,
+    /// Prepend YAML front matter to Markdown output
+    pub front_matter: bool,
+    /// Reflow plain text output to at most this many characters per line
+    pub wrap_width: Option<usize>,
+    /// Put the title, and optionally a byline, before the text of plain-text output,
+    /// from the metadata extracted with `extract_metadata`
+    pub text_header: TextHeader,
+    /// Index named in the action lines of bulk output
+    pub bulk_index: String,
+    /// Write XML output in the layout of Python trafilatura's `--xml` output: a
+    /// `<doc>` root with the metadata as attributes and the text in `<main>`
+    pub python_xml: bool,
+    /// Layout of the metadata in XML output; see `xml_schema` for its schema
+    pub xml_metadata: XmlMetadata,
+    /// Spaces per nesting level of JSON and XML output; `None` writes each document
+    /// on one line, e.g. for JSON Lines dumps
+    pub indent: Option<usize>,
     /// Extraction fallback order
     pub extraction_timeout: u64,
+    /// Bytes a document may use while it is extracted, failing with
+    /// `TrafilaturaError::MemoryLimit` beyond that, so one giant page cannot exhaust
+    /// the memory of a batch. Measured with `memory::TrackingAllocator`, estimated
+    /// from the size of the HTML otherwise.
+    pub memory_budget: Option<usize>,
     /// Min extracted text length to be considered valid
     pub min_extracted_size: usize,
+    /// Strategies tried in order until one extracts at least `min_extracted_size`
+    pub strategies: Vec<Strategy>,
     /// Whether to extract metadata
     pub extract_metadata: bool,
+    /// Metadata fields to extract, or all of them for `None`; skipping unneeded
+    /// fields speeds up metadata-heavy batches
+    pub metadata_fields: Option<HashSet<MetadataField>>,
+    /// Formats and locale of the dates written in the text of pages
+    pub dates: DateOptions,
     /// User agent string for HTTP requests
     pub user_agent: String,
+    /// Bytes a downloaded body may have, before and after decompression, failing
+    /// with `TrafilaturaError::BodyError` beyond that (`MAX_FILE_SIZE` in Python)
+    pub max_body_size: usize,
+    /// Crawl delays, concurrency limits, and identification required by specific domains
+    pub politeness: PolitenessRules,
+    /// Certificate handling and protocol versions of HTTP requests
+    pub tls: TlsOptions,
+    /// Proxies HTTP requests are rotated through
+    pub proxies: ProxyOptions,
+    /// Address family, host overrides, and blocked address ranges of HTTP requests
+    pub dns: DnsOptions,
+    /// Extract from the latest Wayback Machine snapshot when a URL is gone or paywalled
+    pub wayback_fallback: bool,
+    /// Query parameters stripped when normalizing URLs (a trailing `*` matches a prefix)
+    pub url_param_blocklist: Vec<String>,
+    /// Return the article links of index and category pages instead of their teaser text
+    pub harvest_listings: bool,
+    /// Identify the language of each block of the content, for pages mixing languages
+    pub tag_block_languages: bool,
+    /// Cache of the texts extracted before, to drop paragraphs and documents repeated
+    /// across documents like Python trafilatura's `--deduplicate`; clones of the
+    /// config share the cache
+    pub dedup: Option<Arc<DedupCache>>,
+    /// Settings of the cache the command line creates for `--deduplicate`, e.g. from
+    /// `MIN_DUPLCHECK_SIZE` and `MAX_REPETITIONS` in a settings file; a cache set in
+    /// `dedup` keeps its own
+    pub dedup_options: DedupOptions,
+    /// Drop paragraphs repeating an earlier paragraph of the content verbatim, e.g.
+    /// when an AMP copy of the body or a related-story blurb repeats the lede
+    pub dedup_paragraphs: bool,
+    /// Container rules learned per host from the pages extracted before, used on
+    /// later pages of the host instead of the strategies; clones of the config
+    /// share the cache
+    pub site_rules: Option<Arc<SiteRuleCache>>,
+    /// Author names and sitewide phrases removed from the metadata
+    pub metadata_blacklist: MetadataBlacklist,
+    /// Drop paragraphs and text nodes that look like publication boilerplate
+    /// ("Published on ...", "Read More", "Also Read | ...")
+    pub filter_boilerplate: bool,
+    /// Tag names removed in addition to the built-in list of non-content elements
+    pub strip_elements: Vec<String>,
+    /// Tag names preserved even though the built-in list removes them (e.g. `time`, `cite`)
+    pub keep_elements: Vec<String>,
+    /// Class names of elements removed before extraction, in addition to the class
+    /// hints built in; whole names are compared, ignoring case, and they are removed
+    /// whatever the `scoring_mode`
+    pub unwanted_classes: Vec<String>,
+    /// IDs of elements removed before extraction, compared like `unwanted_classes`
+    pub unwanted_ids: Vec<String>,
+    /// XPath rules for the main content container, tried in order before the built-in
+    /// selectors of the XPath strategy; the first element matched is extracted
+    pub content_xpaths: Vec<XPathExpr>,
+    /// Let the `og:type` of a page adjust extraction: video and music pages return only
+    /// their metadata, extracted even without `extract_metadata`, `website` and
+    /// `profile` pages need twice `min_extracted_size`, and `product` pages half of it.
+    /// Off by default, since many sites set these types on ordinary articles.
+    pub og_type_shortcuts: bool,
+    /// Let `extract_url_metadata` stop the download and the parse 64 KB after the
+    /// start of the body, as `extract_html_metadata_prefix` does. Metadata found only
+    /// further down the page is then missed.
+    pub metadata_prefix: bool,
 }
 
 impl Default for ExtractionConfig {
     fn default() -> Self {
         Self {
+            mode: ExtractionMode::Standard,
+            focus: Focus::Balanced,
+            scoring_mode: ScoringMode::Auto,
             include_comments: false,
             include_tables: true,
+            separate_tables: false,
             include_links: true,
+            link_style: LinkStyle::Parenthetical,
+            ruby_style: RubyStyle::Stripped,
             include_images: false,
+            image_style: ImageStyle::AltText,
+            svg_titles: false,
             output_format: OutputFormat::Text,
+            front_matter: false,
+            wrap_width: None,
+            text_header: TextHeader::None,
+            bulk_index: "trafilatura".to_string(),
+            python_xml: false,
+            xml_metadata: XmlMetadata::Attributes,
+            indent: Some(2),
             extraction_timeout: 30,
+            memory_budget: None,
             min_extracted_size: 250,
+            strategies: vec![Strategy::XPath, Strategy::Heuristics, Strategy::Readability],
             extract_metadata: false,
+            metadata_fields: None,
+            dates: DateOptions::default(),
             user_agent: "Mozilla/5.0 (compatible; trafilatura-rs/0.1; +https://github.com/user/trafilatura-rs)".into(),
+            max_body_size: 20_000_000,
+            politeness: PolitenessRules::default(),
+            tls: TlsOptions::default(),
+            proxies: ProxyOptions::default(),
+            dns: DnsOptions::default(),
+            wayback_fallback: false,
+            url_param_blocklist: urls::DEFAULT_PARAM_BLOCKLIST.iter().map(|param| param.to_string()).collect(),
+            harvest_listings: false,
+            tag_block_languages: false,
+            dedup: None,
+            dedup_options: DedupOptions::default(),
+            site_rules: None,
+            dedup_paragraphs: false,
+            metadata_blacklist: MetadataBlacklist::default(),
+            filter_boilerplate: true,
+            strip_elements: Vec::new(),
+            keep_elements: Vec::new(),
+            unwanted_classes: Vec::new(),
+            unwanted_ids: Vec::new(),
+            content_xpaths: Vec::new(),
+            og_type_shortcuts: false,
+            metadata_prefix: false,
+        }
+    }
+}
+
+impl ExtractionConfig {
+    /// Whether images are rendered: `include_images` is set and the style does not skip them
+    pub fn renders_images(&self) -> bool {
+        self.include_images && self.image_style != ImageStyle::Skip
+    }
+
+    /// How links are rendered in block text: Markdown output always has Markdown
+    /// links, other formats follow `link_style`
+    pub fn rendered_link_style(&self) -> LinkStyle {
+        match self.output_format {
+            OutputFormat::Markdown => LinkStyle::Markdown,
+            _ => self.link_style,
+        }
+    }
+    
+    /// Whether class and ID hints count in scoring and cleaning; see `ScoringMode`
+    pub fn uses_class_hints(&self) -> bool {
+        self.scoring_mode != ScoringMode::Structural
+    }
+    
+    /// Build a configuration tuned for a kind of site
+    pub fn preset(preset: Preset) -> Self {
+        let defaults = Self::default();
+        match preset {
+            Preset::News => Self {
+                extract_metadata: true,
+                dedup_paragraphs: true,
+                ..defaults
+            },
+            Preset::Blog => Self {
+                extract_metadata: true,
+                include_images: true,
+                min_extracted_size: 150,
+                ..defaults
+            },
+            Preset::Wiki => Self {
+                include_links: false,
+                min_extracted_size: 500,
+                ..defaults
+            },
+            Preset::Forum => Self {
+                include_comments: true,
+                min_extracted_size: 100,
+                strategies: vec![Strategy::Heuristics, Strategy::XPath, Strategy::Readability],
+                ..defaults
+            },
+            Preset::Docs => Self {
+                mode: ExtractionMode::Docs,
+                min_extracted_size: 100,
+                ..defaults
+            },
+            Preset::Recall => Self {
+                include_comments: true,
+                focus: Focus::Recall,
+                include_images: true,
+                filter_boilerplate: false,
+                min_extracted_size: 50,
+                strategies: vec![Strategy::XPath, Strategy::Readability, Strategy::Heuristics],
+                ..defaults
+            },
+            Preset::Precision => Self {
+                focus: Focus::Precision,
+                include_tables: false,
+                include_links: false,
+                dedup_paragraphs: true,
+                min_extracted_size: 500,
+                strategies: vec![Strategy::XPath, Strategy::Heuristics],
+                ..defaults
+            },
         }
     }
 }
@@ -98,47 +718,207 @@ impl Default for ExtractionConfig {
 pub struct ExtractionResult {
     /// Main content
     pub content: String,
+    /// Structure of the main content as extracted, before repeated paragraphs are
+    /// removed; `content` is its text
+    pub document: Document,
     /// Document title
     pub title: Option<String>,
     /// Document author
     pub author: Option<String>,
+    /// Profile page of the author, from `rel=author` links or JSON-LD `author.url`
+    pub author_url: Option<String>,
     /// Document date
     pub date: Option<String>,
     /// Document URL
     pub url: Option<String>,
+    /// Canonical publisher URL, from `link[rel=canonical]` or AMP URL resolution
+    pub canonical_url: Option<String>,
     /// Document description
     pub description: Option<String>,
     /// Document sitename
     pub sitename: Option<String>,
+    /// Publisher and copyright holder, from JSON-LD `publisher.name`, `og:site_name`,
+    /// or a copyright meta tag
+    pub publisher: Option<String>,
+    /// Latest year of the copyright notice
+    pub copyright_year: Option<u16>,
+    /// Language declared by the document, e.g. `en` or `pt-BR`
+    pub language: Option<String>,
+    /// Open Graph type of the page, e.g. `article`, `website`, or `video.other`
+    pub og_type: Option<String>,
     /// Document categories/tags
     pub categories: Vec<String>,
+    /// Citation metadata of journal and preprint pages
+    pub academic: Option<AcademicMetadata>,
+    /// Language variants of the document declared with hreflang links
+    pub alternates: Vec<LanguageAlternate>,
+    /// Tables referenced by `[Table N]` placeholders in the content
+    pub tables: Vec<Table>,
+    /// Capture timestamp (`YYYYMMDDhhmmss`) when the content came from the Wayback Machine
+    pub wayback_timestamp: Option<String>,
+    /// Article links of a listing page, when `harvest_listings` is set
+    pub article_links: Vec<ArticleLink>,
+    /// Quality label of the extracted content
+    pub quality: Option<Quality>,
+    /// Blocks of the content with their identified language, when `tag_block_languages` is set
+    pub language_blocks: Vec<LanguageBlock>,
+    /// Text of the comments section, with `ExtractionConfig::include_comments`
+    pub comments: Option<String>,
+    /// CSS path of the element the content was extracted from, e.g.
+    /// `body > div#main > article.post`, to pin in a content rule for the site
+    pub container_path: Option<String>,
 }
 
 /// Extract text from a URL
+///
+/// `file://` URLs are read from the local filesystem via `extract_file`.
+#[cfg(feature = "http")]
 pub fn extract_url(url: &str, config: &ExtractionConfig) -> Result<ExtractionResult, TrafilaturaError> {
+    extract_url_with(url, config, &HttpFetcher::new(config)?)
+}
+
+/// Extract text from a URL downloaded with the given fetcher
+pub fn extract_url_with(url: &str, config: &ExtractionConfig, fetcher: &dyn Fetcher) -> Result<ExtractionResult, TrafilaturaError> {
+    extract_download(download(url, config, fetcher)?, config)
+}
+
+/// Where the HTML of a `Download` came from
+pub(crate) enum DownloadSource {
+    /// The live page
+    Live,
+    /// A local file named by a `file://` URL
+    File,
+    /// A Wayback Machine snapshot with this capture timestamp
+    Wayback(String),
+}
+
+/// HTML downloaded for extraction. Batches download and extract in separate
+/// stages, so network latency and parsing overlap.
+pub(crate) struct Download {
+    pub(crate) url: Url,
+    /// URL the HTML was served from, after redirects, which its relative links
+    /// resolve against
+    pub(crate) page_url: Url,
+    pub(crate) html: String,
+    pub(crate) source: DownloadSource,
+}
+
+/// Download the HTML of a URL for `extract_download`, reading `file://` URLs from
+/// the local filesystem and falling back to the Wayback Machine if configured
+pub(crate) fn download(url: &str, config: &ExtractionConfig, fetcher: &dyn Fetcher) -> Result<Download, TrafilaturaError> {
     let url = Url::parse(url)?;
-    let client = Client::builder()
-        .timeout(std::time::Duration::from_secs(config.extraction_timeout))
-        .user_agent(&config.user_agent)
-        .build()?;
     
-    let response = client.get(url.clone()).send()?;
+    if url.scheme() == "file" {
+        let path = url.to_file_path().map_err(|_| {
+            TrafilaturaError::ExtractionError(format!("Invalid file URL: {}", url))
+        })?;
+        debug!("Reading local file {}", path.display());
+        
+        let html = fs::read_to_string(&path)?;
+        return Ok(Download { page_url: url.clone(), url, html, source: DownloadSource::File });
+    }
+    
+    let page = {
+        let _span = info_span!("fetch", url = %url).entered();
+        fetcher.fetch(&url)?
+    };
     
-    if !response.status().is_success() {
-        return Err(TrafilaturaError::RequestError(reqwest::Error::from(
-            response.error_for_status().unwrap_err()
-        )));
+    if !page.is_success() {
+        if config.wayback_fallback && wayback::FALLBACK_STATUSES.contains(&page.status) {
+            debug!("Got HTTP {} for {}, trying the Wayback Machine", page.status, url);
+            if let Some((html, timestamp)) = fetch_from_wayback(fetcher, &url)? {
+                return Ok(Download { page_url: url.clone(), url, html, source: DownloadSource::Wayback(timestamp) });
+            }
+        }
+        
+        return Err(TrafilaturaError::StatusError(page.status, url.to_string()));
     }
     
-    let html = response.text()?;
-    let mut result = extract_html(&html, config)?;
+    let page_url = Url::parse(&page.url).unwrap_or_else(|_| url.clone());
+    Ok(Download { url, page_url, html: page.body, source: DownloadSource::Live })
+}
+
+/// Extract text from downloaded HTML, setting the URL of the result
+pub(crate) fn extract_download(download: Download, config: &ExtractionConfig) -> Result<ExtractionResult, TrafilaturaError> {
+    let url = &download.url;
+    let mut result = {
+        let _span = info_span!("document", url = %url).entered();
+        extract_document(&download.html, config, url.host_str())?
+    };
     
-    // Set the URL in the result
+    // Set the URL in the result; blacklists of its domain apply now, and profile
+    // links left relative resolve against the page
     result.url = Some(url.to_string());
+    result.author_url = result.author_url.and_then(|author_url| download.page_url.join(&author_url).ok().map(String::from));
+    config.metadata_blacklist.apply(&mut result);
+    match download.source {
+        DownloadSource::Live => resolve_canonical_url(&mut result, url, config),
+        DownloadSource::File => return Ok(result),
+        DownloadSource::Wayback(timestamp) => result.wayback_timestamp = Some(timestamp),
+    }
+    listing::resolve_links(&mut result.article_links, url);
     
     Ok(result)
 }
 
+/// Download the HTML of a URL without extracting it
+#[cfg(feature = "http")]
+pub fn fetch_url(url: &str, config: &ExtractionConfig) -> Result<String, TrafilaturaError> {
+    fetch_url_with(url, &HttpFetcher::new(config)?)
+}
+
+/// Download the HTML of a URL with the given fetcher, failing on non-2xx statuses
+pub fn fetch_url_with(url: &str, fetcher: &dyn Fetcher) -> Result<String, TrafilaturaError> {
+    let url = Url::parse(url)?;
+    let _span = info_span!("fetch", url = %url).entered();
+    
+    fetcher.fetch(&url)?.into_body()
+}
+
+/// Resolve the normalized canonical URL of a fetched document and report AMP pages
+/// under it, so AMP, canonical, and campaign-tagged versions of an article are
+/// treated as the same document
+fn resolve_canonical_url(result: &mut ExtractionResult, url: &Url, config: &ExtractionConfig) {
+    // Declared canonical links may be relative to the fetched URL
+    let declared = result.canonical_url.as_deref()
+        .and_then(|canonical| url.join(canonical).ok())
+        .map(|canonical| canonical.to_string());
+    
+    let amp_canonical = urls::resolve_amp_url(url.as_str());
+    let is_amp = amp_canonical.is_some();
+    
+    let canonical = declared
+        .map(|canonical| urls::resolve_amp_url(&canonical).unwrap_or(canonical))
+        .or(amp_canonical)
+        .unwrap_or_else(|| url.to_string());
+    
+    if let Some(canonical) = urls::canonicalize_url(&canonical, &config.url_param_blocklist) {
+        if is_amp {
+            debug!("Reporting AMP page {} as {}", url, canonical);
+            result.url = Some(canonical.clone());
+        }
+        result.canonical_url = Some(canonical);
+    }
+}
+
+/// Download the latest Wayback Machine snapshot of a URL with its capture timestamp,
+/// if there is one
+fn fetch_from_wayback(fetcher: &dyn Fetcher, url: &Url) -> Result<Option<(String, String)>, TrafilaturaError> {
+    let availability = fetcher.fetch(&wayback::availability_url(url.as_str())?)?.body;
+    let snapshot = match wayback::parse_availability(&availability)? {
+        Some(snapshot) => snapshot,
+        None => return Ok(None),
+    };
+    
+    debug!("Using Wayback Machine snapshot from {}", snapshot.timestamp);
+    let page = fetcher.fetch(&Url::parse(&snapshot.raw_url())?)?;
+    if !page.is_success() {
+        return Ok(None);
+    }
+    
+    Ok(Some((page.body, snapshot.timestamp)))
+}
+
 /// Extract text from a local HTML file
 pub fn extract_file<P: AsRef<Path>>(path: P, config: &ExtractionConfig) -> Result<ExtractionResult, TrafilaturaError> {
     let mut file = File::open(path)?;
@@ -148,66 +928,858 @@ pub fn extract_file<P: AsRef<Path>>(path: P, config: &ExtractionConfig) -> Resul
     extract_html(&html, config)
 }
 
+/// Extract only the metadata of an HTML string, leaving the content empty
+pub fn extract_html_metadata(html: &str) -> Result<ExtractionResult, TrafilaturaError> {
+    let document = html::parse_document(html);
+    metadata::extract_metadata(&document, ExtractionResult::default())
+}
+
+/// Extract only the metadata of an HTML string, parsing it only up to shortly after
+/// the start of the body (see `html::MetadataParser`). Faster on long pages, but
+/// metadata found only further down the page, such as a byline or JSON-LD at the
+/// end, is missed.
+pub fn extract_html_metadata_prefix(html: &str) -> Result<ExtractionResult, TrafilaturaError> {
+    let document = html::parse_metadata_document(html);
+    metadata::extract_metadata(&document, ExtractionResult::default())
+}
+
+/// Extract only the metadata of a URL, leaving the content empty. With
+/// `metadata_prefix`, the download stops once enough of the page has been parsed,
+/// as in `extract_html_metadata_prefix`.
+///
+/// `file://` URLs are read from the local filesystem.
+#[cfg(feature = "http")]
+pub fn extract_url_metadata(url: &str, config: &ExtractionConfig) -> Result<ExtractionResult, TrafilaturaError> {
+    extract_url_metadata_with(url, config, &HttpFetcher::new(config)?)
+}
+
+/// Extract only the metadata of a URL downloaded with the given fetcher, failing
+/// on non-2xx statuses
+pub fn extract_url_metadata_with(url: &str, config: &ExtractionConfig, fetcher: &dyn Fetcher) -> Result<ExtractionResult, TrafilaturaError> {
+    let url = Url::parse(url)?;
+    if url.scheme() == "file" {
+        let path = url.to_file_path().map_err(|_| {
+            TrafilaturaError::ExtractionError(format!("Invalid file URL: {}", url))
+        })?;
+        let html = fs::read_to_string(path)?;
+        return if config.metadata_prefix { extract_html_metadata_prefix(&html) } else { extract_html_metadata(&html) };
+    }
+    
+    let mut parser = html::MetadataParser::new();
+    let page = {
+        let _span = info_span!("fetch", url = %url).entered();
+        if config.metadata_prefix {
+            fetcher.fetch_prefix(&url, &mut |chunk| parser.feed(chunk))?
+        } else {
+            fetcher.fetch(&url)?
+        }
+    };
+    if !page.is_success() {
+        return Err(TrafilaturaError::StatusError(page.status, url.to_string()));
+    }
+    
+    if config.metadata_prefix {
+        metadata::extract_metadata(&parser.finish(), ExtractionResult::default())
+    } else {
+        extract_html_metadata(&page.body)
+    }
+}
+
 /// Extract text from an HTML string
+///
+/// Runs inside an `extract` span whose `strategy` field records the strategy
+/// that produced the content (`listing`, `xpath`, `heuristics`, or `readability`),
+/// or `metadata` for media pages whose body is skipped (see `og_type_shortcuts`).
+///
+/// No input makes this function panic or overflow the stack, however malformed or
+/// deeply nested: it is fed untrusted crawl data. The `extract_html` fuzz target and
+/// `tests/panic_free.rs` check this; inputs that break it are bugs.
+///
+/// With `ExtractionConfig::memory_budget`, documents too large for the budget are
+/// refused before parsing, and the memory used is checked after each stage.
 pub fn extract_html(html: &str, config: &ExtractionConfig) -> Result<ExtractionResult, TrafilaturaError> {
-    let document = Html::parse_document(html);
+    extract_document(html, config, None)
+}
+
+/// Extract a document of the given host, which the rules of `site_rules` are kept by
+fn extract_document(html: &str, config: &ExtractionConfig, host: Option<&str>) -> Result<ExtractionResult, TrafilaturaError> {
+    let span = info_span!("extract", strategy = tracing::field::Empty).entered();
+    memory::check_estimate(html, config.memory_budget)?;
+    let memory = MemoryScope::start();
+    // Comments go before parsing, so commented-out blocks cannot leak text into
+    // any strategy
+    let stripped = html::strip_comments(html);
+    let html = stripped.as_ref();
+    let mut document = info_span!("parse").in_scope(|| html::parse_document(html));
+    memory.check(config.memory_budget)?;
+    
+    // Hashed class names say nothing about the content, so candidates are scored by
+    // their structure alone
+    let structural_config;
+    let config = if config.scoring_mode == ScoringMode::Auto && structure::has_generated_class_names(&document) {
+        debug!("Class names look generated, scoring by structure");
+        structural_config = ExtractionConfig { scoring_mode: ScoringMode::Structural, ..config.clone() };
+        &structural_config
+    } else {
+        config
+    };
     
     let mut result = ExtractionResult::default();
     
     // Extract metadata if configured
     if config.extract_metadata {
-        result = metadata::extract_metadata(&document, result)?;
+        result = metadata::extract_metadata_fields(&document, result, config.metadata_fields.as_ref(), &config.dates)?;
+        config.metadata_blacklist.apply(&mut result);
     }
     
-    // First try using XPath-based extraction (similar to Python trafilatura)
-    let xpath_content = xpath::extract_with_xpath(html, config)?;
+    let og_type = metadata::extract_og_type(&document);
+    let min_extracted_size = if config.og_type_shortcuts {
+        match min_content_size(og_type.as_deref(), config.min_extracted_size) {
+            Some(size) => size,
+            None => {
+                // The metadata is all these pages return
+                if !config.extract_metadata {
+                    result = metadata::extract_metadata_fields(&document, result, config.metadata_fields.as_ref(), &config.dates)?;
+                    config.metadata_blacklist.apply(&mut result);
+                }
+                span.record("strategy", "metadata");
+                return Ok(result);
+            },
+        }
+    } else {
+        config.min_extracted_size
+    };
     
-    if !xpath_content.is_empty() && xpath_content.len() >= config.min_extracted_size {
-        result.content = xpath_content;
-        return Ok(result);
+    // Email HTML is rewritten, shadow roots and templates are hoisted, collapsed
+    // panels are expanded, hidden elements are dropped, and ruby readings are
+    // rendered first; the XPath strategy then parses the rewritten markup
+    if config.mode == ExtractionMode::Email {
+        info_span!("email").in_scope(|| email::prepare_email(&mut document));
     }
+    let templates = info_span!("templates").in_scope(|| shadow::hoist_templates(&mut document));
+    let expanded = info_span!("collapsed").in_scope(|| collapsible::expand_collapsed(&mut document));
+    let removed_hidden = info_span!("hidden").in_scope(|| html::remove_hidden_elements(&mut document));
+    let ruby = html::rewrite_ruby(&mut document, config.ruby_style);
+    memory.check(config.memory_budget)?;
+    let rewritten_html;
+    let html = if config.mode == ExtractionMode::Email || templates > 0 || expanded > 0 || removed_hidden > 0 || ruby > 0 {
+        rewritten_html = document.html();
+        rewritten_html.as_str()
+    } else {
+        html
+    };
     
-    // Try original extraction methods as fallback
-    let content = extractors::extract_content(&document, config)?;
+    // A rule learned for the site selects the container without running the
+    // strategies; listing pages of the site go through the listing check instead
+    let site_rules = config.site_rules.as_ref().zip(host).filter(|_| !config.harvest_listings);
+    if let Some((cache, host)) = site_rules {
+        if let Some(rule) = cache.rule(host) {
+            let extracted = info_span!("strategy", strategy = "site_rule")
+                .in_scope(|| xpath::extract_with_container_selector(html, &rule.selector, config));
+            memory.check(config.memory_budget)?;
+            match extracted {
+                Ok(Some((extracted, container))) => {
+                    let content = extracted.to_text(config.image_style);
+                    let content = if config.dedup_paragraphs { remove_repeated_paragraphs(&content) } else { content };
+                    if !content.is_empty() && text_length(&content) >= min_extracted_size {
+                        span.record("strategy", "site_rule");
+                        cache.record_success(host);
+                        return complete_extraction(result, extracted, content, Some(container), html, config);
+                    }
+                    debug!("Site rule {} of {} found too little content", rule.path, host);
+                    cache.record_failure(host);
+                },
+                _ => {
+                    debug!("Site rule {} of {} matched nothing", rule.path, host);
+                    cache.record_failure(host);
+                },
+            }
+        }
+    }
     
-    if content.is_empty() || content.len() < config.min_extracted_size {
-        // Try readability algorithm as fallback
-        let readability_content = readability::extract_with_readability(&document, config)?;
-        
-        if !readability_content.is_empty() && readability_content.len() >= config.min_extracted_size {
-            result.content = readability_content;
-        } else {
-            result.content = content;
+    // The XPath strategy parses the raw HTML itself, while the listing check and
+    // the fallback strategies share one cleaned tree. Link blocks are pruned after
+    // the listing check, since a list of headlines looks like a link rail.
+    info_span!("clean").in_scope(|| html::remove_unwanted_elements(&mut document, config));
+    
+    if config.harvest_listings {
+        if let Some(links) = listing::harvest_listing(&document) {
+            span.record("strategy", "listing");
+            result.content = links.iter().map(|link| link.title.as_str()).collect::<Vec<_>>().join("\n");
+            result.article_links = links;
+            return Ok(result);
         }
-    } else {
-        result.content = content;
     }
     
-    // If the content is still too short, return extraction error
-    if result.content.is_empty() || result.content.len() < config.min_extracted_size {
-        return Err(TrafilaturaError::ExtractionError(
-            format!("Extracted content too short: {} chars", result.content.len())
-        ));
+    info_span!("prune").in_scope(|| html::prune_link_blocks(&mut document));
+    
+    // Run the strategies in the configured order until one finds enough content,
+    // keeping the longest shorter content for the partial result
+    let mut fallback: Option<(Strategy, String)> = None;
+    let mut strategies = config.strategies.clone();
+    if !config.uses_class_hints() {
+        // Stable, so the other strategies keep their order
+        strategies.sort_by_key(|&strategy| strategy == Strategy::XPath);
+    }
+    for strategy in strategies {
+        let (extracted, container) = info_span!("strategy", strategy = strategy.as_str()).in_scope(|| {
+            Ok::<_, TrafilaturaError>(match strategy {
+                Strategy::XPath => {
+                    let (extracted, container) = xpath::extract_with_xpath_container(html, config)?;
+                    (extracted, Some(container))
+                },
+                Strategy::Heuristics => extractors::extract_content_container(&document, config)?,
+                Strategy::Readability => readability::extract_with_readability_container(&document, config)?,
+            })
+        })?;
+        memory.check(config.memory_budget)?;
+        let content = extracted.to_text(config.image_style);
+        let content = if config.dedup_paragraphs { remove_repeated_paragraphs(&content) } else { content };
+        
+        if !content.is_empty() && text_length(&content) >= min_extracted_size {
+            span.record("strategy", strategy.as_str());
+            if let (Some(cache), Some(host), Some(container)) = (&config.site_rules, host, &container) {
+                cache.record_win(host, container);
+            }
+            return complete_extraction(result, extracted, content, container, html, config);
+        }
+        
+        if fallback.as_ref().is_none_or(|(_, best)| text_length(&content) > text_length(best)) {
+            fallback = Some((strategy, content));
+        }
     }
     
+    // No strategy found enough content: return what was recovered with the reason
+    let reason = match fallback {
+        Some((strategy, content)) if !content.is_empty() => {
+            span.record("strategy", strategy.as_str());
+            result.content = content;
+            FailureReason::ContentTooShort {
+                length: text_length(&result.content),
+                min_length: min_extracted_size,
+            }
+        },
+        _ => {
+            // Baseline text: everything visible that survived cleaning
+            result.content = document.root_element().text()
+                .flat_map(str::split_whitespace)
+                .collect::<Vec<_>>()
+                .join(" ");
+            if result.content.is_empty() { FailureReason::EmptyDocument } else { FailureReason::NoContent }
+        },
+    };
+    Err(TrafilaturaError::PartialExtraction(Box::new(PartialExtraction { result, reason })))
+}
+
+/// Finish a result with the content found in a container, given with its text:
+/// deduplication across documents, quality, language blocks, and comments
+fn complete_extraction(
+    mut result: ExtractionResult,
+    document: Document,
+    content: String,
+    container: Option<String>,
+    html: &str,
+    config: &ExtractionConfig,
+) -> Result<ExtractionResult, TrafilaturaError> {
+    if let Some(container) = &container {
+        debug!(container = container.as_str(), "Content container");
+    }
+    result.content = content;
+    result.tables = document.tables.clone();
+    result.document = document;
+    result.container_path = container;
+    if let Some(cache) = &config.dedup {
+        result.content = cache.remove_duplicates(&result.content);
+    }
+    // Keep the blocks of structured output in step with the deduplicated text
+    if config.dedup.is_some() || config.dedup_paragraphs {
+        retain_kept_blocks(&mut result.document, &result.content, config.image_style);
+    }
+    if let Some(cache) = &config.dedup {
+        if cache.is_duplicate(&result.content) {
+            return Err(TrafilaturaError::PartialExtraction(Box::new(PartialExtraction { result, reason: FailureReason::Duplicate })));
+        }
+    }
+    result.quality = Some(quality::assess_quality(&result.content));
+    if config.tag_block_languages {
+        result.language_blocks = language::tag_blocks(&result.content);
+    }
+    // The cleaned tree has lost the comments section
+    if config.include_comments {
+        result.comments = xpath::extract_comments(&html::parse_document(html));
+    }
     Ok(result)
 }
 
+/// Drop the blocks of a document whose lines are not all left in `content`. Lines
+/// are counted, so of two equal blocks only as many are kept as the text repeats.
+fn retain_kept_blocks(document: &mut Document, content: &str, image_style: ImageStyle) {
+    let mut kept: HashMap<&str, usize> = HashMap::new();
+    for line in content.lines() {
+        *kept.entry(line).or_insert(0) += 1;
+    }
+    document.elements.retain(|element| {
+        let Some(text) = element.to_text(image_style) else {
+            return true;
+        };
+        let mut needed: HashMap<&str, usize> = HashMap::new();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            *needed.entry(line).or_insert(0) += 1;
+        }
+        if !needed.iter().all(|(line, count)| kept.get(line).is_some_and(|left| left >= count)) {
+            return false;
+        }
+        for (line, count) in needed {
+            if let Some(left) = kept.get_mut(line) {
+                *left -= count;
+            }
+        }
+        true
+    });
+}
+
+/// Minimum content length of a page of the given `og:type`, or `None` for video and
+/// music pages, which carry their information in metadata rather than body text
+fn min_content_size(og_type: Option<&str>, min_extracted_size: usize) -> Option<usize> {
+    let kind = og_type.map(|og_type| og_type.split('.').next().unwrap_or(og_type));
+    match kind {
+        Some("video" | "music") => None,
+        // Home pages and profiles are mostly navigation, teasers, and bios
+        Some("website" | "profile") => Some(min_extracted_size * 2),
+        // Product descriptions are short
+        Some("product") => Some(min_extracted_size / 2),
+        _ => Some(min_extracted_size),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::document::Element;
+    use crate::fetch::FetchedPage;
 
     #[test]
     fn test_extraction_config_default() {
         let config = ExtractionConfig::default();
-        assert_eq!(config.include_comments, false);
-        assert_eq!(config.include_tables, true);
-        assert_eq!(config.include_links, true);
-        assert_eq!(config.include_images, false);
+        assert!(!config.include_comments);
+        assert!(config.include_tables);
+        assert!(config.include_links);
+        assert!(!config.include_images);
         assert_eq!(config.output_format, OutputFormat::Text);
         assert_eq!(config.min_extracted_size, 250);
     }
+
+    #[test]
+    fn test_output_format_names() {
+        for format in OutputFormat::ALL {
+            assert_eq!(format.to_string().parse::<OutputFormat>().unwrap(), format);
+        }
+        assert_eq!("Markdown".parse::<OutputFormat>().unwrap(), OutputFormat::Markdown);
+        assert!(matches!("yaml".parse::<OutputFormat>(), Err(TrafilaturaError::ConfigError(_))));
+        assert_eq!(serde_json::from_str::<OutputFormat>(r#""json""#).unwrap(), OutputFormat::Json);
+        assert!(serde_json::from_str::<OutputFormat>(r#""yaml""#).is_err());
+    }
+    
+    #[test]
+    fn test_presets() {
+        let config = ExtractionConfig::preset(Preset::Forum);
+        assert!(config.include_comments);
+        assert_eq!(config.strategies[0], Strategy::Heuristics);
+        
+        let config = ExtractionConfig::preset(Preset::Precision);
+        assert!(!config.include_links && !config.include_tables);
+        assert!(!config.strategies.contains(&Strategy::Readability));
+        
+        assert_eq!(ExtractionConfig::preset(Preset::Docs).mode, ExtractionMode::Docs);
+        assert!(ExtractionConfig::preset(Preset::News).dedup_paragraphs);
+    }
+    
+    #[test]
+    fn test_resolve_canonical_url_for_amp() {
+        let url = Url::parse("https://www-example-com.cdn.ampproject.org/c/s/www.example.com/story").unwrap();
+        let mut result = ExtractionResult::default();
+        let config = ExtractionConfig::default();
+        
+        resolve_canonical_url(&mut result, &url, &config);
+        
+        assert_eq!(result.url.as_deref(), Some("https://www.example.com/story"));
+        assert_eq!(result.canonical_url.as_deref(), Some("https://www.example.com/story"));
+        
+        // Declared canonical links take precedence and may be relative
+        let url = Url::parse("https://example.com/story?outputType=amp").unwrap();
+        let mut result = ExtractionResult {
+            url: Some(url.to_string()),
+            canonical_url: Some("/2024/story".into()),
+            ..Default::default()
+        };
+        
+        resolve_canonical_url(&mut result, &url, &config);
+        
+        assert_eq!(result.url.as_deref(), Some("https://example.com/2024/story"));
+    }
+    
+    #[test]
+    fn test_resolve_canonical_url_strips_tracking() {
+        let url = Url::parse("https://example.com/story/?utm_campaign=spring&page=2").unwrap();
+        let mut result = ExtractionResult {
+            url: Some(url.to_string()),
+            ..Default::default()
+        };
+        let config = ExtractionConfig::default();
+        
+        resolve_canonical_url(&mut result, &url, &config);
+        
+        // The fetched URL is kept, the canonical URL serves as deduplication key
+        assert_eq!(result.url.as_deref(), Some(url.as_str()));
+        assert_eq!(result.canonical_url.as_deref(), Some("https://example.com/story?page=2"));
+    }
     
+    /// Extract a page with each strategy alone, on top of `config`
+    fn extract_with_each_strategy(html: &str, config: &ExtractionConfig) -> Vec<(Strategy, ExtractionResult)> {
+        [Strategy::XPath, Strategy::Heuristics, Strategy::Readability].into_iter()
+            .map(|strategy| {
+                let config = ExtractionConfig { strategies: vec![strategy], ..config.clone() };
+                let result = extract_html(html, &config).unwrap_or_else(|e| panic!("{:?}: {}", strategy, e));
+                (strategy, result)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_hidden_elements_skipped_by_all_strategies() {
+        let html = r##"<html><body><article>
+            <a class="visually-hidden" href="#recipe">Skip to recipe</a>
+            <p>Knead the dough for ten minutes, until it springs back when pressed with a floured finger.</p>
+            <p style="display:none">cheap bread flour discount code stand mixer deals buy online</p>
+            <div hidden><p>Print this recipe or save it to your collection.</p></div>
+            <p aria-hidden="true">★★★★☆</p>
+            <p>Leave it to rise in a warm place for an hour, or until it has doubled in size.</p>
+            </article></body></html>"##;
+        let config = ExtractionConfig { min_extracted_size: 50, ..Default::default() };
+
+        for (strategy, result) in extract_with_each_strategy(html, &config) {
+            assert!(result.content.starts_with("Knead the dough"), "{:?}: {}", strategy, result.content);
+            assert!(result.content.ends_with("doubled in size."), "{:?}: {}", strategy, result.content);
+            for hidden in ["Skip to recipe", "discount code", "Print this recipe", "★"] {
+                assert!(!result.content.contains(hidden), "{:?}: {}", strategy, result.content);
+            }
+        }
+    }
+
+    #[test]
+    fn test_container_path() {
+        // The review sits in a layout wrapper next to a sidebar
+        let html = r#"<html><body><div id="layout">
+            <aside class="sidebar"><h3>Departments</h3><a href="/fiction">Fiction</a> <a href="/history">History</a></aside>
+            <main><article class="review">
+            <h1>A history of the lighthouse keepers</h1>
+            <p>The book follows four generations of one family who kept the same rock lighthouse for a century.</p>
+            <p>Its best chapters draw on logbooks that record every storm, shipwreck, and supply boat in their own words.</p>
+            <p>The author, a keeper's granddaughter, fills the gaps with letters, tide tables, and the memories of the last relief crews.</p>
+            </article></main></div></body></html>"#;
+        let document = scraper::Html::parse_document(html);
+        let config = ExtractionConfig { min_extracted_size: 50, ..Default::default() };
+
+        for (strategy, result) in extract_with_each_strategy(html, &config) {
+            let path = result.container_path.unwrap();
+
+            // The path selects the container in the original page
+            let selector = scraper::Selector::parse(&path).unwrap();
+            let container = document.select(&selector).next().unwrap();
+            assert!(container.text().collect::<String>().contains("four generations"), "{:?}: {}", strategy, path);
+            if strategy != Strategy::Readability {
+                assert_eq!(path, "body > div#layout > main > article.review", "{:?}", strategy);
+            }
+        }
+    }
+
+    #[test]
+    fn test_link_rendering() {
+        // Links in a heading, a paragraph, a list item, and a table cell
+        let html = r#"<html><body><nav><a href="/">Home</a></nav><article class="post">
+            <h2>Upgrading to <a href="/docs/v2">version 2</a></h2>
+            <p>Version 2 drops the legacy configuration format, so read <a href="https://example.com/migrate">the migration guide</a> before upgrading a production deployment.</p>
+            <ul><li>Every removed command-line flag and its replacement is listed in <a href="/changelog">the changelog</a></li><li>Plugins built against version 1 have to be rebuilt with the new toolkit</li></ul>
+            <table><tr><th>Client</th><th>Package</th></tr><tr><td>Python</td><td><a href="/clients/python">pyclient</a></td></tr></table>
+            </article></body></html>"#;
+        let rendered = [
+            (LinkStyle::Stripped, ["version 2", "the migration guide", "the changelog", "pyclient"]),
+            (LinkStyle::Parenthetical, ["version 2 (/docs/v2)", "the migration guide (https://example.com/migrate)", "the changelog (/changelog)", "pyclient (/clients/python)"]),
+            (LinkStyle::Footnote, ["version 2[1]", "the migration guide[2]", "the changelog[3]", "pyclient[4]"]),
+            (LinkStyle::Markdown, ["[version 2](/docs/v2)", "[the migration guide](https://example.com/migrate)", "[the changelog](/changelog)", "[pyclient](/clients/python)"]),
+        ];
+
+        // Every block renders links alike in every strategy, and `include_links: false`
+        // leaves every target out whatever the style
+        for (link_style, links) in rendered {
+            let config = ExtractionConfig { link_style, min_extracted_size: 50, ..Default::default() };
+            for (strategy, result) in extract_with_each_strategy(html, &config) {
+                for link in links {
+                    assert!(result.content.contains(link), "{:?} {:?}: {}", strategy, link_style, result.content);
+                }
+            }
+
+            let config = ExtractionConfig { include_links: false, ..config };
+            for (strategy, result) in extract_with_each_strategy(html, &config) {
+                for target in ["/docs/v2", "/migrate", "/changelog", "/clients", "[1]"] {
+                    assert!(!result.content.contains(target), "{:?} {:?}: {}", strategy, link_style, result.content);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_image_styles() {
+        // A captioned figure, an icon, and an image without alt text
+        let html = r#"<html><body><nav><a href="/">Home</a></nav><article class="post">
+            <p>Three pairs of ospreys nested on the estuary platforms this spring, the most since monitoring began.</p>
+            <figure><img src="https://example.com/osprey.jpg" alt="An osprey with a fish"><figcaption>Seen from the north hide.</figcaption></figure>
+            <p>Volunteers counted eleven chicks, all of which had fledged by the end of July.</p>
+            <img src="/icons/share.png" class="icon-share" alt="Share"><img src="https://example.com/platforms.png">
+            </article></body></html>"#;
+        let text = |images: &str| format!(
+            "Three pairs of ospreys nested on the estuary platforms this spring, the most since monitoring began.{}\n\n\
+             Volunteers counted eleven chicks, all of which had fledged by the end of July.",
+            images,
+        );
+        let expected = [
+            (ImageStyle::Skip, text("")),
+            (ImageStyle::AltText, text("\n\n[Image: An osprey with a fish]")),
+            (ImageStyle::Markdown, text("\n\n[Image: An osprey with a fish](https://example.com/osprey.jpg)") + "\n\n[Image](https://example.com/platforms.png)"),
+            (
+                ImageStyle::Figure,
+                text("\n\n[Image: An osprey with a fish](https://example.com/osprey.jpg)\nFigure: Seen from the north hide.") + "\n\n[Image](https://example.com/platforms.png)",
+            ),
+        ];
+
+        // Every strategy renders the content the same way, with the images in place
+        for (image_style, content) in &expected {
+            let config = ExtractionConfig { include_images: true, image_style: *image_style, min_extracted_size: 50, ..Default::default() };
+            for (strategy, result) in extract_with_each_strategy(html, &config) {
+                assert_eq!(&result.content, content, "{:?} {:?}", strategy, image_style);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rendering_across_strategies() {
+        let html = r#"<html><body><nav><a href="/">Home</a></nav><article class="post">
+            <h2>Winter timetable</h2>
+            <p>From December the night trains to Vienna run <a href="https://example.com/night">three times a week</a> instead of daily.</p>
+            <ul><li>Sleeper cars on Fridays</li><li>Seats only on Tuesdays</li></ul>
+            <table><tr><th>Route</th><th>Departures</th></tr><tr><td>Vienna</td><td>3</td></tr></table>
+            <p>Tickets for the new timetable go on sale in October, at stations and online.</p>
+            </article></body></html>"#;
+        let expected = "Winter timetable\n\n\
+            From December the night trains to Vienna run [three times a week](https://example.com/night) instead of daily.\n\n\
+            • Sleeper cars on Fridays\n• Seats only on Tuesdays\n\n\
+            [Table 1]\n\n\
+            Tickets for the new timetable go on sale in October, at stations and online.";
+        let config = ExtractionConfig {
+            link_style: LinkStyle::Markdown,
+            separate_tables: true,
+            min_extracted_size: 50,
+            ..Default::default()
+        };
+
+        for (strategy, result) in extract_with_each_strategy(html, &config) {
+            assert_eq!(result.content, expected, "{:?}", strategy);
+            assert_eq!(result.tables.len(), 1, "{:?}", strategy);
+            assert_eq!(result.document.elements[0], Element::Heading { level: 2, text: "Winter timetable".into() }, "{:?}", strategy);
+            assert_eq!(result.document.elements[3], Element::TableRef(1), "{:?}", strategy);
+        }
+    }
+
+    #[test]
+    fn test_landmark_roles_across_strategies() {
+        // Generated class names carry no hints; the roles do
+        let paragraph = "The council voted to extend the cycle lane network by twelve kilometres over the next two years.";
+        let html = format!(
+            r#"<html><body><div class="x9f2a" role="navigation"><p>Council agendas, meeting minutes, and committee members</p></div>
+            <div class="k3b7c"><div class="q1d8e" role="main"><p>{p}</p><p>{p}</p><p>{p}</p>
+            <div class="z4c1f" role="complementary"><p>Most read: the mayor opens the new library branch in the old station</p></div></div></div>
+            </body></html>"#,
+            p = paragraph
+        );
+        let config = ExtractionConfig { min_extracted_size: 50, ..Default::default() };
+
+        for (strategy, result) in extract_with_each_strategy(&html, &config) {
+            assert!(result.content.contains("twelve kilometres"), "{:?}", strategy);
+            assert!(!result.content.contains("Council agendas"), "{:?}: {}", strategy, result.content);
+            assert!(!result.content.contains("library branch"), "{:?}: {}", strategy, result.content);
+        }
+    }
+
+    #[test]
+    fn test_structural_scoring() {
+        // CSS-in-JS class names: `css-1adx3` holds the `ad` hint, and nothing names
+        // the rail of teaser cards
+        let paragraph = "The screen stays readable in sunlight, lasts five weeks per charge, and turns pages without a flicker.";
+        let card = |i: usize| format!(
+            r#"<div class="css-{i}k2v9q"><a class="css-7ba{i}fq" href="/review/{i}">Travel headphones tested, no. {i}</a><span class="css-{i}x0ze1">{i} min read</span></div>"#
+        );
+        let html = format!(
+            r#"<html><body><div class="css-175oi2r"><div class="sc-bdVaJa">{cards}</div>
+            <div class="css-1adx3"><h2 class="css-8ka0p2">Review: the new e-reader</h2><p class="sc-gsTCUz">{p}</p><p class="sc-gsTCUz">{p}</p><p class="sc-gsTCUz">{p}</p></div>
+            </div></body></html>"#,
+            cards = (1..=5).map(card).collect::<String>(),
+            p = paragraph
+        );
+
+        let config = ExtractionConfig { min_extracted_size: 50, ..Default::default() };
+        for (strategy, result) in extract_with_each_strategy(&html, &config) {
+            assert!(result.content.starts_with("Review: the new e-reader"), "{:?}: {}", strategy, result.content);
+            assert!(result.content.contains("pages without a flicker."), "{:?}: {}", strategy, result.content);
+            assert!(!result.content.contains("headphones tested"), "{:?}: {}", strategy, result.content);
+        }
+
+        // Scored by the class names, readability takes the hashes for hints
+        let config = ExtractionConfig {
+            strategies: vec![Strategy::Readability],
+            scoring_mode: ScoringMode::Hints,
+            min_extracted_size: 50,
+            ..Default::default()
+        };
+        assert!(extract_html(&html, &config).is_err());
+    }
+
+    #[test]
+    fn test_svg_and_canvas_text() {
+        let html = r#"<html><body><article class="post">
+            <p>Ferry traffic through the harbour doubled over the summer months this year.</p>
+            <figure><svg viewBox="0 0 100 50"><title>Ferry crossings per month</title>
+              <g><text>0</text><text>250</text><text>500</text><text>June</text><text>July</text></g></svg></figure>
+            <p>Operators expect the numbers to fall again <svg><text>42</text></svg>once the season ends.</p>
+            <canvas id="chart"><p>Crossings: 120, 240, 480, and 960 in the busiest week.</p></canvas>
+            </article></body></html>"#;
+        let text = "Ferry traffic through the harbour doubled over the summer months this year.\n\n\
+            Operators expect the numbers to fall again once the season ends.";
+
+        let config = ExtractionConfig { min_extracted_size: 50, ..Default::default() };
+        for (strategy, result) in extract_with_each_strategy(html, &config) {
+            assert_eq!(result.content, text, "{:?}", strategy);
+        }
+
+        // The titles of graphics are kept as captions on request
+        let config = ExtractionConfig { svg_titles: true, ..config };
+        for (strategy, result) in extract_with_each_strategy(html, &config) {
+            let content = result.content;
+            assert!(content.contains("this year.\n\n[Image: Ferry crossings per month]\n\nOperators"), "{:?} {}", strategy, content);
+            assert!(!content.contains("250") && !content.contains("42"), "{:?} {}", strategy, content);
+        }
+    }
+
+    #[test]
+    fn test_site_rules() {
+        struct SiteFetcher;
+
+        impl Fetcher for SiteFetcher {
+            fn fetch(&self, url: &Url) -> Result<FetchedPage, TrafilaturaError> {
+                let text = format!(
+                    "<p>Story {} of the harbour: crews repaired the storm-damaged pier over three weeks.</p>\
+                     <p>Fishing crews said the closure had cost them most of the spring season.</p>",
+                    url.path()
+                );
+                // Pages under /new/ use a redesigned template
+                let body = if url.path().starts_with("/new/") {
+                    format!(r#"<html><body><nav><a href="/">Home</a></nav><main><section class="story">{}</section></main></body></html>"#, text)
+                } else {
+                    format!(r#"<html><body><nav><a href="/">Home</a></nav><div id="main"><article class="post">{}</article></div></body></html>"#, text)
+                };
+                Ok(FetchedPage { url: url.to_string(), status: 200, body })
+            }
+        }
+
+        let cache = Arc::new(SiteRuleCache::new(site_rules::SiteRuleOptions::default()));
+        let config = ExtractionConfig { min_extracted_size: 50, site_rules: Some(cache.clone()), ..Default::default() };
+        let path = "body > div#main > article.post";
+        for page in 1..=3 {
+            assert!(cache.rule("example.com").is_none());
+            let result = extract_url_with(&format!("https://example.com/{}", page), &config, &SiteFetcher).unwrap();
+            assert_eq!(result.container_path.as_deref(), Some(path));
+        }
+        assert_eq!(cache.rule("example.com").unwrap().path, path);
+        assert!(cache.rule("example.org").is_none());
+
+        let result = extract_url_with("https://example.com/4", &config, &SiteFetcher).unwrap();
+        assert_eq!(result.container_path.as_deref(), Some(path));
+        assert!(result.content.contains("Story /4 of the harbour"));
+
+        // The redesigned pages fall back to the full search until the rule is forgotten
+        for page in 1..=2 {
+            let result = extract_url_with(&format!("https://example.com/new/{}", page), &config, &SiteFetcher).unwrap();
+            assert!(result.content.contains("of the harbour"));
+            assert_ne!(result.container_path.as_deref(), Some(path));
+        }
+        assert!(cache.rule("example.com").is_none());
+    }
+
+    #[test]
+    fn test_collapsed_panels_extracted() {
+        let html = r#"<html><body><article>
+            <p>The museum reopens this spring with a new wing dedicated to maritime history.</p>
+            <p>Curators spent two years restoring the ship models, charts, and logbooks, many of which were donated by local families.</p>
+            <details><summary>Is the museum accessible?</summary><p>All floors can be reached by lift and ramps.</p></details>
+            <div role="tabpanel" id="hours" hidden><p>Opening hours are ten to six from Tuesday to Sunday.</p></div>
+            </article></body></html>"#;
+
+        let config = ExtractionConfig { min_extracted_size: 50, ..Default::default() };
+        for (strategy, result) in extract_with_each_strategy(html, &config) {
+            assert!(result.content.contains("Is the museum accessible?"), "{:?}: {}", strategy, result.content);
+            assert!(result.content.contains("Opening hours"), "{:?}: {}", strategy, result.content);
+            assert_eq!(result.content.matches("reached by lift").count(), 1, "{:?}", strategy);
+        }
+    }
+
+    #[test]
+    fn test_shadow_roots_extracted() {
+        let html = r#"<html><body><article>
+            <p>The museum reopens this spring with a new wing dedicated to maritime history.</p>
+            <museum-exhibit>
+              <template shadowrootmode="open"><section><p>Curators spent two years restoring the ship models and charts.</p><slot></slot></section></template>
+              <p>Many of the logbooks on display were donated by local families.</p>
+            </museum-exhibit>
+            <template><p>Tickets are {{ price }} at the door.</p></template>
+            </article></body></html>"#;
+
+        let config = ExtractionConfig { min_extracted_size: 50, ..Default::default() };
+        for (strategy, result) in extract_with_each_strategy(html, &config) {
+            let restoring = result.content.find("Curators spent").unwrap_or_else(|| panic!("{:?}: {}", strategy, result.content));
+            let logbooks = result.content.find("logbooks on display").unwrap_or_else(|| panic!("{:?}: {}", strategy, result.content));
+            assert!(restoring < logbooks, "{:?}", strategy);
+            assert!(!result.content.contains("price"), "{:?}: {}", strategy, result.content);
+        }
+    }
+
+    #[test]
+    fn test_block_languages() {
+        let html = r#"<html lang="en"><body><article>
+            <p>The city council approved the budget for the new tram line on Tuesday evening.</p>
+            <p lang="fr">Le conseil municipal a approuvé le budget pour la nouvelle ligne de tramway.</p>
+            </article></body></html>"#;
+        let config = ExtractionConfig { tag_block_languages: true, min_extracted_size: 50, ..Default::default() };
+        let result = extract_html(html, &config).unwrap();
+
+        let languages: Vec<Option<&str>> = result.language_blocks.iter().map(|block| block.language.as_deref()).collect();
+        assert_eq!(languages, vec![Some("en"), Some("fr")]);
+        assert!(extract_html(html, &ExtractionConfig { min_extracted_size: 50, ..Default::default() }).unwrap().language_blocks.is_empty());
+
+        let json = output::format_result(&result, &ExtractionConfig { output_format: OutputFormat::Json, ..config }).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json["blocks"][1]["language"], "fr");
+    }
+
+    #[test]
+    fn test_dedup_across_documents() {
+        let teaser = "Subscribe to our newsletter for the latest news on the harbour renovation and the new ferry timetable.";
+        let page = |topic: &str| format!(
+            "<html><body><article><p>{} The article goes on with details that only this page reports in full.</p><p>{}</p></article></body></html>",
+            topic, teaser
+        );
+        let config = ExtractionConfig {
+            dedup: Some(Arc::new(DedupCache::new(Default::default()))),
+            min_extracted_size: 50,
+            ..Default::default()
+        };
+
+        for topic in ["Ferries", "Bridges", "Tunnels", "Locks"] {
+            assert!(extract_html(&page(topic), &config.clone()).unwrap().content.contains("Subscribe"));
+        }
+        let result = extract_html(&page("Harbours"), &config).unwrap();
+        assert!(result.content.starts_with("Harbours") && !result.content.contains("Subscribe"), "{}", result.content);
+
+        // The same page extracted again after four copies is a duplicate document
+        let long = "<html><body><article><p>A report on the harbour renovation and its many delays.</p>\
+            <p>The new terminal opens next spring, a year later than planned.</p></article></body></html>";
+        for _ in 0..4 {
+            extract_html(long, &config).unwrap();
+        }
+        match extract_html(long, &config) {
+            Err(TrafilaturaError::PartialExtraction(partial)) => assert_eq!(partial.reason, FailureReason::Duplicate),
+            other => panic!("expected a duplicate, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_ruby_style() {
+        let html = r#"<html><body><article>
+            <p><ruby>東京<rp>(</rp><rt>とうきょう</rt><rp>)</rp></ruby>で<ruby>桜<rt>さくら</rt></ruby>が咲き始めました。今年の開花は平年より五日早く、各地の公園では花見客で賑わっています。</p>
+            </article></body></html>"#;
+
+        let config = ExtractionConfig { min_extracted_size: 20, ..Default::default() };
+        for (strategy, result) in extract_with_each_strategy(html, &config) {
+            assert!(result.content.starts_with("東京で桜が咲き始めました。"), "{:?}: {}", strategy, result.content);
+        }
+
+        let config = ExtractionConfig { ruby_style: RubyStyle::Annotated, ..config };
+        for (strategy, result) in extract_with_each_strategy(html, &config) {
+            assert!(result.content.starts_with("東京(とうきょう)で桜(さくら)が"), "{:?}: {}", strategy, result.content);
+        }
+    }
+
+    #[test]
+    fn test_og_type_shortcuts() {
+        let page = |og_type: &str, text: &str| format!(
+            r#"<html><head><meta property="og:type" content="{}"><meta property="og:title" content="Harbour tour"></head>
+            <body><article><p>{}</p></article></body></html>"#,
+            og_type, text
+        );
+        let text = "The harbour tour takes an hour and passes the lighthouse, the old customs house, and the shipyard.";
+
+        // Off by default: every page is extracted the same way
+        let config = ExtractionConfig { min_extracted_size: 60, ..Default::default() };
+        assert_eq!(extract_html(&page("video.other", text), &config).unwrap().content, text);
+        assert!(extract_html(&page("website", text), &config).is_ok());
+
+        // Media pages return their metadata, even when it was not asked for
+        let config = ExtractionConfig { og_type_shortcuts: true, ..config };
+        let result = extract_html(&page("video.other", text), &config).unwrap();
+        assert!(result.content.is_empty());
+        assert_eq!(result.og_type.as_deref(), Some("video.other"));
+        assert_eq!(result.title.as_deref(), Some("Harbour tour"));
+
+        assert!(extract_html(&page("article", text), &config).is_ok());
+        assert!(matches!(extract_html(&page("website", text), &config), Err(TrafilaturaError::PartialExtraction(_))));
+        assert!(extract_html(&page("product", "A waterproof jacket for sailing, with taped seams."), &config).is_ok());
+    }
+
+    #[test]
+    fn test_extract_url_metadata() {
+        struct PageFetcher(u16);
+
+        impl Fetcher for PageFetcher {
+            fn fetch(&self, url: &Url) -> Result<FetchedPage, TrafilaturaError> {
+                Ok(FetchedPage { url: url.to_string(), status: self.0, body: long_page() })
+            }
+        }
+
+        // The byline and the JSON-LD come after 87 KB of scripts
+        fn long_page() -> String {
+            format!(
+                r#"<html><head><title>Harbour tour</title></head><body>{}<p class="author">Tom Reed</p><script type="application/ld+json">{{"@type": "NewsArticle", "publisher": {{"@type": "Organization", "name": "Harbour Gazette"}}}}</script></body></html>"#,
+                "<script>track();</script>".repeat(3_500)
+            )
+        }
+
+        let config = ExtractionConfig::default();
+        let result = extract_url_metadata_with("https://example.com/tour", &config, &PageFetcher(200)).unwrap();
+        assert_eq!(result.title.as_deref(), Some("Harbour tour"));
+        assert_eq!(result.author.as_deref(), Some("Tom Reed"));
+        assert_eq!(result.publisher.as_deref(), Some("Harbour Gazette"));
+        assert!(result.content.is_empty());
+        assert_eq!(extract_html_metadata(&long_page()).unwrap().author.as_deref(), Some("Tom Reed"));
+
+        // The prefix parse stops before them
+        let config = ExtractionConfig { metadata_prefix: true, ..config };
+        let result = extract_url_metadata_with("https://example.com/tour", &config, &PageFetcher(200)).unwrap();
+        assert_eq!(result.title.as_deref(), Some("Harbour tour"));
+        assert_eq!(result.author, None);
+        assert_eq!(result.publisher, None);
+        assert_eq!(extract_html_metadata_prefix(&long_page()).unwrap().author, None);
+
+        assert!(matches!(extract_url_metadata_with("https://example.com/tour", &config, &PageFetcher(404)), Err(TrafilaturaError::StatusError(404, _))));
+    }
+
     #[test]
     fn test_xpath_extraction() {
         let html = r#"<!DOCTYPE html>
@@ -221,8 +1793,10 @@ mod tests {
                 <h1>Main Heading</h1>
                 <div id="mw-content-text">
                     <div class="mw-parser-output">
-                        <p>This is the main paragraph of content that should be extracted.</p>
-                        <p>This is a second paragraph.</p>
+                        <p>This is the main paragraph of content that should be extracted. It is long
+                        enough to look like real article prose rather than a navigation label.</p>
+                        <p>This is a second paragraph. Together with the first one it pushes the
+                        extracted text past the default minimum extraction size.</p>
                         <ul>
                             <li>List item 1</li>
                             <li>List item 2</li>
@@ -250,4 +1824,23 @@ mod tests {
         // References should be excluded
         assert!(!result.content.contains("Reference 1"));
     }
+    
+    #[test]
+    fn test_xpath_prunes_link_blocks() {
+        let html = r#"<html><body><article>
+            <p>The harbour authority approved the new ferry terminal after a long public consultation.</p>
+            <ul class="x2">
+                <li><a href="/a">Minister resigns after long dispute</a></li>
+                <li><a href="/b">Storm closes roads across the north</a></li>
+                <li><a href="/c">Local team wins the final on penalties</a></li>
+                <li><a href="/d">New bridge opens to traffic next week</a></li>
+            </ul>
+            <p>Construction starts in spring, and the first ferries should dock there within two years.</p>
+        </article></body></html>"#;
+        let config = ExtractionConfig { strategies: vec![Strategy::XPath], min_extracted_size: 50, ..Default::default() };
+        
+        let result = extract_html(html, &config).unwrap();
+        assert!(result.content.contains("ferry terminal") && result.content.contains("Construction starts"));
+        assert!(!result.content.contains("Minister resigns"), "{}", result.content);
+    }
 }
@@ -0,0 +1,119 @@
+//! Listing page handling for Trafilatura Rust port.
+//! This module recognizes index and category pages, which consist of teasers
+//! linking to articles, and harvests those links instead of extracting the
+//! teaser text as if it were an article.
+
+use std::collections::HashSet;
+
+use scraper::{ElementRef, Html};
+use url::Url;
+
+use crate::html::{descendants_named, element_text_length};
+use crate::utils::{count_words, text_length, try_selector};
+
+/// Minimum number of words in the anchor text of an article link
+const MIN_TITLE_WORDS: usize = 3;
+
+/// Minimum number of article links on a listing page
+const MIN_ARTICLE_LINKS: usize = 5;
+
+/// Minimum share of the main area text that is article link text
+const MIN_LINK_TEXT_RATIO: f64 = 0.3;
+
+/// A link to an article found on a listing page
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArticleLink {
+    /// Anchor text of the link
+    pub title: String,
+    /// Link target, as written in the page until resolved against the page URL
+    pub url: String,
+}
+
+/// Classify a cleaned document as a listing page and return its article links,
+/// or `None` when the page looks like an article
+pub fn harvest_listing(document: &Html) -> Option<Vec<ArticleLink>> {
+    let main = main_area(document)?;
+    let links = article_links(main);
+    if links.len() < MIN_ARTICLE_LINKS {
+        return None;
+    }
+
+    let total_length = element_text_length(&main);
+    let link_length: usize = links.iter().map(|link| text_length(&link.title)).sum();
+    if total_length == 0 || (link_length as f64 / total_length as f64) < MIN_LINK_TEXT_RATIO {
+        return None;
+    }
+
+    Some(links)
+}
+
+/// Resolve harvested links against the URL of the listing page
+pub fn resolve_links(links: &mut [ArticleLink], base: &Url) {
+    for link in links {
+        if let Ok(url) = base.join(&link.url) {
+            link.url = url.to_string();
+        }
+    }
+}
+
+/// The `main` element if the page declares one, the body otherwise
+fn main_area(document: &Html) -> Option<ElementRef<'_>> {
+    ["main", "[role='main']", "body"].iter()
+        .filter_map(|selector| try_selector(selector))
+        .find_map(|selector| document.select(&selector).next())
+}
+
+/// Links with headline-like anchor text, deduplicated by target
+fn article_links(main: ElementRef) -> Vec<ArticleLink> {
+    let mut seen = HashSet::new();
+
+    descendants_named(main, &["a"])
+        .filter_map(|anchor| {
+            let url = anchor.value().attr("href")?.trim();
+            if url.is_empty() || url.starts_with('#') || url.starts_with("javascript:") || url.starts_with("mailto:") {
+                return None;
+            }
+
+            let title = anchor.text().collect::<Vec<_>>().join(" ");
+            let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+            (count_words(&title) >= MIN_TITLE_WORDS).then(|| ArticleLink { title, url: url.to_string() })
+        })
+        .filter(|link| seen.insert(link.url.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn teaser(n: usize) -> String {
+        format!(
+            r#"<article><h2><a href="/news/story-{n}">Headline number {n} about the news</a></h2>
+            <p>Short teaser {n}.</p></article>"#
+        )
+    }
+
+    #[test]
+    fn test_harvest_listing() {
+        let teasers: String = (1..=6).map(teaser).collect();
+        let html = format!(r##"<html><body><main>{teasers}<a href="#top">Back to the top of page</a></main></body></html>"##);
+
+        let mut links = harvest_listing(&Html::parse_document(&html)).unwrap();
+        resolve_links(&mut links, &Url::parse("https://example.com/section/").unwrap());
+
+        assert_eq!(links.len(), 6);
+        assert_eq!(links[0], ArticleLink {
+            title: "Headline number 1 about the news".into(),
+            url: "https://example.com/news/story-1".into(),
+        });
+    }
+
+    #[test]
+    fn test_article_is_not_listing() {
+        let paragraph = "<p>This is a long paragraph of article text that goes on and on about the topic. </p>".repeat(20);
+        let related: String = (1..=5).map(|n| format!(r#"<a href="/r{n}">Related story number {n}</a>"#)).collect();
+        let html = format!("<html><body>{paragraph}{related}</body></html>");
+
+        assert_eq!(harvest_listing(&Html::parse_document(&html)), None);
+    }
+}
@@ -1,5 +1,12 @@
 //! Main executable for Trafilatura Rust port.
 
+use trafilatura::memory::TrackingAllocator;
+
+/// Counts the allocations of each thread, so batches report the memory every
+/// document used and can enforce `--memory-budget`
+#[global_allocator]
+static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
 fn main() {
     if let Err(e) = trafilatura::cli::run() {
         eprintln!("Error: {}", e);
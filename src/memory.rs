@@ -0,0 +1,215 @@
+//! Memory accounting for Trafilatura Rust port.
+//! This module counts the heap allocations of each thread through an optional
+//! allocator shim, so batches can report the peak memory of every document and
+//! stop extracting documents that exceed `ExtractionConfig::memory_budget`.
+//! Binaries opt in by installing the shim, as the `trafilatura` binary does:
+//!
+//! ```
+//! #[global_allocator]
+//! static ALLOCATOR: trafilatura::memory::TrackingAllocator = trafilatura::memory::TrackingAllocator;
+//! # fn main() {}
+//! ```
+//!
+//! Without the shim, budgets are checked against an estimate from the size of the HTML.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::TrafilaturaError;
+
+/// Estimated peak memory of extracting a document, per byte of its HTML: about what
+/// pages of hundreds of kilobytes take, smaller pages take more. Documents whose
+/// estimate exceeds the budget are refused before they are parsed.
+pub const ESTIMATED_BYTES_PER_HTML_BYTE: usize = 8;
+
+/// Documents measured before peaks are compared with the batch mean
+const MIN_MEASURED_DOCUMENTS: usize = 8;
+
+/// Multiple of the batch mean above which a peak is an outlier
+const OUTLIER_FACTOR: usize = 10;
+
+/// Whether the shim has served an allocation
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// Bytes allocated and not yet freed by this thread. Frees of memory allocated
+    /// on other threads can make it negative.
+    static LIVE: Cell<isize> = const { Cell::new(0) };
+    /// Highest value of `LIVE` since the innermost `MemoryScope` started
+    static PEAK: Cell<isize> = const { Cell::new(0) };
+    /// Allocations made by this thread, reallocations included
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Global allocator counting the allocations of each thread on top of the system
+/// allocator
+pub struct TrackingAllocator;
+
+impl TrackingAllocator {
+    fn record(&self, delta: isize, allocations: usize) {
+        if !INSTALLED.load(Ordering::Relaxed) {
+            INSTALLED.store(true, Ordering::Relaxed);
+        }
+        // Thread locals are gone while a thread shuts down; its last frees are not counted
+        let _ = LIVE.try_with(|live| {
+            let value = live.get() + delta;
+            live.set(value);
+            let _ = PEAK.try_with(|peak| peak.set(peak.get().max(value)));
+        });
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + allocations));
+    }
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            self.record(layout.size() as isize, 1);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            self.record(layout.size() as isize, 1);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        self.record(-(layout.size() as isize), 0);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            self.record(new_size as isize - layout.size() as isize, 1);
+        }
+        new_ptr
+    }
+}
+
+/// Check if `TrackingAllocator` is the global allocator
+pub fn is_tracking() -> bool {
+    INSTALLED.load(Ordering::Relaxed)
+}
+
+/// Memory used while extracting a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MemoryUsage {
+    /// Highest number of bytes allocated at once
+    pub peak_bytes: usize,
+    /// Number of allocations
+    pub allocations: usize,
+}
+
+/// Measures the allocations of the current thread from its start until it is
+/// dropped. Scopes nest: an inner scope does not hide its peak from the outer one.
+pub struct MemoryScope {
+    base: isize,
+    allocations: usize,
+    outer_peak: isize,
+}
+
+impl MemoryScope {
+    /// Start measuring on the current thread
+    pub fn start() -> Self {
+        let base = LIVE.with(Cell::get);
+        Self {
+            base,
+            allocations: ALLOCATIONS.with(Cell::get),
+            outer_peak: PEAK.with(|peak| peak.replace(base)),
+        }
+    }
+
+    /// Memory used since the scope started, or `None` without `TrackingAllocator`
+    pub fn usage(&self) -> Option<MemoryUsage> {
+        is_tracking().then(|| MemoryUsage {
+            peak_bytes: (PEAK.with(Cell::get) - self.base).max(0) as usize,
+            allocations: ALLOCATIONS.with(Cell::get) - self.allocations,
+        })
+    }
+
+    /// Fail with `TrafilaturaError::MemoryLimit` if the peak so far exceeds the budget
+    pub fn check(&self, budget: Option<usize>) -> Result<(), TrafilaturaError> {
+        match (budget, self.usage()) {
+            (Some(budget), Some(usage)) if usage.peak_bytes > budget => {
+                Err(TrafilaturaError::MemoryLimit(usage.peak_bytes, budget))
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Drop for MemoryScope {
+    fn drop(&mut self) {
+        PEAK.with(|peak| peak.set(peak.get().max(self.outer_peak)));
+    }
+}
+
+/// Refuse a document whose estimated peak exceeds the budget before parsing it
+pub fn check_estimate(html: &str, budget: Option<usize>) -> Result<(), TrafilaturaError> {
+    let estimate = html.len().saturating_mul(ESTIMATED_BYTES_PER_HTML_BYTE);
+    match budget {
+        Some(budget) if estimate > budget => Err(TrafilaturaError::MemoryLimit(estimate, budget)),
+        _ => Ok(()),
+    }
+}
+
+/// Running mean of the peaks of a batch, to spot documents that use far more memory
+/// than the others
+#[derive(Debug, Default)]
+pub struct PeakTracker {
+    total: AtomicUsize,
+    documents: AtomicUsize,
+}
+
+impl PeakTracker {
+    /// Add the peak of a document, and return how many times the mean of the
+    /// documents before it the peak is, if it is an outlier
+    pub fn record(&self, usage: MemoryUsage) -> Option<usize> {
+        let total = self.total.fetch_add(usage.peak_bytes, Ordering::Relaxed);
+        let documents = self.documents.fetch_add(1, Ordering::Relaxed);
+        if documents < MIN_MEASURED_DOCUMENTS {
+            return None;
+        }
+        let mean = (total / documents).max(1);
+        (usage.peak_bytes / mean >= OUTLIER_FACTOR).then_some(usage.peak_bytes / mean)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_estimate() {
+        let html = "<p>text</p>".repeat(100);
+        assert!(check_estimate(&html, None).is_ok());
+        assert!(check_estimate(&html, Some(html.len() * ESTIMATED_BYTES_PER_HTML_BYTE)).is_ok());
+        assert!(matches!(check_estimate(&html, Some(html.len())), Err(TrafilaturaError::MemoryLimit(_, _))));
+    }
+
+    #[test]
+    fn test_peak_tracker() {
+        let tracker = PeakTracker::default();
+        let usage = |peak_bytes| MemoryUsage { peak_bytes, allocations: 1 };
+        for _ in 0..MIN_MEASURED_DOCUMENTS {
+            assert_eq!(tracker.record(usage(1000)), None);
+        }
+        assert_eq!(tracker.record(usage(50_000)), Some(50));
+        assert_eq!(tracker.record(usage(5000)), None);
+    }
+
+    #[test]
+    fn test_scope_without_tracking_allocator() {
+        // The test binary uses the system allocator
+        let scope = MemoryScope::start();
+        let _buffer = vec![0u8; 1 << 20];
+        assert_eq!(scope.usage(), None);
+        assert!(scope.check(Some(1)).is_ok());
+    }
+}
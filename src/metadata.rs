@@ -1,48 +1,329 @@
 //! Metadata extraction for Trafilatura Rust port.
 //! This module contains utilities for extracting metadata from HTML documents.
 
-use scraper::{Html, Selector};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use scraper::Html;
 use regex::Regex;
 use lazy_static::lazy_static;
+use serde::Deserialize;
+use serde_json::Value;
+use url::Url;
 
+use crate::dates::DateOptions;
+use crate::utils::try_selector;
 use crate::{ExtractionResult, TrafilaturaError};
 
 lazy_static! {
-    /// Regex to match dates in common formats
-    static ref DATE_REGEX: Regex = Regex::new(
-        r"(?i)\d{4}[-/]\d{1,2}[-/]\d{1,2}|\d{1,2}[-/]\d{1,2}[-/]\d{4}|(?:Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)[a-z]* \d{1,2},? \d{4}"
+    /// Copyright notices: `©`, `(c)`, or `Copyright` followed by a year or range of years
+    static ref COPYRIGHT_NOTICE_RE: Regex = Regex::new(
+        r"(?i)(?:©|\(c\)|copyright)\s*(?:©\s*)?(?:19|20)\d{2}(?:\s*[-–—]\s*(?:19|20)\d{2})?"
+    ).unwrap();
+
+    /// Everything in a copyright meta tag besides the holder
+    static ref COPYRIGHT_NOISE_RE: Regex = Regex::new(
+        r"(?i)©|\(c\)|\bcopyright\b|\b(?:19|20)\d{2}\b|[-–—]|\ball rights reserved\b|\bby\b"
     ).unwrap();
+
+    /// Prefixes of DOIs given as `doi:` URIs or resolver links
+    static ref DOI_PREFIX_RE: Regex = Regex::new(r"(?i)^(?:doi:\s*|https?://(?:dx\.)?doi\.org/)").unwrap();
+
+    static ref YEAR_RE: Regex = Regex::new(r"\b(?:19|20)\d{2}\b").unwrap();
+
+    /// Separators between the names of several authors
+    static ref AUTHOR_SEPARATOR_RE: Regex = Regex::new(r"\s*[;,|]\s*|\s+and\s+").unwrap();
+
+    /// Separators left at the edges of a field after a phrase was removed
+    static ref DANGLING_SEPARATOR_RE: Regex = Regex::new(r"^[\s|:·•\-–—]+|[\s|:·•\-–—]+$").unwrap();
+}
+
+/// Author names and phrases removed from the metadata of every page, or of the
+/// pages of one domain
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SiteBlacklist {
+    /// Generic bylines that are not author names, e.g. `Staff Writer`, compared
+    /// case-insensitively with each author of a byline
+    pub authors: Vec<String>,
+    /// Sitewide phrases cut from titles, descriptions, and authors, e.g. a site
+    /// name appended to every title
+    pub phrases: Vec<String>,
+}
+
+/// Author and phrase blacklists for all pages and for specific domains
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetadataBlacklist {
+    /// Entries applying to every page
+    #[serde(flatten)]
+    pub global: SiteBlacklist,
+    /// Entries applying to a domain and its subdomains, keyed by domain name
+    pub domains: BTreeMap<String, SiteBlacklist>,
+}
+
+/// The sections of a config file the metadata blacklist is read from
+#[derive(Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    metadata_blacklist: MetadataBlacklist,
+}
+
+impl MetadataBlacklist {
+    /// Read the `metadata_blacklist` section of a JSON config file, e.g.
+    /// `{"metadata_blacklist": {"authors": ["Admin"], "domains": {"example.com": {"phrases": ["Example News"]}}}}`
+    pub fn from_file(path: &Path) -> Result<Self, TrafilaturaError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_json(&content)
+    }
+
+    /// Parse the `metadata_blacklist` section of a JSON config document
+    pub fn from_json(content: &str) -> Result<Self, TrafilaturaError> {
+        let file: ConfigFile = serde_json::from_str(content)?;
+        Ok(file.metadata_blacklist)
+    }
+
+    /// Check if nothing is blacklisted
+    pub fn is_empty(&self) -> bool {
+        self.global == SiteBlacklist::default() && self.domains.is_empty()
+    }
+
+    /// The global entries, and the entries of every domain matching a host
+    fn for_host<'a>(&'a self, host: Option<&'a str>) -> impl Iterator<Item = &'a SiteBlacklist> {
+        let domains = self.domains.iter()
+            .filter(move |(domain, _)| host.is_some_and(|host| {
+                // Compared as bytes, since hosts of non-HTTP URLs need not be ASCII
+                let (host, domain) = (host.as_bytes(), domain.trim_start_matches('.').as_bytes());
+                host.eq_ignore_ascii_case(domain)
+                    || host.len() > domain.len()
+                        && host[host.len() - domain.len()..].eq_ignore_ascii_case(domain)
+                        && host[host.len() - domain.len() - 1] == b'.'
+            }))
+            .map(|(_, entries)| entries);
+        std::iter::once(&self.global).chain(domains)
+    }
+
+    /// Remove blacklisted authors and phrases from a result. Pages are matched to
+    /// domains by their URL, or by their canonical URL before the URL is known.
+    pub fn apply(&self, result: &mut ExtractionResult) {
+        if self.is_empty() {
+            return;
+        }
+        let url = result.url.as_deref().or(result.canonical_url.as_deref()).and_then(|url| Url::parse(url).ok());
+        let host = url.as_ref().and_then(Url::host_str);
+        let entries: Vec<&SiteBlacklist> = self.for_host(host).collect();
+
+        for phrase in entries.iter().flat_map(|entries| &entries.phrases) {
+            for field in [&mut result.title, &mut result.description, &mut result.author] {
+                *field = field.take().and_then(|value| remove_phrase(&value, phrase));
+            }
+        }
+
+        let blacklisted = |name: &str| entries.iter().flat_map(|entries| &entries.authors).any(|author| author.trim().eq_ignore_ascii_case(name));
+        if let Some(author) = result.author.take() {
+            let names: Vec<&str> = AUTHOR_SEPARATOR_RE.split(&author).filter(|name| !name.is_empty()).collect();
+            result.author = if names.iter().any(|name| blacklisted(name)) {
+                let kept: Vec<&str> = names.into_iter().filter(|name| !blacklisted(name)).collect();
+                (!kept.is_empty()).then(|| kept.join("; "))
+            } else {
+                Some(author)
+            };
+        }
+    }
+}
+
+/// Cut every case-insensitive occurrence of a phrase from a field, with separators
+/// left dangling at its edges; `None` when nothing else remains
+fn remove_phrase(value: &str, phrase: &str) -> Option<String> {
+    let phrase = phrase.trim();
+    if phrase.is_empty() {
+        return Some(value.to_string());
+    }
+    let pattern = Regex::new(&format!("(?i){}", regex::escape(phrase))).ok()?;
+    if !pattern.is_match(value) {
+        return Some(value.to_string());
+    }
+    let removed = pattern.replace_all(value, " ");
+    let cleaned = DANGLING_SEPARATOR_RE.replace_all(&removed, "");
+    let cleaned = cleaned.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!cleaned.is_empty()).then_some(cleaned)
+}
+
+/// A metadata field that can be requested with `ExtractionConfig::metadata_fields`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MetadataField {
+    Title,
+    Author,
+    AuthorUrl,
+    Date,
+    Description,
+    Publisher,
+    CopyrightYear,
+    Sitename,
+    Language,
+    CanonicalUrl,
+    /// Citation metadata of academic pages
+    Academic,
+    OgType,
+    /// Language variants declared with hreflang links
+    Alternates,
+    /// Categories and tags
+    Categories,
+}
+
+/// A language variant of the document declared with `link[rel=alternate][hreflang]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguageAlternate {
+    /// Language code of the variant (e.g. `en`, `de-AT`, `x-default`)
+    pub hreflang: String,
+    /// URL of the variant
+    pub url: String,
+}
+
+/// Citation metadata of journal articles and preprints, from the Highwire Press
+/// `citation_*` meta tags read by Google Scholar
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AcademicMetadata {
+    /// DOI without a `doi:` or resolver prefix, e.g. `10.1000/xyz123`
+    pub doi: Option<String>,
+    /// Title of the article
+    pub title: Option<String>,
+    /// Authors in the order of the `citation_author` tags
+    pub authors: Vec<String>,
+    /// Journal, or conference for proceedings papers
+    pub journal_title: Option<String>,
+    /// Publisher of the journal
+    pub publisher: Option<String>,
+    /// Publication date as given, often `YYYY/MM/DD`
+    pub publication_date: Option<String>,
+    /// Journal volume
+    pub volume: Option<String>,
+    /// Journal issue
+    pub issue: Option<String>,
+    /// First page of the article
+    pub first_page: Option<String>,
+    /// Last page of the article
+    pub last_page: Option<String>,
+    /// ISSN of the journal, print or electronic
+    pub issn: Option<String>,
+    /// arXiv identifier of preprints
+    pub arxiv_id: Option<String>,
+    /// Full text PDF
+    pub pdf_url: Option<String>,
+}
+
+impl AcademicMetadata {
+    /// The fields set, except the authors, by name
+    pub fn fields(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("doi", &self.doi),
+            ("title", &self.title),
+            ("journal_title", &self.journal_title),
+            ("publisher", &self.publisher),
+            ("publication_date", &self.publication_date),
+            ("volume", &self.volume),
+            ("issue", &self.issue),
+            ("first_page", &self.first_page),
+            ("last_page", &self.last_page),
+            ("issn", &self.issn),
+            ("arxiv_id", &self.arxiv_id),
+            ("pdf_url", &self.pdf_url),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value.as_deref()?)))
+        .collect()
+    }
 }
 
 /// Extract metadata from a document
-pub fn extract_metadata(document: &Html, mut result: ExtractionResult) -> Result<ExtractionResult, TrafilaturaError> {
+pub fn extract_metadata(document: &Html, result: ExtractionResult) -> Result<ExtractionResult, TrafilaturaError> {
+    extract_metadata_fields(document, result, None, &DateOptions::default())
+}
+
+/// Extract the requested metadata fields from a document, or all of them for `None`,
+/// reading dates written in the text with `dates`
+pub fn extract_metadata_fields(
+    document: &Html,
+    mut result: ExtractionResult,
+    fields: Option<&HashSet<MetadataField>>,
+    dates: &DateOptions,
+) -> Result<ExtractionResult, TrafilaturaError> {
+    let wants = |field: MetadataField| fields.is_none_or(|fields| fields.contains(&field));
     // Extract title if not already set
-    if result.title.is_none() {
+    if wants(MetadataField::Title) && result.title.is_none() {
         result.title = extract_title(document);
     }
     
     // Extract author if not already set
-    if result.author.is_none() {
+    if wants(MetadataField::Author) && result.author.is_none() {
         result.author = extract_author(document);
     }
     
+    // Extract author profile URL if not already set
+    if wants(MetadataField::AuthorUrl) && result.author_url.is_none() {
+        // Relative profile links resolve against the page, or else its canonical URL
+        let base = result.url.clone()
+            .or_else(|| result.canonical_url.clone())
+            .or_else(|| extract_canonical_url(document))
+            .and_then(|url| Url::parse(&url).ok());
+        result.author_url = extract_author_url(document, base.as_ref());
+    }
+    
     // Extract date if not already set
-    if result.date.is_none() {
-        result.date = extract_date(document);
+    if wants(MetadataField::Date) && result.date.is_none() {
+        result.date = extract_date(document, dates);
     }
     
     // Extract description if not already set
-    if result.description.is_none() {
+    if wants(MetadataField::Description) && result.description.is_none() {
         result.description = extract_description(document);
     }
     
+    // Extract publisher if not already set
+    if wants(MetadataField::Publisher) && result.publisher.is_none() {
+        result.publisher = extract_publisher(document);
+    }
+    
+    // Extract copyright year if not already set
+    if wants(MetadataField::CopyrightYear) && result.copyright_year.is_none() {
+        result.copyright_year = extract_copyright_year(document);
+    }
+    
     // Extract sitename if not already set
-    if result.sitename.is_none() {
+    if wants(MetadataField::Sitename) && result.sitename.is_none() {
         result.sitename = extract_sitename(document);
     }
     
+    // Extract language if not already set
+    if wants(MetadataField::Language) && result.language.is_none() {
+        result.language = extract_language(document);
+    }
+    
+    // Extract canonical URL if not already set
+    if wants(MetadataField::CanonicalUrl) && result.canonical_url.is_none() {
+        result.canonical_url = extract_canonical_url(document);
+    }
+    
+    // Extract citation metadata of academic pages
+    if wants(MetadataField::Academic) && result.academic.is_none() {
+        result.academic = extract_academic(document);
+    }
+    
+    // Extract Open Graph type if not already set
+    if wants(MetadataField::OgType) && result.og_type.is_none() {
+        result.og_type = extract_og_type(document);
+    }
+    
+    // Extract language alternates
+    if wants(MetadataField::Alternates) {
+        result.alternates = extract_alternates(document);
+    }
+    
     // Extract categories
-    result.categories = extract_categories(document);
+    if wants(MetadataField::Categories) {
+        result.categories = extract_categories(document);
+    }
     
     Ok(result)
 }
@@ -50,7 +331,7 @@ pub fn extract_metadata(document: &Html, mut result: ExtractionResult) -> Result
 /// Extract the title from a document
 fn extract_title(document: &Html) -> Option<String> {
     // Try Open Graph title
-    let og_title_selector = Selector::parse("meta[property='og:title']").unwrap();
+    let og_title_selector = try_selector("meta[property='og:title']")?;
     if let Some(og_title) = document.select(&og_title_selector).next() {
         if let Some(content) = og_title.value().attr("content") {
             if !content.is_empty() {
@@ -60,7 +341,7 @@ fn extract_title(document: &Html) -> Option<String> {
     }
     
     // Try Twitter title
-    let twitter_title_selector = Selector::parse("meta[name='twitter:title']").unwrap();
+    let twitter_title_selector = try_selector("meta[name='twitter:title']")?;
     if let Some(twitter_title) = document.select(&twitter_title_selector).next() {
         if let Some(content) = twitter_title.value().attr("content") {
             if !content.is_empty() {
@@ -70,7 +351,7 @@ fn extract_title(document: &Html) -> Option<String> {
     }
     
     // Try standard title tag
-    let title_selector = Selector::parse("title").unwrap();
+    let title_selector = try_selector("title")?;
     if let Some(title) = document.select(&title_selector).next() {
         let title_text = title.text().collect::<Vec<_>>().join(" ");
         if !title_text.is_empty() {
@@ -79,7 +360,7 @@ fn extract_title(document: &Html) -> Option<String> {
     }
     
     // Try h1
-    let h1_selector = Selector::parse("h1").unwrap();
+    let h1_selector = try_selector("h1")?;
     if let Some(h1) = document.select(&h1_selector).next() {
         let h1_text = h1.text().collect::<Vec<_>>().join(" ");
         if !h1_text.is_empty() {
@@ -93,7 +374,7 @@ fn extract_title(document: &Html) -> Option<String> {
 /// Extract the author from a document
 fn extract_author(document: &Html) -> Option<String> {
     // Try meta author
-    let meta_author_selector = Selector::parse("meta[name='author']").unwrap();
+    let meta_author_selector = try_selector("meta[name='author']")?;
     if let Some(meta_author) = document.select(&meta_author_selector).next() {
         if let Some(content) = meta_author.value().attr("content") {
             if !content.is_empty() {
@@ -103,7 +384,7 @@ fn extract_author(document: &Html) -> Option<String> {
     }
     
     // Try article:author
-    let og_author_selector = Selector::parse("meta[property='article:author']").unwrap();
+    let og_author_selector = try_selector("meta[property='article:author']")?;
     if let Some(og_author) = document.select(&og_author_selector).next() {
         if let Some(content) = og_author.value().attr("content") {
             if !content.is_empty() {
@@ -114,7 +395,7 @@ fn extract_author(document: &Html) -> Option<String> {
     
     // Try common author classes
     for class_name in &["author", "byline", "dc-creator"] {
-        let selector = Selector::parse(&format!(".{}", class_name)).unwrap();
+        let selector = try_selector(&format!(".{}", class_name))?;
         if let Some(author_elem) = document.select(&selector).next() {
             let author_text = author_elem.text().collect::<Vec<_>>().join(" ");
             if !author_text.is_empty() {
@@ -126,66 +407,228 @@ fn extract_author(document: &Html) -> Option<String> {
     None
 }
 
-/// Extract the date from a document
-fn extract_date(document: &Html) -> Option<String> {
-    // Try published date meta
-    let published_time_selector = Selector::parse("meta[property='article:published_time']").unwrap();
-    if let Some(meta_date) = document.select(&published_time_selector).next() {
-        if let Some(content) = meta_date.value().attr("content") {
-            if !content.is_empty() {
-                return Some(content.to_string());
-            }
+/// Extract the author profile URL from `rel=author` links or JSON-LD `author.url`,
+/// resolved against `base`; without one, relative URLs are left as they are
+fn extract_author_url(document: &Html, base: Option<&Url>) -> Option<String> {
+    let resolve = |href: String| match base {
+        Some(base) => base.join(&href).ok().map(String::from),
+        None => Some(href),
+    };
+    let rel_selector = try_selector("a[rel~='author'][href], link[rel~='author'][href]")?;
+    for link in document.select(&rel_selector) {
+        let href = link.value().attr("href").unwrap_or("").trim();
+        if !href.is_empty() && !href.starts_with('#') && !href.starts_with("mailto:") {
+            return resolve(href.to_string());
         }
     }
     
-    // Try date meta
-    let date_selector = Selector::parse("meta[name='date']").unwrap();
-    if let Some(meta_date) = document.select(&date_selector).next() {
-        if let Some(content) = meta_date.value().attr("content") {
-            if !content.is_empty() {
-                return Some(content.to_string());
-            }
+    json_ld(document).iter()
+        .find_map(|value| find_json_ld(value, &|item| item.get("author").and_then(profile_url)))
+        .and_then(resolve)
+}
+
+/// The `url` of a JSON-LD person or organization, or its `@id` when that is a URL
+fn profile_url(author: &Value) -> Option<String> {
+    match author {
+        Value::Array(authors) => authors.iter().find_map(profile_url),
+        Value::Object(author) => {
+            let url = match author.get("url") {
+                Some(Value::Array(urls)) => urls.first(),
+                url => url,
+            };
+            url.and_then(Value::as_str)
+                .or_else(|| author.get("@id").and_then(Value::as_str).filter(|id| id.starts_with("http")))
+                .map(str::trim)
+                .filter(|url| !url.is_empty())
+                .map(str::to_string)
+        },
+        _ => None,
+    }
+}
+
+/// Extract the publisher from JSON-LD `publisher.name`, `og:site_name`, or the holder
+/// named in a copyright meta tag
+fn extract_publisher(document: &Html) -> Option<String> {
+    let publisher = json_ld(document).iter()
+        .find_map(|value| find_json_ld(value, &|item| item.get("publisher").and_then(organization_name)));
+    if publisher.is_some() {
+        return publisher;
+    }
+    
+    if let Some(site_name) = meta_content(document, "meta[property='og:site_name']") {
+        return Some(site_name);
+    }
+    
+    let rights = meta_content(document, "meta[name='copyright' i], meta[name='dcterms.rightsHolder' i], meta[name='dc.rights' i]")?;
+    let holder = COPYRIGHT_NOISE_RE.replace_all(&rights, " ");
+    let holder = holder.split_whitespace().collect::<Vec<_>>().join(" ");
+    let holder = holder.trim_matches(|c: char| c.is_whitespace() || matches!(c, '.' | ',' | '-' | '|'));
+    (!holder.is_empty()).then(|| holder.to_string())
+}
+
+/// The `name` of a JSON-LD organization, or the publisher itself when given as text
+fn organization_name(publisher: &Value) -> Option<String> {
+    let name = match publisher {
+        Value::Array(publishers) => return publishers.iter().find_map(organization_name),
+        Value::Object(publisher) => publisher.get("name")?.as_str()?,
+        Value::String(name) => name,
+        _ => return None,
+    };
+    let name = name.trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Extract the copyright year from JSON-LD `copyrightYear`, a copyright meta tag, or
+/// a `©` notice in the page footer. Ranges such as `2009–2024` give the latest year.
+fn extract_copyright_year(document: &Html) -> Option<u16> {
+    let year = json_ld(document).iter().find_map(|value| find_json_ld(value, &|item| match item.get("copyrightYear")? {
+        Value::Number(year) => year.as_u64().and_then(|year| u16::try_from(year).ok()),
+        Value::String(year) => latest_year(year),
+        _ => None,
+    }));
+    if year.is_some() {
+        return year;
+    }
+    
+    if let Some(year) = meta_content(document, "meta[name='copyright' i], meta[name='dc.rights' i]").and_then(|rights| latest_year(&rights)) {
+        return Some(year);
+    }
+    
+    let footer_selector = try_selector("footer, [class*='copyright'], [id*='copyright']")?;
+    document.select(&footer_selector).find_map(|footer| {
+        let text = footer.text().collect::<String>();
+        COPYRIGHT_NOTICE_RE.captures(&text).and_then(|notice| latest_year(&notice[0]))
+    })
+}
+
+/// Extract the citation metadata of journal and preprint pages, if any `citation_*`
+/// meta tag is present
+fn extract_academic(document: &Html) -> Option<AcademicMetadata> {
+    let citation = |names: &[&str]| names.iter().find_map(|name| meta_content(document, &format!("meta[name='{}' i]", name)));
+    let author_selector = try_selector("meta[name='citation_author' i]")?;
+    
+    let academic = AcademicMetadata {
+        doi: citation(&["citation_doi", "dc.identifier.doi"]).map(|doi| DOI_PREFIX_RE.replace(&doi, "").into_owned()),
+        title: citation(&["citation_title"]),
+        authors: document.select(&author_selector)
+            .filter_map(|meta| meta.value().attr("content"))
+            .map(str::trim)
+            .filter(|author| !author.is_empty())
+            .map(str::to_string)
+            .collect(),
+        journal_title: citation(&["citation_journal_title", "citation_conference_title", "citation_inbook_title"]),
+        publisher: citation(&["citation_publisher"]),
+        publication_date: citation(&["citation_publication_date", "citation_date", "citation_online_date"]),
+        volume: citation(&["citation_volume"]),
+        issue: citation(&["citation_issue"]),
+        first_page: citation(&["citation_firstpage"]),
+        last_page: citation(&["citation_lastpage"]),
+        issn: citation(&["citation_issn", "citation_eissn"]),
+        arxiv_id: citation(&["citation_arxiv_id"]),
+        pdf_url: citation(&["citation_pdf_url"]),
+    };
+    (academic != AcademicMetadata::default()).then_some(academic)
+}
+
+/// The latest four-digit year mentioned in a text
+fn latest_year(text: &str) -> Option<u16> {
+    YEAR_RE.find_iter(text).filter_map(|year| year.as_str().parse().ok()).max()
+}
+
+/// The content of the first matching meta tag that is not empty
+fn meta_content(document: &Html, selector: &str) -> Option<String> {
+    let selector = try_selector(selector)?;
+    document.select(&selector)
+        .filter_map(|meta| meta.value().attr("content"))
+        .map(str::trim)
+        .find(|content| !content.is_empty())
+        .map(str::to_string)
+}
+
+/// Parse the JSON-LD blocks of a document, skipping invalid ones
+fn json_ld(document: &Html) -> Vec<Value> {
+    let Some(selector) = try_selector("script[type='application/ld+json' i]") else {
+        return Vec::new();
+    };
+    document.select(&selector)
+        .filter_map(|script| serde_json::from_str(&script.text().collect::<String>()).ok())
+        .collect()
+}
+
+/// Apply `find` to the JSON-LD items of a value, searching lists of items and `@graph`
+fn find_json_ld<T>(value: &Value, find: &dyn Fn(&serde_json::Map<String, Value>) -> Option<T>) -> Option<T> {
+    match value {
+        Value::Array(items) => items.iter().find_map(|item| find_json_ld(item, find)),
+        Value::Object(item) => find(item).or_else(|| item.get("@graph").and_then(|graph| find_json_ld(graph, find))),
+        _ => None,
+    }
+}
+
+/// Where a date of a page was found
+#[derive(Clone, Copy, PartialEq)]
+enum DateSource {
+    /// Meta tags and `datetime` attributes, kept as they are when starting with the year
+    Machine,
+    /// Text of `time` elements, used only when it reads as a date
+    Time,
+    /// Text of elements with a date class, kept as it is when it does not read as a date
+    Label,
+}
+
+/// Extract the date from a document. Dates of meta tags and `datetime` attributes
+/// starting with the year are kept as they are; other dates are read in the locale
+/// of `dates` or of the page, and given as `YYYY-MM-DD`. Dates outside the window
+/// of `dates` are skipped for the next place a date is found.
+fn extract_date(document: &Html, dates: &DateOptions) -> Option<String> {
+    let mut candidates: Vec<(String, DateSource)> = Vec::new();
+    
+    // Try published date meta, then date meta
+    for selector in ["meta[property='article:published_time']", "meta[name='date']"] {
+        if let Some(content) = meta_content(document, selector) {
+            candidates.push((content, DateSource::Machine));
         }
     }
     
     // Try time elements
-    let time_selector = Selector::parse("time").unwrap();
+    let time_selector = try_selector("time")?;
     if let Some(time) = document.select(&time_selector).next() {
-        if let Some(datetime) = time.value().attr("datetime") {
-            if !datetime.is_empty() {
-                return Some(datetime.to_string());
-            }
-        }
-        
-        let time_text = time.text().collect::<Vec<_>>().join(" ");
-        if !time_text.is_empty() {
-            if let Some(date_match) = DATE_REGEX.find(&time_text) {
-                return Some(date_match.as_str().to_string());
-            }
+        if let Some(datetime) = time.value().attr("datetime").map(str::trim).filter(|datetime| !datetime.is_empty()) {
+            candidates.push((datetime.to_string(), DateSource::Machine));
         }
+        candidates.push((time.text().collect::<Vec<_>>().join(" "), DateSource::Time));
     }
     
     // Try date classes
     for class_name in &["date", "published", "timestamp", "post-date"] {
-        let selector = Selector::parse(&format!(".{}", class_name)).unwrap();
+        let selector = try_selector(&format!(".{}", class_name))?;
         if let Some(date_elem) = document.select(&selector).next() {
             let date_text = date_elem.text().collect::<Vec<_>>().join(" ");
             if !date_text.is_empty() {
-                if let Some(date_match) = DATE_REGEX.find(&date_text) {
-                    return Some(date_match.as_str().to_string());
-                }
-                return Some(date_text);
+                candidates.push((date_text, DateSource::Label));
             }
         }
     }
     
+    let language = extract_language(document);
+    for (value, source) in candidates {
+        let found = dates.parse_all(&value, language.as_deref());
+        match found.iter().copied().find(|&date| dates.in_window(date)) {
+            Some(_) if source == DateSource::Machine && value.get(..4).is_some_and(|year| year.bytes().all(|b| b.is_ascii_digit())) => {
+                return Some(value);
+            },
+            Some(date) => return Some(date.format("%Y-%m-%d").to_string()),
+            None if found.is_empty() && source != DateSource::Time => return Some(value),
+            None => {},
+        }
+    }
+    
     None
 }
 
 /// Extract the description from a document
 fn extract_description(document: &Html) -> Option<String> {
     // Try Open Graph description
-    let og_desc_selector = Selector::parse("meta[property='og:description']").unwrap();
+    let og_desc_selector = try_selector("meta[property='og:description']")?;
     if let Some(og_desc) = document.select(&og_desc_selector).next() {
         if let Some(content) = og_desc.value().attr("content") {
             if !content.is_empty() {
@@ -195,7 +638,7 @@ fn extract_description(document: &Html) -> Option<String> {
     }
     
     // Try meta description
-    let meta_desc_selector = Selector::parse("meta[name='description']").unwrap();
+    let meta_desc_selector = try_selector("meta[name='description']")?;
     if let Some(meta_desc) = document.select(&meta_desc_selector).next() {
         if let Some(content) = meta_desc.value().attr("content") {
             if !content.is_empty() {
@@ -205,7 +648,7 @@ fn extract_description(document: &Html) -> Option<String> {
     }
     
     // Try Twitter description
-    let twitter_desc_selector = Selector::parse("meta[name='twitter:description']").unwrap();
+    let twitter_desc_selector = try_selector("meta[name='twitter:description']")?;
     if let Some(twitter_desc) = document.select(&twitter_desc_selector).next() {
         if let Some(content) = twitter_desc.value().attr("content") {
             if !content.is_empty() {
@@ -220,7 +663,7 @@ fn extract_description(document: &Html) -> Option<String> {
 /// Extract the site name from a document
 fn extract_sitename(document: &Html) -> Option<String> {
     // Try Open Graph site name
-    let og_site_selector = Selector::parse("meta[property='og:site_name']").unwrap();
+    let og_site_selector = try_selector("meta[property='og:site_name']")?;
     if let Some(og_site) = document.select(&og_site_selector).next() {
         if let Some(content) = og_site.value().attr("content") {
             if !content.is_empty() {
@@ -230,7 +673,7 @@ fn extract_sitename(document: &Html) -> Option<String> {
     }
     
     // Try copyright
-    let copyright_selector = Selector::parse(".copyright").unwrap();
+    let copyright_selector = try_selector(".copyright")?;
     if let Some(copyright) = document.select(&copyright_selector).next() {
         let text = copyright.text().collect::<Vec<_>>().join(" ");
         if !text.is_empty() {
@@ -241,13 +684,79 @@ fn extract_sitename(document: &Html) -> Option<String> {
     None
 }
 
+/// Extract the document language from `<html lang>`, the `Content-Language`
+/// meta header, or the Open Graph locale
+fn extract_language(document: &Html) -> Option<String> {
+    let declared = [
+        ("html[lang]", "lang"),
+        ("meta[http-equiv='content-language' i]", "content"),
+        ("meta[property='og:locale']", "content"),
+    ];
+    for (selector, attribute) in declared {
+        let selector = try_selector(selector)?;
+        if let Some(element) = document.select(&selector).next() {
+            // The header may list several languages, and Open Graph uses underscores (`en_US`)
+            let language = element.value().attr(attribute).unwrap_or("").split(',').next().unwrap_or("").trim();
+            if !language.is_empty() {
+                return Some(language.replace('_', "-"));
+            }
+        }
+    }
+    
+    None
+}
+
+/// Extract the Open Graph type of a page, lowercased, e.g. `article` or `video.other`
+pub fn extract_og_type(document: &Html) -> Option<String> {
+    meta_content(document, "meta[property='og:type']").map(|og_type| og_type.to_lowercase())
+}
+
+/// Extract the canonical URL declared by the document
+pub fn extract_canonical_url(document: &Html) -> Option<String> {
+    let canonical_selector = try_selector("link[rel='canonical']")?;
+    if let Some(link) = document.select(&canonical_selector).next() {
+        if let Some(href) = link.value().attr("href") {
+            let href = href.trim();
+            if !href.is_empty() {
+                return Some(href.to_string());
+            }
+        }
+    }
+    
+    None
+}
+
+/// Extract the language variants declared with `link[rel=alternate][hreflang]`
+fn extract_alternates(document: &Html) -> Vec<LanguageAlternate> {
+    let mut alternates: Vec<LanguageAlternate> = Vec::new();
+    
+    let alternate_selector = try_selector("link[rel~='alternate'][hreflang]");
+    for link in alternate_selector.iter().flat_map(|selector| document.select(selector)) {
+        let hreflang = link.value().attr("hreflang").unwrap_or("").trim();
+        let href = link.value().attr("href").unwrap_or("").trim();
+        if hreflang.is_empty() || href.is_empty() {
+            continue;
+        }
+        
+        let alternate = LanguageAlternate {
+            hreflang: hreflang.to_string(),
+            url: href.to_string(),
+        };
+        if !alternates.contains(&alternate) {
+            alternates.push(alternate);
+        }
+    }
+    
+    alternates
+}
+
 /// Extract categories and tags from a document
 fn extract_categories(document: &Html) -> Vec<String> {
     let mut categories = Vec::new();
     
     // Try article:section
-    let section_selector = Selector::parse("meta[property='article:section']").unwrap();
-    if let Some(section) = document.select(&section_selector).next() {
+    let section_selector = try_selector("meta[property='article:section']");
+    if let Some(section) = section_selector.iter().flat_map(|selector| document.select(selector)).next() {
         if let Some(content) = section.value().attr("content") {
             if !content.is_empty() {
                 categories.push(content.to_string());
@@ -256,8 +765,8 @@ fn extract_categories(document: &Html) -> Vec<String> {
     }
     
     // Try article:tag
-    let tag_selector = Selector::parse("meta[property='article:tag']").unwrap();
-    for tag in document.select(&tag_selector) {
+    let tag_selector = try_selector("meta[property='article:tag']");
+    for tag in tag_selector.iter().flat_map(|selector| document.select(selector)) {
         if let Some(content) = tag.value().attr("content") {
             if !content.is_empty() {
                 categories.push(content.to_string());
@@ -267,7 +776,9 @@ fn extract_categories(document: &Html) -> Vec<String> {
     
     // Try common tag classes
     for class_name in &["tags", "categories", "category", "topics"] {
-        let selector = Selector::parse(&format!(".{} a", class_name)).unwrap();
+        let Some(selector) = try_selector(&format!(".{} a", class_name)) else {
+            continue;
+        };
         for link in document.select(&selector) {
             let text = link.text().collect::<Vec<_>>().join(" ");
             if !text.is_empty() {
@@ -323,6 +834,130 @@ mod tests {
         assert_eq!(extract_author(&document), Some("John Doe".to_string()));
     }
 
+    #[test]
+    fn test_extract_author_url() {
+        let html = r#"<html><head><script type="application/ld+json">
+            {"@context": "https://schema.org", "@graph": [
+                {"@type": "WebSite", "name": "Example News"},
+                {"@type": "NewsArticle", "author": [{"@type": "Person", "name": "Jane Smith", "url": "https://example.com/authors/jane-smith"}]}
+            ]}
+        </script></head><body><p>By Jane Smith</p></body></html>"#;
+        let document = Html::parse_document(html);
+        assert_eq!(extract_author_url(&document, None), Some("https://example.com/authors/jane-smith".to_string()));
+
+        // rel=author links take precedence, resolved against the page URL
+        let html = html.replace("<p>By Jane Smith</p>", r#"<p>By <a rel="author" href="/staff/jsmith">Jane Smith</a></p>"#);
+        let document = Html::parse_document(&html);
+        let base = Url::parse("https://example.com/2024/05/story").unwrap();
+        assert_eq!(extract_author_url(&document, Some(&base)), Some("https://example.com/staff/jsmith".to_string()));
+        assert_eq!(extract_author_url(&document, None), Some("/staff/jsmith".to_string()));
+
+        // or else against the canonical URL of the page
+        let html = html.replace("<head>", r#"<head><link rel="canonical" href="https://news.example.com/story">"#);
+        let result = extract_metadata(&Html::parse_document(&html), ExtractionResult::default()).unwrap();
+        assert_eq!(result.author_url.as_deref(), Some("https://news.example.com/staff/jsmith"));
+    }
+
+    #[test]
+    fn test_extract_publisher_and_copyright() {
+        let html = r#"<html><head>
+            <meta property="og:site_name" content="The Daily Example">
+            <meta name="copyright" content="© 2009-2023 Example Media Group Ltd. All rights reserved.">
+            <script type="application/ld+json">{"@type": "NewsArticle", "publisher": {"@type": "Organization", "name": "Example Media Group"}}</script>
+        </head><body><footer>© 2024 Example Media Group</footer></body></html>"#;
+        let document = Html::parse_document(html);
+        assert_eq!(extract_publisher(&document), Some("Example Media Group".to_string()));
+        assert_eq!(extract_copyright_year(&document), Some(2023));
+
+        // Without JSON-LD and og:site_name, the holder comes from the copyright meta
+        let html = r#"<html><head><meta name="copyright" content="Copyright (c) 2021 Jane Doe"></head>
+            <body><footer>Copyright 2020–2025 Jane Doe</footer></body></html>"#;
+        let document = Html::parse_document(html);
+        assert_eq!(extract_publisher(&document), Some("Jane Doe".to_string()));
+        assert_eq!(extract_copyright_year(&document), Some(2021));
+
+        let document = Html::parse_document("<html><body><div class='site-copyright'>Photos &copy; 2019–2022 Example</div></body></html>");
+        assert_eq!(extract_copyright_year(&document), Some(2022));
+    }
+
+    #[test]
+    fn test_extract_academic() {
+        let html = r#"<html><head>
+            <meta name="citation_title" content="Measuring Boilerplate in Web Corpora">
+            <meta name="citation_author" content="Doe, Jane">
+            <meta name="citation_author" content="Roe, Richard">
+            <meta name="citation_doi" content="doi:10.1000/xyz123">
+            <meta name="citation_journal_title" content="Journal of Web Linguistics">
+            <meta name="citation_volume" content="12"><meta name="citation_firstpage" content="45">
+            <meta name="citation_publication_date" content="2023/05/17">
+            <meta name="citation_pdf_url" content="https://journal.example.org/article/45.pdf">
+        </head><body></body></html>"#;
+        let academic = extract_academic(&Html::parse_document(html)).unwrap();
+
+        assert_eq!(academic.doi.as_deref(), Some("10.1000/xyz123"));
+        assert_eq!(academic.authors, vec!["Doe, Jane", "Roe, Richard"]);
+        assert_eq!(academic.journal_title.as_deref(), Some("Journal of Web Linguistics"));
+        assert_eq!(academic.pdf_url.as_deref(), Some("https://journal.example.org/article/45.pdf"));
+        assert_eq!(academic.fields().len(), 7);
+
+        assert_eq!(extract_academic(&Html::parse_document("<html><head><title>News</title></head></html>")), None);
+    }
+
+    #[test]
+    fn test_metadata_blacklist() {
+        let blacklist = MetadataBlacklist::from_json(r#"{
+            "politeness": {},
+            "metadata_blacklist": {
+                "authors": ["Staff Writer", "admin"],
+                "domains": { "example.com": { "authors": ["Newsroom"], "phrases": ["Example News"] } }
+            }
+        }"#).unwrap();
+
+        let mut result = ExtractionResult {
+            title: Some("Harbour reopens | Example News".into()),
+            description: Some("example news".into()),
+            author: Some("Jane Doe, Staff Writer and Newsroom".into()),
+            url: Some("https://www.example.com/harbour".into()),
+            ..Default::default()
+        };
+        blacklist.apply(&mut result);
+        assert_eq!(result.title.as_deref(), Some("Harbour reopens"));
+        assert_eq!(result.description, None);
+        assert_eq!(result.author.as_deref(), Some("Jane Doe"));
+
+        // Domain entries stay on their domain, bylines without blacklisted names are kept as they are
+        let mut result = ExtractionResult {
+            title: Some("Example News of the week".into()),
+            author: Some("Newsroom, Jane Doe".into()),
+            canonical_url: Some("https://notexample.com/story".into()),
+            ..Default::default()
+        };
+        blacklist.apply(&mut result);
+        assert_eq!(result.title.as_deref(), Some("Example News of the week"));
+        assert_eq!(result.author.as_deref(), Some("Newsroom, Jane Doe"));
+
+        let mut result = ExtractionResult { author: Some("ADMIN".into()), ..Default::default() };
+        blacklist.apply(&mut result);
+        assert_eq!(result.author, None);
+        assert!(MetadataBlacklist::from_json("{}").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_extract_metadata_fields() {
+        let document = Html::parse_document(r#"<html lang="en"><head><title>Harbour reopens</title>
+            <meta name="author" content="Jane Doe">
+            <meta property="article:published_time" content="2024-03-01"></head></html>"#);
+
+        let fields = HashSet::from([MetadataField::Title, MetadataField::Date]);
+        let result = extract_metadata_fields(&document, ExtractionResult::default(), Some(&fields), &DateOptions::default()).unwrap();
+        assert_eq!(result.title.as_deref(), Some("Harbour reopens"));
+        assert!(result.date.is_some());
+        assert!(result.author.is_none() && result.language.is_none());
+
+        let result = extract_metadata(&document, ExtractionResult::default()).unwrap();
+        assert!(result.author.is_some() && result.language.is_some());
+    }
+
     #[test]
     fn test_extract_date() {
         let html = r#"
@@ -339,6 +974,70 @@ mod tests {
         let document = Html::parse_document(html);
         
         // Should prefer article:published_time
-        assert_eq!(extract_date(&document), Some("2023-09-01".to_string()));
+        assert_eq!(extract_date(&document, &DateOptions::default()), Some("2023-09-01".to_string()));
+    }
+
+    #[test]
+    fn test_extract_date_locale() {
+        let page = |lang: &str, body: &str| Html::parse_document(&format!(r#"<html lang="{}"><body>{}</body></html>"#, lang, body));
+        let dates = DateOptions::default();
+
+        let document = page("de", r#"<p class="date">Veröffentlicht am 03.04.2021</p>"#);
+        assert_eq!(extract_date(&document, &dates).as_deref(), Some("2021-04-03"));
+        let document = page("en-US", r#"<p class="date">Posted 03.04.2021</p>"#);
+        assert_eq!(extract_date(&document, &dates).as_deref(), Some("2021-03-04"));
+        let document = page("fr", "<time>le 12 mars 2021</time>");
+        assert_eq!(extract_date(&document, &dates).as_deref(), Some("2021-03-12"));
+        let document = Html::parse_document(r#"<html lang="it"><head><meta name="date" content="05/06/2020"></head></html>"#);
+        assert_eq!(extract_date(&document, &dates).as_deref(), Some("2020-06-05"));
+
+        // Placeholders and event dates outside the window give way to the next date found
+        let document = Html::parse_document(r#"<html><head><meta property="article:published_time" content="1970-01-01T00:00:00Z"></head>
+            <body><time datetime="2999-05-01">May 1, 2999</time><p class="date">Posted May 3, 2021</p></body></html>"#);
+        assert_eq!(extract_date(&document, &dates).as_deref(), Some("2021-05-03"));
+        let old = DateOptions { min_date: chrono::NaiveDate::from_ymd_opt(1960, 1, 1).unwrap(), ..Default::default() };
+        assert_eq!(extract_date(&document, &old).as_deref(), Some("1970-01-01T00:00:00Z"));
+
+        // A configured locale applies to pages declaring another language
+        let dates = DateOptions { locale: Some("en-GB".into()), ..Default::default() };
+        let document = page("en", r#"<p class="date">03/04/2021</p>"#);
+        assert_eq!(extract_date(&document, &dates).as_deref(), Some("2021-04-03"));
+    }
+
+    #[test]
+    fn test_extract_language() {
+        let declared = |head: &str| extract_language(&Html::parse_document(&format!("<html><head>{}</head></html>", head)));
+        
+        assert_eq!(extract_language(&Html::parse_document(r#"<html lang="pt-BR"></html>"#)), Some("pt-BR".to_string()));
+        assert_eq!(declared(r#"<meta http-equiv="Content-Language" content="de, en">"#), Some("de".to_string()));
+        assert_eq!(declared(r#"<meta property="og:locale" content="en_US">"#), Some("en-US".to_string()));
+        assert_eq!(declared(""), None);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_extract_canonical_url() {
+        let html = r#"<html><head><link rel="canonical" href="https://example.com/story"></head></html>"#;
+        let document = Html::parse_document(html);
+        
+        assert_eq!(extract_canonical_url(&document), Some("https://example.com/story".to_string()));
+    }
+
+    #[test]
+    fn test_extract_alternates() {
+        let html = r#"<html><head>
+            <link rel="alternate" hreflang="en" href="https://example.com/en/story">
+            <link rel="alternate" hreflang="de" href="https://example.com/de/story">
+            <link rel="alternate" hreflang="de" href="https://example.com/de/story">
+            <link rel="alternate" type="application/rss+xml" href="/feed.xml">
+        </head></html>"#;
+        let document = Html::parse_document(html);
+        
+        let alternates = extract_alternates(&document);
+        
+        assert_eq!(alternates.len(), 2);
+        assert_eq!(alternates[1], LanguageAlternate {
+            hreflang: "de".into(),
+            url: "https://example.com/de/story".into(),
+        });
+    }
+}
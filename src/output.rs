@@ -0,0 +1,1182 @@
+//! Output formatting for Trafilatura Rust port.
+//! This module serializes extraction results into the supported output formats,
+//! and aggregates batches of results into RSS or Atom feeds.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use html_escape::encode_text;
+use serde::Serialize;
+use serde_json::ser::PrettyFormatter;
+use sha2::{Digest, Sha256};
+use tracing::debug_span;
+
+use crate::document::{Document, Element, Image};
+use crate::sitemaps::parse_lastmod;
+use crate::tables::escape_csv_field;
+use crate::utils::simhash;
+use crate::{ExtractionConfig, ExtractionResult, OutputFormat, TextHeader, TrafilaturaError, XmlMetadata};
+
+/// Columns of CSV output, one row per document
+pub const CSV_COLUMNS: [&str; 6] = ["url", "title", "author", "date", "sitename", "content"];
+
+/// Results without a description are summarized by the start of their content, up to
+/// this many characters
+const FEED_SUMMARY_LENGTH: usize = 300;
+
+/// Syndication format of an aggregated batch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeedFormat {
+    /// RSS 2.0
+    Rss,
+    /// Atom 1.0
+    Atom,
+}
+
+/// Feed-level metadata of an aggregated batch
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedChannel {
+    pub title: String,
+    /// Web page the feed belongs to, e.g. the start page of the crawl
+    pub link: String,
+    pub description: String,
+}
+
+/// Format an extraction result according to the configured output format
+pub fn format_result(result: &ExtractionResult, config: &ExtractionConfig) -> Result<String, TrafilaturaError> {
+    let _span = debug_span!("render", format = ?config.output_format).entered();
+    let output = match config.output_format {
+        // Comments follow the text after a blank line, as in Python trafilatura
+        OutputFormat::Text => {
+            let text = match &result.comments {
+                Some(comments) => format!("{}{}\n\n{}", text_header(result, config.text_header), result.content, comments),
+                None => format!("{}{}", text_header(result, config.text_header), result.content),
+            };
+            match config.wrap_width {
+                Some(width) => wrap_text(&text, width),
+                None => text,
+            }
+        },
+        OutputFormat::Html => format!(
+            "<html><body>{}</body></html>",
+            result.content
+        ),
+        OutputFormat::Json => to_json(&json_document(result), config.indent)?,
+        OutputFormat::JsonLines => to_json(&json_document(result), None)?,
+        OutputFormat::Csv => format!("{}\n{}", CSV_COLUMNS.join(","), format_csv_row(result)),
+        OutputFormat::Xml if config.python_xml => indent_xml(&format_python_xml(result), config.indent),
+        OutputFormat::Xml => indent_xml(&format_xml(result, config.xml_metadata), config.indent),
+        OutputFormat::Markdown => format_markdown(result, config),
+        OutputFormat::Bulk => format_bulk(result, &config.bulk_index)?,
+    };
+
+    Ok(output)
+}
+
+/// The title and byline lines put before plain text, followed by a blank line, or
+/// nothing if the result has neither
+fn text_header(result: &ExtractionResult, header: TextHeader) -> String {
+    fn present(value: &Option<String>) -> Option<&str> {
+        value.as_deref().map(str::trim).filter(|value| !value.is_empty())
+    }
+    let mut lines = Vec::new();
+    if header != TextHeader::None {
+        lines.extend(present(&result.title).map(str::to_string));
+    }
+    if header == TextHeader::TitleAndByline {
+        let byline: Vec<&str> = [&result.author, &result.date].into_iter().filter_map(present).collect();
+        if !byline.is_empty() {
+            lines.push(byline.join(" | "));
+        }
+    }
+    if lines.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n\n", lines.join("\n"))
+    }
+}
+
+/// Reflow text to lines of at most `width` characters. Line breaks of the input
+/// are kept as paragraph boundaries, and words longer than `width` get a line of their own.
+pub fn wrap_text(text: &str, width: usize) -> String {
+    let width = width.max(1);
+    let mut wrapped = Vec::new();
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        let mut line_width = 0;
+
+        for word in paragraph.split_whitespace() {
+            let word_width = word.chars().count();
+            if line_width > 0 && line_width + 1 + word_width > width {
+                wrapped.push(std::mem::take(&mut line));
+                line_width = 0;
+            }
+            if line_width > 0 {
+                line.push(' ');
+                line_width += 1;
+            }
+            line.push_str(word);
+            line_width += word_width;
+        }
+
+        wrapped.push(line);
+    }
+
+    wrapped.join("\n")
+}
+
+/// Serialize a value as JSON, indented by `indent` spaces per level, or on one line
+pub fn to_json<T: Serialize>(value: &T, indent: Option<usize>) -> Result<String, TrafilaturaError> {
+    let Some(indent) = indent else {
+        return Ok(serde_json::to_string(value)?);
+    };
+    let indent = " ".repeat(indent);
+    let mut json = Vec::new();
+    let mut serializer = serde_json::Serializer::with_formatter(&mut json, PrettyFormatter::with_indent(indent.as_bytes()));
+    value.serialize(&mut serializer)?;
+    // The serializer only writes valid UTF-8
+    Ok(String::from_utf8(json).unwrap_or_default())
+}
+
+/// Re-indent XML laid out with two spaces per level to `indent` spaces, or join it
+/// into one line. Text in CDATA sections is left as is, line breaks included.
+fn indent_xml(xml: &str, indent: Option<usize>) -> String {
+    if indent == Some(2) {
+        return xml.to_string();
+    }
+    let mut indented = String::with_capacity(xml.len());
+    let mut in_cdata = false;
+    for (i, line) in xml.split('\n').enumerate() {
+        if in_cdata {
+            indented.push('\n');
+            indented.push_str(line);
+        } else {
+            let markup = line.trim_start_matches(' ');
+            if let Some(indent) = indent {
+                if i > 0 {
+                    indented.push('\n');
+                }
+                let depth = (line.len() - markup.len()) / 2;
+                indented.extend(std::iter::repeat_n(' ', depth * indent));
+            }
+            indented.push_str(markup);
+        }
+        in_cdata = ends_in_cdata(line, in_cdata);
+    }
+    indented
+}
+
+/// Check if a CDATA section is still open at the end of a line
+fn ends_in_cdata(mut line: &str, mut in_cdata: bool) -> bool {
+    loop {
+        let delimiter = if in_cdata { "]]>" } else { "<![CDATA[" };
+        match line.find(delimiter) {
+            Some(position) => {
+                line = &line[position + delimiter.len()..];
+                in_cdata = !in_cdata;
+            },
+            None => return in_cdata,
+        }
+    }
+}
+
+/// Format a result as a bulk API action line and a document line. The document ID
+/// is the SHA-256 of the URL, so re-indexing a page replaces its previous version.
+fn format_bulk(result: &ExtractionResult, index: &str) -> Result<String, TrafilaturaError> {
+    let mut action = serde_json::Map::new();
+    action.insert("_index".into(), serde_json::Value::String(index.to_string()));
+    if let Some(url) = result.url.as_deref().or(result.canonical_url.as_deref()) {
+        let id: String = Sha256::digest(url.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect();
+        action.insert("_id".into(), serde_json::Value::String(id));
+    }
+
+    Ok(format!(
+        "{}\n{}",
+        serde_json::json!({ "index": action }),
+        serde_json::to_string(&json_document(result))?
+    ))
+}
+
+/// Format a result as a CSV row with the fields of `CSV_COLUMNS`, without the header
+/// row; missing fields are empty
+pub fn format_csv_row(result: &ExtractionResult) -> String {
+    let url = result.url.as_deref().or(result.canonical_url.as_deref());
+    [url, result.title.as_deref(), result.author.as_deref(), result.date.as_deref(), result.sitename.as_deref(), Some(result.content.as_str())]
+        .iter()
+        .map(|field| escape_csv_field(field.unwrap_or_default()))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// The fields of a result as a JSON value
+fn json_document(result: &ExtractionResult) -> serde_json::Value {
+    let mut json_obj = serde_json::Map::new();
+    json_obj.insert("content".into(), serde_json::Value::String(result.content.clone()));
+
+    if let Some(title) = &result.title {
+        json_obj.insert("title".into(), serde_json::Value::String(title.clone()));
+    }
+
+    if let Some(author) = &result.author {
+        json_obj.insert("author".into(), serde_json::Value::String(author.clone()));
+    }
+
+    if let Some(author_url) = &result.author_url {
+        json_obj.insert("author_url".into(), serde_json::Value::String(author_url.clone()));
+    }
+
+    if let Some(date) = &result.date {
+        json_obj.insert("date".into(), serde_json::Value::String(date.clone()));
+    }
+
+    if let Some(url) = &result.url {
+        json_obj.insert("url".into(), serde_json::Value::String(url.clone()));
+    }
+
+    if let Some(canonical_url) = &result.canonical_url {
+        json_obj.insert("canonical_url".into(), serde_json::Value::String(canonical_url.clone()));
+    }
+
+    if let Some(description) = &result.description {
+        json_obj.insert("description".into(), serde_json::Value::String(description.clone()));
+    }
+
+    if let Some(sitename) = &result.sitename {
+        json_obj.insert("sitename".into(), serde_json::Value::String(sitename.clone()));
+    }
+
+    if let Some(publisher) = &result.publisher {
+        json_obj.insert("publisher".into(), serde_json::Value::String(publisher.clone()));
+    }
+
+    if let Some(year) = result.copyright_year {
+        json_obj.insert("copyright_year".into(), serde_json::Value::from(year));
+    }
+
+    if let Some(og_type) = &result.og_type {
+        json_obj.insert("og_type".into(), serde_json::Value::String(og_type.clone()));
+    }
+
+    if let Some(language) = &result.language {
+        json_obj.insert("language".into(), serde_json::Value::String(language.clone()));
+    }
+
+    if let Some(quality) = result.quality {
+        json_obj.insert("quality".into(), serde_json::Value::String(quality.to_string()));
+    }
+
+    if let Some(timestamp) = &result.wayback_timestamp {
+        json_obj.insert("wayback_timestamp".into(), serde_json::Value::String(timestamp.clone()));
+    }
+
+    if let Some(comments) = &result.comments {
+        json_obj.insert("comments".into(), serde_json::Value::String(comments.clone()));
+    }
+
+    if !result.categories.is_empty() {
+        let categories = serde_json::Value::Array(
+            result.categories.iter()
+                .cloned()
+                .map(serde_json::Value::String)
+                .collect()
+        );
+        json_obj.insert("categories".into(), categories);
+    }
+
+    if let Some(academic) = &result.academic {
+        let mut fields: serde_json::Map<String, serde_json::Value> = academic.fields().into_iter()
+            .map(|(name, value)| (name.to_string(), serde_json::Value::String(value.to_string())))
+            .collect();
+        if !academic.authors.is_empty() {
+            fields.insert("authors".into(), serde_json::json!(academic.authors));
+        }
+        json_obj.insert("academic".into(), serde_json::Value::Object(fields));
+    }
+
+    if !result.language_blocks.is_empty() {
+        let blocks = result.language_blocks.iter()
+            .map(|block| serde_json::json!({
+                "text": block.text,
+                "language": block.language,
+            }))
+            .collect();
+        json_obj.insert("blocks".into(), serde_json::Value::Array(blocks));
+    }
+
+    if !result.alternates.is_empty() {
+        let alternates = result.alternates.iter()
+            .map(|alternate| serde_json::json!({
+                "hreflang": alternate.hreflang,
+                "url": alternate.url,
+            }))
+            .collect();
+        json_obj.insert("alternates".into(), serde_json::Value::Array(alternates));
+    }
+
+    if !result.article_links.is_empty() {
+        let links = result.article_links.iter()
+            .map(|link| serde_json::json!({
+                "title": link.title,
+                "url": link.url,
+            }))
+            .collect();
+        json_obj.insert("article_links".into(), serde_json::Value::Array(links));
+    }
+
+    if let Some(container_path) = &result.container_path {
+        json_obj.insert("container_path".into(), serde_json::Value::String(container_path.clone()));
+    }
+
+    serde_json::Value::Object(json_obj)
+}
+
+/// Format a result as an XML document of the schema `xml_schema::XML_SCHEMA`
+fn format_xml(result: &ExtractionResult, layout: XmlMetadata) -> String {
+    let metadata = xml_metadata(result);
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<document");
+    if layout == XmlMetadata::Attributes {
+        for (name, value, _) in &metadata {
+            xml.push_str(&format!(" {}=\"{}\"", name, xml_attribute(value)));
+        }
+        if !result.categories.is_empty() {
+            xml.push_str(&format!(" categories=\"{}\"", xml_attribute(&result.categories.join(";"))));
+        }
+    }
+    xml.push_str(">\n");
+
+    xml.push_str(&format!("  <content>{}</content>\n", cdata(&result.content)));
+
+    if let Some(comments) = &result.comments {
+        xml.push_str(&format!("  <comments>{}</comments>\n", cdata(comments)));
+    }
+
+    if layout == XmlMetadata::Elements {
+        for (name, value, free_text) in &metadata {
+            if *free_text {
+                xml.push_str(&format!("  <{}>{}</{}>\n", name, cdata(value), name));
+            } else {
+                xml.push_str(&format!("  <{}>{}</{}>\n", name, encode_text(value), name));
+            }
+        }
+
+        if !result.categories.is_empty() {
+            xml.push_str("  <categories>\n");
+            for category in &result.categories {
+                xml.push_str(&format!("    <category>{}</category>\n", cdata(category)));
+            }
+            xml.push_str("  </categories>\n");
+        }
+    }
+
+    if let Some(academic) = &result.academic {
+        xml.push_str("  <academic>\n");
+        for (name, value) in academic.fields() {
+            xml.push_str(&format!("    <{}>{}</{}>\n", name, cdata(value), name));
+        }
+        for author in &academic.authors {
+            xml.push_str(&format!("    <author>{}</author>\n", cdata(author)));
+        }
+        xml.push_str("  </academic>\n");
+    }
+
+    if !result.language_blocks.is_empty() {
+        xml.push_str("  <blocks>\n");
+        for block in &result.language_blocks {
+            match &block.language {
+                Some(language) => xml.push_str(&format!(
+                    "    <block language=\"{}\">{}</block>\n",
+                    html_escape::encode_double_quoted_attribute(language),
+                    cdata(&block.text)
+                )),
+                None => xml.push_str(&format!("    <block>{}</block>\n", cdata(&block.text))),
+            }
+        }
+        xml.push_str("  </blocks>\n");
+    }
+
+    if !result.alternates.is_empty() {
+        xml.push_str("  <alternates>\n");
+        for alternate in &result.alternates {
+            xml.push_str(&format!(
+                "    <alternate hreflang=\"{}\">{}</alternate>\n",
+                html_escape::encode_double_quoted_attribute(&alternate.hreflang),
+                cdata(&alternate.url)
+            ));
+        }
+        xml.push_str("  </alternates>\n");
+    }
+
+    if !result.article_links.is_empty() {
+        xml.push_str("  <article_links>\n");
+        for link in &result.article_links {
+            xml.push_str(&format!(
+                "    <article_link url=\"{}\">{}</article_link>\n",
+                html_escape::encode_double_quoted_attribute(&link.url),
+                cdata(&link.title)
+            ));
+        }
+        xml.push_str("  </article_links>\n");
+    }
+
+    xml.push_str("</document>");
+    xml
+}
+
+/// The scalar metadata of a result in the order of `xml_schema`, as name, value,
+/// and whether the value is free text, written in CDATA as an element
+fn xml_metadata(result: &ExtractionResult) -> Vec<(&'static str, String, bool)> {
+    let text = [
+        ("title", &result.title),
+        ("author", &result.author),
+        ("author_url", &result.author_url),
+        ("date", &result.date),
+        ("url", &result.url),
+        ("canonical_url", &result.canonical_url),
+        ("description", &result.description),
+        ("sitename", &result.sitename),
+        ("publisher", &result.publisher),
+    ];
+    let mut metadata: Vec<_> = text.into_iter()
+        .filter_map(|(name, value)| Some((name, value.clone()?, true)))
+        .collect();
+    metadata.extend(result.copyright_year.map(|year| ("copyright_year", year.to_string(), false)));
+    metadata.extend(result.og_type.clone().map(|og_type| ("og_type", og_type, false)));
+    metadata.extend(result.language.clone().map(|language| ("language", language, false)));
+    metadata.extend(result.quality.map(|quality| ("quality", quality.to_string(), false)));
+    metadata.extend(result.wayback_timestamp.clone().map(|timestamp| ("wayback_timestamp", timestamp, false)));
+    metadata
+}
+
+/// Wrap text in a CDATA section, splitting it around any `]]>` it contains
+fn cdata(text: &str) -> String {
+    format!("<![CDATA[{}]]>", text.replace("]]>", "]]]]><![CDATA[>"))
+}
+
+/// Format a result like Python trafilatura's `--xml` output: a pretty-printed `<doc>`
+/// root without XML declaration, whose attributes carry the metadata in Python's
+/// order, with the blocks of the document in `<main>` and one `<p>` per line of
+/// `<comments>`. Results without blocks get one `<p>` per line of text in `<main>`.
+fn format_python_xml(result: &ExtractionResult) -> String {
+    let hostname = result.url.as_deref()
+        .and_then(|url| url::Url::parse(url).ok())
+        .and_then(|url| url.host_str().map(|host| host.trim_start_matches("www.").to_string()));
+    let categories = result.categories.join(";");
+    let fingerprint = format!("{:x}", simhash(&result.content));
+    // Metadata is split into categories and tags in Python; tags are merged into
+    // the categories here
+    let attributes = [
+        ("sitename", result.sitename.as_deref()),
+        ("title", result.title.as_deref()),
+        ("author", result.author.as_deref()),
+        ("date", result.date.as_deref()),
+        ("url", result.url.as_deref()),
+        ("hostname", hostname.as_deref()),
+        ("description", result.description.as_deref()),
+        ("categories", Some(categories.as_str())),
+        ("tags", Some("")),
+        ("fingerprint", Some(fingerprint.as_str())),
+        ("language", result.language.as_deref()),
+    ];
+
+    let mut xml = String::from("<doc");
+    for (name, value) in attributes {
+        if let Some(value) = value {
+            xml.push_str(&format!(" {}=\"{}\"", name, xml_attribute(value)));
+        }
+    }
+    xml.push_str(">\n");
+
+    let mut main = String::new();
+    if result.document.is_empty() {
+        for line in result.content.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            match table_placeholder(line).and_then(|index| result.tables.get(index)) {
+                Some(table) => main.push_str(&python_xml_table(&table.rows)),
+                None => main.push_str(&format!("    <p>{}</p>\n", encode_text(line))),
+            }
+        }
+    } else {
+        let document = &result.document;
+        for element in &document.elements {
+            main.push_str(&python_xml_element(element, document));
+        }
+        for (i, href) in document.footnotes.iter().enumerate() {
+            main.push_str(&format!("    <p>[{}] {}</p>\n", i + 1, encode_text(href)));
+        }
+    }
+    if main.is_empty() {
+        xml.push_str("  <main/>\n");
+    } else {
+        xml.push_str(&format!("  <main>\n{}  </main>\n", main));
+    }
+
+    let comments: String = result.comments.iter()
+        .flat_map(|comments| comments.lines())
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| format!("    <p>{}</p>\n", encode_text(line)))
+        .collect();
+    if comments.is_empty() {
+        xml.push_str("  <comments/>\n</doc>");
+    } else {
+        xml.push_str(&format!("  <comments>\n{}  </comments>\n</doc>", comments));
+    }
+    xml
+}
+
+/// Index into `ExtractionResult::tables` of a `[Table N]` placeholder line
+fn table_placeholder(line: &str) -> Option<usize> {
+    line.strip_prefix("[Table ")?.strip_suffix(']')?.parse::<usize>().ok()?.checked_sub(1)
+}
+
+/// A block in the TEI-like markup of Python trafilatura, on lines of its own
+fn python_xml_element(element: &Element, document: &Document) -> String {
+    match element {
+        Element::Heading { level, text } => format!("    <head rend=\"h{}\">{}</head>\n", level, python_xml_text(text)),
+        Element::Paragraph(text) => format!("    <p>{}</p>\n", python_xml_text(text)),
+        Element::Code(code) => format!("    <code>{}</code>\n", python_xml_text(code)),
+        Element::List { ordered, items } => {
+            let mut xml = format!("    <list rend=\"{}\">\n", if *ordered { "ol" } else { "ul" });
+            for item in items {
+                xml.push_str(&format!("      <item>{}</item>\n", python_xml_text(item)));
+            }
+            xml.push_str("    </list>\n");
+            xml
+        },
+        Element::Table(rows) => python_xml_table(rows),
+        Element::TableRef(index) => index.checked_sub(1)
+            .and_then(|index| document.tables.get(index))
+            .map(|table| python_xml_table(&table.rows))
+            .unwrap_or_default(),
+        Element::Image(image) => {
+            let attributes: String = [("src", &image.src), ("alt", &image.alt)].into_iter()
+                .filter_map(|(name, value)| value.as_ref().map(|value| format!(" {}=\"{}\"", name, xml_attribute(value))))
+                .collect();
+            if attributes.is_empty() { String::new() } else { format!("    <graphic{}/>\n", attributes) }
+        },
+    }
+}
+
+/// Escape the text of a block, with line breaks as `<lb/>` so that reindenting the
+/// output leaves the whitespace of code samples alone
+fn python_xml_text(text: &str) -> String {
+    encode_text(text).replace('\n', "<lb/>")
+}
+
+/// A table in the TEI-like markup of Python trafilatura: rows of cells
+fn python_xml_table(rows: &[Vec<String>]) -> String {
+    let mut xml = String::from("    <table>\n");
+    for row in rows {
+        xml.push_str("      <row>\n");
+        for cell in row {
+            if cell.is_empty() {
+                xml.push_str("        <cell/>\n");
+            } else {
+                xml.push_str(&format!("        <cell>{}</cell>\n", encode_text(cell)));
+            }
+        }
+        xml.push_str("      </row>\n");
+    }
+    xml.push_str("    </table>\n");
+    xml
+}
+
+/// Escape an attribute value the way lxml serializes it, including whitespace
+/// characters that attribute normalization would otherwise turn into spaces
+fn xml_attribute(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\r' => escaped.push_str("&#13;"),
+            '\t' => escaped.push_str("&#9;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Format a result as Markdown, optionally preceded by YAML front matter. The body
+/// is written from the blocks of the document, or is the text for results without
+/// blocks.
+fn format_markdown(result: &ExtractionResult, config: &ExtractionConfig) -> String {
+    let mut markdown = String::new();
+
+    if config.front_matter {
+        markdown.push_str(&format_front_matter(result));
+    } else if let Some(title) = &result.title {
+        // Without front matter the title becomes the top-level heading
+        markdown.push_str(&format!("# {}\n\n", title.trim()));
+    }
+
+    if result.document.is_empty() {
+        markdown.push_str(&result.content);
+    } else {
+        markdown.push_str(&markdown_body(&result.document));
+    }
+    markdown
+}
+
+/// The blocks of a document as Markdown, separated by blank lines. Tables kept
+/// aside stay `[Table N]` placeholders, and images without a source are left out.
+fn markdown_body(document: &Document) -> String {
+    let mut blocks: Vec<String> = document.elements.iter()
+        .filter_map(|element| match element {
+            Element::Heading { level, text } => Some(format!("{} {}", "#".repeat(usize::from(*level)), text)),
+            Element::Paragraph(text) => Some(text.clone()),
+            Element::List { ordered, items } => Some(items.iter()
+                .enumerate()
+                .map(|(i, item)| if *ordered { format!("{}. {}", i + 1, item) } else { format!("- {}", item) })
+                .collect::<Vec<_>>()
+                .join("\n")),
+            Element::Code(code) => Some(format!("```\n{}\n```", code)),
+            Element::Table(rows) => markdown_table(rows),
+            Element::TableRef(index) => Some(format!("[Table {}]", index)),
+            Element::Image(image) => markdown_image(image),
+        })
+        .collect();
+    if !document.footnotes.is_empty() {
+        let list = document.footnotes.iter()
+            .enumerate()
+            .map(|(i, href)| format!("[{}] {}", i + 1, href))
+            .collect::<Vec<_>>();
+        blocks.push(list.join("\n"));
+    }
+    blocks.join("\n\n")
+}
+
+/// A table in Markdown pipe syntax with its first row as the header, or `None`
+/// for a table without rows
+fn markdown_table(rows: &[Vec<String>]) -> Option<String> {
+    let columns = rows.iter().map(Vec::len).max().filter(|&columns| columns > 0)?;
+    let row = |cells: &[String]| {
+        let cells = (0..columns)
+            .map(|i| cells.get(i).map_or(String::new(), |cell| cell.replace('|', "\\|").replace('\n', " ")))
+            .collect::<Vec<_>>();
+        format!("| {} |", cells.join(" | "))
+    };
+
+    let mut lines = vec![row(&rows[0]), format!("|{}", " --- |".repeat(columns))];
+    lines.extend(rows[1..].iter().map(|cells| row(cells)));
+    Some(lines.join("\n"))
+}
+
+/// An image as `![alt](src)`, followed by its caption in italics
+fn markdown_image(image: &Image) -> Option<String> {
+    let src = image.src.as_deref()?;
+    let markdown = format!("![{}]({})", image.alt.as_deref().unwrap_or_default(), src);
+    Some(match &image.caption {
+        Some(caption) => format!("{}\n*{}*", markdown, caption),
+        None => markdown,
+    })
+}
+
+/// Build a YAML front matter block with the title, date, author, url, and tags
+fn format_front_matter(result: &ExtractionResult) -> String {
+    let mut yaml = String::from("---\n");
+
+    let fields = [
+        ("title", &result.title),
+        ("date", &result.date),
+        ("author", &result.author),
+        ("url", &result.url),
+    ];
+    for (key, value) in fields {
+        if let Some(value) = value {
+            yaml.push_str(&format!("{}: {}\n", key, yaml_string(value)));
+        }
+    }
+
+    if !result.categories.is_empty() {
+        yaml.push_str("tags:\n");
+        for tag in &result.categories {
+            yaml.push_str(&format!("  - {}\n", yaml_string(tag)));
+        }
+    }
+
+    yaml.push_str("---\n\n");
+    yaml
+}
+
+/// Quote a value as a double-quoted YAML scalar
+fn yaml_string(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.trim().chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => {}
+            '\t' => quoted.push_str("\\t"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Aggregate extraction results into a feed, newest first. Dates of the results are
+/// parsed as W3C datetimes; undated results come last and use `updated` where a date
+/// is required.
+pub fn format_feed(results: &[ExtractionResult], channel: &FeedChannel, format: FeedFormat, updated: DateTime<Utc>) -> String {
+    let mut items: Vec<(&ExtractionResult, Option<DateTime<Utc>>)> = results.iter()
+        .map(|result| (result, result.date.as_deref().and_then(parse_lastmod)))
+        .collect();
+    items.sort_by_key(|(_, date)| std::cmp::Reverse(*date));
+
+    match format {
+        FeedFormat::Rss => format_rss(&items, channel, updated),
+        FeedFormat::Atom => format_atom(&items, channel, updated),
+    }
+}
+
+fn format_rss(items: &[(&ExtractionResult, Option<DateTime<Utc>>)], channel: &FeedChannel, updated: DateTime<Utc>) -> String {
+    let mut rss = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    rss.push_str("<rss version=\"2.0\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\" xmlns:content=\"http://purl.org/rss/1.0/modules/content/\">\n<channel>\n");
+    rss.push_str(&format!("  <title>{}</title>\n", encode_text(&channel.title)));
+    rss.push_str(&format!("  <link>{}</link>\n", encode_text(&channel.link)));
+    rss.push_str(&format!("  <description>{}</description>\n", encode_text(&channel.description)));
+    rss.push_str(&format!("  <lastBuildDate>{}</lastBuildDate>\n", updated.to_rfc2822()));
+
+    for (result, date) in items {
+        rss.push_str("  <item>\n");
+        rss.push_str(&format!("    <title>{}</title>\n", encode_text(feed_title(result))));
+        if let Some(link) = feed_link(result) {
+            rss.push_str(&format!("    <link>{}</link>\n", encode_text(link)));
+            rss.push_str(&format!("    <guid isPermaLink=\"true\">{}</guid>\n", encode_text(link)));
+        }
+        rss.push_str(&format!("    <description>{}</description>\n", encode_text(&feed_summary(result))));
+        if let Some(date) = date {
+            rss.push_str(&format!("    <pubDate>{}</pubDate>\n", date.to_rfc2822()));
+        }
+        if let Some(author) = &result.author {
+            rss.push_str(&format!("    <dc:creator>{}</dc:creator>\n", encode_text(author)));
+        }
+        for category in &result.categories {
+            rss.push_str(&format!("    <category>{}</category>\n", encode_text(category)));
+        }
+        rss.push_str(&format!("    <content:encoded>{}</content:encoded>\n", encode_text(&paragraphs_html(&result.content))));
+        rss.push_str("  </item>\n");
+    }
+
+    rss.push_str("</channel>\n</rss>");
+    rss
+}
+
+fn format_atom(items: &[(&ExtractionResult, Option<DateTime<Utc>>)], channel: &FeedChannel, updated: DateTime<Utc>) -> String {
+    let timestamp = |date: &DateTime<Utc>| date.to_rfc3339_opts(SecondsFormat::Secs, true);
+
+    let mut atom = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    atom.push_str(&format!("  <id>{}</id>\n", encode_text(&channel.link)));
+    atom.push_str(&format!("  <title>{}</title>\n", encode_text(&channel.title)));
+    atom.push_str(&format!("  <subtitle>{}</subtitle>\n", encode_text(&channel.description)));
+    atom.push_str(&format!("  <link href=\"{}\"/>\n", html_escape::encode_double_quoted_attribute(&channel.link)));
+    atom.push_str(&format!("  <updated>{}</updated>\n", timestamp(&updated)));
+    // Atom requires an author for the entries without one; the site stands in
+    atom.push_str(&format!("  <author><name>{}</name></author>\n", encode_text(&channel.title)));
+
+    for (result, date) in items {
+        atom.push_str("  <entry>\n");
+        atom.push_str(&format!("    <id>{}</id>\n", encode_text(&atom_id(result))));
+        atom.push_str(&format!("    <title>{}</title>\n", encode_text(feed_title(result))));
+        if let Some(link) = feed_link(result) {
+            atom.push_str(&format!("    <link href=\"{}\"/>\n", html_escape::encode_double_quoted_attribute(link)));
+        }
+        atom.push_str(&format!("    <updated>{}</updated>\n", timestamp(&date.unwrap_or(updated))));
+        if let Some(date) = date {
+            atom.push_str(&format!("    <published>{}</published>\n", timestamp(date)));
+        }
+        if let Some(author) = &result.author {
+            let uri = result.author_url.as_ref()
+                .map(|url| format!("<uri>{}</uri>", encode_text(url)))
+                .unwrap_or_default();
+            atom.push_str(&format!("    <author><name>{}</name>{}</author>\n", encode_text(author), uri));
+        }
+        for category in &result.categories {
+            atom.push_str(&format!("    <category term=\"{}\"/>\n", html_escape::encode_double_quoted_attribute(category)));
+        }
+        atom.push_str(&format!("    <summary>{}</summary>\n", encode_text(&feed_summary(result))));
+        atom.push_str(&format!("    <content type=\"text\">{}</content>\n", encode_text(&result.content)));
+        atom.push_str("  </entry>\n");
+    }
+
+    atom.push_str("</feed>");
+    atom
+}
+
+fn feed_title(result: &ExtractionResult) -> &str {
+    result.title.as_deref().map(str::trim).filter(|title| !title.is_empty()).unwrap_or("Untitled")
+}
+
+fn feed_link(result: &ExtractionResult) -> Option<&str> {
+    result.canonical_url.as_deref().or(result.url.as_deref())
+}
+
+/// Permanent identifier of an Atom entry: its URL, or a hash of its title and content
+/// for pages without one, so the id does not change with the order of the entries
+fn atom_id(result: &ExtractionResult) -> String {
+    if let Some(link) = feed_link(result) {
+        return link.to_string();
+    }
+    let digest = Sha256::new()
+        .chain_update(feed_title(result).as_bytes())
+        .chain_update([0])
+        .chain_update(result.content.as_bytes())
+        .finalize();
+    format!("urn:sha256:{}", digest.iter().map(|byte| format!("{:02x}", byte)).collect::<String>())
+}
+
+/// The description of a result, or the start of its content cut at a word boundary
+fn feed_summary(result: &ExtractionResult) -> String {
+    if let Some(description) = result.description.as_deref().map(str::trim).filter(|description| !description.is_empty()) {
+        return description.to_string();
+    }
+
+    let content = result.content.split_whitespace().collect::<Vec<_>>().join(" ");
+    if content.chars().count() <= FEED_SUMMARY_LENGTH {
+        return content;
+    }
+    let cut: String = content.chars().take(FEED_SUMMARY_LENGTH).collect();
+    let cut = cut.rsplit_once(' ').map_or(cut.as_str(), |(start, _)| start);
+    format!("{}…", cut)
+}
+
+/// Plain text content as HTML paragraphs, one per non-empty line
+fn paragraphs_html(content: &str) -> String {
+    content.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| format!("<p>{}</p>", encode_text(line)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::{AcademicMetadata, LanguageAlternate};
+    use crate::quality::Quality;
+    use crate::tables::Table;
+    use crate::xml_schema::validate_xml;
+    use crate::{ImageStyle, LinkStyle};
+
+    fn sample_result() -> ExtractionResult {
+        ExtractionResult {
+            content: "First paragraph.\n\nSecond paragraph.".into(),
+            title: Some("A \"quoted\" title".into()),
+            author: Some("Jane Doe".into()),
+            date: Some("2024-03-01".into()),
+            url: Some("https://example.com/post".into()),
+            categories: vec!["rust".into(), "web".into()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_wrap_text() {
+        let text = "The quick brown fox jumps over the lazy dog\n\nSecond paragraph with an extraordinarily long word";
+
+        assert_eq!(
+            wrap_text(text, 15),
+            "The quick brown\nfox jumps over\nthe lazy dog\n\nSecond\nparagraph with\nan\nextraordinarily\nlong word"
+        );
+    }
+
+    #[test]
+    fn test_text_header() {
+        let config = |text_header| ExtractionConfig { text_header, ..Default::default() };
+
+        assert_eq!(format_result(&sample_result(), &config(TextHeader::None)).unwrap(), "First paragraph.\n\nSecond paragraph.");
+        assert_eq!(
+            format_result(&sample_result(), &config(TextHeader::Title)).unwrap(),
+            "A \"quoted\" title\n\nFirst paragraph.\n\nSecond paragraph."
+        );
+        assert_eq!(
+            format_result(&sample_result(), &config(TextHeader::TitleAndByline)).unwrap(),
+            "A \"quoted\" title\nJane Doe | 2024-03-01\n\nFirst paragraph.\n\nSecond paragraph."
+        );
+
+        // Missing metadata leaves no empty lines
+        let undated = ExtractionResult { title: None, date: None, ..sample_result() };
+        assert_eq!(format_result(&undated, &config(TextHeader::TitleAndByline)).unwrap(), "Jane Doe\n\nFirst paragraph.\n\nSecond paragraph.");
+        let untitled = ExtractionResult { title: None, ..sample_result() };
+        assert_eq!(format_result(&untitled, &config(TextHeader::Title)).unwrap(), "First paragraph.\n\nSecond paragraph.");
+    }
+
+    #[test]
+    fn test_markdown_with_front_matter() {
+        let config = ExtractionConfig {
+            output_format: OutputFormat::Markdown,
+            front_matter: true,
+            ..Default::default()
+        };
+
+        let output = format_result(&sample_result(), &config).unwrap();
+
+        assert_eq!(
+            output,
+            "---\ntitle: \"A \\\"quoted\\\" title\"\ndate: \"2024-03-01\"\nauthor: \"Jane Doe\"\n\
+             url: \"https://example.com/post\"\ntags:\n  - \"rust\"\n  - \"web\"\n---\n\n\
+             First paragraph.\n\nSecond paragraph."
+        );
+    }
+
+    #[test]
+    fn test_python_xml() {
+        let config = ExtractionConfig { output_format: OutputFormat::Xml, python_xml: true, ..Default::default() };
+        let result = ExtractionResult {
+            content: "Fares & times\n\n[Table 1]\n".into(),
+            url: Some("https://www.example.com/post".into()),
+            language: Some("en".into()),
+            tables: vec![Table { rows: vec![vec!["Zone".into(), "".into()]] }],
+            ..sample_result()
+        };
+
+        let output = format_result(&result, &config).unwrap();
+        let fingerprint = format!("{:x}", simhash(&result.content));
+        assert_eq!(
+            output,
+            format!(
+                "<doc title=\"A &quot;quoted&quot; title\" author=\"Jane Doe\" date=\"2024-03-01\" \
+                 url=\"https://www.example.com/post\" hostname=\"example.com\" categories=\"rust;web\" tags=\"\" \
+                 fingerprint=\"{}\" language=\"en\">\n  <main>\n    <p>Fares &amp; times</p>\n    <table>\n      <row>\n\
+                 \x20       <cell>Zone</cell>\n        <cell/>\n      </row>\n    </table>\n  </main>\n  <comments/>\n</doc>",
+                fingerprint
+            )
+        );
+
+        let empty = ExtractionResult::default();
+        assert!(format_result(&empty, &config).unwrap().contains("<main/>"));
+        
+        let commented = ExtractionResult { comments: Some("First!\nAgreed <3".into()), ..empty };
+        assert!(format_result(&commented, &config).unwrap()
+            .ends_with("<comments>\n    <p>First!</p>\n    <p>Agreed &lt;3</p>\n  </comments>\n</doc>"));
+    }
+
+    #[test]
+    fn test_indentation() {
+        let result = ExtractionResult { content: "First line\n  indented code".into(), categories: vec!["rust".into()], ..Default::default() };
+        let json = ExtractionConfig { output_format: OutputFormat::Json, ..Default::default() };
+        let xml = ExtractionConfig { output_format: OutputFormat::Xml, xml_metadata: XmlMetadata::Elements, ..Default::default() };
+
+        assert!(format_result(&result, &json).unwrap().starts_with("{\n  \""));
+        let compact = format_result(&result, &ExtractionConfig { indent: None, ..json.clone() }).unwrap();
+        assert!(!compact.contains('\n'));
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&compact).unwrap()["content"], "First line\n  indented code");
+        assert!(format_result(&result, &ExtractionConfig { indent: Some(4), ..json }).unwrap().starts_with("{\n    \""));
+
+        // JSON Lines stays on one line whatever the indentation
+        let lines = format_result(&result, &ExtractionConfig { output_format: OutputFormat::JsonLines, indent: Some(4), ..Default::default() }).unwrap();
+        assert_eq!(lines, compact);
+
+        // Text in CDATA keeps its line breaks and indentation
+        assert_eq!(
+            format_result(&result, &ExtractionConfig { indent: None, ..xml.clone() }).unwrap(),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><document><content><![CDATA[First line\n  indented code]]></content>\
+             <categories><category><![CDATA[rust]]></category></categories></document>"
+        );
+        assert!(format_result(&result, &ExtractionConfig { indent: Some(4), ..xml }).unwrap()
+            .contains("\n  indented code]]></content>\n    <categories>\n        <category>"));
+    }
+
+    #[test]
+    fn test_xml_layouts() {
+        let result = ExtractionResult {
+            comments: Some("Nice ]]> post".into()),
+            copyright_year: Some(2024),
+            language: Some("en".into()),
+            quality: Some(Quality::High),
+            academic: Some(AcademicMetadata { doi: Some("10.1000/xyz".into()), authors: vec!["A".into(), "B".into()], ..Default::default() }),
+            alternates: vec![LanguageAlternate { hreflang: "de".into(), url: "https://example.de/post".into() }],
+            ..sample_result()
+        };
+        let config = |xml_metadata| ExtractionConfig { output_format: OutputFormat::Xml, xml_metadata, ..Default::default() };
+
+        let attributes = format_result(&result, &config(XmlMetadata::Attributes)).unwrap();
+        assert!(attributes.contains(
+            "<document title=\"A &quot;quoted&quot; title\" author=\"Jane Doe\" date=\"2024-03-01\" url=\"https://example.com/post\" \
+             copyright_year=\"2024\" language=\"en\" quality=\"high\" categories=\"rust;web\">\n  <content>"
+        ));
+        assert!(!attributes.contains("<title>") && !attributes.contains("<category>"));
+
+        let elements = format_result(&result, &config(XmlMetadata::Elements)).unwrap();
+        assert!(elements.contains("<document>\n"));
+        assert!(elements.contains("  <title><![CDATA[A \"quoted\" title]]></title>\n"));
+        assert!(elements.contains("  <categories>\n    <category><![CDATA[rust]]></category>\n"));
+
+        for xml in [attributes, elements] {
+            assert!(validate_xml(&xml).is_ok(), "{}: {:?}", xml, validate_xml(&xml));
+            assert!(xml.contains("<comments><![CDATA[Nice ]]]]><![CDATA[> post]]></comments>"));
+        }
+    }
+
+    #[test]
+    fn test_feeds() {
+        let older = ExtractionResult { title: Some("Older & wiser".into()), date: Some("2024-01-15".into()), ..sample_result() };
+        let undated = ExtractionResult { title: None, date: None, url: None, ..sample_result() };
+        let results = [older, sample_result(), undated];
+        let channel = FeedChannel {
+            title: "Example".into(),
+            link: "https://example.com/".into(),
+            description: "Crawled articles".into(),
+        };
+        let updated = parse_lastmod("2024-03-02T12:00:00Z").unwrap();
+
+        let rss = format_feed(&results, &channel, FeedFormat::Rss, updated);
+        assert!(rss.contains("<pubDate>Fri, 1 Mar 2024 00:00:00 +0000</pubDate>"));
+        assert!(rss.contains("<title>Older &amp; wiser</title>"));
+        assert!(rss.contains("<content:encoded>&lt;p&gt;First paragraph.&lt;/p&gt;&lt;p&gt;Second paragraph.&lt;/p&gt;</content:encoded>"));
+        // Newest first, undated last
+        let positions: Vec<usize> = ["A \"quoted\" title", "Older &amp; wiser", "Untitled"].iter()
+            .map(|title| rss.find(title).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let atom = format_feed(&results, &channel, FeedFormat::Atom, updated);
+        assert!(atom.contains("  <author><name>Example</name></author>\n  <entry>"));
+        // Entries are identified by their URL, or by their text without one, whatever their order
+        assert!(atom.contains("<id>https://example.com/post</id>"));
+        assert!(!atom.contains("#entry-"));
+        let id = |atom: &str| atom.split("<id>").find(|id| id.starts_with("urn:sha256:")).and_then(|rest| rest.split_once("</id>")).unwrap().0.to_string();
+        let reordered = format_feed(&[results[2].clone(), results[0].clone()], &channel, FeedFormat::Atom, updated);
+        assert_eq!(id(&reordered), id(&atom));
+        assert!(atom.contains("<published>2024-01-15T00:00:00Z</published>"));
+        assert!(atom.contains("<updated>2024-03-02T12:00:00Z</updated>"));
+        assert_eq!(atom.matches("<entry>").count(), 3);
+    }
+
+    #[test]
+    fn test_bulk() {
+        let config = ExtractionConfig {
+            output_format: OutputFormat::Bulk,
+            bulk_index: "articles".into(),
+            ..Default::default()
+        };
+
+        let output = format_result(&sample_result(), &config).unwrap();
+
+        let lines: Vec<serde_json::Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["index"]["_index"], "articles");
+        assert_eq!(lines[0]["index"]["_id"], "061128ccde4ce4703519b9b2e51c036d85b137886dedcd2908fd11da004ab8c7");
+        assert_eq!(lines[1]["title"], "A \"quoted\" title");
+    }
+
+    #[test]
+    fn test_csv() {
+        let config = ExtractionConfig { output_format: OutputFormat::Csv, ..Default::default() };
+        let result = ExtractionResult { sitename: Some("Example, Inc.".into()), ..sample_result() };
+
+        // Fields with separators, quotes, or line breaks are quoted; missing fields are empty
+        assert_eq!(
+            format_result(&result, &config).unwrap(),
+            "url,title,author,date,sitename,content\n\
+             https://example.com/post,\"A \"\"quoted\"\" title\",Jane Doe,2024-03-01,\"Example, Inc.\",\"First paragraph.\n\nSecond paragraph.\""
+        );
+        let result = ExtractionResult { content: "Plain".into(), ..Default::default() };
+        assert_eq!(format_csv_row(&result), ",,,,,Plain");
+    }
+
+    #[test]
+    fn test_markdown_without_front_matter() {
+        let config = ExtractionConfig {
+            output_format: OutputFormat::Markdown,
+            ..Default::default()
+        };
+
+        let output = format_result(&sample_result(), &config).unwrap();
+
+        assert!(output.starts_with("# A \"quoted\" title\n\nFirst paragraph."));
+    }
+
+    /// A result holding a document with one block of each kind
+    fn structured_result() -> ExtractionResult {
+        let document = Document {
+            elements: vec![
+                Element::Heading { level: 2, text: "Tide tables".into() },
+                Element::Paragraph("High water at <6 am, see [the almanac](https://example.com/almanac).".into()),
+                Element::List { ordered: true, items: vec!["Check the gauge".into(), "Log the height".into()] },
+                Element::Code("if height > 4.5:\n    close_gate()".into()),
+                Element::Table(vec![vec!["Port".into(), "Range".into()], vec!["Dover | East".into(), "6.0 m".into()]]),
+                Element::Image(Image {
+                    alt: Some("Gauge".into()),
+                    src: Some("https://example.com/gauge.png".into()),
+                    caption: Some("The tide gauge".into()),
+                }),
+                Element::Image(Image { alt: Some("Unsourced".into()), src: None, caption: None }),
+            ],
+            ..Default::default()
+        };
+        ExtractionResult { content: document.to_text(ImageStyle::Figure), title: Some("Tides".into()), document, ..Default::default() }
+    }
+
+    #[test]
+    fn test_markdown_from_document() {
+        let config = ExtractionConfig { output_format: OutputFormat::Markdown, ..Default::default() };
+
+        assert_eq!(
+            format_result(&structured_result(), &config).unwrap(),
+            "# Tides\n\n## Tide tables\n\nHigh water at <6 am, see [the almanac](https://example.com/almanac).\n\n\
+             1. Check the gauge\n2. Log the height\n\n```\nif height > 4.5:\n    close_gate()\n```\n\n\
+             | Port | Range |\n| --- | --- |\n| Dover \\| East | 6.0 m |\n\n\
+             ![Gauge](https://example.com/gauge.png)\n*The tide gauge*"
+        );
+
+        // Links are Markdown links whatever the link style
+        let html = "<html><body><article><h2>Harbour works</h2><p>Dredging of the outer basin starts in May, \
+                    once <a href=\"https://example.com/permit\">the permit</a> is granted. Boats moored there \
+                    must move to the <a href=\"/marina\">new marina</a> by the end of April.</p>\
+                    <ul><li>Outer basin: closed</li><li>Inner basin: open</li></ul></article></body></html>";
+        for link_style in [LinkStyle::Stripped, LinkStyle::Parenthetical, LinkStyle::Footnote, LinkStyle::Markdown] {
+            let config = ExtractionConfig { link_style, min_extracted_size: 50, ..config.clone() };
+            let output = format_result(&crate::extract_html(html, &config).unwrap(), &config).unwrap();
+            assert!(output.contains("## Harbour works\n\n"), "{:?}: {}", link_style, output);
+            assert!(output.contains("once [the permit](https://example.com/permit) is granted"), "{:?}: {}", link_style, output);
+            assert!(output.contains("[new marina](/marina)"), "{:?}: {}", link_style, output);
+            assert!(output.ends_with("- Outer basin: closed\n- Inner basin: open"), "{:?}: {}", link_style, output);
+        }
+    }
+
+    #[test]
+    fn test_dedup_paragraphs_in_every_format() {
+        // A pull quote repeating a paragraph of a review
+        let quote = "The second act drags, but the finale more than makes up for it.";
+        let html = format!(
+            "<html><body><article><h2>Opera review</h2><p>The new production of the opera opened on Friday \
+             to a full house and a long standing ovation.</p><p>{quote}</p><p>Tickets for the remaining \
+             performances in March are still available.</p><blockquote><p>{quote}</p></blockquote></article></body></html>"
+        );
+        let formats = [
+            (OutputFormat::Text, false), (OutputFormat::Html, false), (OutputFormat::Json, false),
+            (OutputFormat::Markdown, false), (OutputFormat::Xml, false), (OutputFormat::Xml, true),
+        ];
+        for (output_format, python_xml) in formats {
+            let config = ExtractionConfig { output_format, python_xml, dedup_paragraphs: true, min_extracted_size: 50, ..Default::default() };
+            let output = format_result(&crate::extract_html(&html, &config).unwrap(), &config).unwrap();
+            assert_eq!(output.matches("finale more than makes up").count(), 1, "{:?} {}: {}", output_format, python_xml, output);
+        }
+    }
+
+    #[test]
+    fn test_python_xml_from_document() {
+        let config = ExtractionConfig { output_format: OutputFormat::Xml, python_xml: true, ..Default::default() };
+        let output = format_result(&structured_result(), &config).unwrap();
+
+        let main = &output[output.find("<main>").unwrap()..output.find("</main>").unwrap()];
+        assert_eq!(
+            main,
+            "<main>\n    <head rend=\"h2\">Tide tables</head>\n\
+             \x20   <p>High water at &lt;6 am, see [the almanac](https://example.com/almanac).</p>\n\
+             \x20   <list rend=\"ol\">\n      <item>Check the gauge</item>\n      <item>Log the height</item>\n    </list>\n\
+             \x20   <code>if height &gt; 4.5:<lb/>    close_gate()</code>\n\
+             \x20   <table>\n      <row>\n        <cell>Port</cell>\n        <cell>Range</cell>\n      </row>\n\
+             \x20     <row>\n        <cell>Dover | East</cell>\n        <cell>6.0 m</cell>\n      </row>\n    </table>\n\
+             \x20   <graphic src=\"https://example.com/gauge.png\" alt=\"Gauge\"/>\n\
+             \x20   <graphic alt=\"Unsourced\"/>\n  "
+        );
+        // Reindenting leaves the whitespace of code alone
+        let config = ExtractionConfig { indent: Some(4), ..config };
+        assert!(format_result(&structured_result(), &config).unwrap().contains("<code>if height &gt; 4.5:<lb/>    close_gate()</code>"));
+    }
+}
@@ -0,0 +1,254 @@
+//! Per-domain politeness rules for Trafilatura Rust port.
+//! Some sites require a specific identification and rate limits from crawlers.
+//! This module reads those rules from the `politeness` section of a JSON config
+//! file and enforces their delays and concurrency limits around each download.
+//! The times of the last requests can be carried over to a later run, such as a
+//! resumed crawl, so that restarting does not shorten the delays.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::TrafilaturaError;
+
+/// Fetch settings for one domain and its subdomains
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DomainPolicy {
+    /// Minimum time between the starts of two requests to the domain, in milliseconds
+    pub crawl_delay_ms: Option<u64>,
+    /// Maximum number of requests to the domain in flight at once
+    pub concurrency: Option<usize>,
+    /// User agent sent instead of the configured one
+    pub user_agent: Option<String>,
+    /// Additional request headers
+    pub headers: BTreeMap<String, String>,
+}
+
+/// Domain policies, keyed by domain name
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct PolitenessRules {
+    domains: BTreeMap<String, DomainPolicy>,
+    /// Policy of hosts whose domain has none
+    #[serde(skip)]
+    fallback: Option<DomainPolicy>,
+}
+
+/// The sections of a config file this module reads
+#[derive(Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    politeness: PolitenessRules,
+}
+
+impl PolitenessRules {
+    /// Read the `politeness` section of a JSON config file, e.g.
+    /// `{"politeness": {"example.com": {"crawl_delay_ms": 2000, "headers": {"From": "crawler@example.org"}}}}`
+    pub fn from_file(path: &Path) -> Result<Self, TrafilaturaError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_json(&content)
+    }
+
+    /// Parse the `politeness` section of a JSON config document
+    pub fn from_json(content: &str) -> Result<Self, TrafilaturaError> {
+        let file: ConfigFile = serde_json::from_str(content)?;
+        Ok(file.politeness)
+    }
+
+    /// Add or replace the policy of a domain
+    pub fn insert(&mut self, domain: &str, policy: DomainPolicy) {
+        self.domains.insert(domain.trim_start_matches('.').to_lowercase(), policy);
+    }
+
+    /// Set the policy of hosts whose domain has none, e.g. the delay of a crawl.
+    /// Domain policies without a crawl delay of their own take its delay.
+    pub fn set_fallback(&mut self, policy: DomainPolicy) {
+        for domain_policy in self.domains.values_mut() {
+            domain_policy.crawl_delay_ms = domain_policy.crawl_delay_ms.or(policy.crawl_delay_ms);
+        }
+        self.fallback = Some(policy);
+    }
+
+    /// Policy of hosts whose domain has none, if set
+    pub fn fallback(&self) -> Option<&DomainPolicy> {
+        self.fallback.as_ref()
+    }
+
+    /// Check if no host has a policy
+    pub fn is_empty(&self) -> bool {
+        self.domains.is_empty() && self.fallback.is_none()
+    }
+
+    /// Policy of the most specific domain matching a host, e.g. `news.example.com`
+    /// before `example.com`, or else the fallback policy
+    pub fn for_host(&self, host: &str) -> Option<&DomainPolicy> {
+        let host = host.to_lowercase();
+        let mut domain = host.as_str();
+        loop {
+            if let Some(policy) = self.domains.get(domain) {
+                return Some(policy);
+            }
+            match domain.split_once('.') {
+                Some((_, parent)) => domain = parent,
+                None => return self.fallback.as_ref(),
+            }
+        }
+    }
+}
+
+/// Request bookkeeping of one domain
+#[derive(Debug, Default)]
+struct DomainState {
+    last_start: Option<Instant>,
+    in_flight: usize,
+}
+
+/// Enforces crawl delays and concurrency limits across the threads sharing it
+#[derive(Debug, Default)]
+pub struct PolitenessGate {
+    domains: Mutex<HashMap<String, DomainState>>,
+    released: Condvar,
+}
+
+/// A request slot of a domain, given back when dropped
+#[derive(Debug)]
+pub struct Permit<'a> {
+    gate: &'a PolitenessGate,
+    domain: String,
+}
+
+impl PolitenessGate {
+    /// Wait until a request to `domain` is allowed by its policy
+    pub fn acquire(&self, domain: &str, policy: &DomainPolicy) -> Permit<'_> {
+        let delay = Duration::from_millis(policy.crawl_delay_ms.unwrap_or(0));
+        let limit = policy.concurrency.unwrap_or(usize::MAX).max(1);
+
+        let mut domains = self.domains.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            let state = domains.entry(domain.to_string()).or_default();
+            if state.in_flight >= limit {
+                domains = self.released.wait(domains).unwrap_or_else(|e| e.into_inner());
+                continue;
+            }
+
+            let wait = state.last_start.map_or(Duration::ZERO, |last| delay.saturating_sub(last.elapsed()));
+            if wait.is_zero() {
+                state.last_start = Some(Instant::now());
+                state.in_flight += 1;
+                return Permit { gate: self, domain: domain.to_string() };
+            }
+
+            // Sleep without holding the lock, then check again
+            drop(domains);
+            thread::sleep(wait);
+            domains = self.domains.lock().unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Start times of the last request to each domain
+    pub fn last_requests(&self) -> BTreeMap<String, DateTime<Utc>> {
+        let domains = self.domains.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Utc::now();
+        domains.iter()
+            .filter_map(|(domain, state)| {
+                let elapsed = chrono::Duration::from_std(state.last_start?.elapsed()).ok()?;
+                Some((domain.clone(), now - elapsed))
+            })
+            .collect()
+    }
+
+    /// Count the delays of domains from requests started before, e.g. by an earlier
+    /// run; requests made since take precedence
+    pub fn restore(&self, last_requests: &BTreeMap<String, DateTime<Utc>>) {
+        let mut domains = self.domains.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        for (domain, started) in last_requests {
+            // Requests older than the uptime of the system are past any delay
+            let Some(start) = (Utc::now() - *started).to_std().ok().and_then(|ago| now.checked_sub(ago)) else {
+                continue;
+            };
+            let state = domains.entry(domain.clone()).or_default();
+            if state.last_start.is_none_or(|last| last < start) {
+                state.last_start = Some(start);
+            }
+        }
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        let mut domains = self.gate.domains.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(state) = domains.get_mut(&self.domain) {
+            state.in_flight = state.in_flight.saturating_sub(1);
+        }
+        self.gate.released.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules_from_json() {
+        let rules = PolitenessRules::from_json(r#"{"politeness": {
+            "example.com": {"crawl_delay_ms": 2000, "concurrency": 1, "headers": {"From": "crawler@example.org"}},
+            "news.example.com": {"user_agent": "PartnerBot/1.0"}
+        }}"#).unwrap();
+
+        assert_eq!(rules.for_host("www.example.com").unwrap().crawl_delay_ms, Some(2000));
+        assert_eq!(rules.for_host("News.Example.com").unwrap().user_agent.as_deref(), Some("PartnerBot/1.0"));
+        assert_eq!(rules.for_host("example.org"), None);
+        assert!(PolitenessRules::from_json("{}").unwrap().is_empty());
+
+        let mut rules = rules;
+        rules.set_fallback(DomainPolicy { crawl_delay_ms: Some(1000), ..Default::default() });
+        assert_eq!(rules.for_host("example.org").unwrap().crawl_delay_ms, Some(1000));
+        assert_eq!(rules.for_host("www.example.com").unwrap().crawl_delay_ms, Some(2000));
+        assert_eq!(rules.for_host("news.example.com").unwrap().crawl_delay_ms, Some(1000));
+        assert!(PolitenessRules::from_json(r#"{"politeness": {"example.com": {"delay": 1}}}"#).is_err());
+    }
+
+    #[test]
+    fn test_gate_enforces_delay() {
+        let gate = PolitenessGate::default();
+        let policy = DomainPolicy { crawl_delay_ms: Some(50), ..Default::default() };
+
+        let start = Instant::now();
+        drop(gate.acquire("example.com", &policy));
+        drop(gate.acquire("example.org", &policy));
+        assert!(start.elapsed() < Duration::from_millis(50));
+
+        drop(gate.acquire("example.com", &policy));
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_gate_restores_last_requests() {
+        let gate = PolitenessGate::default();
+        let policy = DomainPolicy { crawl_delay_ms: Some(100), ..Default::default() };
+        drop(gate.acquire("example.com", &policy));
+        let last_requests = gate.last_requests();
+        assert_eq!(last_requests.keys().collect::<Vec<_>>(), ["example.com"]);
+
+        // A new gate, as in a resumed run, waits out the delay of the earlier request
+        let resumed = PolitenessGate::default();
+        resumed.restore(&last_requests);
+        let start = Instant::now();
+        drop(resumed.acquire("example.com", &policy));
+        assert!(start.elapsed() >= Duration::from_millis(50), "{:?}", start.elapsed());
+
+        let stale = BTreeMap::from([("example.org".to_string(), Utc::now() - chrono::Duration::days(1))]);
+        resumed.restore(&stale);
+        let start = Instant::now();
+        drop(resumed.acquire("example.org", &policy));
+        assert!(start.elapsed() < Duration::from_millis(100));
+    }
+}
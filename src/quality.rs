@@ -0,0 +1,139 @@
+//! Extraction quality assessment for Trafilatura Rust port.
+//! This module rates extracted text with a few cheap signals, so pipelines can
+//! quarantine garbage extractions from hostile or broken pages.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::stats::text_stats;
+
+lazy_static! {
+    /// Phrases of cookie banners, paywalls, and error pages
+    static ref BOILERPLATE_PHRASES: Regex = Regex::new(
+        r"(?i)cookie|javascript|subscribe|sign in|log in|all rights reserved|privacy policy|terms of (?:use|service)|enable ads|ad blocker|404|page not found|access denied"
+    ).unwrap();
+}
+
+/// Texts shorter than this many words are at most of medium quality
+const MIN_WORDS_FOR_HIGH: usize = 50;
+
+/// Below this share of stopwords, text is rarely running prose
+const MIN_STOPWORD_RATIO: f64 = 0.15;
+
+/// Above this share of punctuation among visible characters, text is symbol soup
+const MAX_PUNCTUATION_DENSITY: f64 = 0.12;
+
+/// Above this share of duplicated lines, text is a repeated template
+const MAX_REPEATED_LINE_RATIO: f64 = 0.2;
+
+/// Above this share of lines with boilerplate phrases, text is a banner or error page
+const MAX_BOILERPLATE_DENSITY: f64 = 0.25;
+
+/// Quality label of an extraction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Quality {
+    Low,
+    Medium,
+    High,
+}
+
+impl fmt::Display for Quality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Quality::Low => "low",
+            Quality::Medium => "medium",
+            Quality::High => "high",
+        })
+    }
+}
+
+/// Signals the quality label is derived from
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct QualitySignals {
+    /// Number of words
+    pub words: usize,
+    /// Share of words that are stopwords, `None` for CJK text without spaces
+    pub stopword_ratio: Option<f64>,
+    /// Share of punctuation among non-whitespace characters
+    pub punctuation_density: f64,
+    /// Share of non-empty lines that repeat an earlier line
+    pub repeated_line_ratio: f64,
+    /// Share of non-empty lines containing a boilerplate phrase
+    pub boilerplate_density: f64,
+}
+
+impl QualitySignals {
+    /// Compute the signals of a text
+    pub fn of(text: &str) -> Self {
+        let stats = text_stats(text);
+        let (words, stopword_ratio) = (stats.words, stats.stopword_ratio);
+
+        let visible = text.chars().filter(|c| !c.is_whitespace()).count();
+        let punctuation = text.chars().filter(|c| c.is_ascii_punctuation()).count();
+        let punctuation_density = if visible == 0 { 0.0 } else { punctuation as f64 / visible as f64 };
+
+        let lines: Vec<&str> = text.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+        let mut seen = HashSet::new();
+        let repeated = lines.iter().filter(|line| !seen.insert(**line)).count();
+        let boilerplate = lines.iter().filter(|line| BOILERPLATE_PHRASES.is_match(line)).count();
+        let (repeated_line_ratio, boilerplate_density) = if lines.is_empty() {
+            (0.0, 0.0)
+        } else {
+            (repeated as f64 / lines.len() as f64, boilerplate as f64 / lines.len() as f64)
+        };
+
+        Self { words, stopword_ratio, punctuation_density, repeated_line_ratio, boilerplate_density }
+    }
+
+    /// Derive the quality label: each failing signal lowers it by one level
+    pub fn quality(&self) -> Quality {
+        if self.words == 0 {
+            return Quality::Low;
+        }
+
+        let problems = [
+            self.stopword_ratio.is_some_and(|ratio| ratio < MIN_STOPWORD_RATIO),
+            self.punctuation_density > MAX_PUNCTUATION_DENSITY,
+            self.repeated_line_ratio > MAX_REPEATED_LINE_RATIO,
+            self.boilerplate_density > MAX_BOILERPLATE_DENSITY,
+        ].iter().filter(|problem| **problem).count();
+
+        match problems {
+            0 if self.words >= MIN_WORDS_FOR_HIGH => Quality::High,
+            0 | 1 => Quality::Medium,
+            _ => Quality::Low,
+        }
+    }
+}
+
+/// Rate the quality of an extracted text
+pub fn assess_quality(text: &str) -> Quality {
+    QualitySignals::of(text).quality()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prose_is_high_quality() {
+        let text = "The council approved the new budget on Monday after a long debate. \
+            It will fund repairs to the bridge and the library, which have been closed for a year. \
+            Residents said they were relieved that the work can finally start, and the mayor \
+            promised that both buildings would open again before the end of the summer.";
+
+        assert_eq!(assess_quality(text), Quality::High);
+    }
+
+    #[test]
+    fn test_garbage_is_low_quality() {
+        let banner = "We use cookies. Accept all cookies | Privacy policy\n".repeat(5);
+        assert_eq!(assess_quality(&banner), Quality::Low);
+
+        assert_eq!(assess_quality("{{ $ctrl.items[0] }} => {{ $ctrl.total | currency }};"), Quality::Low);
+        assert_eq!(assess_quality(""), Quality::Low);
+    }
+}
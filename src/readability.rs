@@ -1,13 +1,19 @@
 //! Readability implementation for Trafilatura Rust port.
-//! This module provides a fallback extraction method based on a simplified readability algorithm.
+//! This module provides a fallback extraction method based on Mozilla's readability
+//! algorithm: paragraphs score their ancestors, the best candidate is merged with
+//! related siblings, and the result is cleaned of link lists, forms, and other clutter.
 
-use scraper::{Html, Selector, ElementRef};
-use std::collections::HashMap;
+use ego_tree::NodeId;
+use scraper::{Html, ElementRef};
+use std::collections::{HashMap, HashSet};
 use regex::Regex;
 use lazy_static::lazy_static;
 
 use crate::{ExtractionConfig, TrafilaturaError};
-use crate::html::{get_text_content, clean_html};
+use crate::html::{add_content, css_path, descendants_named, get_content, has_role, is_hidden, TextLengths, BOILERPLATE_ROLES, CONTENT_ROLES};
+use crate::document::Document;
+use crate::render::ContentRenderer;
+use crate::utils::{create_selector, text_length};
 
 lazy_static! {
     /// Regex to match unlikely content candidates
@@ -15,6 +21,9 @@ lazy_static! {
         r"(?i)combx|comment|community|disqus|extra|foot|header|menu|remark|rss|shoutbox|sidebar|sponsor|ad-break|agegate|pagination|pager|popup|tweet|twitter|social|share"
     ).unwrap();
     
+    /// Regex to match unlikely candidates that may still hold the content
+    static ref MAYBE_CANDIDATE_RE: Regex = Regex::new(r"(?i)and|article|body|column|content|main|shadow").unwrap();
+    
     /// Regex to match positive candidates
     static ref POSITIVE_CANDIDATES_RE: Regex = Regex::new(
         r"(?i)article|body|content|entry|hentry|main|page|pagination|post|text|blog|story"
@@ -24,71 +33,351 @@ lazy_static! {
     static ref NEGATIVE_CANDIDATES_RE: Regex = Regex::new(
         r"(?i)hidden|^hid$|combx|comment|com-|contact|foot|footer|footnote|masthead|media|meta|outbrain|promo|related|scroll|shoutbox|sidebar|sponsor|shopping|tags|tool|widget"
     ).unwrap();
+    
+    /// Sentence ends of short paragraphs worth keeping next to the content
+    static ref SENTENCE_END_RE: Regex = Regex::new(r"[.!?。！？](?:\s|$)").unwrap();
 }
 
-/// Extract content using readability algorithm
-pub fn extract_with_readability(document: &Html, config: &ExtractionConfig) -> Result<String, TrafilaturaError> {
-    // Clean the document first
-    let working_document = clean_html(document, config)?;
-    
-    // Find all paragraphs
-    let p_selector = Selector::parse("p").unwrap();
-    let paragraphs: Vec<ElementRef> = working_document.select(&p_selector).collect();
-    
-    if paragraphs.is_empty() {
-        return Ok(String::new());
+/// Paragraphs shorter than this do not score their ancestors
+const MIN_PARAGRAPH_LENGTH: usize = 25;
+
+/// Number of ancestors a paragraph scores
+const SCORED_ANCESTORS: usize = 5;
+
+/// Candidates compared with the top candidate when looking for a common ancestor
+const TOP_CANDIDATES: usize = 5;
+
+/// Candidates within this share of the top score sharing an ancestor make it the top candidate
+const ALTERNATIVE_SCORE_SHARE: f64 = 0.75;
+const MIN_ALTERNATIVE_CANDIDATES: usize = 3;
+
+/// Weight of class, ID, data attribute, and structural hints
+const HINT_WEIGHT: f64 = 25.0;
+
+/// Weight of elements that are not rendered
+const HIDDEN_WEIGHT: f64 = -50.0;
+
+/// Data attributes naming components in front-end frameworks, e.g. `data-testid="article-body"`
+const DATA_HINT_ATTRIBUTES: [&str; 5] = ["data-testid", "data-test", "data-qa", "data-component", "data-module"];
+
+/// Elements that can hold a paragraph's worth of text
+const BLOCK_ELEMENTS: [&str; 14] = [
+    "address", "article", "aside", "blockquote", "div", "dl", "fieldset", "figure",
+    "form", "ol", "p", "pre", "section", "table",
+];
+
+/// Elements removed from the content when they look like clutter
+const CONDITIONAL_ELEMENTS: [&str; 7] = ["form", "fieldset", "table", "ul", "ol", "div", "section"];
+
+/// Readability score of a candidate element, from its tag name, class/ID (with
+/// `class_hints`) and data attribute hints, structural roles, and visibility
+fn score_node(element: &ElementRef, class_hints: bool) -> f64 {
+    if is_hidden(element) {
+        return HIDDEN_WEIGHT;
     }
-    
-    // Score paragraphs
-    let mut paragraph_scores: HashMap<String, f64> = HashMap::new();
-    for paragraph in &paragraphs {
-        let text = paragraph.text().collect::<String>();
-        let words = text.split_whitespace().count();
-        
-        if words < 20 {
+
+    let tag_score = match element.value().name() {
+        "div" => 5.0,
+        "pre" | "td" | "blockquote" => 3.0,
+        "address" | "ol" | "ul" | "dl" | "dd" | "dt" | "li" | "form" => -3.0,
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th" => -5.0,
+        _ => 0.0,
+    };
+    let class_weight = if class_hints { class_weight(element) } else { 0.0 };
+    tag_score + class_weight + data_weight(element) + structure_weight(element)
+}
+
+/// +25 for each of the class and ID matching a positive pattern, -25 for a negative one
+fn class_weight(element: &ElementRef) -> f64 {
+    let mut weight = 0.0;
+    for attribute in ["class", "id"] {
+        if let Some(value) = element.value().attr(attribute) {
+            weight += hint_weight(value);
+        }
+    }
+    weight
+}
+
+/// +25 if the component names in data attributes match a positive pattern, -25 for a negative one
+fn data_weight(element: &ElementRef) -> f64 {
+    let names: Vec<&str> = DATA_HINT_ATTRIBUTES.iter()
+        .filter_map(|attribute| element.value().attr(attribute))
+        .collect();
+    hint_weight(&names.join(" "))
+}
+
+fn hint_weight(value: &str) -> f64 {
+    let mut weight = 0.0;
+    if value.is_empty() {
+        return weight;
+    }
+    if NEGATIVE_CANDIDATES_RE.is_match(value) {
+        weight -= HINT_WEIGHT;
+    }
+    if POSITIVE_CANDIDATES_RE.is_match(value) {
+        weight += HINT_WEIGHT;
+    }
+    weight
+}
+
+/// +25 for elements declared as the article body or main content, -25 for navigation
+/// and other landmark roles around the content
+fn structure_weight(element: &ElementRef) -> f64 {
+    let value = element.value();
+    let is_article_body = value.attr("itemprop")
+        .is_some_and(|itemprop| itemprop.split_whitespace().any(|property| property == "articleBody" || property == "text"));
+    if is_article_body || has_role(element, &CONTENT_ROLES) {
+        HINT_WEIGHT
+    } else if has_role(element, &BOILERPLATE_ROLES) {
+        -HINT_WEIGHT
+    } else {
+        0.0
+    }
+}
+
+fn inner_text(element: &ElementRef) -> String {
+    element.text().collect::<String>()
+}
+
+/// Elements whose class or ID suggests navigation, comments, or other non-content
+fn is_unlikely(element: &ElementRef) -> bool {
+    if matches!(element.value().name(), "body" | "a" | "article" | "main") || has_role(element, &CONTENT_ROLES) {
+        return false;
+    }
+    let hints = format!("{} {}", element.value().attr("class").unwrap_or(""), element.value().attr("id").unwrap_or(""));
+    UNLIKELY_CANDIDATES_RE.is_match(&hints) && !MAYBE_CANDIDATE_RE.is_match(&hints)
+}
+
+/// Elements scored as paragraphs: `p`, `pre`, `td`, and `div`s without block children
+fn is_paragraph(element: &ElementRef) -> bool {
+    match element.value().name() {
+        "p" | "pre" | "td" => true,
+        "div" => descendants_named(*element, &BLOCK_ELEMENTS).next().is_none(),
+        _ => false,
+    }
+}
+
+fn element_ancestors<'a>(element: &ElementRef<'a>) -> impl Iterator<Item = ElementRef<'a>> {
+    element.ancestors()
+        .filter_map(ElementRef::wrap)
+        .filter(|ancestor| ancestor.value().name() != "html")
+}
+
+/// Score the ancestors of every paragraph: the parent gets the full paragraph score,
+/// the grandparent half of it, and further ancestors a third per level. Without
+/// `class_hints`, class and ID names neither exclude nor weigh candidates.
+fn score_candidates(document: &Html, lengths: &TextLengths, class_hints: bool) -> HashMap<NodeId, f64> {
+    let mut scores: HashMap<NodeId, f64> = HashMap::new();
+
+    for paragraph in descendants_named(document.root_element(), &["p", "pre", "td", "div"]) {
+        let is_excluded = |element: &ElementRef| (class_hints && is_unlikely(element)) || is_hidden(element);
+        if !is_paragraph(&paragraph) || is_excluded(&paragraph) || element_ancestors(&paragraph).any(|ancestor| is_excluded(&ancestor)) {
             continue;
         }
-        
-        // Find parent element to score
-        let parent_selector = Selector::parse("body").unwrap();
-        if let Some(_parent) = working_document.select(&parent_selector).next() {
-            // Use a simple string identifier for the body element
-            let parent_id = "body_element".to_string();
-            let score = paragraph_scores.entry(parent_id).or_insert(0.0);
-            *score += words as f64 / 20.0;
+
+        let text = inner_text(&paragraph);
+        let text = text.trim();
+        let length = text_length(text);
+        if length < MIN_PARAGRAPH_LENGTH {
+            continue;
+        }
+
+        let commas = text.chars().filter(|c| matches!(c, ',' | '，' | '、')).count();
+        let content_score = 1.0 + commas as f64 + (length / 100).min(3) as f64;
+
+        for (level, ancestor) in element_ancestors(&paragraph).take(SCORED_ANCESTORS).enumerate() {
+            let divider = match level {
+                0 => 1.0,
+                1 => 2.0,
+                _ => level as f64 * 3.0,
+            };
+            *scores.entry(ancestor.id()).or_insert_with(|| score_node(&ancestor, class_hints)) += content_score / divider;
         }
     }
-    
-    // Find the top-scoring parent
-    let mut top_parent = None;
-    let mut top_score = 0.0;
-    
-    for (_parent_hash, score) in &paragraph_scores {
-        if *score > top_score {
-            top_score = *score;
-            
-            // Find the parent by hash
-            // Note: In a real implementation, we would need a better way to find
-            // the element by hash. This is just a placeholder.
-            let body_selector = Selector::parse("body").unwrap();
-            if let Some(body) = working_document.select(&body_selector).next() {
-                top_parent = Some(body);
+
+    // Content is mostly text, not links
+    for (id, score) in scores.iter_mut() {
+        if let Some(element) = document.tree.get(*id).and_then(ElementRef::wrap) {
+            *score *= 1.0 - lengths.get(&element).link_density();
+        }
+    }
+
+    scores
+}
+
+/// Pick the top candidate, moving up to a common ancestor when several good candidates
+/// share one (an article split into sections), or when the candidate is an only child
+fn find_top_candidate<'a>(document: &'a Html, scores: &HashMap<NodeId, f64>) -> Option<(ElementRef<'a>, f64)> {
+    let mut ranked: Vec<(ElementRef, f64)> = scores.iter()
+        .filter_map(|(id, score)| Some((document.tree.get(*id).and_then(ElementRef::wrap)?, *score)))
+        .collect();
+    // Ties go to the deepest element
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| element_ancestors(&b.0).count().cmp(&element_ancestors(&a.0).count())));
+    let (mut top, top_score) = *ranked.first()?;
+
+    let alternatives: Vec<ElementRef> = ranked.iter()
+        .skip(1)
+        .take(TOP_CANDIDATES - 1)
+        .filter(|(_, score)| *score / top_score >= ALTERNATIVE_SCORE_SHARE)
+        .map(|(element, _)| *element)
+        .collect();
+    if alternatives.len() >= MIN_ALTERNATIVE_CANDIDATES {
+        let common = element_ancestors(&top)
+            .take_while(|ancestor| ancestor.value().name() != "body")
+            .find(|ancestor| {
+                alternatives.iter()
+                    .filter(|alternative| element_ancestors(alternative).any(|outer| outer.id() == ancestor.id()))
+                    .count() >= MIN_ALTERNATIVE_CANDIDATES
+            });
+        if let Some(common) = common {
+            top = common;
+        }
+    }
+
+    while let Some(parent) = top.parent().and_then(ElementRef::wrap) {
+        if parent.value().name() == "body" || parent.children().filter_map(ElementRef::wrap).count() != 1 {
+            break;
+        }
+        top = parent;
+    }
+
+    let score = scores.get(&top.id()).copied().unwrap_or(top_score);
+    Some((top, score))
+}
+
+/// The top candidate and those of its siblings that look like part of the content
+fn merge_siblings(top: ElementRef, top_score: f64, scores: &HashMap<NodeId, f64>, lengths: &TextLengths) -> Vec<NodeId> {
+    let parent = match top.parent().and_then(ElementRef::wrap) {
+        Some(parent) if parent.value().name() != "html" => parent,
+        _ => return vec![top.id()],
+    };
+
+    let threshold = (top_score * 0.2).max(10.0);
+    let top_class = top.value().attr("class").unwrap_or("");
+
+    parent.children()
+        .filter_map(ElementRef::wrap)
+        .filter(|sibling| {
+            if sibling.id() == top.id() {
+                return true;
             }
+
+            let bonus = if !top_class.is_empty() && sibling.value().attr("class") == Some(top_class) { top_score * 0.2 } else { 0.0 };
+            if scores.get(&sibling.id()).is_some_and(|score| score + bonus >= threshold) {
+                return true;
+            }
+
+            if sibling.value().name() != "p" {
+                return false;
+            }
+            let text = inner_text(sibling);
+            let length = text_length(text.trim());
+            let density = lengths.get(sibling).link_density();
+            (length > 80 && density < 0.25) || (length > 0 && density == 0.0 && SENTENCE_END_RE.is_match(&text))
+        })
+        .map(|sibling| sibling.id())
+        .collect()
+}
+
+/// Collect the elements of the content that look like clutter: link lists, forms,
+/// image galleries, and blocks with a negative score or class (with `class_hints`)
+fn conditional_removals(element: ElementRef, scores: &HashMap<NodeId, f64>, lengths: &TextLengths, class_hints: bool, removed: &mut Vec<NodeId>) {
+    for node in descendants_named(element, &CONDITIONAL_ELEMENTS) {
+        let name = node.value().name();
+        if name == "table" && descendants_named(node, &["th", "caption"]).next().is_some() {
+            // Data tables are content
+            continue;
+        }
+
+        let weight = if class_hints { class_weight(&node) } else { 0.0 };
+        if weight + scores.get(&node.id()).copied().unwrap_or(0.0) < 0.0 {
+            removed.push(node.id());
+            continue;
+        }
+
+        let text = inner_text(&node);
+        if text.chars().filter(|c| matches!(c, ',' | '，' | '、')).count() >= 10 {
+            continue;
+        }
+
+        let count = |names: &[&str]| descendants_named(node, names).count();
+        let paragraphs = count(&["p"]) as f64;
+        let images = count(&["img"]) as f64;
+        // Lists are only suspicious with a hundred items more than paragraphs
+        let items = count(&["li"]) as f64 - 100.0;
+        let inputs = descendants_named(node, &["input"])
+            .filter(|input| input.value().attr("type") != Some("hidden"))
+            .count() as f64;
+        let is_list = matches!(name, "ul" | "ol");
+        let in_figure = element_ancestors(&node).any(|ancestor| ancestor.value().name() == "figure");
+        let length = text_length(text.trim());
+        let density = lengths.get(&node).link_density();
+
+        let is_clutter = (images > 1.0 && paragraphs / images < 0.5 && !in_figure)
+            || (!is_list && items > paragraphs)
+            || (inputs > (paragraphs / 3.0).floor())
+            || (!is_list && !in_figure && length < MIN_PARAGRAPH_LENGTH && (images == 0.0 || images > 2.0))
+            || (!is_list && weight < 25.0 && density > 0.2)
+            || (weight >= 25.0 && density > 0.5);
+        if is_clutter {
+            removed.push(node.id());
         }
     }
-    
-    // Extract content from top parent
-    if let Some(parent) = top_parent {
-        let content = get_text_content(&parent, config);
-        Ok(content)
-    } else {
-        Ok(String::new())
+}
+
+/// Extract content using readability algorithm.
+/// The document is expected to have been cleaned with `clean_html`.
+pub fn extract_with_readability(document: &Html, config: &ExtractionConfig) -> Result<Document, TrafilaturaError> {
+    extract_with_readability_container(document, config).map(|(content, _)| content)
+}
+
+/// Extract content like `extract_with_readability`, also returning the CSS path of
+/// the top candidate, or of the body without one; merged siblings are not included
+pub fn extract_with_readability_container(document: &Html, config: &ExtractionConfig) -> Result<(Document, Option<String>), TrafilaturaError> {
+    let lengths = TextLengths::new(document);
+    let class_hints = config.uses_class_hints();
+    let scores = score_candidates(document, &lengths, class_hints);
+
+    let (top, top_score) = match find_top_candidate(document, &scores) {
+        Some(top) => top,
+        None => {
+            // Without scored paragraphs, fall back to the whole body
+            let body_selector = create_selector("body")?;
+            let body = document.select(&body_selector).next();
+            let content = body.map(|body| get_content(&body, config)).unwrap_or_default();
+            return Ok((content, body.map(|body| css_path(&body))));
+        }
+    };
+    let container = css_path(&top);
+
+    let included = merge_siblings(top, top_score, &scores, &lengths);
+    let mut removed = Vec::new();
+    for id in &included {
+        if let Some(element) = document.tree.get(*id).and_then(ElementRef::wrap) {
+            conditional_removals(element, &scores, &lengths, class_hints, &mut removed);
+        }
     }
+
+    // Node IDs stay valid in a clone of the tree
+    let mut cleaned = document.clone();
+    let removed: HashSet<NodeId> = removed.into_iter().collect();
+    for id in removed {
+        if let Some(mut node) = cleaned.tree.get_mut(id) {
+            node.detach();
+        }
+    }
+
+    let mut renderer = ContentRenderer::new(config).skip_boilerplate();
+    for element in included.iter().filter_map(|id| cleaned.tree.get(*id).and_then(ElementRef::wrap)) {
+        add_content(&element, &mut renderer);
+    }
+    Ok((renderer.finish(), Some(container)))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ImageStyle;
 
     #[test]
     fn test_readability_extraction() {
@@ -113,11 +402,121 @@ mod tests {
         let document = Html::parse_document(html);
         let config = ExtractionConfig::default();
         
-        let content = extract_with_readability(&document, &config).unwrap();
+        let content = extract_with_readability(&document, &config).unwrap().to_text(config.image_style);
         
         assert!(content.contains("Article Title"));
         assert!(content.contains("long paragraph"));
         assert!(content.contains("main content"));
         assert!(content.contains("another paragraph"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_readability_extraction_cjk() {
+        // Chinese paragraphs have no spaces, so whitespace word counts would drop them
+        let html = r#"
+        <html>
+            <body>
+                <div class="content">
+                    <h1>新闻标题</h1>
+                    <p>这是一段足够长的中文段落，用于测试可读性算法是否能够正确识别没有空格分隔的文本内容。</p>
+                </div>
+            </body>
+        </html>
+        "#;
+        
+        let document = Html::parse_document(html);
+        let config = ExtractionConfig::default();
+        
+        let content = extract_with_readability(&document, &config).unwrap().to_text(config.image_style);
+        
+        assert!(content.contains("新闻标题"));
+        assert!(content.contains("中文段落"));
+    }
+
+    #[test]
+    fn test_readability_merges_siblings_and_cleans() {
+        let paragraph = |topic: &str| format!(
+            "<p>The second part of the story covers {}, with quotes from residents, officials, and experts who followed the case for years.</p>",
+            topic
+        );
+        let html = format!(r#"<html><body>
+            <div class="story-part">{}{}
+                <div class="links"><a href="/1">Related one</a> | <a href="/2">Related two</a></div>
+                <ul><li>Bring water</li><li>See the <a href="/map">map</a></li></ul>
+            </div>
+            <div class="ad-slot"><a href="/buy">Buy now</a></div>
+            <div class="story-part">{}{}</div>
+            <div id="sidebar"><p>Popular stories from elsewhere on the site, updated every hour for our readers.</p></div>
+            </body></html>"#,
+            paragraph("the budget"), paragraph("the vote"), paragraph("the protests"), paragraph("the appeal"));
+        let document = Html::parse_document(&html);
+
+        let content = extract_with_readability(&document, &ExtractionConfig::default()).unwrap().to_text(ImageStyle::AltText);
+
+        for topic in ["the budget", "the vote", "the protests", "the appeal"] {
+            assert!(content.contains(topic), "missing {}", topic);
+        }
+        assert!(!content.contains("Related one"));
+        // Lists are not clutter for being short or holding links
+        assert!(content.contains("Bring water") && content.contains("See the map"), "{}", content);
+        assert!(!content.contains("Buy now"));
+        assert!(!content.contains("Popular stories"));
+    }
+
+    #[test]
+    fn test_score_node_signals() {
+        let html = r#"<html><body>
+            <div id="a" data-testid="article-body"></div>
+            <div id="b" itemprop="articleBody"></div>
+            <div id="c" role="navigation"></div>
+            <div id="d" data-component="related-widget"></div>
+            <div id="e" class="post" style="display: none"></div>
+            </body></html>"#;
+        let document = Html::parse_document(html);
+        let score = |id: &str| {
+            let selector = create_selector(&format!("#{}", id)).unwrap();
+            score_node(&document.select(&selector).next().unwrap(), true)
+        };
+
+        assert_eq!(score("a"), 30.0);
+        assert_eq!(score("b"), 30.0);
+        assert_eq!(score("c"), -20.0);
+        assert_eq!(score("d"), -20.0);
+        assert_eq!(score("e"), HIDDEN_WEIGHT);
+    }
+
+    #[test]
+    fn test_readability_prefers_declared_body_over_hidden_text() {
+        let paragraph = "<p>Text long enough to count as a paragraph of the page, with some commas, here and there.</p>";
+        let html = format!(r#"<html><body>
+            <div aria-hidden="true">{p}{p}{p}<p>Hidden keyword stuffing for search engines.</p></div>
+            <div data-testid="story-body">{p}<p>The visible story ends here.</p></div>
+            <div class="box">{p}</div>
+            </body></html>"#, p = paragraph);
+        let document = Html::parse_document(&html);
+
+        let content = extract_with_readability(&document, &ExtractionConfig::default()).unwrap().to_text(ImageStyle::AltText);
+
+        assert!(content.contains("The visible story ends here."));
+        assert!(!content.contains("keyword stuffing"));
+    }
+
+    #[test]
+    fn test_score_propagation() {
+        let html = r#"<html><body><article><section><div>
+            <p>A paragraph long enough to score, with a comma, and another comma, for good measure here.</p>
+            </div></section></article></body></html>"#;
+        let document = Html::parse_document(html);
+
+        let scores = score_candidates(&document, &TextLengths::new(&document), true);
+        let score_of = |name: &str| {
+            let element = descendants_named(document.root_element(), &[name]).next().unwrap();
+            scores[&element.id()]
+        };
+
+        // 1 + 3 commas + 0 length bonus, divided by 1, 2, and 6 up the tree
+        assert_eq!(score_of("div"), 5.0 + 4.0);
+        assert_eq!(score_of("section"), 2.0);
+        assert!((score_of("article") - 4.0 / 6.0).abs() < 1e-9);
+    }
+}
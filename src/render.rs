@@ -0,0 +1,746 @@
+//! Content rendering for Trafilatura Rust port.
+//! Every strategy finds the elements holding the content; this module turns them
+//! into a `Document`, the same way for all of them. An element is read as a
+//! sequence of blocks in document order (headings, paragraphs, lists, code samples,
+//! tables, and images), and each block is rendered according to the link, image,
+//! and table settings of the configuration.
+
+use ego_tree::iter::Edge;
+use ego_tree::NodeId;
+use lazy_static::lazy_static;
+use regex::{Regex, RegexBuilder};
+use scraper::{ElementRef, Node, Selector};
+
+use crate::{ExtractionConfig, ExtractionMode, LinkStyle};
+use crate::document::{Document, Element, Image};
+use crate::html::{descendants_named, has_class_hint};
+use crate::tables::Table;
+use crate::utils::{count_words, text_length};
+use crate::xpath::WIKI_SKIP_SECTION_TITLES;
+
+lazy_static! {
+    /// Labels of boilerplate lines in news articles ("Read More", "Published on ...")
+    static ref BOILERPLATE_LINE_RE: Regex = Regex::new(
+        r"(?i)^(?:also read|read more|catch all the|download the|(?:first )?published(?:\s*:|\s+on\b)|last updated\b|business news|economy news|breaking news events|latest news updates|daily market updates|more less)"
+    ).unwrap();
+
+    /// Lines marking the end of the main content of news articles ("Related articles",
+    /// "Copyright 2024 ...")
+    static ref CONTENT_END_RE: Regex = Regex::new(
+        r"(?i)^(?:catch all the\b|download (?:the|our)\b.*\bapp\b|follow us\b|first published\b|read more about\b|more on this topic\b|related (?:articles|stories)\b|tags:|copyright\s+(?:©\s*)?\d{4}|©)"
+    ).unwrap();
+
+    /// Bylines, dates, and sharing prompts, and teasers of other articles
+    static ref METADATA_LINE_RE: Regex = Regex::new(
+        r"^(?i:(?:published|updated|posted|written by|share|subscribe|follow us|also read|more less|business news|latest news)\b|read:|watch:|see also:)|^By\s+\p{Lu}|^©|(?i:all rights reserved\.?)$"
+    ).unwrap();
+
+    /// Noise in extracted text, removed in a single pass: parentheses holding nothing
+    /// but links, link paths in parentheses, and leftover links, each with the space
+    /// before it; then isolated parentheses and runs of whitespace, which collapse
+    /// to a space. No alternative runs past a word that fails it, so an unclosed
+    /// parenthesis does not make every later match rescan the rest of the text.
+    static ref TEXT_NOISE_RE: Regex = cleanup_regex(&[
+        r"\s*\((?:\s|https?://[^\s)]*)*\)", r"\s*\(\s*/\S*\s*\)", r"\s*https?://\S+",
+        r"\s+[()]\s+", r"\s+",
+    ]);
+
+    /// The same noise when links were requested, so link text is kept
+    static ref TEXT_NOISE_KEEP_LINKS_RE: Regex = cleanup_regex(&[
+        r"\s*\(\s*\)", r"\s+[()]\s+", r"\s+",
+    ]);
+
+    /// Containers styled as paragraphs, read as one when they hold no blocks
+    static ref PARAGRAPH_CONTAINERS: Selector = Selector::parse(
+        "div[class*='paragraph'], div[class*='text-block'], div[class*='post-block'], div[class*='entry-block'], div.post-text, div.text, div[class*='article-text'], section[class*='paragraph']"
+    ).unwrap();
+}
+
+/// Compiled size limit of the text cleanup regexes
+const CLEANUP_REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/// Lazy DFA cache of the text cleanup regexes. Once the cache fills up, matching
+/// falls back to a much slower engine, which took seconds on megabytes of text.
+const CLEANUP_DFA_SIZE_LIMIT: usize = 8 << 20;
+
+/// Compile the alternatives into one cleanup regex, tried in order at each position
+fn cleanup_regex(alternatives: &[&str]) -> Regex {
+    RegexBuilder::new(&alternatives.join("|"))
+        .size_limit(CLEANUP_REGEX_SIZE_LIMIT)
+        .dfa_size_limit(CLEANUP_DFA_SIZE_LIMIT)
+        .build()
+        .expect("cleanup regex exceeds its size limit")
+}
+
+/// Maximum length of a line that is dropped as boilerplate when it starts with a boilerplate label
+const MAX_BOILERPLATE_LINE_LENGTH: usize = 80;
+
+/// Maximum words of a line that ends the main content when it starts with a marker
+const MAX_CONTENT_END_WORDS: usize = 12;
+
+/// Maximum words of a line that is dropped as metadata, e.g. "By Ann Smith"
+const MAX_METADATA_LINE_WORDS: usize = 8;
+
+/// Elements inside a table that make it a layout table, read block by block
+const LAYOUT_TABLE_BLOCKS: [&str; 11] = ["table", "p", "h1", "h2", "h3", "h4", "h5", "h6", "ul", "ol", "dl"];
+
+/// Elements inside a paragraph container that make it a wrapper of blocks
+const CONTAINER_BLOCKS: [&str; 12] = ["p", "h1", "h2", "h3", "h4", "h5", "h6", "ul", "ol", "dl", "table", "pre"];
+
+/// Elements whose text is never content: scripts, and the labels and tick values of
+/// charts drawn as SVG or on a canvas
+const NON_TEXT_ELEMENTS: [&str; 6] = ["script", "style", "noscript", "template", "svg", "canvas"];
+
+/// Kinds of blocks of rendered content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Heading,
+    Paragraph,
+    List,
+    Code,
+    Table,
+    Image,
+    /// Elements without text, skipped with their content
+    NonText,
+}
+
+/// Renders the content of elements block by block, in document order.
+///
+/// Footnote links are numbered across every element added to the same renderer,
+/// and their targets are collected in the document returned by `finish`.
+pub struct ContentRenderer<'a> {
+    config: &'a ExtractionConfig,
+    inline: InlineRenderer<'a>,
+    elements: Vec<Element>,
+    /// Tables kept aside with `ExtractionConfig::separate_tables`
+    tables: Vec<Table>,
+    /// Paragraphs with at most this weighted length are left out
+    min_paragraph_length: Option<usize>,
+    /// Whether publication boilerplate is left out with `ExtractionConfig::filter_boilerplate`
+    skip_boilerplate: bool,
+    /// Whether Wikipedia reference sections are left out
+    skip_reference_sections: bool,
+    in_reference_section: bool,
+    /// Set once a marker of the end of the main content is found; later blocks are left out
+    skip_rest: bool,
+}
+
+impl<'a> ContentRenderer<'a> {
+    /// Create a renderer for the given configuration
+    pub fn new(config: &'a ExtractionConfig) -> Self {
+        Self {
+            config,
+            inline: InlineRenderer::new(config),
+            elements: Vec::new(),
+            tables: Vec::new(),
+            min_paragraph_length: None,
+            skip_boilerplate: false,
+            skip_reference_sections: false,
+            in_reference_section: false,
+            skip_rest: false,
+        }
+    }
+
+    /// The configuration the renderer was created for
+    pub fn config(&self) -> &'a ExtractionConfig {
+        self.config
+    }
+
+    /// Leave out paragraphs with at most this weighted length, except in documentation
+    /// mode; the images they hold are kept
+    pub fn min_paragraph_length(mut self, length: usize) -> Self {
+        self.min_paragraph_length = (self.config.mode != ExtractionMode::Docs).then_some(length);
+        self
+    }
+
+    /// Leave out publication boilerplate such as bylines and "Read more" lines, and
+    /// every block after a marker of the end of the main content
+    pub fn skip_boilerplate(mut self) -> Self {
+        self.skip_boilerplate = self.config.filter_boilerplate;
+        self
+    }
+
+    /// Leave out the sections of Wikipedia references, such as "See also" and "External links"
+    pub fn skip_reference_sections(mut self) -> Self {
+        self.skip_reference_sections = true;
+        self
+    }
+
+    /// Add the blocks of an element, leaving out the blocks for which `excluded` holds.
+    ///
+    /// An element without any block is added as a paragraph of its text.
+    pub fn add(&mut self, element: &ElementRef, excluded: &dyn Fn(&ElementRef) -> bool) {
+        let mut found = false;
+        // The block being skipped over after it was added or left out
+        let mut inside: Option<NodeId> = None;
+
+        // Walked without recursion, so deeply nested documents cannot overflow the stack
+        for edge in element.traverse() {
+            match edge {
+                Edge::Open(node) if inside.is_none() => {
+                    let Some(child) = ElementRef::wrap(node) else { continue };
+                    let Some(kind) = self.block_kind(&child) else { continue };
+                    found |= kind != BlockKind::NonText;
+                    inside = Some(node.id());
+                    if !excluded(&child) {
+                        self.add_block(&child, kind, excluded);
+                    }
+                }
+                Edge::Close(node) if inside == Some(node.id()) => inside = None,
+                _ => {}
+            }
+        }
+
+        if !found && !excluded(element) && !self.skip_rest {
+            self.add_text(element);
+        }
+    }
+
+    /// The document of the blocks added so far, with the tables kept aside and the
+    /// targets of footnote-style links
+    pub fn finish(self) -> Document {
+        Document {
+            elements: self.elements,
+            tables: self.tables,
+            footnotes: self.inline.into_footnotes(),
+        }
+    }
+
+    /// The kind of block an element is, or `None` for elements whose content is
+    /// read block by block
+    fn block_kind(&self, element: &ElementRef) -> Option<BlockKind> {
+        match element.value().name() {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => Some(BlockKind::Heading),
+            "p" => Some(BlockKind::Paragraph),
+            // Code samples are part of documentation
+            "pre" if self.config.mode == ExtractionMode::Docs => Some(BlockKind::Code),
+            "ul" | "ol" | "dl" => Some(BlockKind::List),
+            "table" if descendants_named(*element, &LAYOUT_TABLE_BLOCKS).next().is_none() => Some(BlockKind::Table),
+            "img" | "svg" => Some(BlockKind::Image),
+            name if NON_TEXT_ELEMENTS.contains(&name) => Some(BlockKind::NonText),
+            _ if PARAGRAPH_CONTAINERS.matches(element) && descendants_named(*element, &CONTAINER_BLOCKS).next().is_none() => {
+                Some(BlockKind::Paragraph)
+            },
+            _ => None,
+        }
+    }
+
+    fn add_block(&mut self, element: &ElementRef, kind: BlockKind, excluded: &dyn Fn(&ElementRef) -> bool) {
+        if kind == BlockKind::Heading && self.skip_reference_sections {
+            let heading = element.text().collect::<String>();
+            self.in_reference_section = is_reference_section(&heading);
+        }
+        if self.skip_rest || self.in_reference_section {
+            return;
+        }
+
+        match kind {
+            BlockKind::Heading | BlockKind::Paragraph => {
+                if self.skip_boilerplate {
+                    let raw = element.text().collect::<String>();
+                    let skip_short = self.config.mode != ExtractionMode::Docs;
+                    if is_boilerplate_paragraph(&raw, skip_short, &mut self.skip_rest) {
+                        return;
+                    }
+                }
+                let text = self.render_clean(element);
+                let long_enough = self.min_paragraph_length.is_none_or(|min| text_length(&text) > min);
+                if kind == BlockKind::Heading {
+                    let level = element.value().name()[1..].parse().unwrap_or(1);
+                    self.push(Element::Heading { level, text });
+                } else if long_enough {
+                    self.push(Element::Paragraph(text));
+                }
+            },
+            BlockKind::List => {
+                let mut items = Vec::new();
+                for item in descendants_named(*element, &["li", "dt", "dd"]) {
+                    if excluded(&item) {
+                        continue;
+                    }
+                    // Nested lists are rendered as items of their own
+                    let text = self.inline.render_filtered(&item, "", |parent, _| nearest_list_item(parent) == Some(item.id()));
+                    let text = clean_text(&text, self.config);
+                    if !text.is_empty() {
+                        items.push(text);
+                    }
+                }
+                if !items.is_empty() {
+                    let ordered = element.value().name() == "ol";
+                    self.push(Element::List { ordered, items });
+                }
+            },
+            BlockKind::Code => {
+                let text = self.inline.render(element);
+                self.push(Element::Code(text.trim_matches('\n').trim_end().to_string()));
+                return;
+            },
+            BlockKind::Table => {
+                if !self.config.include_tables {
+                    return;
+                }
+                if self.config.separate_tables {
+                    // Keep the table aside and leave a placeholder in the text
+                    let table = Table::from_element(element);
+                    if !table.is_empty() {
+                        self.tables.push(table);
+                        self.push(Element::TableRef(self.tables.len()));
+                    }
+                } else {
+                    let rows = self.render_table(element);
+                    self.push(Element::Table(rows));
+                }
+            },
+            BlockKind::Image => {
+                if let Some(image) = self.image(element) {
+                    self.push(Element::Image(image));
+                }
+                return;
+            },
+            BlockKind::NonText => return,
+        }
+
+        // Images inside a block follow it
+        for img in descendants_named(*element, &["img", "svg"]) {
+            if let Some(image) = self.image(&img).filter(|_| !excluded(&img)) {
+                self.push(Element::Image(image));
+            }
+        }
+    }
+
+    /// Add the text of an element without blocks, leaving out boilerplate text nodes
+    fn add_text(&mut self, element: &ElementRef) {
+        let filter = self.skip_boilerplate;
+        let text = self.inline.render_filtered(element, " ", |_, text| {
+            let text = text.trim();
+            !(text.is_empty() || (filter && is_boilerplate_text(text)))
+        });
+        let text = clean_text(&text, self.config);
+        self.push(Element::Paragraph(text));
+    }
+
+    /// Render the non-empty cells of each row of a table; a table without any is a
+    /// single cell of its text
+    fn render_table(&mut self, table: &ElementRef) -> Vec<Vec<String>> {
+        let mut rows = Vec::new();
+        for row in descendants_named(*table, &["tr"]) {
+            let cells: Vec<String> = descendants_named(row, &["td", "th"])
+                .map(|cell| self.render_clean(&cell))
+                .filter(|cell| !cell.is_empty())
+                .collect();
+            if !cells.is_empty() {
+                rows.push(cells);
+            }
+        }
+        if rows.is_empty() {
+            let text = self.render_clean(table);
+            if !text.is_empty() {
+                rows.push(vec![text]);
+            }
+        }
+        rows
+    }
+
+    fn render_clean(&mut self, element: &ElementRef) -> String {
+        clean_text(&self.inline.render(element), self.config)
+    }
+
+    /// The image of an `img` or `svg` element, if images are rendered and it has
+    /// something to show in `ExtractionConfig::image_style`
+    fn image(&self, img: &ElementRef) -> Option<Image> {
+        let rendered = if img.value().name() == "svg" { self.config.svg_titles } else { self.config.renders_images() };
+        extract_image(img)
+            .filter(|image| rendered && image.to_text(self.config.image_style).is_some())
+    }
+
+    fn push(&mut self, element: Element) {
+        let empty = match &element {
+            Element::Heading { text, .. } | Element::Paragraph(text) | Element::Code(text) => text.is_empty(),
+            Element::Table(rows) => rows.is_empty(),
+            Element::List { .. } | Element::TableRef(_) | Element::Image(_) => false,
+        };
+        if !empty {
+            self.elements.push(element);
+        }
+    }
+}
+
+/// The list item a text node with this parent belongs to
+fn nearest_list_item(parent: &ElementRef) -> Option<NodeId> {
+    std::iter::once(*parent)
+        .chain(parent.ancestors().filter_map(ElementRef::wrap))
+        .find(|element| matches!(element.value().name(), "li" | "dt" | "dd"))
+        .map(|element| element.id())
+}
+
+/// Check if a heading starts a Wikipedia reference section, such as "See also"
+fn is_reference_section(heading_text: &str) -> bool {
+    let heading_lower = heading_text.to_lowercase();
+
+    WIKI_SKIP_SECTION_TITLES.iter().any(|&title| {
+        heading_lower.contains(&title.to_lowercase())
+    })
+}
+
+/// Remove URL references that may have slipped through, unless links were requested,
+/// along with empty and isolated parentheses, and normalize spaces
+fn clean_text(text: &str, config: &ExtractionConfig) -> String {
+    let noise: &Regex = if config.include_links { &TEXT_NOISE_KEEP_LINKS_RE } else { &TEXT_NOISE_RE };
+    let mut cleaned = String::with_capacity(text.len());
+    let mut last = 0;
+    for found in noise.find_iter(text) {
+        cleaned.push_str(&text[last..found.start()]);
+        // Whitespace runs and isolated parentheses end in whitespace and become a
+        // space; links and parentheses are dropped
+        if found.as_str().ends_with(char::is_whitespace) {
+            cleaned.push(' ');
+        }
+        last = found.end();
+    }
+    cleaned.push_str(&text[last..]);
+    cleaned.trim().to_string()
+}
+
+/// Read an image with its alternative text, source, and figure caption, or `None`
+/// for decoration (icons, logos, avatars). An SVG graphic is read with its `<title>`
+/// as alternative text, and without one it has nothing to show.
+pub fn extract_image(img: &ElementRef) -> Option<Image> {
+    if has_class_hint(img, &["icon", "logo", "social", "avatar", "ad"]) {
+        return None;
+    }
+    let attr = |name| img.value().attr(name).map(str::trim).filter(|value| !value.is_empty()).map(str::to_string);
+    let (alt, src) = if img.value().name() == "svg" {
+        let title = img.children()
+            .filter_map(ElementRef::wrap)
+            .find(|child| child.value().name() == "title")?;
+        let title = title.text().flat_map(str::split_whitespace).collect::<Vec<_>>().join(" ");
+        (Some(title).filter(|title| !title.is_empty()), None)
+    } else {
+        (attr("alt"), attr("src"))
+    };
+    let caption = img.ancestors()
+        .filter_map(ElementRef::wrap)
+        .find(|ancestor| ancestor.value().name() == "figure")
+        .and_then(|figure| descendants_named(figure, &["figcaption"]).next())
+        .map(|caption| caption.text().flat_map(str::split_whitespace).collect::<Vec<_>>().join(" "))
+        .filter(|caption| !caption.is_empty());
+
+    Some(Image { alt, src, caption })
+}
+
+/// Check if a paragraph is publication boilerplate rather than content. Only whole
+/// short lines starting with a marker match; longer text mentioning one is kept.
+///
+/// `skip_rest` is set once a marker of the end of the main content is found
+/// (e.g. "Catch all the ...", "Related articles"); every later paragraph is then skipped.
+/// With `skip_short`, bylines, dates, and prompts such as "Share this article" are
+/// treated as metadata.
+fn is_boilerplate_paragraph(text: &str, skip_short: bool, skip_rest: &mut bool) -> bool {
+    let text = text.trim();
+    if count_words(text) <= MAX_CONTENT_END_WORDS && CONTENT_END_RE.is_match(text) {
+        *skip_rest = true;
+        return true;
+    }
+
+    if *skip_rest {
+        return true;
+    }
+
+    (skip_short && is_metadata_line(text)) || is_boilerplate_line(text)
+}
+
+/// Check if a text node is publication boilerplate rather than content
+fn is_boilerplate_text(text: &str) -> bool {
+    is_metadata_line(text) || is_boilerplate_line(text)
+}
+
+/// Check if a short line is a byline, a date, or a prompt, e.g. "By Ann Smith",
+/// "Updated 5 May 2024", "Share this article"
+fn is_metadata_line(text: &str) -> bool {
+    count_words(text) <= MAX_METADATA_LINE_WORDS && METADATA_LINE_RE.is_match(text)
+}
+
+/// Check if a short line consists of a boilerplate label such as "Read More" or
+/// "Last Updated : ...". Longer text that merely contains such a phrase is kept.
+fn is_boilerplate_line(text: &str) -> bool {
+    text_length(text) <= MAX_BOILERPLATE_LINE_LENGTH && BOILERPLATE_LINE_RE.is_match(text)
+}
+
+/// Renders inline text with links formatted according to `ExtractionConfig::rendered_link_style`
+pub struct InlineRenderer<'a> {
+    config: &'a ExtractionConfig,
+    /// Link targets collected for footnote-style rendering, in marker order
+    footnotes: Vec<String>,
+}
+
+impl<'a> InlineRenderer<'a> {
+    /// Create a renderer for the given configuration
+    pub fn new(config: &'a ExtractionConfig) -> Self {
+        Self {
+            config,
+            footnotes: Vec::new(),
+        }
+    }
+
+    /// Render the text of an element, keeping link targets in place
+    pub fn render(&mut self, element: &ElementRef) -> String {
+        self.render_filtered(element, "", |_, _| true)
+    }
+
+    /// Render the text nodes of an element accepted by `keep`, which is given the
+    /// parent element of each text node, joined by `separator`
+    pub fn render_filtered<F>(&mut self, element: &ElementRef, separator: &str, keep: F) -> String
+    where
+        F: Fn(&ElementRef, &str) -> bool,
+    {
+        let mut text = String::new();
+        let mut has_text = false;
+        // Element whose content is being skipped, as it is never text
+        let mut non_text: Option<NodeId> = None;
+
+        for edge in element.traverse() {
+            match edge {
+                Edge::Open(_) if non_text.is_some() => {}
+                Edge::Close(node) if non_text.is_some() => {
+                    if non_text == Some(node.id()) {
+                        non_text = None;
+                    }
+                }
+                Edge::Open(node) if node.value().as_element().is_some_and(|el| NON_TEXT_ELEMENTS.contains(&el.name())) => {
+                    non_text = Some(node.id());
+                }
+                Edge::Open(node) => {
+                    if let Node::Text(t) = node.value() {
+                        let parent = node.parent().and_then(ElementRef::wrap);
+                        if parent.is_some_and(|parent| keep(&parent, t)) {
+                            if has_text {
+                                text.push_str(separator);
+                            }
+                            text.push_str(t);
+                            has_text = true;
+                        }
+                    } else if self.config.rendered_link_style() == LinkStyle::Markdown {
+                        let is_link = ElementRef::wrap(node)
+                            .and_then(|link| self.link_target(&link))
+                            .is_some();
+                        if is_link {
+                            text.push('[');
+                        }
+                    }
+                }
+                Edge::Close(node) => {
+                    if let Some(href) = ElementRef::wrap(node).and_then(|link| self.link_target(&link)) {
+                        self.push_link_suffix(&mut text, href);
+                    }
+                }
+            }
+        }
+
+        text
+    }
+
+    /// The link targets collected for footnote-style rendering, in marker order
+    pub fn into_footnotes(self) -> Vec<String> {
+        self.footnotes
+    }
+
+    /// Return the target of a link element that should be rendered
+    fn link_target<'b>(&self, link: &ElementRef<'b>) -> Option<&'b str> {
+        if !self.config.include_links || self.config.rendered_link_style() == LinkStyle::Stripped {
+            return None;
+        }
+
+        if link.value().name() != "a" {
+            return None;
+        }
+
+        is_content_link(link)
+    }
+
+    fn push_link_suffix(&mut self, text: &mut String, href: &str) {
+        match self.config.rendered_link_style() {
+            LinkStyle::Stripped => {}
+            LinkStyle::Parenthetical => {
+                text.push_str(&format!(" ({})", href));
+            }
+            LinkStyle::Markdown => {
+                text.push_str(&format!("]({})", href));
+            }
+            LinkStyle::Footnote => {
+                // Reuse the marker for links pointing to the same target
+                let index = match self.footnotes.iter().position(|f| f == href) {
+                    Some(index) => index,
+                    None => {
+                        self.footnotes.push(href.to_string());
+                        self.footnotes.len() - 1
+                    }
+                };
+                text.push_str(&format!("[{}]", index + 1));
+            }
+        }
+    }
+}
+
+/// Return the href of a link that points to content worth keeping in the output
+fn is_content_link<'a>(link: &ElementRef<'a>) -> Option<&'a str> {
+    // Skip navigation/sharing links
+    if has_class_hint(link, &["nav", "menu", "social", "share", "tag", "author", "byline", "timestamp"]) {
+        return None;
+    }
+
+    let href = link.value().attr("href")?.trim();
+    if href.is_empty() || href.starts_with('#') || href.starts_with("javascript:") {
+        return None;
+    }
+
+    // Skip links to common news site patterns or social media
+    if href.contains("/tag/") ||
+       href.contains("/tags/") ||
+       href.contains("/topic/") ||
+       href.contains("/topics/") ||
+       href.contains("/author/") ||
+       href.contains("/authors/") ||
+       href.contains("/category/") ||
+       href.contains("/categories/") ||
+       href.contains("facebook.com") ||
+       href.contains("twitter.com") ||
+       href.contains("linkedin.com") ||
+       href.contains("instagram.com") ||
+       href.contains("youtube.com") ||
+       href.contains("mailto:") {
+        return None;
+    }
+
+    // Only include links that have meaningful text
+    let link_text = link.text().collect::<String>();
+    if link_text.trim().is_empty() ||
+       text_length(&link_text) <= 3 ||
+       link_text.contains("Read more") ||
+       link_text.contains("More") ||
+       link_text.contains("Also") {
+        return None;
+    }
+
+    Some(href)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scraper::Html;
+
+    fn render(html: &str, config: &ExtractionConfig) -> Document {
+        let document = Html::parse_document(html);
+        let body = document.select(&Selector::parse("body").unwrap()).next().unwrap();
+        let mut renderer = ContentRenderer::new(config);
+        renderer.add(&body, &|element| has_class_hint(element, &["skip"]));
+        renderer.finish()
+    }
+
+    #[test]
+    fn test_inline_renderer() {
+        let html = r#"<p>See <a href="https://example.com/a">the docs</a> for details.</p>"#;
+        let document = Html::parse_fragment(html);
+        let p = document.select(&Selector::parse("p").unwrap()).next().unwrap();
+
+        let config = ExtractionConfig::default();
+        assert_eq!(InlineRenderer::new(&config).render(&p), "See the docs (https://example.com/a) for details.");
+
+        let config = ExtractionConfig { include_links: false, ..Default::default() };
+        assert_eq!(InlineRenderer::new(&config).render(&p), "See the docs for details.");
+    }
+
+    #[test]
+    fn test_link_styles() {
+        let html = r#"<p>Read <a href="https://a.example/">first source</a><a href="https://b.example/">second source</a> and <a href="https://a.example/">first source</a>.</p>"#;
+        let document = Html::parse_fragment(html);
+        let p = document.select(&Selector::parse("p").unwrap()).next().unwrap();
+
+        let render = |link_style| {
+            let config = ExtractionConfig { link_style, ..Default::default() };
+            let mut renderer = InlineRenderer::new(&config);
+            let text = renderer.render(&p);
+            let document = Document { elements: vec![Element::Paragraph(text)], footnotes: renderer.into_footnotes(), ..Default::default() };
+            document.to_text(config.image_style)
+        };
+
+        assert_eq!(render(LinkStyle::Stripped), "Read first sourcesecond source and first source.");
+        assert_eq!(
+            render(LinkStyle::Parenthetical),
+            "Read first source (https://a.example/)second source (https://b.example/) and first source (https://a.example/)."
+        );
+        assert_eq!(
+            render(LinkStyle::Markdown),
+            "Read [first source](https://a.example/)[second source](https://b.example/) and [first source](https://a.example/)."
+        );
+        assert_eq!(
+            render(LinkStyle::Footnote),
+            "Read first source[1]second source[2] and first source[1].\n\n[1] https://a.example/\n[2] https://b.example/"
+        );
+    }
+
+    #[test]
+    fn test_is_reference_section() {
+        assert!(is_reference_section("References"));
+        assert!(is_reference_section("External links"));
+        assert!(is_reference_section("See also"));
+        assert!(!is_reference_section("Introduction"));
+        assert!(!is_reference_section("Main content"));
+    }
+
+    #[test]
+    fn test_content_renderer_blocks() {
+        let html = r#"<html><body><h2>Harbour  news</h2>
+            <div><p>The pier <a href="https://example.com/pier">reopened</a> on Monday.</p>
+            <img src="https://example.com/pier.jpg" alt="The pier"></div>
+            <ul><li>Boats <ul><li>Ferries</li></ul></li><li class="skip">Hidden item</li></ul>
+            <table><tr><th>Day</th><th>Berths</th></tr><tr><td>Monday</td><td>12</td></tr></table>
+            <table><tr><td><p>A layout table holding a paragraph.</p></td></tr></table>
+            <p class="skip">Excluded paragraph.</p>
+        </body></html>"#;
+        let config = ExtractionConfig { include_images: true, link_style: LinkStyle::Footnote, ..Default::default() };
+        let document = render(html, &config);
+        assert_eq!(document.elements[0], Element::Heading { level: 2, text: "Harbour news".into() });
+        assert_eq!(document.elements[3], Element::List { ordered: false, items: vec!["Boats".into(), "Ferries".into()] });
+        assert_eq!(document.footnotes, ["https://example.com/pier"]);
+        assert_eq!(
+            document.to_text(config.image_style),
+            "Harbour news\n\nThe pier reopened[1] on Monday.\n\n[Image: The pier]\n\n• Boats\n• Ferries\n\n\
+            [Table: Day | Berths\nMonday | 12]\n\nA layout table holding a paragraph.\n\n[1] https://example.com/pier"
+        );
+        assert!(document.tables.is_empty());
+
+        let config = ExtractionConfig { separate_tables: true, ..Default::default() };
+        let document = render(html, &config);
+        assert_eq!(document.elements[3], Element::TableRef(1));
+        assert_eq!(document.tables.len(), 1);
+
+        let config = ExtractionConfig { include_tables: false, ..Default::default() };
+        let text = render(html, &config).to_text(config.image_style);
+        assert!(!text.contains("Berths"));
+        assert!(text.contains("A layout table holding a paragraph."));
+
+        // Elements without blocks are rendered as a paragraph of their text
+        let document = render("<html><body><div>Loose <b>text</b><script>var x;</script></div></body></html>", &config);
+        assert_eq!(document.elements, [Element::Paragraph("Loose text".into())]);
+    }
+
+    #[test]
+    fn test_content_renderer_options() {
+        let html = r#"<html><body><p>Short one.</p><p>A paragraph that is long enough to keep.</p>
+            <h2>See also</h2><p>A paragraph in a reference section.</p>
+            <h2>History</h2><p>Published on Monday.</p><p>Related articles</p><p>After the marker.</p>
+        </body></html>"#;
+        let config = ExtractionConfig::default();
+        let document = Html::parse_document(html);
+        let body = document.select(&Selector::parse("body").unwrap()).next().unwrap();
+
+        let mut renderer = ContentRenderer::new(&config).min_paragraph_length(10).skip_boilerplate().skip_reference_sections();
+        renderer.add(&body, &|_| false);
+        assert_eq!(renderer.finish().to_text(config.image_style), "A paragraph that is long enough to keep.\n\nHistory");
+
+        let mut renderer = ContentRenderer::new(&config);
+        renderer.add(&body, &|_| false);
+        assert!(renderer.finish().to_text(config.image_style).contains("Short one.\n\nA paragraph that is long"));
+    }
+}
@@ -0,0 +1,133 @@
+//! HTTP extraction service for Trafilatura Rust port.
+//! This module exposes extraction over a small HTTP API so other services can
+//! use the extractor without linking the crate:
+//!
+//! - `POST /extract` with an HTML body extracts that document
+//! - `GET /extract?url=...` fetches and extracts a URL
+//! - `GET /health` reports that the service is up
+//!
+//! Results are returned as JSON objects.
+
+use std::time::Duration;
+
+use tiny_http::{Header, Method, Request, Response, Server};
+use tracing::{info, info_span, warn};
+use url::Url;
+
+use crate::fetch::{Fetcher, HttpFetcher};
+use crate::output::format_result;
+use crate::shutdown::ShutdownSignal;
+use crate::{extract_html, extract_url_with, ExtractionConfig, OutputFormat, TrafilaturaError};
+
+/// How often the server checks for a shutdown request while idle
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Serve extraction requests on `address` until `shutdown` is requested
+pub fn serve(address: &str, config: &ExtractionConfig, shutdown: &ShutdownSignal) -> Result<(), TrafilaturaError> {
+    let server = Server::http(address)
+        .map_err(|e| TrafilaturaError::ExtractionError(format!("Cannot listen on {}: {}", address, e)))?;
+    info!("Listening on http://{}", address);
+
+    // Responses are always JSON
+    let config = ExtractionConfig {
+        output_format: OutputFormat::Json,
+        ..config.clone()
+    };
+    // One fetcher for all requests, so per-domain delays and limits hold across them
+    let fetcher = HttpFetcher::new(&config)?;
+
+    while !shutdown.is_requested() {
+        let request = match server.recv_timeout(SHUTDOWN_POLL_INTERVAL)? {
+            Some(request) => request,
+            None => continue,
+        };
+
+        if let Err(e) = handle(request, &config, &fetcher) {
+            warn!("Failed to answer request: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+fn handle(mut request: Request, config: &ExtractionConfig, fetcher: &dyn Fetcher) -> Result<(), TrafilaturaError> {
+    let _span = info_span!("request", method = %request.method(), path = %request.url()).entered();
+    let (path, query) = match request.url().split_once('?') {
+        Some((path, query)) => (path.to_string(), query.to_string()),
+        None => (request.url().to_string(), String::new()),
+    };
+
+    let (status, body) = match (request.method(), path.as_str()) {
+        (Method::Get, "/health") => (200, r#"{"status":"ok"}"#.to_string()),
+        (Method::Post, "/extract") => {
+            let mut html = String::new();
+            request.as_reader().read_to_string(&mut html)?;
+            respond(extract_html(&html, config), config)
+        },
+        (Method::Get, "/extract") => match query_param(&query, "url") {
+            Some(url) => respond(extract_url_with(&url, config, fetcher), config),
+            None => (400, error_body("Missing url parameter")),
+        },
+        (_, "/extract") | (_, "/health") => (405, error_body("Method not allowed")),
+        _ => (404, error_body("Not found")),
+    };
+
+    let header = Header::from_bytes("Content-Type", "application/json").unwrap();
+    request.respond(Response::from_string(body).with_status_code(status).with_header(header))?;
+    Ok(())
+}
+
+/// Turn an extraction outcome into a status code and JSON body
+fn respond(result: Result<crate::ExtractionResult, TrafilaturaError>, config: &ExtractionConfig) -> (u16, String) {
+    let formatted = result.and_then(|result| format_result(&result, config));
+    match formatted {
+        Ok(body) => (200, body),
+        Err(e @ (TrafilaturaError::ExtractionError(_) | TrafilaturaError::PartialExtraction(_))) => (422, error_body(&e.to_string())),
+        Err(e @ TrafilaturaError::BlockedAddress(..)) => (403, error_body(&e.to_string())),
+        Err(e @ (TrafilaturaError::RequestError(_) | TrafilaturaError::StatusError(..) | TrafilaturaError::BodyError(..) | TrafilaturaError::UrlError(_))) => (502, error_body(&e.to_string())),
+        Err(e) => (500, error_body(&e.to_string())),
+    }
+}
+
+fn error_body(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+fn query_param(query: &str, name: &str) -> Option<String> {
+    let url = Url::parse(&format!("http://localhost/?{}", query)).ok()?;
+    let value = url.query_pairs()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.into_owned());
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_param() {
+        assert_eq!(
+            query_param("url=https%3A%2F%2Fexample.com%2Fa%3Fb%3D1&x=2", "url").as_deref(),
+            Some("https://example.com/a?b=1")
+        );
+        assert_eq!(query_param("x=2", "url"), None);
+    }
+
+    #[test]
+    fn test_respond_status_codes() {
+        let config = ExtractionConfig { output_format: OutputFormat::Json, ..Default::default() };
+
+        let (status, body) = respond(Err(TrafilaturaError::ExtractionError("too short".into())), &config);
+        assert_eq!(status, 422);
+        assert!(body.contains("too short"));
+        
+        let (status, _) = respond(Err(TrafilaturaError::BlockedAddress("localhost".into(), [127, 0, 0, 1].into())), &config);
+        assert_eq!(status, 403);
+
+        let result = crate::ExtractionResult { content: "text".into(), ..Default::default() };
+        let (status, body) = respond(Ok(result), &config);
+        assert_eq!(status, 200);
+        assert!(body.contains("\"content\": \"text\""));
+    }
+}
@@ -0,0 +1,264 @@
+//! Settings files for Trafilatura Rust port.
+//! Extraction options can be kept in a file like Python trafilatura's `settings.cfg`,
+//! made of `key = value` lines in INI or simple TOML syntax:
+//!
+//! ```ini
+//! [DEFAULT]
+//! MIN_EXTRACTED_SIZE = 200
+//! EXTRACTION_TIMEOUT = 10
+//! user_agent = "harbour-bot/1.0 (+https://example.com/bot)"
+//! unwanted_classes = promo-box, newsletter-signup
+//! content_xpaths =
+//!     //div[@id="story"]
+//!     (//article)[1]
+//! ```
+//!
+//! Keys are the names of `ExtractionConfig` fields or the upper-case names of
+//! `settings.cfg`; the options of `settings.cfg` this port has no equivalent for
+//! are refused, so a file is never half applied. Keys before the first section and in the `DEFAULT` and `extraction`
+//! sections are read; other sections are left to other tools. Lists take one item
+//! per line, or a TOML array; items of name lists may also be separated by commas.
+
+use std::fs;
+use std::path::Path;
+
+use crate::politeness::DomainPolicy;
+use crate::xpath_expr::XPathExpr;
+use crate::{ExtractionConfig, TrafilaturaError};
+
+/// Sections whose keys are read
+const SECTIONS: [&str; 2] = ["DEFAULT", "extraction"];
+
+/// Keys of Python trafilatura's `settings.cfg` without an equivalent here
+const PYTHON_ONLY_KEYS: [&str; 8] = [
+    "cookie", "max_redirects", "min_file_size", "min_extracted_comm_size",
+    "min_output_size", "min_output_comm_size", "extensive_date_search", "external_urls",
+];
+
+/// Options read from a settings file, in file order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Settings {
+    /// Keys, lowercased with `-` as `_`, and their raw values
+    values: Vec<(String, String)>,
+}
+
+impl Settings {
+    /// Read a settings file
+    pub fn from_file(path: &Path) -> Result<Self, TrafilaturaError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parse the text of a settings file. Whole-line comments start with `#` or `;`,
+    /// and indented lines continue the value of the key before them.
+    pub fn parse(text: &str) -> Result<Self, TrafilaturaError> {
+        let invalid = |number: usize, message: &str| {
+            Err(TrafilaturaError::ConfigError(format!("line {} of settings file: {}", number + 1, message)))
+        };
+
+        // Values of every section, with whether the section is read
+        let mut values: Vec<(bool, String, String)> = Vec::new();
+        let mut in_read_section = true;
+        let mut after_key = false;
+        let mut lines = text.lines().enumerate();
+        while let Some((number, line)) = lines.next() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with(['#', ';']) {
+                continue;
+            }
+            let indented = line.starts_with(char::is_whitespace);
+            if indented && after_key {
+                if let Some((_, _, value)) = values.last_mut() {
+                    value.push('\n');
+                    value.push_str(trimmed);
+                }
+                continue;
+            }
+            if let Some(section) = trimmed.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+                in_read_section = SECTIONS.iter().any(|read| section.trim().eq_ignore_ascii_case(read));
+                after_key = false;
+                continue;
+            }
+
+            let Some(separator) = trimmed.find(['=', ':']) else {
+                return invalid(number, "expected `key = value`");
+            };
+            let key = trimmed[..separator].trim().to_lowercase().replace('-', "_");
+            let mut value = trimmed[separator + 1..].trim().to_string();
+            if key.is_empty() {
+                return invalid(number, "missing key");
+            }
+            // TOML arrays may span lines
+            if value.starts_with('[') {
+                while !value.ends_with(']') {
+                    let Some((_, next)) = lines.next() else {
+                        return invalid(number, "unclosed array");
+                    };
+                    value.push('\n');
+                    value.push_str(next.trim());
+                }
+            }
+            values.push((in_read_section, key, value));
+            after_key = true;
+        }
+
+        Ok(Settings {
+            values: values.into_iter().filter(|(read, _, _)| *read).map(|(_, key, value)| (key, value)).collect(),
+        })
+    }
+
+    /// Override the options of a config with the settings. Name and rule lists are
+    /// added to those of the config, and empty user agents leave the config's own.
+    pub fn apply(&self, mut config: ExtractionConfig) -> Result<ExtractionConfig, TrafilaturaError> {
+        for (key, value) in &self.values {
+            match key.as_str() {
+                "min_extracted_size" => config.min_extracted_size = number(key, value)?,
+                "extraction_timeout" | "download_timeout" => config.extraction_timeout = number(key, value)?,
+                "max_body_size" | "max_file_size" => config.max_body_size = number(key, value)?,
+                "min_duplcheck_size" => config.dedup_options.min_size = number(key, value)?,
+                "max_repetitions" => config.dedup_options.max_repetitions = number(key, value)?,
+                // Seconds between two requests to the same host, as the crawl delay of
+                // hosts without a politeness rule of their own
+                "sleep_time" => {
+                    let seconds: f64 = number(key, value)?;
+                    if !(seconds.is_finite() && seconds >= 0.0) {
+                        return Err(TrafilaturaError::ConfigError(format!("invalid number {:?} for setting {}", value, key)));
+                    }
+                    let policy = DomainPolicy { crawl_delay_ms: Some((seconds * 1000.0).round() as u64), ..Default::default() };
+                    config.politeness.set_fallback(policy);
+                },
+                // Python trafilatura picks one of its user agents at random; the first is used here
+                "user_agent" | "user_agents" => {
+                    if let Some(user_agent) = items(value, false).into_iter().next() {
+                        config.user_agent = user_agent;
+                    }
+                },
+                "unwanted_classes" => config.unwanted_classes.extend(items(value, true)),
+                "unwanted_ids" => config.unwanted_ids.extend(items(value, true)),
+                "strip_elements" => config.strip_elements.extend(items(value, true)),
+                "keep_elements" => config.keep_elements.extend(items(value, true)),
+                "content_xpaths" => {
+                    for rule in items(value, false) {
+                        config.content_xpaths.push(XPathExpr::parse(&rule)?);
+                    }
+                },
+                key if PYTHON_ONLY_KEYS.contains(&key) => {
+                    return Err(TrafilaturaError::ConfigError(format!("setting {} is not supported, remove it", key)));
+                },
+                _ => return Err(TrafilaturaError::ConfigError(format!("unknown setting {}", key))),
+            }
+        }
+        Ok(config)
+    }
+}
+
+impl ExtractionConfig {
+    /// Load a config from a settings file, with the defaults for the options it does
+    /// not set; see the `settings` module for the format
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, TrafilaturaError> {
+        let config = Settings::from_file(path.as_ref())?.apply(Self::default())?;
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// Parse a number value
+fn number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, TrafilaturaError> {
+    unquote(value).parse()
+        .map_err(|_| TrafilaturaError::ConfigError(format!("invalid number {:?} for setting {}", value, key)))
+}
+
+/// Items of a list value: a TOML array, or items on their own lines and, with
+/// `commas`, separated by commas. Separators inside quotes do not count.
+fn items(value: &str, commas: bool) -> Vec<String> {
+    let (value, commas) = match value.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+        Some(array) => (array, true),
+        None => (value, commas),
+    };
+
+    let mut items = Vec::new();
+    let mut item = String::new();
+    let mut quote = None;
+    for c in value.chars() {
+        match quote {
+            None if c == '\n' || (commas && c == ',') => {
+                items.push(std::mem::take(&mut item));
+                continue;
+            },
+            None if c == '"' || c == '\'' => quote = Some(c),
+            Some(open) if c == open => quote = None,
+            _ => {},
+        }
+        item.push(c);
+    }
+    items.push(item);
+
+    items.iter()
+        .map(|item| unquote(item.trim()).to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// A value without the quotes around it, if any
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    ['"', '\''].iter()
+        .find_map(|&quote| value.strip_prefix(quote).and_then(|rest| rest.strip_suffix(quote)))
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_settings() {
+        let text = r#"
+# Python trafilatura settings.cfg
+[DEFAULT]
+DOWNLOAD_TIMEOUT = 10
+MIN_EXTRACTED_SIZE = 200
+MAX_FILE_SIZE = 5000000
+SLEEP_TIME = 2.5
+MIN_DUPLCHECK_SIZE = 50
+MAX_REPETITIONS = 3
+USER_AGENTS =
+    harbour-bot/1.0 (compatible; +https://example.com/bot)
+    harbour-bot/0.9
+unwanted-classes = promo-box, "newsletter, signup"
+content_xpaths = [
+    "//div[contains(@class, 'story')]",
+    '(//article)[1]',
+]
+
+[politeness]
+unwanted_ids = skipped
+"#;
+        let config = Settings::parse(text).unwrap().apply(ExtractionConfig::default()).unwrap();
+
+        assert_eq!(config.extraction_timeout, 10);
+        assert_eq!(config.min_extracted_size, 200);
+        assert_eq!(config.max_body_size, 5_000_000);
+        assert_eq!(config.politeness.for_host("example.com").and_then(|policy| policy.crawl_delay_ms), Some(2500));
+        assert_eq!((config.dedup_options.min_size, config.dedup_options.max_repetitions), (50, 3));
+        assert_eq!(config.user_agent, "harbour-bot/1.0 (compatible; +https://example.com/bot)");
+        assert_eq!(config.unwanted_classes, ["promo-box", "newsletter, signup"]);
+        assert!(config.unwanted_ids.is_empty());
+        let rules: Vec<_> = config.content_xpaths.iter().map(XPathExpr::as_str).collect();
+        assert_eq!(rules, ["//div[contains(@class, 'story')]", "(//article)[1]"]);
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        let apply = |text: &str| Settings::parse(text).and_then(|settings| settings.apply(ExtractionConfig::default()));
+
+        for text in ["min_extracted_size", "min_extracted_size = many", "color = blue", "strip_elements = [\"mark\"", "SLEEP_TIME = -1"] {
+            assert!(matches!(apply(text), Err(TrafilaturaError::ConfigError(_))), "{}", text);
+        }
+        // Options of Python trafilatura this port does not have are refused
+        for text in ["MIN_OUTPUT_SIZE = 1", "COOKIE = session=1", "EXTERNAL_URLS = off"] {
+            assert!(matches!(apply(text), Err(TrafilaturaError::ConfigError(message)) if message.contains("not supported")), "{}", text);
+        }
+        // Python's own settings.cfg leaves the user agents empty
+        assert_eq!(apply("USER_AGENTS =").unwrap().user_agent, ExtractionConfig::default().user_agent);
+    }
+}
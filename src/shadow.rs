@@ -0,0 +1,200 @@
+//! Shadow DOM support for Trafilatura Rust port.
+//! Web components deliver their markup as declarative shadow roots
+//! (`<template shadowrootmode="open">`), and some pages park content in plain
+//! `<template>` elements. The parser keeps template contents in a separate
+//! fragment, which selectors and the re-parsing XPath strategy treat
+//! inconsistently. This module moves that content into the light DOM the way a
+//! browser renders it.
+
+use ego_tree::NodeId;
+use lazy_static::lazy_static;
+use regex::Regex;
+use scraper::{ElementRef, Html, Node};
+
+lazy_static! {
+    /// Placeholders of client-side templates, e.g. `{{ title }}` or `${price}`
+    static ref PLACEHOLDER_RE: Regex = Regex::new(r"\{\{.*?\}\}|\$\{.*?\}").unwrap();
+}
+
+/// Flatten declarative shadow roots and unwrap plain templates, returning how many
+/// templates were processed.
+///
+/// A shadow root replaces the children of its host: each `<slot>` is replaced by the
+/// host children assigned to it (by their `slot` attribute, or unnamed children for
+/// the default slot) or by its fallback content, and unassigned host children are
+/// dropped. Plain templates are unwrapped in place, except client-side templates
+/// with placeholders, which are dropped.
+pub fn hoist_templates(document: &mut Html) -> usize {
+    let mut count = 0;
+    // Templates inside a template become reachable once the outer one is hoisted
+    while let Some(template) = next_template(document) {
+        let value = template.value();
+        let shadow_root = value.attr("shadowrootmode").or_else(|| value.attr("shadowroot")).is_some();
+        let host = template.parent().filter(|parent| parent.value().is_element()).map(|parent| parent.id());
+        let template = template.id();
+        match host {
+            Some(host) if shadow_root => attach_shadow_root(document, host, template),
+            _ => unwrap_template(document, template),
+        }
+        count += 1;
+    }
+    count
+}
+
+fn next_template(document: &Html) -> Option<ElementRef<'_>> {
+    document.tree.root().descendants()
+        .filter_map(ElementRef::wrap)
+        .find(|element| element.value().name() == "template")
+}
+
+/// Children of the fragment holding the contents of a template
+fn template_contents(document: &Html, template: NodeId) -> Vec<NodeId> {
+    document.tree.get(template)
+        .into_iter()
+        .flat_map(|template| template.children())
+        .filter(|child| child.value().is_fragment())
+        .flat_map(|fragment| fragment.children())
+        .map(|child| child.id())
+        .collect()
+}
+
+/// Replace the children of `host` with the contents of its shadow root, with the
+/// light children distributed over the slots
+fn attach_shadow_root(document: &mut Html, host: NodeId, template: NodeId) {
+    let contents = template_contents(document, template);
+    let light: Vec<NodeId> = document.tree.get(host)
+        .into_iter()
+        .flat_map(|host| host.children())
+        .filter(|child| child.id() != template)
+        .map(|child| child.id())
+        .collect();
+
+    // Slots of nested shadow roots belong to their own hosts
+    let slots: Vec<(NodeId, String)> = contents.iter()
+        .filter_map(|id| document.tree.get(*id))
+        .flat_map(|node| node.descendants())
+        .filter_map(ElementRef::wrap)
+        .filter(|element| element.value().name() == "slot")
+        .filter(|slot| {
+            !slot.ancestors()
+                .take_while(|ancestor| ancestor.id() != template)
+                .filter_map(ElementRef::wrap)
+                .any(|ancestor| ancestor.value().name() == "template")
+        })
+        .map(|slot| (slot.id(), slot.value().attr("name").unwrap_or_default().to_string()))
+        .collect();
+
+    let mut assigned: Vec<NodeId> = Vec::new();
+    for (slot, name) in &slots {
+        let nodes: Vec<NodeId> = light.iter()
+            .copied()
+            .filter(|id| !assigned.contains(id) && slot_name(document, *id) == name.as_str())
+            .collect();
+        assigned.extend(&nodes);
+        // Without assigned nodes a slot shows its fallback content
+        let replacement = if nodes.is_empty() {
+            document.tree.get(*slot).map(|slot| slot.children().map(|child| child.id()).collect()).unwrap_or_default()
+        } else {
+            nodes
+        };
+        if let Some(mut slot) = document.tree.get_mut(*slot) {
+            for id in replacement {
+                slot.insert_id_before(id);
+            }
+            slot.detach();
+        }
+    }
+
+    for id in light.into_iter().filter(|id| !assigned.contains(id)) {
+        if let Some(mut node) = document.tree.get_mut(id) {
+            node.detach();
+        }
+    }
+    // Slots at the top level of the shadow root were replaced in the meantime
+    let contents = template_contents(document, template);
+    if let Some(mut host) = document.tree.get_mut(host) {
+        for id in contents {
+            host.append_id(id);
+        }
+    }
+    if let Some(mut template) = document.tree.get_mut(template) {
+        template.detach();
+    }
+}
+
+/// Name of the slot a light child is assigned to; text goes to the default slot
+fn slot_name(document: &Html, id: NodeId) -> &str {
+    document.tree.get(id)
+        .and_then(|node| node.value().as_element())
+        .and_then(|element| element.attr("slot"))
+        .unwrap_or_default()
+}
+
+/// Move the contents of a plain template in its place, unless it is a client-side
+/// template with placeholders
+fn unwrap_template(document: &mut Html, template: NodeId) {
+    let contents = template_contents(document, template);
+    let is_client_side = contents.iter()
+        .filter_map(|id| document.tree.get(*id))
+        .flat_map(|node| node.descendants())
+        .any(|node| match node.value() {
+            Node::Text(text) => PLACEHOLDER_RE.is_match(text),
+            _ => false,
+        });
+
+    let Some(mut node) = document.tree.get_mut(template) else {
+        return;
+    };
+    if !is_client_side {
+        for id in contents {
+            node.insert_id_before(id);
+        }
+    }
+    node.detach();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html::descendants_named;
+
+    fn paragraphs(document: &Html) -> Vec<String> {
+        descendants_named(document.root_element(), &["p"])
+            .map(|p| p.text().collect::<String>())
+            .collect()
+    }
+
+    #[test]
+    fn test_hoist_templates() {
+        let html = r#"<html><body><article>
+            <news-card>
+              <template shadowrootmode="open">
+                <h2><slot name="title">Untitled</slot></h2>
+                <p>Shadow text.</p>
+                <slot></slot>
+                <footer><slot name="byline">Staff</slot></footer>
+              </template>
+              <span slot="title">Harbour reopens</span>
+              <p>Slotted text.</p>
+              <p slot="unknown">Unassigned text.</p>
+            </news-card>
+            <template><p>Deferred text.</p></template>
+            <template id="row"><p>{{ name }}</p></template>
+            </article></body></html>"#;
+        let mut document = Html::parse_document(html);
+
+        assert_eq!(hoist_templates(&mut document), 3);
+
+        assert_eq!(paragraphs(&document), vec!["Shadow text.", "Slotted text.", "Deferred text."]);
+        let root = document.root_element();
+        let heading = descendants_named(root, &["h2"]).next().unwrap();
+        assert_eq!(heading.text().collect::<String>(), "Harbour reopens");
+        let footer = descendants_named(root, &["footer"]).next().unwrap();
+        assert_eq!(footer.text().collect::<String>(), "Staff");
+        assert!(descendants_named(root, &["template", "slot"]).next().is_none());
+
+        // The flattened markup survives a round trip through the parser
+        let reparsed = Html::parse_document(&document.html());
+        assert_eq!(paragraphs(&reparsed), paragraphs(&document));
+    }
+}
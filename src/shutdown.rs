@@ -0,0 +1,66 @@
+//! Graceful shutdown for Trafilatura Rust port.
+//! This module turns SIGINT/SIGTERM into a flag that long-running modes check
+//! between documents, so in-flight work finishes and output is flushed before exit.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use tracing::info;
+
+use crate::TrafilaturaError;
+
+/// Shared flag raised when the process is asked to stop
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownSignal {
+    requested: Arc<AtomicBool>,
+}
+
+impl ShutdownSignal {
+    /// Create a signal that is only raised through `request`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a signal raised on SIGINT/SIGTERM. A second signal exits immediately.
+    pub fn install() -> Result<Self, TrafilaturaError> {
+        let signal = Self::new();
+        let handler_signal = signal.clone();
+
+        ctrlc::set_handler(move || {
+            if handler_signal.is_requested() {
+                std::process::exit(130);
+            }
+            info!("Shutdown requested, finishing in-flight documents");
+            handler_signal.request();
+        })
+        .map_err(|e| TrafilaturaError::ExtractionError(format!("Cannot install signal handler: {}", e)))?;
+
+        Ok(signal)
+    }
+
+    /// Ask long-running loops to stop after the current document
+    pub fn request(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+    }
+
+    /// Check if shutdown has been requested
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shutdown_signal_is_shared() {
+        let signal = ShutdownSignal::new();
+        let clone = signal.clone();
+        assert!(!signal.is_requested());
+
+        clone.request();
+
+        assert!(signal.is_requested());
+    }
+}
@@ -0,0 +1,150 @@
+//! Learned per-site rules for Trafilatura Rust port.
+//! Pages of one site share a template, so the same element tends to win the
+//! container search on every page. This module remembers the CSS path of the
+//! winning container of each host; once one path has won `min_pages` times in a
+//! row it becomes the rule of the host, and later pages are extracted from the
+//! element it selects without running the strategies. A rule that stops finding
+//! enough content falls back to the full search, and is forgotten after
+//! `max_failures` failures in a row so the site can be learned again.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use scraper::Selector;
+
+/// Settings of a site rule cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SiteRuleOptions {
+    /// Wins in a row of the same container before it becomes the rule of a host
+    pub min_pages: usize,
+    /// Failures in a row after which a rule is forgotten
+    pub max_failures: usize,
+    /// Hosts tracked at once; further hosts are extracted without learning
+    pub max_sites: usize,
+}
+
+impl Default for SiteRuleOptions {
+    fn default() -> Self {
+        Self {
+            min_pages: 3,
+            max_failures: 2,
+            max_sites: 10_000,
+        }
+    }
+}
+
+/// A rule learned for a host
+#[derive(Debug, Clone)]
+pub struct SiteRule {
+    /// CSS path of the container, as in `ExtractionResult::container_path`
+    pub path: String,
+    pub selector: Selector,
+}
+
+#[derive(Debug, Default)]
+struct SiteState {
+    /// Path of the last winning container and its wins in a row
+    streak: Option<(String, usize)>,
+    rule: Option<SiteRule>,
+    /// Failures in a row of the rule
+    failures: usize,
+}
+
+/// Rules learned from the extractions of a batch, crawl, or server, by host;
+/// clones of the config share the cache
+#[derive(Debug)]
+pub struct SiteRuleCache {
+    options: SiteRuleOptions,
+    sites: Mutex<HashMap<String, SiteState>>,
+}
+
+impl SiteRuleCache {
+    pub fn new(options: SiteRuleOptions) -> Self {
+        Self { options, sites: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn options(&self) -> SiteRuleOptions {
+        self.options
+    }
+
+    /// The rule learned for a host, if any
+    pub fn rule(&self, host: &str) -> Option<SiteRule> {
+        let sites = self.sites.lock().unwrap_or_else(|e| e.into_inner());
+        sites.get(host)?.rule.clone()
+    }
+
+    /// Record the container that won the full search on a page of a host, learning
+    /// it as the rule of the host once it has won `min_pages` times in a row
+    pub fn record_win(&self, host: &str, path: &str) {
+        let mut sites = self.sites.lock().unwrap_or_else(|e| e.into_inner());
+        if !sites.contains_key(host) && sites.len() >= self.options.max_sites {
+            return;
+        }
+        let site = sites.entry(host.to_string()).or_default();
+        let wins = match &site.streak {
+            Some((last, wins)) if last == path => wins + 1,
+            _ => 1,
+        };
+        site.streak = Some((path.to_string(), wins));
+        if wins >= self.options.min_pages && site.rule.is_none() {
+            // Paths are built to parse; one that does not is never learned
+            site.rule = Selector::parse(path).ok().map(|selector| SiteRule { path: path.to_string(), selector });
+            site.failures = 0;
+        }
+    }
+
+    /// Record that the rule of a host found enough content
+    pub fn record_success(&self, host: &str) {
+        let mut sites = self.sites.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(site) = sites.get_mut(host) {
+            site.failures = 0;
+        }
+    }
+
+    /// Record that the rule of a host did not find enough content, forgetting it
+    /// after `max_failures` failures in a row
+    pub fn record_failure(&self, host: &str) {
+        let mut sites = self.sites.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(site) = sites.get_mut(host) {
+            site.failures += 1;
+            if site.failures >= self.options.max_failures.max(1) {
+                *site = SiteState::default();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_site_rule_cache() {
+        let cache = SiteRuleCache::new(SiteRuleOptions { min_pages: 2, max_failures: 2, max_sites: 2 });
+        let path = "body > div#main > article.post";
+
+        // A streak broken by another container starts over
+        cache.record_win("example.com", path);
+        cache.record_win("example.com", "body");
+        cache.record_win("example.com", path);
+        assert!(cache.rule("example.com").is_none());
+        cache.record_win("example.com", path);
+        assert_eq!(cache.rule("example.com").unwrap().path, path);
+        assert!(cache.rule("example.org").is_none());
+
+        // Failures in a row forget the rule, a success in between keeps it
+        cache.record_failure("example.com");
+        cache.record_success("example.com");
+        cache.record_failure("example.com");
+        assert!(cache.rule("example.com").is_some());
+        cache.record_failure("example.com");
+        assert!(cache.rule("example.com").is_none());
+
+        // Hosts beyond `max_sites` are not learned
+        cache.record_win("example.org", path);
+        for _ in 0..2 {
+            cache.record_win("example.net", path);
+        }
+        assert!(cache.rule("example.net").is_none());
+    }
+}
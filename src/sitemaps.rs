@@ -0,0 +1,421 @@
+//! Sitemap handling for Trafilatura Rust port.
+//! This module parses XML and plain-text sitemaps and follows sitemap indexes,
+//! so the URLs of a whole site can be listed or extracted in one run.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::fetch::Fetcher;
+use crate::{fetch_url_with, TrafilaturaError};
+
+lazy_static! {
+    static ref URL_BLOCK: Regex = Regex::new(r"(?s)<url>(.*?)</url>").unwrap();
+    static ref SITEMAP_BLOCK: Regex = Regex::new(r"(?s)<sitemap>(.*?)</sitemap>").unwrap();
+    static ref LOC: Regex = Regex::new(r"(?s)<loc>\s*(?:<!\[CDATA\[)?\s*(.*?)\s*(?:\]\]>)?\s*</loc>").unwrap();
+    static ref LASTMOD: Regex = Regex::new(r"(?s)<lastmod>\s*(.*?)\s*</lastmod>").unwrap();
+}
+
+/// A page listed in a sitemap
+#[derive(Debug, Clone, PartialEq)]
+pub struct SitemapEntry {
+    /// Page URL
+    pub url: String,
+    /// Last modification date as declared in the sitemap
+    pub lastmod: Option<String>,
+}
+
+/// Contents of a single sitemap document
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sitemap {
+    /// Pages listed in the sitemap
+    pub entries: Vec<SitemapEntry>,
+    /// Nested sitemaps listed in a sitemap index, with their last modification dates
+    pub sitemaps: Vec<SitemapEntry>,
+}
+
+/// Last run times of incremental sitemap crawls, stored as a JSON file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SitemapState {
+    last_runs: BTreeMap<String, DateTime<Utc>>,
+}
+
+impl SitemapState {
+    /// Load the state file, or start empty if it does not exist yet
+    pub fn load(path: &Path) -> Result<Self, TrafilaturaError> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let stored: BTreeMap<String, String> = serde_json::from_str(&content)?;
+        let last_runs = stored.into_iter()
+            .filter_map(|(url, time)| Some((url, parse_lastmod(&time)?)))
+            .collect();
+        Ok(Self { last_runs })
+    }
+
+    /// Write the state file
+    pub fn save(&self, path: &Path) -> Result<(), TrafilaturaError> {
+        let stored: BTreeMap<&str, String> = self.last_runs.iter()
+            .map(|(url, time)| (url.as_str(), time.to_rfc3339()))
+            .collect();
+        fs::write(path, serde_json::to_string_pretty(&stored)?)?;
+        Ok(())
+    }
+
+    /// Start time of the last run over a sitemap
+    pub fn last_run(&self, sitemap_url: &str) -> Option<DateTime<Utc>> {
+        self.last_runs.get(sitemap_url).copied()
+    }
+
+    /// Record the start time of a completed run over a sitemap
+    pub fn record_run(&mut self, sitemap_url: &str, started: DateTime<Utc>) {
+        self.last_runs.insert(sitemap_url.to_string(), started);
+    }
+}
+
+/// Parse an XML `urlset`/`sitemapindex` or a plain-text list of URLs
+pub fn parse_sitemap(content: &str, base: &Url) -> Sitemap {
+    let mut sitemap = Sitemap::default();
+
+    if !content.contains("<urlset") && !content.contains("<sitemapindex") {
+        // Plain-text sitemap: one URL per line
+        sitemap.entries = content.lines()
+            .filter_map(|line| resolve(line.trim(), base))
+            .map(|url| SitemapEntry { url, lastmod: None })
+            .collect();
+        return sitemap;
+    }
+
+    for block in URL_BLOCK.captures_iter(content) {
+        let block = &block[1];
+        if let Some(url) = LOC.captures(block).and_then(|loc| resolve(&loc[1], base)) {
+            let lastmod = LASTMOD.captures(block).map(|lastmod| lastmod[1].to_string());
+            sitemap.entries.push(SitemapEntry { url, lastmod });
+        }
+    }
+
+    for block in SITEMAP_BLOCK.captures_iter(content) {
+        let block = &block[1];
+        if let Some(url) = LOC.captures(block).and_then(|loc| resolve(&loc[1], base)) {
+            let lastmod = LASTMOD.captures(block).map(|lastmod| lastmod[1].to_string());
+            sitemap.sitemaps.push(SitemapEntry { url, lastmod });
+        }
+    }
+
+    sitemap
+}
+
+/// Parse a W3C datetime as used by `lastmod`: a date (`2024-05-01`, `2024-05`, `2024`)
+/// or a date and time with a UTC offset, which is assumed to be UTC when missing
+pub fn parse_lastmod(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+    // Seconds are optional in W3C datetimes
+    if let Ok(datetime) = DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M%:z") {
+        return Some(datetime.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S"] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(datetime.and_utc());
+        }
+    }
+
+    let date = match value.len() {
+        4 => NaiveDate::parse_from_str(&format!("{}-01-01", value), "%Y-%m-%d"),
+        7 => NaiveDate::parse_from_str(&format!("{}-01", value), "%Y-%m-%d"),
+        _ => NaiveDate::parse_from_str(value, "%Y-%m-%d"),
+    };
+    date.ok().and_then(|date| date.and_hms_opt(0, 0, 0)).map(|datetime| datetime.and_utc())
+}
+
+/// Check if an entry may have changed after `since`. Entries without a readable
+/// `lastmod` are kept, as nothing tells they are unchanged.
+pub fn is_modified_since(entry: &SitemapEntry, since: DateTime<Utc>) -> bool {
+    entry.lastmod.as_deref()
+        .and_then(parse_lastmod)
+        .is_none_or(|lastmod| lastmod > since)
+}
+
+/// Fetch a sitemap and the sitemaps it references, returning up to `max_urls` pages
+pub fn fetch_sitemap_entries(url: &str, fetcher: &dyn Fetcher, max_urls: usize) -> Result<Vec<SitemapEntry>, TrafilaturaError> {
+    fetch_sitemap_entries_since(url, fetcher, max_urls, None)
+}
+
+/// Fetch a sitemap and the sitemaps it references, returning up to `max_urls` pages
+/// modified after `since`. Nested sitemaps not modified since are not fetched.
+pub fn fetch_sitemap_entries_since(
+    url: &str,
+    fetcher: &dyn Fetcher,
+    max_urls: usize,
+    since: Option<DateTime<Utc>>,
+) -> Result<Vec<SitemapEntry>, TrafilaturaError> {
+    let is_new = |entry: &SitemapEntry| since.is_none_or(|since| is_modified_since(entry, since));
+    let mut pending = vec![Url::parse(url)?.to_string()];
+    let mut seen_sitemaps = HashSet::new();
+    let mut seen_urls = HashSet::new();
+    let mut entries = Vec::new();
+
+    while let Some(sitemap_url) = pending.pop() {
+        if entries.len() >= max_urls || !seen_sitemaps.insert(sitemap_url.clone()) {
+            continue;
+        }
+
+        debug!("Fetching sitemap {}", sitemap_url);
+        let is_root = seen_sitemaps.len() == 1;
+        let content = match fetch_url_with(&sitemap_url, fetcher) {
+            Ok(content) => content,
+            // A broken nested sitemap should not abort the whole listing
+            Err(e) if !is_root => {
+                warn!("Skipping sitemap {}: {}", sitemap_url, e);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        let sitemap = parse_sitemap(&content, &Url::parse(&sitemap_url)?);
+        for entry in sitemap.entries.into_iter().filter(is_new) {
+            if entries.len() < max_urls && seen_urls.insert(entry.url.clone()) {
+                entries.push(entry);
+            }
+        }
+
+        // Keep document order when following nested sitemaps
+        pending.extend(sitemap.sitemaps.into_iter().filter(is_new).map(|entry| entry.url).rev());
+    }
+
+    Ok(entries)
+}
+
+/// URLs a sitemap file may list under the sitemap protocol
+pub const MAX_SITEMAP_URLS: usize = 50_000;
+
+/// Write entries as an XML `urlset`, failing with more than `MAX_SITEMAP_URLS`
+/// (see `write_sitemap_files`)
+pub fn write_sitemap(writer: &mut dyn io::Write, entries: &[SitemapEntry]) -> io::Result<()> {
+    if entries.len() > MAX_SITEMAP_URLS {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} URLs exceed the {} of a sitemap", entries.len(), MAX_SITEMAP_URLS),
+        ));
+    }
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#)?;
+    for entry in entries {
+        write!(writer, "  <url><loc>{}</loc>", html_escape::encode_text(&entry.url))?;
+        if let Some(lastmod) = &entry.lastmod {
+            write!(writer, "<lastmod>{}</lastmod>", html_escape::encode_text(lastmod))?;
+        }
+        writeln!(writer, "</url>")?;
+    }
+    writeln!(writer, "</urlset>")
+}
+
+/// Write locations of sitemaps as an XML `sitemapindex`
+pub fn write_sitemap_index(writer: &mut dyn io::Write, locations: &[String]) -> io::Result<()> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#)?;
+    for location in locations {
+        writeln!(writer, "  <sitemap><loc>{}</loc></sitemap>", html_escape::encode_text(location))?;
+    }
+    writeln!(writer, "</sitemapindex>")
+}
+
+/// Write entries to a sitemap file. Beyond `MAX_SITEMAP_URLS`, the file is a
+/// sitemap index of numbered parts written next to it (`sitemap-1.xml`,
+/// `sitemap-2.xml`, ... for `sitemap.xml`), listed by file name, so they resolve
+/// against the URL the index is published at. Returns the files written.
+pub fn write_sitemap_files(path: &Path, entries: &[SitemapEntry]) -> io::Result<Vec<PathBuf>> {
+    let write = |path: &Path, write: &dyn Fn(&mut dyn io::Write) -> io::Result<()>| {
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+        write(&mut file)?;
+        io::Write::flush(&mut file)
+    };
+    if entries.len() <= MAX_SITEMAP_URLS {
+        write(path, &|file| write_sitemap(file, entries))?;
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().map_or(String::new(), |extension| format!(".{}", extension.to_string_lossy()));
+    let mut parts = Vec::new();
+    for (number, chunk) in entries.chunks(MAX_SITEMAP_URLS).enumerate() {
+        let part = path.with_file_name(format!("{}-{}{}", stem, number + 1, extension));
+        write(&part, &|file| write_sitemap(file, chunk))?;
+        parts.push(part);
+    }
+    let locations: Vec<String> = parts.iter()
+        .map(|part| part.file_name().unwrap_or_default().to_string_lossy().into_owned())
+        .collect();
+    write(path, &|file| write_sitemap_index(file, &locations))?;
+
+    parts.insert(0, path.to_path_buf());
+    Ok(parts)
+}
+
+/// Resolve a sitemap location against the sitemap URL, keeping only HTTP(S) URLs
+fn resolve(location: &str, base: &Url) -> Option<String> {
+    let location = html_escape::decode_html_entities(location);
+    if location.is_empty() {
+        return None;
+    }
+
+    let url = base.join(&location).ok()?;
+    matches!(url.scheme(), "http" | "https").then(|| url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> Url {
+        Url::parse("https://example.com/sitemap.xml").unwrap()
+    }
+
+    #[test]
+    fn test_parse_urlset() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+              <url><loc>https://example.com/a?x=1&amp;y=2</loc><lastmod>2024-05-01</lastmod></url>
+              <url><loc><![CDATA[/b]]></loc></url>
+            </urlset>"#;
+
+        let sitemap = parse_sitemap(xml, &base());
+
+        assert_eq!(sitemap.entries, vec![
+            SitemapEntry { url: "https://example.com/a?x=1&y=2".into(), lastmod: Some("2024-05-01".into()) },
+            SitemapEntry { url: "https://example.com/b".into(), lastmod: None },
+        ]);
+        assert!(sitemap.sitemaps.is_empty());
+    }
+
+    #[test]
+    fn test_parse_sitemap_index() {
+        let xml = r#"<sitemapindex><sitemap><loc>https://example.com/news.xml</loc></sitemap></sitemapindex>"#;
+
+        let sitemap = parse_sitemap(xml, &base());
+
+        assert!(sitemap.entries.is_empty());
+        assert_eq!(sitemap.sitemaps, vec![SitemapEntry { url: "https://example.com/news.xml".into(), lastmod: None }]);
+    }
+
+    #[test]
+    fn test_parse_text_sitemap() {
+        let sitemap = parse_sitemap("https://example.com/a\n\nmailto:x@example.com\n/b\n", &base());
+
+        let urls: Vec<_> = sitemap.entries.iter().map(|entry| entry.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://example.com/a", "https://example.com/b"]);
+    }
+
+    struct MockFetcher;
+
+    impl Fetcher for MockFetcher {
+        fn fetch(&self, url: &Url) -> Result<crate::fetch::FetchedPage, TrafilaturaError> {
+            let (status, body) = match url.path() {
+                "/sitemap.xml" => (200, "<sitemapindex><sitemap><loc>/broken.xml</loc></sitemap>\
+                    <sitemap><loc>/pages.xml</loc><lastmod>2024-05-02</lastmod></sitemap>\
+                    <sitemap><loc>/archive.xml</loc><lastmod>2020-01-01</lastmod></sitemap></sitemapindex>"),
+                "/pages.xml" => (200, "<urlset><url><loc>/a</loc><lastmod>2024-05-02T08:00:00+02:00</lastmod></url>\
+                    <url><loc>/b</loc><lastmod>2024-04-30</lastmod></url></urlset>"),
+                "/archive.xml" => (200, "<urlset><url><loc>/old</loc></url></urlset>"),
+                _ => (404, ""),
+            };
+            Ok(crate::fetch::FetchedPage { url: url.to_string(), status, body: body.into() })
+        }
+    }
+
+    #[test]
+    fn test_fetch_nested_sitemaps() {
+        let entries = fetch_sitemap_entries("https://example.com/sitemap.xml", &MockFetcher, 10).unwrap();
+
+        let urls: Vec<_> = entries.iter().map(|entry| entry.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://example.com/a", "https://example.com/b", "https://example.com/old"]);
+        assert!(fetch_sitemap_entries("https://example.com/missing.xml", &MockFetcher, 10).is_err());
+    }
+
+    #[test]
+    fn test_fetch_modified_since() {
+        let since = parse_lastmod("2024-05-01").unwrap();
+
+        let entries = fetch_sitemap_entries_since("https://example.com/sitemap.xml", &MockFetcher, 10, Some(since)).unwrap();
+
+        // The archive sitemap is skipped, and so are pages last modified before
+        let urls: Vec<_> = entries.iter().map(|entry| entry.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://example.com/a"]);
+    }
+
+    #[test]
+    fn test_write_sitemap_roundtrip() {
+        let entries = vec![
+            SitemapEntry { url: "https://example.com/a?x=1&y=2".into(), lastmod: Some("2024-05-01T06:30:00Z".into()) },
+            SitemapEntry { url: "https://example.com/b".into(), lastmod: None },
+        ];
+        let mut xml = Vec::new();
+
+        write_sitemap(&mut xml, &entries).unwrap();
+
+        let xml = String::from_utf8(xml).unwrap();
+        assert!(xml.contains("<loc>https://example.com/a?x=1&amp;y=2</loc>"));
+        assert_eq!(parse_sitemap(&xml, &base()).entries, entries);
+    }
+
+    #[test]
+    fn test_write_sitemap_files() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("sitemap.xml");
+        let entries: Vec<_> = (0..MAX_SITEMAP_URLS + 2)
+            .map(|number| SitemapEntry { url: format!("https://example.com/{}", number), lastmod: None })
+            .collect();
+
+        // One file up to the limit
+        assert_eq!(write_sitemap_files(&path, &entries[..MAX_SITEMAP_URLS]).unwrap(), vec![path.clone()]);
+        assert_eq!(parse_sitemap(&fs::read_to_string(&path).unwrap(), &base()).entries.len(), MAX_SITEMAP_URLS);
+        assert!(write_sitemap(&mut Vec::new(), &entries).is_err());
+
+        // An index of numbered parts beyond it
+        let files = write_sitemap_files(&path, &entries).unwrap();
+        assert_eq!(files, vec![path.clone(), directory.path().join("sitemap-1.xml"), directory.path().join("sitemap-2.xml")]);
+        let index = parse_sitemap(&fs::read_to_string(&path).unwrap(), &base());
+        assert!(index.entries.is_empty());
+        assert_eq!(index.sitemaps.iter().map(|sitemap| sitemap.url.as_str()).collect::<Vec<_>>(), vec![
+            "https://example.com/sitemap-1.xml", "https://example.com/sitemap-2.xml",
+        ]);
+        let last = parse_sitemap(&fs::read_to_string(&files[2]).unwrap(), &base());
+        assert_eq!(last.entries, entries[MAX_SITEMAP_URLS..]);
+    }
+
+    #[test]
+    fn test_parse_lastmod() {
+        let expected = parse_lastmod("2024-05-01T06:30:00Z").unwrap();
+        assert_eq!(parse_lastmod("2024-05-01T08:30:00+02:00"), Some(expected));
+        assert_eq!(parse_lastmod("2024-05-01T08:30+02:00"), Some(expected));
+        assert_eq!(parse_lastmod("2024-05-01T06:30:00"), Some(expected));
+        assert_eq!(parse_lastmod("2024-05"), parse_lastmod("2024-05-01"));
+        assert_eq!(parse_lastmod("yesterday"), None);
+    }
+
+    #[test]
+    fn test_sitemap_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        let mut state = SitemapState::load(&path).unwrap();
+        assert_eq!(state.last_run("https://example.com/sitemap.xml"), None);
+
+        let started = parse_lastmod("2024-05-01T06:30:00Z").unwrap();
+        state.record_run("https://example.com/sitemap.xml", started);
+        state.save(&path).unwrap();
+
+        assert_eq!(SitemapState::load(&path).unwrap().last_run("https://example.com/sitemap.xml"), Some(started));
+    }
+}
@@ -0,0 +1,640 @@
+//! Focused crawling for Trafilatura Rust port.
+//! This module keeps the frontier of a breadth-first crawl restricted to the
+//! host of the start page, so the pages of a site without sitemap or feed can
+//! still be discovered. URL patterns recognized as crawler traps are
+//! blacklisted on the way. The crawl state can be saved to a JSON Lines file, so
+//! long crawls survive restarts: a snapshot of the state followed by a log of the
+//! changes since, which is folded into a new snapshot once it outgrows the last one.
+
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use scraper::Html;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::html::descendants_named;
+use crate::traps::{Trap, TrapDetector};
+use crate::urls::{canonicalize_url, registrable_domain};
+use crate::utils::simhash;
+use crate::TrafilaturaError;
+
+/// File extensions of links that never lead to HTML pages
+const SKIPPED_EXTENSIONS: [&str; 18] = [
+    "jpg", "jpeg", "png", "gif", "svg", "webp", "ico", "pdf", "zip", "gz", "mp3", "mp4",
+    "avi", "mov", "css", "js", "xml", "json",
+];
+
+/// Hosts a crawl may visit
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum HostScope {
+    /// Only the host of the start page
+    #[default]
+    SameHost,
+    /// Any host under the registrable domain of the start page, e.g. `blog.example.com`
+    /// when starting at `www.example.com`
+    SameDomain,
+    /// The host of the start page, and these domains and their subdomains
+    Allowlist(Vec<String>),
+}
+
+/// Rules deciding which discovered URLs are crawled, keeping crawls out of
+/// endless calendar and faceted search spaces
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CrawlScope {
+    /// Hosts the crawl may visit
+    pub hosts: HostScope,
+    /// Path prefixes URLs must start with, e.g. `/blog/`; any path when empty
+    pub path_prefixes: Vec<String>,
+    /// Regular expressions of which URLs must match at least one; all URLs when empty
+    pub include: Vec<String>,
+    /// Regular expressions of URLs never crawled
+    pub exclude: Vec<String>,
+    /// Maximum length of crawled URLs
+    pub max_url_length: Option<usize>,
+}
+
+/// Limits of a crawl
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawlOptions {
+    /// Maximum number of pages to visit
+    pub max_pages: usize,
+    /// Maximum link depth from the start page
+    pub max_depth: usize,
+    /// Query parameters stripped when normalizing discovered URLs
+    pub url_param_blocklist: Vec<String>,
+    /// Which discovered URLs are crawled
+    #[serde(default)]
+    pub scope: CrawlScope,
+}
+
+impl Default for CrawlOptions {
+    fn default() -> Self {
+        Self {
+            max_pages: 10,
+            max_depth: 3,
+            url_param_blocklist: Vec::new(),
+            scope: CrawlScope::default(),
+        }
+    }
+}
+
+/// Compiled regular expressions of a `CrawlScope`
+#[derive(Debug, Clone, Default)]
+struct ScopePatterns {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+impl ScopePatterns {
+    fn compile(scope: &CrawlScope) -> Result<Self, TrafilaturaError> {
+        let compile = |patterns: &[String]| {
+            patterns.iter()
+                .map(|pattern| Regex::new(pattern)
+                    .map_err(|e| TrafilaturaError::ExtractionError(format!("Invalid crawl scope pattern {}: {}", pattern, e))))
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        Ok(Self {
+            include: compile(&scope.include)?,
+            exclude: compile(&scope.exclude)?,
+        })
+    }
+}
+
+/// A page whose HTML was retrieved during the crawl
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrawledPage {
+    pub url: String,
+    pub fetched: DateTime<Utc>,
+}
+
+/// Change of the crawl state, logged to the state file between snapshots and
+/// replayed on load
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum CrawlEvent {
+    /// `next_url` handed out the head of the frontier
+    Visit,
+    /// `add_links` recorded a page, given by the fingerprint of its text, and its links
+    Page { url: String, fetched: DateTime<Utc>, fingerprint: u64, links: Vec<String> },
+    /// A request to a host with a politeness policy started
+    Request { host: String, started: DateTime<Utc> },
+}
+
+/// State file a crawl was last saved to, and the bytes of its two parts
+#[derive(Debug, Clone)]
+struct StateFile {
+    path: PathBuf,
+    snapshot_len: usize,
+    log_len: usize,
+}
+
+/// Breadth-first crawl state for one site
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Spider {
+    options: CrawlOptions,
+    host: String,
+    frontier: VecDeque<(String, usize)>,
+    known: HashSet<String>,
+    visited: usize,
+    /// URLs handed out by `next_url`, in visiting order
+    visited_urls: Vec<String>,
+    /// Pages passed to `add_links`, with their fetch times
+    #[serde(default)]
+    fetched: Vec<CrawledPage>,
+    current_depth: usize,
+    #[serde(default)]
+    traps: TrapDetector,
+    /// Start times of the last requests to hosts with a politeness policy
+    #[serde(default)]
+    last_requests: BTreeMap<String, DateTime<Utc>>,
+    #[serde(skip)]
+    patterns: ScopePatterns,
+    /// Changes not yet appended to `state`
+    #[serde(skip)]
+    journal: Vec<CrawlEvent>,
+    #[serde(skip)]
+    state: Option<StateFile>,
+}
+
+impl Spider {
+    /// Start a crawl at the given URL
+    pub fn new(start: &str, options: CrawlOptions) -> Result<Self, TrafilaturaError> {
+        let start = Url::parse(start)?;
+        let host = start.host_str()
+            .ok_or_else(|| TrafilaturaError::ExtractionError(format!("URL has no host: {}", start)))?
+            .to_lowercase();
+
+        let mut spider = Self {
+            patterns: ScopePatterns::compile(&options.scope)?,
+            options,
+            host,
+            frontier: VecDeque::new(),
+            known: HashSet::new(),
+            visited: 0,
+            visited_urls: Vec::new(),
+            fetched: Vec::new(),
+            current_depth: 0,
+            traps: TrapDetector::default(),
+            last_requests: BTreeMap::new(),
+            journal: Vec::new(),
+            state: None,
+        };
+
+        // The start page is crawled even if the scope rules would exclude it
+        if let Some(url) = canonicalize_url(start.as_str(), &spider.options.url_param_blocklist) {
+            spider.known.insert(url.clone());
+            spider.frontier.push_back((url, 0));
+        }
+        Ok(spider)
+    }
+
+    /// Continue the crawl saved at `path` with new limits (`max_pages` counts the pages
+    /// of all runs), or start a new one at `start` if no state was saved yet
+    pub fn resume_or_new(path: &Path, start: &str, options: CrawlOptions) -> Result<Self, TrafilaturaError> {
+        if !path.exists() {
+            return Self::new(start, options);
+        }
+
+        let mut spider = Self::load(path)?;
+        spider.patterns = ScopePatterns::compile(&options.scope)?;
+        spider.options = options;
+        Ok(spider)
+    }
+
+    /// Load a crawl state saved with `save`. The last line of the file may have been
+    /// cut short by an interrupted save, and is then left out.
+    pub fn load(path: &Path) -> Result<Self, TrafilaturaError> {
+        let text = fs::read_to_string(path)?;
+        let mut lines = text.lines().peekable();
+        let mut spider: Self = serde_json::from_str(lines.next().unwrap_or_default())?;
+        spider.patterns = ScopePatterns::compile(&spider.options.scope)?;
+
+        while let Some(line) = lines.next() {
+            match serde_json::from_str(line) {
+                Ok(event) => spider.apply(event),
+                Err(_) if lines.peek().is_none() => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(spider)
+    }
+
+    /// Save the frontier, the visited pages, the limits, and the politeness state of
+    /// the crawl. The changes since the last save to the same file are appended to
+    /// it, so saving after every page stays cheap; once they take more room than the
+    /// snapshot they follow, or when saving to another file, the file is replaced
+    /// atomically by a new snapshot.
+    pub fn save(&mut self, path: &Path) -> Result<(), TrafilaturaError> {
+        let Some(state) = self.state.as_mut().filter(|state| state.path == path) else {
+            return self.write_snapshot(path);
+        };
+        if self.journal.is_empty() {
+            return Ok(());
+        }
+
+        let mut log = String::new();
+        for event in self.journal.drain(..) {
+            log.push_str(&serde_json::to_string(&event)?);
+            log.push('\n');
+        }
+        OpenOptions::new().append(true).open(path)?.write_all(log.as_bytes())?;
+        state.log_len += log.len();
+        if state.log_len > state.snapshot_len {
+            self.write_snapshot(path)?;
+        }
+        Ok(())
+    }
+
+    /// Replace the state file with a snapshot of the whole state
+    fn write_snapshot(&mut self, path: &Path) -> Result<(), TrafilaturaError> {
+        let mut snapshot = serde_json::to_string(self)?;
+        snapshot.push('\n');
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, &snapshot)?;
+        fs::rename(&temporary, path)?;
+        self.journal.clear();
+        self.state = Some(StateFile { path: path.to_path_buf(), snapshot_len: snapshot.len(), log_len: 0 });
+        Ok(())
+    }
+
+    /// Log a change for the next `save`; crawls never saved have no log
+    fn record(&mut self, event: CrawlEvent) {
+        if self.state.is_some() {
+            self.journal.push(event);
+        }
+    }
+
+    /// Replay a logged change
+    fn apply(&mut self, event: CrawlEvent) {
+        match event {
+            CrawlEvent::Visit => {
+                self.visit();
+            },
+            CrawlEvent::Page { url, fetched, fingerprint, links } => self.add_page(url, fetched, fingerprint, &links),
+            CrawlEvent::Request { host, started } => {
+                self.last_requests.insert(host, started);
+            },
+        }
+    }
+
+    /// Take the next URL to visit, or `None` when the crawl is finished
+    pub fn next_url(&mut self) -> Option<String> {
+        if self.visited >= self.options.max_pages {
+            return None;
+        }
+
+        let url = self.visit()?;
+        self.record(CrawlEvent::Visit);
+        Some(url)
+    }
+
+    fn visit(&mut self) -> Option<String> {
+        let (url, depth) = self.frontier.pop_front()?;
+        self.visited += 1;
+        self.visited_urls.push(url.clone());
+        self.current_depth = depth;
+        Some(url)
+    }
+
+    /// Record the page returned by the last `next_url` call as fetched and add its
+    /// in-scope links. `page_url` is the URL the page was served from, after
+    /// redirects: relative links resolve against it before they are canonicalized.
+    pub fn add_links(&mut self, page_url: &str, html: &str) {
+        let document = Html::parse_document(html);
+        let text = document.root_element().text().collect::<Vec<_>>().join(" ");
+        let fingerprint = simhash(&text);
+        let links = document_links(&document, page_url);
+        if let Some(page_url) = canonicalize_url(page_url, &self.options.url_param_blocklist) {
+            let fetched = Utc::now();
+            self.add_page(page_url.clone(), fetched, fingerprint, &links);
+            self.record(CrawlEvent::Page { url: page_url, fetched, fingerprint, links });
+        } else {
+            self.enqueue_links(&links);
+        }
+    }
+
+    fn add_page(&mut self, page_url: String, fetched: DateTime<Utc>, fingerprint: u64, links: &[String]) {
+        if self.traps.record_fingerprint(&page_url, fingerprint).is_some() {
+            let traps = &self.traps;
+            self.frontier.retain(|(url, _)| !traps.is_blacklisted(url));
+        }
+        self.fetched.push(CrawledPage { url: page_url, fetched });
+        self.enqueue_links(links);
+    }
+
+    fn enqueue_links(&mut self, links: &[String]) {
+        let depth = self.current_depth + 1;
+        if depth > self.options.max_depth {
+            return;
+        }
+
+        for link in links {
+            self.enqueue(link, depth);
+        }
+    }
+
+    /// Start times of the last requests to hosts with a politeness policy, as of the
+    /// last `record_requests`
+    pub fn last_requests(&self) -> &BTreeMap<String, DateTime<Utc>> {
+        &self.last_requests
+    }
+
+    /// Keep the start times of the last requests to hosts with a politeness policy,
+    /// see `PolitenessGate::last_requests`, so a resumed crawl keeps to their delays
+    pub fn record_requests(&mut self, requests: BTreeMap<String, DateTime<Utc>>) {
+        for (host, started) in requests {
+            if self.last_requests.get(&host) != Some(&started) {
+                self.last_requests.insert(host.clone(), started);
+                self.record(CrawlEvent::Request { host, started });
+            }
+        }
+    }
+
+    /// Number of pages handed out by `next_url`
+    pub fn visited(&self) -> usize {
+        self.visited
+    }
+
+    /// Number of URLs waiting to be visited
+    pub fn frontier_len(&self) -> usize {
+        self.frontier.len()
+    }
+
+    /// URLs handed out by `next_url`, in visiting order
+    pub fn visited_urls(&self) -> &[String] {
+        &self.visited_urls
+    }
+
+    /// Pages fetched so far, in visiting order
+    pub fn crawled_pages(&self) -> &[CrawledPage] {
+        &self.fetched
+    }
+
+    /// Crawler traps detected so far, with the number of URLs skipped because of each
+    pub fn traps(&self) -> &[Trap] {
+        self.traps.traps()
+    }
+
+    /// Number of visited pages per host
+    pub fn domain_coverage(&self) -> BTreeMap<String, usize> {
+        let mut coverage = BTreeMap::new();
+        for url in &self.visited_urls {
+            if let Some(host) = Url::parse(url).ok().and_then(|url| url.host_str().map(str::to_string)) {
+                *coverage.entry(host).or_insert(0) += 1;
+            }
+        }
+        coverage
+    }
+
+    fn enqueue(&mut self, url: &str, depth: usize) {
+        let url = match canonicalize_url(url, &self.options.url_param_blocklist) {
+            Some(url) => url,
+            None => return,
+        };
+
+        if self.is_in_scope(&url) && !self.known.contains(&url) && self.traps.accept(&url) {
+            self.known.insert(url.clone());
+            self.frontier.push_back((url, depth));
+        }
+    }
+
+    fn is_in_scope(&self, url: &str) -> bool {
+        let scope = &self.options.scope;
+        if scope.max_url_length.is_some_and(|max_length| url.len() > max_length) {
+            return false;
+        }
+
+        let parsed = match Url::parse(url) {
+            Ok(url) => url,
+            Err(_) => return false,
+        };
+
+        let skipped = parsed.path().rsplit_once('.')
+            .is_some_and(|(_, extension)| SKIPPED_EXTENSIONS.contains(&extension.to_lowercase().as_str()));
+
+        matches!(parsed.scheme(), "http" | "https")
+            && parsed.host_str().is_some_and(|host| self.is_allowed_host(&host.to_lowercase()))
+            && !skipped
+            && (scope.path_prefixes.is_empty() || scope.path_prefixes.iter().any(|prefix| parsed.path().starts_with(prefix.as_str())))
+            && (self.patterns.include.is_empty() || self.patterns.include.iter().any(|pattern| pattern.is_match(url)))
+            && !self.patterns.exclude.iter().any(|pattern| pattern.is_match(url))
+    }
+
+    fn is_allowed_host(&self, host: &str) -> bool {
+        let is_within = |domain: &str| {
+            let domain = domain.trim_start_matches('.').to_lowercase();
+            host == domain || host.strip_suffix(domain.as_str()).is_some_and(|sub| sub.ends_with('.'))
+        };
+
+        match &self.options.scope.hosts {
+            HostScope::SameHost => host == self.host,
+            HostScope::SameDomain => registrable_domain(host) == registrable_domain(&self.host),
+            HostScope::Allowlist(domains) => host == self.host || domains.iter().any(|domain| is_within(domain)),
+        }
+    }
+}
+
+/// Extract the absolute targets of all `<a href>` links in a page
+pub fn extract_links(html: &str, base: &str) -> Vec<String> {
+    document_links(&Html::parse_document(html), base)
+}
+
+fn document_links(document: &Html, base: &str) -> Vec<String> {
+    let base = match Url::parse(base) {
+        Ok(base) => base,
+        Err(_) => return Vec::new(),
+    };
+
+    descendants_named(document.root_element(), &["a"])
+        .filter(|link| !link.value().attr("rel").is_some_and(|rel| rel.contains("nofollow")))
+        .filter_map(|link| link.value().attr("href"))
+        .filter_map(|href| base.join(href.trim()).ok())
+        .map(|mut url| {
+            url.set_fragment(None);
+            url.to_string()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traps::TrapKind;
+
+    const PAGE: &str = r#"<html><body>
+        <a href="/a">A</a> <a href="b#comments">B</a> <a href="https://other.com/x">Other</a>
+        <a href="/logo.png">Logo</a> <a href="/c" rel="nofollow">C</a> <a href="mailto:x@example.com">Mail</a>
+        <a href="/a?utm_source=feed">A again</a>
+        </body></html>"#;
+
+    #[test]
+    fn test_extract_links() {
+        let links = extract_links(PAGE, "https://example.com/dir/");
+
+        assert!(links.contains(&"https://example.com/a".to_string()));
+        assert!(links.contains(&"https://example.com/dir/b".to_string()));
+        assert!(!links.iter().any(|link| link.ends_with("/c")));
+    }
+
+    #[test]
+    fn test_spider_stays_on_host() {
+        let options = CrawlOptions {
+            url_param_blocklist: vec!["utm_*".into()],
+            ..Default::default()
+        };
+        let mut spider = Spider::new("https://example.com/dir/", options).unwrap();
+
+        assert_eq!(spider.next_url().as_deref(), Some("https://example.com/dir"));
+        spider.add_links("https://example.com/dir/", PAGE);
+
+        let mut urls = Vec::new();
+        while let Some(url) = spider.next_url() {
+            urls.push(url);
+        }
+        assert_eq!(urls, vec!["https://example.com/a", "https://example.com/dir/b"]);
+        assert_eq!(spider.visited(), 3);
+    }
+
+    #[test]
+    fn test_spider_scope() {
+        let page = r#"<html><body>
+            <a href="https://blog.example.com/post">Blog</a> <a href="https://example.com/events/2024/05">Calendar</a>
+            <a href="https://example.com/news/story">Story</a> <a href="https://example.com/search?q=a&page=2">Search</a>
+            <a href="https://partner.org/x">Partner</a> <a href="https://example.com/news/very-long-slug-of-a-story">Long</a>
+            </body></html>"#;
+        let crawl = |scope| {
+            let mut spider = Spider::new("https://www.example.com/", CrawlOptions { scope, ..Default::default() }).unwrap();
+            let start = spider.next_url().unwrap();
+            spider.add_links(&start, page);
+            std::iter::from_fn(|| spider.next_url()).collect::<Vec<_>>()
+        };
+
+        let scope = CrawlScope {
+            hosts: HostScope::SameDomain,
+            exclude: vec![r"/events/\d{4}/".into(), r"[?&]page=".into()],
+            max_url_length: Some(40),
+            ..Default::default()
+        };
+        assert_eq!(crawl(scope), vec!["https://blog.example.com/post", "https://example.com/news/story"]);
+
+        let scope = CrawlScope {
+            hosts: HostScope::Allowlist(vec!["example.com".into()]),
+            path_prefixes: vec!["/news/".into()],
+            include: vec!["story".into()],
+            ..Default::default()
+        };
+        assert_eq!(crawl(scope), vec!["https://example.com/news/story", "https://example.com/news/very-long-slug-of-a-story"]);
+
+        let scope = CrawlScope { include: vec!["(".into()], ..Default::default() };
+        assert!(Spider::new("https://example.com/", CrawlOptions { scope, ..Default::default() }).is_err());
+    }
+
+    #[test]
+    fn test_spider_resume() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("crawl.json");
+        let options = CrawlOptions { max_pages: 1, url_param_blocklist: vec!["utm_*".into()], ..Default::default() };
+
+        let mut spider = Spider::resume_or_new(&path, "https://example.com/dir/", options.clone()).unwrap();
+        let url = spider.next_url().unwrap();
+        spider.add_links(&url, PAGE);
+        spider.save(&path).unwrap();
+        assert_eq!(spider.next_url(), None);
+
+        // A later run continues from the saved frontier with a higher page limit
+        let options = CrawlOptions { max_pages: 10, ..options };
+        let mut spider = Spider::resume_or_new(&path, "https://example.com/dir/", options).unwrap();
+        assert_eq!(spider.frontier_len(), 2);
+        assert_eq!(spider.next_url().as_deref(), Some("https://example.com/a"));
+        assert_eq!(spider.visited_urls(), ["https://example.com/dir", "https://example.com/a"]);
+        assert_eq!(spider.domain_coverage().get("example.com"), Some(&2));
+        assert_eq!(spider.crawled_pages().len(), 1);
+        assert_eq!(spider.crawled_pages()[0].url, "https://example.com/dir");
+    }
+
+    #[test]
+    fn test_spider_state_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("crawl.jsonl");
+        let lines = || fs::read_to_string(&path).unwrap().lines().count();
+        let mut spider = Spider::new("https://example.com/", CrawlOptions { max_pages: 100, ..Default::default() }).unwrap();
+        let start = spider.next_url().unwrap();
+        let links: String = (0..30).map(|i| format!(r#"<a href="/story-{}">Story</a> "#, i)).collect();
+        spider.add_links(&start, &format!("<html><body>{}</body></html>", links));
+        spider.save(&path).unwrap();
+        assert_eq!(lines(), 1);
+
+        // Each save appends the changes since the last one
+        for _ in 0..3 {
+            let url = spider.next_url().unwrap();
+            spider.add_links(&url, "<html><body><p>A story.</p></body></html>");
+            spider.save(&path).unwrap();
+        }
+        spider.record_requests(BTreeMap::from([("example.com".to_string(), spider.crawled_pages()[0].fetched)]));
+        spider.save(&path).unwrap();
+        assert_eq!(lines(), 8);
+
+        let loaded = Spider::load(&path).unwrap();
+        assert_eq!(loaded.visited_urls(), spider.visited_urls());
+        assert_eq!(loaded.frontier, spider.frontier);
+        assert_eq!(loaded.crawled_pages(), spider.crawled_pages());
+        assert_eq!(loaded.last_requests(), spider.last_requests());
+
+        // Once the log outgrows the snapshot, the file is a single snapshot again
+        while lines() > 1 {
+            let url = spider.next_url().unwrap();
+            spider.add_links(&url, "<html><body><p>A story.</p></body></html>");
+            spider.save(&path).unwrap();
+        }
+        assert_eq!(Spider::load(&path).unwrap().visited_urls(), spider.visited_urls());
+
+        // A save cut short leaves out the last change only
+        spider.next_url().unwrap();
+        spider.save(&path).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"page\":{\"url\":\"https://exa").unwrap();
+        assert_eq!(Spider::load(&path).unwrap().visited_urls(), spider.visited_urls());
+    }
+
+    #[test]
+    fn test_spider_skips_traps() {
+        let mut spider = Spider::new("https://example.com/shop", CrawlOptions::default()).unwrap();
+        let start = spider.next_url().unwrap();
+        let links: String = (0..10)
+            .map(|color| format!(r#"<a href="/shop?color={}">Color</a> "#, color))
+            .chain([r#"<a href="/a/b/a/b/a/b/a">Loop</a> <a href="/about">About</a>"#.to_string()])
+            .collect();
+        spider.add_links(&start, &format!("<html><body>{}</body></html>", links));
+        assert_eq!(spider.frontier_len(), 11);
+
+        // Faceted pages showing the same products get their remaining variants dropped
+        for _ in 0..5 {
+            let url = spider.next_url().unwrap();
+            spider.add_links(&url, "<html><body><p>The same ten products.</p></body></html>");
+        }
+        assert_eq!(spider.frontier_len(), 1);
+        assert_eq!(spider.next_url().as_deref(), Some("https://example.com/about"));
+
+        let kinds: Vec<TrapKind> = spider.traps().iter().map(|trap| trap.kind).collect();
+        assert_eq!(kinds, vec![TrapKind::RepeatedSegments, TrapKind::DuplicateContent]);
+    }
+
+    #[test]
+    fn test_spider_limits() {
+        let options = CrawlOptions { max_pages: 2, max_depth: 0, ..Default::default() };
+        let mut spider = Spider::new("https://example.com/", options).unwrap();
+
+        spider.next_url();
+        spider.add_links("https://example.com/", PAGE);
+
+        assert_eq!(spider.frontier_len(), 0);
+        assert_eq!(spider.next_url(), None);
+    }
+}
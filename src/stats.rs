@@ -0,0 +1,122 @@
+//! Text statistics for Trafilatura Rust port.
+//! This module measures extracted text: word and sentence counts, vocabulary size,
+//! the share of stopwords, and a Flesch reading ease score. The quality
+//! classifier is built on these statistics, and downstream filters can use them
+//! to select documents, e.g. for a training corpus.
+
+use std::collections::HashSet;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::utils::{contains_cjk, count_words, tokenize};
+
+lazy_static! {
+    /// Frequent function words of the languages most commonly extracted
+    static ref STOPWORDS: HashSet<&'static str> = [
+        // English
+        "the", "a", "an", "and", "or", "but", "of", "to", "in", "on", "at", "for", "with",
+        "by", "from", "as", "is", "are", "was", "were", "be", "been", "it", "its", "this",
+        "that", "these", "those", "he", "she", "they", "we", "you", "i", "his", "her",
+        "their", "our", "not", "no", "have", "has", "had", "do", "does", "did", "will",
+        "would", "can", "could", "there", "which", "who", "what", "when", "if", "than",
+        // German
+        "der", "die", "das", "und", "ist", "nicht", "ein", "eine", "mit", "den", "von", "zu",
+        // French
+        "le", "la", "les", "et", "est", "des", "un", "une", "du", "pas", "pour", "que",
+        // Spanish
+        "el", "los", "las", "y", "es", "del", "por", "con", "para", "una", "se",
+    ].into_iter().collect();
+
+    /// Sentence boundaries: terminal punctuation followed by whitespace, CJK full
+    /// stops, and line breaks
+    static ref SENTENCE_END_RE: Regex = Regex::new(r"[.!?…]+(?:\s+|$)|[。！？]+|\n+").unwrap();
+
+    /// Vowel groups, each counted as one syllable
+    static ref VOWEL_GROUP_RE: Regex = Regex::new(r"[aeiouyàáâäèéêëìíîïòóôöùúûü]+").unwrap();
+}
+
+/// Statistics of a text
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TextStats {
+    /// Number of words, with CJK-aware segmentation
+    pub words: usize,
+    /// Number of sentences containing at least one letter or digit
+    pub sentences: usize,
+    /// Number of distinct words, ignoring case and surrounding punctuation
+    pub unique_tokens: usize,
+    /// Mean number of words per sentence
+    pub avg_sentence_length: f64,
+    /// Share of words that are stopwords, `None` for CJK text without spaces
+    pub stopword_ratio: Option<f64>,
+    /// Flesch reading ease (higher is easier, 60-70 is plain English), `None` for
+    /// CJK text and text without words. The formula is calibrated for English and
+    /// only indicative for other languages.
+    pub readability: Option<f64>,
+}
+
+/// Compute the statistics of a text
+pub fn text_stats(text: &str) -> TextStats {
+    let words = count_words(text);
+    let normalized: Vec<String> = tokenize(text).into_iter()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+        .filter(|word| !word.is_empty())
+        .collect();
+    let unique_tokens = normalized.iter().collect::<HashSet<_>>().len();
+
+    let sentences = SENTENCE_END_RE.split(text)
+        .filter(|sentence| sentence.chars().any(char::is_alphanumeric))
+        .count();
+    let avg_sentence_length = if sentences == 0 { 0.0 } else { words as f64 / sentences as f64 };
+
+    let latin = !contains_cjk(text) && words > 0;
+    let stopword_ratio = latin.then(|| {
+        let stopwords = normalized.iter().filter(|word| STOPWORDS.contains(word.as_str())).count();
+        stopwords as f64 / words as f64
+    });
+    let readability = (latin && sentences > 0 && !normalized.is_empty()).then(|| {
+        let syllables: usize = normalized.iter().map(|word| syllables(word)).sum();
+        206.835 - 1.015 * avg_sentence_length - 84.6 * syllables as f64 / normalized.len() as f64
+    });
+
+    TextStats { words, sentences, unique_tokens, avg_sentence_length, stopword_ratio, readability }
+}
+
+/// Estimate the syllables of a lowercase word from its vowel groups, not counting
+/// a silent final `e`
+fn syllables(word: &str) -> usize {
+    if !word.chars().any(char::is_alphabetic) {
+        return 1;
+    }
+    let groups = VOWEL_GROUP_RE.find_iter(word).count();
+    let silent_e = groups > 1 && word.ends_with('e') && !word.ends_with("le") && !word.ends_with("ee");
+    (groups - usize::from(silent_e)).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_stats() {
+        let stats = text_stats("The cat sat on the mat. The dog ran!\nA bird sang");
+        assert_eq!(stats.words, 12);
+        assert_eq!(stats.sentences, 3);
+        assert_eq!(stats.unique_tokens, 10);
+        assert_eq!(stats.avg_sentence_length, 4.0);
+        assert_eq!(stats.stopword_ratio, Some(5.0 / 12.0));
+        // One-syllable words in short sentences read very easily
+        assert!(stats.readability.unwrap() > 100.0);
+
+        let dense = text_stats("Institutional accountability necessitates comprehensive organizational transformation.");
+        assert!(dense.readability.unwrap() < 0.0);
+        assert_eq!(syllables("accountability"), 6);
+        assert_eq!(syllables("make"), 1);
+
+        let cjk = text_stats("今日は晴れです。明日は雨です。");
+        assert_eq!(cjk.sentences, 2);
+        assert_eq!((cjk.stopword_ratio, cjk.readability), (None, None));
+
+        assert_eq!(text_stats(""), TextStats::default());
+    }
+}
@@ -0,0 +1,145 @@
+//! Structural signals for Trafilatura Rust port.
+//! Sites styled with CSS-in-JS or CSS modules ship hashed class names such as
+//! `css-1x9f2kq`, `sc-bdVaJa`, or `Header_title__3xK9a`, which defeat the class
+//! and ID hint lists and can even match them by accident (`css-1adx3` holds `ad`).
+//! This module tells such pages apart, and measures the structure of candidates
+//! that scoring relies on instead: text density and the similarity of siblings.
+
+use std::collections::{HashMap, HashSet};
+
+use scraper::{ElementRef, Html};
+
+/// Distinct class names a page needs before its naming is judged
+const MIN_CLASS_NAMES: usize = 10;
+
+/// Share of distinct class names that look generated above which a page is scored by structure
+const MIN_GENERATED_SHARE: f64 = 0.4;
+
+/// Length from which a part of a class name can be a hash
+const MIN_HASH_LENGTH: usize = 5;
+
+/// Longest part of a class name read as a mixed-case hash like `bdVaJa`; longer
+/// mixed-case parts are camel-case words
+const MAX_MIXED_CASE_HASH_LENGTH: usize = 8;
+
+/// Siblings sharing the shape of an element from which it is one of a series of cards
+pub const MIN_REPEATED_SIBLINGS: usize = 3;
+
+/// Check if a class name looks machine-generated: one of its parts between `-` and
+/// `_` is a run of letters and digits mixing both, or a short run with capitals
+/// inside it
+pub fn is_generated_class(class: &str) -> bool {
+    class.split(['-', '_'])
+        .filter(|part| part.len() >= MIN_HASH_LENGTH && part.chars().all(|c| c.is_ascii_alphanumeric()))
+        .any(|part| {
+            let digits = part.chars().any(|c| c.is_ascii_digit());
+            let letters = part.chars().any(|c| c.is_ascii_alphabetic());
+            let inner_capitals = part.chars().skip(1).filter(|c| c.is_ascii_uppercase()).count();
+            (digits && letters) || (part.len() <= MAX_MIXED_CASE_HASH_LENGTH && inner_capitals >= 2)
+        })
+}
+
+/// Check if the class names of a document look machine-generated, so they say
+/// nothing about the role of the elements carrying them
+pub fn has_generated_class_names(document: &Html) -> bool {
+    let classes: HashSet<&str> = document.root_element()
+        .descendants()
+        .filter_map(|node| node.value().as_element())
+        .flat_map(|element| element.classes())
+        .collect();
+    if classes.len() < MIN_CLASS_NAMES {
+        return false;
+    }
+
+    let generated = classes.iter().filter(|class| is_generated_class(class)).count();
+    generated as f64 / classes.len() as f64 >= MIN_GENERATED_SHARE
+}
+
+/// Shape of an element: its tag name and the tag names of its child elements
+fn shape(element: &ElementRef) -> (String, Vec<String>) {
+    let children = element.children()
+        .filter_map(ElementRef::wrap)
+        .map(|child| child.value().name().to_string())
+        .collect();
+    (element.value().name().to_string(), children)
+}
+
+/// Number of the nearest sibling elements, up to `MIN_REPEATED_SIBLINGS` on each
+/// side, with the same shape as an element, such as the other cards of a teaser rail
+pub fn similar_siblings(element: &ElementRef) -> usize {
+    let own = shape(element);
+    let similar = |sibling: &ElementRef| shape(sibling) == own;
+    let before = element.prev_siblings().filter_map(ElementRef::wrap).take(MIN_REPEATED_SIBLINGS).filter(similar).count();
+    let after = element.next_siblings().filter_map(ElementRef::wrap).take(MIN_REPEATED_SIBLINGS).filter(similar).count();
+    before + after
+}
+
+/// The child elements forming the largest group of one shape, if there are at
+/// least `MIN_REPEATED_SIBLINGS` of them and they are not paragraphs
+pub fn repeated_children<'a>(element: &ElementRef<'a>) -> Vec<ElementRef<'a>> {
+    // Groups in order of their first child, so ties go to the earliest group
+    let mut groups: Vec<Vec<ElementRef<'a>>> = Vec::new();
+    let mut group_of: HashMap<(String, Vec<String>), usize> = HashMap::new();
+    for child in element.children().filter_map(ElementRef::wrap) {
+        let index = *group_of.entry(shape(&child)).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[index].push(child);
+    }
+    groups.into_iter()
+        .rev()
+        .filter(|children| children[0].value().name() != "p" && children.len() >= MIN_REPEATED_SIBLINGS)
+        .max_by_key(|children| children.len())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scraper::Selector;
+
+    #[test]
+    fn test_is_generated_class() {
+        for class in ["css-1x9f2kq", "sc-bdVaJa", "Header_title__3xK9a", "kJHdgs"] {
+            assert!(is_generated_class(class), "{}", class);
+        }
+        for class in ["article-body", "postContent", "NavBar", "col-md-6", "mt-4", "w-[100px]", "h1", "postid-12345"] {
+            assert!(!is_generated_class(class), "{}", class);
+        }
+    }
+
+    #[test]
+    fn test_has_generated_class_names() {
+        let hashed = ["css-1x9f2kq", "css-8ka0p2", "sc-bdVaJa", "sc-gsTCUz", "e1fn3q4z0", "css-k008qs",
+                      "r-1awozwy", "css-175oi2r", "sc-dlfnbm", "x9f2aQ"];
+        let html = format!(
+            "<html><body>{}<div class=\"container\"></div></body></html>",
+            hashed.iter().map(|class| format!("<div class=\"{}\"></div>", class)).collect::<String>()
+        );
+        assert!(has_generated_class_names(&Html::parse_document(&html)));
+
+        let named = ["container", "header", "nav", "main", "article", "article-body", "sidebar", "footer", "card", "btn"];
+        let html = format!(
+            "<html><body>{}</body></html>",
+            named.iter().map(|class| format!("<div class=\"{}\"></div>", class)).collect::<String>()
+        );
+        assert!(!has_generated_class_names(&Html::parse_document(&html)));
+    }
+
+    #[test]
+    fn test_sibling_shapes() {
+        let html = r#"<html><body><div id="rail">
+            <div><a href="/a">One</a><span>1 min</span></div>
+            <div><a href="/b">Two</a><span>2 min</span></div>
+            <div><a href="/c">Three</a><span>3 min</span></div>
+            <div><p>Different</p></div>
+        </div></body></html>"#;
+        let document = Html::parse_document(html);
+        let rail = document.select(&Selector::parse("#rail").unwrap()).next().unwrap();
+        let first = rail.children().filter_map(ElementRef::wrap).next().unwrap();
+
+        assert_eq!(similar_siblings(&first), 2);
+        assert_eq!(repeated_children(&rail).len(), 3);
+    }
+}
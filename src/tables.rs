@@ -0,0 +1,108 @@
+//! Table extraction for Trafilatura Rust port.
+//! This module converts HTML tables into rows of cells so they can be exported
+//! separately from the main text, e.g. as CSV files.
+
+use scraper::ElementRef;
+
+use crate::html::descendants_named;
+
+/// A table extracted from the main content, stored as rows of cell texts
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Table {
+    /// Rows of the table, each holding the text of its cells
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Build a table from a `<table>` element, ignoring rows of nested tables
+    pub fn from_element(table: &ElementRef) -> Self {
+        let mut rows = Vec::new();
+        for row in descendants_named(*table, &["tr"]) {
+            if !belongs_to_table(&row, table) {
+                continue;
+            }
+
+            let cells: Vec<String> = row.children()
+                .filter_map(ElementRef::wrap)
+                .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+                .map(|cell| normalize_cell(&cell.text().collect::<String>()))
+                .collect();
+
+            if !cells.is_empty() {
+                rows.push(cells);
+            }
+        }
+
+        Self { rows }
+    }
+
+    /// Check if the table has no rows
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Serialize the table as CSV (RFC 4180 quoting, `\n` line endings)
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+
+        for row in &self.rows {
+            let line = row.iter()
+                .map(|cell| escape_csv_field(cell))
+                .collect::<Vec<_>>()
+                .join(",");
+            csv.push_str(&line);
+            csv.push('\n');
+        }
+
+        csv
+    }
+}
+
+/// Check that the nearest table ancestor of a row is the given table
+fn belongs_to_table(row: &ElementRef, table: &ElementRef) -> bool {
+    row.ancestors()
+        .filter_map(ElementRef::wrap)
+        .find(|ancestor| ancestor.value().name() == "table")
+        .map(|ancestor| ancestor.id() == table.id())
+        .unwrap_or(false)
+}
+
+/// Collapse whitespace inside a cell
+fn normalize_cell(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Quote a CSV field if it contains separators, quotes, or line breaks
+pub fn escape_csv_field(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') || field.contains('\r') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scraper::{Html, Selector};
+
+    #[test]
+    fn test_table_from_element() {
+        let html = r#"<table>
+            <tr><th>City</th><th>Population</th></tr>
+            <tr><td>Berlin</td><td>3,645,000</td></tr>
+            <tr><td>Notes <table><tr><td>nested</td></tr></table></td><td>"quoted"</td></tr>
+        </table>"#;
+        let document = Html::parse_fragment(html);
+        let table = document.select(&Selector::parse("table").unwrap()).next().unwrap();
+
+        let table = Table::from_element(&table);
+
+        assert_eq!(table.rows.len(), 3);
+        assert_eq!(table.rows[0], vec!["City", "Population"]);
+        assert_eq!(
+            table.to_csv(),
+            "City,Population\nBerlin,\"3,645,000\"\nNotes nested,\"\"\"quoted\"\"\"\n"
+        );
+    }
+}
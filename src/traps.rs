@@ -0,0 +1,292 @@
+//! Crawler trap detection for Trafilatura Rust port.
+//! Some sites generate endless URLs: query strings that grow on every click,
+//! session IDs in links, calendars paging through every month, or faceted
+//! search returning the same page under countless parameter combinations. This
+//! module recognizes such URL patterns while crawling and blacklists them.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::utils::simhash;
+
+lazy_static! {
+    /// Query parameter names carrying session identifiers
+    static ref SESSION_PARAM_RE: Regex = Regex::new(
+        r"(?i)^(?:s|sid|sess|session|sessid|session_?id|jsessionid|phpsessid|aspsessionid\w*|cfid|cftoken|zenid|osCsid)$"
+    ).unwrap();
+
+    /// Session identifier values: long runs of letters and digits
+    static ref SESSION_VALUE_RE: Regex = Regex::new(r"^[A-Za-z0-9_-]{16,}$").unwrap();
+
+    /// Years followed by a month or date, in paths (`/2024/05`) or values (`2024-05-01`)
+    static ref DATE_RE: Regex = Regex::new(r"(?:^|[/=&-])(?:19|20)\d{2}[/-](?:0?[1-9]|1[0-2])(?:[/-]|$|&)").unwrap();
+
+    /// Query parameters of calendar widgets
+    static ref CALENDAR_PARAM_RE: Regex = Regex::new(r"(?i)^(?:year|month|day|date|week|cal|calendar|ym|tribe-bar-date)$").unwrap();
+
+    static ref DIGITS_RE: Regex = Regex::new(r"\d+").unwrap();
+}
+
+/// Query strings longer than this are considered ever-growing
+const MAX_QUERY_LENGTH: usize = 250;
+
+/// URLs with more query parameters than this are considered ever-growing
+const MAX_QUERY_PARAMS: usize = 8;
+
+/// A path segment repeated this often points to a relative link loop (`/a/b/a/b/a/b`)
+const MAX_REPEATED_SEGMENTS: usize = 3;
+
+/// Distinct date-shaped URLs of one pattern after which a calendar is blacklisted
+const MAX_CALENDAR_URLS: usize = 24;
+
+/// Near-identical pages under one path after which its query variants are blacklisted
+const MAX_DUPLICATE_PAGES: usize = 5;
+
+/// Fingerprints differing in at most this many bits belong to near-identical pages
+const MAX_FINGERPRINT_DISTANCE: u32 = 3;
+
+/// Kind of crawler trap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrapKind {
+    /// Query strings growing with every link followed
+    GrowingQuery,
+    /// Session identifiers in query parameters
+    SessionId,
+    /// Paths repeating the same segments
+    RepeatedSegments,
+    /// Calendar pages linking to the next and previous period forever
+    Calendar,
+    /// Near-identical pages under one path, e.g. faceted search results
+    DuplicateContent,
+}
+
+impl fmt::Display for TrapKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TrapKind::GrowingQuery => "growing query string",
+            TrapKind::SessionId => "session ID",
+            TrapKind::RepeatedSegments => "repeated path segments",
+            TrapKind::Calendar => "calendar",
+            TrapKind::DuplicateContent => "near-identical pages",
+        })
+    }
+}
+
+/// A blacklisted URL pattern
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Trap {
+    /// Kind of trap
+    pub kind: TrapKind,
+    /// Pattern of the URLs blacklisted: a URL shape (host and path with digits replaced
+    /// by `N`, and for calendars the query parameter names), a URL prefix for duplicate
+    /// content, or a parameter name for session IDs
+    pub pattern: String,
+    /// Number of URLs skipped because of the trap
+    pub skipped: usize,
+}
+
+impl fmt::Display for Trap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} ({} URLs skipped)", self.kind, self.pattern, self.skipped)
+    }
+}
+
+/// Crawl trap detector, fed with every discovered URL and every fetched page
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrapDetector {
+    traps: Vec<Trap>,
+    /// Distinct URLs seen per date-shaped URL pattern
+    calendar_counts: HashMap<String, usize>,
+    /// Fingerprints of the pages fetched per path, for URLs with a query string
+    fingerprints: HashMap<String, Vec<u64>>,
+}
+
+impl TrapDetector {
+    /// Check a newly discovered URL: returns `false` if it matches a known trap or
+    /// reveals a new one, which is then blacklisted
+    pub fn accept(&mut self, url: &str) -> bool {
+        let parsed = match Url::parse(url) {
+            Ok(parsed) => parsed,
+            Err(_) => return true,
+        };
+
+        if let Some(trap) = self.traps.iter_mut().find(|trap| matches_trap(trap, url, &parsed)) {
+            trap.skipped += 1;
+            return false;
+        }
+
+        if let Some((kind, pattern)) = self.detect(&parsed) {
+            self.traps.push(Trap { kind, pattern, skipped: 1 });
+            return false;
+        }
+        true
+    }
+
+    /// Record the text of a fetched page: returns the new trap if it is one of too
+    /// many near-identical pages under the same path
+    pub fn record_page(&mut self, url: &str, text: &str) -> Option<&Trap> {
+        self.record_fingerprint(url, simhash(text))
+    }
+
+    /// Record a fetched page by the `simhash` fingerprint of its text, see `record_page`
+    pub fn record_fingerprint(&mut self, url: &str, fingerprint: u64) -> Option<&Trap> {
+        let parsed = Url::parse(url).ok()?;
+        parsed.query()?;
+
+        let mut prefix = parsed.clone();
+        prefix.set_query(None);
+        prefix.set_fragment(None);
+        let prefix = format!("{}?", prefix);
+        if self.traps.iter().any(|trap| trap.kind == TrapKind::DuplicateContent && trap.pattern == prefix) {
+            return None;
+        }
+
+        let fingerprints = self.fingerprints.entry(prefix.clone()).or_default();
+        fingerprints.push(fingerprint);
+        let similar = fingerprints.iter()
+            .filter(|other| (**other ^ fingerprint).count_ones() <= MAX_FINGERPRINT_DISTANCE)
+            .count();
+        if similar < MAX_DUPLICATE_PAGES {
+            return None;
+        }
+
+        self.fingerprints.remove(&prefix);
+        self.traps.push(Trap { kind: TrapKind::DuplicateContent, pattern: prefix, skipped: 0 });
+        self.traps.last()
+    }
+
+    /// Check if a URL matches a blacklisted pattern, without counting it
+    pub fn is_blacklisted(&self, url: &str) -> bool {
+        Url::parse(url).is_ok_and(|parsed| self.traps.iter().any(|trap| matches_trap(trap, url, &parsed)))
+    }
+
+    /// Traps detected so far
+    pub fn traps(&self) -> &[Trap] {
+        &self.traps
+    }
+
+    fn detect(&mut self, url: &Url) -> Option<(TrapKind, String)> {
+        if let Some((name, _)) = url.query_pairs()
+            .find(|(name, value)| SESSION_PARAM_RE.is_match(name) && SESSION_VALUE_RE.is_match(value))
+        {
+            return Some((TrapKind::SessionId, name.into_owned()));
+        }
+
+        let query = url.query().unwrap_or_default();
+        if has_growing_query(url) {
+            return Some((TrapKind::GrowingQuery, path_shape(url)));
+        }
+
+        let segments: Vec<&str> = url.path_segments().map(|segments| segments.filter(|s| !s.is_empty()).collect()).unwrap_or_default();
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for segment in &segments {
+            *counts.entry(segment).or_default() += 1;
+        }
+        if counts.values().any(|count| *count >= MAX_REPEATED_SEGMENTS) {
+            return Some((TrapKind::RepeatedSegments, url_shape(url)));
+        }
+
+        let is_calendar = DATE_RE.is_match(url.path())
+            || DATE_RE.is_match(query)
+            || url.query_pairs().any(|(name, _)| CALENDAR_PARAM_RE.is_match(&name));
+        if is_calendar {
+            let shape = url_shape(url);
+            let count = self.calendar_counts.entry(shape.clone()).or_default();
+            *count += 1;
+            if *count > MAX_CALENDAR_URLS {
+                self.calendar_counts.remove(&shape);
+                return Some((TrapKind::Calendar, shape));
+            }
+        }
+
+        None
+    }
+}
+
+fn matches_trap(trap: &Trap, url: &str, parsed: &Url) -> bool {
+    match trap.kind {
+        TrapKind::SessionId => parsed.query_pairs().any(|(name, _)| name == trap.pattern.as_str()),
+        TrapKind::DuplicateContent => url.starts_with(&trap.pattern),
+        TrapKind::GrowingQuery => has_growing_query(parsed) && path_shape(parsed) == trap.pattern,
+        TrapKind::RepeatedSegments | TrapKind::Calendar => url_shape(parsed) == trap.pattern,
+    }
+}
+
+fn has_growing_query(url: &Url) -> bool {
+    url.query().is_some_and(|query| query.len() > MAX_QUERY_LENGTH) || url.query_pairs().count() > MAX_QUERY_PARAMS
+}
+
+/// Host and path with digit runs replaced by `N`
+fn path_shape(url: &Url) -> String {
+    format!("{}{}", url.host_str().unwrap_or_default(), DIGITS_RE.replace_all(url.path(), "N"))
+}
+
+/// Path shape followed by the sorted query parameter names
+fn url_shape(url: &Url) -> String {
+    let mut names: Vec<String> = url.query_pairs().map(|(name, _)| name.into_owned()).collect();
+    names.sort();
+    names.dedup();
+
+    let mut shape = path_shape(url);
+    if !names.is_empty() {
+        shape.push('?');
+        shape.push_str(&names.join("&"));
+    }
+    shape
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_and_growing_query_traps() {
+        let mut detector = TrapDetector::default();
+
+        assert!(detector.accept("https://example.com/shop?cat=1"));
+        assert!(!detector.accept("https://example.com/shop?osCsid=a8f5f167f44f4964e6c998dee827110c"));
+        assert!(!detector.accept("https://example.com/other?osCsid=b8f5f167f44f4964e6c998dee827110c"));
+
+        let growing = format!("https://example.com/list?{}", (0..10).map(|n| format!("f{}=x", n)).collect::<Vec<_>>().join("&"));
+        assert!(!detector.accept(&growing));
+        assert!(!detector.accept("https://example.com/a/b/a/b/a/b"));
+
+        let kinds: Vec<_> = detector.traps().iter().map(|trap| trap.kind).collect();
+        assert_eq!(kinds, vec![TrapKind::SessionId, TrapKind::GrowingQuery, TrapKind::RepeatedSegments]);
+        assert_eq!(detector.traps()[0].skipped, 2);
+    }
+
+    #[test]
+    fn test_calendar_trap() {
+        let mut detector = TrapDetector::default();
+        let months: Vec<String> = (0..40).map(|n| format!("https://example.com/events/{}/{:02}", 2000 + n / 12, n % 12 + 1)).collect();
+
+        let accepted = months.iter().filter(|url| detector.accept(url)).count();
+
+        assert_eq!(accepted, MAX_CALENDAR_URLS);
+        assert_eq!(detector.traps()[0].kind, TrapKind::Calendar);
+        assert_eq!(detector.traps()[0].pattern, "example.com/events/N/N");
+        assert!(detector.accept("https://example.com/2024/05/a-dated-article"));
+    }
+
+    #[test]
+    fn test_duplicate_content_trap() {
+        let mut detector = TrapDetector::default();
+        let text = "Showing results for your search. No products match the selected filters. Try removing some filters.";
+
+        for n in 0..MAX_DUPLICATE_PAGES - 1 {
+            assert!(detector.record_page(&format!("https://example.com/search?color={}", n), text).is_none());
+        }
+        let trap = detector.record_page("https://example.com/search?size=9", text).unwrap();
+
+        assert_eq!(trap.pattern, "https://example.com/search?");
+        assert!(!detector.accept("https://example.com/search?color=red&size=2"));
+        assert!(detector.accept("https://example.com/search-tips"));
+    }
+}
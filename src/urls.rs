@@ -0,0 +1,276 @@
+//! URL handling for Trafilatura Rust port.
+//! This module resolves alternate URL forms (such as AMP pages) to the
+//! canonical publisher URL and normalizes URLs, so the same document is
+//! reported under one address.
+
+use lazy_static::lazy_static;
+use publicsuffix::{List, Psl};
+use url::Url;
+
+/// Host suffix of the Google AMP cache
+const AMP_CACHE_SUFFIX: &str = ".cdn.ampproject.org";
+
+/// Query parameters that only select the AMP rendering of a page
+const AMP_QUERY_PARAMS: [(&str, &str); 3] = [("outputType", "amp"), ("amp", "1"), ("amp", "")];
+
+lazy_static! {
+    /// The Public Suffix List (publicsuffix.org), ICANN and private sections
+    static ref PUBLIC_SUFFIXES: List = include_str!("data/public_suffix_list.dat")
+        .parse()
+        .expect("bundled public suffix list parses");
+}
+
+/// Query parameters removed by `canonicalize_url` by default. A trailing `*` matches
+/// any parameter starting with the given prefix; matching is case-insensitive.
+pub const DEFAULT_PARAM_BLOCKLIST: [&str; 16] = [
+    "utm_*", "gclid", "gclsrc", "dclid", "fbclid", "msclkid", "yclid", "mc_cid", "mc_eid",
+    "_ga", "_hsenc", "_hsmi", "sessionid", "session_id", "phpsessid", "jsessionid",
+];
+
+/// Normalize a URL so that variants of the same address compare equal.
+///
+/// Lowercases scheme and host, drops default ports (done by the URL parser), fragments and `;jsessionid=`-style
+/// path parameters, removes query parameters matching the blocklist, sorts the remaining
+/// ones, and strips trailing slashes from non-root paths.
+pub fn canonicalize_url<S: AsRef<str>>(url: &str, param_blocklist: &[S]) -> Option<String> {
+    let mut parsed = Url::parse(url).ok()?;
+    if parsed.cannot_be_a_base() {
+        return Some(parsed.to_string());
+    }
+
+    parsed.set_fragment(None);
+
+    // Strip session path parameters and trailing slashes
+    let path = parsed.path().to_string();
+    let path = match path.find(';') {
+        Some(index) => &path[..index],
+        None => path.as_str(),
+    };
+    let trimmed = path.trim_end_matches('/');
+    parsed.set_path(if trimmed.is_empty() { "/" } else { trimmed });
+
+    // Filter and sort query parameters
+    let mut pairs: Vec<(String, String)> = parsed.query_pairs()
+        .into_owned()
+        .filter(|(key, _)| !is_blocked_param(key, param_blocklist))
+        .collect();
+    pairs.sort();
+    if pairs.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut()
+            .clear()
+            .extend_pairs(pairs.iter().map(|(key, value)| (key.as_str(), value.as_str())));
+    }
+
+    Some(parsed.to_string())
+}
+
+/// Check if a query parameter matches an entry of the blocklist
+fn is_blocked_param<S: AsRef<str>>(key: &str, blocklist: &[S]) -> bool {
+    let key = key.to_lowercase();
+    blocklist.iter().any(|pattern| {
+        let pattern = pattern.as_ref().to_lowercase();
+        match pattern.strip_suffix('*') {
+            Some(prefix) => key.starts_with(prefix),
+            None => key == pattern,
+        }
+    })
+}
+
+/// Check if a URL points to an AMP cache, the Google AMP viewer, or an AMP variant of a page
+pub fn is_amp_url(url: &str) -> bool {
+    resolve_amp_url(url).is_some()
+}
+
+/// Resolve an AMP URL to the canonical publisher URL.
+///
+/// Handles AMP cache URLs (`https://www-example-com.cdn.ampproject.org/c/s/www.example.com/a`),
+/// Google AMP viewer URLs (`https://www.google.com/amp/s/www.example.com/a`), and publisher
+/// AMP variants (`?outputType=amp`, `?amp=1`, trailing `/amp`). Returns `None` for URLs
+/// that are not AMP.
+pub fn resolve_amp_url(url: &str) -> Option<String> {
+    let parsed = Url::parse(url).ok()?;
+    let host = parsed.host_str()?.to_lowercase();
+
+    if host.ends_with(AMP_CACHE_SUFFIX) {
+        return resolve_amp_cache_path(&parsed);
+    }
+
+    if is_google_host(&host) && parsed.path().starts_with("/amp/") {
+        let rest = &parsed.path()["/amp/".len()..];
+        return rebuild_origin_url(rest, parsed.query());
+    }
+
+    resolve_amp_variant(&parsed)
+}
+
+/// Resolve the path of an AMP cache URL: `/{c|v|i|r}/[s/]{host}/{path}`
+fn resolve_amp_cache_path(parsed: &Url) -> Option<String> {
+    let path = parsed.path().trim_start_matches('/');
+    let (kind, rest) = path.split_once('/')?;
+    if !matches!(kind, "c" | "v" | "i" | "r") {
+        return None;
+    }
+
+    rebuild_origin_url(rest, parsed.query())
+}
+
+/// Rebuild the origin URL from `[s/]{host}/{path}`, where `s/` marks HTTPS origins
+fn rebuild_origin_url(rest: &str, query: Option<&str>) -> Option<String> {
+    let (scheme, rest) = match rest.strip_prefix("s/") {
+        Some(rest) => ("https", rest),
+        None => ("http", rest),
+    };
+
+    if rest.is_empty() {
+        return None;
+    }
+
+    let mut origin = Url::parse(&format!("{}://{}", scheme, rest)).ok()?;
+    origin.set_query(query);
+
+    // The origin itself may still be an AMP variant
+    let origin = origin.to_string();
+    Some(resolve_amp_url(&origin).unwrap_or(origin))
+}
+
+/// Strip AMP selectors from a publisher URL, if it has any
+fn resolve_amp_variant(parsed: &Url) -> Option<String> {
+    let mut canonical = parsed.clone();
+    let mut changed = false;
+
+    let pairs: Vec<(String, String)> = parsed.query_pairs().into_owned().collect();
+    let kept: Vec<&(String, String)> = pairs.iter()
+        .filter(|(key, value)| {
+            !AMP_QUERY_PARAMS.iter().any(|(amp_key, amp_value)| key == amp_key && value == amp_value)
+        })
+        .collect();
+    if kept.len() != pairs.len() {
+        changed = true;
+        if kept.is_empty() {
+            canonical.set_query(None);
+        } else {
+            canonical.query_pairs_mut()
+                .clear()
+                .extend_pairs(kept.iter().map(|(key, value)| (key.as_str(), value.as_str())));
+        }
+    }
+
+    let path = parsed.path();
+    let trimmed = path.trim_end_matches('/');
+    if let Some(stripped) = trimmed.strip_suffix("/amp") {
+        changed = true;
+        canonical.set_path(if stripped.is_empty() { "/" } else { stripped });
+    }
+
+    if changed {
+        Some(canonical.to_string())
+    } else {
+        None
+    }
+}
+
+/// The registrable domain of a host according to the Public Suffix List, e.g.
+/// `example.com` for `news.example.com`, `example.co.uk` for `www.example.co.uk`
+/// and `alice.github.io` for `blog.alice.github.io`. IP addresses and hosts without
+/// a registrable domain (such as `localhost`) are returned as they are.
+pub fn registrable_domain(host: &str) -> String {
+    let host = host.trim_end_matches('.').to_lowercase();
+    if host.parse::<std::net::IpAddr>().is_ok() || host.starts_with('[') {
+        return host;
+    }
+
+    PUBLIC_SUFFIXES.domain(host.as_bytes())
+        .and_then(|domain| std::str::from_utf8(domain.as_bytes()).ok().map(str::to_string))
+        .unwrap_or(host)
+}
+
+/// Check if a host belongs to Google (e.g. `www.google.com`, `google.co.uk`)
+fn is_google_host(host: &str) -> bool {
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    host.starts_with("google.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_amp_cache_url() {
+        assert_eq!(
+            resolve_amp_url("https://www-example-com.cdn.ampproject.org/c/s/www.example.com/news/story.html").as_deref(),
+            Some("https://www.example.com/news/story.html")
+        );
+        assert_eq!(
+            resolve_amp_url("https://example-com.cdn.ampproject.org/v/example.com/story/amp?x=1").as_deref(),
+            Some("http://example.com/story?x=1")
+        );
+    }
+
+    #[test]
+    fn test_resolve_google_amp_viewer() {
+        assert_eq!(
+            resolve_amp_url("https://www.google.com/amp/s/www.example.com/story").as_deref(),
+            Some("https://www.example.com/story")
+        );
+    }
+
+    #[test]
+    fn test_resolve_amp_variants() {
+        assert_eq!(
+            resolve_amp_url("https://example.com/story?outputType=amp").as_deref(),
+            Some("https://example.com/story")
+        );
+        assert_eq!(
+            resolve_amp_url("https://example.com/story?id=4&amp=1").as_deref(),
+            Some("https://example.com/story?id=4")
+        );
+        assert_eq!(
+            resolve_amp_url("https://example.com/2024/story/amp/").as_deref(),
+            Some("https://example.com/2024/story")
+        );
+    }
+
+    #[test]
+    fn test_canonicalize_url() {
+        let canonicalize = |url| canonicalize_url(url, &DEFAULT_PARAM_BLOCKLIST);
+
+        assert_eq!(
+            canonicalize("HTTPS://Example.COM:443/news/story/?utm_source=tw&b=2&a=1&gclid=x#comments").as_deref(),
+            Some("https://example.com/news/story?a=1&b=2")
+        );
+        assert_eq!(
+            canonicalize("http://example.com:80/story;jsessionid=ABC?PHPSESSID=1").as_deref(),
+            Some("http://example.com/story")
+        );
+        assert_eq!(canonicalize("https://example.com").as_deref(), Some("https://example.com/"));
+        assert_eq!(
+            canonicalize("https://example.com:8443/a/").as_deref(),
+            Some("https://example.com:8443/a")
+        );
+        assert_eq!(canonicalize_url("https://example.com/?ref=home", &["ref"]).as_deref(), Some("https://example.com/"));
+        assert_eq!(canonicalize("not a url"), None);
+    }
+
+    #[test]
+    fn test_registrable_domain() {
+        assert_eq!(registrable_domain("news.Example.com"), "example.com");
+        assert_eq!(registrable_domain("www.example.co.uk"), "example.co.uk");
+        assert_eq!(registrable_domain("example.de"), "example.de");
+        assert_eq!(registrable_domain("localhost"), "localhost");
+        assert_eq!(registrable_domain("www.example.com.au"), "example.com.au");
+        assert_eq!(registrable_domain("city.kawasaki.jp"), "city.kawasaki.jp");
+        assert_eq!(registrable_domain("news.bbc.co.uk."), "bbc.co.uk");
+        assert_eq!(registrable_domain("blog.alice.github.io"), "alice.github.io");
+        assert_ne!(registrable_domain("alice.github.io"), registrable_domain("bob.github.io"));
+        assert_eq!(registrable_domain("192.168.0.1"), "192.168.0.1");
+    }
+
+    #[test]
+    fn test_non_amp_urls() {
+        assert!(!is_amp_url("https://example.com/story"));
+        assert!(!is_amp_url("https://example.com/amplifier"));
+        assert!(!is_amp_url("https://www.google.com/search?q=amp"));
+    }
+}
@@ -1,14 +1,37 @@
 //! Utility functions for Trafilatura Rust port.
 //! This module provides various helper functions for the library.
 
-use scraper::Html;
+use scraper::{Html, Selector};
+use std::collections::HashSet;
 use std::path::Path;
 use std::fs::File;
 use std::io::Read;
+use tracing::warn;
 use url::Url;
 
 use crate::TrafilaturaError;
 
+/// File extensions treated as HTML documents
+const HTML_EXTENSIONS: [&str; 3] = ["html", "htm", "xhtml"];
+
+/// Parse a CSS selector, reporting invalid selectors as `TrafilaturaError::SelectorError`
+pub fn create_selector(selector: &str) -> Result<Selector, TrafilaturaError> {
+    Selector::parse(selector)
+        .map_err(|e| TrafilaturaError::SelectorError(format!("Invalid selector: {} - {:?}", selector, e)))
+}
+
+/// Parse a CSS selector, logging and skipping it when it is invalid, for
+/// lookups that are optional and must never abort an extraction
+pub fn try_selector(selector: &str) -> Option<Selector> {
+    match create_selector(selector) {
+        Ok(selector) => Some(selector),
+        Err(e) => {
+            warn!("{}", e);
+            None
+        }
+    }
+}
+
 /// Check if input is a URL
 pub fn is_url(input: &str) -> bool {
     Url::parse(input).is_ok()
@@ -19,6 +42,14 @@ pub fn is_file_path(input: &str) -> bool {
     Path::new(input).exists() && Path::new(input).is_file()
 }
 
+/// Check if a path has an HTML file extension
+pub fn is_html_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| HTML_EXTENSIONS.iter().any(|html| extension.eq_ignore_ascii_case(html)))
+        .unwrap_or(false)
+}
+
 /// Check if input is likely HTML content
 pub fn is_html_content(input: &str) -> bool {
     input.contains("<html") || 
@@ -55,14 +86,230 @@ pub fn normalize_html(html: &str) -> String {
 /// Parse HTML content to a document
 pub fn parse_html(content: &str) -> Result<Html, TrafilaturaError> {
     let normalized = normalize_html(content);
-    let document = Html::parse_document(&normalized);
-    Ok(document)
+    Ok(crate::html::parse_document(&normalized))
+}
+
+/// Weight applied to CJK characters when measuring text length. A single ideograph,
+/// kana or Hangul syllable carries roughly as much content as two Latin letters.
+pub const CJK_CHAR_WEIGHT: usize = 2;
+
+/// Character classes relevant for length measurement and word segmentation
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CharClass {
+    Whitespace,
+    /// CJK punctuation and full-width symbols, which separate words
+    CjkPunctuation,
+    /// Han ideographs and hiragana, segmented one character per token
+    Ideograph,
+    /// Katakana, where consecutive characters form a single token
+    Katakana,
+    /// Hangul syllables and jamo, which are space-separated like Latin text
+    Hangul,
+    Other,
+}
+
+fn char_class(c: char) -> CharClass {
+    if c.is_whitespace() {
+        return CharClass::Whitespace;
+    }
+    
+    match c as u32 {
+        0x3000..=0x303F | 0xFF00..=0xFF65 | 0xFFA0..=0xFFEF => CharClass::CjkPunctuation,
+        0x3040..=0x309F | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0x20000..=0x2FA1F => CharClass::Ideograph,
+        0x30A0..=0x30FF | 0x31F0..=0x31FF | 0xFF66..=0xFF9F => CharClass::Katakana,
+        0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => CharClass::Hangul,
+        _ => CharClass::Other,
+    }
+}
+
+/// Split text into chunks of at most `length` bytes, or of a single character
+/// where one is longer, without splitting characters
+pub fn char_chunks(text: &str, length: usize) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let mut end = length.min(rest.len());
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        if end == 0 {
+            end = rest.chars().next().map_or(rest.len(), char::len_utf8);
+        }
+        let (chunk, tail) = rest.split_at(end);
+        rest = tail;
+        Some(chunk)
+    })
+}
+
+/// Check if a character belongs to a CJK script (Han, kana or Hangul)
+pub fn is_cjk_char(c: char) -> bool {
+    matches!(char_class(c), CharClass::Ideograph | CharClass::Katakana | CharClass::Hangul)
+}
+
+/// Check if text contains any CJK characters
+pub fn contains_cjk(text: &str) -> bool {
+    text.chars().any(is_cjk_char)
+}
+
+/// Measure text length in Latin-equivalent characters.
+///
+/// Unlike `str::len`, this counts characters rather than UTF-8 bytes and gives CJK
+/// characters a weight of `CJK_CHAR_WEIGHT`, so length thresholds behave comparably
+/// on Latin and Chinese/Japanese/Korean pages.
+pub fn text_length(text: &str) -> usize {
+    text.chars()
+        .map(|c| if is_cjk_char(c) { CJK_CHAR_WEIGHT } else { 1 })
+        .sum()
+}
+
+/// Split text into word tokens with CJK-aware segmentation.
+///
+/// Latin and Hangul text is split on whitespace. Han ideographs and hiragana form one
+/// token per character and katakana runs form a single token, since these scripts
+/// don't separate words with spaces. CJK punctuation acts as a separator.
+pub fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start: Option<(usize, CharClass)> = None;
+    
+    for (idx, c) in text.char_indices() {
+        let class = char_class(c);
+        
+        // Close the current token when the character class boundary requires it
+        if let Some((token_start, token_class)) = start {
+            let continues = match class {
+                CharClass::Whitespace | CharClass::CjkPunctuation | CharClass::Ideograph => false,
+                CharClass::Katakana => token_class == CharClass::Katakana,
+                CharClass::Hangul | CharClass::Other => {
+                    matches!(token_class, CharClass::Hangul | CharClass::Other)
+                }
+            };
+            
+            if !continues {
+                tokens.push(&text[token_start..idx]);
+                start = None;
+            }
+        }
+        
+        match class {
+            CharClass::Whitespace | CharClass::CjkPunctuation => {}
+            CharClass::Ideograph => tokens.push(&text[idx..idx + c.len_utf8()]),
+            _ => {
+                if start.is_none() {
+                    start = Some((idx, class));
+                }
+            }
+        }
+    }
+    
+    if let Some((token_start, _)) = start {
+        tokens.push(&text[token_start..]);
+    }
+    
+    tokens
+}
+
+/// Count words with CJK-aware segmentation
+pub fn count_words(text: &str) -> usize {
+    tokenize(text).len()
+}
+
+/// 64-bit SimHash of the word trigrams of a text: similar texts differ in few bits.
+/// Shingles are hashed with FNV-1a, so fingerprints are stable across builds and
+/// can be stored.
+pub fn simhash(text: &str) -> u64 {
+    let words = tokenize(text);
+    let mut weights = [0i64; 64];
+    for shingle in words.windows(3.min(words.len().max(1))) {
+        let hash = hash_shingle(shingle);
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            *weight += if hash >> bit & 1 == 1 { 1 } else { -1 };
+        }
+    }
+
+    weights.iter().enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0, |hash, (bit, _)| hash | 1 << bit)
+}
+
+/// MinHash signature of the word shingles of a text: the share of equal values in
+/// two signatures estimates the Jaccard similarity of the texts.
+///
+/// Each of the `permutations` hash functions is `(a * h + b) mod (2^61 - 1)` truncated
+/// to 32 bits, applied to the FNV-1a hash `h` of every shingle of `shingle_size`
+/// words. The parameters `a` and `b` are derived from `seed` with SplitMix64, so
+/// signatures computed with the same settings can be compared across runs.
+pub fn minhash(text: &str, permutations: usize, shingle_size: usize, seed: u64) -> Vec<u32> {
+    const MERSENNE_PRIME: u64 = (1 << 61) - 1;
+
+    let mut state = seed;
+    let mut next = || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+    let parameters: Vec<(u64, u64)> = (0..permutations)
+        .map(|_| (next() % (MERSENNE_PRIME - 1) + 1, next() % MERSENNE_PRIME))
+        .collect();
+
+    let words = tokenize(text);
+    let mut signature = vec![u32::MAX; permutations];
+    for shingle in words.windows(shingle_size.max(1).min(words.len().max(1))) {
+        let hash = hash_shingle(shingle) as u128;
+        for (value, (a, b)) in signature.iter_mut().zip(&parameters) {
+            let permuted = ((*a as u128 * hash + *b as u128) % MERSENNE_PRIME as u128) as u32;
+            *value = (*value).min(permuted);
+        }
+    }
+    signature
+}
+
+/// Paragraphs shorter than this many words may legitimately repeat, e.g. "Yes" in
+/// an interview or a recurring subheading
+pub const MIN_REPEATED_PARAGRAPH_WORDS: usize = 5;
+
+/// Remove paragraphs (lines) that repeat an earlier paragraph verbatim, ignoring
+/// differences in whitespace, together with the blank line separating them from the
+/// previous paragraph. Paragraphs of fewer than `MIN_REPEATED_PARAGRAPH_WORDS` words are kept.
+pub fn remove_repeated_paragraphs(text: &str) -> String {
+    let mut seen = HashSet::new();
+    let mut kept: Vec<&str> = Vec::new();
+    for line in text.lines() {
+        let normalized = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if count_words(&normalized) >= MIN_REPEATED_PARAGRAPH_WORDS && !seen.insert(normalized) {
+            if kept.last().is_some_and(|previous| previous.trim().is_empty()) {
+                kept.pop();
+            }
+            continue;
+        }
+        kept.push(line);
+    }
+    kept.join("\n")
+}
+
+/// FNV-1a hash of a word shingle
+fn hash_shingle(words: &[&str]) -> u64 {
+    words.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, word| {
+        // Words are separated by a zero byte, which cannot occur inside them
+        word.bytes().chain([0]).fold(hash, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     
+    #[test]
+    fn test_is_html_file() {
+        assert!(is_html_file(Path::new("inbox/story.html")));
+        assert!(is_html_file(Path::new("inbox/STORY.HTM")));
+        assert!(!is_html_file(Path::new("inbox/story.html.part")));
+        assert!(!is_html_file(Path::new("inbox/notes")));
+    }
+
     #[test]
     fn test_is_url() {
         assert!(is_url("https://example.com"));
@@ -95,4 +342,69 @@ mod tests {
             "<html><body><div>content</div></body></html>"
         );
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_text_length() {
+        assert_eq!(text_length("hello"), 5);
+        assert_eq!(text_length("café"), 4);
+        assert_eq!(text_length("新闻"), 2 * CJK_CHAR_WEIGHT);
+        assert_eq!(text_length("ニュース a"), 4 * CJK_CHAR_WEIGHT + 2);
+    }
+    
+    #[test]
+    fn test_char_chunks() {
+        assert_eq!(char_chunks("abcdefg", 3).collect::<Vec<_>>(), vec!["abc", "def", "g"]);
+        assert_eq!(char_chunks("aé中x", 2).collect::<Vec<_>>(), vec!["a", "é", "中", "x"]);
+        assert_eq!(char_chunks("", 3).count(), 0);
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("Hello, world!"), vec!["Hello,", "world!"]);
+        assert_eq!(tokenize("我爱北京。"), vec!["我", "爱", "北", "京"]);
+        assert_eq!(tokenize("東京のニュースです"), vec!["東", "京", "の", "ニュース", "で", "す"]);
+        assert_eq!(tokenize("한국어 텍스트"), vec!["한국어", "텍스트"]);
+        assert_eq!(tokenize("Rust语言"), vec!["Rust", "语", "言"]);
+        assert_eq!(count_words("  "), 0);
+    }
+    
+    #[test]
+    fn test_contains_cjk() {
+        assert!(contains_cjk("Article 新闻"));
+        assert!(!contains_cjk("Plain Latin text"));
+    }
+    
+    #[test]
+    fn test_minhash() {
+        let similarity = |a: &[u32], b: &[u32]| a.iter().zip(b).filter(|(x, y)| x == y).count() as f64 / a.len() as f64;
+        let text = "the quick brown fox jumps over the lazy dog while the cat sleeps in the warm sun all afternoon long";
+        let edited = text.replace("afternoon", "morning");
+        
+        let signature = minhash(text, 128, 3, 1);
+        
+        assert_eq!(signature.len(), 128);
+        assert_eq!(signature, minhash(text, 128, 3, 1));
+        assert!(similarity(&signature, &minhash(&edited, 128, 3, 1)) > 0.6);
+        assert!(similarity(&signature, &minhash("an entirely different sentence about tax law and accounting rules", 128, 3, 1)) < 0.1);
+        assert_ne!(signature, minhash(text, 128, 3, 2));
+    }
+    
+    #[test]
+    fn test_remove_repeated_paragraphs() {
+        // Interviews repeat a quote as a pull quote, and answers may be short
+        let quote = "“Nobody expected the vote to be this close,” the campaign manager said.";
+        let text = format!("{}\n\nWas it a surprise?\n\nYes\n\nYes\n\n{}\n\nThe count  ended  at midnight.\n\nThe count ended at midnight.", quote, quote);
+
+        assert_eq!(
+            remove_repeated_paragraphs(&text),
+            format!("{}\n\nWas it a surprise?\n\nYes\n\nYes\n\nThe count  ended  at midnight.", quote)
+        );
+    }
+
+    #[test]
+    fn test_invalid_selector_does_not_panic() {
+        assert!(matches!(create_selector("p["), Err(TrafilaturaError::SelectorError(_))));
+        assert!(try_selector("p[").is_none());
+        assert!(try_selector("p").is_some());
+    }
+}
@@ -0,0 +1,105 @@
+//! Directory watching for Trafilatura Rust port.
+//! This module monitors a directory and reports HTML files as they appear,
+//! so documents dropped into an inbox can be extracted without external tooling.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, SystemTime};
+
+use tracing::{debug, warn};
+use notify::event::{AccessKind, AccessMode, CreateKind, ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+use crate::shutdown::ShutdownSignal;
+use crate::utils::is_html_file;
+use crate::TrafilaturaError;
+
+/// How often the watcher checks for a shutdown request while idle
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Watch a directory and call `handler` for every new or replaced HTML file.
+///
+/// Files are reported once they are complete: on close after writing or when
+/// moved into the directory. Empty files and repeated events for an unchanged
+/// file are skipped. Runs until `shutdown` is requested, the watcher fails, or
+/// `handler` returns an error; a file being handled is always finished first.
+pub fn watch_directory<F>(dir: &Path, shutdown: &ShutdownSignal, mut handler: F) -> Result<(), TrafilaturaError>
+where
+    F: FnMut(&Path) -> Result<(), TrafilaturaError>,
+{
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    debug!("Watching {} for new HTML files", dir.display());
+
+    // Last seen modification time and size per file
+    let mut seen: HashMap<PathBuf, (SystemTime, u64)> = HashMap::new();
+
+    while !shutdown.is_requested() {
+        let event = match receiver.recv_timeout(SHUTDOWN_POLL_INTERVAL) {
+            Ok(Ok(event)) => event,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+            Ok(Err(e)) => {
+                warn!("Watch error: {}", e);
+                continue;
+            }
+        };
+
+        if !is_complete_file_event(&event) {
+            continue;
+        }
+
+        for path in event.paths.iter().filter(|path| is_html_file(path)) {
+            let metadata = match path.metadata() {
+                Ok(metadata) if metadata.is_file() && metadata.len() > 0 => metadata,
+                _ => continue,
+            };
+
+            let stamp = (metadata.modified()?, metadata.len());
+            if seen.get(path) == Some(&stamp) {
+                continue;
+            }
+            seen.insert(path.clone(), stamp);
+
+            debug!("New HTML file: {}", path.display());
+            handler(path)?;
+
+            if shutdown.is_requested() {
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Check if an event signals that a file is ready to be read
+fn is_complete_file_event(event: &Event) -> bool {
+    matches!(
+        event.kind,
+        EventKind::Access(AccessKind::Close(AccessMode::Write))
+            | EventKind::Modify(ModifyKind::Name(RenameMode::To | RenameMode::Both))
+            // Backends without close events only report creation
+            | EventKind::Create(CreateKind::File | CreateKind::Any)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete_file_events() {
+        let closed = Event::new(EventKind::Access(AccessKind::Close(AccessMode::Write)));
+        let renamed = Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::To)));
+        let opened = Event::new(EventKind::Access(AccessKind::Open(AccessMode::Any)));
+        let removed = Event::new(EventKind::Remove(notify::event::RemoveKind::File));
+
+        assert!(is_complete_file_event(&closed));
+        assert!(is_complete_file_event(&renamed));
+        assert!(!is_complete_file_event(&opened));
+        assert!(!is_complete_file_event(&removed));
+    }
+}
@@ -0,0 +1,113 @@
+//! Wayback Machine support for Trafilatura Rust port.
+//! This module queries the Internet Archive availability API so that dead or
+//! paywalled URLs can be extracted from their latest archived snapshot.
+
+use serde::Deserialize;
+use url::Url;
+
+use crate::TrafilaturaError;
+
+/// Endpoint of the Wayback Machine availability API
+const AVAILABILITY_ENDPOINT: &str = "https://archive.org/wayback/available";
+
+/// HTTP status codes that trigger the Wayback Machine fallback:
+/// payment required (paywall), not found, and gone
+pub const FALLBACK_STATUSES: [u16; 3] = [402, 404, 410];
+
+/// An archived snapshot of a URL
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    /// URL of the snapshot on web.archive.org
+    pub url: String,
+    /// Capture timestamp in the Wayback format (`YYYYMMDDhhmmss`)
+    pub timestamp: String,
+}
+
+impl Snapshot {
+    /// URL of the original archived HTML, without the Wayback toolbar and link rewriting
+    pub fn raw_url(&self) -> String {
+        let marker = format!("/{}/", self.timestamp);
+        self.url.replacen(&marker, &format!("/{}id_/", self.timestamp), 1)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AvailabilityResponse {
+    #[serde(default)]
+    archived_snapshots: ArchivedSnapshots,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ArchivedSnapshots {
+    closest: Option<ClosestSnapshot>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClosestSnapshot {
+    #[serde(default)]
+    available: bool,
+    url: String,
+    timestamp: String,
+    #[serde(default)]
+    status: Option<String>,
+}
+
+/// Build the availability API query for a URL
+pub fn availability_url(url: &str) -> Result<Url, TrafilaturaError> {
+    Ok(Url::parse_with_params(AVAILABILITY_ENDPOINT, &[("url", url)])?)
+}
+
+/// Parse an availability API response into the closest usable snapshot
+pub fn parse_availability(json: &str) -> Result<Option<Snapshot>, TrafilaturaError> {
+    let response: AvailabilityResponse = serde_json::from_str(json)?;
+
+    let snapshot = response.archived_snapshots.closest
+        .filter(|closest| closest.available)
+        // Skip captures of error pages
+        .filter(|closest| closest.status.as_deref().is_none_or(|status| status.starts_with('2')))
+        .map(|closest| Snapshot {
+            url: closest.url,
+            timestamp: closest.timestamp,
+        });
+
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_availability() {
+        let json = r#"{"url": "example.com/gone", "archived_snapshots": {"closest": {
+            "status": "200", "available": true,
+            "url": "http://web.archive.org/web/20130919044612/http://example.com/gone",
+            "timestamp": "20130919044612"}}}"#;
+
+        let snapshot = parse_availability(json).unwrap().unwrap();
+
+        assert_eq!(snapshot.timestamp, "20130919044612");
+        assert_eq!(
+            snapshot.raw_url(),
+            "http://web.archive.org/web/20130919044612id_/http://example.com/gone"
+        );
+    }
+
+    #[test]
+    fn test_parse_availability_without_snapshot() {
+        assert_eq!(parse_availability(r#"{"url": "x", "archived_snapshots": {}}"#).unwrap(), None);
+
+        let error_capture = r#"{"archived_snapshots": {"closest": {"status": "404", "available": true,
+            "url": "http://web.archive.org/web/2020/http://x/", "timestamp": "2020"}}}"#;
+        assert_eq!(parse_availability(error_capture).unwrap(), None);
+    }
+
+    #[test]
+    fn test_availability_url() {
+        let url = availability_url("https://example.com/a?b=c").unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://archive.org/wayback/available?url=https%3A%2F%2Fexample.com%2Fa%3Fb%3Dc"
+        );
+    }
+}
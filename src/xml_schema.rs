@@ -0,0 +1,365 @@
+//! XML output schema for Trafilatura Rust port.
+//! This module describes the `<document>` layout of `OutputFormat::Xml` as an XSD,
+//! for external validators such as `xmllint --schema`, and checks XML against the
+//! same rules in code, so tests and pipelines can verify emitted XML without a
+//! schema processor. Python trafilatura's `<doc>` layout is not covered.
+
+use roxmltree::{Document, Node};
+
+use crate::TrafilaturaError;
+
+/// Type of the value of a metadata field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldType {
+    Text,
+    /// Four-digit year
+    Year,
+    /// `low`, `medium`, or `high`
+    Quality,
+}
+
+/// Scalar metadata fields in output order, written as attributes of `<document>`
+/// or as elements after the content, see `XmlMetadata`
+const METADATA_FIELDS: &[(&str, FieldType)] = &[
+    ("title", FieldType::Text),
+    ("author", FieldType::Text),
+    ("author_url", FieldType::Text),
+    ("date", FieldType::Text),
+    ("url", FieldType::Text),
+    ("canonical_url", FieldType::Text),
+    ("description", FieldType::Text),
+    ("sitename", FieldType::Text),
+    ("publisher", FieldType::Text),
+    ("copyright_year", FieldType::Year),
+    ("og_type", FieldType::Text),
+    ("language", FieldType::Text),
+    ("quality", FieldType::Quality),
+    ("wayback_timestamp", FieldType::Text),
+];
+
+/// Children of `<academic>` in output order; `author` repeats
+const ACADEMIC_FIELDS: &[&str] = &[
+    "doi", "title", "journal_title", "publisher", "publication_date", "volume", "issue",
+    "first_page", "last_page", "issn", "arxiv_id", "pdf_url", "author",
+];
+
+/// Elements of `<document>` before and after the metadata elements
+const LEADING_ELEMENTS: &[&str] = &["content", "comments"];
+const TRAILING_ELEMENTS: &[&str] = &["categories", "academic", "blocks", "alternates", "article_links"];
+
+/// XSD of XML output. Metadata is either in attributes of `<document>`, with the
+/// categories joined by `;`, or in elements; the two layouts share one schema.
+pub const XML_SCHEMA: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" elementFormDefault="qualified">
+  <xs:simpleType name="year">
+    <xs:restriction base="xs:unsignedShort"/>
+  </xs:simpleType>
+  <xs:simpleType name="quality">
+    <xs:restriction base="xs:string">
+      <xs:enumeration value="low"/>
+      <xs:enumeration value="medium"/>
+      <xs:enumeration value="high"/>
+    </xs:restriction>
+  </xs:simpleType>
+  <xs:element name="document">
+    <xs:complexType>
+      <xs:sequence>
+        <xs:element name="content" type="xs:string"/>
+        <xs:element name="comments" type="xs:string" minOccurs="0"/>
+        <xs:element name="title" type="xs:string" minOccurs="0"/>
+        <xs:element name="author" type="xs:string" minOccurs="0"/>
+        <xs:element name="author_url" type="xs:string" minOccurs="0"/>
+        <xs:element name="date" type="xs:string" minOccurs="0"/>
+        <xs:element name="url" type="xs:string" minOccurs="0"/>
+        <xs:element name="canonical_url" type="xs:string" minOccurs="0"/>
+        <xs:element name="description" type="xs:string" minOccurs="0"/>
+        <xs:element name="sitename" type="xs:string" minOccurs="0"/>
+        <xs:element name="publisher" type="xs:string" minOccurs="0"/>
+        <xs:element name="copyright_year" type="year" minOccurs="0"/>
+        <xs:element name="og_type" type="xs:string" minOccurs="0"/>
+        <xs:element name="language" type="xs:string" minOccurs="0"/>
+        <xs:element name="quality" type="quality" minOccurs="0"/>
+        <xs:element name="wayback_timestamp" type="xs:string" minOccurs="0"/>
+        <xs:element name="categories" minOccurs="0">
+          <xs:complexType>
+            <xs:sequence>
+              <xs:element name="category" type="xs:string" maxOccurs="unbounded"/>
+            </xs:sequence>
+          </xs:complexType>
+        </xs:element>
+        <xs:element name="academic" minOccurs="0">
+          <xs:complexType>
+            <xs:sequence>
+              <xs:element name="doi" type="xs:string" minOccurs="0"/>
+              <xs:element name="title" type="xs:string" minOccurs="0"/>
+              <xs:element name="journal_title" type="xs:string" minOccurs="0"/>
+              <xs:element name="publisher" type="xs:string" minOccurs="0"/>
+              <xs:element name="publication_date" type="xs:string" minOccurs="0"/>
+              <xs:element name="volume" type="xs:string" minOccurs="0"/>
+              <xs:element name="issue" type="xs:string" minOccurs="0"/>
+              <xs:element name="first_page" type="xs:string" minOccurs="0"/>
+              <xs:element name="last_page" type="xs:string" minOccurs="0"/>
+              <xs:element name="issn" type="xs:string" minOccurs="0"/>
+              <xs:element name="arxiv_id" type="xs:string" minOccurs="0"/>
+              <xs:element name="pdf_url" type="xs:string" minOccurs="0"/>
+              <xs:element name="author" type="xs:string" minOccurs="0" maxOccurs="unbounded"/>
+            </xs:sequence>
+          </xs:complexType>
+        </xs:element>
+        <xs:element name="blocks" minOccurs="0">
+          <xs:complexType>
+            <xs:sequence>
+              <xs:element name="block" maxOccurs="unbounded">
+                <xs:complexType>
+                  <xs:simpleContent>
+                    <xs:extension base="xs:string">
+                      <xs:attribute name="language" type="xs:string"/>
+                    </xs:extension>
+                  </xs:simpleContent>
+                </xs:complexType>
+              </xs:element>
+            </xs:sequence>
+          </xs:complexType>
+        </xs:element>
+        <xs:element name="alternates" minOccurs="0">
+          <xs:complexType>
+            <xs:sequence>
+              <xs:element name="alternate" maxOccurs="unbounded">
+                <xs:complexType>
+                  <xs:simpleContent>
+                    <xs:extension base="xs:string">
+                      <xs:attribute name="hreflang" type="xs:string" use="required"/>
+                    </xs:extension>
+                  </xs:simpleContent>
+                </xs:complexType>
+              </xs:element>
+            </xs:sequence>
+          </xs:complexType>
+        </xs:element>
+        <xs:element name="article_links" minOccurs="0">
+          <xs:complexType>
+            <xs:sequence>
+              <xs:element name="article_link" maxOccurs="unbounded">
+                <xs:complexType>
+                  <xs:simpleContent>
+                    <xs:extension base="xs:string">
+                      <xs:attribute name="url" type="xs:string" use="required"/>
+                    </xs:extension>
+                  </xs:simpleContent>
+                </xs:complexType>
+              </xs:element>
+            </xs:sequence>
+          </xs:complexType>
+        </xs:element>
+      </xs:sequence>
+      <xs:attribute name="title" type="xs:string"/>
+      <xs:attribute name="author" type="xs:string"/>
+      <xs:attribute name="author_url" type="xs:string"/>
+      <xs:attribute name="date" type="xs:string"/>
+      <xs:attribute name="url" type="xs:string"/>
+      <xs:attribute name="canonical_url" type="xs:string"/>
+      <xs:attribute name="description" type="xs:string"/>
+      <xs:attribute name="sitename" type="xs:string"/>
+      <xs:attribute name="publisher" type="xs:string"/>
+      <xs:attribute name="copyright_year" type="year"/>
+      <xs:attribute name="og_type" type="xs:string"/>
+      <xs:attribute name="language" type="xs:string"/>
+      <xs:attribute name="quality" type="quality"/>
+      <xs:attribute name="wayback_timestamp" type="xs:string"/>
+      <xs:attribute name="categories" type="xs:string"/>
+    </xs:complexType>
+  </xs:element>
+</xs:schema>
+"#;
+
+/// Check XML output against `XML_SCHEMA`, failing with
+/// `TrafilaturaError::XmlSchemaError` on the first violation
+pub fn validate_xml(xml: &str) -> Result<(), TrafilaturaError> {
+    let document = Document::parse(xml).map_err(|e| invalid(format!("not well-formed: {}", e)))?;
+    let root = document.root_element();
+    if root.tag_name().name() != "document" {
+        return Err(invalid(format!("root element is <{}>, not <document>", root.tag_name().name())));
+    }
+
+    for attribute in root.attributes() {
+        match field_type(attribute.name()) {
+            Some(field_type) => check_value(attribute.name(), attribute.value(), field_type)?,
+            None if attribute.name() == "categories" => {},
+            None => return Err(invalid(format!("unknown attribute {} of <document>", attribute.name()))),
+        }
+    }
+
+    // Each child appears at most once, in the order of the schema
+    let mut next = 0;
+    for child in elements(root)? {
+        let name = child.tag_name().name();
+        let position = document_position(name).ok_or_else(|| invalid(format!("unknown element <{}>", name)))?;
+        if position < next {
+            return Err(invalid(format!("<{}> out of order or repeated", name)));
+        }
+        next = position + 1;
+        if root.has_attribute(name) {
+            return Err(invalid(format!("{} is both an attribute and an element", name)));
+        }
+
+        match name {
+            "categories" => check_list(child, "category", None)?,
+            "academic" => check_academic(child)?,
+            "blocks" => check_list(child, "block", Some(("language", false)))?,
+            "alternates" => check_list(child, "alternate", Some(("hreflang", true)))?,
+            "article_links" => check_list(child, "article_link", Some(("url", true)))?,
+            _ => {
+                let text = check_text(child, None)?;
+                if let Some(field_type) = field_type(name) {
+                    check_value(name, text, field_type)?;
+                }
+            },
+        }
+    }
+    if next == 0 {
+        return Err(invalid("missing <content>".into()));
+    }
+    Ok(())
+}
+
+fn invalid(message: String) -> TrafilaturaError {
+    TrafilaturaError::XmlSchemaError(message)
+}
+
+fn field_type(name: &str) -> Option<FieldType> {
+    METADATA_FIELDS.iter().find(|(field, _)| *field == name).map(|&(_, field_type)| field_type)
+}
+
+/// Position of a child of `<document>` in the schema
+fn document_position(name: &str) -> Option<usize> {
+    LEADING_ELEMENTS.iter().copied()
+        .chain(METADATA_FIELDS.iter().map(|&(field, _)| field))
+        .chain(TRAILING_ELEMENTS.iter().copied())
+        .position(|element| element == name)
+}
+
+fn check_value(name: &str, value: &str, field_type: FieldType) -> Result<(), TrafilaturaError> {
+    let valid = match field_type {
+        FieldType::Text => true,
+        FieldType::Year => value.parse::<u16>().is_ok(),
+        FieldType::Quality => matches!(value, "low" | "medium" | "high"),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(invalid(format!("invalid {} value {:?}", name, value)))
+    }
+}
+
+/// The child elements of a node, which may not hold text between them
+fn elements<'a, 'input>(node: Node<'a, 'input>) -> Result<Vec<Node<'a, 'input>>, TrafilaturaError> {
+    let mut elements = Vec::new();
+    for child in node.children() {
+        if child.is_element() {
+            elements.push(child);
+        } else if child.is_text() && !child.text().unwrap_or_default().trim().is_empty() {
+            return Err(invalid(format!("text directly inside <{}>", node.tag_name().name())));
+        }
+    }
+    Ok(elements)
+}
+
+/// Check an element holding only text, with an optional attribute given as its
+/// name and whether it is required, and return the text
+fn check_text<'a>(node: Node<'a, '_>, attribute: Option<(&str, bool)>) -> Result<&'a str, TrafilaturaError> {
+    let name = node.tag_name().name();
+    if node.children().any(|child| child.is_element()) {
+        return Err(invalid(format!("<{}> holds elements", name)));
+    }
+    for present in node.attributes() {
+        if attribute.is_none_or(|(allowed, _)| allowed != present.name()) {
+            return Err(invalid(format!("unknown attribute {} of <{}>", present.name(), name)));
+        }
+    }
+    if let Some((required, true)) = attribute {
+        if !node.has_attribute(required) {
+            return Err(invalid(format!("<{}> without {} attribute", name, required)));
+        }
+    }
+    Ok(node.text().unwrap_or_default())
+}
+
+/// Check a list of text elements named `item`
+fn check_list(node: Node, item: &str, attribute: Option<(&str, bool)>) -> Result<(), TrafilaturaError> {
+    let items = elements(node)?;
+    if items.is_empty() {
+        return Err(invalid(format!("empty <{}>", node.tag_name().name())));
+    }
+    for child in items {
+        if child.tag_name().name() != item {
+            return Err(invalid(format!("<{}> inside <{}>", child.tag_name().name(), node.tag_name().name())));
+        }
+        check_text(child, attribute)?;
+    }
+    Ok(())
+}
+
+fn check_academic(node: Node) -> Result<(), TrafilaturaError> {
+    let mut next = 0;
+    for child in elements(node)? {
+        let name = child.tag_name().name();
+        let position = ACADEMIC_FIELDS.iter().position(|&field| field == name)
+            .ok_or_else(|| invalid(format!("<{}> inside <academic>", name)))?;
+        if position < next && name != "author" {
+            return Err(invalid(format!("<{}> out of order or repeated in <academic>", name)));
+        }
+        next = position + 1;
+        check_text(child, None)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_lists_every_field() {
+        for (field, _) in METADATA_FIELDS {
+            assert!(XML_SCHEMA.contains(&format!("<xs:element name=\"{}\"", field)), "{}", field);
+            assert!(XML_SCHEMA.contains(&format!("<xs:attribute name=\"{}\"", field)), "{}", field);
+        }
+        for field in ACADEMIC_FIELDS {
+            assert!(XML_SCHEMA.contains(&format!("<xs:element name=\"{}\"", field)), "{}", field);
+        }
+    }
+
+    #[test]
+    fn test_validate_xml() {
+        let valid = [
+            "<document><content>Text</content></document>",
+            "<document title=\"T\" copyright_year=\"2024\" categories=\"a;b\"><content/><comments>C</comments></document>",
+            "<document><content><![CDATA[a < b]]></content><title>T</title><quality>high</quality>\
+             <categories><category>a</category></categories><academic><doi>10.1/x</doi><author>A</author><author>B</author></academic>\
+             <alternates><alternate hreflang=\"de\">https://example.de/</alternate></alternates></document>",
+        ];
+        for xml in valid {
+            assert!(validate_xml(xml).is_ok(), "{}: {:?}", xml, validate_xml(xml));
+        }
+
+        let invalid = [
+            "<document><content>Unclosed</document>",
+            "<doc><main/></doc>",
+            "<document/>",
+            "<document color=\"red\"><content/></document>",
+            "<document><content/><content/></document>",
+            "<document><title>T</title><content/></document>",
+            "<document title=\"T\"><content/><title>T</title></document>",
+            "<document><content/><copyright_year>soon</copyright_year></document>",
+            "<document quality=\"great\"><content/></document>",
+            "<document><content/>stray text</document>",
+            "<document><content><b>bold</b></content></document>",
+            "<document><content/><categories><tag>a</tag></categories></document>",
+            "<document><content/><alternates><alternate>https://example.de/</alternate></alternates></document>",
+            "<document><content/><academic><author>A</author><doi>10.1/x</doi></academic></document>",
+        ];
+        for xml in invalid {
+            assert!(matches!(validate_xml(xml), Err(TrafilaturaError::XmlSchemaError(_))), "{}", xml);
+        }
+    }
+}
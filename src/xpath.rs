@@ -1,34 +1,27 @@
 //! CSS selector extraction module for Trafilatura Rust port.
 //! This module uses CSS selectors for HTML element selection.
-//! The module name remains xpath.rs for compatibility with the original design.
+//! The module name remains xpath.rs for compatibility with the original design;
+//! user-supplied XPath rules for the content container are evaluated by `xpath_expr`.
 
-use log::debug;
-use scraper::{Html, Selector, ElementRef};
-use regex::Regex;
+use std::collections::HashSet;
 
-use crate::ExtractionConfig;
+use ego_tree::NodeId;
+use lazy_static::lazy_static;
+use tracing::debug;
+use scraper::{Html, ElementRef, Selector};
+
+use crate::{ExtractionConfig, ExtractionMode, Focus};
 use crate::TrafilaturaError;
+use crate::html::{ancestors_within, css_path, element_text_length, has_role, is_stripped_tag, parse_document, prune_link_blocks, BOILERPLATE_ROLES};
+use crate::document::Document;
+use crate::render::ContentRenderer;
+use crate::utils::try_selector;
+use crate::xpath_expr::XPathExpr;
 
 /// CSS selectors used for content extraction
 pub struct XPaths {
     /// CSS selector for the main content area
     pub main_content: &'static str,
-    /// CSS selector for paragraphs
-    pub paragraphs: &'static str,
-    /// CSS selector for headings
-    pub headings: &'static str,
-    /// CSS selector for lists
-    pub lists: &'static str,
-    /// CSS selector for list items
-    pub list_items: &'static str,
-    /// CSS selector for tables
-    pub tables: &'static str,
-    /// CSS selector for images
-    pub images: &'static str,
-    /// CSS selector for captions
-    pub captions: &'static str,
-    /// CSS selector for anchors/links
-    pub anchors: &'static str,
 }
 
 /// Default CSS selector expressions for content extraction
@@ -86,35 +79,215 @@ pub const DEFAULT_XPATHS: XPaths = XPaths {
         "section[class^='main'], section[id^='main'], section[role^='main'], ",
         "div[class^='main'], div[id^='main'], div[role^='main']"
     ),
-    paragraphs: "p, div[class*='paragraph'], div[class*='text-block'], div[class*='post-block'], div[class*='entry-block'], div.post-text, div.text, div[class*='article-text'], section[class*='paragraph']",
-    headings: "h1, h2, h3, h4, h5, h6",
-    lists: "ul, ol, dl",
-    list_items: "li, dt, dd",
-    tables: "table",
-    images: "img",
-    captions: "figcaption",
-    anchors: "a",
 };
 
 /// Wikipedia-specific CSS selector expressions for content extraction
 pub const WIKI_XPATHS: XPaths = XPaths {
     main_content: "div#content, div#bodyContent, div#mw-content-text, div.mw-parser-output",
-    paragraphs: "div#mw-content-text p, div.mw-parser-output p, .mw-parser-output .mw-empty-elt",
-    headings: "div#mw-content-text h1, div#mw-content-text h2, div#mw-content-text h3, div#mw-content-text h4, div#mw-content-text h5, div#mw-content-text h6, div.mw-parser-output h1, div.mw-parser-output h2, div.mw-parser-output h3, div.mw-parser-output h4, div.mw-parser-output h5, div.mw-parser-output h6",
-    lists: "div#mw-content-text ul, div#mw-content-text ol, div.mw-parser-output ul, div.mw-parser-output ol",
-    list_items: "div#mw-content-text li, div.mw-parser-output li",
-    tables: "div#mw-content-text table, div.mw-parser-output table",
-    images: "div#mw-content-text img, div.mw-parser-output img",
-    captions: "div#mw-content-text figcaption, div.mw-parser-output figcaption",
-    anchors: "div#mw-content-text a, div.mw-parser-output a",
 };
 
-/// Helper function to create a Selector from a CSS selector string
-pub fn create_selector(selector: &str) -> Result<Selector, TrafilaturaError> {
-    Selector::parse(selector)
-        .map_err(|e| TrafilaturaError::SelectorError(format!("Invalid selector: {} - {:?}", selector, e)))
+/// Paragraphs with at most this weighted length are left out, except in documentation mode
+const MIN_PARAGRAPH_LENGTH: usize = 10;
+
+/// Boilerplate modules nested in content containers: share bars, related-article
+/// modules, inline newsletter sign-ups, and ad slots
+pub const DISCARD_SELECTORS: &str = concat!(
+    // Share bars
+    "[class*='share-bar'], [class*='sharebar'], [class*='share-buttons'], [class*='social-share'], ",
+    "[class*='sharing'], [class*='addthis'], ",
+    
+    // Related-article modules
+    "[class*='related-articles'], [class*='related-posts'], [class*='related-content'], ",
+    "[class*='more-stories'], [class*='recommended'], [class*='read-more-links'], ",
+    
+    // Inline newsletter sign-ups
+    "[class*='newsletter'], [id*='newsletter'], [class*='signup-form'], [class*='subscribe-box'], ",
+    
+    // Ad slots
+    "[class*='ad-slot'], [class*='adslot'], [class*='ad-container'], [class*='ad-wrapper'], ",
+    "[id^='div-gpt-ad'], [id^='google_ads'], ins.adsbygoogle, [data-ad-slot], [data-ad-unit]"
+);
+
+/// Content containers of documentation generators in priority order: MkDocs Material,
+/// Docusaurus, Sphinx and Read the Docs, then generic main areas
+pub const DOCS_MAIN_CONTENT: [&str; 7] = [
+    ".md-content", ".theme-doc-markdown", "main[role='main']", "div[role='main']",
+    "div.document", "main", "article",
+];
+
+/// Content containers, ported from Python trafilatura's `BODY_XPATH` and tried in order
+pub const BODY_XPATH: [&str; 5] = [
+    r#".//*[self::article or self::div or self::main or self::section][
+    @class="post" or @class="entry" or
+    contains(@class, "post-text") or contains(@class, "post_text") or
+    contains(@class, "post-body") or contains(@class, "post-entry") or contains(@class, "postentry") or
+    contains(@class, "post-content") or contains(@class, "post_content") or
+    contains(@class, "postcontent") or contains(@class, "postContent") or contains(@class, "post_inner_wrapper") or
+    contains(@class, "article-text") or contains(@class, "articletext") or contains(@class, "articleText") or
+    contains(@id, "entry-content") or contains(@class, "entry-content") or
+    contains(@id, "article-content") or contains(@class, "article-content") or
+    contains(@id, "article__content") or contains(@class, "article__content") or
+    contains(@id, "article-body") or contains(@class, "article-body") or
+    contains(@id, "article__body") or contains(@class, "article__body") or @itemprop="articleBody" or
+    contains(translate(@id, "B", "b"), "articlebody") or contains(translate(@class, "B", "b"), "articlebody") or
+    @id="articleContent" or contains(@class, "ArticleContent") or
+    contains(@class, "page-content") or contains(@class, "text-content") or
+    contains(@id, "body-text") or contains(@class, "body-text") or
+    contains(@class, "article__container") or contains(@id, "art-content") or contains(@class, "art-content")][1]"#,
+    "(.//article)[1]",
+    r#"(.//*[self::article or self::div or self::main or self::section][
+    contains(@class, "post-bodycopy") or
+    contains(@class, "storycontent") or contains(@class, "story-content") or
+    @class="postarea" or @class="art-postcontent" or
+    contains(@class, "theme-content") or contains(@class, "blog-content") or
+    contains(@class, "section-content") or contains(@class, "single-content") or
+    contains(@class, "single-post") or
+    contains(@class, "main-column") or contains(@class, "wpb_text_column") or
+    starts-with(@id, "primary") or starts-with(@class, "article ") or @class="text" or
+    @id="article" or @class="cell" or @id="story" or @class="story" or
+    contains(@class, "story-body") or contains(@id, "story-body") or contains(@class, "field-body") or
+    contains(translate(@class, "FULTEX", "fultex"), "fulltext") or @role="article"])[1]"#,
+    r#"(.//*[self::article or self::div or self::main or self::section][
+    contains(@id, "content-main") or contains(@class, "content-main") or contains(@class, "content_main") or
+    contains(@id, "content-body") or contains(@class, "content-body") or contains(@id, "contentBody") or
+    contains(@class, "content__body") or
+    contains(translate(@id, "CM", "cm"), "main-content") or contains(translate(@class, "CM", "cm"), "main-content") or
+    contains(translate(@class, "CP", "cp"), "page-content") or
+    @id="content" or @class="content"])[1]"#,
+    r#"(.//*[self::article or self::div or self::section][
+    starts-with(@class, "main") or starts-with(@id, "main") or starts-with(@role, "main")])[1]|(.//main)[1]"#,
+];
+
+/// Comments sections, ported from Python trafilatura's `COMMENTS_XPATH` and tried in order
+pub const COMMENTS_XPATH: [&str; 4] = [
+    r#".//*[self::div or self::ol or self::ul or self::section][
+    contains(@id|@class, "commentlist") or contains(@class, "comment-page") or
+    contains(@id|@class, "comment-list") or
+    contains(@class, "comments-content") or contains(@class, "post-comments")]"#,
+    r#".//*[self::div or self::section or self::ol or self::ul][
+    starts-with(@id|@class, "comments") or starts-with(@class, "Comments") or
+    starts-with(@id|@class, "comment-") or contains(@class, "article-comments")]"#,
+    r#".//*[self::div or self::section or self::ol or self::ul][
+    starts-with(@id, "comol") or starts-with(@id, "disqus_thread") or starts-with(@id, "dsq-comments")]"#,
+    r#".//*[self::div or self::section][starts-with(@id, "social") or contains(@class, "comment")]"#,
+];
+
+/// Comments sections removed from the main content, from Python trafilatura's `REMOVE_COMMENTS_XPATH`
+pub const REMOVE_COMMENTS_XPATH: [&str; 1] = [
+    r#".//*[self::div or self::ol or self::ul or self::section][
+    starts-with(translate(@id, "C", "c"), "comment") or
+    starts-with(translate(@class, "C", "c"), "comment") or
+    contains(@class, "article-comments") or contains(@class, "post-comments") or
+    starts-with(@id, "comol") or starts-with(@id, "disqus_thread") or starts-with(@id, "dsq-comments")]"#,
+];
+
+/// Boilerplate inside the main content, from Python trafilatura's `OVERALL_DISCARD_XPATH`:
+/// footers, related posts, sharing and navigation, then comment debris and hidden parts
+pub const OVERALL_DISCARD_XPATH: [&str; 2] = [
+    r#".//*[self::div or self::li or self::ol or self::ul or self::p or self::section or self::span][
+    contains(translate(@id, "F", "f"), "footer") or contains(translate(@class, "F", "f"), "footer") or
+    contains(@id, "related") or contains(translate(@class, "R", "r"), "related") or
+    contains(@id, "viral") or contains(@class, "viral") or
+    starts-with(@id, "shar") or starts-with(@class, "shar") or
+    contains(@class, "share-") or contains(translate(@id, "S", "s"), "share") or
+    contains(@id, "social") or contains(@class, "social") or contains(@class, "sociable") or
+    contains(@id, "syndication") or contains(@class, "syndication") or
+    starts-with(@id, "jp-") or starts-with(@id, "dpsp-content") or
+    contains(@class, "embedded") or contains(@class, "embed") or
+    contains(@id, "newsletter") or contains(@class, "newsletter") or
+    contains(@class, "subnav") or
+    contains(@id, "cookie") or contains(@class, "cookie") or
+    contains(@id, "tags") or contains(@class, "tags") or
+    contains(@id, "sidebar") or contains(@class, "sidebar") or
+    contains(@id, "banner") or contains(@class, "banner") or
+    contains(@class, "meta") or
+    contains(@id, "menu") or contains(@class, "menu") or
+    contains(translate(@id, "N", "n"), "nav") or contains(translate(@role, "N", "n"), "nav") or
+    starts-with(@class, "nav") or contains(translate(@class, "N", "n"), "navigation") or
+    contains(@class, "navbar") or contains(@class, "navbox") or starts-with(@class, "post-nav") or
+    contains(@id, "breadcrumb") or contains(@class, "breadcrumb") or
+    contains(@id, "bread-crumb") or contains(@class, "bread-crumb") or
+    contains(@id, "author") or contains(@class, "author") or
+    contains(@id, "button") or contains(@class, "button") or
+    contains(translate(@class, "B", "b"), "byline") or
+    contains(@class, "rating") or starts-with(@class, "widget") or
+    contains(@class, "attachment") or contains(@class, "timestamp") or
+    contains(@class, "user-info") or contains(@class, "user-profile") or
+    contains(@class, "-ad-") or contains(@class, "-icon") or
+    contains(@class, "article-infos") or contains(translate(@class, "I", "i"), "infoline") or
+    contains(@data-component, "MostPopularStories") or
+    contains(@class, "outbrain") or contains(@class, "taboola") or contains(@class, "criteo") or
+    contains(@class, "options") or contains(@class, "consent") or contains(@class, "modal-content") or
+    contains(@class, "paid-content") or contains(@class, "paidcontent") or
+    contains(@id, "premium-") or contains(@id, "paywall") or
+    contains(@class, "obfuscated") or contains(@class, "blurred") or
+    contains(concat(" ", @class, " "), " ad ") or
+    contains(@class, "next-post") or contains(@class, "side-stories") or
+    contains(@class, "related-stories") or contains(@class, "most-popular") or
+    contains(@class, "mol-factbox") or starts-with(@class, "ZendeskForm") or
+    contains(@class, "message-container") or contains(@id, "message_container") or
+    @data-lp-replacement-content]"#,
+    r#".//*[@class="comments-title" or contains(@class, "comments-title") or
+    contains(@class, "nocomments") or starts-with(@id, "reply-") or starts-with(@class, "reply-") or
+    contains(@class, "-reply-") or contains(@class, "message") or
+    contains(@id, "akismet") or contains(@class, "akismet") or
+    starts-with(@class, "hide-") or contains(@class, "hide-print") or contains(@id, "hidden") or
+    contains(@style, "hidden") or @hidden or contains(@class, "noprint") or
+    contains(translate(@style, " ", ""), "display:none") or contains(concat(" ", @class, " "), " hidden ") or
+    @aria-hidden="true" or contains(@class, "notloaded")]"#,
+];
+
+/// Teasers, discarded unless recall is favored, from Python trafilatura's `TEASER_DISCARD_XPATH`
+pub const TEASER_DISCARD_XPATH: [&str; 1] = [
+    r#".//*[self::div or self::li or self::ol or self::ul or self::p or self::section or self::span][
+    contains(translate(@id, "T", "t"), "teaser") or contains(translate(@class, "T", "t"), "teaser")]"#,
+];
+
+/// Parts discarded when precision is favored, from Python trafilatura's `PRECISION_DISCARD_XPATH`
+pub const PRECISION_DISCARD_XPATH: [&str; 2] = [
+    ".//header",
+    r#".//*[self::div or self::li or self::ol or self::ul or self::p or self::section or self::span][
+    contains(@id, "bottom") or contains(@class, "bottom") or
+    contains(@id, "link") or contains(@class, "link") or contains(@style, "border")]"#,
+];
+
+/// Image captions, discarded without images, from Python trafilatura's `DISCARD_IMAGE_ELEMENTS`
+pub const DISCARD_IMAGE_ELEMENTS: [&str; 1] = [
+    r#".//*[self::div or self::li or self::ol or self::ul or self::p or self::section or self::span][
+    contains(@id, "caption") or contains(@class, "caption")]"#,
+];
+
+/// Reply forms and debris inside comments sections, from Python trafilatura's `COMMENTS_DISCARD_XPATH`
+pub const COMMENTS_DISCARD_XPATH: [&str; 3] = [
+    r#".//*[self::div or self::section][starts-with(@id, "respond")]"#,
+    ".//cite|.//q",
+    r#".//*[@class="comments-title" or contains(@class, "comments-title") or
+    contains(@class, "nocomments") or starts-with(@id, "reply-") or
+    starts-with(@class, "reply-") or contains(@class, "-reply-") or contains(@class, "message") or
+    contains(@class, "signin") or contains(@id, "akismet") or contains(@class, "akismet") or
+    contains(translate(@style, " ", ""), "display:none")]"#,
+];
+
+lazy_static! {
+    static ref BODY_RULES: Vec<XPathExpr> = compile(&BODY_XPATH);
+    static ref COMMENTS_RULES: Vec<XPathExpr> = compile(&COMMENTS_XPATH);
+    static ref REMOVE_COMMENTS_RULES: Vec<XPathExpr> = compile(&REMOVE_COMMENTS_XPATH);
+    static ref OVERALL_DISCARD_RULES: Vec<XPathExpr> = compile(&OVERALL_DISCARD_XPATH);
+    static ref TEASER_DISCARD_RULES: Vec<XPathExpr> = compile(&TEASER_DISCARD_XPATH);
+    static ref PRECISION_DISCARD_RULES: Vec<XPathExpr> = compile(&PRECISION_DISCARD_XPATH);
+    static ref DISCARD_IMAGE_RULES: Vec<XPathExpr> = compile(&DISCARD_IMAGE_ELEMENTS);
+    static ref COMMENTS_DISCARD_RULES: Vec<XPathExpr> = compile(&COMMENTS_DISCARD_XPATH);
+    static ref DISCARD_SELECTOR: scraper::Selector = create_selector(DISCARD_SELECTORS).unwrap();
+    static ref COMMENT_BLOCK_SELECTOR: scraper::Selector = create_selector("p, li, dd, blockquote, pre").unwrap();
+}
+
+fn compile(rules: &[&str]) -> Vec<XPathExpr> {
+    rules.iter().map(|rule| XPathExpr::parse(rule).expect("built-in XPath rules are valid")).collect()
 }
 
+/// Helper function to create a Selector from a CSS selector string
+pub use crate::utils::create_selector;
+
 /// Text content of section headers to skip in Wikipedia articles
 pub const WIKI_SKIP_SECTION_TITLES: [&str; 13] = [
     "References",
@@ -158,239 +331,198 @@ pub const EXCLUDE_IDS: [&str; 30] = [
 ];
 
 /// Extract content using CSS selector expressions (simplified XPath-like approach)
-pub fn extract_with_xpath(html_content: &str, config: &ExtractionConfig) -> Result<String, TrafilaturaError> {
+pub fn extract_with_xpath(html_content: &str, config: &ExtractionConfig) -> Result<Document, TrafilaturaError> {
+    extract_with_xpath_container(html_content, config).map(|(content, _)| content)
+}
+
+/// Extract content like `extract_with_xpath`, also returning the CSS path of the
+/// content container
+pub fn extract_with_xpath_container(html_content: &str, config: &ExtractionConfig) -> Result<(Document, String), TrafilaturaError> {
     // Parse the HTML document
-    let document = Html::parse_document(html_content);
+    let document = parse_document(html_content);
     
     // Determine if this is a Wikipedia page
     let is_wiki = is_wikipedia_page(&document);
-    let xpaths = if is_wiki { &WIKI_XPATHS } else { &DEFAULT_XPATHS };
-    
-    debug!("Using CSS selector extraction with {} selectors", if is_wiki { "Wikipedia" } else { "default" });
     
     // Find the main content
-    let mut content = String::new();
-    
-    // Create the selector for main content
-    let main_content_selector = create_selector(xpaths.main_content)?;
-    let mut elements = document.select(&main_content_selector).collect::<Vec<_>>();
+    let main_id = find_container(&document, config, is_wiki)?
+        .ok_or_else(|| TrafilaturaError::ExtractionError("No content elements found".to_string()))?;
+    extract_container(document, main_id, is_wiki, config)
+}
+
+/// Extract content like `extract_with_xpath_container` from the first element
+/// matching a selector, such as a learned site rule, instead of searching for the
+/// container; `None` if no element matches
+pub fn extract_with_container_selector(html_content: &str, selector: &Selector, config: &ExtractionConfig) -> Result<Option<(Document, String)>, TrafilaturaError> {
+    let document = parse_document(html_content);
+    let Some(main_id) = document.select(selector).next().map(|element| element.id()) else {
+        return Ok(None);
+    };
+    let is_wiki = is_wikipedia_page(&document);
+    extract_container(document, main_id, is_wiki, config).map(Some)
+}
+
+/// Render the content of the container of a document
+fn extract_container(mut document: Html, main_id: NodeId, is_wiki: bool, config: &ExtractionConfig) -> Result<(Document, String), TrafilaturaError> {
+    debug!("Using CSS selector extraction with {} selectors", if is_wiki { "Wikipedia" } else { "default" });
     
-    // If we didn't find a main content area, try with a broader approach
-    if elements.is_empty() {
-        let body_selector = create_selector("body")?;
-        elements = document.select(&body_selector).collect::<Vec<_>>();
-    }
+    // The document is parsed anew, so the link blocks pruned from the tree of the
+    // other strategies are pruned here too, once the container is found
+    prune_link_blocks(&mut document);
     
-    if elements.is_empty() {
-        return Err(TrafilaturaError::ExtractionError("No content elements found".to_string()));
+    // Nested boilerplate is pruned before rendering, so it cannot reappear inside
+    // the blocks that contain it
+    let discarded = document.tree.get(main_id)
+        .and_then(ElementRef::wrap)
+        .map(|main| discarded_nodes(main, config))
+        .unwrap_or_default();
+    for id in discarded {
+        if let Some(mut node) = document.tree.get_mut(id) {
+            node.detach();
+        }
     }
     
     // Process the main content
-    let main_element = &elements[0];
+    let main_element = &document.tree.get(main_id)
+        .and_then(ElementRef::wrap)
+        .ok_or_else(|| TrafilaturaError::ExtractionError("No content elements found".to_string()))?;
+    let container = css_path(main_element);
     
-    // Extract headings and content
-    let headings_selector = create_selector(xpaths.headings)?;
-    for element in main_element.select(&headings_selector) {
-        let text = element.text().collect::<String>();
-        let is_skip_section = is_wiki && should_skip_section(&text);
-        
-        // If it's not a section to skip
-        if !is_skip_section && is_heading(element) {
-            if !text.trim().is_empty() {
-                content.push_str(&text);
-                content.push_str("\n\n");
-            }
-        }
+    // Exclude very short paragraphs, and the reference sections of Wikipedia
+    let mut renderer = ContentRenderer::new(config).min_paragraph_length(MIN_PARAGRAPH_LENGTH);
+    if is_wiki {
+        renderer = renderer.skip_reference_sections();
     }
+    renderer.add(main_element, &|element| should_exclude(element, main_element, config));
     
-    // Extract paragraphs, checking if we're in a section to skip
-    let paragraphs_selector = create_selector(xpaths.paragraphs)?;
-    for element in main_element.select(&paragraphs_selector) {
-        // Check if preceding heading is in skip section
-        let mut should_skip = false;
-        if is_wiki {
-            if let Some(heading_text) = find_preceding_heading_text(&document, &element) {
-                should_skip = should_skip_section(&heading_text);
-            }
-        }
-        
-        if should_skip || should_exclude(&element) {
-            continue;
-        }
-        
-        let text = element.text().collect::<String>();
-        let trimmed = text.trim();
-        if !trimmed.is_empty() && trimmed.len() > 10 {  // Exclude very short paragraphs
-            content.push_str(trimmed);
-            content.push_str("\n\n");
-        }
-    }
-    
-    // Extract lists
-    if config.include_tables {
-        let lists_selector = create_selector(xpaths.lists)?;
-        for element in main_element.select(&lists_selector) {
-            // Check if preceding heading is in skip section
-            let mut should_skip = false;
-            if is_wiki {
-                if let Some(heading_text) = find_preceding_heading_text(&document, &element) {
-                    should_skip = should_skip_section(&heading_text);
-                }
-            }
-            
-            if should_skip || should_exclude(&element) {
-                continue;
-            }
-            
-            // Extract list items
-            let list_items_selector = create_selector(xpaths.list_items)?;
-            for item in element.select(&list_items_selector) {
-                if should_exclude(&item) {
-                    continue;
-                }
-                
-                let text = item.text().collect::<String>();
-                let trimmed = text.trim();
-                if !trimmed.is_empty() {
-                    content.push_str("• ");
-                    content.push_str(trimmed);
-                    content.push_str("\n");
-                }
-            }
-            content.push_str("\n");
-        }
-    }
+    Ok((renderer.finish(), container))
+}
+
+/// Find the main content container: user rules, documentation containers, the
+/// upstream container rules, the built-in selectors, and finally the body
+fn find_container(document: &Html, config: &ExtractionConfig, is_wiki: bool) -> Result<Option<NodeId>, TrafilaturaError> {
+    // User rules override the built-in container selectors
+    let rule_element = config.content_xpaths.iter().find_map(|rule| {
+        let element = rule.select(document).into_iter().next()?;
+        debug!("Content container matched XPath rule {}", rule.as_str());
+        Some(element)
+    });
     
-    // Extract tables
-    if config.include_tables {
-        let tables_selector = create_selector(xpaths.tables)?;
-        for element in main_element.select(&tables_selector) {
-            // Check if preceding heading is in skip section
-            let mut should_skip = false;
-            if is_wiki {
-                if let Some(heading_text) = find_preceding_heading_text(&document, &element) {
-                    should_skip = should_skip_section(&heading_text);
-                }
-            }
-            
-            if should_skip || should_exclude(&element) {
-                continue;
-            }
-            
-            // Simple extraction of table text
-            let text = element.text().collect::<String>();
-            let trimmed = text.trim();
-            if !trimmed.is_empty() {
-                content.push_str("[Table: ");
-                content.push_str(trimmed);
-                content.push_str("]\n\n");
-            }
+    // Documentation containers are tried one by one, so the most specific one wins
+    let container = rule_element.or_else(|| {
+        if config.mode != ExtractionMode::Docs {
+            return None;
         }
-    }
+        DOCS_MAIN_CONTENT.iter()
+            .filter_map(|selector| try_selector(selector))
+            .find_map(|selector| document.select(&selector).next())
+    });
     
-    // Extract images
-    if config.include_images {
-        let images_selector = create_selector(xpaths.images)?;
-        for element in main_element.select(&images_selector) {
-            if should_exclude(&element) {
-                continue;
-            }
-            
-            let alt = element.value().attr("alt").unwrap_or("");
-            let src = element.value().attr("src").unwrap_or("");
-            
-            if !alt.is_empty() || !src.is_empty() {
-                content.push_str("[Image: ");
-                if !alt.is_empty() {
-                    content.push_str(alt);
-                } else {
-                    content.push_str(src);
-                }
-                content.push_str("]\n\n");
-            }
+    // Then the upstream container rules, skipping empty containers
+    let container = container.or_else(|| {
+        if is_wiki {
+            return None;
         }
+        BODY_RULES.iter()
+            .flat_map(|rule| rule.select(document))
+            .find(|element| element.text().any(|text| !text.trim().is_empty()))
+    });
+    if let Some(container) = container {
+        return Ok(Some(container.id()));
     }
     
-    // Clean up the content
-    let mut cleaned_content = content.trim().to_string();
-    
-    // Replace consecutive newlines with just two
-    let multiple_newlines_re = Regex::new(r"\n{3,}").unwrap();
-    cleaned_content = multiple_newlines_re.replace_all(&cleaned_content, "\n\n").to_string();
-    
-    Ok(cleaned_content)
+    let xpaths = if is_wiki { &WIKI_XPATHS } else { &DEFAULT_XPATHS };
+    let main_content_selector = create_selector(xpaths.main_content)?;
+    let body_selector = create_selector("body")?;
+    Ok(document.select(&main_content_selector)
+        .next()
+        .or_else(|| document.select(&body_selector).next())
+        .map(|element| element.id()))
 }
 
-/// Find the text of the preceding heading of an element
-fn find_preceding_heading_text(document: &Html, element: &ElementRef) -> Option<String> {
-    // Try to find headings by traversing the DOM upwards
-    let h_selector = Selector::parse("h1, h2, h3, h4, h5, h6").unwrap();
-    
-    // Find parent section or article
-    let _section_selector = Selector::parse("section, article, div").unwrap();
-    let mut current = element.clone();
+/// Elements of the main content removed by the upstream discard rules and the
+/// discard selectors, like Python trafilatura's `prune_unwanted_sections`
+fn discarded_nodes(main: ElementRef, config: &ExtractionConfig) -> HashSet<NodeId> {
+    let select = |rules: &[XPathExpr]| -> HashSet<NodeId> {
+        rules.iter().flat_map(|rule| rule.select_from(main)).map(|element| element.id()).collect()
+    };
     
-    // First check if we can find a heading within the same parent
-    while let Some(parent_ref) = current.parent().and_then(ElementRef::wrap) {
-        // Check direct children of parent for headings that come before our element
-        for heading in parent_ref.select(&h_selector) {
-            // Check if heading is before our element in document order
-            if is_before(document, &heading, element) {
-                return Some(heading.text().collect());
-            }
-        }
-        
-        // Move up to parent
-        current = parent_ref;
-        
-        // If we've reached a section or article, stop
-        if current.value().name.local.eq_str_ignore_ascii_case("section") || 
-           current.value().name.local.eq_str_ignore_ascii_case("article") {
-            break;
-        }
+    // As in Python, the overall rules are undone when they remove nearly all the text
+    let mut discarded = select(&OVERALL_DISCARD_RULES);
+    discarded.extend(main.select(&DISCARD_SELECTOR).map(|element| element.id()));
+    let total = element_text_length(&main);
+    let removed: usize = discarded.iter()
+        .filter_map(|id| main.tree().get(*id).and_then(ElementRef::wrap))
+        .filter(|element| !element.ancestors().any(|ancestor| discarded.contains(&ancestor.id())))
+        .map(|element| element_text_length(&element))
+        .sum();
+    if total.saturating_sub(removed) * 7 < total {
+        debug!("Discard rules would remove {} of {} chars, keeping them", removed, total);
+        discarded.clear();
     }
     
-    None
+    discarded.extend(select(&REMOVE_COMMENTS_RULES));
+    if !config.renders_images() {
+        discarded.extend(select(&DISCARD_IMAGE_RULES));
+    }
+    if config.focus != Focus::Recall {
+        discarded.extend(select(&TEASER_DISCARD_RULES));
+    }
+    if config.focus == Focus::Precision {
+        discarded.extend(select(&PRECISION_DISCARD_RULES));
+    }
+    discarded
 }
 
-/// Check if element a is before element b in document order
-fn is_before(document: &Html, a: &ElementRef, b: &ElementRef) -> bool {
-    // Simplistic approach: compare the source positions
-    // This is a heuristic that works in most cases but not all
-    let all_elements: Vec<_> = document.tree.nodes().collect();
-    let a_pos = all_elements.iter().position(|n| n.id() == a.id());
-    let b_pos = all_elements.iter().position(|n| n.id() == b.id());
-    
-    match (a_pos, b_pos) {
-        (Some(a_idx), Some(b_idx)) => a_idx < b_idx,
-        _ => false,
-    }
+/// Check if an element or one of its ancestors was discarded
+fn is_discarded(element: &ElementRef, discarded: &HashSet<NodeId>) -> bool {
+    !discarded.is_empty() && std::iter::once(**element).chain(element.ancestors()).any(|node| discarded.contains(&node.id()))
 }
 
-/// Check if a section should be skipped based on heading text
-fn should_skip_section(heading_text: &str) -> bool {
-    let heading_lower = heading_text.to_lowercase();
-    
-    WIKI_SKIP_SECTION_TITLES.iter().any(|&title| {
-        heading_lower.contains(&title.to_lowercase())
-    })
+/// Extract the comments section of a page with Python trafilatura's `COMMENTS_XPATH`
+/// rules: the text blocks of the first section with text, without reply forms and
+/// other debris
+pub fn extract_comments(document: &Html) -> Option<String> {
+    COMMENTS_RULES.iter()
+        .flat_map(|rule| rule.select(document).into_iter().next())
+        .find_map(|section| {
+            let discarded: HashSet<NodeId> = COMMENTS_DISCARD_RULES.iter()
+                .flat_map(|rule| rule.select_from(section))
+                .map(|element| element.id())
+                .collect();
+            let blocks: Vec<String> = section.descendants()
+                .filter_map(ElementRef::wrap)
+                // Innermost blocks only, so nested replies are not repeated
+                .filter(|element| is_comment_block(element) && !element.select(&COMMENT_BLOCK_SELECTOR).any(|_| true))
+                .filter(|element| !is_discarded(element, &discarded))
+                .map(|element| element.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" "))
+                .filter(|text| !text.is_empty())
+                .collect();
+            (!blocks.is_empty()).then(|| blocks.join("\n"))
+        })
+}
+
+/// Elements holding the text of a comment
+fn is_comment_block(element: &ElementRef) -> bool {
+    matches!(element.value().name(), "p" | "li" | "dd" | "blockquote" | "pre")
 }
 
 /// Check if the page is a Wikipedia page
 fn is_wikipedia_page(document: &Html) -> bool {
-    // Check meta tags for Wikipedia
-    let meta_selector = Selector::parse("meta[property='og:site_name']").unwrap();
-    for meta in document.select(&meta_selector) {
+    // Check meta tags for Wikipedia; only the site name itself counts, since other
+    // sites may name themselves after it
+    let meta_selector = try_selector("meta[property='og:site_name']");
+    for meta in meta_selector.iter().flat_map(|selector| document.select(selector)) {
         if let Some(content) = meta.value().attr("content") {
-            if content.contains("Wikipedia") {
+            if content.trim() == "Wikipedia" {
                 return true;
             }
         }
     }
     
     // Check domain in canonical link
-    let link_selector = Selector::parse("link[rel='canonical']").unwrap();
-    for link in document.select(&link_selector) {
+    let link_selector = try_selector("link[rel='canonical']");
+    for link in link_selector.iter().flat_map(|selector| document.select(selector)) {
         if let Some(href) = link.value().attr("href") {
             if href.contains("wikipedia.org") {
                 return true;
@@ -401,74 +533,44 @@ fn is_wikipedia_page(document: &Html) -> bool {
     false
 }
 
-/// Check if an element is a heading
-fn is_heading(element: ElementRef) -> bool {
-    let name = element.value().name.local.to_lowercase();
-    matches!(name.as_str(), "h1" | "h2" | "h3" | "h4" | "h5" | "h6")
+/// Check if an element, or one of its ancestors inside the content container (up to
+/// `MAX_ANCESTOR_DEPTH`), should be excluded based on its tag, role, class, or ID
+fn should_exclude(element: &ElementRef, container: &ElementRef, config: &ExtractionConfig) -> bool {
+    std::iter::once(*element)
+        .chain(ancestors_within(element, container))
+        .any(|element| is_excluded_element(&element, config))
 }
 
-/// Check if an element should be excluded based on its tag, class, or ID
-fn should_exclude(element: &ElementRef) -> bool {
-    // Check element itself
+/// Check if an element should be excluded based on its own tag, landmark role, class, or ID
+fn is_excluded_element(element: &ElementRef, config: &ExtractionConfig) -> bool {
     let el = element.value();
     
     // Check tag name
     let tag_name = el.name.local.to_lowercase();
-    if EXCLUDE_ELEMENTS.iter().any(|&tag| tag.eq_ignore_ascii_case(&tag_name)) {
+    if is_stripped_tag(&tag_name, &EXCLUDE_ELEMENTS, config) {
         return true;
     }
     
-    // Check classes
-    if let Some(class_attr) = el.attr("class") {
-        let classes: Vec<&str> = class_attr.split_whitespace().collect();
-        for class in classes {
-            if EXCLUDE_CLASSES.iter().any(|&excl| class.eq_ignore_ascii_case(excl)) {
-                return true;
-            }
-        }
+    // Check landmark role
+    if has_role(element, &BOILERPLATE_ROLES) {
+        return true;
     }
     
-    // Check id
-    if let Some(id) = el.attr("id") {
-        if EXCLUDE_IDS.iter().any(|&excl_id| id.eq_ignore_ascii_case(excl_id)) {
+    // Check classes
+    if let Some(class_attr) = el.attr("class") {
+        if class_attr.split_whitespace().any(|class| EXCLUDE_CLASSES.iter().any(|&excl| class.eq_ignore_ascii_case(excl))) {
             return true;
         }
     }
     
-    // Check parent elements
-    if let Some(parent_ref) = element.parent().and_then(ElementRef::wrap) {
-        let parent = parent_ref.value();
-        
-        // Check parent tag
-        let parent_tag = parent.name.local.to_lowercase();
-        if EXCLUDE_ELEMENTS.iter().any(|&tag| tag.eq_ignore_ascii_case(&parent_tag)) {
-            return true;
-        }
-        
-        // Check parent classes
-        if let Some(parent_class) = parent.attr("class") {
-            let parent_classes: Vec<&str> = parent_class.split_whitespace().collect();
-            for class in parent_classes {
-                if EXCLUDE_CLASSES.iter().any(|&excl| class.eq_ignore_ascii_case(excl)) {
-                    return true;
-                }
-            }
-        }
-        
-        // Check parent id
-        if let Some(parent_id) = parent.attr("id") {
-            if EXCLUDE_IDS.iter().any(|&excl_id| parent_id.eq_ignore_ascii_case(excl_id)) {
-                return true;
-            }
-        }
-    }
-    
-    false
+    // Check id
+    el.attr("id").is_some_and(|id| EXCLUDE_IDS.iter().any(|&excl_id| id.eq_ignore_ascii_case(excl_id)))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ImageStyle;
     
     #[test]
     fn test_wikipedia_page_detection() {
@@ -495,14 +597,119 @@ mod tests {
         
         let document = Html::parse_document(html);
         assert!(!is_wikipedia_page(&document));
+        
+        // Padding around the site name does not matter
+        let html = r#"<html><head><meta property="og:site_name" content=" Wikipedia " /></head><body></body></html>"#;
+        assert!(is_wikipedia_page(&Html::parse_document(html)));
+    }
+    
+    #[test]
+    fn test_separate_tables() {
+        let html = r#"<html><body><article>
+            <p>The following figures were published by the statistics office this week.</p>
+            <table><tr><th>Year</th><th>Value</th></tr><tr><td>2023</td><td>42</td></tr></table>
+        </article></body></html>"#;
+        
+        let config = ExtractionConfig { separate_tables: true, ..Default::default() };
+        let document = extract_with_xpath(html, &config).unwrap();
+        let content = document.to_text(config.image_style);
+        
+        assert!(content.contains("[Table 1]"));
+        assert!(!content.contains("2023"));
+        assert_eq!(document.tables.len(), 1);
+        assert_eq!(document.tables[0].rows[1], vec!["2023", "42"]);
+    }
+    
+    #[test]
+    fn test_content_xpaths() {
+        let html = r#"<html><body>
+            <article><p>Teaser text that the default selectors would pick.</p></article>
+            <div class="story-wrapper"><p>Story text that only the site rule selects.</p></div>
+            </body></html>"#;
+        let rules = ["//section[@id='missing']", "//div[contains(@class, 'story')]"];
+        let config = ExtractionConfig {
+            content_xpaths: rules.iter().map(|rule| XPathExpr::parse(rule).unwrap()).collect(),
+            ..Default::default()
+        };
+        
+        let content = extract_with_xpath(html, &config).unwrap().to_text(config.image_style);
+        
+        assert!(content.contains("Story text"));
+        assert!(!content.contains("Teaser text"));
     }
     
     #[test]
-    fn test_should_skip_section() {
-        assert!(should_skip_section("References"));
-        assert!(should_skip_section("External links"));
-        assert!(should_skip_section("See also"));
-        assert!(!should_skip_section("Introduction"));
-        assert!(!should_skip_section("Main content"));
+    fn test_upstream_rules() {
+        for rules in [&*BODY_RULES, &*COMMENTS_RULES, &*REMOVE_COMMENTS_RULES, &*OVERALL_DISCARD_RULES,
+                      &*TEASER_DISCARD_RULES, &*PRECISION_DISCARD_RULES, &*DISCARD_IMAGE_RULES, &*COMMENTS_DISCARD_RULES] {
+            assert!(!rules.is_empty());
+        }
+        
+        let html = r#"<html><body>
+            <header><p>Site header with a long enough tagline to be kept.</p></header>
+            <div class="story-body">
+              <p>The ferry timetable changes next week for the summer season.</p>
+              <div class="share-buttons"><p>Share this story with your friends</p></div>
+              <div class="teaser"><p>Also read: harbour dredging resumes in June.</p></div>
+              <p class="link-list">Links to the timetable pages of the operator.</p>
+            </div>
+            <div id="comments"><ol class="commentlist">
+              <li><p>Will the night ferry keep running?</p></li>
+              <li><p>Yes, on Fridays and Saturdays.</p> <cite>Harbour office</cite></li>
+            </ol><div id="respond"><p>Leave a reply</p></div></div>
+            </body></html>"#;
+        
+        let content = extract_with_xpath(html, &ExtractionConfig::default()).unwrap().to_text(ImageStyle::AltText);
+        assert!(content.contains("ferry timetable changes"));
+        assert!(content.contains("Links to the timetable"));
+        assert!(!content.contains("Share this story") && !content.contains("Also read") && !content.contains("night ferry"));
+        
+        let recall = ExtractionConfig { focus: Focus::Recall, ..Default::default() };
+        assert!(extract_with_xpath(html, &recall).unwrap().to_text(recall.image_style).contains("Also read"));
+        let precision = ExtractionConfig { focus: Focus::Precision, ..Default::default() };
+        assert!(!extract_with_xpath(html, &precision).unwrap().to_text(precision.image_style).contains("Links to the timetable"));
+        
+        let comments = extract_comments(&Html::parse_document(html)).unwrap();
+        assert_eq!(comments, "Will the night ferry keep running?\nYes, on Fridays and Saturdays.");
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_nested_boilerplate() {
+        let html = r#"<html><body><article>
+            <div class="paragraph">The harbour authority confirmed the new berths will open in June.
+              <div class="inline-promo"><div class="newsletter-signup">Sign up for our morning briefing today</div></div>
+            </div>
+            <div class="widget"><div class="inner"><p>Most read articles this week on the website</p></div></div>
+            <div id="div-gpt-ad-1234"><div><p>Advertisement for a local ferry operator</p></div></div>
+            <section><div class="story-tools"><div class="share-buttons"><p>Share on social networks today</p></div></div></section>
+            <p>Dredging work in the outer basin is expected to finish in May.</p>
+            </article></body></html>"#;
+        
+        let content = extract_with_xpath(html, &ExtractionConfig::default()).unwrap().to_text(ImageStyle::AltText);
+        
+        assert!(content.contains("new berths will open in June"));
+        assert!(content.contains("Dredging work"));
+        for junk in ["Sign up", "Most read", "Advertisement", "Share on"] {
+            assert!(!content.contains(junk), "{} in {}", junk, content);
+        }
+    }
+    
+    #[test]
+    fn test_docs_mode() {
+        let html = r#"<html><body><main class="md-main">
+            <nav class="md-nav"><ul><li><a href="/a">Install</a></li><li><a href="/b">Usage</a></li></ul></nav>
+            <div class="md-content"><article>
+                <h1>Configuration</h1>
+                <p>See <a href="/api/config">Config</a>.</p>
+                <pre><code>let config = Config::default();</code></pre>
+            </article></div>
+            </main></body></html>"#;
+        let config = ExtractionConfig { mode: ExtractionMode::Docs, ..Default::default() };
+        
+        let content = extract_with_xpath(html, &config).unwrap().to_text(config.image_style);
+        
+        assert!(content.contains("See Config"));
+        assert!(content.contains("let config = Config::default();"));
+        assert!(!content.contains("Install"));
+    }
+}
@@ -0,0 +1,918 @@
+//! XPath evaluation for Trafilatura Rust port.
+//! The extraction rules of the `xpath` module are CSS selectors. This module
+//! evaluates real XPath expressions, so content container rules can be copied
+//! from Python trafilatura's XPath lists and from community site rules. It
+//! supports the XPath 1.0 subset those rules use: location paths on every axis
+//! but `namespace`, boolean and positional predicates, unions, comparisons, and
+//! the core string, boolean, and node-set functions. Arithmetic, variables, and
+//! namespace prefixes are not supported.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use ego_tree::{NodeId, NodeRef, Tree};
+use scraper::{ElementRef, Html, Node};
+use serde::Deserialize;
+
+use crate::TrafilaturaError;
+
+/// Supported functions with their minimum and maximum number of arguments
+const FUNCTIONS: &[(&str, usize, usize)] = &[
+    ("boolean", 1, 1),
+    ("concat", 2, usize::MAX),
+    ("contains", 2, 2),
+    ("count", 1, 1),
+    ("ends-with", 2, 2),
+    ("false", 0, 0),
+    ("last", 0, 0),
+    ("local-name", 0, 1),
+    ("name", 0, 1),
+    ("normalize-space", 0, 1),
+    ("not", 1, 1),
+    ("number", 0, 1),
+    ("position", 0, 0),
+    ("starts-with", 2, 2),
+    ("string", 0, 1),
+    ("string-length", 0, 1),
+    ("translate", 3, 3),
+    ("true", 0, 0),
+];
+
+/// A compiled XPath expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct XPathExpr {
+    source: String,
+    expr: Expr,
+}
+
+/// The sections of a config file the content rules are read from
+#[derive(Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    content_xpaths: Vec<XPathExpr>,
+}
+
+impl XPathExpr {
+    /// Compile an expression, e.g. `(.//article)[1]` or
+    /// `.//div[contains(@class, "post-content") or @id="story"]`
+    pub fn parse(source: &str) -> Result<Self, TrafilaturaError> {
+        let invalid = |message: String| {
+            TrafilaturaError::ConfigError(format!("invalid XPath expression '{}': {}", source, message))
+        };
+        let mut parser = Parser { tokens: tokenize(source).map_err(invalid)?, pos: 0 };
+        let expr = parser.expr().map_err(invalid)?;
+        if let Some(token) = parser.peek() {
+            return Err(invalid(format!("unexpected {:?}", token)));
+        }
+        Ok(Self { source: source.to_string(), expr })
+    }
+
+    /// Read the `content_xpaths` section of a JSON config file, a list of expressions
+    /// tried in order, e.g. `{"content_xpaths": ["//div[@id=\"story\"]", "(.//article)[1]"]}`
+    pub fn from_file(path: &Path) -> Result<Vec<Self>, TrafilaturaError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_json(&content)
+    }
+
+    /// Parse the `content_xpaths` section of a JSON config document
+    pub fn from_json(content: &str) -> Result<Vec<Self>, TrafilaturaError> {
+        let file: ConfigFile = serde_json::from_str(content)?;
+        Ok(file.content_xpaths)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Elements selected by the expression in document order. Relative paths start
+    /// at the `<html>` element, as with lxml.
+    pub fn select<'a>(&self, document: &'a Html) -> Vec<ElementRef<'a>> {
+        self.select_from(document.root_element())
+    }
+
+    /// Elements selected by the expression in document order, with relative paths
+    /// starting at `context`
+    pub fn select_from<'a>(&self, context: ElementRef<'a>) -> Vec<ElementRef<'a>> {
+        let evaluator = Evaluator::new(context.tree());
+        let context = Context { node: XNode::Node(*context), position: 1, size: 1 };
+        match evaluator.eval(&self.expr, &context) {
+            Value::Nodes(nodes) => nodes.into_iter()
+                .filter_map(|node| match node {
+                    XNode::Node(node) => ElementRef::wrap(node),
+                    XNode::Attribute(..) => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for XPathExpr {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        XPathExpr::parse(&source).map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CmpOp {
+    /// The operator giving the same result with the operands swapped
+    fn swapped(self) -> Self {
+        match self {
+            CmpOp::Lt => CmpOp::Gt,
+            CmpOp::Le => CmpOp::Ge,
+            CmpOp::Gt => CmpOp::Lt,
+            CmpOp::Ge => CmpOp::Le,
+            op => op,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Slash,
+    DoubleSlash,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Dot,
+    DotDot,
+    At,
+    Comma,
+    Pipe,
+    Star,
+    DoubleColon,
+    Op(CmpOp),
+    Literal(String),
+    Number(f64),
+    Name(String),
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let is_name_char = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.');
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let next = chars.get(i + 1).copied();
+        let token = match chars[i] {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '/' if next == Some('/') => {
+                i += 1;
+                Token::DoubleSlash
+            }
+            '/' => Token::Slash,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            '@' => Token::At,
+            ',' => Token::Comma,
+            '|' => Token::Pipe,
+            '*' => Token::Star,
+            '=' => Token::Op(CmpOp::Eq),
+            '!' if next == Some('=') => {
+                i += 1;
+                Token::Op(CmpOp::Ne)
+            }
+            '<' if next == Some('=') => {
+                i += 1;
+                Token::Op(CmpOp::Le)
+            }
+            '<' => Token::Op(CmpOp::Lt),
+            '>' if next == Some('=') => {
+                i += 1;
+                Token::Op(CmpOp::Ge)
+            }
+            '>' => Token::Op(CmpOp::Gt),
+            ':' if next == Some(':') => {
+                i += 1;
+                Token::DoubleColon
+            }
+            '.' if next == Some('.') => {
+                i += 1;
+                Token::DotDot
+            }
+            '.' if !next.is_some_and(|c| c.is_ascii_digit()) => Token::Dot,
+            quote @ ('"' | '\'') => {
+                let length = chars[i + 1..].iter()
+                    .position(|&c| c == quote)
+                    .ok_or("unterminated string literal")?;
+                let literal = chars[i + 1..i + 1 + length].iter().collect();
+                i += length + 1;
+                Token::Literal(literal)
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let start = i;
+                while chars.get(i + 1).is_some_and(|c| c.is_ascii_digit() || *c == '.') {
+                    i += 1;
+                }
+                let number: String = chars[start..=i].iter().collect();
+                Token::Number(number.parse().map_err(|_| format!("invalid number {}", number))?)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while chars.get(i + 1).is_some_and(|c| is_name_char(*c)) {
+                    i += 1;
+                }
+                Token::Name(chars[start..=i].iter().collect())
+            }
+            c => return Err(format!("unexpected character '{}'", c)),
+        };
+        tokens.push(token);
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Compare(CmpOp, Box<Expr>, Box<Expr>),
+    Union(Box<Expr>, Box<Expr>),
+    Path(PathStart, Vec<Step>),
+    /// A parenthesized expression or function call with predicates, e.g. `(.//p)[1]`
+    Filter(Box<Expr>, Vec<Expr>),
+    Literal(String),
+    Number(f64),
+    Function(String, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PathStart {
+    /// The document node, for absolute paths
+    Root,
+    Context,
+    Expr(Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    axis: Axis,
+    test: NodeTest,
+    predicates: Vec<Expr>,
+}
+
+impl Step {
+    /// The step `//` abbreviates
+    fn descendant_or_self() -> Self {
+        Step { axis: Axis::DescendantOrSelf, test: NodeTest::Node, predicates: Vec::new() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Axis {
+    Child,
+    Descendant,
+    DescendantOrSelf,
+    Self_,
+    Parent,
+    Ancestor,
+    AncestorOrSelf,
+    FollowingSibling,
+    PrecedingSibling,
+    Following,
+    Preceding,
+    Attribute,
+}
+
+impl Axis {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "child" => Axis::Child,
+            "descendant" => Axis::Descendant,
+            "descendant-or-self" => Axis::DescendantOrSelf,
+            "self" => Axis::Self_,
+            "parent" => Axis::Parent,
+            "ancestor" => Axis::Ancestor,
+            "ancestor-or-self" => Axis::AncestorOrSelf,
+            "following-sibling" => Axis::FollowingSibling,
+            "preceding-sibling" => Axis::PrecedingSibling,
+            "following" => Axis::Following,
+            "preceding" => Axis::Preceding,
+            "attribute" => Axis::Attribute,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum NodeTest {
+    /// Lowercase element or attribute name
+    Name(String),
+    /// `*`, any element or attribute
+    Any,
+    Text,
+    Comment,
+    Node,
+}
+
+/// Recursive descent parser; comparisons share one precedence level
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = self.peek() == Some(token);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), String> {
+        if self.eat(&token) {
+            Ok(())
+        } else {
+            Err(format!("expected {:?}, found {:?}", token, self.peek()))
+        }
+    }
+
+    fn eat_name(&mut self, name: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Name(n)) if n == name);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut left = self.and_expr()?;
+        while self.eat_name("or") {
+            left = Expr::Or(Box::new(left), Box::new(self.and_expr()?));
+        }
+        Ok(left)
+    }
+
+    fn and_expr(&mut self) -> Result<Expr, String> {
+        let mut left = self.comparison()?;
+        while self.eat_name("and") {
+            left = Expr::And(Box::new(left), Box::new(self.comparison()?));
+        }
+        Ok(left)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let mut left = self.union()?;
+        while let Some(Token::Op(op)) = self.peek().cloned() {
+            self.pos += 1;
+            left = Expr::Compare(op, Box::new(left), Box::new(self.union()?));
+        }
+        Ok(left)
+    }
+
+    fn union(&mut self) -> Result<Expr, String> {
+        let mut left = self.path()?;
+        while self.eat(&Token::Pipe) {
+            left = Expr::Union(Box::new(left), Box::new(self.path()?));
+        }
+        Ok(left)
+    }
+
+    fn path(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            Some(Token::Slash) => {
+                self.pos += 1;
+                let steps = if self.starts_step() { self.relative_path()? } else { Vec::new() };
+                Ok(Expr::Path(PathStart::Root, steps))
+            }
+            Some(Token::DoubleSlash) => {
+                self.pos += 1;
+                let mut steps = vec![Step::descendant_or_self()];
+                steps.extend(self.relative_path()?);
+                Ok(Expr::Path(PathStart::Root, steps))
+            }
+            Some(Token::LParen | Token::Literal(_) | Token::Number(_)) => self.filter(),
+            Some(Token::Name(name))
+                if self.peek_at(1) == Some(&Token::LParen) && !matches!(name.as_str(), "text" | "node" | "comment") =>
+            {
+                self.filter()
+            }
+            _ => Ok(Expr::Path(PathStart::Context, self.relative_path()?)),
+        }
+    }
+
+    /// A primary expression with predicates, optionally followed by a relative path
+    fn filter(&mut self) -> Result<Expr, String> {
+        let primary = self.primary()?;
+        let predicates = self.predicates()?;
+        let expr = if predicates.is_empty() { primary } else { Expr::Filter(Box::new(primary), predicates) };
+
+        let mut steps = Vec::new();
+        if self.eat(&Token::DoubleSlash) {
+            steps.push(Step::descendant_or_self());
+        } else if !self.eat(&Token::Slash) {
+            return Ok(expr);
+        }
+        steps.extend(self.relative_path()?);
+        Ok(Expr::Path(PathStart::Expr(Box::new(expr)), steps))
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.peek().cloned() {
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let expr = self.expr()?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            Some(Token::Literal(literal)) => {
+                self.pos += 1;
+                Ok(Expr::Literal(literal))
+            }
+            Some(Token::Number(number)) => {
+                self.pos += 1;
+                Ok(Expr::Number(number))
+            }
+            Some(Token::Name(name)) => {
+                self.pos += 2;
+                let mut args = Vec::new();
+                if !self.eat(&Token::RParen) {
+                    args.push(self.expr()?);
+                    while self.eat(&Token::Comma) {
+                        args.push(self.expr()?);
+                    }
+                    self.expect(Token::RParen)?;
+                }
+                match FUNCTIONS.iter().find(|(function, _, _)| *function == name) {
+                    None => Err(format!("unsupported function {}()", name)),
+                    Some((_, min, max)) if args.len() < *min || args.len() > *max => {
+                        Err(format!("wrong number of arguments for {}()", name))
+                    }
+                    Some(_) => Ok(Expr::Function(name, args)),
+                }
+            }
+            token => Err(format!("unexpected {:?}", token)),
+        }
+    }
+
+    fn starts_step(&self) -> bool {
+        matches!(self.peek(), Some(Token::Dot | Token::DotDot | Token::At | Token::Star | Token::Name(_)))
+    }
+
+    fn relative_path(&mut self) -> Result<Vec<Step>, String> {
+        let mut steps = vec![self.step()?];
+        loop {
+            if self.eat(&Token::DoubleSlash) {
+                steps.push(Step::descendant_or_self());
+            } else if !self.eat(&Token::Slash) {
+                return Ok(steps);
+            }
+            steps.push(self.step()?);
+        }
+    }
+
+    fn step(&mut self) -> Result<Step, String> {
+        if self.eat(&Token::Dot) {
+            return Ok(Step { axis: Axis::Self_, test: NodeTest::Node, predicates: Vec::new() });
+        }
+        if self.eat(&Token::DotDot) {
+            return Ok(Step { axis: Axis::Parent, test: NodeTest::Node, predicates: Vec::new() });
+        }
+        let axis = if self.eat(&Token::At) {
+            Axis::Attribute
+        } else if let (Some(Token::Name(name)), Some(Token::DoubleColon)) = (self.peek(), self.peek_at(1)) {
+            let axis = Axis::from_name(name).ok_or_else(|| format!("unsupported axis {}", name))?;
+            self.pos += 2;
+            axis
+        } else {
+            Axis::Child
+        };
+        let test = self.node_test()?;
+        Ok(Step { axis, test, predicates: self.predicates()? })
+    }
+
+    fn node_test(&mut self) -> Result<NodeTest, String> {
+        match self.peek().cloned() {
+            Some(Token::Star) => {
+                self.pos += 1;
+                Ok(NodeTest::Any)
+            }
+            Some(Token::Name(name)) => {
+                self.pos += 1;
+                let node_type = match name.as_str() {
+                    "text" => NodeTest::Text,
+                    "comment" => NodeTest::Comment,
+                    "node" => NodeTest::Node,
+                    _ => return Ok(NodeTest::Name(name.to_lowercase())),
+                };
+                if self.peek() != Some(&Token::LParen) {
+                    return Ok(NodeTest::Name(name));
+                }
+                self.pos += 1;
+                self.expect(Token::RParen)?;
+                Ok(node_type)
+            }
+            token => Err(format!("expected a node test, found {:?}", token)),
+        }
+    }
+
+    fn predicates(&mut self) -> Result<Vec<Expr>, String> {
+        let mut predicates = Vec::new();
+        while self.eat(&Token::LBracket) {
+            predicates.push(self.expr()?);
+            self.expect(Token::RBracket)?;
+        }
+        Ok(predicates)
+    }
+}
+
+/// A node of the document or an attribute of an element
+#[derive(Debug, Clone, Copy)]
+enum XNode<'a> {
+    Node(NodeRef<'a, Node>),
+    /// Element, name, and value
+    Attribute(NodeId, &'a str, &'a str),
+}
+
+impl<'a> XNode<'a> {
+    fn key(&self) -> (NodeId, Option<&'a str>) {
+        match self {
+            XNode::Node(node) => (node.id(), None),
+            XNode::Attribute(element, name, _) => (*element, Some(name)),
+        }
+    }
+
+    fn string_value(&self) -> String {
+        match self {
+            XNode::Attribute(_, _, value) => value.to_string(),
+            XNode::Node(node) => match node.value() {
+                Node::Text(text) => text.to_string(),
+                Node::Comment(comment) => comment.to_string(),
+                _ => node.descendants()
+                    .filter_map(|node| node.value().as_text().map(|text| &**text))
+                    .collect(),
+            },
+        }
+    }
+
+    fn name(&self) -> &'a str {
+        match self {
+            XNode::Attribute(_, name, _) => name,
+            XNode::Node(node) => node.value().as_element().map_or("", |element| element.name()),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Value<'a> {
+    Nodes(Vec<XNode<'a>>),
+    Str(String),
+    Num(f64),
+    Bool(bool),
+}
+
+impl Value<'_> {
+    fn boolean(&self) -> bool {
+        match self {
+            Value::Nodes(nodes) => !nodes.is_empty(),
+            Value::Str(string) => !string.is_empty(),
+            Value::Num(number) => *number != 0.0 && !number.is_nan(),
+            Value::Bool(boolean) => *boolean,
+        }
+    }
+
+    /// String value, of the first node for node-sets
+    fn string(&self) -> String {
+        match self {
+            Value::Nodes(nodes) => nodes.first().map(XNode::string_value).unwrap_or_default(),
+            Value::Str(string) => string.clone(),
+            Value::Num(number) if number.fract() == 0.0 => format!("{}", *number as i64),
+            Value::Num(number) => number.to_string(),
+            Value::Bool(boolean) => boolean.to_string(),
+        }
+    }
+
+    fn number(&self) -> f64 {
+        match self {
+            Value::Num(number) => *number,
+            Value::Bool(boolean) => f64::from(u8::from(*boolean)),
+            value => value.string().trim().parse().unwrap_or(f64::NAN),
+        }
+    }
+}
+
+/// Comparison with XPath 1.0 semantics: a node-set compares true if any of its
+/// nodes does
+fn compare(op: CmpOp, left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Nodes(left), Value::Nodes(right)) => left.iter().any(|a| {
+            right.iter().any(|b| compare_atoms(op, &Value::Str(a.string_value()), &Value::Str(b.string_value())))
+        }),
+        (Value::Nodes(nodes), Value::Bool(_)) => compare_atoms(op, &Value::Bool(!nodes.is_empty()), right),
+        (Value::Nodes(nodes), _) => nodes.iter().any(|node| compare_atoms(op, &Value::Str(node.string_value()), right)),
+        (_, Value::Nodes(_)) => compare(op.swapped(), right, left),
+        _ => compare_atoms(op, left, right),
+    }
+}
+
+fn compare_atoms(op: CmpOp, left: &Value, right: &Value) -> bool {
+    let equal = || match (left, right) {
+        (Value::Bool(_), _) | (_, Value::Bool(_)) => left.boolean() == right.boolean(),
+        (Value::Num(_), _) | (_, Value::Num(_)) => left.number() == right.number(),
+        _ => left.string() == right.string(),
+    };
+    let (a, b) = (left.number(), right.number());
+    match op {
+        CmpOp::Eq => equal(),
+        CmpOp::Ne => !equal(),
+        CmpOp::Lt => a < b,
+        CmpOp::Le => a <= b,
+        CmpOp::Gt => a > b,
+        CmpOp::Ge => a >= b,
+    }
+}
+
+struct Context<'a> {
+    node: XNode<'a>,
+    position: usize,
+    size: usize,
+}
+
+struct Evaluator<'a> {
+    tree: &'a Tree<Node>,
+    /// Position of each node in document order
+    order: HashMap<NodeId, usize>,
+}
+
+impl<'a> Evaluator<'a> {
+    fn new(tree: &'a Tree<Node>) -> Self {
+        let order = tree.root().descendants().enumerate().map(|(i, node)| (node.id(), i)).collect();
+        Self { tree, order }
+    }
+
+    fn eval(&self, expr: &Expr, context: &Context<'a>) -> Value<'a> {
+        match expr {
+            Expr::Or(left, right) => Value::Bool(self.eval(left, context).boolean() || self.eval(right, context).boolean()),
+            Expr::And(left, right) => Value::Bool(self.eval(left, context).boolean() && self.eval(right, context).boolean()),
+            Expr::Compare(op, left, right) => Value::Bool(compare(*op, &self.eval(left, context), &self.eval(right, context))),
+            Expr::Union(left, right) => {
+                let mut nodes = self.nodes(left, context);
+                nodes.extend(self.nodes(right, context));
+                Value::Nodes(self.in_document_order(nodes))
+            }
+            Expr::Path(start, steps) => {
+                let mut nodes = match start {
+                    PathStart::Root => vec![XNode::Node(self.tree.root())],
+                    PathStart::Context => vec![context.node],
+                    PathStart::Expr(expr) => self.nodes(expr, context),
+                };
+                for step in steps {
+                    nodes = self.step(&nodes, step);
+                }
+                Value::Nodes(nodes)
+            }
+            Expr::Filter(expr, predicates) => {
+                let nodes = self.in_document_order(self.nodes(expr, context));
+                Value::Nodes(self.filter(nodes, predicates))
+            }
+            Expr::Literal(literal) => Value::Str(literal.clone()),
+            Expr::Number(number) => Value::Num(*number),
+            Expr::Function(name, args) => self.function(name, args, context),
+        }
+    }
+
+    /// Nodes selected by an expression; other values select nothing
+    fn nodes(&self, expr: &Expr, context: &Context<'a>) -> Vec<XNode<'a>> {
+        match self.eval(expr, context) {
+            Value::Nodes(nodes) => nodes,
+            _ => Vec::new(),
+        }
+    }
+
+    fn in_document_order(&self, nodes: Vec<XNode<'a>>) -> Vec<XNode<'a>> {
+        let mut seen = HashSet::new();
+        let mut nodes: Vec<XNode<'a>> = nodes.into_iter().filter(|node| seen.insert(node.key())).collect();
+        nodes.sort_by_key(|node| {
+            let (id, attribute) = node.key();
+            (self.order.get(&id).copied().unwrap_or(usize::MAX), attribute)
+        });
+        nodes
+    }
+
+    fn step(&self, nodes: &[XNode<'a>], step: &Step) -> Vec<XNode<'a>> {
+        let mut selected = Vec::new();
+        for node in nodes {
+            let candidates = self.axis(*node, step.axis).into_iter()
+                .filter(|candidate| matches_test(&step.test, step.axis, candidate))
+                .collect();
+            selected.extend(self.filter(candidates, &step.predicates));
+        }
+        self.in_document_order(selected)
+    }
+
+    /// Apply predicates to nodes in axis order; a number selects that position
+    fn filter(&self, mut nodes: Vec<XNode<'a>>, predicates: &[Expr]) -> Vec<XNode<'a>> {
+        for predicate in predicates {
+            let size = nodes.len();
+            nodes = nodes.into_iter()
+                .enumerate()
+                .filter(|(i, node)| {
+                    let context = Context { node: *node, position: i + 1, size };
+                    match self.eval(predicate, &context) {
+                        Value::Num(position) => position == (i + 1) as f64,
+                        value => value.boolean(),
+                    }
+                })
+                .map(|(_, node)| node)
+                .collect();
+        }
+        nodes
+    }
+
+    /// Nodes on an axis, nearest first for reverse axes
+    fn axis(&self, node: XNode<'a>, axis: Axis) -> Vec<XNode<'a>> {
+        let node = match node {
+            XNode::Node(node) => node,
+            XNode::Attribute(element, ..) => {
+                let parent = self.tree.get(element);
+                return match axis {
+                    Axis::Self_ => vec![node],
+                    Axis::Parent => parent.into_iter().map(XNode::Node).collect(),
+                    Axis::Ancestor | Axis::AncestorOrSelf => {
+                        let this = (axis == Axis::AncestorOrSelf).then_some(node);
+                        this.into_iter()
+                            .chain(parent.into_iter().flat_map(|parent| {
+                                std::iter::once(parent).chain(parent.ancestors()).map(XNode::Node)
+                            }))
+                            .collect()
+                    }
+                    _ => Vec::new(),
+                };
+            }
+        };
+        let nodes: Vec<NodeRef<'a, Node>> = match axis {
+            Axis::Child => node.children().collect(),
+            Axis::Descendant => node.descendants().skip(1).collect(),
+            Axis::DescendantOrSelf => node.descendants().collect(),
+            Axis::Self_ => vec![node],
+            Axis::Parent => node.parent().into_iter().collect(),
+            Axis::Ancestor => node.ancestors().collect(),
+            Axis::AncestorOrSelf => std::iter::once(node).chain(node.ancestors()).collect(),
+            Axis::FollowingSibling => node.next_siblings().collect(),
+            Axis::PrecedingSibling => node.prev_siblings().collect(),
+            Axis::Following => std::iter::once(node)
+                .chain(node.ancestors())
+                .flat_map(|ancestor| ancestor.next_siblings())
+                .flat_map(|sibling| sibling.descendants())
+                .collect(),
+            Axis::Preceding => {
+                let ancestors: HashSet<NodeId> = node.ancestors().map(|ancestor| ancestor.id()).collect();
+                let position = self.order.get(&node.id()).copied().unwrap_or_default();
+                let mut preceding: Vec<NodeRef<'a, Node>> = self.tree.root().descendants()
+                    .take(position)
+                    .filter(|preceding| !ancestors.contains(&preceding.id()))
+                    .collect();
+                preceding.reverse();
+                preceding
+            }
+            Axis::Attribute => {
+                return node.value().as_element()
+                    .into_iter()
+                    .flat_map(|element| element.attrs())
+                    .map(|(name, value)| XNode::Attribute(node.id(), name, value))
+                    .collect();
+            }
+        };
+        nodes.into_iter().map(XNode::Node).collect()
+    }
+
+    fn function(&self, name: &str, args: &[Expr], context: &Context<'a>) -> Value<'a> {
+        let string = |i: usize| match args.get(i) {
+            Some(arg) => self.eval(arg, context).string(),
+            None => context.node.string_value(),
+        };
+        match name {
+            "boolean" => Value::Bool(self.eval(&args[0], context).boolean()),
+            "concat" => Value::Str((0..args.len()).map(string).collect()),
+            "contains" => Value::Bool(string(0).contains(&string(1))),
+            "count" => Value::Num(self.nodes(&args[0], context).len() as f64),
+            "ends-with" => Value::Bool(string(0).ends_with(&string(1))),
+            "false" => Value::Bool(false),
+            "last" => Value::Num(context.size as f64),
+            "local-name" | "name" => {
+                let node = match args.first() {
+                    Some(arg) => self.in_document_order(self.nodes(arg, context)).first().copied(),
+                    None => Some(context.node),
+                };
+                Value::Str(node.map(|node| node.name().to_string()).unwrap_or_default())
+            }
+            "normalize-space" => Value::Str(string(0).split_whitespace().collect::<Vec<_>>().join(" ")),
+            "not" => Value::Bool(!self.eval(&args[0], context).boolean()),
+            "number" => Value::Num(match args.first() {
+                Some(arg) => self.eval(arg, context).number(),
+                None => Value::Str(context.node.string_value()).number(),
+            }),
+            "position" => Value::Num(context.position as f64),
+            "starts-with" => Value::Bool(string(0).starts_with(&string(1))),
+            "string" => Value::Str(string(0)),
+            "string-length" => Value::Num(string(0).chars().count() as f64),
+            "translate" => {
+                let (from, to): (Vec<char>, Vec<char>) = (string(1).chars().collect(), string(2).chars().collect());
+                Value::Str(string(0).chars()
+                    .filter_map(|c| match from.iter().position(|&f| f == c) {
+                        Some(i) => to.get(i).copied(),
+                        None => Some(c),
+                    })
+                    .collect())
+            }
+            "true" => Value::Bool(true),
+            _ => unreachable!("functions are checked by the parser"),
+        }
+    }
+}
+
+/// Check a node against a node test; `*` and names match the principal node type
+/// of the axis, attributes on the attribute axis and elements elsewhere
+fn matches_test(test: &NodeTest, axis: Axis, node: &XNode) -> bool {
+    match (test, node) {
+        (NodeTest::Node, _) => true,
+        (NodeTest::Any, XNode::Attribute(..)) => axis == Axis::Attribute,
+        (NodeTest::Name(name), XNode::Attribute(_, attribute, _)) => axis == Axis::Attribute && attribute == name,
+        (_, XNode::Attribute(..)) => false,
+        (NodeTest::Any, XNode::Node(node)) => node.value().is_element(),
+        (NodeTest::Name(name), XNode::Node(node)) => node.value().as_element().is_some_and(|element| element.name() == name),
+        (NodeTest::Text, XNode::Node(node)) => node.value().is_text(),
+        (NodeTest::Comment, XNode::Node(node)) => node.value().is_comment(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn select_ids(expr: &str, html: &str) -> Vec<String> {
+        let document = Html::parse_document(html);
+        XPathExpr::parse(expr).unwrap()
+            .select(&document)
+            .into_iter()
+            .map(|element| element.value().attr("id").unwrap_or(element.value().name()).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_xpath_expr() {
+        let html = r#"<html><body>
+            <div id="nav" class="menu"><p>Home</p></div>
+            <section id="body" class="Post-Text"><p>One</p><p>Two</p></section>
+            <article id="first"><p>Three</p></article>
+            <article id="second"><p lang="en">Four</p></article>
+            <main id="main"></main>
+            </body></html>"#;
+
+        // Rules in the style of Python trafilatura's BODY_XPATH
+        let rule = r#".//*[self::article or self::div or self::main or self::section][
+            contains(translate(@class, "PT", "pt"), "post-text") or @id="story"]"#;
+        assert_eq!(select_ids(rule, html), vec!["body"]);
+        assert_eq!(select_ids("(.//article)[1]", html), vec!["first"]);
+        assert_eq!(select_ids(".//article[last()]", html), vec!["second"]);
+        assert_eq!(select_ids(r#".//*[self::article][starts-with(@id, "sec")]|//main"#, html), vec!["second", "main"]);
+
+        // Positions count per parent, and predicates see text and attributes
+        assert_eq!(select_ids("//section/p[2]", html).len(), 1);
+        assert_eq!(select_ids("//p[text()='Three']/..", html), vec!["first"]);
+        assert_eq!(select_ids("//p[@lang]/ancestor::*[1]", html), vec!["second"]);
+        assert_eq!(select_ids("//*[count(p) = 2]", html), vec!["body"]);
+        assert_eq!(select_ids("//article[not(preceding-sibling::article)]", html), vec!["first"]);
+        assert_eq!(select_ids("//div/following-sibling::*[position() < 3]", html), vec!["body", "first"]);
+        assert_eq!(select_ids(r#"//*[normalize-space(concat(" ", @class, " ")) = "menu"]"#, html), vec!["nav"]);
+        assert!(select_ids("//@class", html).is_empty());
+
+        for invalid in ["//div[", "//div[lower-case(@id)]", "//div[contains(@id)]", "//div[@id='x]", "namespace::x"] {
+            assert!(matches!(XPathExpr::parse(invalid), Err(TrafilaturaError::ConfigError(_))), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_content_xpaths_from_json() {
+        let rules = XPathExpr::from_json(r#"{"content_xpaths": ["(.//article)[1]", "//main"]}"#).unwrap();
+        assert_eq!(rules.iter().map(XPathExpr::as_str).collect::<Vec<_>>(), vec!["(.//article)[1]", "//main"]);
+        assert!(XPathExpr::from_json(r#"{"content_xpaths": ["//div["]}"#).is_err());
+        assert!(XPathExpr::from_json("{}").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,78 @@
+//! Golden-corpus regression tests for trafilatura-rs
+//!
+//! Every `tests/corpus/<name>.html` fixture is extracted with the default
+//! configuration and compared against `tests/corpus/<name>.txt`. After an
+//! intended change in extraction output, update the golden files with:
+//!
+//! ```sh
+//! TRAFILATURA_BLESS=1 cargo test --test corpus
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use trafilatura::{extract_html, ExtractionConfig};
+
+/// Environment variable that rewrites golden files instead of comparing against them
+const BLESS_VAR: &str = "TRAFILATURA_BLESS";
+
+fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("corpus")
+}
+
+fn fixtures() -> Vec<PathBuf> {
+    let mut fixtures: Vec<PathBuf> = fs::read_dir(corpus_dir())
+        .expect("tests/corpus is missing")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "html"))
+        .collect();
+    fixtures.sort();
+    fixtures
+}
+
+#[test]
+fn test_golden_corpus() {
+    let bless = std::env::var_os(BLESS_VAR).is_some();
+    let config = ExtractionConfig::default();
+    let mut failures = Vec::new();
+
+    let fixtures = fixtures();
+    assert!(!fixtures.is_empty(), "no fixtures in tests/corpus");
+
+    for fixture in fixtures {
+        let name = fixture.file_stem().unwrap().to_string_lossy().into_owned();
+        let golden_path = fixture.with_extension("txt");
+
+        let html = fs::read_to_string(&fixture).unwrap();
+        let actual = match extract_html(&html, &config) {
+            Ok(result) => result.content,
+            Err(e) => format!("ERROR: {}", e),
+        };
+
+        if bless {
+            fs::write(&golden_path, format!("{}\n", actual)).unwrap();
+            continue;
+        }
+
+        let expected = match fs::read_to_string(&golden_path) {
+            Ok(expected) => expected,
+            Err(_) => {
+                failures.push(format!("{}: missing golden file {}", name, golden_path.display()));
+                continue;
+            }
+        };
+
+        if expected.trim_end() != actual.trim_end() {
+            failures.push(format!(
+                "{}: output differs from golden file\n--- expected\n{}\n--- actual\n{}",
+                name, expected.trim_end(), actual.trim_end()
+            ));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} corpus fixture(s) regressed (rerun with {}=1 to accept the new output):\n\n{}",
+        failures.len(), BLESS_VAR, failures.join("\n\n")
+    );
+}
@@ -0,0 +1,69 @@
+//! Memory accounting tests for trafilatura-rs
+//!
+//! The allocator shim is installed per binary, so these tests run in their own
+//! test binary with `TrackingAllocator` as the global allocator.
+
+use trafilatura::memory::{is_tracking, MemoryScope, TrackingAllocator, ESTIMATED_BYTES_PER_HTML_BYTE};
+use trafilatura::{extract_html, ExtractionConfig, TrafilaturaError};
+
+#[global_allocator]
+static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
+/// An article of about 30 KB
+fn article() -> String {
+    let paragraph = "<p>The harbour renovation finished two months early, and the new quay walls were \
+        built from the granite of the old breakwater, which divers recovered block by block.</p>";
+    format!("<html><head><title>Harbour</title></head><body><article>{}</article></body></html>", paragraph.repeat(150))
+}
+
+#[test]
+fn test_extraction_is_measured() {
+    let html = article();
+    let config = ExtractionConfig::default();
+
+    let scope = MemoryScope::start();
+    extract_html(&html, &config).unwrap();
+    let usage = scope.usage().unwrap();
+
+    assert!(is_tracking());
+    assert!(usage.peak_bytes > html.len(), "{:?}", usage);
+    assert!(usage.allocations > 0);
+
+    // An inner scope starts from the current allocations and leaves the outer peak intact
+    let inner = MemoryScope::start();
+    drop(vec![0u8; 1 << 16]);
+    assert!(inner.usage().unwrap().peak_bytes >= 1 << 16);
+    drop(inner);
+    assert!(scope.usage().unwrap().peak_bytes >= usage.peak_bytes);
+}
+
+#[test]
+fn test_memory_budget() {
+    let html = article();
+
+    // Documents that fit the budget by estimate are stopped once they exceed it
+    let config = ExtractionConfig { memory_budget: Some(html.len() * ESTIMATED_BYTES_PER_HTML_BYTE), ..Default::default() };
+    assert!(matches!(extract_html(&html, &config), Err(TrafilaturaError::MemoryLimit(_, _))));
+
+    // Documents too large for the budget by estimate are refused before parsing
+    let config = ExtractionConfig { memory_budget: Some(html.len()), ..Default::default() };
+    assert!(matches!(extract_html(&html, &config), Err(TrafilaturaError::MemoryLimit(used, _)) if used == html.len() * ESTIMATED_BYTES_PER_HTML_BYTE));
+
+    let config = ExtractionConfig { memory_budget: Some(64 << 20), ..Default::default() };
+    assert!(extract_html(&html, &config).is_ok());
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn test_batch_reports_memory() {
+    use std::fs;
+    use trafilatura::batch::{BatchOptions, BatchStream};
+
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("article.html"), article()).unwrap();
+
+    let items: Vec<_> = BatchStream::dir(dir.path(), &ExtractionConfig::default(), BatchOptions::default()).unwrap().collect();
+    assert_eq!(items.len(), 1);
+    assert!(items[0].result.is_ok());
+    assert!(items[0].memory.is_some_and(|usage| usage.peak_bytes > 0));
+}
@@ -0,0 +1,109 @@
+//! Robustness tests for trafilatura-rs
+//!
+//! `extract_html` is fed untrusted crawl data, so no input may make it panic. The
+//! `extract_html` fuzz target in `fuzz/` searches for such inputs; this suite replays
+//! the ones it found, and runs a fixed sample of generated tag soup on every
+//! `cargo test`.
+
+use trafilatura::output::format_result;
+use trafilatura::{extract_html, ExtractionConfig, ExtractionMode, Focus, OutputFormat};
+
+/// Minimized inputs that made extraction panic
+const REGRESSIONS: &[&str] = &[
+    // The parser left the comment and meta with a stale parent after moving them
+    // into a reconstructed formatting element
+    "<a><div>?<!><meta>></a>",
+];
+
+/// Pieces the generated documents are made of: misnested formatting, hidden and
+/// collapsed content, templates, tables, ruby, comments, and odd text
+const FRAGMENTS: &[&str] = &[
+    "<div>", "</div>", "<p>", "</p>", "<a href=\"/x\">", "</a>", "<b>", "</b>", "<i>", "</i>",
+    "<article>", "</article>", "<main>", "</main>", "<section class=\"content\">", "</section>",
+    "<nav>", "</nav>", "<footer>", "</footer>", "<aside class=\"related\">", "</aside>",
+    "<div class=\"comments\">", "<div id=\"share\" hidden>", "<span aria-hidden=\"true\">", "</span>",
+    "<div style=\"display:none\">", "<details><summary>More</summary>", "</details>",
+    "<template shadowrootmode=\"open\">", "</template>", "<slot>", "</slot>",
+    "<table>", "</table>", "<tr>", "<td>", "</td>", "<th>", "<caption>", "<ul>", "<li>", "</ul>",
+    "<ruby>漢<rt>kan</rt></ruby>", "<meta property=\"og:type\" content=\"article\">",
+    "<title>", "</title>", "<h1>", "</h1>", "<h2>", "<img src=\"a.png\" alt=\"x\">", "<br>",
+    "<pre>", "</pre>", "<code>", "<blockquote>", "<figure>", "<figcaption>", "<form>", "<input>",
+    "<script>var a = '</div>';</script>", "<style>p{}</style>", "<!-- comment -->", "<!>", "<!--",
+    "<![CDATA[x]]>", "]]>", "&amp;", "&#0;", "&#x1F600;", "\u{0}", "\u{FEFF}", "e\u{301}",
+    "中文的段落内容", "Ελληνικά", "مرحبا", "🙂🙃", "\r\n", "    ", "\"", "'", "<", ">", "=",
+    "A sentence of ordinary article text, long enough to count as a paragraph of content.",
+];
+
+/// Nesting depth and stack size of the deep nesting test
+const NESTING_DEPTH: usize = 1_000;
+const STACK_SIZE: usize = 256 * 1024;
+
+fn generate(rng: &mut fastrand::Rng) -> String {
+    (0..rng.usize(0..200))
+        .map(|_| FRAGMENTS[rng.usize(..FRAGMENTS.len())])
+        .collect()
+}
+
+fn configs() -> Vec<ExtractionConfig> {
+    let default = ExtractionConfig::default();
+    vec![
+        ExtractionConfig { include_comments: true, include_tables: true, ..default.clone() },
+        ExtractionConfig { mode: ExtractionMode::Docs, focus: Focus::Recall, ..default.clone() },
+        ExtractionConfig { mode: ExtractionMode::Email, focus: Focus::Precision, ..default.clone() },
+        ExtractionConfig { extract_metadata: true, separate_tables: true, ..default },
+    ]
+}
+
+/// Extract the input with every config and render the results in every format
+fn exercise(html: &str, configs: &[ExtractionConfig]) {
+    for config in configs {
+        if let Ok(result) = extract_html(html, config) {
+            for format in OutputFormat::ALL {
+                let _ = format_result(&result, &ExtractionConfig { output_format: format, ..config.clone() });
+            }
+        }
+    }
+}
+
+#[test]
+fn test_regressions_do_not_panic() {
+    let configs = configs();
+    for html in REGRESSIONS {
+        exercise(html, &configs);
+    }
+}
+
+#[test]
+fn test_generated_documents_do_not_panic() {
+    let configs = configs();
+    let mut rng = fastrand::Rng::with_seed(0x7261_6669);
+    for _ in 0..100 {
+        exercise(&generate(&mut rng), &configs);
+    }
+}
+
+#[test]
+fn test_arbitrary_bytes_do_not_panic() {
+    let configs = configs();
+    let mut rng = fastrand::Rng::with_seed(0x6c61_7475);
+    for _ in 0..100 {
+        let bytes: Vec<u8> = (0..rng.usize(0..2048)).map(|_| rng.u8(..)).collect();
+        exercise(&String::from_utf8_lossy(&bytes), &configs);
+    }
+}
+
+#[test]
+fn test_deep_nesting_does_not_overflow() {
+    // On a small stack, a recursion over the tree overflows long before this depth
+    let nesting = std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(|| {
+            let configs = configs();
+            for tag in ["div", "ul", "details"] {
+                let html = format!("{}text{}", format!("<{}>", tag).repeat(NESTING_DEPTH), format!("</{}>", tag).repeat(NESTING_DEPTH));
+                exercise(&html, &configs);
+            }
+        })
+        .unwrap();
+    assert!(nesting.join().is_ok());
+}